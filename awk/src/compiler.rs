@@ -1884,7 +1884,7 @@ pub fn compile_program(sources: &[SourceFile]) -> Result<Program, CompilerErrors
         .into_inner()
         .into_iter()
         .filter_map(|(k, v)| match v {
-            GlobalName::Variable(id) => Some((k, id)),
+            GlobalName::Variable(id) | GlobalName::SpecialVar(id) => Some((k, id)),
             _ => None,
         })
         .collect();
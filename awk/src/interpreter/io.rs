@@ -57,8 +57,6 @@ macro_rules! read_iter_next {
 pub trait RecordReader: Iterator<Item = ReadResult> {
     fn is_done(&self) -> bool;
 
-    fn last_byte_read(&self) -> Option<u8>;
-
     fn read_next_record(&mut self, separator: &RecordSeparator) -> Result<Option<String>, String> {
         if self.is_done() {
             return Ok(None);
@@ -74,23 +72,32 @@ pub trait RecordReader: Iterator<Item = ReadResult> {
                 Ok(Some(str))
             }
             RecordSeparator::Null => {
-                let mut next = if let Some(byte) = self.last_byte_read() {
-                    byte
-                } else {
-                    read_iter_next!(self)
-                };
-                while next.is_ascii_whitespace() {
-                    next = read_iter_next!(self);
-                }
+                // records are separated by one or more blank lines. Blank lines
+                // before the first record or after the last one are skipped
                 let mut str = String::new();
-                while next != b'\n' {
-                    str.push(next as char);
-                    next = read_iter_next!(self, Ok(Some(str)));
-                }
-                while next.is_ascii_whitespace() {
-                    next = read_iter_next!(self, Ok(Some(str)));
+                loop {
+                    let mut line = String::new();
+                    let mut is_last_line = true;
+                    for byte in &mut *self {
+                        let byte = byte?;
+                        if byte == b'\n' {
+                            is_last_line = false;
+                            break;
+                        }
+                        line.push(byte as char);
+                    }
+                    let is_blank = line.bytes().all(|b| b == b' ' || b == b'\t');
+                    if !is_blank {
+                        if !str.is_empty() {
+                            str.push('\n');
+                        }
+                        str.push_str(&line);
+                    }
+                    if is_last_line || (is_blank && !str.is_empty()) {
+                        break;
+                    }
                 }
-                Ok(Some(str))
+                Ok(Some(str).filter(|str| !str.is_empty()))
             }
         }
     }
@@ -98,7 +105,6 @@ pub trait RecordReader: Iterator<Item = ReadResult> {
 
 pub struct FileStream {
     bytes: Bytes<BufReader<File>>,
-    last_byte_read: Option<u8>,
     is_done: bool,
 }
 
@@ -108,7 +114,6 @@ impl FileStream {
         let reader = BufReader::new(file);
         Ok(Self {
             bytes: reader.bytes(),
-            last_byte_read: None,
             is_done: false,
         })
     }
@@ -119,10 +124,7 @@ impl Iterator for FileStream {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.bytes.next() {
-            Some(Ok(byte)) => {
-                self.last_byte_read = Some(byte);
-                Some(Ok(byte))
-            }
+            Some(Ok(byte)) => Some(Ok(byte)),
             Some(Err(e)) => Some(Err(e.to_string())),
             None => {
                 self.is_done = true;
@@ -136,10 +138,6 @@ impl RecordReader for FileStream {
     fn is_done(&self) -> bool {
         self.is_done
     }
-
-    fn last_byte_read(&self) -> Option<u8> {
-        self.last_byte_read
-    }
 }
 
 pub struct StringRecordReader {
@@ -174,14 +172,6 @@ impl RecordReader for StringRecordReader {
     fn is_done(&self) -> bool {
         self.index == self.string.len()
     }
-
-    fn last_byte_read(&self) -> Option<u8> {
-        if self.index == 0 {
-            None
-        } else {
-            Some(self.string.as_bytes()[self.index - 1])
-        }
-    }
 }
 
 pub type EmptyRecordReader = std::iter::Empty<ReadResult>;
@@ -190,10 +180,6 @@ impl RecordReader for EmptyRecordReader {
     fn is_done(&self) -> bool {
         true
     }
-
-    fn last_byte_read(&self) -> Option<u8> {
-        None
-    }
 }
 
 #[derive(Default)]
@@ -343,7 +329,6 @@ impl Drop for WritePipes {
 
 pub struct PipeRecordReader {
     pipe: *mut libc::FILE,
-    last_byte_read: Option<u8>,
     is_done: bool,
 }
 
@@ -359,7 +344,6 @@ impl PipeRecordReader {
         };
         Ok(Self {
             pipe: file,
-            last_byte_read: None,
             is_done: false,
         })
     }
@@ -374,7 +358,6 @@ impl Iterator for PipeRecordReader {
             self.is_done = true;
             None
         } else {
-            self.last_byte_read = Some(result as u8);
             Some(Ok(result as u8))
         }
     }
@@ -384,10 +367,6 @@ impl RecordReader for PipeRecordReader {
     fn is_done(&self) -> bool {
         self.is_done
     }
-
-    fn last_byte_read(&self) -> Option<u8> {
-        self.last_byte_read
-    }
 }
 
 impl Drop for PipeRecordReader {
@@ -430,7 +409,6 @@ impl ReadPipes {
 
 #[derive(Default)]
 pub struct StdinRecordReader {
-    last_byte_read: Option<u8>,
     is_done: bool,
 }
 
@@ -440,10 +418,7 @@ impl Iterator for StdinRecordReader {
     fn next(&mut self) -> Option<Self::Item> {
         let next = std::io::stdin().lock().bytes().next();
         match next {
-            Some(Ok(byte)) => {
-                self.last_byte_read = Some(byte);
-                Some(Ok(byte))
-            }
+            Some(Ok(byte)) => Some(Ok(byte)),
             Some(Err(e)) => Some(Err(e.to_string())),
             None => {
                 self.is_done = true;
@@ -457,10 +432,6 @@ impl RecordReader for StdinRecordReader {
     fn is_done(&self) -> bool {
         self.is_done
     }
-
-    fn last_byte_read(&self) -> Option<u8> {
-        self.last_byte_read
-    }
 }
 
 #[cfg(test)]
@@ -483,8 +454,11 @@ mod tests {
 
     #[test]
     fn split_records_with_default_separator() {
-        let records = split_records("record1\nrecord2\n  \t\nrecord3\n", RecordSeparator::Null);
-        assert_eq!(records, vec!["record1", "record2", "record3"]);
+        let records = split_records(
+            "\n\nrecord1\nrecord2\n  \t\n\nrecord3\n\n",
+            RecordSeparator::Null,
+        );
+        assert_eq!(records, vec!["record1\nrecord2", "record3"]);
     }
 
     #[test]
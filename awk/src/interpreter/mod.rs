@@ -362,7 +362,11 @@ fn call_simple_builtin(
             let separator = if argc == 2 {
                 None
             } else {
-                Some(FieldSeparator::Ere(stack.pop_value().into_ere()?))
+                let value = stack.pop_value();
+                match value.value {
+                    AwkValueVariant::Regex { ere, .. } => Some(FieldSeparator::Ere(ere)),
+                    _ => Some(value.scalar_to_string(&global_env.convfmt)?.try_into()?),
+                }
             };
             let s = stack
                 .pop_scalar_value()?
@@ -437,6 +441,7 @@ enum FieldSeparator {
     Default,
     Char(u8),
     Ere(Rc<Regex>),
+    Empty,
 }
 
 /// Splits a record into fields and calls the provided closure for each field.
@@ -454,10 +459,13 @@ fn split_record<S: FnMut(usize, AwkString) -> Result<(), String>>(
             s.into()
         }
     };
+    if record.is_empty() {
+        return Ok(());
+    }
     match field_separator {
         FieldSeparator::Default => record
-            .trim_start()
-            .split_ascii_whitespace()
+            .split([' ', '\t', '\n'])
+            .filter(|s| !s.is_empty())
             .enumerate()
             .try_for_each(|(i, s)| store_result(i, string(s))),
         FieldSeparator::Char(c) => record
@@ -474,15 +482,56 @@ fn split_record<S: FnMut(usize, AwkString) -> Result<(), String>>(
             }
             store_result(index, string(&record[split_start..]))
         }
+        FieldSeparator::Empty => {
+            record
+                .char_indices()
+                .enumerate()
+                .try_for_each(|(i, (start, c))| {
+                    store_result(i, string(&record[start..start + c.len_utf8()]))
+                })
+        }
     }
 }
 
+/// Splits a record read from the input into fields. If `RS` is null,
+/// newlines separate fields in addition to `FS`.
+fn split_input_record<S: FnMut(usize, AwkString) -> Result<(), String>>(
+    record: AwkString,
+    global_env: &GlobalEnv,
+    mut store_result: S,
+) -> Result<(), String> {
+    if !matches!(global_env.rs, RecordSeparator::Null)
+        || matches!(global_env.fs, FieldSeparator::Default)
+    {
+        return split_record(record, &global_env.fs, store_result);
+    }
+    let mut field_count = 0;
+    for line in record.split('\n') {
+        let line = if record.is_numeric {
+            AwkString::numeric_string(line)
+        } else {
+            line.into()
+        };
+        let mut fields_in_line = 0;
+        split_record(line, &global_env.fs, |i, s| {
+            fields_in_line = i + 1;
+            store_result(field_count + i, s)
+        })?;
+        field_count += fields_in_line;
+    }
+    Ok(())
+}
+
 impl TryFrom<AwkString> for FieldSeparator {
     type Error = String;
 
     fn try_from(value: AwkString) -> Result<Self, Self::Error> {
         if value.as_str() == " " {
             Ok(FieldSeparator::Default)
+        } else if value.is_empty() {
+            // the behaviour for an empty FS is not specified. Here we follow what other
+            // implementations do and split the record into characters
+            Ok(FieldSeparator::Empty)
         } else if value.len() == 1 {
             Ok(FieldSeparator::Char(*value.as_bytes().first().unwrap()))
         } else {
@@ -558,11 +607,11 @@ struct Record {
 impl Record {
     const MAX_FIELDS: usize = 1024;
 
-    fn reset(&mut self, record: String, field_separator: &FieldSeparator) -> Result<(), String> {
+    fn reset(&mut self, record: String, global_env: &GlobalEnv) -> Result<(), String> {
         let previous_last_field = *self.last_field.get_mut();
         let mut last_field = 0;
         let record = maybe_numeric_string(record);
-        split_record(record.clone(), field_separator, |i, s| {
+        split_input_record(record.clone(), global_env, |i, s| {
             let field_index = i + 1;
             last_field += 1;
             *self.fields[field_index].get_mut() = AwkValue::field_ref(s, field_index as u16);
@@ -604,17 +653,15 @@ impl Record {
             self.last_field.borrow().max(last_field)
         };
         let mut new_record = String::new();
-        for field in self.fields.iter().skip(1).take(last_field - 1) {
+        for (i, field) in self.fields.iter().skip(1).take(last_field).enumerate() {
+            if i > 0 {
+                write!(new_record, "{}", &global_env.ofs).expect("error writing to string");
+            }
             let field_str = (*field.get())
                 .clone()
                 .scalar_to_string(&global_env.convfmt)?;
-            write!(new_record, "{}{}", field_str, &global_env.ofs)
-                .expect("error writing to string");
+            write!(new_record, "{}", field_str).expect("error writing to string");
         }
-        let last_field_str = (*self.fields[last_field].get())
-            .clone()
-            .scalar_to_string(&global_env.convfmt)?;
-        write!(new_record, "{}", last_field_str).expect("error writing to string");
         // the spec doesn't specify if a recomputed record should be a numeric string.
         // Most other implementations don't really handle this case. Here we just
         // mark it as a numeric string if appropriate
@@ -632,7 +679,7 @@ impl Record {
         let record_str = (*self.fields[0].get())
             .to_owned()
             .scalar_to_string(&global_env.convfmt)?;
-        split_record(record_str.clone(), &global_env.fs, |i, s| {
+        split_input_record(record_str.clone(), global_env, |i, s| {
             let field_index = i + 1;
             last_field += 1;
             *self.fields[field_index].get() = AwkValue::field_ref(s, field_index as u16);
@@ -1838,18 +1885,18 @@ fn set_globals_with_assignment_arguments(
     global_env: &mut GlobalEnv,
     assignments: &[String],
 ) -> Result<(), String> {
-    assignments
-        .iter()
-        .filter_map(|s| parse_assignment(s))
-        .filter_map(|(var, value)| globals.get(var).copied().map(|index| (index, value)))
-        .try_for_each(|(global_index, value)| {
+    for assignment in assignments {
+        let (var, value) = parse_assignment(assignment)
+            .ok_or_else(|| format!("invalid variable assignment '{}'", assignment))?;
+        // variables the program never mentions cannot affect it
+        if let Some(&global_index) = globals.get(var) {
             let value = escape_string_contents(value)?;
             interpreter.globals[global_index as usize]
                 .get_mut()
-                .assign(maybe_numeric_string(value), global_env)
-                .expect("failed to assign value");
-            Ok(())
-        })
+                .assign(maybe_numeric_string(value), global_env)?;
+        }
+    }
+    Ok(())
 }
 
 pub fn interpret(
@@ -1890,7 +1937,10 @@ pub fn interpret(
     if let Some(separator) = separator {
         interpreter.globals[SpecialVar::Fs as usize]
             .get_mut()
-            .assign(AwkString::from(separator), &mut global_env)?;
+            .assign(
+                maybe_numeric_string(escape_string_contents(&separator)?),
+                &mut global_env,
+            )?;
     }
 
     for action in program.begin_actions {
@@ -1970,7 +2020,7 @@ pub fn interpret(
 
         global_env.fnr = 1;
         'record_loop: while let Some(record) = reader.read_next_record(&global_env.rs)? {
-            current_record.reset(record, &global_env.fs)?;
+            current_record.reset(record, &global_env)?;
             interpreter.globals[SpecialVar::Nf as usize].get_mut().value =
                 AwkValue::from(current_record.get_last_field() as f64).value;
             global_env.nf = current_record.get_last_field();
@@ -2121,7 +2171,7 @@ mod tests {
 
         fn add_record(mut self, record_string: &str) -> Self {
             self.record
-                .reset(record_string.to_string(), &FieldSeparator::Default)
+                .reset(record_string.to_string(), &GlobalEnv::default())
                 .expect("could not split record");
             self
         }
@@ -48,7 +48,7 @@ fn exit_if_error<T, U: Display>(r: Result<T, U>) -> T {
     match r {
         Ok(v) => v,
         Err(err) => {
-            eprintln!("{err}");
+            eprintln!("awk: {err}");
             std::process::exit(1);
         }
     }
@@ -64,11 +64,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let return_status = if !args.program_files.is_empty() {
         let mut sources = Vec::new();
         for source_file in &args.program_files {
-            let mut file = std::fs::File::open(source_file)
-                .map_err(|_| gettext!("could not open file '{}'", source_file))?;
+            let mut file = exit_if_error(
+                std::fs::File::open(source_file)
+                    .map_err(|_| gettext!("could not open file '{}'", source_file)),
+            );
             let mut contents = String::new();
-            file.read_to_string(&mut contents)
-                .map_err(|_| gettext!("could not read file '{}'", source_file))?;
+            exit_if_error(
+                file.read_to_string(&mut contents)
+                    .map_err(|_| gettext!("could not read file '{}'", source_file)),
+            );
             sources.push(SourceFile {
                 contents,
                 filename: source_file.clone(),
@@ -0,0 +1,37 @@
+BEGIN {
+    $0 = " \t a  b \n c \t"
+    print NF ": " $1 "|" $2 "|" $3
+
+    FS = "|"
+    $0 = "a|b||c"
+    print NF ": " $2 "|" $3 "|" $4
+
+    FS = "."
+    $0 = "a.b.c"
+    print NF ": " $2
+
+    FS = "\t"
+    $0 = "a b\tc"
+    print NF ": " $1
+
+    FS = "[0-9]+"
+    $0 = "a1b22c333"
+    print NF ": " $1 "|" $2 "|" $3 "|" $4
+
+    FS = ","
+    $0 = ""
+    print NF
+
+    $0 = "a,b c"
+    FS = " "
+    print NF ": " $2
+
+    n = split("a:b:c", parts, ":")
+    print n ": " parts[2]
+    n = split("a1b22c", parts, /[0-9]+/)
+    print n ": " parts[3]
+    n = split("", parts, ",")
+    print n
+    n = split("  x  y  ", parts)
+    print n ": " parts[1] parts[2]
+}
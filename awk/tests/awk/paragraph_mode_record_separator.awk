@@ -0,0 +1,14 @@
+BEGIN {
+    RS = ""
+}
+
+{
+    print NR ": " NF
+    for (i = 1; i <= NF; i++) {
+        print "[" $i "]"
+    }
+}
+
+NR == 2 {
+    FS = ","
+}
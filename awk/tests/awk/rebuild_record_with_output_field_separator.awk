@@ -0,0 +1,17 @@
+BEGIN {
+    OFS = "-"
+}
+
+{
+    print
+    $1 = $1
+    print
+    $2 = "X"
+    print
+    $(NF + 2) = "Y"
+    print NF ": " $0
+    NF = 2
+    print NF ": " $0
+    NF = 0
+    print NF ": [" $0 "]"
+}
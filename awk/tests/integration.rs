@@ -1,4 +1,5 @@
 use plib::testing::{run_test, run_test_with_checker, TestPlan};
+use std::iter;
 
 fn test_awk(args: Vec<String>, expected_output: &str) {
     run_test(TestPlan {
@@ -242,6 +243,40 @@ fn test_awk_ere_field_separator() {
     test_awk!(ere_field_separator, "tests/awk/test_data4.txt");
 }
 
+#[test]
+fn test_awk_field_separator_edge_cases() {
+    test_awk!(field_separator_edge_cases);
+}
+
+#[test]
+fn test_awk_paragraph_mode_record_separator() {
+    test_awk!(paragraph_mode_record_separator, "tests/awk/test_data5.txt");
+}
+
+#[test]
+fn test_awk_rebuild_record_with_output_field_separator() {
+    test_awk!(
+        rebuild_record_with_output_field_separator,
+        "tests/awk/test_data3.txt"
+    );
+}
+
+#[test]
+fn test_awk_field_separator_option_is_escaped() {
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec![
+            "-F".to_string(),
+            "\\t".to_string(),
+            "{ print $2 }".to_string(),
+        ],
+        stdin_data: String::from("a b\tc d\n"),
+        expected_out: String::from("c d\n"),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+    })
+}
+
 #[test]
 fn test_awk_program_with_only_end_actions_reads_input_files() {
     test_awk!(
@@ -567,3 +602,89 @@ fn test_awk_modifying_nf_recomputes_the_record() {
         "tests/awk/test_data.txt"
     );
 }
+
+#[test]
+fn test_awk_cli_assignment_to_special_variables() {
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec![
+            "-v".to_string(),
+            "OFS=-".to_string(),
+            "-v".to_string(),
+            "FS=,".to_string(),
+            "{ print $1, $2 }".to_string(),
+        ],
+        stdin_data: String::from("a,b\n"),
+        expected_out: String::from("a-b\n"),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+    })
+}
+
+#[test]
+fn test_awk_operand_assignment_to_special_variable() {
+    let second_fields = include_str!("awk/test_data.txt")
+        .lines()
+        .map(|line| format!("{}\n", line.split_whitespace().nth(1).unwrap()))
+        .collect::<String>();
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec![
+            "{ print $2 }".to_string(),
+            "tests/awk/test_data.txt".to_string(),
+            "FS=,".to_string(),
+            "tests/awk/test_data.csv".to_string(),
+        ],
+        stdin_data: String::new(),
+        expected_out: second_fields.repeat(2),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+    })
+}
+
+#[test]
+fn test_awk_invalid_cli_variable_assignment() {
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec![
+            "-v".to_string(),
+            "1x=3".to_string(),
+            "BEGIN { print 1 }".to_string(),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::from(""),
+        expected_err: String::from("awk: invalid variable assignment '1x=3'\n"),
+        expected_exit_code: 1,
+    })
+}
+
+#[test]
+fn test_awk_add_input_file_to_argv_while_reading() {
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec![
+            "NR == 1 { ARGV[ARGC++] = \"-\" } { print FILENAME \": \" $0 }".to_string(),
+            "tests/awk/test_data.txt".to_string(),
+        ],
+        stdin_data: String::from("from stdin\n"),
+        expected_out: include_str!("awk/test_data.txt")
+            .lines()
+            .map(|line| format!("tests/awk/test_data.txt: {}\n", line))
+            .chain(iter::once(String::from("-: from stdin\n")))
+            .collect(),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+    })
+}
+
+#[test]
+fn test_awk_missing_program_file() {
+    run_test(TestPlan {
+        cmd: String::from("awk"),
+        args: vec!["-f".to_string(), "tests/awk/missing.awk".to_string()],
+        stdin_data: String::new(),
+        expected_out: String::from(""),
+        expected_err: String::from("awk: could not open file 'tests/awk/missing.awk'\n"),
+        expected_exit_code: 1,
+    })
+}
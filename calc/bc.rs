@@ -8,6 +8,7 @@
 //
 
 use std::ffi::OsString;
+use std::io::IsTerminal;
 
 use bc_util::{
     interpreter::{ExecutionResult, Interpreter},
@@ -75,6 +76,7 @@ fn main() -> Result<()> {
         }
     }
 
+    let interactive = std::io::stdin().is_terminal();
     let mut repl = DefaultEditor::new()?;
     let mut line_buffer = String::new();
     while !interpreter.has_quit() {
@@ -101,11 +103,15 @@ fn main() -> Result<()> {
                 repl.add_history_entry(line)?;
             }
             Err(ReadlineError::Eof) => {
-                println!("CTRL-D");
+                if interactive {
+                    println!("CTRL-D");
+                }
                 break;
             }
             Err(ReadlineError::Interrupted) => {
-                println!("CTRL-C");
+                if interactive {
+                    println!("CTRL-C");
+                }
                 break;
             }
             Err(e) => {
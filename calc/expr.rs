@@ -7,378 +7,227 @@
 // SPDX-License-Identifier: MIT
 //
 
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
+use std::cmp::Ordering;
+use std::io::Write;
+
+use plib::collate::strcoll;
+use plib::regex::Regex;
+
+/// The expression is invalid, or its evaluation failed.
+const EXIT_INVALID: i32 = 2;
+
+/// The result could not be written.
+const EXIT_ERROR: i32 = 3;
 
 #[derive(Clone, Debug, PartialEq)]
-enum Token {
-    LParen,
-    RParen,
-    OpMul,
-    OpDiv,
-    OpRem,
-    OpAdd,
-    OpSub,
-    OpEq,
-    OpGT,
-    OpLT,
-    OpGE,
-    OpLE,
-    OpNE,
-    OpAnd,
-    OpOr,
-    OpMatch,
+enum Value {
     Integer(i64),
     Str(String),
 }
 
-// comparison operators
-#[derive(Clone, Debug)]
-enum CmpOp {
-    EQ,
-    NE,
-    GT,
-    LT,
-    GE,
-    LE,
-}
-
-// integer operations
-#[derive(Clone, Debug)]
-enum IntOp {
-    Add,
-    Sub,
-    Mul,
-    Div,
-    Rem,
-}
-
-// convert an lval to a string
-fn token_display(t: &Token) -> String {
-    match t {
-        Token::Integer(val) => val.to_string(),
-        Token::Str(val) => String::from(val),
-        _ => {
-            panic!("BUG: not an lval");
+impl Value {
+    /// An operand is an integer if it is decimal digits, optionally after
+    /// a minus sign, and a string otherwise.
+    fn from_operand(s: &str) -> Value {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(n) = s.parse() {
+                return Value::Integer(n);
+            }
         }
+        Value::Str(String::from(s))
     }
-}
-
-// is token an lval?
-fn token_is_lval(t: &Token) -> bool {
-    match t {
-        Token::Integer(_) => true,
-        Token::Str(_) => true,
-        _ => false,
-    }
-}
-
-// is token zero?
-fn token_is_zero(t: &Token) -> bool {
-    match t {
-        Token::Integer(val) => *val == 0,
-        Token::Str(s) => s.is_empty(),
-        _ => false,
-    }
-}
-
-// convert token to string
-fn token_to_string(t: &Token) -> Result<String, &'static str> {
-    match t {
-        Token::Integer(val) => Ok(val.to_string()),
-        Token::Str(val) => Ok(String::from(val)),
-        _ => Err("syntax error: not a string"),
-    }
-}
 
-// convert token to integer
-fn token_to_int(t: &Token) -> Option<i64> {
-    match t {
-        Token::Integer(val) => Some(*val),
-        _ => None,
+    fn to_int(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            Value::Str(s) => match Value::from_operand(s) {
+                Value::Integer(n) => Some(n),
+                Value::Str(_) => None,
+            },
+        }
     }
-}
 
-// convert token to integer, returning an error if not an integer
-fn token_to_int_req(t: &Token) -> Result<i64, &'static str> {
-    match token_to_int(t) {
-        Some(val) => Ok(val),
-        None => Err("syntax error: not an integer"),
+    /// Whether the value is null or zero, which | and & take as false.
+    fn is_null_or_zero(&self) -> bool {
+        match self {
+            Value::Integer(n) => *n == 0,
+            Value::Str(s) => s.is_empty() || self.to_int() == Some(0),
+        }
     }
 }
 
-// parse a single token
-fn parse_token(s: &str) -> Token {
-    match s {
-        "(" => Token::LParen,
-        ")" => Token::RParen,
-        "*" => Token::OpMul,
-        "/" => Token::OpDiv,
-        "%" => Token::OpRem,
-        "+" => Token::OpAdd,
-        "-" => Token::OpSub,
-        "=" => Token::OpEq,
-        ">" => Token::OpGT,
-        "<" => Token::OpLT,
-        ">=" => Token::OpGE,
-        "<=" => Token::OpLE,
-        "!=" => Token::OpNE,
-        "&" => Token::OpAnd,
-        "|" => Token::OpOr,
-        ":" => Token::OpMatch,
-        _ => match s.parse::<i64>() {
-            Ok(n) => Token::Integer(n),
-            Err(_) => Token::Str(String::from(s)),
-        },
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+        }
     }
 }
 
-// tokenize the command line arguments, all in a single pass
-fn tokenize() -> Vec<Token> {
-    // collect program's command line args
-    let mut args: Vec<String> = std::env::args().collect();
-    args.remove(0); // remove 1st value, the unnecessary program name
-
-    // parse each arg into a Token
-    let mut tokens = Vec::new();
-    for arg in &args {
-        tokens.push(parse_token(arg));
+/// Compares two values as integers if both are, and otherwise as strings
+/// in the collating sequence of the locale.
+fn compare(lhs: &Value, rhs: &Value) -> Ordering {
+    match (lhs.to_int(), rhs.to_int()) {
+        (Some(l), Some(r)) => l.cmp(&r),
+        _ => strcoll(lhs.to_string().as_bytes(), rhs.to_string().as_bytes()),
     }
-
-    tokens
 }
 
-// compare two integers
-fn cmpint(lhs: i64, rhs: i64, op: CmpOp) -> Token {
-    let result: bool = match op {
-        CmpOp::EQ => lhs == rhs,
-        CmpOp::NE => lhs != rhs,
-        CmpOp::GT => lhs > rhs,
-        CmpOp::LT => lhs < rhs,
-        CmpOp::GE => lhs >= rhs,
-        CmpOp::LE => lhs <= rhs,
+/// Applies an arithmetic operator to integer operands.
+fn arithmetic(op: &str, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let (Some(l), Some(r)) = (lhs.to_int(), rhs.to_int()) else {
+        return Err(plib::tr!("non-integer argument"));
     };
-
-    Token::Integer(result as i64)
-}
-
-// compare two strings
-fn cmpstr(lhs: &Token, rhs: &Token, op: CmpOp) -> Result<Token, &'static str> {
-    let lhs = token_to_string(lhs)?;
-    let rhs = token_to_string(rhs)?;
-
-    let result: bool = match op {
-        CmpOp::EQ => lhs == rhs,
-        CmpOp::NE => lhs != rhs,
-        CmpOp::GT => lhs > rhs,
-        CmpOp::LT => lhs < rhs,
-        CmpOp::GE => lhs >= rhs,
-        CmpOp::LE => lhs <= rhs,
+    if r == 0 && (op == "/" || op == "%") {
+        return Err(plib::tr!("division by zero"));
+    }
+    let result = match op {
+        "+" => l.checked_add(r),
+        "-" => l.checked_sub(r),
+        "*" => l.checked_mul(r),
+        "/" => l.checked_div(r),
+        _ => l.checked_rem(r),
     };
-
-    Ok(Token::Integer(result as i64))
-}
-
-// perform a comparison operation
-fn cmpop(lhs: &Token, rhs: &Token, op: CmpOp) -> Result<Token, &'static str> {
-    let lhs_int = token_to_int(lhs);
-    let rhs_int = token_to_int(rhs);
-
-    match (lhs_int, rhs_int) {
-        (Some(lhs), Some(rhs)) => {
-            // if both are integers, perform int comparison
-            Ok(cmpint(lhs, rhs, op))
-        }
-        // otherwise, convert int to string, and perform string compare
-        (Some(lhs), _) => {
-            let tmp = Token::Str(lhs.to_string());
-            cmpstr(&tmp, rhs, op)
-        }
-        (_, Some(rhs)) => {
-            let tmp = Token::Str(rhs.to_string());
-            cmpstr(lhs, &tmp, op)
-        }
-        _ => cmpstr(lhs, rhs, op),
+    result
+        .map(Value::Integer)
+        .ok_or_else(|| plib::tr!("integer overflow"))
+}
+
+/// The `:` operator: matches the BRE against the start of the string,
+/// giving the text matched by `\(...\)` if the BRE has it, and the number
+/// of characters matched otherwise.
+fn match_op(lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let text = lhs.to_string();
+    let pattern = rhs.to_string();
+    let re = Regex::bre(pattern.as_bytes()).map_err(|e| format!("'{}': {}", pattern, e))?;
+
+    // The leftmost match starts at 0 if any match there does
+    let groups = re
+        .captures(text.as_bytes())
+        .filter(|groups| groups[0].as_ref().is_some_and(|m| m.start == 0));
+    if re.groups() > 0 {
+        let group = groups.and_then(|groups| groups[1].clone());
+        Ok(Value::Str(group.map_or_else(String::new, |m| {
+            String::from_utf8_lossy(&text.as_bytes()[m]).into_owned()
+        })))
+    } else {
+        let len = groups.and_then(|groups| groups[0].clone()).map_or(0, |m| {
+            String::from_utf8_lossy(&text.as_bytes()[m]).chars().count()
+        });
+        Ok(Value::Integer(len as i64))
     }
 }
 
-// perform an integer math operation
-fn intop(lhs: &Token, rhs: &Token, op: IntOp) -> Result<Token, &'static str> {
-    let i1 = token_to_int_req(lhs)?;
-    let i2 = token_to_int_req(rhs)?;
-
-    match op {
-        IntOp::Add => Ok(Token::Integer(i1 + i2)),
-        IntOp::Sub => Ok(Token::Integer(i1 - i2)),
-        IntOp::Mul => Ok(Token::Integer(i1 * i2)),
-        IntOp::Div => Ok(Token::Integer(i1 / i2)),
-        IntOp::Rem => Ok(Token::Integer(i1 % i2)),
+/// A recursive-descent evaluator over the arguments, one level per
+/// precedence class, lowest first: `|`, `&`, comparisons, `+ -`,
+/// `* / %`, `:`, and operands and parentheses. All operators are left
+/// associative.
+struct Parser<'a> {
+    args: &'a [String],
+    pos: usize,
+}
+
+/// The operators at each precedence level, lowest first.
+const LEVELS: [&[&str]; 6] = [
+    &["|"],
+    &["&"],
+    &["=", ">", ">=", "<", "<=", "!="],
+    &["+", "-"],
+    &["*", "/", "%"],
+    &[":"],
+];
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(String::as_str)
     }
-}
 
-// logical and/or operation
-fn logop(lhs: &Token, rhs: &Token, is_and: bool) -> Token {
-    let lhs_zero = token_is_zero(lhs);
-    let rhs_zero = token_is_zero(rhs);
-
-    if is_and {
-        if !lhs_zero && !rhs_zero {
-            lhs.clone()
-        } else {
-            Token::Integer(0)
+    fn expression(&mut self, level: usize) -> Result<Value, String> {
+        if level == LEVELS.len() {
+            return self.primary();
         }
-    } else {
-        if !lhs_zero {
-            lhs.clone()
-        } else if !rhs_zero {
-            rhs.clone()
-        } else {
-            Token::Integer(0)
+        let mut lhs = self.expression(level + 1)?;
+        while let Some(op) = self.peek().filter(|op| LEVELS[level].contains(op)) {
+            self.pos += 1;
+            let rhs = self.expression(level + 1)?;
+            lhs = match op {
+                "|" if !lhs.is_null_or_zero() => lhs,
+                "|" if !rhs.is_null_or_zero() => rhs,
+                "|" => Value::Integer(0),
+                "&" if !lhs.is_null_or_zero() && !rhs.is_null_or_zero() => lhs,
+                "&" => Value::Integer(0),
+                "+" | "-" | "*" | "/" | "%" => arithmetic(op, &lhs, &rhs)?,
+                ":" => match_op(&lhs, &rhs)?,
+                _ => {
+                    let ordering = compare(&lhs, &rhs);
+                    let result = match op {
+                        "=" => ordering == Ordering::Equal,
+                        "!=" => ordering != Ordering::Equal,
+                        ">" => ordering == Ordering::Greater,
+                        ">=" => ordering != Ordering::Less,
+                        "<" => ordering == Ordering::Less,
+                        _ => ordering != Ordering::Greater,
+                    };
+                    Value::Integer(i64::from(result))
+                }
+            };
         }
+        Ok(lhs)
     }
-}
-
-// regex match operation
-fn matchop(lhs: &Token, rhs: &Token) -> Result<Token, &'static str> {
-    let lhs = token_to_string(lhs)?;
-    let rhs = token_to_string(rhs)?;
-
-    let re = match Regex::new(&rhs) {
-        Ok(re_res) => re_res,
-        Err(_) => {
-            return Err("invalid regex");
-        }
-    };
-
-    match re.captures(&lhs) {
-        // no regex match: zero
-        None => Ok(Token::Integer(0)),
-
-        // regex matched
-        Some(caps) => {
-            let cap1 = caps.get(1);
 
-            // if regex subexpression #1 matched, return as string
-            if let Some(mtch) = cap1 {
-                Ok(Token::Str(String::from(mtch.as_str())))
-
-            // otherwise, return length of overall match as int
-            } else {
-                Ok(Token::Integer(caps.get(0).unwrap().len() as i64))
+    /// An operand, or a parenthesized expression. Any argument where an
+    /// operand is expected is taken as one, so that in `expr + : +` both
+    /// operands are the string "+".
+    fn primary(&mut self) -> Result<Value, String> {
+        let Some(arg) = self.peek() else {
+            return Err(match self.pos.checked_sub(1) {
+                Some(prev) => {
+                    plib::tr!("syntax error: missing argument after '{}'", self.args[prev])
+                }
+                None => plib::tr!("syntax error: missing argument"),
+            });
+        };
+        self.pos += 1;
+        if arg == "(" && self.pos < self.args.len() {
+            let value = self.expression(0)?;
+            if self.peek() != Some(")") {
+                return Err(plib::tr!("syntax error: expecting ')'"));
             }
+            self.pos += 1;
+            return Ok(value);
         }
+        Ok(Value::from_operand(arg))
     }
 }
 
-// find closing right paren
-fn find_matching_paren(tokens: &[Token]) -> Option<usize> {
-    let mut depth = 0;
-    for (i, token) in tokens.iter().enumerate() {
-        match token {
-            Token::LParen => depth += 1,
-            Token::RParen => {
-                if depth == 0 {
-                    return None;
-                }
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
-                }
-            }
-            _ => {}
-        }
+fn evaluate(args: &[String]) -> Result<Value, String> {
+    let mut parser = Parser { args, pos: 0 };
+    let value = parser.expression(0)?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(arg) => Err(plib::tr!("syntax error: unexpected argument '{}'", arg)),
     }
-
-    None
 }
 
-// evaluate an expression
-fn eval_expression(tokens: &[Token]) -> Result<Token, &'static str> {
-    let mut tokens = tokens.to_vec();
+fn main() {
+    plib::i18n::init();
 
-    // continually consume tokens until only one remains
-    while tokens.len() >= 3 {
-        // handle nested expressions: left hand side
-        if tokens[0] == Token::LParen {
-            if let Some(i) = find_matching_paren(&tokens) {
-                let subexpr = &tokens[1..i];
-                let result = eval_expression(subexpr)?;
-                tokens.splice(0..=i, vec![result]);
-                continue;
-            } else {
-                return Err("syntax error EP0: unmatched left paren");
-            }
+    // expr takes no options: every argument is part of the expression
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = match evaluate(&args) {
+        Ok(value) => value,
+        Err(e) => {
+            plib::diag!("{}", e);
+            std::process::exit(EXIT_INVALID);
         }
-        // handle nested expressions: right hand side
-        if tokens[2] == Token::LParen {
-            if let Some(i) = find_matching_paren(&tokens[2..]) {
-                let subexpr = &tokens[3..i + 2];
-                let result = eval_expression(subexpr)?;
-                tokens.splice(2..=i + 2, vec![result]);
-                continue;
-            } else {
-                return Err("syntax error EP1: unmatched left paren");
-            }
-        }
-
-        // extract our left hand side, operator, and right hand side
-        let lhs = &tokens[0];
-        let operator = &tokens[1];
-        let rhs = &tokens[2];
-
-        // dispatch to the appropriate operation
-        let result = match operator {
-            Token::OpAdd => intop(lhs, rhs, IntOp::Add)?,
-            Token::OpSub => intop(lhs, rhs, IntOp::Sub)?,
-            Token::OpMul => intop(lhs, rhs, IntOp::Mul)?,
-            Token::OpDiv => intop(lhs, rhs, IntOp::Div)?,
-            Token::OpRem => intop(lhs, rhs, IntOp::Rem)?,
-
-            Token::OpEq => cmpop(lhs, rhs, CmpOp::EQ)?,
-            Token::OpNE => cmpop(lhs, rhs, CmpOp::NE)?,
-            Token::OpGT => cmpop(lhs, rhs, CmpOp::GT)?,
-            Token::OpLT => cmpop(lhs, rhs, CmpOp::LT)?,
-            Token::OpGE => cmpop(lhs, rhs, CmpOp::GE)?,
-            Token::OpLE => cmpop(lhs, rhs, CmpOp::LE)?,
-
-            Token::OpAnd => logop(lhs, rhs, true),
-            Token::OpOr => logop(lhs, rhs, false),
-
-            Token::OpMatch => matchop(lhs, rhs)?,
-
-            Token::LParen | Token::RParen | Token::Integer(_) | Token::Str(_) => {
-                return Err("syntax error: wanted operator");
-            }
-        };
-
-        // replace the lhs, operator, and rhs with the result
-        tokens.splice(0..=2, vec![result]);
-    }
+    };
 
-    // final result should be a single token
-    if tokens.len() == 1 {
-        let lhs = &tokens[0];
-        if token_is_lval(lhs) {
-            Ok(lhs.clone())
-        } else {
-            Err("syntax error: E1")
-        }
-    } else {
-        Err("syntax error: E2")
+    let mut stdout = plib::io::stdout();
+    if let Err(e) = writeln!(stdout, "{}", value).and_then(|()| stdout.finish()) {
+        plib::diag!("{}", plib::diag::error_message(&e));
+        std::process::exit(EXIT_ERROR);
     }
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
-
-    // tokenize and evaluate the expression
-    let arg_tokens = tokenize();
-    let final_val = eval_expression(&arg_tokens)?;
-
-    // display the result
-    println!("{}", token_display(&final_val));
-
-    Ok(())
+    std::process::exit(i32::from(value.is_null_or_zero()));
 }
@@ -293,3 +293,13 @@ fn test_bc_sin_to_scale_18() {
 fn test_bc_cos_to_scale_18() {
     test_bc_l!(cos_to_scale_18)
 }
+
+#[test]
+fn test_bc_math_functions_truncate_to_scale() {
+    test_bc_l!(math_functions_truncate_to_scale)
+}
+
+#[test]
+fn test_bc_end_of_input_without_quit() {
+    test_bc("1 + 1\n", "2\n");
+}
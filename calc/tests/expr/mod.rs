@@ -10,6 +10,10 @@
 use plib::testing::{run_test, TestPlan};
 
 fn expr_test(args: &[&str], expected_output: &str) {
+    expr_test_status(args, expected_output, "", 0);
+}
+
+fn expr_test_status(args: &[&str], expected_output: &str, expected_error: &str, code: i32) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
 
     run_test(TestPlan {
@@ -17,17 +21,17 @@ fn expr_test(args: &[&str], expected_output: &str) {
         args: str_args,
         stdin_data: String::new(),
         expected_out: String::from(expected_output),
-        expected_err: String::from(""),
-        expected_exit_code: 0,
+        expected_err: String::from(expected_error),
+        expected_exit_code: code,
     });
 }
 
 #[test]
 fn expr_logops() {
-    expr_test(&["4", "|", "5", "+", "1"], "5\n");
-    expr_test(&["0", "|", "5", "+", "1"], "6\n");
-    expr_test(&["4", "&", "5", "+", "1"], "5\n");
-    expr_test(&["4", "&", "0", "+", "1"], "1\n");
+    expr_test(&["(", "4", "|", "5", ")", "+", "1"], "5\n");
+    expr_test(&["(", "0", "|", "5", ")", "+", "1"], "6\n");
+    expr_test(&["(", "4", "&", "5", ")", "+", "1"], "5\n");
+    expr_test(&["(", "4", "&", "0", ")", "+", "1"], "1\n");
     expr_test(&["0", "%", "5", "+", "1"], "1\n");
 }
 
@@ -42,20 +46,87 @@ fn expr_intops() {
 
 #[test]
 fn expr_cmpint() {
-    expr_test(&["4", "<", "5", "+", "1"], "2\n");
-    expr_test(&["4", ">", "5", "+", "1"], "1\n");
-    expr_test(&["4", "<=", "5", "+", "1"], "2\n");
-    expr_test(&["4", ">=", "5", "+", "1"], "1\n");
-    expr_test(&["4", "=", "5", "+", "1"], "1\n");
-    expr_test(&["4", "!=", "5", "+", "1"], "2\n");
+    expr_test(&["(", "4", "<", "5", ")", "+", "1"], "2\n");
+    expr_test(&["(", "4", ">", "5", ")", "+", "1"], "1\n");
+    expr_test(&["(", "4", "<=", "5", ")", "+", "1"], "2\n");
+    expr_test(&["(", "4", ">=", "5", ")", "+", "1"], "1\n");
+    expr_test(&["(", "4", "=", "5", ")", "+", "1"], "1\n");
+    expr_test(&["(", "4", "!=", "5", ")", "+", "1"], "2\n");
 }
 
 #[test]
 fn expr_cmpstr() {
-    expr_test(&["aaa", "<", "bbb", "+", "1"], "2\n");
-    expr_test(&["aaa", ">", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "<=", "bbb", "+", "1"], "2\n");
-    expr_test(&["aaa", ">=", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "=", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "!=", "bbb", "+", "1"], "2\n");
+    expr_test(&["(", "aaa", "<", "bbb", ")", "+", "1"], "2\n");
+    expr_test(&["(", "aaa", ">", "bbb", ")", "+", "1"], "1\n");
+    expr_test(&["(", "aaa", "<=", "bbb", ")", "+", "1"], "2\n");
+    expr_test(&["(", "aaa", ">=", "bbb", ")", "+", "1"], "1\n");
+    expr_test(&["(", "aaa", "=", "bbb", ")", "+", "1"], "1\n");
+    expr_test(&["(", "aaa", "!=", "bbb", ")", "+", "1"], "2\n");
+}
+
+#[test]
+fn expr_precedence() {
+    expr_test(&["2", "+", "3", "*", "4"], "14\n");
+    expr_test(&["(", "2", "+", "3", ")", "*", "4"], "20\n");
+    expr_test(&["10", "-", "4", "-", "3"], "3\n");
+    expr_test(&["4", "<", "5", "+", "1"], "1\n");
+    expr_test(&["4", "|", "5", "+", "1"], "4\n");
+    expr_test(&["1", "=", "1", "&", "abc", ":", "a"], "1\n");
+}
+
+#[test]
+fn expr_numeric_string_duality() {
+    expr_test(&["10", ">", "9"], "1\n");
+    expr_test_status(&["10", ">", "9a"], "0\n", "", 1);
+    expr_test(&["-5", "<", "3"], "1\n");
+    expr_test_status(&["+5", "=", "5"], "0\n", "", 1);
+}
+
+#[test]
+fn expr_match() {
+    expr_test(&["abcdef", ":", "abc"], "3\n");
+    expr_test_status(&["abcdef", ":", "bcd"], "0\n", "", 1);
+    expr_test(&["abcdef", ":", ".*"], "6\n");
+    expr_test(&["foo.c", ":", "\\(.*\\)\\.c"], "foo\n");
+    expr_test_status(&["foo.h", ":", "\\(.*\\)\\.c"], "\n", "", 1);
+    expr_test(&["a+b", ":", "a+"], "2\n");
+    expr_test(&["+", ":", "+"], "1\n");
+}
+
+#[test]
+fn expr_exit_status() {
+    expr_test_status(&["0"], "0\n", "", 1);
+    expr_test_status(&[""], "\n", "", 1);
+    expr_test_status(&["0", "&", "1"], "0\n", "", 1);
+    expr_test(&["", "|", "x"], "x\n");
+}
+
+#[test]
+fn expr_errors() {
+    expr_test_status(&["1", "+", "a"], "", "expr: non-integer argument\n", 2);
+    expr_test_status(&["1", "/", "0"], "", "expr: division by zero\n", 2);
+    expr_test_status(
+        &["9223372036854775807", "+", "1"],
+        "",
+        "expr: integer overflow\n",
+        2,
+    );
+    expr_test_status(
+        &["1", "+"],
+        "",
+        "expr: syntax error: missing argument after '+'\n",
+        2,
+    );
+    expr_test_status(
+        &["1", "2"],
+        "",
+        "expr: syntax error: unexpected argument '2'\n",
+        2,
+    );
+    expr_test_status(
+        &["(", "1", "+", "2"],
+        "",
+        "expr: syntax error: expecting ')'\n",
+        2,
+    );
 }
@@ -0,0 +1,185 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod spool;
+mod timespec;
+
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use chrono::Local;
+use clap::Parser;
+use plib::diag::error_message;
+use plib::{diag, tdiag, tr};
+
+use spool::{AtJob, AtScript, User};
+
+/// at - execute commands at a later time
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Mail the user when the job has run, even if it had no output
+    #[arg(short = 'm', conflicts_with_all = ["list", "remove"])]
+    mail: bool,
+
+    /// Read the commands from the file instead of standard input
+    #[arg(short = 'f', conflicts_with_all = ["list", "remove"])]
+    file: Option<PathBuf>,
+
+    /// Queue of the jobs: a for at, b for batch, or another letter
+    #[arg(short = 'q', value_parser = parse_queue, conflicts_with = "remove")]
+    queue: Option<char>,
+
+    /// Run the job at the time [[CC]YY]MMDDhhmm[.SS]
+    #[arg(short = 't', conflicts_with_all = ["list", "remove"])]
+    time: Option<String>,
+
+    /// Remove the jobs of the operands
+    #[arg(short = 'r', conflicts_with = "list")]
+    remove: bool,
+
+    /// List the jobs of the operands, or all the jobs of the user
+    #[arg(short = 'l')]
+    list: bool,
+
+    /// A timespec, or with -r or -l, the IDs of jobs
+    operands: Vec<String>,
+}
+
+fn parse_queue(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(c),
+        _ => Err(tr!("invalid queue name '{}'", s)),
+    }
+}
+
+/// The jobs the user may see: their own, or all of them for the superuser.
+fn user_jobs(user: &User) -> io::Result<Vec<AtJob>> {
+    let mut jobs = spool::at_jobs(&spool::at_dir()?)?;
+    jobs.retain(|job| user.is_root() || job.owner == user.uid);
+    Ok(jobs)
+}
+
+/// Finds the job of each ID, reporting the IDs that match none.
+fn select_jobs(jobs: Vec<AtJob>, ids: &[String]) -> (Vec<AtJob>, bool) {
+    let mut success = true;
+    let mut selected = Vec::new();
+    for id in ids {
+        let number = id.parse::<u32>().ok();
+        match jobs.iter().position(|job| Some(job.number) == number) {
+            Some(pos) => selected.push(jobs[pos].clone()),
+            None => {
+                tdiag!("{}: no such job", id);
+                success = false;
+            }
+        }
+    }
+    (selected, success)
+}
+
+fn list(user: &User, queue: Option<char>, ids: &[String]) -> io::Result<bool> {
+    let mut jobs = user_jobs(user)?;
+    if let Some(queue) = queue {
+        jobs.retain(|job| job.queue == queue);
+    }
+    let (jobs, success) = if ids.is_empty() {
+        (jobs, true)
+    } else {
+        select_jobs(jobs, ids)
+    };
+    for job in jobs {
+        println!("{}\t{}", job.number, spool::format_time(job.time));
+    }
+    Ok(success)
+}
+
+fn remove(user: &User, ids: &[String]) -> io::Result<bool> {
+    let (jobs, mut success) = select_jobs(user_jobs(user)?, ids);
+    for job in jobs {
+        if let Err(e) = std::fs::remove_file(&job.path) {
+            diag!("{}: {}", job.number, error_message(&e));
+            success = false;
+        }
+    }
+    Ok(success)
+}
+
+fn submit(user: &User, args: &Args) -> Result<(), String> {
+    let now = Local::now();
+    let time = match &args.time {
+        Some(time) => {
+            if !args.operands.is_empty() {
+                return Err(tr!("a timespec can't be given with -t"));
+            }
+            timespec::parse_touch_time(time, &now)?
+        }
+        None => {
+            if args.operands.is_empty() {
+                return Err(tr!("missing timespec"));
+            }
+            timespec::parse_timespec(&args.operands.join(" "), &now)?
+        }
+    };
+
+    let commands = match &args.file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), error_message(&e)))?,
+        None => {
+            let mut commands = String::new();
+            io::stdin()
+                .read_to_string(&mut commands)
+                .map_err(|e| error_message(&e))?;
+            commands
+        }
+    };
+
+    let dir = std::env::current_dir().map_err(|e| error_message(&e))?;
+    let script = AtScript::new(user, args.mail, &dir, &commands).render();
+    let number = spool::submit_at_job(args.queue.unwrap_or('a'), time, &script)
+        .map_err(|e| error_message(&e))?;
+    eprintln!("{}", tr!("job {} at {}", number, spool::format_time(time)));
+    Ok(())
+}
+
+fn main() {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    let Some(user) = User::current() else {
+        tdiag!("cannot determine the current user");
+        std::process::exit(1);
+    };
+    if !spool::at_allowed(&user) {
+        tdiag!("you do not have permission to use at");
+        std::process::exit(1);
+    }
+
+    let result = if args.list {
+        list(&user, args.queue, &args.operands).map_err(|e| error_message(&e))
+    } else if args.remove {
+        if args.operands.is_empty() {
+            tdiag!("missing job ID");
+            std::process::exit(1);
+        }
+        remove(&user, &args.operands).map_err(|e| error_message(&e))
+    } else {
+        submit(&user, &args).map(|()| true)
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            diag!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
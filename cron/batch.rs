@@ -0,0 +1,56 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod spool;
+
+use std::io::{self, Read};
+
+use clap::Parser;
+use plib::diag::error_message;
+use plib::{diag, tdiag, tr};
+
+use spool::{AtScript, User};
+
+/// batch - schedule commands to be executed in a batch queue
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {}
+
+/// Submits the commands of standard input as `at -q b -m now` would.
+fn submit(user: &User) -> io::Result<()> {
+    let mut commands = String::new();
+    io::stdin().read_to_string(&mut commands)?;
+
+    let dir = std::env::current_dir()?;
+    let script = AtScript::new(user, true, &dir, &commands).render();
+    let time = plib::timefmt::unix_seconds(std::time::SystemTime::now());
+    let number = spool::submit_at_job('b', time, &script)?;
+    eprintln!("{}", tr!("job {} at {}", number, spool::format_time(time)));
+    Ok(())
+}
+
+fn main() {
+    plib::i18n::init();
+
+    Args::parse();
+
+    let Some(user) = User::current() else {
+        tdiag!("cannot determine the current user");
+        std::process::exit(1);
+    };
+    if !spool::at_allowed(&user) {
+        tdiag!("you do not have permission to use batch");
+        std::process::exit(1);
+    }
+
+    if let Err(e) = submit(&user) {
+        diag!("{}", error_message(&e));
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,309 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod schedule;
+mod spool;
+
+use std::ffi::{CStr, CString};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use clap::Parser;
+use plib::diag::error_message;
+use plib::{diag, tr};
+
+use schedule::Minute;
+use spool::User;
+
+/// crond - run the jobs of crontab and at
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Run the jobs due in the current minute, wait for them, and exit
+    #[arg(long)]
+    once: bool,
+}
+
+/// Environment of jobs, before that of their crontab or at job.
+fn base_env(user: &User) -> Vec<(String, String)> {
+    vec![
+        (String::from("HOME"), user.home.clone()),
+        (String::from("LOGNAME"), user.name.clone()),
+        (String::from("USER"), user.name.clone()),
+        (String::from("SHELL"), String::from("/bin/sh")),
+        (String::from("PATH"), String::from("/usr/bin:/bin")),
+    ]
+}
+
+/// A job to run: `sh` with these arguments, as `user`, mailing them its
+/// output.
+struct Job {
+    user: User,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    input: Option<String>,
+    mail_subject: String,
+    /// Mail the user even if the job had no output
+    mail_always: bool,
+}
+
+/// The groups `name` belongs to: `gid`, and those listing them as a member.
+fn group_list(name: &CStr, gid: libc::gid_t) -> io::Result<Vec<libc::gid_t>> {
+    let mut groups: Vec<libc::gid_t> = vec![0; 32];
+    loop {
+        let mut ngroups = groups.len() as libc::c_int;
+        // SAFETY: name is a valid C string, and groups has room for
+        // ngroups entries.
+        let ret = unsafe {
+            libc::getgrouplist(
+                name.as_ptr(),
+                gid as _,
+                groups.as_mut_ptr() as *mut _,
+                &mut ngroups,
+            )
+        };
+        if ret != -1 {
+            groups.truncate(ngroups as usize);
+            return Ok(groups);
+        }
+        // Too many groups: ngroups is the number needed on some systems,
+        // others leave it unchanged
+        let needed = (ngroups as usize).max(groups.len() * 2);
+        groups.resize(needed, 0);
+    }
+}
+
+/// Makes the command run as `user`, if crond runs as the superuser.
+fn run_as(cmd: &mut Command, user: &User) -> io::Result<()> {
+    if unsafe { libc::geteuid() } != 0 {
+        return Ok(());
+    }
+    let name = CString::new(user.name.as_str())?;
+    let (uid, gid) = (user.uid, user.gid);
+    // The group database is read here, as the child of a multithreaded
+    // process must not take the locks it needs
+    let groups = group_list(&name, gid)?;
+    // SAFETY: the closure only makes the setgroups, setgid and setuid
+    // system calls, which take no locks and don't allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::setgroups(groups.len() as _, groups.as_ptr()) != 0
+                || libc::setgid(gid) != 0
+                || libc::setuid(uid) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+fn mail(user: &User, subject: &str, output: &[u8]) {
+    let child = Command::new("mailx")
+        .args(["-s", subject, &user.name])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    // Without a mailer, the output is lost
+    if let Ok(mut child) = child {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(output);
+        }
+        let _ = child.wait();
+    }
+}
+
+fn run_job(job: &Job) -> io::Result<()> {
+    let (reader, writer) = plib::fd::pipe()?;
+    let mut cmd = Command::new("/bin/sh");
+    cmd.args(&job.args)
+        .env_clear()
+        .envs(job.env.iter().map(|(name, value)| (name, value)))
+        .current_dir(&job.user.home)
+        .stdin(if job.input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::from(writer.try_clone()?))
+        .stderr(Stdio::from(writer));
+    run_as(&mut cmd, &job.user)?;
+
+    let child = cmd.spawn();
+    // The command holds the write end of the pipe, which must be closed
+    // for the read to end
+    drop(cmd);
+    let mut child = child?;
+
+    if let (Some(input), Some(mut stdin)) = (&job.input, child.stdin.take()) {
+        let input = input.clone();
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+    }
+    let mut output = Vec::new();
+    File::from(reader).read_to_end(&mut output)?;
+    child.wait()?;
+
+    if !output.is_empty() || job.mail_always {
+        mail(&job.user, &job.mail_subject, &output);
+    }
+    Ok(())
+}
+
+fn spawn_job(job: Job) -> JoinHandle<()> {
+    thread::spawn(move || {
+        if let Err(e) = run_job(&job) {
+            diag!("{}: {}", job.user.name, error_message(&e));
+        }
+    })
+}
+
+/// The crontabs crond may run: all of them as the superuser, else only
+/// the one of the user running it.
+fn crontab_jobs(time: &Minute) -> io::Result<Vec<Job>> {
+    let euid = unsafe { libc::geteuid() };
+    let host = plib::curuser::hostname();
+    let mut jobs = Vec::new();
+    for (name, mut file) in spool::crontabs()? {
+        let Some(user) = User::by_name(&name) else {
+            continue;
+        };
+        // Crontabs are owned by their user, see spool::install_crontab
+        let owner = file.metadata()?.uid();
+        if (euid != 0 && user.uid != euid) || (owner != user.uid && owner != euid) {
+            continue;
+        }
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let crontab = match schedule::parse_crontab(&contents) {
+            Ok(crontab) => crontab,
+            Err(e) => {
+                diag!("{}: {}", name, e);
+                continue;
+            }
+        };
+
+        for entry in crontab.entries.iter().filter(|entry| entry.matches(time)) {
+            let mut env = base_env(&user);
+            env.extend(crontab.env.iter().cloned());
+            jobs.push(Job {
+                user: user.clone(),
+                args: vec![String::from("-c"), entry.command.clone()],
+                env,
+                input: entry.input.clone(),
+                mail_subject: format!("Cron <{}@{}> {}", name, host, entry.command),
+                mail_always: false,
+            });
+        }
+    }
+    Ok(jobs)
+}
+
+/// The at jobs due at `now`, taken out of the queue.
+fn at_jobs(now: i64) -> io::Result<Vec<Job>> {
+    let euid = unsafe { libc::geteuid() };
+    let dir = spool::at_dir()?;
+    let mut jobs = Vec::new();
+    for job in spool::at_jobs(&dir)? {
+        if job.time > now || !spool::at_job_is_complete(&job) {
+            continue;
+        }
+        if euid != 0 && job.owner != euid {
+            continue;
+        }
+        let Some(user) = User::by_uid(job.owner) else {
+            continue;
+        };
+
+        let path = spool::start_at_job(&job)?;
+        let script = fs::read_to_string(&path)?;
+        if spool::at_job_uid(&script) != Some(job.owner) {
+            diag!(
+                "{}: {}",
+                path.display(),
+                tr!("job header doesn't match its owner")
+            );
+            fs::remove_file(&path)?;
+            continue;
+        }
+        let (mail_user, mail_always) = spool::at_job_mail(&script).unwrap_or_default();
+        let user = User::by_name(&mail_user)
+            .filter(|mail_user| mail_user.uid == user.uid)
+            .unwrap_or(user);
+
+        jobs.push(Job {
+            env: base_env(&user),
+            user,
+            args: vec![path.to_string_lossy().into_owned()],
+            input: None,
+            mail_subject: format!("Output from your job {}", job.number),
+            mail_always,
+        });
+    }
+    Ok(jobs)
+}
+
+/// Starts the jobs due in the minute of `now`.
+fn run_due_jobs(now: &DateTime<Local>) -> Vec<JoinHandle<()>> {
+    let minute = Minute {
+        minute: now.minute(),
+        hour: now.hour(),
+        mday: now.day(),
+        month: now.month(),
+        wday: now.weekday().num_days_from_sunday(),
+    };
+
+    let mut handles = Vec::new();
+    match crontab_jobs(&minute) {
+        Ok(jobs) => handles.extend(jobs.into_iter().map(spawn_job)),
+        Err(e) => diag!("{}", error_message(&e)),
+    }
+    match at_jobs(now.timestamp()) {
+        Ok(jobs) => {
+            handles.extend(jobs.into_iter().map(|job| {
+                let path = job.args[0].clone();
+                thread::spawn(move || {
+                    spawn_job(job).join().ok();
+                    let _ = fs::remove_file(Path::new(&path));
+                })
+            }));
+        }
+        Err(e) => diag!("{}", error_message(&e)),
+    }
+    handles
+}
+
+fn main() {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    loop {
+        let now = Local::now();
+        let handles = run_due_jobs(&now);
+        if args.once {
+            for handle in handles {
+                let _ = handle.join();
+            }
+            break;
+        }
+
+        let elapsed = u64::from(Local::now().second());
+        thread::sleep(Duration::from_secs(60 - elapsed.min(59)));
+    }
+}
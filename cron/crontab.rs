@@ -0,0 +1,175 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod schedule;
+mod spool;
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::Parser;
+use plib::diag::error_message;
+use plib::{diag, tdiag, tr};
+
+use spool::User;
+
+/// crontab - schedule periodic background work
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Edit a copy of the crontab of the user, or an empty one, and
+    /// install it
+    #[arg(short = 'e', group = "action")]
+    edit: bool,
+
+    /// Write the crontab of the user to standard output
+    #[arg(short = 'l', group = "action")]
+    list: bool,
+
+    /// Remove the crontab of the user
+    #[arg(short = 'r', group = "action")]
+    remove: bool,
+
+    /// File to install as the crontab of the user, instead of standard
+    /// input
+    #[arg(conflicts_with = "action")]
+    file: Option<PathBuf>,
+}
+
+fn no_crontab(user: &User, e: io::Error) -> String {
+    if e.kind() == io::ErrorKind::NotFound {
+        tr!("no crontab for {}", user.name)
+    } else {
+        error_message(&e)
+    }
+}
+
+/// Installs `contents` as the crontab of `user`, if it is valid.
+/// `name` is what diagnostics call it.
+fn install(user: &User, name: &str, contents: &str) -> Result<(), String> {
+    if let Err(e) = schedule::parse_crontab(contents) {
+        return Err(format!("{}: {}", name, e));
+    }
+    spool::install_crontab(user, contents).map_err(|e| error_message(&e))
+}
+
+/// Creates a file only the user can read for the crontab being edited.
+fn create_temp_file(contents: &str) -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir();
+    for i in 0.. {
+        let path = dir.join(format!("crontab.{}.{}", std::process::id(), i));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// Runs the editor of the EDITOR environment variable on `path`. As the
+/// variable may hold arguments, it is expanded by the shell.
+fn run_editor(path: &Path) -> Result<(), String> {
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .map_err(|e| format!("{}: {}", editor, error_message(&e)))?;
+    if !status.success() {
+        return Err(tr!("{}: the editor failed", editor));
+    }
+    Ok(())
+}
+
+fn edit(user: &User) -> Result<(), String> {
+    let current = match spool::read_crontab(user) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(error_message(&e)),
+    };
+
+    let path = create_temp_file(&current).map_err(|e| error_message(&e))?;
+    let result = run_editor(&path).and_then(|()| {
+        let edited = fs::read_to_string(&path)
+            .map_err(|e| format!("{}: {}", path.display(), error_message(&e)))?;
+        if edited == current {
+            eprintln!("{}", tr!("no changes made to crontab"));
+            return Ok(());
+        }
+        install(user, &path.to_string_lossy(), &edited)
+            .map_err(|e| format!("{}\n{}", e, tr!("errors in crontab file, can't install")))
+    });
+    let _ = fs::remove_file(&path);
+    result
+}
+
+fn run(user: &User, args: &Args) -> Result<(), String> {
+    if args.list {
+        let contents = spool::read_crontab(user).map_err(|e| no_crontab(user, e))?;
+        print!("{}", contents);
+        Ok(())
+    } else if args.remove {
+        spool::remove_crontab(user).map_err(|e| no_crontab(user, e))
+    } else if args.edit {
+        edit(user)
+    } else {
+        let (name, contents) = match &args.file {
+            Some(path) => (
+                path.to_string_lossy().into_owned(),
+                fs::read_to_string(path)
+                    .map_err(|e| format!("{}: {}", path.display(), error_message(&e)))?,
+            ),
+            None => {
+                let mut contents = String::new();
+                io::stdin()
+                    .read_to_string(&mut contents)
+                    .map_err(|e| error_message(&e))?;
+                (String::from("-"), contents)
+            }
+        };
+        install(user, &name, &contents)
+    }
+}
+
+fn main() {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    let Some(user) = User::current() else {
+        tdiag!("cannot determine the current user");
+        std::process::exit(1);
+    };
+    if !spool::cron_allowed(&user) {
+        tdiag!("you do not have permission to use crontab");
+        std::process::exit(1);
+    }
+
+    if let Err(e) = run(&user, &args) {
+        diag!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,346 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The crontab format: blank lines, `#` comments, `NAME=value`
+//! environment settings, and entries of five time fields and a command:
+//!
+//! ```text
+//! minute hour monthday month weekday command
+//! ```
+//!
+//! A field is `*`, or a comma-separated list of numbers and ranges, each
+//! optionally followed by `/step`, as `*/15` or `1-5,10`. An entry runs
+//! in a minute that matches all of its fields, except that when both the
+//! day of the month and the day of the week are restricted, either of
+//! them matching is enough.
+//!
+//! In the command, the first unescaped `%` ends the command proper: what
+//! follows is its standard input, each other `%` standing for a newline.
+
+// Each utility uses its own part of the module
+#![allow(dead_code)]
+
+use std::fmt;
+
+use plib::tr;
+
+/// The set of values of a field, as a bitmask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field {
+    bits: u64,
+    /// Whether the field is `*`
+    any: bool,
+}
+
+impl Field {
+    fn contains(&self, value: u32) -> bool {
+        value < 64 && self.bits & (1 << value) != 0
+    }
+
+    fn parse(s: &str, min: u32, max: u32) -> Result<Field, String> {
+        let number = |n: &str| -> Result<u32, String> {
+            let value = n
+                .parse::<u32>()
+                .map_err(|_| tr!("invalid number '{}'", n))?;
+            if value < min || value > max {
+                return Err(tr!("{} is out of range {}-{}", value, min, max));
+            }
+            Ok(value)
+        };
+
+        let mut bits = 0;
+        for element in s.split(',') {
+            let (range, step) = match element.split_once('/') {
+                Some((range, step)) => {
+                    let step = step
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&step| step > 0)
+                        .ok_or_else(|| tr!("invalid step '{}'", step))?;
+                    (range, Some(step))
+                }
+                None => (element, None),
+            };
+
+            let (first, last) = if range == "*" {
+                (min, max)
+            } else if let Some((first, last)) = range.split_once('-') {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    return Err(tr!("invalid range '{}'", range));
+                }
+                (first, last)
+            } else if step.is_some() {
+                return Err(tr!("a step needs a range: '{}'", element));
+            } else {
+                let value = number(range)?;
+                (value, value)
+            };
+
+            let step = step.unwrap_or(1) as usize;
+            for value in (first..=last).step_by(step) {
+                bits |= 1 << value;
+            }
+        }
+
+        Ok(Field {
+            bits,
+            any: s == "*",
+        })
+    }
+}
+
+/// A time, broken down as the fields of an entry are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minute {
+    pub minute: u32,
+    pub hour: u32,
+    pub mday: u32,
+    pub month: u32,
+    /// Day of the week, 0 for Sunday
+    pub wday: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    minutes: Field,
+    hours: Field,
+    mdays: Field,
+    months: Field,
+    wdays: Field,
+    /// The command, for `sh -c`
+    pub command: String,
+    /// What follows the first `%` of the command, as its standard input
+    pub input: Option<String>,
+}
+
+impl Entry {
+    pub fn matches(&self, time: &Minute) -> bool {
+        let mday = self.mdays.contains(time.mday);
+        let wday = self.wdays.contains(time.wday);
+        let day = match (self.mdays.any, self.wdays.any) {
+            (false, false) => mday || wday,
+            _ => mday && wday,
+        };
+        day && self.minutes.contains(time.minute)
+            && self.hours.contains(time.hour)
+            && self.months.contains(time.month)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Crontab {
+    /// The environment settings, in order
+    pub env: Vec<(String, String)>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", tr!("line {}: {}", self.line, self.message))
+    }
+}
+
+/// Splits a command at its first unescaped `%`, and turns the escapes
+/// and the other `%` of its standard input into what they stand for.
+fn split_command(s: &str) -> (String, Option<String>) {
+    let mut command = String::new();
+    let mut input: Option<String> = None;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' if chars.peek() == Some(&'%') => chars.next().unwrap(),
+            '%' => match &mut input {
+                None => {
+                    input = Some(String::new());
+                    continue;
+                }
+                Some(_) => '\n',
+            },
+            c => c,
+        };
+        match &mut input {
+            Some(input) => input.push(c),
+            None => command.push(c),
+        }
+    }
+    (command, input.map(|input| input + "\n"))
+}
+
+/// Parses a `NAME=value` line; the value may be quoted.
+fn parse_env(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim();
+    let is_name = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_name {
+        return None;
+    }
+    let value = value.trim();
+    let unquoted = ['"', '\''].iter().find_map(|&q| {
+        value
+            .strip_prefix(q)
+            .and_then(|value| value.strip_suffix(q))
+    });
+    Some((name.to_string(), unquoted.unwrap_or(value).to_string()))
+}
+
+fn parse_entry(line: &str) -> Result<Entry, String> {
+    const RANGES: [(u32, u32); 5] = [(0, 59), (0, 23), (1, 31), (1, 12), (0, 6)];
+
+    let mut rest = line;
+    let mut fields = Vec::with_capacity(5);
+    for (min, max) in RANGES {
+        rest = rest.trim_start();
+        let end = rest
+            .find([' ', '\t'])
+            .ok_or_else(|| tr!("missing command"))?;
+        fields.push(Field::parse(&rest[..end], min, max)?);
+        rest = &rest[end..];
+    }
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return Err(tr!("missing command"));
+    }
+
+    let (command, input) = split_command(rest);
+    Ok(Entry {
+        minutes: fields[0],
+        hours: fields[1],
+        mdays: fields[2],
+        months: fields[3],
+        wdays: fields[4],
+        command,
+        input,
+    })
+}
+
+pub fn parse_crontab(text: &str) -> Result<Crontab, SyntaxError> {
+    let mut crontab = Crontab::default();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !trimmed.starts_with(|c: char| c.is_ascii_digit() || c == '*') {
+            match parse_env(trimmed) {
+                Some(setting) => crontab.env.push(setting),
+                None => {
+                    return Err(SyntaxError {
+                        line: i + 1,
+                        message: tr!("invalid entry"),
+                    })
+                }
+            }
+            continue;
+        }
+        let entry = parse_entry(trimmed).map_err(|message| SyntaxError {
+            line: i + 1,
+            message,
+        })?;
+        crontab.entries.push(entry);
+    }
+    Ok(crontab)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute(minute: u32, hour: u32, mday: u32, month: u32, wday: u32) -> Minute {
+        Minute {
+            minute,
+            hour,
+            mday,
+            month,
+            wday,
+        }
+    }
+
+    fn entry(line: &str) -> Entry {
+        parse_entry(line).unwrap()
+    }
+
+    #[test]
+    fn test_fields() {
+        let e = entry("*/15 9-17 * * 1-5 true");
+        assert!(e.matches(&minute(0, 9, 4, 3, 1)));
+        assert!(e.matches(&minute(45, 17, 4, 3, 5)));
+        assert!(!e.matches(&minute(50, 9, 4, 3, 1)));
+        assert!(!e.matches(&minute(0, 18, 4, 3, 1)));
+        assert!(!e.matches(&minute(0, 9, 4, 3, 0)));
+
+        let e = entry("0,30 0 1 1,7 * true");
+        assert!(e.matches(&minute(30, 0, 1, 7, 3)));
+        assert!(!e.matches(&minute(30, 0, 2, 7, 3)));
+    }
+
+    #[test]
+    fn test_day_fields() {
+        // The 13th, or any Friday
+        let e = entry("0 0 13 * 5 true");
+        assert!(e.matches(&minute(0, 0, 13, 2, 2)));
+        assert!(e.matches(&minute(0, 0, 14, 2, 5)));
+        assert!(!e.matches(&minute(0, 0, 14, 2, 4)));
+
+        // Fridays only
+        let e = entry("0 0 * * 5 true");
+        assert!(!e.matches(&minute(0, 0, 13, 2, 2)));
+        assert!(e.matches(&minute(0, 0, 14, 2, 5)));
+    }
+
+    #[test]
+    fn test_command() {
+        let e = entry("* * * * *\techo  a%b%c\\%d");
+        assert_eq!(e.command, "echo  a");
+        assert_eq!(e.input.as_deref(), Some("b\nc%d\n"));
+
+        let e = entry("* * * * * date +\\%s");
+        assert_eq!(e.command, "date +%s");
+        assert_eq!(e.input, None);
+    }
+
+    #[test]
+    fn test_crontab() {
+        let crontab =
+            parse_crontab("# comment\n\nSHELL=/bin/sh\nMAILTO = \"root\"\n0 * * * * a\n").unwrap();
+        assert_eq!(
+            crontab.env,
+            vec![
+                (String::from("SHELL"), String::from("/bin/sh")),
+                (String::from("MAILTO"), String::from("root"))
+            ]
+        );
+        assert_eq!(crontab.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        let line = |text: &str| parse_crontab(text).unwrap_err().line;
+        assert_eq!(line("0 * * * * a\n60 * * * * b\n"), 2);
+        assert_eq!(line("* 24 * * * a"), 1);
+        assert_eq!(line("* * 0 * * a"), 1);
+        assert_eq!(line("* * * 13 * a"), 1);
+        assert_eq!(line("* * * * 7 a"), 1);
+        assert_eq!(line("* * * * *"), 1);
+        assert_eq!(line("* * * *"), 1);
+        assert_eq!(line("5-1 * * * * a"), 1);
+        assert_eq!(line("*/0 * * * * a"), 1);
+        assert_eq!(line("1/5 * * * * a"), 1);
+        assert_eq!(line("x * * * * a"), 1);
+        assert_eq!(line("echo hello"), 1);
+    }
+}
@@ -0,0 +1,428 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The spool directories shared by at, batch, crontab and crond.
+//!
+//! An at job is a file of the at spool directory named after its queue,
+//! its job number and its execution time: `a0001c01c0b4e0` is job 0x1c of
+//! queue `a`, due 0x01c0b4e0 minutes after the Epoch. It is a shell
+//! script, owned by the user who submitted it, starting with a header
+//! for crond:
+//!
+//! ```text
+//! #!/bin/sh
+//! # atrun uid=1000 gid=1000
+//! # mail alice 0
+//! ```
+//!
+//! Crontabs are files of the crontab spool directory named after their
+//! user.
+
+// Each utility uses its own part of the module
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use libc::{gid_t, uid_t};
+use plib::timefmt::Tm;
+use plib::userdb::{self, user_by_name, user_by_uid};
+
+#[cfg(target_os = "linux")]
+const AT_SPOOL_DIRECTORIES: &[&str] = &[
+    "/var/spool/cron/atjobs",
+    "/var/spool/at",
+    "/var/spool/atjobs",
+];
+
+#[cfg(not(target_os = "linux"))]
+const AT_SPOOL_DIRECTORIES: &[&str] = &["/var/at/jobs"];
+
+#[cfg(target_os = "linux")]
+const CRONTAB_DIRECTORY: &str = "/var/spool/cron/crontabs";
+
+#[cfg(not(target_os = "linux"))]
+const CRONTAB_DIRECTORY: &str = "/var/at/tabs";
+
+#[cfg(target_os = "linux")]
+const CRON_ACCESS_FILES: (&str, &str) = ("/etc/cron.allow", "/etc/cron.deny");
+
+#[cfg(not(target_os = "linux"))]
+const CRON_ACCESS_FILES: (&str, &str) = ("/usr/lib/cron/cron.allow", "/usr/lib/cron/cron.deny");
+
+const AT_ACCESS_FILES: (&str, &str) = ("/etc/at.allow", "/etc/at.deny");
+
+/// File of the at spool directory holding the last job number used.
+const SEQUENCE_FILE: &str = ".SEQ";
+
+/// Job numbers wrap around after this one.
+const MAX_JOB_NUMBER: u32 = 0xfffff;
+
+/// Returns `dir`, creating it if it doesn't exist yet.
+fn ensure_dir(dir: &Path) -> io::Result<PathBuf> {
+    if !dir.is_dir() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    Ok(dir.to_path_buf())
+}
+
+/// The at spool directory: `AT_JOB_DIR` if set, else the first of the
+/// usual directories that exists, created if none does.
+pub fn at_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("AT_JOB_DIR") {
+        return ensure_dir(Path::new(&dir));
+    }
+    let existing = AT_SPOOL_DIRECTORIES
+        .iter()
+        .map(Path::new)
+        .find(|dir| dir.is_dir());
+    ensure_dir(existing.unwrap_or(Path::new(AT_SPOOL_DIRECTORIES[0])))
+}
+
+/// The crontab spool directory: `CRONTAB_DIR` if set, else the usual
+/// one, created if it doesn't exist.
+pub fn crontab_dir() -> io::Result<PathBuf> {
+    match std::env::var_os("CRONTAB_DIR") {
+        Some(dir) => ensure_dir(Path::new(&dir)),
+        None => ensure_dir(Path::new(CRONTAB_DIRECTORY)),
+    }
+}
+
+/// The user running the utility, by real user ID.
+#[derive(Clone)]
+pub struct User {
+    pub name: String,
+    pub uid: uid_t,
+    pub gid: gid_t,
+    pub home: String,
+    pub shell: String,
+}
+
+impl User {
+    pub fn current() -> Option<User> {
+        Self::by_uid(unsafe { libc::getuid() })
+    }
+
+    pub fn by_uid(uid: uid_t) -> Option<User> {
+        user_by_uid(uid).map(|user| Self::from_entry(&user))
+    }
+
+    pub fn by_name(name: &str) -> Option<User> {
+        user_by_name(name).map(|user| Self::from_entry(&user))
+    }
+
+    fn from_entry(user: &userdb::User) -> User {
+        let shell = if user.shell.is_empty() {
+            String::from("/bin/sh")
+        } else {
+            user.shell.clone()
+        };
+        User {
+            name: user.name.clone(),
+            uid: user.uid,
+            gid: user.gid,
+            home: user.dir.clone(),
+            shell,
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.uid == 0
+    }
+}
+
+fn read_user_file(path: &str) -> io::Result<HashSet<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+/// Whether `user` may use a utility with these allow and deny files: if
+/// the allow file exists, only the users it names may; otherwise, if the
+/// deny file exists, all users but those it names may. Without either,
+/// only the superuser may use crontab, and everyone may use at.
+fn is_allowed(user: &User, (allow, deny): (&str, &str), default: bool) -> bool {
+    if let Ok(allowed) = read_user_file(allow) {
+        return allowed.contains(&user.name);
+    }
+    if let Ok(denied) = read_user_file(deny) {
+        return !denied.contains(&user.name);
+    }
+    default || user.is_root()
+}
+
+pub fn at_allowed(user: &User) -> bool {
+    is_allowed(user, AT_ACCESS_FILES, true)
+}
+
+pub fn cron_allowed(user: &User) -> bool {
+    is_allowed(user, CRON_ACCESS_FILES, true)
+}
+
+/// An at job of the spool directory.
+#[derive(Clone)]
+pub struct AtJob {
+    pub path: PathBuf,
+    pub queue: char,
+    pub number: u32,
+    /// Execution time, in seconds since the Epoch
+    pub time: i64,
+    /// The user who submitted the job
+    pub owner: uid_t,
+}
+
+/// Formats an execution time as at reports it.
+pub fn format_time(time: i64) -> String {
+    Tm::local(time)
+        .map(|tm| tm.format("%a %b %e %H:%M:%S %Y"))
+        .unwrap_or_else(|| time.to_string())
+}
+
+/// The name of the file of an at job.
+fn at_job_file_name(queue: char, number: u32, time: i64) -> String {
+    format!("{}{:05x}{:08x}", queue, number, time.div_euclid(60))
+}
+
+impl AtJob {
+    fn from_file_name(dir: &Path, name: &OsStr) -> Option<AtJob> {
+        let name = name.to_str()?;
+        let queue = name.chars().next().filter(char::is_ascii_alphabetic)?;
+        if name.len() != 14 || !name.is_ascii() {
+            return None;
+        }
+        let number = u32::from_str_radix(&name[1..6], 16).ok()?;
+        let minutes = i64::from_str_radix(&name[6..], 16).ok()?;
+        let path = dir.join(name);
+        let owner = fs::symlink_metadata(&path).ok()?.uid();
+        Some(AtJob {
+            path,
+            queue,
+            number,
+            time: minutes * 60,
+            owner,
+        })
+    }
+}
+
+/// The at jobs of the spool directory `dir`, ordered by execution time.
+pub fn at_jobs(dir: &Path) -> io::Result<Vec<AtJob>> {
+    let mut jobs: Vec<AtJob> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| AtJob::from_file_name(dir, &entry.file_name()))
+        .collect();
+    jobs.sort_by_key(|job| (job.time, job.number));
+    Ok(jobs)
+}
+
+/// Takes the next job number, under a lock of the sequence file so that
+/// concurrent submissions get different ones.
+fn next_job_number(dir: &Path) -> io::Result<u32> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(dir.join(SEQUENCE_FILE))?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let last = u32::from_str_radix(contents.trim(), 16).unwrap_or(0);
+    let number = last % MAX_JOB_NUMBER + 1;
+
+    file.rewind()?;
+    file.set_len(0)?;
+    writeln!(file, "{:05x}", number)?;
+    Ok(number)
+}
+
+/// Quotes `s` for the shell.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// What an at job runs, besides its commands: the environment and the
+/// working directory and file mode creation mask of the submission.
+pub struct AtScript<'a> {
+    pub user: &'a User,
+    pub mail: bool,
+    pub umask: u32,
+    pub dir: &'a Path,
+    pub env: Vec<(String, String)>,
+    pub commands: &'a str,
+}
+
+impl<'a> AtScript<'a> {
+    /// The script of a job submitted now, from the current directory.
+    pub fn new(user: &'a User, mail: bool, dir: &'a Path, commands: &'a str) -> Self {
+        let umask = unsafe {
+            let mask = libc::umask(0o022);
+            libc::umask(mask);
+            mask
+        };
+        AtScript {
+            user,
+            mail,
+            umask: umask as u32,
+            dir,
+            env: std::env::vars().collect(),
+            commands,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut script = format!(
+            "#!/bin/sh\n# atrun uid={} gid={}\n# mail {} {}\numask {:o}\n",
+            self.user.uid,
+            self.user.gid,
+            self.user.name,
+            u8::from(self.mail),
+            self.umask
+        );
+        for (name, value) in &self.env {
+            let is_name = !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            // Variables that describe the session of the submission
+            let is_session = matches!(name.as_str(), "TERM" | "DISPLAY" | "_" | "SHLVL");
+            if is_name && !is_session {
+                script.push_str(&format!(
+                    "{}={}; export {}\n",
+                    name,
+                    shell_quote(value),
+                    name
+                ));
+            }
+        }
+        script.push_str(&format!(
+            "cd {} || {{\n\techo 'Execution directory inaccessible' >&2\n\texit 1\n}}\n",
+            shell_quote(&self.dir.to_string_lossy())
+        ));
+        script.push_str(self.commands);
+        if !self.commands.is_empty() && !self.commands.ends_with('\n') {
+            script.push('\n');
+        }
+        script
+    }
+}
+
+/// Adds a job to queue `queue` of the spool directory, due at `time`, in
+/// seconds since the Epoch. Returns its job number.
+pub fn submit_at_job(queue: char, time: i64, script: &str) -> io::Result<u32> {
+    let dir = at_dir()?;
+    let number = next_job_number(&dir)?;
+    let path = dir.join(at_job_file_name(queue, number, time));
+
+    // Only made executable once complete, so that crond doesn't run a
+    // partly written job
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    file.write_all(script.as_bytes())?;
+    file.sync_all()?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+    Ok(number)
+}
+
+/// Whether the job file is complete, see [`submit_at_job`].
+pub fn at_job_is_complete(job: &AtJob) -> bool {
+    fs::metadata(&job.path).is_ok_and(|md| md.mode() & 0o100 != 0)
+}
+
+/// Takes a due job out of the queue: it is renamed so that it is neither
+/// listed nor run again, and can be removed once done. Returns its new
+/// path.
+pub fn start_at_job(job: &AtJob) -> io::Result<PathBuf> {
+    let name = job.path.file_name().unwrap_or_default().to_string_lossy();
+    let path = job.path.with_file_name(format!("={}", name));
+    fs::rename(&job.path, &path)?;
+    Ok(path)
+}
+
+/// The user ID of the header of an at job, which must be its owner.
+pub fn at_job_uid(script: &str) -> Option<uid_t> {
+    let line = script.lines().nth(1)?.strip_prefix("# atrun uid=")?;
+    line.split_whitespace().next()?.parse().ok()
+}
+
+/// The header fields of an at job: the user to mail its output to, and
+/// whether to mail them even if there is none.
+pub fn at_job_mail(script: &str) -> Option<(String, bool)> {
+    let line = script
+        .lines()
+        .find_map(|line| line.strip_prefix("# mail "))?;
+    let (user, flag) = line.rsplit_once(' ')?;
+    Some((user.to_string(), flag == "1"))
+}
+
+/// Writes `contents` as the crontab of `user`, replacing the file at
+/// once so that crond never reads a partial one.
+pub fn install_crontab(user: &User, contents: &str) -> io::Result<()> {
+    let dir = crontab_dir()?;
+    let tmp = dir.join(format!(".{}.tmp{}", user.name, std::process::id()));
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if unsafe { libc::geteuid() } == 0 {
+            std::os::unix::fs::fchown(&file, Some(user.uid), Some(user.gid))?;
+        }
+        fs::rename(&tmp, dir.join(&user.name))
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+pub fn read_crontab(user: &User) -> io::Result<String> {
+    fs::read_to_string(crontab_dir()?.join(&user.name))
+}
+
+pub fn remove_crontab(user: &User) -> io::Result<()> {
+    fs::remove_file(crontab_dir()?.join(&user.name))
+}
+
+/// The crontabs of the spool directory, by user name.
+pub fn crontabs() -> io::Result<Vec<(String, File)>> {
+    let dir = crontab_dir()?;
+    let mut tabs = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        if let Ok(file) = File::open(entry.path()) {
+            tabs.push((name, file));
+        }
+    }
+    tabs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(tabs)
+}
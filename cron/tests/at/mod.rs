@@ -0,0 +1,151 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+
+use plib::testing::{run_test_with_options, OutputMatch, TestOptions, TestPlan};
+
+use crate::{cron_test, spool_fixture, spool_options};
+
+#[test]
+fn test_at_submit_list_remove() {
+    let fixture = spool_fixture("at_submit");
+
+    cron_test(
+        &fixture,
+        "at",
+        &["-t", "203001011200"],
+        "echo hello\n",
+        "",
+        "job 1 at Tue Jan  1 12:00:00 2030\n",
+        0,
+    );
+    cron_test(
+        &fixture,
+        "at",
+        &["-q", "c", "-t", "203001021330"],
+        "echo hello\n",
+        "",
+        "job 2 at Wed Jan  2 13:30:00 2030\n",
+        0,
+    );
+    cron_test(
+        &fixture,
+        "at",
+        &["-l"],
+        "",
+        "1\tTue Jan  1 12:00:00 2030\n2\tWed Jan  2 13:30:00 2030\n",
+        "",
+        0,
+    );
+    cron_test(
+        &fixture,
+        "at",
+        &["-l", "-q", "c"],
+        "",
+        "2\tWed Jan  2 13:30:00 2030\n",
+        "",
+        0,
+    );
+
+    cron_test(&fixture, "at", &["-r", "1"], "", "", "", 0);
+    cron_test(
+        &fixture,
+        "at",
+        &["-r", "1"],
+        "",
+        "",
+        "at: 1: no such job\n",
+        1,
+    );
+    cron_test(
+        &fixture,
+        "at",
+        &["-l"],
+        "",
+        "2\tWed Jan  2 13:30:00 2030\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_at_job_file() {
+    let fixture = spool_fixture("at_job_file");
+    let commands = fixture.path("commands");
+    fs::write(&commands, "echo from file\n").unwrap();
+
+    cron_test(
+        &fixture,
+        "at",
+        &["-m", "-f", &commands.to_string_lossy(), "noon jan 1, 2030"],
+        "",
+        "",
+        "job 1 at Tue Jan  1 12:00:00 2030\n",
+        0,
+    );
+
+    // Queue a, job 1, due 2030-01-01 12:00 in minutes since the Epoch
+    let script = fs::read_to_string(fixture.path("atjobs/a0000101e18ab0")).unwrap();
+    assert!(script.starts_with("#!/bin/sh\n# atrun uid="));
+    assert!(script.contains("\n# mail "));
+    assert!(script.contains("\nTZ='UTC'; export TZ\n"));
+    assert!(script.ends_with("\necho from file\n"));
+}
+
+#[test]
+fn test_at_invalid_timespec() {
+    let fixture = spool_fixture("at_invalid");
+
+    cron_test(
+        &fixture,
+        "at",
+        &["25:00"],
+        "",
+        "",
+        "at: invalid hour '25'\n",
+        1,
+    );
+    cron_test(
+        &fixture,
+        "at",
+        &["noon", "jan", "1,", "2020"],
+        "",
+        "",
+        "at: the time is in the past\n",
+        1,
+    );
+    cron_test(&fixture, "at", &[], "", "", "at: missing timespec\n", 1);
+}
+
+#[test]
+fn test_batch_and_crond() {
+    let fixture = spool_fixture("batch_crond");
+    let out = fixture.path("out");
+
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("batch"),
+            args: Vec::new(),
+            stdin_data: format!("echo ran > '{}'\n", out.display()),
+            expected_out: String::new(),
+            expected_err: String::from("job 1 at "),
+            expected_exit_code: 0,
+        },
+        TestOptions {
+            stderr_match: OutputMatch::Contains,
+            ..spool_options(&fixture)
+        },
+    );
+
+    cron_test(&fixture, "crond", &["--once"], "", "", "", 0);
+    assert_eq!(fs::read_to_string(&out).unwrap(), "ran\n");
+    assert_eq!(fs::read_dir(fixture.path("atjobs")).unwrap().count(), 1);
+    cron_test(&fixture, "at", &["-l"], "", "", "", 0);
+}
@@ -0,0 +1,47 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod at;
+mod crontab;
+
+use plib::testing::{run_test_with_options, Fixture, TestOptions, TestPlan};
+
+/// Options running the utilities with the spool directories of `fixture`.
+pub fn spool_options(fixture: &Fixture) -> TestOptions {
+    TestOptions::default()
+        .env("AT_JOB_DIR", &fixture.arg("atjobs"))
+        .env("CRONTAB_DIR", &fixture.arg("crontabs"))
+        .env("TZ", "UTC")
+}
+
+pub fn cron_test(
+    fixture: &Fixture,
+    cmd: &str,
+    args: &[&str],
+    stdin_data: &str,
+    expected_out: &str,
+    expected_err: &str,
+    expected_exit_code: i32,
+) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from(cmd),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::from(stdin_data),
+            expected_out: String::from(expected_out),
+            expected_err: String::from(expected_err),
+            expected_exit_code,
+        },
+        spool_options(fixture),
+    );
+}
+
+pub fn spool_fixture(name: &str) -> Fixture {
+    Fixture::builder(name).dir("atjobs").dir("crontabs").build()
+}
@@ -0,0 +1,139 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+
+use plib::testing::{run_test_with_options, TestPlan};
+use plib::userdb::user_by_uid;
+
+use crate::{cron_test, spool_fixture, spool_options};
+
+fn user_name() -> String {
+    user_by_uid(unsafe { libc::getuid() }).unwrap().name.clone()
+}
+
+#[test]
+fn test_crontab_install_list_remove() {
+    let fixture = spool_fixture("crontab_install");
+    let crontab = "# hourly\nSHELL=/bin/sh\n0 * * * * echo hello%world\n";
+
+    cron_test(&fixture, "crontab", &[], crontab, "", "", 0);
+    assert_eq!(
+        fs::read_to_string(fixture.path("crontabs").join(user_name())).unwrap(),
+        crontab
+    );
+    cron_test(&fixture, "crontab", &["-l"], "", crontab, "", 0);
+
+    cron_test(&fixture, "crontab", &["-r"], "", "", "", 0);
+    let no_crontab = format!("crontab: no crontab for {}\n", user_name());
+    cron_test(&fixture, "crontab", &["-l"], "", "", &no_crontab, 1);
+    cron_test(&fixture, "crontab", &["-r"], "", "", &no_crontab, 1);
+}
+
+#[test]
+fn test_crontab_syntax_error() {
+    let fixture = spool_fixture("crontab_syntax");
+
+    cron_test(
+        &fixture,
+        "crontab",
+        &[],
+        "0 * * * * true\n60 * * * * true\n",
+        "",
+        "crontab: -: line 2: 60 is out of range 0-59\n",
+        1,
+    );
+    cron_test(
+        &fixture,
+        "crontab",
+        &[],
+        "* * * * 1-5\n",
+        "",
+        "crontab: -: line 1: missing command\n",
+        1,
+    );
+    assert_eq!(fs::read_dir(fixture.path("crontabs")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_crontab_edit() {
+    let fixture = spool_fixture("crontab_edit");
+    let edited = fixture.path("edited");
+    fs::write(&edited, "30 6 * * 1-5 true\n").unwrap();
+
+    let editor = format!("cp '{}'", edited.display());
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("crontab"),
+            args: vec![String::from("-e")],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        spool_options(&fixture).env("EDITOR", &editor),
+    );
+    cron_test(
+        &fixture,
+        "crontab",
+        &["-l"],
+        "",
+        "30 6 * * 1-5 true\n",
+        "",
+        0,
+    );
+
+    // Unchanged, then invalid
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("crontab"),
+            args: vec![String::from("-e")],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::from("no changes made to crontab\n"),
+            expected_exit_code: 0,
+        },
+        spool_options(&fixture).env("EDITOR", "true"),
+    );
+    fs::write(&edited, "30 6 * * 1-7 true\n").unwrap();
+    let output = plib::testing::run_test_base_with_options(
+        "crontab",
+        &vec![String::from("-e")],
+        b"",
+        &spool_options(&fixture).env("EDITOR", &editor),
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(": line 1: 7 is out of range 0-6\n"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.ends_with("errors in crontab file, can't install\n"));
+    assert_eq!(output.status.code(), Some(1));
+    cron_test(
+        &fixture,
+        "crontab",
+        &["-l"],
+        "",
+        "30 6 * * 1-5 true\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_crond_runs_crontab() {
+    let fixture = spool_fixture("crond_crontab");
+    let out = fixture.path("out");
+
+    let crontab = format!("* * * * * cat > '{}'%line 1%line 2\n", out.display());
+    cron_test(&fixture, "crontab", &[], &crontab, "", "", 0);
+    cron_test(&fixture, "crond", &["--once"], "", "", "", 0);
+    assert_eq!(fs::read_to_string(&out).unwrap(), "line 1\nline 2\n");
+}
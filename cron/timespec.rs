@@ -0,0 +1,502 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The time specifications of at: the `timespec` operand, and the
+//! `[[CC]YY]MMDDhhmm[.SS]` argument of `-t`.
+
+use chrono::{
+    DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+    Weekday,
+};
+use plib::tr;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("sunday", Weekday::Sun),
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(String),
+    Word(String),
+    Colon,
+    Comma,
+    Plus,
+}
+
+fn tokenize(spec: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                number.push(c);
+            }
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                word.extend(c.to_lowercase());
+            }
+            tokens.push(Token::Word(word));
+        } else {
+            tokens.push(match c {
+                ':' => Token::Colon,
+                ',' => Token::Comma,
+                '+' => Token::Plus,
+                _ => return Err(tr!("unexpected character '{}'", c)),
+            });
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether `word` names `name`, in full or abbreviated to three letters.
+fn names(word: &str, name: &str) -> bool {
+    word == name || (word.len() == 3 && name.starts_with(word))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Date {
+    MonthDay {
+        month: u32,
+        day: u32,
+        year: Option<i32>,
+    },
+    Weekday(Weekday),
+    Today,
+    Tomorrow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+    Months,
+    Years,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Spec {
+    /// Hour and minute, or `None` for `now`
+    time: Option<(u32, u32)>,
+    utc: bool,
+    date: Option<Date>,
+    increment: Option<(u32, Period)>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn next_word_is(&mut self, f: impl Fn(&str) -> bool) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if f(word) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn number(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            _ => Err(tr!("expected a number")),
+        }
+    }
+
+    fn spec(&mut self) -> Result<Spec, String> {
+        let mut spec = Spec::default();
+        if self.next_word_is(|w| w == "now") {
+            spec.increment = self.increment()?;
+            return Ok(spec);
+        }
+
+        spec.time = Some(self.time()?);
+        spec.utc = self.next_word_is(|w| w == "utc" || w == "gmt" || w == "z");
+        spec.date = self.date()?;
+        spec.increment = self.increment()?;
+        Ok(spec)
+    }
+
+    fn time(&mut self) -> Result<(u32, u32), String> {
+        if self.next_word_is(|w| w == "noon") {
+            return Ok((12, 0));
+        }
+        if self.next_word_is(|w| w == "midnight") {
+            return Ok((0, 0));
+        }
+
+        let digits = self.number().map_err(|_| tr!("expected a time"))?;
+        let (mut hour, minute) = match digits.len() {
+            1 | 2 if self.peek() == Some(&Token::Colon) => {
+                self.pos += 1;
+                let minute = self.number()?;
+                if minute.len() != 2 {
+                    return Err(tr!("invalid minute '{}'", minute));
+                }
+                (digits.parse::<u32>().unwrap(), minute.parse().unwrap())
+            }
+            1 | 2 => (digits.parse().unwrap(), 0),
+            4 => (digits[..2].parse().unwrap(), digits[2..].parse().unwrap()),
+            _ => return Err(tr!("invalid time '{}'", digits)),
+        };
+        if minute > 59 {
+            return Err(tr!("invalid minute '{}'", minute));
+        }
+
+        let am = self.next_word_is(|w| w == "am");
+        let pm = !am && self.next_word_is(|w| w == "pm");
+        if am || pm {
+            if !(1..=12).contains(&hour) {
+                return Err(tr!("invalid hour '{}'", hour));
+            }
+            hour %= 12;
+            if pm {
+                hour += 12;
+            }
+        } else if hour > 23 {
+            return Err(tr!("invalid hour '{}'", hour));
+        }
+        Ok((hour, minute))
+    }
+
+    fn date(&mut self) -> Result<Option<Date>, String> {
+        let Some(Token::Word(word)) = self.peek().cloned() else {
+            return Ok(None);
+        };
+
+        let date = if word == "today" {
+            Date::Today
+        } else if word == "tomorrow" {
+            Date::Tomorrow
+        } else if let Some(&(_, weekday)) = WEEKDAYS.iter().find(|(name, _)| names(&word, name)) {
+            Date::Weekday(weekday)
+        } else if let Some(month) = MONTHS.iter().position(|name| names(&word, name)) {
+            self.pos += 1;
+            let day = self.number()?;
+            let day = day.parse().ok().filter(|d| (1..=31).contains(d));
+            let day = day.ok_or_else(|| tr!("invalid day of the month"))?;
+            let year = if self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+                let year = self.number()?;
+                if year.len() != 4 {
+                    return Err(tr!("invalid year '{}'", year));
+                }
+                Some(year.parse().unwrap())
+            } else {
+                None
+            };
+            return Ok(Some(Date::MonthDay {
+                month: month as u32 + 1,
+                day,
+                year,
+            }));
+        } else {
+            return Ok(None);
+        };
+        self.pos += 1;
+        Ok(Some(date))
+    }
+
+    fn increment(&mut self) -> Result<Option<(u32, Period)>, String> {
+        let count = match self.peek() {
+            Some(Token::Plus) => {
+                self.pos += 1;
+                let count = self.number()?;
+                count
+                    .parse()
+                    .map_err(|_| tr!("invalid increment '{}'", count))?
+            }
+            Some(Token::Word(word)) if word == "next" => {
+                self.pos += 1;
+                1
+            }
+            _ => return Ok(None),
+        };
+
+        let period = match self.next() {
+            Some(Token::Word(word)) => match word.strip_suffix('s').unwrap_or(&word) {
+                "minute" => Period::Minutes,
+                "hour" => Period::Hours,
+                "day" => Period::Days,
+                "week" => Period::Weeks,
+                "month" => Period::Months,
+                "year" => Period::Years,
+                _ => return Err(tr!("invalid increment period '{}'", word)),
+            },
+            _ => return Err(tr!("expected an increment period")),
+        };
+        Ok(Some((count, period)))
+    }
+}
+
+fn add_increment(time: NaiveDateTime, (count, period): (u32, Period)) -> Option<NaiveDateTime> {
+    let count = u64::from(count);
+    match period {
+        Period::Minutes => time.checked_add_signed(chrono::Duration::minutes(count as i64)),
+        Period::Hours => time.checked_add_signed(chrono::Duration::hours(count as i64)),
+        Period::Days => time.checked_add_days(Days::new(count)),
+        Period::Weeks => time.checked_add_days(Days::new(count * 7)),
+        Period::Months => time.checked_add_months(Months::new(count as u32)),
+        Period::Years => time.checked_add_months(Months::new(count.checked_mul(12)? as u32)),
+    }
+}
+
+/// The time that `spec` designates, in the time zone of `now`.
+fn evaluate(spec: &Spec, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let now = now.with_second(0).unwrap().with_nanosecond(0).unwrap();
+    let today = now.date();
+    let out_of_range = || tr!("time out of range");
+
+    let time = match spec.time {
+        None => now,
+        Some((hour, minute)) => {
+            let at =
+                |date: NaiveDate| date.and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap());
+            match spec.date {
+                None if at(today) < now => at(today.succ_opt().ok_or_else(out_of_range)?),
+                None | Some(Date::Today) => at(today),
+                Some(Date::Tomorrow) => at(today.succ_opt().ok_or_else(out_of_range)?),
+                Some(Date::Weekday(weekday)) => {
+                    let mut days = (7 + weekday.num_days_from_sunday()
+                        - today.weekday().num_days_from_sunday())
+                        % 7;
+                    if days == 0 && at(today) < now {
+                        days = 7;
+                    }
+                    at(today + Days::new(u64::from(days)))
+                }
+                Some(Date::MonthDay { month, day, year }) => {
+                    let date = |year| {
+                        NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| tr!("invalid date"))
+                    };
+                    match year {
+                        Some(year) => at(date(year)?),
+                        None => {
+                            let time = at(date(today.year())?);
+                            if time < now {
+                                at(date(today.year() + 1)?)
+                            } else {
+                                time
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    match spec.increment {
+        Some(increment) => add_increment(time, increment).ok_or_else(out_of_range),
+        None => Ok(time),
+    }
+}
+
+/// Converts `time`, in the time zone of `now`, to seconds since the
+/// Epoch.
+fn timestamp<Tz: TimeZone>(time: NaiveDateTime, now: &DateTime<Tz>) -> Result<i64, String> {
+    now.timezone()
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp())
+        .ok_or_else(|| tr!("nonexistent local time"))
+}
+
+/// The time, in seconds since the Epoch, that the timespec operand of at
+/// designates at `now`.
+pub fn parse_timespec<Tz: TimeZone>(spec: &str, now: &DateTime<Tz>) -> Result<i64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(spec)?,
+        pos: 0,
+    };
+    let parsed = parser.spec()?;
+    if let Some(token) = parser.peek() {
+        let token = match token {
+            Token::Number(s) | Token::Word(s) => s.clone(),
+            Token::Colon => String::from(":"),
+            Token::Comma => String::from(","),
+            Token::Plus => String::from("+"),
+        };
+        return Err(tr!("unexpected '{}'", token));
+    }
+
+    let time = if parsed.utc {
+        evaluate(&parsed, now.naive_utc())?.and_utc().timestamp()
+    } else {
+        timestamp(evaluate(&parsed, now.naive_local())?, now)?
+    };
+    if time < now.timestamp() - i64::from(now.second()) {
+        return Err(tr!("the time is in the past"));
+    }
+    Ok(time)
+}
+
+/// The time, in seconds since the Epoch, of a `[[CC]YY]MMDDhhmm[.SS]`
+/// argument, in the time zone of `now`. Without a century, years 69 to
+/// 99 are in the 20th century and 00 to 68 in the 21st.
+pub fn parse_touch_time<Tz: TimeZone>(arg: &str, now: &DateTime<Tz>) -> Result<i64, String> {
+    let invalid = || tr!("invalid time '{}'", arg);
+    let (digits, seconds) = match arg.split_once('.') {
+        Some((digits, seconds)) if seconds.len() == 2 => (digits, Some(seconds)),
+        Some(_) => return Err(invalid()),
+        None => (arg, None),
+    };
+    if !digits
+        .bytes()
+        .chain(seconds.unwrap_or("").bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let (year, rest) = match digits.len() {
+        8 => (now.year(), digits),
+        10 => {
+            let yy: i32 = digits[..2].parse().unwrap();
+            (if yy < 69 { 2000 + yy } else { 1900 + yy }, &digits[2..])
+        }
+        12 => (digits[..4].parse().unwrap(), &digits[4..]),
+        _ => return Err(invalid()),
+    };
+    let field = |i: usize| rest[i..i + 2].parse::<u32>().unwrap();
+    let second = seconds.map_or(0, |s| s.parse().unwrap());
+
+    let time = NaiveDate::from_ymd_opt(year, field(0), field(2))
+        .and_then(|date| date.and_hms_opt(field(4), field(6), second))
+        .ok_or_else(invalid)?;
+    timestamp(time, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    /// Wednesday 2024-03-13 14:30:20, UTC+1
+    fn now() -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 3, 13, 14, 30, 20)
+            .unwrap()
+    }
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        now()
+            .timezone()
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+            .timestamp()
+    }
+
+    fn parse(spec: &str) -> Result<i64, String> {
+        parse_timespec(spec, &now())
+    }
+
+    #[test]
+    fn test_times() {
+        assert_eq!(parse("now"), Ok(local(2024, 3, 13, 14, 30)));
+        assert_eq!(parse("16:00"), Ok(local(2024, 3, 13, 16, 0)));
+        assert_eq!(parse("1600"), Ok(local(2024, 3, 13, 16, 0)));
+        assert_eq!(parse("4pm"), Ok(local(2024, 3, 13, 16, 0)));
+        assert_eq!(parse("12am"), Ok(local(2024, 3, 14, 0, 0)));
+        assert_eq!(parse("noon"), Ok(local(2024, 3, 14, 12, 0)));
+        assert_eq!(parse("midnight"), Ok(local(2024, 3, 14, 0, 0)));
+        assert_eq!(parse("9"), Ok(local(2024, 3, 14, 9, 0)));
+        assert_eq!(parse("14:30"), Ok(local(2024, 3, 13, 14, 30)));
+        assert_eq!(parse("15:00 utc"), Ok(local(2024, 3, 13, 16, 0)));
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(parse("noon today"), Err(tr!("the time is in the past")));
+        assert_eq!(parse("noon tomorrow"), Ok(local(2024, 3, 14, 12, 0)));
+        assert_eq!(parse("10am fri"), Ok(local(2024, 3, 15, 10, 0)));
+        assert_eq!(parse("10am Wednesday"), Ok(local(2024, 3, 20, 10, 0)));
+        assert_eq!(parse("6pm wed"), Ok(local(2024, 3, 13, 18, 0)));
+        assert_eq!(parse("0815 jan 24"), Ok(local(2025, 1, 24, 8, 15)));
+        assert_eq!(parse("0815 December 24"), Ok(local(2024, 12, 24, 8, 15)));
+        assert_eq!(parse("8:15am jan 24, 2026"), Ok(local(2026, 1, 24, 8, 15)));
+    }
+
+    #[test]
+    fn test_increments() {
+        assert_eq!(parse("now + 1 hour"), Ok(local(2024, 3, 13, 15, 30)));
+        assert_eq!(parse("now +90 minutes"), Ok(local(2024, 3, 13, 16, 0)));
+        assert_eq!(parse("now next day"), Ok(local(2024, 3, 14, 14, 30)));
+        assert_eq!(
+            parse("noon tomorrow + 2 weeks"),
+            Ok(local(2024, 3, 28, 12, 0))
+        );
+        assert_eq!(parse("4pm + 1 month"), Ok(local(2024, 4, 13, 16, 0)));
+        assert_eq!(parse("now + 1 year"), Ok(local(2025, 3, 13, 14, 30)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("25:00").is_err());
+        assert!(parse("13pm").is_err());
+        assert!(parse("10:5").is_err());
+        assert!(parse("noon feb 30").is_err());
+        assert!(parse("now + 1 fortnight").is_err());
+        assert!(parse("now tomorrow").is_err());
+        assert!(parse("noon jan 1, 2020").is_err());
+    }
+
+    #[test]
+    fn test_touch_time() {
+        let parse = |arg| parse_touch_time(arg, &now());
+        assert_eq!(parse("03141200"), Ok(local(2024, 3, 14, 12, 0)));
+        assert_eq!(parse("2503141200"), Ok(local(2025, 3, 14, 12, 0)));
+        assert_eq!(parse("202503141200.30"), Ok(local(2025, 3, 14, 12, 0) + 30));
+        assert!(parse("0314120").is_err());
+        assert!(parse("03141200.3").is_err());
+        assert!(parse("13141200").is_err());
+    }
+}
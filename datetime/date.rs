@@ -6,14 +6,16 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - add tests (how?)
-// - double-check that Rust stftime() is POSIX compliant
-//
 
-use chrono::{DateTime, Datelike, Local, LocalResult, TimeZone, Utc};
+use std::io;
+use std::time::SystemTime;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use gettextrs::gettext;
+use plib::diag::error_message;
+use plib::tdiag;
+use plib::timefmt;
 
 const DEF_TIMESTR: &str = "%a %b %e %H:%M:%S %Z %Y";
 
@@ -32,122 +34,81 @@ struct Args {
     #[arg(
         help = gettext(
             "If prefixed with '+', Display the current time in the given FORMAT, \
-             as in strftime(3). Otherwise, set the current time to the given string"
+             as in strftime(3). Otherwise, set the current time to the given \
+             MMDDhhmm[[CC]YY] time"
         )
     )]
     timestr: Option<String>,
 }
 
-fn show_time_local(formatstr: &str) -> String {
-    let now = chrono::Local::now();
-    now.format(formatstr).to_string()
-}
-
-fn show_time_utc(formatstr: &str) -> String {
-    let now = chrono::Utc::now();
-    now.format(formatstr).to_string()
-}
-
 fn show_time(utc: bool, formatstr: &str) {
+    let now = SystemTime::now();
     let timestr = {
         if utc {
-            show_time_utc(formatstr)
+            timefmt::format_utc(formatstr, now)
         } else {
-            show_time_local(formatstr)
+            timefmt::format_local(formatstr, now)
         }
     };
 
     println!("{}", timestr);
 }
 
-fn set_time(utc: bool, timestr: &str) -> Result<(), &'static str> {
-    for ch in timestr.chars() {
-        if !ch.is_ascii_digit() {
-            return Err("date: invalid date");
-        }
+/// Parses a `MMDDhhmm[[CC]YY]` operand into seconds since the Epoch. A
+/// two-digit year of 69 to 99 is in the 20th century, else in the 21st.
+/// Without a year, the time is in the current year.
+fn parse_time(utc: bool, timestr: &str) -> Option<i64> {
+    if !timestr.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
+    let field = |range: std::ops::Range<usize>| timestr[range].parse::<u32>().ok();
 
-    let cur_year = {
-        if utc {
-            let now = chrono::Utc::now();
-            now.year()
-        } else {
-            let now = chrono::Local::now();
-            now.year()
-        }
-    };
-
-    let (year, month, day, hour, minute) = match timestr.len() {
+    let year = match timestr.len() {
         8 => {
-            let month = timestr[0..2].parse::<u32>().unwrap();
-            let day = timestr[2..4].parse::<u32>().unwrap();
-            let hour = timestr[4..6].parse::<u32>().unwrap();
-            let minute = timestr[6..8].parse::<u32>().unwrap();
-            (cur_year, month, day, hour, minute)
-        }
-        10 => {
-            let month = timestr[0..2].parse::<u32>().unwrap();
-            let day = timestr[2..4].parse::<u32>().unwrap();
-            let hour = timestr[4..6].parse::<u32>().unwrap();
-            let minute = timestr[6..8].parse::<u32>().unwrap();
-            let year = timestr[8..10].parse::<i32>().unwrap();
-            if year < 70 {
-                (year + 2000, month, day, hour, minute)
+            if utc {
+                Utc::now().year()
             } else {
-                (year + 1900, month, day, hour, minute)
-            }
-        }
-        12 => {
-            let month = timestr[0..2].parse::<u32>().unwrap();
-            let day = timestr[2..4].parse::<u32>().unwrap();
-            let hour = timestr[4..6].parse::<u32>().unwrap();
-            let minute = timestr[6..8].parse::<u32>().unwrap();
-            let year = timestr[8..12].parse::<i32>().unwrap();
-            (year, month, day, hour, minute)
-        }
-        _ => {
-            return Err("date: invalid date");
-        }
-    };
-
-    // calculate system time
-    let new_time = {
-        if utc {
-            match chrono::Utc.with_ymd_and_hms(year, month, day, hour, minute, 0) {
-                LocalResult::<DateTime<Utc>>::Single(t) => t.timestamp(),
-                _ => {
-                    return Err("date: invalid date");
-                }
-            }
-        } else {
-            match chrono::Local.with_ymd_and_hms(year, month, day, hour, minute, 0) {
-                LocalResult::<DateTime<Local>>::Single(t) => t.timestamp(),
-                _ => {
-                    return Err("date: invalid date");
-                }
+                Local::now().year()
             }
         }
+        10 => match field(8..10)? {
+            year @ 69..=99 => 1900 + year as i32,
+            year => 2000 + year as i32,
+        },
+        12 => field(8..12)? as i32,
+        _ => return None,
     };
+    let (month, day, hour, minute) = (field(0..2)?, field(2..4)?, field(4..6)?, field(6..8)?);
+
+    let time: NaiveDateTime =
+        NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, 0)?;
+    if utc {
+        Some(Utc.from_utc_datetime(&time).timestamp())
+    } else {
+        // In a repeated hour, the first of the two times is meant; a
+        // skipped hour doesn't exist
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|time| time.timestamp())
+    }
+}
 
+fn set_time(seconds: i64) -> io::Result<()> {
     let new_time = libc::timespec {
-        tv_sec: new_time,
+        tv_sec: seconds as libc::time_t,
         tv_nsec: 0,
     };
 
-    // set system time
-    unsafe {
-        if libc::clock_settime(libc::CLOCK_REALTIME, &new_time) != 0 {
-            return Err("date: failed to set time");
-        }
+    // SAFETY: new_time is a valid timespec
+    if unsafe { libc::clock_settime(libc::CLOCK_REALTIME, &new_time) } != 0 {
+        return Err(io::Error::last_os_error());
     }
-
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
@@ -156,11 +117,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(timestr) => {
             if let Some(st) = timestr.strip_prefix("+") {
                 show_time(args.utc, st);
-            } else {
-                set_time(args.utc, timestr)?;
+                return;
+            }
+
+            // The operand is checked in full before the clock is touched
+            let Some(seconds) = parse_time(args.utc, timestr) else {
+                tdiag!("invalid date '{}'", timestr);
+                std::process::exit(1);
+            };
+            if let Err(e) = set_time(seconds) {
+                tdiag!("cannot set date: {}", error_message(&e));
+                std::process::exit(1);
             }
         }
     }
-
-    Ok(())
 }
@@ -28,10 +28,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
-    unsafe {
-        // Ignore the SIGALRM signal
-        libc::signal(libc::SIGALRM, libc::SIG_IGN);
-    }
+    // Ignore the SIGALRM signal
+    plib::signal::ignore(libc::SIGALRM)?;
 
     thread::sleep(time::Duration::from_secs(args.seconds));
 
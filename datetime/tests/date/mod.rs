@@ -0,0 +1,92 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test_base_with_options, run_test_with_options, TestOptions, TestPlan};
+
+fn date_tz_test(tz: &str, args: &[&str], expected_output: &str) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("date"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: String::from(expected_output),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions::default().env("TZ", tz),
+    );
+}
+
+#[test]
+fn test_date_tz_offset() {
+    date_tz_test("UTC0", &["+%z"], "+0000\n");
+    date_tz_test("EST5", &["+%z"], "-0500\n");
+}
+
+#[test]
+fn test_date_utc_ignores_tz() {
+    date_tz_test("EST5", &["-u", "+%z"], "+0000\n");
+}
+
+#[test]
+fn test_date_utc_zone_name() {
+    date_tz_test("EST5", &["-u", "+%Z"], "UTC\n");
+}
+
+fn date_seconds(tz: &str, args: &[&str]) -> i64 {
+    let output = run_test_base_with_options(
+        "date",
+        &args.iter().map(|s| String::from(*s)).collect(),
+        b"",
+        &TestOptions::default().env("TZ", tz),
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_date_epoch_seconds() {
+    // Seconds since the Epoch don't depend on the time zone
+    let local = date_seconds("EST5", &["+%s"]);
+    let utc = date_seconds("EST5", &["-u", "+%s"]);
+    assert!((utc - local).abs() <= 1, "{} and {} differ", local, utc);
+}
+
+#[test]
+fn test_date_literal_percent() {
+    date_tz_test("UTC0", &["+%%s%n%t."], "%s\n\t.\n");
+}
+
+fn date_invalid_test(operand: &str) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("date"),
+            args: vec![String::from(operand)],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: format!("date: invalid date '{}'\n", operand),
+            expected_exit_code: 1,
+        },
+        TestOptions::default().env("TZ", "UTC0"),
+    );
+}
+
+#[test]
+fn test_date_invalid_operand() {
+    date_invalid_test("0101000");
+    date_invalid_test("010100000");
+    date_invalid_test("13010000");
+    date_invalid_test("02300000");
+    date_invalid_test("022900002023");
+    date_invalid_test("01012400");
+    date_invalid_test("01010060");
+    date_invalid_test("0101000a");
+}
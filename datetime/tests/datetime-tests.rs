@@ -7,4 +7,6 @@
 // SPDX-License-Identifier: MIT
 //
 
+mod date;
+mod sleep;
 mod time;
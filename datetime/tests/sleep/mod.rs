@@ -0,0 +1,40 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::time::Duration;
+
+use plib::testing::{run_test, run_test_with_options, TestOptions, TestPlan};
+
+fn sleep_plan(seconds: &str) -> TestPlan {
+    TestPlan {
+        cmd: String::from("sleep"),
+        args: vec![String::from(seconds)],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    }
+}
+
+#[test]
+fn test_sleep() {
+    run_test(sleep_plan("1"));
+}
+
+#[test]
+#[should_panic(expected = "timed out")]
+fn test_sleep_timeout() {
+    run_test_with_options(
+        sleep_plan("30"),
+        TestOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        },
+    );
+}
@@ -7,42 +7,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::{
-    io::Write,
-    process::{Command, Output, Stdio},
-};
-
-use plib::testing::TestPlan;
-
-fn run_test_base(cmd: &str, args: &Vec<String>, stdin_data: &[u8]) -> Output {
-    let relpath = if cfg!(debug_assertions) {
-        format!("target/debug/{}", cmd)
-    } else {
-        format!("target/release/{}", cmd)
-    };
-    let test_bin_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap() // Move up to the workspace root from the current package directory
-        .join(relpath); // Adjust the path to the binary
-
-    let mut command = Command::new(test_bin_path);
-    let mut child = command
-        .args(args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .expect("failed to spawn head");
-
-    let stdin = child.stdin.as_mut().expect("failed to get stdin");
-    stdin
-        .write_all(stdin_data)
-        .expect("failed to write to stdin");
-
-    let output = child.wait_with_output().expect("failed to wait for child");
-    output
-}
+use std::process::Output;
+
+use plib::testing::{run_test_base, TestPlan};
 
 fn get_output(plan: TestPlan) -> Output {
     let output = run_test_base(&plan.cmd, &plan.args, plan.stdin_data.as_bytes());
@@ -91,3 +58,59 @@ fn parse_error_test() {
 fn command_error_test() {
     run_test_time(&["-s", "ls", "-l"], "", "unexpected argument '-s' found", 0);
 }
+
+fn time_output(args: &[&str]) -> Output {
+    let args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+    run_test_base("time", &args, b"")
+}
+
+#[test]
+fn p_format_test() {
+    let output = time_output(&["-p", "true"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", stderr);
+    for (line, name) in lines.iter().zip(["real", "user", "sys"]) {
+        let value = line.strip_prefix(name).and_then(|s| s.strip_prefix(' '));
+        assert!(
+            value.is_some_and(|value| value.parse::<f64>().is_ok()),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn utility_arguments_test() {
+    let output = time_output(&["-p", "sh", "-c", "echo $0", "x"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "x\n");
+}
+
+#[test]
+fn exit_status_test() {
+    let output = time_output(&["sh", "-c", "exit 3"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn not_found_test() {
+    let output = time_output(&["-p", "/nonexistent/utility"]);
+    assert_eq!(output.status.code(), Some(127));
+}
+
+#[test]
+fn not_executable_test() {
+    let output = time_output(&["-p", "/"]);
+    assert_eq!(output.status.code(), Some(126));
+}
+
+#[test]
+fn killed_test() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let output = time_output(&["-p", "sh", "-c", "kill -TERM $$"]);
+    assert_eq!(output.status.signal(), Some(libc::SIGTERM));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("real "));
+}
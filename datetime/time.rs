@@ -8,152 +8,148 @@
 //
 
 use std::io::{self, Write};
-use std::process::{Command, Stdio};
-use std::time::Instant;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant};
 
 use clap::Parser;
-use gettextrs::{
-    bind_textdomain_codeset, bindtextdomain, gettext, setlocale, textdomain, LocaleCategory,
-};
+use libc::c_int;
+use plib::diag::error_message;
+use plib::signal;
+use plib::tdiag;
 
+/// time - time a simple command
 #[derive(Parser)]
-#[command(
-    version,
-    about = gettext("time - time a simple command or give resource usage"),
-    help_template = gettext("{about}\n\nUsage: {usage}\n\nArguments:\n{positionals}\n\nOptions:\n{options}"),
-    disable_help_flag = true,
-    disable_version_flag = true,
-)]
+#[command(version, about)]
 struct Args {
-    #[arg(
-        short,
-        long,
-        help = gettext("Write timing output to standard error in POSIX format")
-    )]
+    /// Write the timing statistics to standard error in the POSIX format.
+    #[arg(short, long)]
     posix: bool,
 
-    #[arg(help = gettext("The utility to be invoked"))]
-    utility: String,
-
-    #[arg(
-        name = "ARGUMENT",
-        trailing_var_arg = true,
-        help = gettext("Arguments for the utility")
-    )]
-    arguments: Vec<String>,
-
-    #[arg(short, long, help = gettext("Print help"), action = clap::ArgAction::HelpLong)]
-    help: Option<bool>,
+    /// The utility to be invoked, and its arguments.
+    #[arg(required = true, trailing_var_arg = true, value_name = "UTILITY")]
+    command: Vec<String>,
+}
 
-    #[arg(short = 'V', long, help = gettext("Print version"), action = clap::ArgAction::Version)]
-    version: Option<bool>,
+/// Signals sent to time while the utility runs, which are passed on to it.
+const FORWARDED_SIGNALS: [c_int; 5] = [
+    libc::SIGHUP,
+    libc::SIGTERM,
+    libc::SIGUSR1,
+    libc::SIGUSR2,
+    libc::SIGALRM,
+];
+
+/// The process ID of the utility, once it runs.
+static CHILD_PID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward_signal(signal: c_int) {
+    let pid = CHILD_PID.load(Ordering::SeqCst);
+    if pid > 0 {
+        // SAFETY: kill is async-signal-safe
+        unsafe { libc::kill(pid, signal) };
+    }
 }
 
-enum TimeError {
-    ExecCommand(String),
-    ExecTime,
-    CommandNotFound(String),
+/// The user and system CPU time of the waited-for children of time.
+fn children_times() -> (Duration, Duration) {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage fills in the struct, which is zeroed to start with
+    let usage = unsafe {
+        libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr());
+        usage.assume_init()
+    };
+    let duration = |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+    (duration(usage.ru_utime), duration(usage.ru_stime))
 }
 
-fn time(args: Args) -> Result<(), TimeError> {
-    let start_time = Instant::now();
-    // SAFETY: std::mem::zeroed() is used to create an instance of libc::tms with all fields set to zero.
-    // This is safe here because libc::tms is a Plain Old Data type, and zero is a valid value for all its fields.
-    let mut tms_start: libc::tms = unsafe { std::mem::zeroed() };
-    // SAFETY: sysconf is a POSIX function that returns the number of clock ticks per second.
-    // It is safe to call because it does not modify any memory and has no side effects.
-    let clock_ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) as f64 };
-
-    // SAFETY: times is a POSIX function that fills the provided tms structure with time-accounting information.
-    // It is safe to call because we have correctly allocated and initialized tms_start, and the function
-    // only writes to this structure.
-    unsafe { libc::times(&mut tms_start) };
-
-    let mut child = Command::new(&args.utility)
-        .args(args.arguments)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()
-        .map_err(|e| match e.kind() {
-            io::ErrorKind::NotFound => TimeError::CommandNotFound(args.utility),
-            _ => TimeError::ExecCommand(args.utility),
-        })?;
-
-    let _ = child.wait().map_err(|_| TimeError::ExecTime)?;
-
-    let elapsed = start_time.elapsed();
-    let tms_end: libc::tms = unsafe { std::mem::zeroed() };
-
-    let user_time = (tms_start.tms_utime - tms_end.tms_utime) as f64 / clock_ticks_per_second;
-    let system_time = (tms_start.tms_stime - tms_end.tms_stime) as f64 / clock_ticks_per_second;
-
-    if args.posix {
-        writeln!(
-            io::stderr(),
-            "real {:.6}\nuser {:.6}\nsys {:.6}",
-            elapsed.as_secs_f64(),
-            user_time,
-            system_time
+/// Runs the utility and writes its times. Returns how it terminated, or
+/// the exit status of time when it couldn't be run.
+fn time(args: Args) -> Result<ExitStatus, i32> {
+    let (utility, arguments) = args.command.split_first().expect("required by clap");
+
+    let start = Instant::now();
+    let mut child = match Command::new(utility).args(arguments).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            tdiag!("cannot run '{}': {}", utility, error_message(&e));
+            return Err(if e.kind() == io::ErrorKind::NotFound {
+                127
+            } else {
+                126
+            });
+        }
+    };
+
+    // Interrupts and quits from the terminal reach the utility directly,
+    // as it is in the same process group; time outlives it to report.
+    // Ignored signals stay ignored across exec, so these are set up only
+    // once the utility runs.
+    CHILD_PID.store(child.id() as i32, Ordering::SeqCst);
+    let _ = signal::ignore(libc::SIGINT);
+    let _ = signal::ignore(libc::SIGQUIT);
+    let _ = signal::set_handler(&FORWARDED_SIGNALS, forward_signal);
+
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(e) => {
+            tdiag!("cannot wait for '{}': {}", utility, error_message(&e));
+            return Err(1);
+        }
+    };
+    let real = start.elapsed();
+    let (user, system) = children_times();
+
+    let report = if args.posix {
+        format!(
+            "real {:.2}\nuser {:.2}\nsys {:.2}\n",
+            real.as_secs_f64(),
+            user.as_secs_f64(),
+            system.as_secs_f64()
         )
-        .map_err(|_| TimeError::ExecTime)?;
     } else {
-        writeln!(
-            io::stderr(),
-            "Elapsed time: {:.6} seconds\nUser time: {:.6} seconds\nSystem time: {:.6} seconds",
-            elapsed.as_secs_f64(),
-            user_time,
-            system_time
+        format!(
+            "Elapsed time: {:.6} seconds\nUser time: {:.6} seconds\nSystem time: {:.6} seconds\n",
+            real.as_secs_f64(),
+            user.as_secs_f64(),
+            system.as_secs_f64()
         )
-        .map_err(|_| TimeError::ExecTime)?;
+    };
+    if let Err(e) = io::stderr().write_all(report.as_bytes()) {
+        tdiag!("{}", error_message(&e));
+        return Err(1);
     }
-
-    Ok(())
+    Ok(status)
 }
 
-enum Status {
-    Ok,
-    TimeError,
-    UtilError,
-    UtilNotFound,
-}
-
-impl Status {
-    fn exit(self) -> ! {
-        let res = match self {
-            Status::Ok => 0,
-            Status::TimeError => 1,
-            Status::UtilError => 126,
-            Status::UtilNotFound => 127,
-        };
-
-        std::process::exit(res)
-    }
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bindtextdomain("posixutils-rs", "locale")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
-    if let Err(err) = time(args) {
-        match err {
-            TimeError::CommandNotFound(err) => {
-                eprintln!("Command not found: {}", err);
-                Status::UtilNotFound.exit()
-            }
-            TimeError::ExecCommand(err) => {
-                eprintln!("Error while executing command: {}", err);
-                Status::UtilError.exit()
-            }
-            TimeError::ExecTime => {
-                eprintln!("Error while executing time utility");
-                Status::TimeError.exit()
-            }
-        }
+    let status = match time(args) {
+        Ok(status) => status,
+        Err(code) => std::process::exit(code),
+    };
+    if let Some(code) = status.code() {
+        std::process::exit(code);
     }
 
-    Status::Ok.exit()
+    // The utility was killed by a signal: time dies of the same signal,
+    // so that its parent sees the same status
+    if let Some(sig) = status.signal() {
+        // The utility has dumped core already, if it was to
+        let no_core = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: no_core is a valid rlimit
+        unsafe { libc::setrlimit(libc::RLIMIT_CORE, &no_core) };
+        let _ = signal::set_default(sig);
+        // SAFETY: raise accepts any signal number
+        unsafe { libc::raise(sig) };
+        std::process::exit(128 + sig);
+    }
+    std::process::exit(1);
 }
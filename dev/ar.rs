@@ -15,6 +15,7 @@ use std::io::{stdout, Write};
 use std::os::unix::ffi::{OsStrExt, OsStringExt};
 use std::os::unix::fs::MetadataExt;
 use std::path::Path;
+use std::time::UNIX_EPOCH;
 
 #[derive(clap::Args)]
 #[group(required = false, multiple = false)]
@@ -130,6 +131,11 @@ struct ExtractArgs {
     files: Vec<OsString>,
 }
 
+#[derive(clap::Args)]
+struct SymbolTableArgs {
+    archive: OsString,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Delete one or more files from the archive
@@ -153,6 +159,9 @@ enum Commands {
     /// Extract files from the archive
     #[command(name = "-x")]
     Extract(ExtractArgs),
+    /// Regenerate the archive's symbol table
+    #[command(name = "-s")]
+    SymbolTable(SymbolTableArgs),
 }
 
 /// ar - create and maintain library archives
@@ -202,7 +211,8 @@ impl ArchiveMember {
         let date = file_metadata
             .modified()
             .ok()
-            .map(|t| t.elapsed().ok().map(|d| d.as_secs()).unwrap_or_default())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
             .unwrap_or_default();
 
         Ok(ArchiveMember {
@@ -218,21 +228,23 @@ impl ArchiveMember {
         })
     }
 
-    fn write<W: Write>(&self, writer: &mut W) -> ArResult<()> {
-        // format definition taken from: https://en.wikipedia.org/wiki/Ar_(Unix)
-
-        // Since we are using the System V (or GNU) archive format, the data section
-        // needs to be 2 byte aligned, if it isn't we add a newline as filler
-        let size = self.size + (self.data.len() % 2) as u64;
+    /// Size of the member in the archive, including its header and padding.
+    fn archive_size(&self) -> u64 {
+        MEMBER_HEADER_SIZE + self.size + self.size % 2
+    }
 
-        writer.write_all(&format_name_for_header(&self.name)?)?;
+    fn write<W: Write>(&self, writer: &mut W, header_name: &[u8; 16]) -> ArResult<()> {
+        // format definition taken from: https://en.wikipedia.org/wiki/Ar_(Unix)
+        writer.write_all(header_name)?;
         writer.write_all(&pad_metadata_with_spaces::<12>(self.date.to_string())?)?;
         writer.write_all(&pad_metadata_with_spaces::<6>(self.uid.to_string())?)?;
         writer.write_all(&pad_metadata_with_spaces::<6>(self.gid.to_string())?)?;
         writer.write_all(&pad_metadata_with_spaces::<8>(format!("{:o}", self.mode))?)?;
-        writer.write_all(&pad_metadata_with_spaces::<10>(size.to_string())?)?;
+        writer.write_all(&pad_metadata_with_spaces::<10>(self.size.to_string())?)?;
         writer.write_all(&object::archive::TERMINATOR)?;
         writer.write_all(&self.data)?;
+        // Since we are using the System V (or GNU) archive format, the data section
+        // needs to be 2 byte aligned, if it isn't we add a newline as filler
         if self.data.len() % 2 != 0 {
             writer.write_all(b"\n")?;
         }
@@ -305,15 +317,58 @@ impl Archive {
     }
 
     fn write<W: Write>(&self, writer: &mut W) -> ArResult<()> {
+        let (name_table, header_names) = self.member_header_names()?;
+        let name_table_size = if name_table.is_empty() {
+            0
+        } else {
+            MEMBER_HEADER_SIZE as u32 + name_table.len() as u32
+        };
+
         writer.write_all(&object::archive::MAGIC)?;
-        self.write_symbol_table(writer)?;
-        for member in &self.members {
-            member.write(writer)?;
+        self.write_symbol_table(writer, name_table_size)?;
+        if !name_table.is_empty() {
+            writer.write_all(&pad_metadata_with_spaces::<48>("//".to_string())?)?;
+            writer.write_all(&pad_metadata_with_spaces::<10>(
+                name_table.len().to_string(),
+            )?)?;
+            writer.write_all(&object::archive::TERMINATOR)?;
+            writer.write_all(&name_table)?;
+        }
+        for (member, header_name) in self.members.iter().zip(&header_names) {
+            member.write(writer, header_name)?;
         }
         Ok(())
     }
 
-    fn write_symbol_table<W: Write>(&self, writer: &mut W) -> ArResult<()> {
+    /// Builds the GNU extended file name table that holds the member names that don't fit
+    /// into a member header, and the header name of every member.
+    fn member_header_names(&self) -> ArResult<(Vec<u8>, Vec<[u8; 16]>)> {
+        let mut name_table = Vec::new();
+        let mut header_names = Vec::with_capacity(self.members.len());
+
+        for member in &self.members {
+            if member.name.len() > 15 {
+                // the header refers to the name by its offset into the table
+                header_names.push(pad_metadata_with_spaces::<16>(format!(
+                    "/{}",
+                    name_table.len()
+                ))?);
+                name_table.extend(member.name.as_bytes());
+                name_table.extend(b"/\n");
+            } else {
+                header_names.push(format_name_for_header(&member.name)?);
+            }
+        }
+
+        // like member data, the table is 2 byte aligned
+        if name_table.len() % 2 != 0 {
+            name_table.push(b'\n');
+        }
+
+        Ok((name_table, header_names))
+    }
+
+    fn write_symbol_table<W: Write>(&self, writer: &mut W, name_table_size: u32) -> ArResult<()> {
         // format definition taken from: https://en.wikipedia.org/wiki/Ar_(Unix)
 
         // The symbol table is made up of the following:
@@ -328,8 +383,10 @@ impl Archive {
         }
         let mut table_offsets = Vec::with_capacity(self.symbol_count as usize * 4);
         let mut table_symbols = Vec::with_capacity(self.symbol_bytes as usize);
-        let mut total_offset =
-            object::archive::MAGIC.len() as u32 + MEMBER_HEADER_SIZE as u32 + symbol_table_size;
+        let mut total_offset = object::archive::MAGIC.len() as u32
+            + MEMBER_HEADER_SIZE as u32
+            + symbol_table_size
+            + name_table_size;
 
         for member in &self.members {
             for symbol in &member.symbols {
@@ -337,7 +394,7 @@ impl Archive {
                 table_symbols.extend(symbol.as_bytes());
                 table_symbols.push(b'\0');
             }
-            total_offset += (MEMBER_HEADER_SIZE + member.size) as u32;
+            total_offset += member.archive_size() as u32;
         }
 
         let mut symbol_table = Vec::with_capacity(symbol_table_size as usize);
@@ -479,9 +536,11 @@ fn read_member_symbols(member_data: &[u8]) -> Vec<String> {
         let symbols = object_file
             .symbols()
             .filter(|s| {
-                s.kind() == SymbolKind::Text
-                    || s.kind() == SymbolKind::Data
-                    || s.kind() == SymbolKind::Tls
+                s.is_global()
+                    && !s.is_undefined()
+                    && (s.kind() == SymbolKind::Text
+                        || s.kind() == SymbolKind::Data
+                        || s.kind() == SymbolKind::Tls)
             })
             .map(|s| s.name().unwrap().to_string())
             .collect();
@@ -569,7 +628,6 @@ fn print_cmd(args: PrintArgs) -> ArResult<()> {
             }
             stdout().write_all(&member.data)?;
         }
-        return Ok(());
     } else {
         for file in &args.files {
             if let Some(index) = archive.member_index(file) {
@@ -647,7 +705,7 @@ fn replace_cmd(args: ReplaceArgs) -> ArResult<()> {
         if let Some(index) = archive.member_index(file_name) {
             if args.update_if_not_newer {
                 let current_member = archive.get_member(index);
-                if current_member.date > member.date {
+                if current_member.date >= member.date {
                     continue;
                 }
             }
@@ -784,6 +842,16 @@ fn extract_cmd(args: ExtractArgs) -> ArResult<()> {
     Ok(())
 }
 
+fn symbol_table_cmd(args: SymbolTableArgs) -> ArResult<()> {
+    let archive_path = Path::new(&args.archive);
+    let archive = Archive::read_from_file(archive_path)?;
+
+    let mut out_file = std::fs::File::create(archive_path)?;
+    archive.write(&mut out_file)?;
+
+    Ok(())
+}
+
 fn main() -> ArResult<()> {
     let args = Args::parse();
     match args.command {
@@ -794,5 +862,6 @@ fn main() -> ArResult<()> {
         Commands::Replace(args) => replace_cmd(args),
         Commands::List(args) => list_cmd(args),
         Commands::Extract(args) => extract_cmd(args),
+        Commands::SymbolTable(args) => symbol_table_cmd(args),
     }
 }
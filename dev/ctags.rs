@@ -0,0 +1,492 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+
+/// ctags - create a tags file
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Append to the tags file instead of replacing it.
+    #[arg(short = 'a')]
+    append: bool,
+
+    /// Write the tags to tagsfile instead of tags.
+    #[arg(short = 'f', default_value = "tags")]
+    tagsfile: PathBuf,
+
+    /// Write the name, line number, file name and line text of each tag to standard output
+    /// instead of writing a tags file.
+    #[arg(short = 'x')]
+    xref: bool,
+
+    /// C, lex or yacc source files to scan.
+    #[arg(required = true)]
+    pathnames: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Punct(u8),
+    /// The name of a `#define` directive.
+    Define(String),
+}
+
+/// A token with the line number it starts on.
+type Spanned = (Token, usize);
+
+struct Tag {
+    name: String,
+    file: String,
+    line: usize,
+    text: String,
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+fn is_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+/// Splits C source into identifiers and punctuation, skipping comments, literals and all
+/// preprocessor directives except for the names of `#define`s.
+fn tokenize(src: &[u8]) -> Vec<Spanned> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut line_start = true;
+    let mut i = 0;
+
+    // skips a block comment starting at `i`, returning the index after it
+    let skip_comment = |mut i: usize, line: &mut usize| {
+        i += 2;
+        while i < src.len() && !(src[i] == b'*' && src.get(i + 1) == Some(&b'/')) {
+            if src[i] == b'\n' {
+                *line += 1;
+            }
+            i += 1;
+        }
+        i + 2
+    };
+
+    while i < src.len() {
+        let c = src[i];
+        match c {
+            b'\n' => {
+                line += 1;
+                line_start = true;
+                i += 1;
+            }
+            _ if c.is_ascii_whitespace() => i += 1,
+            b'/' if src.get(i + 1) == Some(&b'*') => i = skip_comment(i, &mut line),
+            b'/' if src.get(i + 1) == Some(&b'/') => {
+                while i < src.len() && src[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'#' if line_start => {
+                let start_line = line;
+                i += 1;
+                while i < src.len() && (src[i] == b' ' || src[i] == b'\t') {
+                    i += 1;
+                }
+                let word = i;
+                while i < src.len() && is_ident(src[i]) {
+                    i += 1;
+                }
+                if &src[word..i] == b"define" {
+                    while i < src.len() && (src[i] == b' ' || src[i] == b'\t') {
+                        i += 1;
+                    }
+                    let name = i;
+                    while i < src.len() && is_ident(src[i]) {
+                        i += 1;
+                    }
+                    if name < i {
+                        let name = String::from_utf8_lossy(&src[name..i]).into_owned();
+                        tokens.push((Token::Define(name), start_line));
+                    }
+                }
+
+                // skip the rest of the logical line
+                while i < src.len() && src[i] != b'\n' {
+                    if src[i] == b'\\' && src.get(i + 1) == Some(&b'\n') {
+                        line += 1;
+                        i += 2;
+                    } else if src[i] == b'/' && src.get(i + 1) == Some(&b'*') {
+                        i = skip_comment(i, &mut line);
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'"' | b'\'' => {
+                i += 1;
+                while i < src.len() && src[i] != c && src[i] != b'\n' {
+                    if src[i] == b'\\' && i + 1 < src.len() {
+                        if src[i + 1] == b'\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                line_start = false;
+            }
+            _ if is_ident_start(c) => {
+                let start = i;
+                while i < src.len() && is_ident(src[i]) {
+                    i += 1;
+                }
+                let name = String::from_utf8_lossy(&src[start..i]).into_owned();
+                tokens.push((Token::Ident(name), line));
+                line_start = false;
+            }
+            _ if c.is_ascii_digit() => {
+                while i < src.len() && (is_ident(src[i]) || src[i] == b'.') {
+                    i += 1;
+                }
+                line_start = false;
+            }
+            _ => {
+                tokens.push((Token::Punct(c), line));
+                line_start = false;
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_punct(tokens: &[Spanned], i: usize, c: u8) -> bool {
+    matches!(tokens.get(i), Some((Token::Punct(p), _)) if *p == c)
+}
+
+fn ident_at(tokens: &[Spanned], i: usize) -> Option<&str> {
+    match tokens.get(i) {
+        Some((Token::Ident(name), _)) => Some(name),
+        _ => None,
+    }
+}
+
+/// Returns the index of the `)` closing the `(` at `open`.
+fn matching_paren(tokens: &[Spanned], open: usize) -> Option<usize> {
+    let mut level = 0;
+    for (i, (token, _)) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct(b'(') => level += 1,
+            Token::Punct(b')') => {
+                level -= 1;
+                if level == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Checks whether the tokens following a parameter list start a function body, allowing for
+/// old-style parameter declarations in between.
+fn is_function_body(tokens: &[Spanned], mut i: usize) -> bool {
+    if ident_at(tokens, i).is_none() {
+        return is_punct(tokens, i, b'{');
+    }
+    while let Some((token, _)) = tokens.get(i) {
+        match token {
+            Token::Punct(b'{') => return true,
+            Token::Punct(b'(' | b'=' | b'}') | Token::Define(_) => return false,
+            _ => i += 1,
+        }
+    }
+    false
+}
+
+/// Tags the name of a `struct`, `union` or `enum` definition whose keyword is at `i`.
+fn struct_tag(tokens: &[Spanned], i: usize, tags: &mut Vec<(String, usize)>) {
+    if matches!(ident_at(tokens, i), Some("struct" | "union" | "enum"))
+        && is_punct(tokens, i + 2, b'{')
+    {
+        if let Some((Token::Ident(name), line)) = tokens.get(i + 1) {
+            tags.push((name.clone(), *line));
+        }
+    }
+}
+
+/// Tags the names declared by the `typedef` whose body starts at `i`, returning the index after
+/// its terminating semicolon.
+fn typedef_tags(tokens: &[Spanned], mut i: usize, tags: &mut Vec<(String, usize)>) -> usize {
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    let mut name: Option<(String, usize)> = None;
+
+    while let Some((token, line)) = tokens.get(i) {
+        match token {
+            Token::Punct(b'{') => depth += 1,
+            Token::Punct(b'}') => depth = depth.saturating_sub(1),
+            Token::Punct(b'(') => parens += 1,
+            Token::Punct(b')') => parens = parens.saturating_sub(1),
+            Token::Punct(b';' | b',') if depth == 0 && parens == 0 => {
+                tags.extend(name.take());
+                if is_punct(tokens, i, b';') {
+                    return i + 1;
+                }
+            }
+            Token::Ident(ident) => {
+                struct_tag(tokens, i, tags);
+                // the declarator is the last identifier outside of any parameter list
+                if depth == 0 && (parens == 0 || (parens == 1 && is_punct(tokens, i - 1, b'*'))) {
+                    name = Some((ident.clone(), *line));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    i
+}
+
+/// Finds the functions, macros, typedefs and structure, union and enumeration tags defined in C
+/// source.
+fn c_tags(tokens: &[Spanned]) -> Vec<(String, usize)> {
+    let mut tags = Vec::new();
+    // one entry per open brace, true for the braces of `extern "C"` blocks
+    let mut braces: Vec<bool> = Vec::new();
+    let mut i = 0;
+
+    while let Some((token, line)) = tokens.get(i) {
+        let depth = braces.iter().filter(|is_extern| !**is_extern).count();
+        match token {
+            Token::Define(name) => tags.push((name.clone(), *line)),
+            Token::Punct(b'{') => {
+                braces.push(ident_at(tokens, i.wrapping_sub(1)) == Some("extern"))
+            }
+            Token::Punct(b'}') => {
+                braces.pop();
+            }
+            Token::Ident(name) if name == "typedef" && depth == 0 => {
+                i = typedef_tags(tokens, i + 1, &mut tags);
+                continue;
+            }
+            Token::Ident(name) => {
+                struct_tag(tokens, i, &mut tags);
+
+                let keyword = matches!(
+                    name.as_str(),
+                    "if" | "while" | "for" | "switch" | "return" | "sizeof" | "defined"
+                );
+                if depth == 0 && !keyword && is_punct(tokens, i + 1, b'(') {
+                    if let Some(close) = matching_paren(tokens, i + 1) {
+                        if is_function_body(tokens, close + 1) {
+                            tags.push((name.clone(), *line));
+                        }
+                        i = close + 1;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    tags
+}
+
+/// Finds the nonterminals defined by the rules of a yacc grammar.
+fn yacc_rule_tags(tokens: &[Spanned]) -> Vec<(String, usize)> {
+    let mut tags = Vec::new();
+    let mut depth = 0usize;
+
+    for (i, (token, line)) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct(b'{') => depth += 1,
+            Token::Punct(b'}') => depth = depth.saturating_sub(1),
+            Token::Ident(name) if depth == 0 && is_punct(tokens, i + 1, b':') => {
+                tags.push((name.clone(), *line));
+            }
+            _ => {}
+        }
+    }
+
+    tags
+}
+
+/// Classifies the lines of a lex or yacc input, returning which lines hold C code and which
+/// belong to the rules section.
+fn lex_yacc_sections(lines: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let mut c_code = vec![false; lines.len()];
+    let mut rules = vec![false; lines.len()];
+    let mut section = 0;
+    let mut in_code = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if section < 2 && !in_code && line.starts_with("%%") {
+            section += 1;
+        } else if section == 2 {
+            c_code[i] = true;
+        } else if line.starts_with("%{") {
+            in_code = true;
+        } else if in_code && line.starts_with("%}") {
+            in_code = false;
+        } else {
+            c_code[i] = in_code;
+            rules[i] = section == 1 && !in_code;
+        }
+    }
+
+    (c_code, rules)
+}
+
+/// Blanks out the lines not selected by `keep`, preserving line numbers.
+fn select_lines(lines: &[&str], keep: &[bool]) -> String {
+    lines
+        .iter()
+        .zip(keep)
+        .map(|(line, keep)| if *keep { *line } else { "" })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn scan_file(path: &Path) -> io::Result<Vec<Tag>> {
+    let bytes = fs::read(path)?;
+    let source = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = source.lines().collect();
+
+    let found = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext @ ("y" | "l")) => {
+            let (c_code, rules) = lex_yacc_sections(&lines);
+            let mut found = c_tags(&tokenize(select_lines(&lines, &c_code).as_bytes()));
+            // lex rules are regular expressions, only yacc rules are tokenized
+            if ext == "y" {
+                found.extend(yacc_rule_tags(&tokenize(
+                    select_lines(&lines, &rules).as_bytes(),
+                )));
+            }
+            found
+        }
+        _ => c_tags(&tokenize(&bytes)),
+    };
+
+    let file = path.display().to_string();
+    Ok(found
+        .into_iter()
+        .map(|(name, line)| Tag {
+            name,
+            file: file.clone(),
+            line,
+            text: lines.get(line - 1).unwrap_or(&"").to_string(),
+        })
+        .collect())
+}
+
+/// Escapes a line for use in a `/^...$/` search pattern.
+fn search_pattern(text: &str) -> String {
+    let mut pattern = String::from("/^");
+    for c in text.chars() {
+        if c == '\\' || c == '/' {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str("$/");
+    pattern
+}
+
+fn write_tags_file(args: &Args, tags: &[Tag]) -> io::Result<()> {
+    let mut lines = Vec::new();
+    if args.append {
+        match fs::read_to_string(&args.tagsfile) {
+            Ok(existing) => lines.extend(existing.lines().map(String::from)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    lines.extend(
+        tags.iter()
+            .map(|tag| format!("{}\t{}\t{}", tag.name, tag.file, search_pattern(&tag.text))),
+    );
+    lines.sort();
+    lines.dedup();
+
+    let mut file = io::BufWriter::new(fs::File::create(&args.tagsfile)?);
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    file.flush()
+}
+
+fn main() -> ExitCode {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs").unwrap();
+    bind_textdomain_codeset("posixutils-rs", "UTF-8").unwrap();
+
+    let args = Args::parse();
+
+    let mut exit_code = ExitCode::SUCCESS;
+    let mut tags: Vec<Tag> = Vec::new();
+    let mut seen: HashMap<String, (String, usize)> = HashMap::new();
+
+    for path in &args.pathnames {
+        let found = match scan_file(path) {
+            Ok(found) => found,
+            Err(e) => {
+                eprintln!("ctags: {}: {}", path.display(), e);
+                exit_code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        for tag in found {
+            match seen.get(&tag.name) {
+                // e.g. `typedef struct node {...} node;` tags the same name twice on one line
+                Some((file, line)) if *file == tag.file && *line == tag.line => {}
+                Some(_) => eprintln!(
+                    "ctags: {} {}, {} {}: {}\n{}",
+                    gettext("Duplicate entry in file"),
+                    tag.file,
+                    gettext("line"),
+                    tag.line,
+                    tag.name,
+                    gettext("Second entry ignored")
+                ),
+                None => {
+                    seen.insert(tag.name.clone(), (tag.file.clone(), tag.line));
+                    tags.push(tag);
+                }
+            }
+        }
+    }
+
+    tags.sort_by(|a, b| (&a.name, &a.file, a.line).cmp(&(&b.name, &b.file, b.line)));
+
+    if args.xref {
+        for tag in &tags {
+            println!("{} {} {} {}", tag.name, tag.line, tag.file, tag.text);
+        }
+    } else if let Err(e) = write_tags_file(&args, &tags) {
+        eprintln!("ctags: {}: {}", args.tagsfile.display(), e);
+        exit_code = ExitCode::FAILURE;
+    }
+
+    exit_code
+}
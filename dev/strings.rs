@@ -7,21 +7,13 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use clap::{Parser, ValueEnum};
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use clap::Parser;
 use object::{Object, ObjectSection};
-
-#[derive(Clone, Copy, ValueEnum)]
-enum OffsetFormat {
-    #[value(name = "d", help = "decimal")]
-    Decimal,
-    #[value(name = "o", help = "octal")]
-    Octal,
-    #[value(name = "x", help = "hexadecimal")]
-    Hex,
-}
+use plib::diag::Status;
+use plib::dump::{read_input, OffsetBase};
 
 #[derive(clap::Args, Clone, Copy)]
 struct OutputOptions {
@@ -29,12 +21,16 @@ struct OutputOptions {
     #[arg(short = 'a')]
     scan_all: bool,
 
-    /// Byte offset format
-    #[arg(short = 't')]
-    format: Option<OffsetFormat>,
+    /// Byte offset format: d for decimal, o for octal, x for hexadecimal
+    #[arg(short = 't', value_parser = OffsetBase::parse)]
+    format: Option<OffsetBase>,
 
     /// Minimum string length
-    #[arg(short = 'n', default_value_t = 4)]
+    #[arg(
+        short = 'n',
+        default_value_t = 4,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
     minimum_string_length: usize,
 }
 
@@ -45,7 +41,8 @@ struct Args {
     #[command(flatten)]
     output_options: OutputOptions,
 
-    input_files: Vec<OsString>,
+    /// Files to scan, standard input if none or -
+    input_files: Vec<PathBuf>,
 }
 
 type StringsResult = Result<(), Box<dyn std::error::Error>>;
@@ -119,24 +116,31 @@ fn read_printable_ascii_char(bytes: &[u8]) -> Option<char> {
     }
 }
 
-fn print_string(s: &str, starting_offset: usize, format: Option<OffsetFormat>) {
-    // the width of the byte offset was chosen to match the
-    // behavior of the GNU strings implementation.
+fn print_string<W: Write>(
+    out: &mut W,
+    s: &str,
+    starting_offset: usize,
+    format: Option<OffsetBase>,
+) -> std::io::Result<()> {
     match format {
-        Some(OffsetFormat::Decimal) => {
-            println!("{:7} {}", starting_offset, s);
-        }
-        Some(OffsetFormat::Octal) => {
-            println!("{:7o} {}", starting_offset, s);
-        }
-        Some(OffsetFormat::Hex) => {
-            println!("{:7x} {}", starting_offset, s);
-        }
-        _ => println!("{}", s),
+        // the width of the byte offset was chosen to match the
+        // behavior of the GNU strings implementation.
+        Some(base) => writeln!(
+            out,
+            "{} {}",
+            base.format(starting_offset as u64, 7, false),
+            s
+        ),
+        None => writeln!(out, "{}", s),
     }
 }
 
-fn print_strings<F>(bytes: &[u8], options: OutputOptions, read_char: F)
+fn print_strings<W: Write, F>(
+    out: &mut W,
+    bytes: &[u8],
+    options: OutputOptions,
+    read_char: F,
+) -> std::io::Result<()>
 where
     F: Fn(&[u8]) -> Option<char>,
 {
@@ -149,25 +153,33 @@ where
             offset += c.len_utf8();
         } else {
             if print_buffer.len() >= options.minimum_string_length {
-                print_string(&print_buffer, offset - print_buffer.len(), options.format);
+                let start = offset - print_buffer.len();
+                print_string(out, &print_buffer, start, options.format)?;
             }
             print_buffer.clear();
             offset += 1;
         }
     }
     if print_buffer.len() >= options.minimum_string_length {
-        print_string(&print_buffer, offset - print_buffer.len(), options.format);
+        let start = offset - print_buffer.len();
+        print_string(out, &print_buffer, start, options.format)?;
     }
+    Ok(())
 }
 
-fn print_file<F>(path: OsString, output_options: OutputOptions, read_char: F) -> StringsResult
+fn print_file<W: Write, F>(
+    out: &mut W,
+    path: &Path,
+    output_options: OutputOptions,
+    read_char: F,
+) -> StringsResult
 where
     F: Fn(&[u8]) -> Option<char> + Copy,
 {
-    let bytes = std::fs::read(path)?;
+    let bytes = read_input(path)?;
 
     if output_options.scan_all {
-        print_strings(&bytes, output_options, read_char);
+        print_strings(out, &bytes, output_options, read_char)?;
         return Ok(());
     }
 
@@ -175,34 +187,38 @@ where
         for section in parsed_object.sections() {
             // skip empty sections
             if !section.kind().is_bss() {
-                print_strings(section.data()?, output_options, read_char);
+                print_strings(out, section.data()?, output_options, read_char)?;
             }
         }
     } else {
-        print_strings(&bytes, output_options, read_char);
+        print_strings(out, &bytes, output_options, read_char)?;
     }
 
     Ok(())
 }
 
-fn main() -> StringsResult {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if args.input_files.is_empty() {
+        args.input_files.push(PathBuf::from("-"));
+    }
 
-    match CharacterSet::from_env() {
-        CharacterSet::Utf8 => {
-            for file in args.input_files {
-                print_file(file, args.output_options, read_printable_char_utf8)?;
-            }
-        }
-        CharacterSet::Ascii => {
-            for file in args.input_files {
-                print_file(file, args.output_options, read_printable_ascii_char)?;
-            }
+    let read_char = match CharacterSet::from_env() {
+        CharacterSet::Utf8 => read_printable_char_utf8,
+        CharacterSet::Ascii => read_printable_ascii_char,
+    };
+
+    let mut status = Status::new();
+    let mut stdout = plib::io::stdout();
+    for file in &args.input_files {
+        if let Err(e) = print_file(&mut stdout, file, args.output_options, read_char) {
+            status.operand_error(file.display(), &e);
         }
     }
-    Ok(())
+    if let Err(e) = stdout.finish() {
+        status.error(&e);
+    }
+    status.exit()
 }
@@ -0,0 +1,16 @@
+%{
+#include <stdio.h>
+int yylex(void);
+%}
+%token NUM
+%%
+expr	: expr '+' term { $$ = $1 + $3; }
+	| term
+	;
+term	: NUM
+	;
+%%
+void yyerror(const char *msg)
+{
+    fprintf(stderr, "%s\n", msg);
+}
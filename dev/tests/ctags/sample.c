@@ -0,0 +1,41 @@
+#include <stdio.h>
+
+#define MAX_ITEMS 16
+#define SQUARE(x) ((x) * (x))
+
+typedef unsigned long counter_t;
+typedef int (*compare_fn)(const void *, const void *);
+
+typedef struct point {
+    int x, y;
+} point_t;
+
+struct list {
+    struct list *next;
+};
+
+enum color { RED, GREEN };
+
+static int helper(int value);
+
+/* not_a_function(void) { } */
+static int helper(int value)
+{
+    const char *s = "main(void) {";
+    return SQUARE(value) + s[0];
+}
+
+int old_style(a, b)
+int a;
+char *b;
+{
+    return a;
+}
+
+int main(void)
+{
+    if (helper(2)) {
+        puts("ok");
+    }
+    return 0;
+}
@@ -1,6 +1,9 @@
 use object::{Object, ObjectSection, ObjectSymbol};
-use plib::testing::{run_test, run_test_with_checker, TestPlan};
+use plib::testing::{
+    run_test, run_test_with_checker, run_test_with_options, OutputMatch, TestOptions, TestPlan,
+};
 use std::fs;
+use std::process::Command;
 
 fn ar_compare_test(
     args: &[&str],
@@ -106,6 +109,42 @@ fn strings_test(args: &[&str], stdout: &str) {
     });
 }
 
+fn yacc_test(args: &[&str], stderr: &str, exit_code: i32) {
+    run_test(TestPlan {
+        cmd: "yacc".to_string(),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: "".to_string(),
+        expected_out: "".to_string(),
+        expected_err: stderr.to_string(),
+        expected_exit_code: exit_code,
+    });
+}
+
+/// Returns the file prefix for yacc output files of a test.
+fn yacc_output_prefix(test_name: &str) -> String {
+    let dir = format!("{}/{}", env!("CARGO_TARGET_TMPDIR"), test_name);
+    fs::create_dir_all(&dir).unwrap();
+    format!("{}/y", dir)
+}
+
+fn ctags_test(args: &[&str], stdout: &str) {
+    run_test(TestPlan {
+        cmd: "ctags".to_string(),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        stdin_data: "".to_string(),
+        expected_out: stdout.to_string(),
+        expected_err: "".to_string(),
+        expected_exit_code: 0,
+    });
+}
+
+/// Returns the path of the tags file written by a test.
+fn ctags_output_path(test_name: &str) -> String {
+    let dir = format!("{}/{}", env!("CARGO_TARGET_TMPDIR"), test_name);
+    fs::create_dir_all(&dir).unwrap();
+    format!("{}/tags", dir)
+}
+
 #[test]
 fn test_ar_delete_one() {
     ar_compare_test(
@@ -431,6 +470,75 @@ fn test_ar_list() {
     });
 }
 
+#[test]
+fn test_ar_long_member_names() {
+    let dir = format!("{}/test_ar_long_member_names", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(&dir).unwrap();
+    let long_name = format!("{}/a_long_member_name.o", dir);
+    let archive_path = format!("{}/long_names.a", dir);
+    fs::copy("tests/ar/lib1.o", &long_name).unwrap();
+    let _ = fs::remove_file(&archive_path);
+
+    ar_print_test(
+        &["-q", "-c", &archive_path, &long_name, "tests/ar/lib2.o"],
+        b"",
+    );
+    ar_print_test(&["-t", &archive_path], b"a_long_member_name.o\nlib2.o\n");
+
+    let contents = fs::read(&archive_path).unwrap();
+    let archive = object::read::archive::ArchiveFile::parse(&*contents).unwrap();
+    let members: Vec<_> = archive.members().map(Result::unwrap).collect();
+    assert_eq!(members[0].name(), b"a_long_member_name.o");
+    assert_eq!(
+        members[0].data(&*contents).unwrap(),
+        fs::read("tests/ar/lib1.o").unwrap()
+    );
+    assert_eq!(members[1].name(), b"lib2.o");
+}
+
+#[test]
+fn test_ar_symbol_table() {
+    let dir = format!("{}/test_ar_symbol_table", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(&dir).unwrap();
+    let archive_path = format!("{}/symbols.a", dir);
+    fs::copy("tests/ar/list.a", &archive_path).unwrap();
+
+    ar_print_test(&["-s", &archive_path], b"");
+
+    let contents = fs::read(&archive_path).unwrap();
+    let archive = object::read::archive::ArchiveFile::parse(&*contents).unwrap();
+    let mut symbols = Vec::new();
+    for symbol in archive.symbols().unwrap().unwrap() {
+        let symbol = symbol.unwrap();
+        let member = archive.member(symbol.offset()).unwrap();
+        symbols.push((
+            String::from_utf8_lossy(symbol.name()).to_string(),
+            String::from_utf8_lossy(member.name()).to_string(),
+        ));
+    }
+    assert!(symbols.contains(&("factorial".to_string(), "lib1.o".to_string())));
+    assert!(symbols.iter().all(|(_, member)| member.ends_with(".o")));
+    assert!(symbols
+        .iter()
+        .all(|(name, member)| member_defines_symbol(&archive, &contents, member, name)));
+}
+
+/// Checks that the archive member named `member` defines the global symbol `name`.
+fn member_defines_symbol(
+    archive: &object::read::archive::ArchiveFile,
+    contents: &[u8],
+    member: &str,
+    name: &str,
+) -> bool {
+    archive.members().map(Result::unwrap).any(|m| {
+        m.name() == member.as_bytes()
+            && object::File::parse(m.data(contents).unwrap())
+                .unwrap()
+                .symbols()
+                .any(|s| s.is_global() && !s.is_undefined() && s.name() == Ok(name))
+    })
+}
+
 #[test]
 fn test_ar_list_some() {
     run_test(TestPlan {
@@ -596,3 +704,190 @@ fn test_strings_print_with_octal_offset() {
         include_str!("strings/with_octal_offset.correct.txt"),
     );
 }
+
+#[test]
+fn test_strings_stdin() {
+    run_test(TestPlan {
+        cmd: "strings".to_string(),
+        args: vec![
+            "-t".to_string(),
+            "o".to_string(),
+            "-n".to_string(),
+            "3".to_string(),
+        ],
+        stdin_data: "ab\u{1}abc\u{0}\u{0}\u{0}\u{0}defg".to_string(),
+        expected_out: "      3 abc\n     12 defg\n".to_string(),
+        expected_err: "".to_string(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn test_strings_missing_file() {
+    run_test(TestPlan {
+        cmd: "strings".to_string(),
+        args: vec![
+            "tests/strings/missing.bin".to_string(),
+            "tests/strings/one.txt".to_string(),
+        ],
+        stdin_data: "".to_string(),
+        expected_out: include_str!("strings/one.correct.txt").to_string(),
+        expected_err: "strings: tests/strings/missing.bin: No such file or directory\n".to_string(),
+        expected_exit_code: 1,
+    });
+}
+
+#[test]
+fn test_strings_zero_minimum_length() {
+    run_test_with_options(
+        TestPlan {
+            cmd: "strings".to_string(),
+            args: vec!["-n".to_string(), "0".to_string()],
+            stdin_data: "abcd".to_string(),
+            expected_out: "".to_string(),
+            expected_err: "'-n <MINIMUM_STRING_LENGTH>'".to_string(),
+            expected_exit_code: 2,
+        },
+        TestOptions {
+            stderr_match: OutputMatch::Contains,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn test_yacc_definitions_file() {
+    let prefix = yacc_output_prefix("test_yacc_definitions_file");
+    yacc_test(&["-d", "-b", &prefix, "tests/yacc/calc.y"], "", 0);
+    let header = fs::read_to_string(format!("{}.tab.h", prefix)).unwrap();
+    assert_eq!(header, include_str!("yacc/calc.tab.h"));
+    assert!(fs::metadata(format!("{}.tab.c", prefix)).is_ok());
+}
+
+#[test]
+fn test_yacc_description_file() {
+    let prefix = yacc_output_prefix("test_yacc_description_file");
+    yacc_test(
+        &["-v", "-b", &prefix, "tests/yacc/dangling_else.y"],
+        "yacc: 1 shift/reduce conflict\n",
+        0,
+    );
+    let description = fs::read_to_string(format!("{}.output", prefix)).unwrap();
+    assert!(description.contains("shift/reduce conflict (shift 5, reduce 1) on ELSE"));
+    assert!(description.contains("4 grammar rules, 7 states"));
+}
+
+#[test]
+fn test_yacc_shift_reduce_conflict() {
+    let prefix = yacc_output_prefix("test_yacc_shift_reduce_conflict");
+    yacc_test(
+        &["-b", &prefix, "tests/yacc/dangling_else.y"],
+        "yacc: 1 shift/reduce conflict\n",
+        0,
+    );
+}
+
+#[test]
+fn test_yacc_reduce_reduce_conflict() {
+    let prefix = yacc_output_prefix("test_yacc_reduce_reduce_conflict");
+    yacc_test(
+        &["-b", &prefix, "tests/yacc/reduce_reduce.y"],
+        "yacc: 1 reduce/reduce conflict\nyacc: 1 rule never reduced\n",
+        0,
+    );
+}
+
+#[test]
+fn test_yacc_undefined_symbol() {
+    let prefix = yacc_output_prefix("test_yacc_undefined_symbol");
+    yacc_test(
+        &["-b", &prefix, "tests/yacc/undefined_symbol.y"],
+        "yacc: error: tests/yacc/undefined_symbol.y:3: symbol B is used, but is not defined as a token and has no rules\n",
+        1,
+    );
+}
+
+#[test]
+fn test_yacc_generated_parser() {
+    // Compiling the generated parser needs a C compiler.
+    if Command::new("cc").arg("--version").output().is_err() {
+        return;
+    }
+
+    let prefix = yacc_output_prefix("test_yacc_generated_parser");
+    yacc_test(&["-b", &prefix, "tests/yacc/calc.y"], "", 0);
+
+    let exe = format!("{}.calc", prefix);
+    let status = Command::new("cc")
+        .args(["-o", &exe, &format!("{}.tab.c", prefix)])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&exe)
+        .stdin(fs::File::open("tests/yacc/calc.in").unwrap())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "7\n3\n4\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "syntax error\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_ctags_xref_c() {
+    ctags_test(
+        &["-x", "tests/ctags/sample.c"],
+        "MAX_ITEMS 3 tests/ctags/sample.c #define MAX_ITEMS 16\n\
+         SQUARE 4 tests/ctags/sample.c #define SQUARE(x) ((x) * (x))\n\
+         color 17 tests/ctags/sample.c enum color { RED, GREEN };\n\
+         compare_fn 7 tests/ctags/sample.c typedef int (*compare_fn)(const void *, const void *);\n\
+         counter_t 6 tests/ctags/sample.c typedef unsigned long counter_t;\n\
+         helper 22 tests/ctags/sample.c static int helper(int value)\n\
+         list 13 tests/ctags/sample.c struct list {\n\
+         main 35 tests/ctags/sample.c int main(void)\n\
+         old_style 28 tests/ctags/sample.c int old_style(a, b)\n\
+         point 9 tests/ctags/sample.c typedef struct point {\n\
+         point_t 11 tests/ctags/sample.c } point_t;\n",
+    );
+}
+
+#[test]
+fn test_ctags_xref_lex_yacc() {
+    ctags_test(
+        &["-x", "tests/ctags/grammar.y", "tests/ctags/scanner.l"],
+        "TOKEN_NUM 2 tests/ctags/scanner.l #define TOKEN_NUM 257\n\
+         expr 7 tests/ctags/grammar.y expr\t: expr '+' term { $$ = $1 + $3; }\n\
+         term 10 tests/ctags/grammar.y term\t: NUM\n\
+         yyerror 13 tests/ctags/grammar.y void yyerror(const char *msg)\n\
+         yywrap 9 tests/ctags/scanner.l int yywrap(void)\n",
+    );
+}
+
+#[test]
+fn test_ctags_tags_file() {
+    let tags = ctags_output_path("test_ctags_tags_file");
+    ctags_test(&["-f", &tags, "tests/ctags/grammar.y"], "");
+
+    assert_eq!(
+        fs::read_to_string(&tags).unwrap(),
+        "expr\ttests/ctags/grammar.y\t/^expr\t: expr '+' term { $$ = $1 + $3; }$/\n\
+         term\ttests/ctags/grammar.y\t/^term\t: NUM$/\n\
+         yyerror\ttests/ctags/grammar.y\t/^void yyerror(const char *msg)$/\n"
+    );
+}
+
+#[test]
+fn test_ctags_append() {
+    let tags = ctags_output_path("test_ctags_append");
+    ctags_test(&["-f", &tags, "tests/ctags/scanner.l"], "");
+    ctags_test(&["-a", "-f", &tags, "tests/ctags/grammar.y"], "");
+
+    assert_eq!(
+        fs::read_to_string(&tags).unwrap(),
+        "TOKEN_NUM\ttests/ctags/scanner.l\t/^#define TOKEN_NUM 257$/\n\
+         expr\ttests/ctags/grammar.y\t/^expr\t: expr '+' term { $$ = $1 + $3; }$/\n\
+         term\ttests/ctags/grammar.y\t/^term\t: NUM$/\n\
+         yyerror\ttests/ctags/grammar.y\t/^void yyerror(const char *msg)$/\n\
+         yywrap\ttests/ctags/scanner.l\t/^int yywrap(void)$/\n"
+    );
+}
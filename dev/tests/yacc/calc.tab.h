@@ -0,0 +1,9 @@
+#define NUMBER 257
+#define UMINUS 258
+#ifndef YYSTYPE_IS_DECLARED
+#define YYSTYPE_IS_DECLARED 1
+typedef union YYSTYPE {
+    int num;
+} YYSTYPE;
+#endif
+extern YYSTYPE yylval;
@@ -0,0 +1,49 @@
+%{
+#include <ctype.h>
+#include <stdio.h>
+int yylex(void);
+void yyerror(const char *s);
+%}
+%union {
+    int num;
+}
+%token <num> NUMBER
+%type <num> expr line
+%left '+' '-'
+%left '*' '/'
+%right UMINUS
+%%
+lines   : /* empty */
+        | lines line
+        ;
+line    : expr '\n'             { printf("%d\n", $1); $$ = $1; }
+        | error '\n'            { yyerrok; $$ = 0; }
+        ;
+expr    : expr '+' expr         { $$ = $1 + $3; }
+        | expr '-' expr         { $$ = $1 - $3; }
+        | expr '*' expr         { $$ = $1 * $3; }
+        | expr '/' expr         { $$ = $3 ? $1 / $3 : 0; }
+        | '-' expr %prec UMINUS { $$ = -$2; }
+        | '(' expr ')'          { $$ = $2; }
+        | NUMBER
+        ;
+%%
+int yylex(void)
+{
+    int c;
+    while ((c = getchar()) == ' ')
+        ;
+    if (isdigit(c)) {
+        yylval.num = 0;
+        do {
+            yylval.num = yylval.num * 10 + (c - '0');
+        } while (isdigit(c = getchar()));
+        ungetc(c, stdin);
+        return NUMBER;
+    }
+    if (c == EOF)
+        return 0;
+    return c;
+}
+void yyerror(const char *s) { fprintf(stderr, "%s\n", s); }
+int main(void) { return yyparse(); }
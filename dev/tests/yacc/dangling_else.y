@@ -0,0 +1,6 @@
+%token IF ELSE X
+%%
+stmt : IF stmt
+     | IF stmt ELSE stmt
+     | X
+     ;
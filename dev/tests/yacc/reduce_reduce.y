@@ -0,0 +1,4 @@
+%%
+s : a 'x' | b 'x' ;
+a : 'y' ;
+b : 'y' ;
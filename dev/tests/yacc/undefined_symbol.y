@@ -0,0 +1,3 @@
+%token A
+%%
+s : A B ;
@@ -0,0 +1,138 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod yacc_util;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use yacc_util::codegen::{code_file, definitions_file, description_file, Options};
+use yacc_util::grammar::parse_grammar;
+use yacc_util::lalr::build_automaton;
+
+/// yacc - yet another compiler compiler
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Use file_prefix instead of y as the prefix for all output filenames.
+    #[arg(short = 'b', default_value = "y")]
+    file_prefix: String,
+
+    /// Write the header file y.tab.h.
+    #[arg(short = 'd')]
+    defines: bool,
+
+    /// Produce a code file that does not contain any #line constructs.
+    #[arg(short = 'l')]
+    no_lines: bool,
+
+    /// Use sym_prefix instead of yy as the prefix for all external names.
+    #[arg(short = 'p', default_value = "yy")]
+    sym_prefix: String,
+
+    /// Enable the debugging code in the code file by default.
+    #[arg(short = 't')]
+    debug: bool,
+
+    /// Write a description of the parser to y.output.
+    #[arg(short = 'v')]
+    verbose: bool,
+
+    /// Grammar file.
+    grammar: PathBuf,
+}
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+    if n == 1 {
+        format!("1 {}", one)
+    } else {
+        format!("{} {}", n, many)
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let grammar_name = args.grammar.display().to_string();
+    let source =
+        std::fs::read_to_string(&args.grammar).map_err(|e| format!("{}: {}", grammar_name, e))?;
+
+    let grammar =
+        parse_grammar(&source).map_err(|e| format!("{}:{}: {}", grammar_name, e.line, e.msg))?;
+    let automaton = build_automaton(&grammar);
+
+    let code_name = format!("{}.tab.c", args.file_prefix);
+    let opts = Options {
+        sym_prefix: &args.sym_prefix,
+        line_directives: !args.no_lines,
+        debug: args.debug,
+        grammar_file: &grammar_name,
+        code_file: &code_name,
+    };
+
+    let code = code_file(&grammar, &automaton, &opts)
+        .map_err(|e| format!("{}:{}: {}", grammar_name, e.line, e.msg))?;
+    std::fs::write(&code_name, code).map_err(|e| format!("{}: {}", code_name, e))?;
+
+    if args.defines {
+        let name = format!("{}.tab.h", args.file_prefix);
+        std::fs::write(&name, definitions_file(&grammar, &opts))
+            .map_err(|e| format!("{}: {}", name, e))?;
+    }
+
+    if args.verbose {
+        let name = format!("{}.output", args.file_prefix);
+        std::fs::write(&name, description_file(&grammar, &automaton))
+            .map_err(|e| format!("{}: {}", name, e))?;
+    }
+
+    let mut notes = Vec::new();
+    if automaton.shift_reduce > 0 {
+        notes.push(plural(
+            automaton.shift_reduce,
+            "shift/reduce conflict",
+            "shift/reduce conflicts",
+        ));
+    }
+    if automaton.reduce_reduce > 0 {
+        notes.push(plural(
+            automaton.reduce_reduce,
+            "reduce/reduce conflict",
+            "reduce/reduce conflicts",
+        ));
+    }
+    if !notes.is_empty() {
+        eprintln!("yacc: {}", notes.join(", "));
+    }
+    let unused = automaton.rule_used.iter().filter(|used| !**used).count();
+    if unused > 0 {
+        eprintln!(
+            "yacc: {}",
+            plural(unused, "rule never reduced", "rules never reduced")
+        );
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain("posixutils-rs").unwrap();
+    bind_textdomain_codeset("posixutils-rs", "UTF-8").unwrap();
+
+    let args = Args::parse();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("yacc: {}: {}", gettext("error"), e);
+            ExitCode::FAILURE
+        }
+    }
+}
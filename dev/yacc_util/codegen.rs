@@ -0,0 +1,763 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Writers for the code file (`y.tab.c`), the definitions file
+//! (`y.tab.h`) and the description file (`y.output`).
+
+use super::grammar::{
+    Action, CodeBlock, Declaration, Grammar, GrammarError, ERROR_SYMBOL, UNDEFINED_SYMBOL,
+};
+use super::lalr::{Automaton, Conflict, ParseAction};
+use std::fmt::Write;
+
+pub struct Options<'a> {
+    /// Prefix replacing `yy` in external names (`-p`).
+    pub sym_prefix: &'a str,
+    /// Emit `#line` directives (cleared by `-l`).
+    pub line_directives: bool,
+    /// Enable the debugging code by default (`-t`).
+    pub debug: bool,
+    /// Name of the grammar file, for `#line` directives.
+    pub grammar_file: &'a str,
+    /// Name of the code file being written, for `#line` directives.
+    pub code_file: &'a str,
+}
+
+/// Accumulates output text while counting lines, so that `#line`
+/// directives can point back into the generated file.
+struct CodeWriter {
+    text: String,
+    line: usize,
+}
+
+impl CodeWriter {
+    fn new() -> Self {
+        CodeWriter {
+            text: String::new(),
+            line: 1,
+        }
+    }
+
+    fn push(&mut self, s: &str) {
+        self.line += s.bytes().filter(|&b| b == b'\n').count();
+        self.text.push_str(s);
+    }
+
+    fn line_directive(&mut self, opts: &Options, line: usize, file: &str) {
+        if opts.line_directives {
+            self.push(&format!("#line {} \"{}\"\n", line, escape_c_string(file)));
+        }
+    }
+
+    /// Points the compiler back at the generated file itself.
+    fn restore_line(&mut self, opts: &Options) {
+        let next = self.line + 1;
+        self.line_directive(opts, next, opts.code_file);
+    }
+
+    fn user_code(&mut self, opts: &Options, block: &CodeBlock) {
+        self.line_directive(opts, block.line, opts.grammar_file);
+        self.push(&block.text);
+        if !block.text.ends_with('\n') {
+            self.push("\n");
+        }
+        self.restore_line(opts);
+    }
+}
+
+fn escape_c_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Token `#define`s, shared by the code and definitions files.
+fn token_definitions(grammar: &Grammar) -> String {
+    let mut s = String::new();
+    for sym in &grammar.symbols[..grammar.num_terminals] {
+        if !sym.literal && sym.value > 256 {
+            writeln!(s, "#define {} {}", sym.name, sym.value).unwrap();
+        }
+    }
+    s
+}
+
+fn union_definition(block: &CodeBlock) -> String {
+    format!(
+        "#ifndef YYSTYPE_IS_DECLARED\n#define YYSTYPE_IS_DECLARED 1\ntypedef union YYSTYPE {} YYSTYPE;\n#endif\n",
+        block.text.trim()
+    )
+}
+
+const DEFAULT_STYPE: &str = "#if !defined(YYSTYPE) && !defined(YYSTYPE_IS_DECLARED)
+#define YYSTYPE_IS_DECLARED 1
+typedef int YYSTYPE;
+#endif
+";
+
+fn prefix_definitions(opts: &Options) -> String {
+    let mut s = String::new();
+    if opts.sym_prefix != "yy" {
+        for name in ["parse", "lex", "error", "lval", "char", "debug", "nerrs"] {
+            writeln!(s, "#define yy{} {}{}", name, opts.sym_prefix, name).unwrap();
+        }
+    }
+    s
+}
+
+/// Writes the definitions file (`y.tab.h`).
+pub fn definitions_file(grammar: &Grammar, opts: &Options) -> String {
+    let mut s = String::new();
+    s.push_str(&prefix_definitions(opts));
+    s.push_str(&token_definitions(grammar));
+    match grammar.declarations.iter().find_map(|d| match d {
+        Declaration::Union(block) => Some(block),
+        _ => None,
+    }) {
+        Some(block) => s.push_str(&union_definition(block)),
+        None => s.push_str(DEFAULT_STYPE),
+    }
+    s.push_str("extern YYSTYPE yylval;\n");
+    s
+}
+
+/// Translates `$$`, `$n`, `$<tag>$` and `$<tag>n` references inside an
+/// action into references to the value stack.
+fn translate_action(
+    grammar: &Grammar,
+    rule: usize,
+    action: &Action,
+) -> Result<String, GrammarError> {
+    let err = |msg: String| GrammarError {
+        line: action.code.line,
+        msg,
+    };
+    let lhs = &grammar.symbols[grammar.rules[rule].lhs];
+    let base = action.visible.len() as i64;
+    let typed = grammar.has_union();
+
+    let chars: Vec<char> = action.code.text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                out.push(c);
+                i += 1;
+                while i < chars.len() {
+                    out.push(chars[i]);
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        out.push(chars[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    if chars[i - 1] == c {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map(|j| j + 2)
+                    .unwrap_or(chars.len());
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                let end = (i..chars.len())
+                    .find(|&j| chars[j] == '\n')
+                    .unwrap_or(chars.len());
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '$' => {
+                let mut j = i + 1;
+                let mut tag = None;
+                if chars.get(j) == Some(&'<') {
+                    let close = match (j..chars.len()).find(|&k| chars[k] == '>') {
+                        Some(close) => close,
+                        None => return Err(err("unterminated $<tag>".to_string())),
+                    };
+                    tag = Some(chars[j + 1..close].iter().collect::<String>());
+                    j = close + 1;
+                }
+
+                if chars.get(j) == Some(&'$') {
+                    let tag = tag.or_else(|| {
+                        if action.mid_rule {
+                            None
+                        } else {
+                            lhs.tag.clone()
+                        }
+                    });
+                    match tag {
+                        Some(tag) => write!(out, "yyval.{}", tag).unwrap(),
+                        None if typed => {
+                            return Err(err(format!("$$ of {} has no declared type", lhs.name)))
+                        }
+                        None => out.push_str("yyval"),
+                    }
+                    i = j + 1;
+                    continue;
+                }
+
+                let negative = chars.get(j) == Some(&'-');
+                let digits_start = if negative { j + 1 } else { j };
+                let digits_end = (digits_start..chars.len())
+                    .find(|&k| !chars[k].is_ascii_digit())
+                    .unwrap_or(chars.len());
+                if digits_end == digits_start {
+                    // not a value reference
+                    out.push('$');
+                    i += 1;
+                    continue;
+                }
+                let digits: String = chars[digits_start..digits_end].iter().collect();
+                let mut n: i64 = match digits.parse() {
+                    Ok(n) => n,
+                    Err(_) => return Err(err(format!("invalid value reference ${}", digits))),
+                };
+                if negative {
+                    n = -n;
+                }
+                if n > base {
+                    return Err(err(format!(
+                        "${} is out of range in a rule for {} with {} symbols",
+                        n, lhs.name, base
+                    )));
+                }
+                let tag = tag.or_else(|| {
+                    if n >= 1 {
+                        grammar.symbols[action.visible[n as usize - 1]].tag.clone()
+                    } else {
+                        None
+                    }
+                });
+                write!(out, "yyvsp[{}]", n - base).unwrap();
+                match tag {
+                    Some(tag) => write!(out, ".{}", tag).unwrap(),
+                    None if typed => {
+                        return Err(err(format!("${} of {} has no declared type", n, lhs.name)))
+                    }
+                    None => {}
+                }
+                i = digits_end;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn write_table(w: &mut CodeWriter, ctype: &str, name: &str, values: &[i64]) {
+    let mut s = format!("static const {} {}[] = {{", ctype, name);
+    if values.is_empty() {
+        // C does not allow empty initializers.
+        s.push_str("\n    0");
+    }
+    for (i, v) in values.iter().enumerate() {
+        if i % 10 == 0 {
+            s.push_str("\n   ");
+        }
+        write!(s, " {},", v).unwrap();
+    }
+    s.push_str("\n};\n");
+    w.push(&s);
+}
+
+fn symbol_display_name(grammar: &Grammar, sym: usize) -> &str {
+    &grammar.symbols[sym].name
+}
+
+fn rule_string(grammar: &Grammar, rule: usize) -> String {
+    let r = &grammar.rules[rule];
+    let mut s = format!("{} :", symbol_display_name(grammar, r.lhs));
+    for &sym in &r.rhs {
+        s.push(' ');
+        s.push_str(symbol_display_name(grammar, sym));
+    }
+    s
+}
+
+const SKELETON_PROLOGUE: &str = r#"
+#define YYEMPTY (-1)
+#define yyclearin (yychar = YYEMPTY)
+#define yyerrok (yyerrflag = 0)
+#define YYRECOVERING() (yyerrflag != 0)
+#define YYACCEPT goto yyaccept
+#define YYABORT goto yyabort
+#define YYERROR goto yyerrorlab
+#ifndef YYMAXDEPTH
+#define YYMAXDEPTH 10000
+#endif
+
+int yylex(void);
+void yyerror(const char *);
+
+int yychar;
+YYSTYPE yylval;
+int yynerrs;
+static int yyerrflag;
+#if YYDEBUG
+int yydebug;
+#endif
+"#;
+
+const SKELETON_PARSER: &str = r#"
+static YYSTYPE yyval;
+static int yyss[YYMAXDEPTH];
+static YYSTYPE yyvs[YYMAXDEPTH];
+
+#define YYTRANSLATE(c) ((unsigned)(c) <= YYMAXTOKEN ? yytranslate[c] : YYUNDEFTOK)
+
+static int
+yyfindaction(int state, int tok, int *action)
+{
+    int i;
+
+    for (i = yyactbase[state]; i < yyactbase[state + 1]; i++) {
+        if (yyacttok[i] == tok) {
+            *action = yyactval[i];
+            return 1;
+        }
+    }
+    return 0;
+}
+
+static int
+yyfindgoto(int state, int nonterminal)
+{
+    int i;
+
+    for (i = yygotobase[nonterminal]; i < yygotobase[nonterminal + 1]; i++) {
+        if (yygotofrom[i] == state)
+            return yygototo[i];
+    }
+    return yygotodef[nonterminal];
+}
+
+int
+yyparse(void)
+{
+    int yystate, yyn, yylen, yyrule;
+    int *yyssp;
+    YYSTYPE *yyvsp;
+
+    yynerrs = 0;
+    yyerrflag = 0;
+    yychar = YYEMPTY;
+    yylen = 0;
+    yyssp = yyss;
+    yyvsp = yyvs;
+    *yyssp = yystate = 0;
+    if (0)
+        goto yyerrorlab; /* pacify compilers when YYERROR is unused */
+
+yyloop:
+    if (yyactbase[yystate] == yyactbase[yystate + 1] && yydefred[yystate] != 0) {
+        /* the reduction does not depend on the lookahead */
+        yyn = -yydefred[yystate];
+        goto yyreduce;
+    }
+    if (yychar < 0) {
+        yychar = yylex();
+        if (yychar < 0)
+            yychar = 0;
+#if YYDEBUG
+        if (yydebug)
+            fprintf(stderr, "yydebug: state %d, reading %d (%s)\n",
+                    yystate, yychar, yyname[YYTRANSLATE(yychar)]);
+#endif
+    }
+    if (!yyfindaction(yystate, YYTRANSLATE(yychar), &yyn)) {
+        if (yydefred[yystate] == 0)
+            goto yyerrlab;
+        yyn = -yydefred[yystate];
+    }
+    if (yyn == 0)
+        goto yyerrlab;
+    if (yyn == YYACCEPTACT)
+        goto yyaccept;
+    if (yyn < 0)
+        goto yyreduce;
+
+#if YYDEBUG
+    if (yydebug)
+        fprintf(stderr, "yydebug: state %d, shifting to state %d\n", yystate, yyn);
+#endif
+    if (yyssp >= yyss + YYMAXDEPTH - 1)
+        goto yyoverflow;
+    *++yyssp = yystate = yyn;
+    *++yyvsp = yylval;
+    yychar = YYEMPTY;
+    if (yyerrflag > 0)
+        --yyerrflag;
+    goto yyloop;
+
+yyreduce:
+    yyrule = -yyn;
+    yylen = yyr2[yyrule];
+#if YYDEBUG
+    if (yydebug)
+        fprintf(stderr, "yydebug: state %d, reducing by rule %d (%s)\n",
+                yystate, yyrule, yyrules[yyrule]);
+#endif
+    if (yylen)
+        yyval = yyvsp[1 - yylen];
+    else
+        memset(&yyval, 0, sizeof(yyval));
+    switch (yyrule) {
+"#;
+
+const SKELETON_EPILOGUE: &str = r#"    default:
+        break;
+    }
+    yyssp -= yylen;
+    yyvsp -= yylen;
+    yystate = yyfindgoto(*yyssp, yyr1[yyrule]);
+    if (yyssp >= yyss + YYMAXDEPTH - 1)
+        goto yyoverflow;
+    *++yyssp = yystate;
+    *++yyvsp = yyval;
+    goto yyloop;
+
+yyerrlab:
+    if (yyerrflag == 0) {
+        yyerror("syntax error");
+        ++yynerrs;
+    }
+    goto yyerrlab1;
+
+yyerrorlab:
+    /* YYERROR: discard the right-hand side of the rule being reduced */
+    yyssp -= yylen;
+    yyvsp -= yylen;
+    yystate = *yyssp;
+
+yyerrlab1:
+    if (yyerrflag < 3) {
+        yyerrflag = 3;
+        for (;;) {
+            if (yyfindaction(*yyssp, YYERRTOK, &yyn) && yyn > 0 && yyn != YYACCEPTACT) {
+#if YYDEBUG
+                if (yydebug)
+                    fprintf(stderr, "yydebug: state %d, error recovery shifting to state %d\n",
+                            *yyssp, yyn);
+#endif
+                if (yyssp >= yyss + YYMAXDEPTH - 1)
+                    goto yyoverflow;
+                *++yyssp = yystate = yyn;
+                *++yyvsp = yylval;
+                goto yyloop;
+            }
+            if (yyssp <= yyss)
+                goto yyabort;
+            --yyssp;
+            --yyvsp;
+        }
+    }
+    /* no shift yet since the last error: discard the lookahead */
+    if (yychar == 0)
+        goto yyabort;
+#if YYDEBUG
+    if (yydebug)
+        fprintf(stderr, "yydebug: state %d, error recovery discards token %d\n",
+                yystate, yychar);
+#endif
+    yychar = YYEMPTY;
+    goto yyloop;
+
+yyoverflow:
+    yyerror("yacc stack overflow");
+    return 2;
+
+yyabort:
+    return 1;
+
+yyaccept:
+    return 0;
+}
+"#;
+
+/// Writes the code file (`y.tab.c`).
+pub fn code_file(
+    grammar: &Grammar,
+    automaton: &Automaton,
+    opts: &Options,
+) -> Result<String, GrammarError> {
+    let mut w = CodeWriter::new();
+    let nterms = grammar.num_terminals;
+
+    w.push("/* generated by posixutils yacc */\n");
+    w.push(&prefix_definitions(opts));
+    w.push("#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n");
+    if opts.debug {
+        w.push("#ifndef YYDEBUG\n#define YYDEBUG 1\n#endif\n");
+    } else {
+        w.push("#ifndef YYDEBUG\n#define YYDEBUG 0\n#endif\n");
+    }
+    w.push("#ifndef YYERRCODE\n#define YYERRCODE 256\n#endif\n");
+    w.push(&token_definitions(grammar));
+
+    for decl in &grammar.declarations {
+        match decl {
+            Declaration::Code(block) => w.user_code(opts, block),
+            Declaration::Union(block) => w.push(&union_definition(block)),
+        }
+    }
+    w.push(DEFAULT_STYPE);
+    w.push(SKELETON_PROLOGUE);
+
+    // Tables
+    let max_token = grammar.symbols[..nterms]
+        .iter()
+        .map(|s| s.value)
+        .max()
+        .unwrap_or(0);
+    w.push("\n");
+    w.push(&format!("#define YYMAXTOKEN {}\n", max_token));
+    w.push(&format!("#define YYNSTATES {}\n", automaton.states.len()));
+    w.push("#define YYACCEPTACT YYNSTATES\n");
+    w.push(&format!("#define YYERRTOK {}\n", ERROR_SYMBOL));
+    w.push(&format!("#define YYUNDEFTOK {}\n", UNDEFINED_SYMBOL));
+
+    let mut translate = vec![UNDEFINED_SYMBOL as i64; max_token as usize + 1];
+    for (i, sym) in grammar.symbols[..nterms].iter().enumerate() {
+        if sym.value >= 0 {
+            translate[sym.value as usize] = i as i64;
+        }
+    }
+    write_table(&mut w, "short", "yytranslate", &translate);
+
+    let mut act_base = Vec::new();
+    let mut act_tok = Vec::new();
+    let mut act_val = Vec::new();
+    let mut defred = Vec::new();
+    for state in &automaton.states {
+        act_base.push(act_tok.len() as i64);
+        for &(tok, action) in &state.actions {
+            act_tok.push(tok as i64);
+            act_val.push(match action {
+                ParseAction::Shift(s) => s as i64,
+                ParseAction::Reduce(r) => -(r as i64),
+                ParseAction::Accept => automaton.states.len() as i64,
+                ParseAction::Error => 0,
+            });
+        }
+        defred.push(state.default_reduce.unwrap_or(0) as i64);
+    }
+    act_base.push(act_tok.len() as i64);
+    write_table(&mut w, "int", "yyactbase", &act_base);
+    write_table(&mut w, "short", "yyacttok", &act_tok);
+    write_table(&mut w, "int", "yyactval", &act_val);
+    write_table(&mut w, "int", "yydefred", &defred);
+
+    // Gotos grouped by nonterminal; the most common target is the default.
+    let nnonterms = grammar.num_nonterminals();
+    let mut goto_base = Vec::new();
+    let mut goto_from = Vec::new();
+    let mut goto_to = Vec::new();
+    let mut goto_def = Vec::new();
+    for nt in 0..nnonterms {
+        let sym = nterms + nt;
+        let entries: Vec<(usize, usize)> = automaton
+            .states
+            .iter()
+            .enumerate()
+            .filter_map(|(s, state)| {
+                state
+                    .gotos
+                    .iter()
+                    .find(|(x, _)| *x == sym)
+                    .map(|(_, t)| (s, *t))
+            })
+            .collect();
+        let mut counts = std::collections::BTreeMap::new();
+        for (_, t) in &entries {
+            *counts.entry(*t).or_insert(0) += 1;
+        }
+        let default = counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(&t, _)| t)
+            .unwrap_or(0);
+        goto_base.push(goto_from.len() as i64);
+        for (from, to) in entries {
+            if to != default {
+                goto_from.push(from as i64);
+                goto_to.push(to as i64);
+            }
+        }
+        goto_def.push(default as i64);
+    }
+    goto_base.push(goto_from.len() as i64);
+    write_table(&mut w, "int", "yygotobase", &goto_base);
+    write_table(&mut w, "int", "yygotofrom", &goto_from);
+    write_table(&mut w, "int", "yygototo", &goto_to);
+    write_table(&mut w, "int", "yygotodef", &goto_def);
+
+    let r1: Vec<i64> = grammar
+        .rules
+        .iter()
+        .map(|r| (r.lhs - nterms) as i64)
+        .collect();
+    let r2: Vec<i64> = grammar.rules.iter().map(|r| r.rhs.len() as i64).collect();
+    write_table(&mut w, "short", "yyr1", &r1);
+    write_table(&mut w, "short", "yyr2", &r2);
+
+    w.push("#if YYDEBUG\nstatic const char *const yyname[] = {\n");
+    for sym in &grammar.symbols[..nterms] {
+        w.push(&format!("    \"{}\",\n", escape_c_string(&sym.name)));
+    }
+    w.push("};\nstatic const char *const yyrules[] = {\n");
+    for rule in 0..grammar.rules.len() {
+        w.push(&format!(
+            "    \"{}\",\n",
+            escape_c_string(&rule_string(grammar, rule))
+        ));
+    }
+    w.push("};\n#endif\n");
+
+    w.push(SKELETON_PARSER);
+    for (i, rule) in grammar.rules.iter().enumerate() {
+        if let Some(action) = &rule.action {
+            let code = translate_action(grammar, i, action)?;
+            w.push(&format!("    case {}:\n", i));
+            w.line_directive(opts, action.code.line, opts.grammar_file);
+            w.push(&code);
+            w.push("\n");
+            w.restore_line(opts);
+            w.push("        break;\n");
+        }
+    }
+    w.push(SKELETON_EPILOGUE);
+
+    if let Some(epilogue) = &grammar.epilogue {
+        w.user_code(opts, epilogue);
+    }
+    Ok(w.text)
+}
+
+fn action_name(action: ParseAction) -> String {
+    match action {
+        ParseAction::Shift(s) => format!("shift {}", s),
+        ParseAction::Reduce(r) => format!("reduce {}", r),
+        ParseAction::Accept => "accept".to_string(),
+        ParseAction::Error => "error".to_string(),
+    }
+}
+
+/// Writes the description file (`y.output`) produced by `-v`.
+pub fn description_file(grammar: &Grammar, automaton: &Automaton) -> String {
+    let mut s = String::new();
+    for i in 0..grammar.rules.len() {
+        writeln!(s, "{:4}  {}", i, rule_string(grammar, i)).unwrap();
+    }
+    s.push('\n');
+
+    for (n, state) in automaton.states.iter().enumerate() {
+        for conflict in &automaton.conflicts {
+            match *conflict {
+                Conflict::ShiftReduce {
+                    state,
+                    token,
+                    shift,
+                    rule,
+                } if state == n => writeln!(
+                    s,
+                    "{}: shift/reduce conflict (shift {}, reduce {}) on {}",
+                    n, shift, rule, grammar.symbols[token].name
+                )
+                .unwrap(),
+                Conflict::ReduceReduce {
+                    state,
+                    token,
+                    kept,
+                    dropped,
+                } if state == n => writeln!(
+                    s,
+                    "{}: reduce/reduce conflict (reduce {}, reduce {}) on {}",
+                    n, kept, dropped, grammar.symbols[token].name
+                )
+                .unwrap(),
+                _ => {}
+            }
+        }
+
+        writeln!(s, "state {}", n).unwrap();
+        for &(rule, dot) in &state.kernel {
+            let r = &grammar.rules[rule];
+            let mut item = format!("\t{} :", symbol_display_name(grammar, r.lhs));
+            for (i, &sym) in r.rhs.iter().enumerate() {
+                if i == dot {
+                    item.push_str(" .");
+                }
+                item.push(' ');
+                item.push_str(symbol_display_name(grammar, sym));
+            }
+            if dot == r.rhs.len() {
+                item.push_str(" .");
+            }
+            writeln!(s, "{}  ({})", item, rule).unwrap();
+        }
+        s.push('\n');
+
+        for &(tok, action) in &state.actions {
+            writeln!(
+                s,
+                "\t{}  {}",
+                grammar.symbols[tok].name,
+                action_name(action)
+            )
+            .unwrap();
+        }
+        match state.default_reduce {
+            Some(rule) => writeln!(s, "\t.  reduce {}", rule).unwrap(),
+            None => writeln!(s, "\t.  error").unwrap(),
+        }
+        if !state.gotos.is_empty() {
+            s.push('\n');
+            for &(sym, target) in &state.gotos {
+                writeln!(s, "\t{}  goto {}", grammar.symbols[sym].name, target).unwrap();
+            }
+        }
+        s.push('\n');
+    }
+
+    let unused: Vec<usize> = (0..grammar.rules.len())
+        .filter(|&r| !automaton.rule_used[r])
+        .collect();
+    if !unused.is_empty() {
+        s.push_str("Rules never reduced:\n");
+        for r in unused {
+            writeln!(s, "\t{}  ({})", rule_string(grammar, r), r).unwrap();
+        }
+        s.push('\n');
+    }
+
+    writeln!(
+        s,
+        "{} terminals, {} nonterminals",
+        grammar.num_terminals,
+        grammar.num_nonterminals()
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "{} grammar rules, {} states",
+        grammar.rules.len(),
+        automaton.states.len()
+    )
+    .unwrap();
+    s
+}
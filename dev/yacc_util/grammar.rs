@@ -0,0 +1,1052 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Reader for the yacc grammar file format:
+//!
+//! ```text
+//! declarations
+//! %%
+//! rules
+//! %%
+//! programs
+//! ```
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Token number of the predefined `error` token.
+pub const ERROR_TOKEN_VALUE: i32 = 256;
+
+/// Index of the end-of-input terminal.
+pub const END_SYMBOL: usize = 0;
+
+/// Index of the `error` terminal.
+pub const ERROR_SYMBOL: usize = 1;
+
+/// Index of the terminal that unknown token numbers are translated to.
+pub const UNDEFINED_SYMBOL: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+    Nonassoc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Precedence {
+    pub level: usize,
+    pub assoc: Assoc,
+}
+
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub tag: Option<String>,
+    /// Precedence and associativity, for terminals only.
+    pub prec: Option<Precedence>,
+    /// Token number, for terminals only.
+    pub value: i32,
+    /// True for character literals such as `'+'`.
+    pub literal: bool,
+}
+
+/// A block of C code copied from the grammar file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    pub text: String,
+    pub line: usize,
+}
+
+#[derive(Debug)]
+pub struct Action {
+    pub code: CodeBlock,
+    /// Symbols of the enclosing rule that the action can refer to as
+    /// `$1` .. `$n`.
+    pub visible: Vec<usize>,
+    /// True if the action was written in the middle of a rule, and was
+    /// moved into a rule of its own.
+    pub mid_rule: bool,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub lhs: usize,
+    pub rhs: Vec<usize>,
+    pub prec: Option<Precedence>,
+    pub action: Option<Action>,
+}
+
+/// Code from the declarations section, in the order it was written.
+#[derive(Debug)]
+pub enum Declaration {
+    Code(CodeBlock),
+    Union(CodeBlock),
+}
+
+#[derive(Debug)]
+pub struct Grammar {
+    /// Terminals first, then nonterminals.
+    pub symbols: Vec<Symbol>,
+    pub num_terminals: usize,
+    /// `rules[0]` is the augmented rule `$accept : start $end`.
+    pub rules: Vec<Rule>,
+    pub declarations: Vec<Declaration>,
+    pub epilogue: Option<CodeBlock>,
+}
+
+impl Grammar {
+    pub fn is_terminal(&self, sym: usize) -> bool {
+        sym < self.num_terminals
+    }
+
+    pub fn num_nonterminals(&self) -> usize {
+        self.symbols.len() - self.num_terminals
+    }
+
+    pub fn has_union(&self) -> bool {
+        self.declarations
+            .iter()
+            .any(|d| matches!(d, Declaration::Union(_)))
+    }
+}
+
+#[derive(Debug)]
+pub struct GrammarError {
+    pub line: usize,
+    pub msg: String,
+}
+
+type ParseResult<T> = Result<T, GrammarError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// An identifier immediately followed by a colon.
+    CIdent(String),
+    Literal(char),
+    Number(i32),
+    Tag(String),
+    Mark,
+    LCurl,
+    Directive(String),
+    Action(CodeBlock),
+    Colon,
+    Semicolon,
+    Bar,
+    Comma,
+    Eof,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    peeked: Option<Token>,
+}
+
+impl Lexer {
+    fn new(source: &str) -> Self {
+        Lexer {
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            peeked: None,
+        }
+    }
+
+    fn error<T>(&self, msg: impl Into<String>) -> ParseResult<T> {
+        Err(GrammarError {
+            line: self.line,
+            msg: msg.into(),
+        })
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_char_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied();
+        if let Some(c) = c {
+            self.pos += 1;
+            if c == '\n' {
+                self.line += 1;
+            }
+        }
+        c
+    }
+
+    /// Skips whitespace and C comments.
+    fn skip_blanks(&mut self) -> ParseResult<()> {
+        loop {
+            match self.peek_char() {
+                Some(c) if c.is_whitespace() => {
+                    self.next_char();
+                }
+                Some('/') if self.peek_char_at(1) == Some('*') => {
+                    let start_line = self.line;
+                    self.pos += 2;
+                    loop {
+                        match self.next_char() {
+                            Some('*') if self.peek_char() == Some('/') => {
+                                self.next_char();
+                                break;
+                            }
+                            Some(_) => {}
+                            None => {
+                                return Err(GrammarError {
+                                    line: start_line,
+                                    msg: "unterminated comment".to_string(),
+                                })
+                            }
+                        }
+                    }
+                }
+                Some('/') if self.peek_char_at(1) == Some('/') => {
+                    while let Some(c) = self.next_char() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn is_ident_start(c: char) -> bool {
+        c.is_ascii_alphabetic() || c == '_' || c == '.'
+    }
+
+    fn is_ident_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_' || c == '.'
+    }
+
+    fn read_ident(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek_char() {
+            if !Self::is_ident_char(c) {
+                break;
+            }
+            s.push(c);
+            self.next_char();
+        }
+        s
+    }
+
+    fn read_escape(&mut self) -> ParseResult<char> {
+        let c = match self.next_char() {
+            Some(c) => c,
+            None => return self.error("unexpected end of file in literal"),
+        };
+        let value = match c {
+            'n' => '\n',
+            't' => '\t',
+            'v' => '\u{0b}',
+            'b' => '\u{08}',
+            'r' => '\r',
+            'f' => '\u{0c}',
+            'a' => '\u{07}',
+            '0'..='7' => {
+                let mut value = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match self.peek_char().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            value = value * 8 + d;
+                            self.next_char();
+                        }
+                        None => break,
+                    }
+                }
+                match char::from_u32(value) {
+                    Some(c) => c,
+                    None => return self.error("invalid octal escape"),
+                }
+            }
+            'x' => {
+                let mut value = 0;
+                let mut digits = 0;
+                while let Some(d) = self.peek_char().and_then(|c| c.to_digit(16)) {
+                    value = value * 16 + d;
+                    digits += 1;
+                    self.next_char();
+                }
+                match char::from_u32(value) {
+                    Some(c) if digits > 0 => c,
+                    _ => return self.error("invalid hexadecimal escape"),
+                }
+            }
+            other => other,
+        };
+        Ok(value)
+    }
+
+    fn read_literal(&mut self) -> ParseResult<Token> {
+        let quote = self.next_char().unwrap();
+        let c = match self.next_char() {
+            Some('\\') => self.read_escape()?,
+            Some('\n') | None => return self.error("unterminated literal"),
+            Some(c) => c,
+        };
+        if self.next_char() != Some(quote) {
+            return self.error("multi-character literals are not supported");
+        }
+        if c == '\0' {
+            return self.error("illegal NUL character literal");
+        }
+        Ok(Token::Literal(c))
+    }
+
+    /// Reads a C code fragment between balanced braces, starting at the
+    /// opening brace.  Braces inside strings, character constants and
+    /// comments are not counted.
+    fn read_action(&mut self) -> ParseResult<CodeBlock> {
+        let line = self.line;
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            let c = match self.next_char() {
+                Some(c) => c,
+                None => {
+                    return Err(GrammarError {
+                        line,
+                        msg: "unterminated action".to_string(),
+                    })
+                }
+            };
+            text.push(c);
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(CodeBlock { text, line });
+                    }
+                }
+                '"' | '\'' => self.copy_quoted(c, &mut text)?,
+                '/' if self.peek_char() == Some('*') => {
+                    text.push(self.next_char().unwrap());
+                    loop {
+                        match self.next_char() {
+                            Some('*') if self.peek_char() == Some('/') => {
+                                text.push('*');
+                                text.push(self.next_char().unwrap());
+                                break;
+                            }
+                            Some(c) => text.push(c),
+                            None => return self.error("unterminated comment in action"),
+                        }
+                    }
+                }
+                '/' if self.peek_char() == Some('/') => {
+                    while let Some(c) = self.peek_char() {
+                        if c == '\n' {
+                            break;
+                        }
+                        text.push(c);
+                        self.next_char();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn copy_quoted(&mut self, quote: char, text: &mut String) -> ParseResult<()> {
+        loop {
+            match self.next_char() {
+                Some('\\') => {
+                    text.push('\\');
+                    if let Some(c) = self.next_char() {
+                        text.push(c);
+                    }
+                }
+                Some(c) if c == quote => {
+                    text.push(c);
+                    return Ok(());
+                }
+                Some('\n') | None => return self.error("unterminated string in action"),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    /// Reads the body of a `%{ ... %}` block; the `%{` has been consumed.
+    fn read_code_block(&mut self) -> ParseResult<CodeBlock> {
+        // The text starts on the line following `%{` when it is alone on
+        // its line, which keeps `#line` directives accurate.
+        let mut line = self.line;
+        if self.peek_char() == Some('\n') {
+            self.next_char();
+            line = self.line;
+        }
+        let mut text = String::new();
+        loop {
+            match self.peek_char() {
+                Some('%') if self.peek_char_at(1) == Some('}') => {
+                    self.pos += 2;
+                    return Ok(CodeBlock { text, line });
+                }
+                Some(_) => text.push(self.next_char().unwrap()),
+                None => {
+                    return Err(GrammarError {
+                        line,
+                        msg: "unterminated %{ block".to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    fn peek(&mut self) -> ParseResult<Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(self.peeked.clone().unwrap())
+    }
+
+    fn next(&mut self) -> ParseResult<Token> {
+        match self.peeked.take() {
+            Some(tok) => Ok(tok),
+            None => self.lex(),
+        }
+    }
+
+    fn lex(&mut self) -> ParseResult<Token> {
+        self.skip_blanks()?;
+        let c = match self.peek_char() {
+            Some(c) => c,
+            None => return Ok(Token::Eof),
+        };
+        match c {
+            '%' => {
+                self.next_char();
+                match self.peek_char() {
+                    Some('%') => {
+                        self.next_char();
+                        Ok(Token::Mark)
+                    }
+                    Some('{') => {
+                        self.next_char();
+                        Ok(Token::LCurl)
+                    }
+                    Some('<') => {
+                        self.next_char();
+                        Ok(Token::Directive("left".to_string()))
+                    }
+                    Some('>') => {
+                        self.next_char();
+                        Ok(Token::Directive("right".to_string()))
+                    }
+                    Some('2') => {
+                        self.next_char();
+                        Ok(Token::Directive("nonassoc".to_string()))
+                    }
+                    Some('0') => {
+                        self.next_char();
+                        Ok(Token::Directive("token".to_string()))
+                    }
+                    Some('=') => {
+                        self.next_char();
+                        Ok(Token::Directive("prec".to_string()))
+                    }
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        let name = self.read_ident();
+                        Ok(Token::Directive(name))
+                    }
+                    _ => self.error("unexpected character after '%'"),
+                }
+            }
+            '<' => {
+                self.next_char();
+                let mut tag = String::new();
+                loop {
+                    match self.next_char() {
+                        Some('>') => break,
+                        Some('\n') | None => return self.error("unterminated <tag>"),
+                        Some(c) => tag.push(c),
+                    }
+                }
+                let tag = tag.trim().to_string();
+                if tag.is_empty() {
+                    return self.error("empty <tag>");
+                }
+                Ok(Token::Tag(tag))
+            }
+            '\'' | '"' => self.read_literal(),
+            '{' => Ok(Token::Action(self.read_action()?)),
+            '=' if self.peek_char_at(1) == Some('{') => {
+                // historical "= { action }" form
+                self.next_char();
+                Ok(Token::Action(self.read_action()?))
+            }
+            ':' => {
+                self.next_char();
+                Ok(Token::Colon)
+            }
+            ';' => {
+                self.next_char();
+                Ok(Token::Semicolon)
+            }
+            '|' => {
+                self.next_char();
+                Ok(Token::Bar)
+            }
+            ',' => {
+                self.next_char();
+                Ok(Token::Comma)
+            }
+            c if c.is_ascii_digit() => {
+                let mut value: i32 = 0;
+                while let Some(d) = self.peek_char().and_then(|c| c.to_digit(10)) {
+                    value = match value.checked_mul(10).and_then(|v| v.checked_add(d as i32)) {
+                        Some(v) => v,
+                        None => return self.error("number too large"),
+                    };
+                    self.next_char();
+                }
+                Ok(Token::Number(value))
+            }
+            c if Self::is_ident_start(c) => {
+                let name = self.read_ident();
+                // An identifier followed by a colon starts a new rule.
+                let save = (self.pos, self.line);
+                self.skip_blanks()?;
+                if self.peek_char() == Some(':') {
+                    self.next_char();
+                    Ok(Token::CIdent(name))
+                } else {
+                    (self.pos, self.line) = save;
+                    Ok(Token::Ident(name))
+                }
+            }
+            c => self.error(format!("unexpected character '{}'", c)),
+        }
+    }
+
+    /// Returns the remainder of the input after the second `%%`.
+    fn rest(&mut self) -> CodeBlock {
+        if self.peek_char() == Some('\n') {
+            self.next_char();
+        }
+        let line = self.line;
+        let text: String = self.chars[self.pos..].iter().collect();
+        self.pos = self.chars.len();
+        CodeBlock { text, line }
+    }
+}
+
+/// A symbol reference in a rule body before symbols are resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SymRef {
+    Name(String),
+    Literal(char),
+}
+
+impl SymRef {
+    fn from_token(tok: &Token) -> Option<SymRef> {
+        match tok {
+            Token::Ident(name) => Some(SymRef::Name(name.clone())),
+            Token::Literal(c) => Some(SymRef::Literal(*c)),
+            _ => None,
+        }
+    }
+
+    fn display_name(&self) -> String {
+        match self {
+            SymRef::Name(name) => name.clone(),
+            SymRef::Literal(c) => literal_name(*c),
+        }
+    }
+}
+
+/// Returns the name used for a character literal in diagnostics and in
+/// the symbol name table.
+fn literal_name(c: char) -> String {
+    match c {
+        '\n' => "'\\n'".to_string(),
+        '\t' => "'\\t'".to_string(),
+        '\\' => "'\\\\'".to_string(),
+        '\'' => "'\\''".to_string(),
+        c if (c as u32) < 0x20 || c as u32 == 0x7f => format!("'\\{:03o}'", c as u32),
+        c => format!("'{}'", c),
+    }
+}
+
+struct RawAlternative {
+    lhs: String,
+    rhs: Vec<SymRef>,
+    prec: Option<SymRef>,
+    action: Option<CodeBlock>,
+    /// Actions written before the end of the rule, with the number of
+    /// symbols preceding each one.
+    mid_actions: Vec<(usize, CodeBlock)>,
+    line: usize,
+}
+
+struct TokenDecl {
+    sym: SymRef,
+    value: Option<i32>,
+}
+
+#[derive(Default)]
+struct RawGrammar {
+    tokens: Vec<TokenDecl>,
+    tags: HashMap<SymRef, String>,
+    precs: HashMap<SymRef, Precedence>,
+    start: Option<(String, usize)>,
+    declarations: Vec<Declaration>,
+    rules: Vec<RawAlternative>,
+    epilogue: Option<CodeBlock>,
+}
+
+impl RawGrammar {
+    fn declare_token(&mut self, sym: SymRef, value: Option<i32>) {
+        match self.tokens.iter_mut().find(|t| t.sym == sym) {
+            Some(decl) => {
+                if value.is_some() {
+                    decl.value = value;
+                }
+            }
+            None => self.tokens.push(TokenDecl { sym, value }),
+        }
+    }
+}
+
+fn parse_declarations(lex: &mut Lexer, raw: &mut RawGrammar) -> ParseResult<()> {
+    let mut prec_level = 0;
+    loop {
+        match lex.next()? {
+            Token::Mark => return Ok(()),
+            Token::Eof => return lex.error("unexpected end of file before %%"),
+            Token::LCurl => {
+                let block = lex.read_code_block()?;
+                raw.declarations.push(Declaration::Code(block));
+            }
+            Token::Directive(name) => match name.as_str() {
+                "token" | "term" | "left" | "right" | "nonassoc" | "binary" | "type" => {
+                    let prec = match name.as_str() {
+                        "left" => Some(Assoc::Left),
+                        "right" => Some(Assoc::Right),
+                        "nonassoc" | "binary" => Some(Assoc::Nonassoc),
+                        _ => None,
+                    };
+                    if prec.is_some() {
+                        prec_level += 1;
+                    }
+                    parse_symbol_list(lex, raw, &name, prec, prec_level)?;
+                }
+                "start" => match lex.next()? {
+                    Token::Ident(sym) => raw.start = Some((sym, lex.line)),
+                    _ => return lex.error("%start requires a nonterminal name"),
+                },
+                "union" => {
+                    lex.skip_blanks()?;
+                    if lex.peek_char() != Some('{') {
+                        return lex.error("%union requires a { ... } body");
+                    }
+                    if raw.has_union() {
+                        return lex.error("too many %union declarations");
+                    }
+                    let block = lex.read_action()?;
+                    raw.declarations.push(Declaration::Union(block));
+                }
+                _ => return lex.error(format!("unknown directive %{}", name)),
+            },
+            _ => return lex.error("syntax error in declarations section"),
+        }
+    }
+}
+
+impl RawGrammar {
+    fn has_union(&self) -> bool {
+        self.declarations
+            .iter()
+            .any(|d| matches!(d, Declaration::Union(_)))
+    }
+}
+
+/// Parses the operands of `%token`, `%left`, `%right`, `%nonassoc`
+/// and `%type`.
+fn parse_symbol_list(
+    lex: &mut Lexer,
+    raw: &mut RawGrammar,
+    directive: &str,
+    assoc: Option<Assoc>,
+    level: usize,
+) -> ParseResult<()> {
+    let mut tag = None;
+    if let Token::Tag(t) = lex.peek()? {
+        lex.next()?;
+        tag = Some(t);
+    }
+    if directive == "type" && tag.is_none() {
+        return lex.error("%type requires a <tag>");
+    }
+
+    loop {
+        let tok = lex.peek()?;
+        let sym = match SymRef::from_token(&tok) {
+            Some(sym) => sym,
+            None if tok == Token::Comma => {
+                lex.next()?;
+                continue;
+            }
+            None => return Ok(()),
+        };
+        lex.next()?;
+
+        let mut value = None;
+        if let Token::Number(n) = lex.peek()? {
+            if directive == "type" {
+                return lex.error("token numbers are not allowed in %type");
+            }
+            lex.next()?;
+            value = Some(n);
+        }
+
+        if let SymRef::Name(name) = &sym {
+            if name == "error" && value.is_some() {
+                return lex.error("the number of the error token cannot be changed");
+            }
+        }
+        if let Some(tag) = &tag {
+            raw.tags.insert(sym.clone(), tag.clone());
+        }
+        if let Some(assoc) = assoc {
+            if raw.precs.contains_key(&sym) {
+                return lex.error(format!("precedence of {} redeclared", sym.display_name()));
+            }
+            raw.precs.insert(sym.clone(), Precedence { level, assoc });
+        }
+        if directive != "type" {
+            raw.declare_token(sym, value);
+        }
+    }
+}
+
+fn parse_rules(lex: &mut Lexer, raw: &mut RawGrammar) -> ParseResult<()> {
+    let mut lhs = match lex.next()? {
+        Token::CIdent(name) => name,
+        _ => return lex.error("the rules section must start with a rule"),
+    };
+
+    loop {
+        let mut alt = RawAlternative {
+            lhs: lhs.clone(),
+            rhs: Vec::new(),
+            prec: None,
+            action: None,
+            mid_actions: Vec::new(),
+            line: lex.line,
+        };
+
+        // Read one alternative, up to '|', ';', the next rule or the end.
+        let next = loop {
+            let tok = lex.next()?;
+            match tok {
+                Token::Ident(_) | Token::Literal(_) => {
+                    if alt.prec.is_some() {
+                        return lex.error("%prec must appear at the end of a rule");
+                    }
+                    if let Some(action) = alt.action.take() {
+                        alt.mid_actions.push((alt.rhs.len(), action));
+                    }
+                    alt.rhs.push(SymRef::from_token(&tok).unwrap());
+                }
+                Token::Action(code) => {
+                    if let Some(action) = alt.action.take() {
+                        alt.mid_actions.push((alt.rhs.len(), action));
+                    }
+                    alt.action = Some(code);
+                }
+                Token::Directive(name) if name == "prec" => {
+                    let tok = lex.next()?;
+                    match SymRef::from_token(&tok) {
+                        Some(sym) => alt.prec = Some(sym),
+                        None => return lex.error("%prec requires a token name"),
+                    }
+                }
+                Token::Bar | Token::Semicolon | Token::CIdent(_) | Token::Mark | Token::Eof => {
+                    break tok;
+                }
+                _ => return lex.error("syntax error in rules section"),
+            }
+        };
+
+        raw.rules.push(alt);
+
+        let mut next = next;
+        while next == Token::Semicolon {
+            next = lex.next()?;
+        }
+        match next {
+            Token::Bar => {}
+            Token::CIdent(name) => lhs = name,
+            Token::Mark => {
+                raw.epilogue = Some(lex.rest());
+                return Ok(());
+            }
+            Token::Eof => return Ok(()),
+            _ => return lex.error("syntax error in rules section"),
+        }
+    }
+}
+
+/// Assigns symbol numbers and builds the final rule list.
+fn resolve(raw: RawGrammar) -> ParseResult<Grammar> {
+    let mut symbols = vec![
+        Symbol {
+            name: "$end".to_string(),
+            tag: None,
+            prec: None,
+            value: 0,
+            literal: false,
+        },
+        Symbol {
+            name: "error".to_string(),
+            tag: None,
+            prec: None,
+            value: ERROR_TOKEN_VALUE,
+            literal: false,
+        },
+        Symbol {
+            name: "$undefined".to_string(),
+            tag: None,
+            prec: None,
+            value: -1,
+            literal: false,
+        },
+    ];
+    let mut index: HashMap<SymRef, usize> = HashMap::new();
+    index.insert(SymRef::Name("error".to_string()), ERROR_SYMBOL);
+
+    let lhs_names: Vec<&String> = raw.rules.iter().map(|r| &r.lhs).collect();
+
+    // Declared tokens, then literals that only appear in rules.
+    let mut next_value = ERROR_TOKEN_VALUE + 1;
+    let mut add_terminal = |sym: &SymRef, value: Option<i32>, symbols: &mut Vec<Symbol>| {
+        let (name, literal, default_value) = match sym {
+            SymRef::Name(name) => (name.clone(), false, None),
+            SymRef::Literal(c) => (literal_name(*c), true, Some(*c as i32)),
+        };
+        let value = match value.or(default_value) {
+            Some(v) => v,
+            None => {
+                let v = next_value;
+                next_value += 1;
+                v
+            }
+        };
+        symbols.push(Symbol {
+            name,
+            tag: raw.tags.get(sym).cloned(),
+            prec: raw.precs.get(sym).copied(),
+            value,
+            literal,
+        });
+        symbols.len() - 1
+    };
+
+    for decl in &raw.tokens {
+        if let SymRef::Name(name) = &decl.sym {
+            if name == "error" {
+                let error = &mut symbols[ERROR_SYMBOL];
+                error.tag = raw.tags.get(&decl.sym).cloned();
+                error.prec = raw.precs.get(&decl.sym).copied();
+                continue;
+            }
+            if lhs_names.contains(&name) {
+                let line = raw.rules.iter().find(|r| &r.lhs == name).unwrap().line;
+                return Err(GrammarError {
+                    line,
+                    msg: format!("token {} cannot appear on the left side of a rule", name),
+                });
+            }
+        }
+        let idx = add_terminal(&decl.sym, decl.value, &mut symbols);
+        index.insert(decl.sym.clone(), idx);
+    }
+    for rule in &raw.rules {
+        for sym in rule.rhs.iter().chain(rule.prec.iter()) {
+            if let SymRef::Literal(_) = sym {
+                if !index.contains_key(sym) {
+                    let idx = add_terminal(sym, None, &mut symbols);
+                    index.insert(sym.clone(), idx);
+                }
+            }
+        }
+    }
+
+    let num_terminals = symbols.len();
+    for i in 0..num_terminals {
+        for j in 0..i {
+            if symbols[i].value == symbols[j].value && symbols[i].value >= 0 {
+                return Err(GrammarError {
+                    line: 1,
+                    msg: format!(
+                        "tokens {} and {} have the same number {}",
+                        symbols[j].name, symbols[i].name, symbols[i].value
+                    ),
+                });
+            }
+        }
+    }
+
+    // Nonterminals: $accept, then each left-hand side in order.
+    symbols.push(Symbol {
+        name: "$accept".to_string(),
+        tag: None,
+        prec: None,
+        value: -1,
+        literal: false,
+    });
+    for name in &lhs_names {
+        let sym = SymRef::Name((*name).clone());
+        if let Entry::Vacant(entry) = index.entry(sym) {
+            symbols.push(Symbol {
+                name: (*name).clone(),
+                tag: raw.tags.get(entry.key()).cloned(),
+                prec: None,
+                value: -1,
+                literal: false,
+            });
+            entry.insert(symbols.len() - 1);
+        }
+    }
+
+    if raw.rules.is_empty() {
+        return Err(GrammarError {
+            line: 1,
+            msg: "no grammar rules".to_string(),
+        });
+    }
+
+    let start = match &raw.start {
+        Some((name, line)) => match index.get(&SymRef::Name(name.clone())) {
+            Some(&idx) if idx >= num_terminals => idx,
+            _ => {
+                return Err(GrammarError {
+                    line: *line,
+                    msg: format!("start symbol {} is not a nonterminal", name),
+                })
+            }
+        },
+        None => index[&SymRef::Name(raw.rules[0].lhs.clone())],
+    };
+
+    let mut rules = vec![Rule {
+        lhs: num_terminals,
+        rhs: vec![start, END_SYMBOL],
+        prec: None,
+        action: None,
+    }];
+    let mut mid_rule_count = 0;
+
+    for alt in raw.rules {
+        let lhs = index[&SymRef::Name(alt.lhs.clone())];
+        let mut rhs = Vec::new();
+        for sym in &alt.rhs {
+            match index.get(sym) {
+                Some(&idx) => rhs.push(idx),
+                None => {
+                    return Err(GrammarError {
+                        line: alt.line,
+                        msg: format!(
+                            "symbol {} is used, but is not defined as a token and has no rules",
+                            sym.display_name()
+                        ),
+                    })
+                }
+            }
+        }
+
+        // Split out mid-rule actions into empty rules of their own.
+        let mut full_rhs = Vec::new();
+        let mut mid_actions = alt.mid_actions.into_iter().peekable();
+        for pos in 0..=rhs.len() {
+            while let Some((_, code)) = mid_actions.next_if(|(p, _)| *p == pos) {
+                mid_rule_count += 1;
+                symbols.push(Symbol {
+                    name: format!("$${}", mid_rule_count),
+                    tag: None,
+                    prec: None,
+                    value: -1,
+                    literal: false,
+                });
+                let mid_sym = symbols.len() - 1;
+                rules.push(Rule {
+                    lhs: mid_sym,
+                    rhs: Vec::new(),
+                    prec: None,
+                    action: Some(Action {
+                        code,
+                        visible: full_rhs.clone(),
+                        mid_rule: true,
+                    }),
+                });
+                full_rhs.push(mid_sym);
+            }
+            if pos < rhs.len() {
+                full_rhs.push(rhs[pos]);
+            }
+        }
+
+        let prec = match &alt.prec {
+            Some(sym) => match index.get(sym) {
+                Some(&idx) if idx < num_terminals => symbols[idx].prec,
+                _ => {
+                    return Err(GrammarError {
+                        line: alt.line,
+                        msg: format!("%prec {} is not a token", sym.display_name()),
+                    })
+                }
+            },
+            None => full_rhs
+                .iter()
+                .rev()
+                .filter(|&&s| s < num_terminals)
+                .find_map(|&s| symbols[s].prec),
+        };
+
+        let action = alt.action.map(|code| Action {
+            code,
+            visible: full_rhs.clone(),
+            mid_rule: false,
+        });
+        rules.push(Rule {
+            lhs,
+            rhs: full_rhs,
+            prec,
+            action,
+        });
+    }
+
+    Ok(Grammar {
+        symbols,
+        num_terminals,
+        rules,
+        declarations: raw.declarations,
+        epilogue: raw.epilogue,
+    })
+}
+
+/// Parses the text of a grammar file.
+pub fn parse_grammar(source: &str) -> Result<Grammar, GrammarError> {
+    let mut lex = Lexer::new(source);
+    let mut raw = RawGrammar::default();
+    parse_declarations(&mut lex, &mut raw)?;
+    parse_rules(&mut lex, &mut raw)?;
+    resolve(raw)
+}
@@ -0,0 +1,476 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! LALR(1) parse table construction.
+//!
+//! The LR(0) automaton is built first; lookaheads are then computed by
+//! the spontaneous generation / propagation method (Aho, Sethi, Ullman,
+//! "Compilers", section 4.7).
+
+use super::grammar::{Assoc, Grammar, END_SYMBOL};
+use std::collections::{BTreeMap, HashMap};
+
+/// A small fixed-size bit set over terminal numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermSet {
+    bits: Vec<u64>,
+}
+
+impl TermSet {
+    pub fn new(size: usize) -> Self {
+        TermSet {
+            bits: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, n: usize) -> bool {
+        let (word, bit) = (n / 64, 1u64 << (n % 64));
+        let added = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+        added
+    }
+
+    /// Adds all members of `other`; returns true if anything was added.
+    pub fn union_with(&mut self, other: &TermSet) -> bool {
+        let mut changed = false;
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            let merged = *a | *b;
+            if merged != *a {
+                *a = merged;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(w, &word)| {
+            (0..64)
+                .filter(move |b| word & (1u64 << b) != 0)
+                .map(move |b| w * 64 + b)
+        })
+    }
+}
+
+/// An LR(0) item: a rule number and the position of the dot.
+pub type Item = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseAction {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+    /// Explicit error entry produced by `%nonassoc`.
+    Error,
+}
+
+#[derive(Debug)]
+pub enum Conflict {
+    ShiftReduce {
+        state: usize,
+        token: usize,
+        shift: usize,
+        rule: usize,
+    },
+    ReduceReduce {
+        state: usize,
+        token: usize,
+        kept: usize,
+        dropped: usize,
+    },
+}
+
+#[derive(Debug)]
+pub struct State {
+    pub kernel: Vec<Item>,
+    /// Explicit actions by terminal, in terminal order.
+    pub actions: Vec<(usize, ParseAction)>,
+    /// Rule reduced when no explicit action matches.
+    pub default_reduce: Option<usize>,
+    /// Transitions on nonterminals.
+    pub gotos: Vec<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub struct Automaton {
+    pub states: Vec<State>,
+    pub conflicts: Vec<Conflict>,
+    pub shift_reduce: usize,
+    pub reduce_reduce: usize,
+    /// For each rule, whether it is reduced in some state.
+    pub rule_used: Vec<bool>,
+}
+
+struct Analysis<'a> {
+    grammar: &'a Grammar,
+    nullable: Vec<bool>,
+    /// FIRST sets of nonterminals, indexed by symbol number.
+    first: Vec<TermSet>,
+    /// Rules for each nonterminal, indexed by symbol number.
+    rules_of: Vec<Vec<usize>>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(grammar: &'a Grammar) -> Self {
+        let nsyms = grammar.symbols.len();
+        let nterms = grammar.num_terminals;
+
+        let mut rules_of = vec![Vec::new(); nsyms];
+        for (i, rule) in grammar.rules.iter().enumerate() {
+            rules_of[rule.lhs].push(i);
+        }
+
+        let mut nullable = vec![false; nsyms];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                if !nullable[rule.lhs] && rule.rhs.iter().all(|&s| nullable[s]) {
+                    nullable[rule.lhs] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut first = vec![TermSet::new(nterms + 1); nsyms];
+        for (t, set) in first.iter_mut().enumerate().take(nterms) {
+            set.insert(t);
+        }
+        changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                for &sym in &rule.rhs {
+                    if sym != rule.lhs {
+                        let sym_first = first[sym].clone();
+                        if first[rule.lhs].union_with(&sym_first) {
+                            changed = true;
+                        }
+                    }
+                    if !nullable[sym] {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Analysis {
+            grammar,
+            nullable,
+            first,
+            rules_of,
+        }
+    }
+
+    /// FIRST of a symbol string, and whether the string is nullable.
+    fn first_of(&self, syms: &[usize]) -> (TermSet, bool) {
+        let mut set = TermSet::new(self.grammar.num_terminals + 1);
+        for &sym in syms {
+            set.union_with(&self.first[sym]);
+            if !self.nullable[sym] {
+                return (set, false);
+            }
+        }
+        (set, true)
+    }
+
+    fn next_symbol(&self, item: Item) -> Option<usize> {
+        self.grammar.rules[item.0].rhs.get(item.1).copied()
+    }
+
+    /// LR(0) closure of a kernel.
+    fn closure(&self, kernel: &[Item]) -> Vec<Item> {
+        let mut items = kernel.to_vec();
+        let mut added = vec![false; self.grammar.symbols.len()];
+        let mut i = 0;
+        while i < items.len() {
+            if let Some(sym) = self.next_symbol(items[i]) {
+                if !self.grammar.is_terminal(sym) && !added[sym] {
+                    added[sym] = true;
+                    for &r in &self.rules_of[sym] {
+                        items.push((r, 0));
+                    }
+                }
+            }
+            i += 1;
+        }
+        items
+    }
+
+    /// LR(1) closure of items with lookahead sets.
+    fn closure_lr1(&self, seeds: Vec<(Item, TermSet)>) -> Vec<(Item, TermSet)> {
+        let mut items = seeds;
+        let mut index: HashMap<Item, usize> = HashMap::new();
+        for (i, (item, _)) in items.iter().enumerate() {
+            index.insert(*item, i);
+        }
+        let mut work: Vec<usize> = (0..items.len()).collect();
+        while let Some(i) = work.pop() {
+            let ((rule, dot), _) = items[i];
+            let rhs = &self.grammar.rules[rule].rhs;
+            let sym = match rhs.get(dot) {
+                Some(&sym) if !self.grammar.is_terminal(sym) => sym,
+                _ => continue,
+            };
+            let (mut la, nullable) = self.first_of(&rhs[dot + 1..]);
+            if nullable {
+                la.union_with(&items[i].1);
+            }
+            for &r in &self.rules_of[sym] {
+                match index.get(&(r, 0)) {
+                    Some(&j) => {
+                        if items[j].1.union_with(&la) {
+                            work.push(j);
+                        }
+                    }
+                    None => {
+                        items.push(((r, 0), la.clone()));
+                        index.insert((r, 0), items.len() - 1);
+                        work.push(items.len() - 1);
+                    }
+                }
+            }
+        }
+        items
+    }
+}
+
+struct Lr0State {
+    kernel: Vec<Item>,
+    transitions: Vec<(usize, usize)>,
+}
+
+fn build_lr0(analysis: &Analysis) -> Vec<Lr0State> {
+    let mut states = vec![Lr0State {
+        kernel: vec![(0, 0)],
+        transitions: Vec::new(),
+    }];
+    let mut index: HashMap<Vec<Item>, usize> = HashMap::new();
+    index.insert(vec![(0, 0)], 0);
+
+    let mut i = 0;
+    while i < states.len() {
+        let mut moves: BTreeMap<usize, Vec<Item>> = BTreeMap::new();
+        for item in analysis.closure(&states[i].kernel) {
+            match analysis.next_symbol(item) {
+                // Shifting $end accepts; no state is needed for it.
+                Some(END_SYMBOL) | None => {}
+                Some(sym) => moves.entry(sym).or_default().push((item.0, item.1 + 1)),
+            }
+        }
+        for (sym, mut kernel) in moves {
+            kernel.sort_unstable();
+            let target = match index.get(&kernel) {
+                Some(&target) => target,
+                None => {
+                    states.push(Lr0State {
+                        kernel: kernel.clone(),
+                        transitions: Vec::new(),
+                    });
+                    index.insert(kernel, states.len() - 1);
+                    states.len() - 1
+                }
+            };
+            states[i].transitions.push((sym, target));
+        }
+        i += 1;
+    }
+    states
+}
+
+/// Computes the lookahead set of every kernel item.
+fn compute_lookaheads(analysis: &Analysis, states: &[Lr0State]) -> Vec<Vec<TermSet>> {
+    let nterms = analysis.grammar.num_terminals;
+    // Terminal number used as the "propagate" marker.
+    let marker = nterms;
+
+    let mut la: Vec<Vec<TermSet>> = states
+        .iter()
+        .map(|s| vec![TermSet::new(nterms + 1); s.kernel.len()])
+        .collect();
+    let mut propagate: Vec<((usize, usize), (usize, usize))> = Vec::new();
+
+    for (s, state) in states.iter().enumerate() {
+        for (k, &kitem) in state.kernel.iter().enumerate() {
+            let mut seed = TermSet::new(nterms + 1);
+            seed.insert(marker);
+            for ((rule, dot), set) in analysis.closure_lr1(vec![(kitem, seed)]) {
+                let sym = match analysis.grammar.rules[rule].rhs.get(dot) {
+                    Some(&sym) if sym != END_SYMBOL => sym,
+                    _ => continue,
+                };
+                let target = state
+                    .transitions
+                    .iter()
+                    .find(|(x, _)| *x == sym)
+                    .map(|(_, t)| *t)
+                    .unwrap();
+                let tk = states[target]
+                    .kernel
+                    .binary_search(&(rule, dot + 1))
+                    .unwrap();
+                for t in set.iter() {
+                    if t == marker {
+                        propagate.push(((s, k), (target, tk)));
+                    } else {
+                        la[target][tk].insert(t);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &((s, k), (t, tk)) in &propagate {
+            if s == t && k == tk {
+                continue;
+            }
+            let from = la[s][k].clone();
+            if la[t][tk].union_with(&from) {
+                changed = true;
+            }
+        }
+    }
+    la
+}
+
+/// Builds the LALR(1) parsing automaton for a grammar, resolving
+/// conflicts with the declared precedences.
+pub fn build_automaton(grammar: &Grammar) -> Automaton {
+    let analysis = Analysis::new(grammar);
+    let lr0 = build_lr0(&analysis);
+    let lookaheads = compute_lookaheads(&analysis, &lr0);
+
+    let nterms = grammar.num_terminals;
+    let mut conflicts = Vec::new();
+    let mut rule_used = vec![false; grammar.rules.len()];
+    let mut states = Vec::with_capacity(lr0.len());
+
+    for (s, lr0_state) in lr0.iter().enumerate() {
+        let mut actions: BTreeMap<usize, ParseAction> = BTreeMap::new();
+        let mut gotos = Vec::new();
+
+        for &(sym, target) in &lr0_state.transitions {
+            if grammar.is_terminal(sym) {
+                actions.insert(sym, ParseAction::Shift(target));
+            } else {
+                gotos.push((sym, target));
+            }
+        }
+        if lr0_state.kernel.contains(&(0, 1)) {
+            actions.insert(END_SYMBOL, ParseAction::Accept);
+        }
+
+        let seeds = lr0_state
+            .kernel
+            .iter()
+            .copied()
+            .zip(lookaheads[s].iter().cloned())
+            .collect();
+        let mut reductions: Vec<(usize, TermSet)> = analysis
+            .closure_lr1(seeds)
+            .into_iter()
+            .filter(|((rule, dot), _)| *dot == grammar.rules[*rule].rhs.len())
+            .map(|((rule, _), set)| (rule, set))
+            .collect();
+        reductions.sort_by_key(|(rule, _)| *rule);
+
+        for (rule, set) in reductions {
+            for t in set.iter().filter(|&t| t < nterms) {
+                let existing = match actions.get(&t) {
+                    None => {
+                        actions.insert(t, ParseAction::Reduce(rule));
+                        continue;
+                    }
+                    Some(&a) => a,
+                };
+                match existing {
+                    ParseAction::Shift(target) => {
+                        let rule_prec = grammar.rules[rule].prec;
+                        let token_prec = grammar.symbols[t].prec;
+                        match (rule_prec, token_prec) {
+                            (Some(rp), Some(tp)) => {
+                                if tp.level < rp.level
+                                    || (tp.level == rp.level && tp.assoc == Assoc::Left)
+                                {
+                                    actions.insert(t, ParseAction::Reduce(rule));
+                                } else if tp.level == rp.level && tp.assoc == Assoc::Nonassoc {
+                                    actions.insert(t, ParseAction::Error);
+                                }
+                            }
+                            _ => conflicts.push(Conflict::ShiftReduce {
+                                state: s,
+                                token: t,
+                                shift: target,
+                                rule,
+                            }),
+                        }
+                    }
+                    ParseAction::Accept => conflicts.push(Conflict::ShiftReduce {
+                        state: s,
+                        token: t,
+                        shift: 0,
+                        rule,
+                    }),
+                    ParseAction::Reduce(kept) => conflicts.push(Conflict::ReduceReduce {
+                        state: s,
+                        token: t,
+                        kept,
+                        dropped: rule,
+                    }),
+                    ParseAction::Error => {}
+                }
+            }
+        }
+
+        // The most frequent reduction becomes the default action.
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for action in actions.values() {
+            if let ParseAction::Reduce(rule) = action {
+                *counts.entry(*rule).or_default() += 1;
+            }
+        }
+        let default_reduce = counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(&rule, _)| rule);
+        for action in actions.values() {
+            if let ParseAction::Reduce(rule) = action {
+                rule_used[*rule] = true;
+            }
+        }
+        if let Some(rule) = default_reduce {
+            actions.retain(|_, a| *a != ParseAction::Reduce(rule));
+        }
+
+        states.push(State {
+            kernel: lr0_state.kernel.clone(),
+            actions: actions.into_iter().collect(),
+            default_reduce,
+            gotos,
+        });
+    }
+
+    let shift_reduce = conflicts
+        .iter()
+        .filter(|c| matches!(c, Conflict::ShiftReduce { .. }))
+        .count();
+    let reduce_reduce = conflicts.len() - shift_reduce;
+    rule_used[0] = true;
+
+    Automaton {
+        states,
+        conflicts,
+        shift_reduce,
+        reduce_reduce,
+        rule_used,
+    }
+}
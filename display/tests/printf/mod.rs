@@ -56,3 +56,49 @@ fn test_hex_output() {
         expected_exit_code: 0,
     });
 }
+
+fn printf_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("printf"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        stdin_data: String::new(),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn test_format_reuse() {
+    printf_test(&["%s-%s\n", "a", "b", "c"], "a-b\nc-\n", "", 0);
+}
+
+#[test]
+fn test_negative_zero_padding() {
+    printf_test(&["%05d|%-5d|", "-42", "-42"], "-0042|-42  |", "", 0);
+}
+
+#[test]
+fn test_float_output() {
+    printf_test(&["%.2f %g", "3.14159", "1e3"], "3.14 1000", "", 0);
+}
+
+#[test]
+fn test_invalid_number() {
+    printf_test(
+        &["%d %d\n", "12abc", "x"],
+        "12 0\n",
+        "printf: 12abc: value not completely converted\nprintf: x: expected a numeric value\n",
+        1,
+    );
+}
+
+#[test]
+fn test_invalid_conversion() {
+    printf_test(
+        &["a%yb"],
+        "",
+        "printf: %y: invalid conversion specification\n",
+        1,
+    );
+}
@@ -0,0 +1,266 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The lines being edited, with the current line and the marks on them.
+//! Line numbers are counted from 1; line 0 is before the first line, as
+//! an address for commands that insert text.
+
+use std::collections::HashMap;
+
+/// Identifies a line for as long as it exists, whatever lines are
+/// inserted or deleted around it.
+pub type LineId = u64;
+
+#[derive(Clone)]
+struct Line {
+    id: LineId,
+    text: Vec<u8>,
+}
+
+/// The lines and current line, as saved for undo.
+#[derive(Clone)]
+pub struct Snapshot {
+    lines: Vec<Line>,
+    current: usize,
+}
+
+#[derive(Default)]
+pub struct Buffer {
+    lines: Vec<Line>,
+    current: usize,
+    next_id: LineId,
+    marks: HashMap<u8, LineId>,
+    modified: bool,
+    /// Counts the changes to the lines, to tell whether a command made any.
+    changes: u64,
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the whole text, as when a file is edited; the current line
+    /// becomes the last one.
+    pub fn load(&mut self, lines: Vec<Vec<u8>>) {
+        self.lines.clear();
+        self.marks.clear();
+        self.insert(0, lines);
+        self.modified = false;
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The current line; 0 only when the buffer is empty.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn set_current(&mut self, n: usize) {
+        self.current = n.min(self.len());
+    }
+
+    /// The text of line `n`, without its newline.
+    pub fn line(&self, n: usize) -> &[u8] {
+        &self.lines[n - 1].text
+    }
+
+    pub fn lines(&self, first: usize, last: usize) -> impl Iterator<Item = &[u8]> {
+        self.lines[first - 1..last]
+            .iter()
+            .map(|line| line.text.as_slice())
+    }
+
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+    }
+
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    fn changed(&mut self) {
+        self.modified = true;
+        self.changes += 1;
+    }
+
+    /// Inserts `lines` after line `after`, and makes the last of them the
+    /// current line.
+    pub fn insert(&mut self, after: usize, lines: Vec<Vec<u8>>) {
+        let count = lines.len();
+        let new_lines: Vec<Line> = lines
+            .into_iter()
+            .map(|text| {
+                self.next_id += 1;
+                Line {
+                    id: self.next_id,
+                    text,
+                }
+            })
+            .collect();
+        self.lines.splice(after..after, new_lines);
+        if count > 0 {
+            self.current = after + count;
+            self.changed();
+        }
+    }
+
+    /// Deletes lines `first` to `last` and returns their text. The line
+    /// after them becomes current, or the last line if there is none.
+    pub fn delete(&mut self, first: usize, last: usize) -> Vec<Vec<u8>> {
+        let deleted = self
+            .lines
+            .drain(first - 1..last)
+            .map(|line| line.text)
+            .collect();
+        self.current = if first <= self.len() {
+            first
+        } else {
+            self.len()
+        };
+        self.changed();
+        deleted
+    }
+
+    /// Moves lines `first` to `last` after line `dest`, which must not be
+    /// among them, keeping their marks. The last of them becomes current.
+    pub fn move_lines(&mut self, first: usize, last: usize, dest: usize) {
+        let moved: Vec<Line> = self.lines.drain(first - 1..last).collect();
+        let count = moved.len();
+        let at = if dest >= last { dest - count } else { dest };
+        self.lines.splice(at..at, moved);
+        self.current = at + count;
+        self.changed();
+    }
+
+    /// Replaces the text of line `n`.
+    pub fn replace(&mut self, n: usize, text: Vec<u8>) {
+        self.lines[n - 1].text = text;
+        self.changed();
+    }
+
+    pub fn id(&self, n: usize) -> LineId {
+        self.lines[n - 1].id
+    }
+
+    /// The number of the line `id`, if it still exists.
+    pub fn find(&self, id: LineId) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.id == id)
+            .map(|i| i + 1)
+    }
+
+    pub fn set_mark(&mut self, name: u8, n: usize) {
+        let id = self.id(n);
+        self.marks.insert(name, id);
+    }
+
+    /// The line a mark is on, unless that line was deleted.
+    pub fn mark(&self, name: u8) -> Option<usize> {
+        self.marks.get(&name).and_then(|&id| self.find(id))
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            current: self.current,
+        }
+    }
+
+    /// Puts back the lines of a snapshot, and returns the lines it
+    /// replaced, so that undo can itself be undone.
+    pub fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        let undone = self.snapshot();
+        self.lines = snapshot.lines;
+        self.current = snapshot.current.min(self.len());
+        self.changed();
+        undone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<Vec<u8>> {
+        texts.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_insert_delete() {
+        let mut buffer = Buffer::new();
+        buffer.load(lines(&["a", "b", "c"]));
+        assert_eq!(buffer.current(), 3);
+        assert!(!buffer.modified());
+
+        buffer.insert(1, lines(&["x", "y"]));
+        assert_eq!(buffer.current(), 3);
+        assert_eq!(
+            buffer.lines(1, 5).collect::<Vec<_>>(),
+            lines(&["a", "x", "y", "b", "c"])
+        );
+
+        assert_eq!(buffer.delete(4, 5), lines(&["b", "c"]));
+        assert_eq!(buffer.current(), 3);
+        assert!(buffer.modified());
+    }
+
+    #[test]
+    fn test_marks_follow_lines() {
+        let mut buffer = Buffer::new();
+        buffer.load(lines(&["a", "b", "c"]));
+        buffer.set_mark(b'x', 3);
+        buffer.delete(1, 1);
+        assert_eq!(buffer.mark(b'x'), Some(2));
+        buffer.delete(2, 2);
+        assert_eq!(buffer.mark(b'x'), None);
+    }
+
+    #[test]
+    fn test_move_lines() {
+        let mut buffer = Buffer::new();
+        buffer.load(lines(&["a", "b", "c", "d"]));
+        buffer.set_mark(b'x', 1);
+        buffer.move_lines(1, 2, 4);
+        assert_eq!(
+            buffer.lines(1, 4).collect::<Vec<_>>(),
+            lines(&["c", "d", "a", "b"])
+        );
+        assert_eq!(buffer.current(), 4);
+        assert_eq!(buffer.mark(b'x'), Some(3));
+        buffer.move_lines(3, 4, 0);
+        assert_eq!(
+            buffer.lines(1, 4).collect::<Vec<_>>(),
+            lines(&["a", "b", "c", "d"])
+        );
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut buffer = Buffer::new();
+        buffer.load(lines(&["a", "b"]));
+        let snapshot = buffer.snapshot();
+        buffer.delete(1, 2);
+        let undone = buffer.restore(snapshot);
+        assert_eq!(buffer.len(), 2);
+        buffer.restore(undone);
+        assert!(buffer.is_empty());
+    }
+}
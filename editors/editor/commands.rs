@@ -0,0 +1,1184 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The ex commands. Each takes its arguments from the rest of the command
+//! line, leaving the parser at the end of the line or at the `|` before
+//! the next command.
+
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::process::{Command, Stdio};
+
+use super::parse::{Flags, Parser};
+use super::{Editor, Result, UNNAMED};
+use plib::diag::error_message;
+
+/// What to print after a command.
+pub enum Print {
+    /// Nothing: the command printed what it shows, or changes nothing.
+    Nothing,
+    /// The current line, if the command has print flags or autoprint is
+    /// set.
+    Auto(Flags),
+}
+
+/// Commands that may be followed by `!`, as in `q!`.
+const BANG: &[&str] = &[
+    "append", "cd", "change", "chdir", "edit", "global", "insert", "join", "next", "quit",
+    "rewind", "write", "wq", "xit",
+];
+
+pub fn takes_bang(name: &str) -> bool {
+    BANG.contains(&name)
+}
+
+/// Splits the contents of a file into lines, without their newlines.
+fn split_lines(text: &[u8]) -> Vec<Vec<u8>> {
+    let text = text.strip_suffix(b"\n").unwrap_or(text);
+    if text.is_empty() {
+        return Vec::new();
+    }
+    text.split(|&b| b == b'\n').map(<[u8]>::to_vec).collect()
+}
+
+/// The message after a file is read or written.
+fn file_message(name: &str, lines: usize, bytes: usize) -> String {
+    plib::tr!("\"{}\" {} lines, {} characters", name, lines, bytes)
+}
+
+/// Appends text converted as the `\u`, `\l`, `\U` and `\L` escapes of a
+/// replacement ask: `one` for the next character only, `all` until `\E`.
+fn push_cased(out: &mut Vec<u8>, text: &[u8], one: &mut Option<u8>, all: Option<u8>) {
+    for &b in text {
+        let case = one.take().or(all);
+        out.push(match case {
+            Some(b'u' | b'U') => b.to_ascii_uppercase(),
+            Some(b'l' | b'L') => b.to_ascii_lowercase(),
+            _ => b,
+        });
+    }
+}
+
+/// Expands a replacement for a match: `&` is the matched text, `\1` to
+/// `\9` the subexpressions, and without magic `\&` stands for `&`.
+fn expand_replacement(
+    replacement: &[u8],
+    text: &[u8],
+    groups: &[Option<Range<usize>>],
+    magic: bool,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut one = None;
+    let mut all = None;
+    let group = |i: usize| {
+        groups
+            .get(i)
+            .cloned()
+            .flatten()
+            .map_or(&[][..], |range| &text[range])
+    };
+
+    let mut bytes = replacement.iter().copied();
+    while let Some(b) = bytes.next() {
+        let escaped = b == b'\\';
+        let b = if escaped {
+            bytes.next().unwrap_or(b'\\')
+        } else {
+            b
+        };
+        match b {
+            b'&' if escaped != magic => push_cased(&mut out, group(0), &mut one, all),
+            b'1'..=b'9' if escaped => {
+                push_cased(&mut out, group(usize::from(b - b'0')), &mut one, all)
+            }
+            b'u' | b'l' if escaped => one = Some(b),
+            b'U' | b'L' if escaped => all = Some(b),
+            b'E' | b'e' if escaped => all = None,
+            _ => push_cased(&mut out, &[b], &mut one, all),
+        }
+    }
+    out
+}
+
+/// Joins two lines as join without `!` does: the leading blanks of the
+/// second are replaced by a space, or two after a period, or none before
+/// a `)` or after a blank.
+fn join_text(line: &mut Vec<u8>, next: &[u8]) {
+    let start = next
+        .iter()
+        .position(|&b| b != b' ' && b != b'\t')
+        .unwrap_or(next.len());
+    let next = &next[start..];
+    if !next.is_empty() && next[0] != b')' {
+        match line.last() {
+            None | Some(b' ' | b'\t') => {}
+            Some(b'.' | b'?' | b'!') => line.extend(b"  "),
+            Some(_) => line.push(b' '),
+        }
+    }
+    line.extend_from_slice(next);
+}
+
+impl Editor {
+    pub(super) fn dispatch(
+        &mut self,
+        name: &str,
+        p: &mut Parser,
+        addrs: &[usize],
+        bang: bool,
+    ) -> Result<Print> {
+        match name {
+            "append" => self.append(p, addrs, false),
+            "insert" => self.append(p, addrs, true),
+            "change" => self.change(p, addrs),
+            "delete" => self.delete(p, addrs),
+            "yank" => self.yank(p, addrs),
+            "put" => self.put(p, addrs),
+            "copy" | "t" => self.copy(p, addrs),
+            "move" => self.move_lines(p, addrs),
+            "join" => self.join(p, addrs, bang),
+            "print" | "list" | "number" => self.print(p, addrs, name),
+            "=" => self.line_number(p, addrs),
+            "z" => self.z(p, addrs),
+            "<" | ">" => self.shift(p, addrs, name),
+            "k" | "mark" => self.mark(p, addrs),
+            "undo" => self.undo(p),
+            "substitute" | "&" | "~" => self.substitute(p, addrs, name),
+            "global" => self.global(p, addrs, !bang),
+            "v" => self.global(p, addrs, false),
+            "edit" => self.edit_command(p, bang),
+            "file" => self.file(p),
+            "next" => self.next(p, bang),
+            "rewind" => self.rewind(p, bang),
+            "args" => self.show_args(p),
+            "read" => self.read(p, addrs),
+            "write" | "wq" | "xit" => self.write(p, addrs, name, bang),
+            "quit" => self.quit(p, bang),
+            "set" => self.set(p),
+            "cd" | "chdir" => self.chdir(p),
+            "source" => {
+                let name = self.expand(&p.argument(), false)?;
+                if name.is_empty() {
+                    return Err(plib::tr!("Missing filename"));
+                }
+                self.source(&name)?;
+                Ok(Print::Nothing)
+            }
+            "!" => self.bang(p, addrs),
+            "shell" => {
+                p.end()?;
+                let shell = self.options.string("shell").to_string();
+                self.out.flush().map_err(|e| error_message(&e))?;
+                Command::new(&shell)
+                    .status()
+                    .map_err(|e| format!("{}: {}", shell, error_message(&e)))?;
+                Ok(Print::Nothing)
+            }
+            "version" => {
+                p.end()?;
+                let version = format!("posixutils ex {}", env!("CARGO_PKG_VERSION"));
+                self.write_out(version.as_bytes())?;
+                Ok(Print::Nothing)
+            }
+            _ => Err(plib::tr!("Open and visual modes are not supported")),
+        }
+    }
+
+    fn write_out(&mut self, line: &[u8]) -> Result<()> {
+        self.out
+            .write_all(line)
+            .and_then(|()| self.out.write_all(b"\n"))
+            .map_err(|e| error_message(&e))
+    }
+
+    /// The lines a command applies to: those its addresses give, or the
+    /// current line, or every line if `whole`, when it has none.
+    fn lines_of(&self, addrs: &[usize], whole: bool) -> Result<(usize, usize)> {
+        let (first, last) = match *addrs {
+            [] if whole => (1, self.buffer.len()),
+            [] => (self.buffer.current(), self.buffer.current()),
+            [n] => (n, n),
+            [.., first, last] => (first, last),
+        };
+        if first > last && !(whole && addrs.is_empty()) {
+            return Err(plib::tr!("The first address is after the second"));
+        }
+        Ok((first, last))
+    }
+
+    /// As `lines_of`, for commands that need existing lines.
+    fn existing_lines(&self, addrs: &[usize], whole: bool) -> Result<(usize, usize)> {
+        if self.buffer.is_empty() {
+            return Err(plib::tr!("The buffer is empty"));
+        }
+        let (first, last) = self.lines_of(addrs, whole)?;
+        if first == 0 {
+            return Err(plib::tr!("Line 0 does not exist"));
+        }
+        Ok((first, last))
+    }
+
+    /// The line a command with one address applies to.
+    fn line_of(&self, addrs: &[usize]) -> usize {
+        addrs.last().copied().unwrap_or(self.buffer.current())
+    }
+
+    /// Applies a count: that many lines, starting from the last line of
+    /// the range.
+    fn with_count(&self, (first, last): (usize, usize), count: Option<usize>) -> (usize, usize) {
+        match count {
+            Some(count) => (last, (last + count - 1).min(self.buffer.len())),
+            None => (first, last),
+        }
+    }
+
+    /// Saves deleted or yanked lines: always in the unnamed register, and
+    /// also in a named one, or appended to it if it is named in upper
+    /// case.
+    fn store_register(&mut self, name: Option<u8>, lines: Vec<Vec<u8>>) {
+        if let Some(name) = name {
+            let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+            if name.is_ascii_uppercase() {
+                register.extend(lines.iter().cloned());
+            } else {
+                *register = lines.clone();
+            }
+        }
+        self.registers.insert(UNNAMED, lines);
+    }
+
+    /// Expands `%` and `#` in a file name to the current and alternate
+    /// file names, and for shell commands, `!` to the previous command.
+    fn expand(&self, text: &[u8], command: bool) -> Result<String> {
+        let mut out = Vec::new();
+        let mut bytes = text.iter().copied();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'\\' => match bytes.next() {
+                    Some(b @ (b'%' | b'#' | b'!')) => out.push(b),
+                    Some(b) => out.extend([b'\\', b]),
+                    None => out.push(b'\\'),
+                },
+                b'%' => out.extend(
+                    self.file
+                        .as_deref()
+                        .ok_or_else(|| plib::tr!("No current filename to substitute for %"))?
+                        .bytes(),
+                ),
+                b'#' => out.extend(
+                    self.alternate
+                        .as_deref()
+                        .ok_or_else(|| plib::tr!("No alternate filename to substitute for #"))?
+                        .bytes(),
+                ),
+                b'!' if command => out.extend(
+                    self.last_shell_command
+                        .as_deref()
+                        .ok_or_else(|| plib::tr!("No previous command to substitute for !"))?
+                        .bytes(),
+                ),
+                _ => out.push(b),
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// Fails if the buffer was modified since it was last written, unless
+    /// `bang`, writing it first if autowrite is set.
+    fn check_modified(&mut self, bang: bool, command: &str) -> Result<()> {
+        if bang || !self.buffer.modified() {
+            return Ok(());
+        }
+        match self.file.clone() {
+            Some(name) if self.options.flag("autowrite") => {
+                self.write_file(&name, 1, self.buffer.len(), false)?;
+                Ok(())
+            }
+            _ => Err(plib::tr!(
+                "No write since last change ({}! overrides)",
+                command
+            )),
+        }
+    }
+
+    fn append(&mut self, p: &mut Parser, addrs: &[usize], insert: bool) -> Result<Print> {
+        let n = self.line_of(addrs);
+        let flags = p.flags();
+        p.end()?;
+        let after = if insert { n.saturating_sub(1) } else { n };
+        let text = self.read_text();
+        if text.is_empty() {
+            self.buffer.set_current(n);
+        } else {
+            self.buffer.insert(after, text);
+        }
+        Ok(Print::Auto(flags))
+    }
+
+    fn change(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        let (first, last) = self.with_count(range, p.count()?);
+        let flags = p.flags();
+        p.end()?;
+        let text = self.read_text();
+        let deleted = self.buffer.delete(first, last);
+        self.store_register(None, deleted);
+        if text.is_empty() {
+            self.buffer.set_current((first - 1).max(1));
+        } else {
+            self.buffer.insert(first - 1, text);
+        }
+        Ok(Print::Auto(flags))
+    }
+
+    fn delete(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        let name = p.buffer_name();
+        let (first, last) = self.with_count(range, p.count()?);
+        let flags = p.flags();
+        p.end()?;
+        let deleted = self.buffer.delete(first, last);
+        self.store_register(name, deleted);
+        Ok(Print::Auto(flags))
+    }
+
+    fn yank(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        let name = p.buffer_name();
+        let (first, last) = self.with_count(range, p.count()?);
+        p.end()?;
+        let lines = self.buffer.lines(first, last).map(<[u8]>::to_vec).collect();
+        self.store_register(name, lines);
+        Ok(Print::Nothing)
+    }
+
+    fn put(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let n = self.line_of(addrs);
+        let name = p.buffer_name().map_or(UNNAMED, |b| b.to_ascii_lowercase());
+        let flags = p.flags();
+        p.end()?;
+        let lines = match self.registers.get(&name) {
+            Some(lines) if !lines.is_empty() => lines.clone(),
+            _ => return Err(plib::tr!("Nothing to put")),
+        };
+        self.buffer.insert(n, lines);
+        Ok(Print::Auto(flags))
+    }
+
+    /// The destination address of copy and move.
+    fn destination(&mut self, p: &mut Parser) -> Result<usize> {
+        let range = p.range()?;
+        let addrs = self.resolve_range(&range)?;
+        addrs
+            .last()
+            .copied()
+            .ok_or_else(|| plib::tr!("Destination address required"))
+    }
+
+    fn copy(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let (first, last) = self.existing_lines(addrs, false)?;
+        let dest = self.destination(p)?;
+        let flags = p.flags();
+        p.end()?;
+        let lines = self.buffer.lines(first, last).map(<[u8]>::to_vec).collect();
+        self.buffer.insert(dest, lines);
+        Ok(Print::Auto(flags))
+    }
+
+    fn move_lines(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let (first, last) = self.existing_lines(addrs, false)?;
+        let dest = self.destination(p)?;
+        let flags = p.flags();
+        p.end()?;
+        if dest >= first && dest < last {
+            return Err(plib::tr!("Destination is inside the lines moved"));
+        }
+        self.buffer.move_lines(first, last, dest);
+        Ok(Print::Auto(flags))
+    }
+
+    fn join(&mut self, p: &mut Parser, addrs: &[usize], bang: bool) -> Result<Print> {
+        let (first, last) = self.existing_lines(addrs, false)?;
+        let (first, mut last) = match p.count()? {
+            Some(count) => (last, last + count.max(2) - 1),
+            None => (first, last),
+        };
+        if first == last {
+            last += 1;
+        }
+        let flags = p.flags();
+        p.end()?;
+        if last > self.buffer.len() {
+            return Err(plib::tr!("Not that many lines in the buffer"));
+        }
+
+        let mut text = self.buffer.line(first).to_vec();
+        for next in self.buffer.lines(first + 1, last) {
+            if bang {
+                text.extend_from_slice(next);
+            } else {
+                join_text(&mut text, next);
+            }
+        }
+        self.buffer.delete(first + 1, last);
+        self.buffer.replace(first, text);
+        self.buffer.set_current(first);
+        Ok(Print::Auto(flags))
+    }
+
+    fn print(&mut self, p: &mut Parser, addrs: &[usize], name: &str) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        let (first, last) = self.with_count(range, p.count()?);
+        let mut flags = p.flags();
+        p.end()?;
+        flags.list |= name == "list";
+        flags.number |= name == "number";
+        self.print_lines(first, last, flags)?;
+        self.buffer.set_current(last);
+        Ok(Print::Nothing)
+    }
+
+    fn line_number(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let n = addrs.last().copied().unwrap_or(self.buffer.len());
+        p.flags();
+        p.end()?;
+        self.write_out(n.to_string().as_bytes())?;
+        Ok(Print::Nothing)
+    }
+
+    /// Prints a window of lines: after the line with `+`, before it with
+    /// `-`, around it with `.` or `=`, or the window before the previous
+    /// one with `^`.
+    fn z(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        if self.buffer.is_empty() {
+            return Err(plib::tr!("The buffer is empty"));
+        }
+        let kind = match p.peek() {
+            Some(b @ (b'+' | b'-' | b'.' | b'^' | b'=')) => {
+                p.next();
+                b
+            }
+            _ => b'+',
+        };
+        let count = p.count()?.unwrap_or(self.options.number("window").max(1));
+        let flags = p.flags();
+        p.end()?;
+
+        let len = self.buffer.len();
+        let n = match addrs.last() {
+            Some(&n) => n.max(1),
+            None if kind == b'+' => (self.buffer.current() + 1).min(len),
+            None => self.buffer.current().max(1),
+        };
+        let (first, last) = match kind {
+            b'+' => (n, n + count - 1),
+            b'-' => (n.saturating_sub(count - 1), n),
+            b'^' => {
+                let last = n.saturating_sub(count);
+                (last.saturating_sub(count - 1), last)
+            }
+            _ => {
+                let first = n.saturating_sub((count - 1) / 2);
+                (first, first + count - 1)
+            }
+        };
+        let (first, last) = (first.max(1), last.min(len));
+        if first > last {
+            return Err(plib::tr!("Not that many lines in the buffer"));
+        }
+
+        if kind == b'=' && (first..=last).contains(&n) {
+            self.print_lines(first, n - 1, flags)?;
+            self.write_out(b"----------")?;
+            self.print_lines(n, n, flags)?;
+            self.write_out(b"----------")?;
+            self.print_lines(n + 1, last, flags)?;
+        } else {
+            self.print_lines(first, last, flags)?;
+        }
+        let current = if matches!(kind, b'.' | b'=') { n } else { last };
+        self.buffer.set_current(current);
+        Ok(Print::Nothing)
+    }
+
+    /// Shifts lines by shiftwidth, once for each `<` or `>`, indenting
+    /// with tabs as far as tabstop allows.
+    fn shift(&mut self, p: &mut Parser, addrs: &[usize], name: &str) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        let c = name.as_bytes()[0];
+        let mut times = 1;
+        while p.eat(c) {
+            times += 1;
+        }
+        let (first, last) = self.with_count(range, p.count()?);
+        let flags = p.flags();
+        p.end()?;
+
+        let width = self.options.number("shiftwidth") * times;
+        let tabstop = self.options.number("tabstop");
+        for n in first..=last {
+            let text = self.buffer.line(n);
+            let mut column = 0;
+            let mut indent = 0;
+            for &b in text {
+                match b {
+                    b' ' => column += 1,
+                    b'\t' if tabstop > 0 => column = (column / tabstop + 1) * tabstop,
+                    b'\t' => column += 1,
+                    _ => break,
+                }
+                indent += 1;
+            }
+            if indent == text.len() && c == b'>' {
+                continue;
+            }
+            let column = if c == b'>' {
+                column + width
+            } else {
+                column.saturating_sub(width)
+            };
+            let mut shifted = Vec::with_capacity(column + text.len() - indent);
+            match column.checked_div(tabstop) {
+                Some(tabs) => {
+                    shifted.resize(tabs, b'\t');
+                    shifted.resize(tabs + column % tabstop, b' ');
+                }
+                None => shifted.resize(column, b' '),
+            }
+            shifted.extend_from_slice(&text[indent..]);
+            self.buffer.replace(n, shifted);
+        }
+        self.buffer.set_current(last);
+        Ok(Print::Auto(flags))
+    }
+
+    fn mark(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let n = self.line_of(addrs);
+        p.skip_blanks();
+        let name = match p.next() {
+            Some(name) if name.is_ascii_lowercase() => name,
+            _ => return Err(plib::tr!("Marks are a-z")),
+        };
+        p.end()?;
+        if n == 0 {
+            return Err(plib::tr!("Line 0 does not exist"));
+        }
+        self.buffer.set_mark(name, n);
+        Ok(Print::Nothing)
+    }
+
+    fn undo(&mut self, p: &mut Parser) -> Result<Print> {
+        p.end()?;
+        let snapshot = self
+            .undo
+            .take()
+            .ok_or_else(|| plib::tr!("Nothing to undo"))?;
+        self.undo = Some(self.buffer.restore(snapshot));
+        self.undone = true;
+        Ok(Print::Auto(Flags::default()))
+    }
+
+    /// Asks whether to make a substitution, showing the match under the
+    /// line, and reads the answer from standard input.
+    fn confirm(&mut self, text: &[u8], range: &Range<usize>) -> Result<bool> {
+        let mut marker = vec![b' '; range.start];
+        marker.resize(range.start + range.len().max(1), b'^');
+        self.write_out(text)?;
+        self.write_out(&marker)?;
+        self.out.flush().map_err(|e| error_message(&e))?;
+        let mut answer = Vec::new();
+        io::BufRead::read_until(&mut self.input, b'\n', &mut answer)
+            .map_err(|e| error_message(&e))?;
+        Ok(answer.first() == Some(&b'y'))
+    }
+
+    /// The substitute command, and `&` and `~`, which repeat the last
+    /// substitution with the pattern of the last substitute or of the
+    /// last search.
+    fn substitute(&mut self, p: &mut Parser, addrs: &[usize], name: &str) -> Result<Print> {
+        let range = self.existing_lines(addrs, false)?;
+        p.skip_blanks();
+        let delim = p.peek().filter(|&b| {
+            name == "substitute" && !b.is_ascii_alphanumeric() && !b"\\|\"".contains(&b)
+        });
+
+        let (mut pattern, replacement, mut global, mut confirm) = if let Some(delim) = delim {
+            p.next();
+            let pattern = p.delimited(delim);
+            let mut replacement = Vec::new();
+            while let Some(b) = p.next() {
+                if b == delim {
+                    break;
+                }
+                replacement.push(b);
+                if b == b'\\' {
+                    replacement.extend(p.next());
+                }
+            }
+            (pattern, Some(replacement), false, false)
+        } else {
+            let (pattern, global, confirm) = self
+                .last_substitute
+                .clone()
+                .ok_or_else(|| plib::tr!("No previous substitute to repeat"))?;
+            let pattern = if name == "~" { Vec::new() } else { pattern };
+            let keep = p.eat(b'&');
+            (pattern, None, keep && global, keep && confirm)
+        };
+        loop {
+            match p.peek() {
+                Some(b'g') => global = true,
+                Some(b'c') => confirm = true,
+                // The last search pattern instead of the last substitute's
+                Some(b'r') => pattern.clear(),
+                _ => break,
+            }
+            p.next();
+        }
+        let (first, last) = self.with_count(range, p.count()?);
+        let flags = p.flags();
+        p.end()?;
+
+        // The pattern's ~ is the previous replacement, not this one
+        let regex = self.regex(&pattern)?;
+        let magic = self.options.flag("magic");
+        let replacement = match replacement {
+            Some(replacement) => {
+                let previous = self.last_replacement.clone().unwrap_or_default();
+                let mut expanded = Vec::new();
+                let mut bytes = replacement.iter().copied();
+                while let Some(b) = bytes.next() {
+                    match (b, magic) {
+                        (b'~', true) => expanded.extend(&previous),
+                        (b'\\', _) => match bytes.next() {
+                            Some(b'~') if !magic => expanded.extend(&previous),
+                            Some(b) => expanded.extend([b'\\', b]),
+                            None => expanded.push(b'\\'),
+                        },
+                        _ => expanded.push(b),
+                    }
+                }
+                expanded
+            }
+            None => self
+                .last_replacement
+                .clone()
+                .ok_or_else(|| plib::tr!("No previous substitute to repeat"))?,
+        };
+        self.last_replacement = Some(replacement.clone());
+        self.last_substitute = self
+            .last_pattern
+            .clone()
+            .map(|pattern| (pattern, global, confirm));
+
+        let mut changed = None;
+        let (mut n, mut last) = (first, last);
+        while n <= last {
+            let text = self.buffer.line(n).to_vec();
+            let mut result = Vec::with_capacity(text.len());
+            let mut pos = 0;
+            let mut substituted = false;
+            while pos <= text.len() {
+                let Some(groups) = regex.captures_at(&text, pos) else {
+                    break;
+                };
+                let Some(found) = groups[0].clone() else {
+                    break;
+                };
+                result.extend_from_slice(&text[pos..found.start]);
+                if !confirm || self.confirm(&text, &found)? {
+                    result.extend(expand_replacement(&replacement, &text, &groups, magic));
+                    substituted = true;
+                } else {
+                    result.extend_from_slice(&text[found.clone()]);
+                }
+                pos = found.end;
+                if found.is_empty() {
+                    // Step over a character after an empty match
+                    result.extend(text.get(pos).copied());
+                    pos += 1;
+                }
+                if !global {
+                    break;
+                }
+            }
+
+            if substituted {
+                if pos < text.len() {
+                    result.extend_from_slice(&text[pos..]);
+                }
+                // An escaped newline in the replacement splits the line
+                let mut lines = result.split(|&b| b == b'\n').map(<[u8]>::to_vec);
+                let first_line = lines.next().unwrap_or_default();
+                let rest: Vec<Vec<u8>> = lines.collect();
+                let added = rest.len();
+                self.buffer.replace(n, first_line);
+                self.buffer.insert(n, rest);
+                n += added;
+                last += added;
+                changed = Some(n);
+            }
+            n += 1;
+        }
+
+        match changed {
+            Some(n) => {
+                self.buffer.set_current(n);
+                Ok(Print::Auto(flags))
+            }
+            None if self.in_global => Ok(Print::Nothing),
+            None => Err(plib::tr!("No match to substitute")),
+        }
+    }
+
+    /// Runs commands on each line that matches a pattern, or with
+    /// `matching` false, that does not. The commands default to print; a
+    /// backslash at the end of a line continues them on the next.
+    fn global(&mut self, p: &mut Parser, addrs: &[usize], matching: bool) -> Result<Print> {
+        if self.in_global {
+            return Err(plib::tr!("Global commands cannot be nested"));
+        }
+        p.skip_blanks();
+        let delim = match p.next() {
+            Some(b) if !b.is_ascii_alphanumeric() && !b"\\|\"".contains(&b) => b,
+            _ => return Err(plib::tr!("Global requires a delimited pattern")),
+        };
+        let pattern = p.delimited(delim);
+        let mut commands = p.rest().to_vec();
+        while commands.ends_with(b"\\") && !commands.ends_with(b"\\\\") {
+            commands.pop();
+            commands.push(b'\n');
+            match self.read_line() {
+                Some(line) => commands.extend(line),
+                None => break,
+            }
+        }
+
+        let regex = self.regex(&pattern)?;
+        let ids: Vec<_> = if self.buffer.is_empty() {
+            Vec::new()
+        } else {
+            let (first, last) = self.existing_lines(addrs, true)?;
+            (first..=last)
+                .filter(|&n| regex.is_match(self.buffer.line(n)) == matching)
+                .map(|n| self.buffer.id(n))
+                .collect()
+        };
+        let lines: VecDeque<Vec<u8>> = if commands.iter().all(u8::is_ascii_whitespace) {
+            VecDeque::from([b"p".to_vec()])
+        } else {
+            commands
+                .split(|&b| b == b'\n')
+                .map(<[u8]>::to_vec)
+                .collect()
+        };
+
+        self.in_global = true;
+        let mut result = Ok(());
+        for id in ids {
+            // Lines deleted by the commands for earlier lines are skipped
+            let Some(n) = self.buffer.find(id) else {
+                continue;
+            };
+            self.buffer.set_current(n);
+            result = self.run_source(lines.clone());
+            if result.is_err() || self.quit {
+                break;
+            }
+        }
+        self.in_global = false;
+        result.map(|()| Print::Nothing)
+    }
+
+    /// Edits a file, or the current one again; the current line becomes
+    /// the last one, and then `command` runs.
+    pub(super) fn edit(&mut self, name: Option<String>, command: Option<Vec<u8>>) -> Result<()> {
+        let name = match name {
+            Some(name) => name,
+            None => self
+                .file
+                .clone()
+                .ok_or_else(|| plib::tr!("No current filename"))?,
+        };
+        if self.file.as_deref() != Some(name.as_str()) {
+            self.alternate = self.file.replace(name.clone());
+        }
+        self.undo = None;
+        match fs::read(&name) {
+            Ok(text) => {
+                let lines = split_lines(&text);
+                let message = file_message(&name, lines.len(), text.len());
+                self.buffer.load(lines);
+                self.message(&message);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.buffer.load(Vec::new());
+                self.message(&plib::tr!("\"{}\" [New file]", name));
+            }
+            Err(e) => {
+                self.buffer.load(Vec::new());
+                return Err(format!("{}: {}", name, error_message(&e)));
+            }
+        }
+        if let Some(command) = command {
+            self.execute_line(&command)?;
+        }
+        Ok(())
+    }
+
+    fn edit_command(&mut self, p: &mut Parser, bang: bool) -> Result<Print> {
+        p.skip_blanks();
+        let command = if p.eat(b'+') {
+            let mut command = Vec::new();
+            while let Some(b) = p.peek() {
+                if b == b' ' || b == b'\t' || b == b'|' {
+                    break;
+                }
+                command.extend(p.next());
+            }
+            // A bare + goes to the last line
+            Some(if command.is_empty() {
+                b"$".to_vec()
+            } else {
+                command
+            })
+        } else {
+            None
+        };
+        let name = self.expand(&p.argument(), false)?;
+        self.check_modified(bang, "edit")?;
+        self.edit((!name.is_empty()).then_some(name), command)?;
+        Ok(Print::Nothing)
+    }
+
+    fn file(&mut self, p: &mut Parser) -> Result<Print> {
+        let name = self.expand(&p.argument(), false)?;
+        if !name.is_empty() {
+            self.alternate = self.file.replace(name);
+        }
+        let Some(name) = self.file.clone() else {
+            self.write_out(plib::tr!("No file").as_bytes())?;
+            return Ok(Print::Nothing);
+        };
+        let modified = if self.buffer.modified() {
+            plib::tr!(" [Modified]")
+        } else {
+            String::new()
+        };
+        let (n, len) = (self.buffer.current(), self.buffer.len());
+        let info = plib::tr!(
+            "\"{}\"{} line {} of {} --{}%--",
+            name,
+            modified,
+            n,
+            len,
+            (n * 100).checked_div(len).unwrap_or(0)
+        );
+        self.write_out(info.as_bytes())?;
+        Ok(Print::Nothing)
+    }
+
+    fn next(&mut self, p: &mut Parser, bang: bool) -> Result<Print> {
+        let args = self.expand(&p.argument(), false)?;
+        self.check_modified(bang, "next")?;
+        if !args.is_empty() {
+            self.args = args.split_whitespace().map(String::from).collect();
+            self.next_arg = 0;
+            self.warned_more = false;
+        }
+        let Some(name) = self.args.get(self.next_arg).cloned() else {
+            return Err(plib::tr!("No more files to edit"));
+        };
+        self.next_arg += 1;
+        self.edit(Some(name), None)?;
+        Ok(Print::Nothing)
+    }
+
+    fn rewind(&mut self, p: &mut Parser, bang: bool) -> Result<Print> {
+        p.end()?;
+        self.check_modified(bang, "rewind")?;
+        let Some(name) = self.args.first().cloned() else {
+            return Err(plib::tr!("No files in the argument list"));
+        };
+        self.next_arg = 1;
+        self.warned_more = false;
+        self.edit(Some(name), None)?;
+        Ok(Print::Nothing)
+    }
+
+    /// Lists the argument list, with the file being edited in brackets.
+    fn show_args(&mut self, p: &mut Parser) -> Result<Print> {
+        p.end()?;
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                if i + 1 == self.next_arg {
+                    format!("[{}]", arg)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect();
+        self.write_out(args.join(" ").as_bytes())?;
+        Ok(Print::Nothing)
+    }
+
+    /// Runs a shell command, with `input` as its standard input if given,
+    /// and returns its standard output; without input, its output goes to
+    /// the terminal.
+    fn run_shell(&mut self, command: &str, input: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        self.out.flush().map_err(|e| error_message(&e))?;
+        let shell = self.options.string("shell").to_string();
+        let mut cmd = Command::new(&shell);
+        cmd.arg("-c").arg(command);
+        let error = |e: io::Error| format!("{}: {}", shell, error_message(&e));
+        let Some(input) = input else {
+            cmd.status().map_err(error)?;
+            return Ok(Vec::new());
+        };
+
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(error)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let mut output = Vec::new();
+        let read = child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_end(&mut output);
+        // A command that exits without reading all of its input is fine
+        let _ = writer.join();
+        read.map_err(error)?;
+        child.wait().map_err(error)?;
+        Ok(output)
+    }
+
+    /// The text of a shell command, up to the end of the line, with `%`,
+    /// `#` and `!` expanded. It becomes the previous command.
+    fn shell_command(&mut self, p: &mut Parser) -> Result<String> {
+        let command = self.expand(p.rest(), true)?;
+        self.last_shell_command = Some(command.clone());
+        Ok(command)
+    }
+
+    /// Runs a shell command, or with addresses, filters the lines through
+    /// it, replacing them by its output.
+    fn bang(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        if addrs.is_empty() {
+            let command = self.shell_command(p)?;
+            if self.buffer.modified() && self.options.flag("warn") {
+                self.message(&plib::tr!("[No write since last change]"));
+            }
+            self.run_shell(&command, None)?;
+            self.message("!");
+            return Ok(Print::Nothing);
+        }
+
+        let (first, last) = self.existing_lines(addrs, false)?;
+        let command = self.shell_command(p)?;
+        let mut input = Vec::new();
+        for line in self.buffer.lines(first, last) {
+            input.extend_from_slice(line);
+            input.push(b'\n');
+        }
+        let output = self.run_shell(&command, Some(input))?;
+        self.buffer.delete(first, last);
+        self.buffer.insert(first - 1, split_lines(&output));
+        Ok(Print::Auto(Flags::default()))
+    }
+
+    fn read(&mut self, p: &mut Parser, addrs: &[usize]) -> Result<Print> {
+        let n = self.line_of(addrs);
+        p.skip_blanks();
+        if p.eat(b'!') {
+            let command = self.shell_command(p)?;
+            let output = self.run_shell(&command, Some(Vec::new()))?;
+            self.buffer.insert(n, split_lines(&output));
+            return Ok(Print::Nothing);
+        }
+
+        let name = self.expand(&p.argument(), false)?;
+        let name = match (name.is_empty(), &self.file) {
+            (false, _) => name,
+            (true, Some(file)) => file.clone(),
+            (true, None) => return Err(plib::tr!("No current filename")),
+        };
+        let text = fs::read(&name).map_err(|e| format!("{}: {}", name, error_message(&e)))?;
+        if self.file.is_none() {
+            self.file = Some(name.clone());
+        }
+        let lines = split_lines(&text);
+        let message = file_message(&name, lines.len(), text.len());
+        self.buffer.insert(n, lines);
+        self.message(&message);
+        Ok(Print::Nothing)
+    }
+
+    /// Writes lines `first` to `last` to a file, and reports how much.
+    fn write_file(&mut self, name: &str, first: usize, last: usize, append: bool) -> Result<()> {
+        let mut text = Vec::new();
+        if first <= last {
+            for line in self.buffer.lines(first, last) {
+                text.extend_from_slice(line);
+                text.push(b'\n');
+            }
+        }
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(name)
+            .and_then(|mut file| file.write_all(&text))
+            .map_err(|e| format!("{}: {}", name, error_message(&e)))?;
+
+        let whole = first == 1 && last == self.buffer.len();
+        if whole && self.file.as_deref() == Some(name) {
+            self.buffer.set_modified(false);
+        }
+        let lines = (last + 1).saturating_sub(first);
+        self.message(&file_message(name, lines, text.len()));
+        Ok(())
+    }
+
+    /// The write command, and wq and xit, which quit after writing; xit
+    /// writes only if the buffer was modified.
+    fn write(&mut self, p: &mut Parser, addrs: &[usize], name: &str, bang: bool) -> Result<Print> {
+        let (first, last) = self.lines_of(addrs, true)?;
+        if first == 0 {
+            return Err(plib::tr!("Line 0 does not exist"));
+        }
+        p.skip_blanks();
+        let append = p.eat(b'>');
+        if append && !p.eat(b'>') {
+            return Err(plib::tr!("Use >> to append to a file"));
+        }
+
+        if !append && name == "write" && p.eat(b'!') {
+            let command = self.shell_command(p)?;
+            let mut input = Vec::new();
+            if first <= last {
+                for line in self.buffer.lines(first, last) {
+                    input.extend_from_slice(line);
+                    input.push(b'\n');
+                }
+            }
+            self.run_shell(&command, Some(input))?;
+            self.message("!");
+            return Ok(Print::Nothing);
+        }
+
+        let file = self.expand(&p.argument(), false)?;
+        let file = match (file.is_empty(), &self.file) {
+            (false, _) => file,
+            (true, Some(current)) => current.clone(),
+            (true, None) => return Err(plib::tr!("No current filename")),
+        };
+        let current = self.file.as_deref() == Some(file.as_str());
+        if !bang {
+            if current && self.options.flag("readonly") {
+                return Err(plib::tr!("\"{}\" is read-only (use w! to write it)", file));
+            }
+            let exists = fs::metadata(&file).is_ok();
+            if !current && !append && exists && !self.options.flag("writeany") {
+                return Err(plib::tr!("\"{}\" exists (use w! to overwrite it)", file));
+            }
+        }
+        if self.file.is_none() {
+            self.file = Some(file.clone());
+        }
+
+        if name != "xit" || self.buffer.modified() {
+            self.write_file(&file, first, last, append)?;
+        }
+        if name != "write" {
+            self.quit = true;
+        }
+        Ok(Print::Nothing)
+    }
+
+    fn quit(&mut self, p: &mut Parser, bang: bool) -> Result<Print> {
+        p.end()?;
+        if !bang {
+            if self.buffer.modified() {
+                return Err(plib::tr!("No write since last change (quit! overrides)"));
+            }
+            let more = self.args.len().saturating_sub(self.next_arg);
+            if more > 0 && !self.warned_more {
+                self.warned_more = true;
+                return Err(plib::tr!("{} more files to edit", more));
+            }
+        }
+        self.quit = true;
+        Ok(Print::Nothing)
+    }
+
+    fn set(&mut self, p: &mut Parser) -> Result<Print> {
+        let args = p.argument();
+        let shown = self.options.set(&String::from_utf8_lossy(&args))?;
+        for line in shown {
+            self.write_out(line.as_bytes())?;
+        }
+        Ok(Print::Nothing)
+    }
+
+    fn chdir(&mut self, p: &mut Parser) -> Result<Print> {
+        let dir = self.expand(&p.argument(), false)?;
+        let dir = if dir.is_empty() {
+            std::env::var("HOME").map_err(|_| plib::tr!("HOME is not set"))?
+        } else {
+            dir
+        };
+        std::env::set_current_dir(&dir).map_err(|e| format!("{}: {}", dir, error_message(&e)))?;
+        Ok(Print::Nothing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_replacement() {
+        let text = b"hello world";
+        let groups = [Some(0..5), Some(0..1), None];
+        let expand = |replacement: &str, magic| {
+            expand_replacement(replacement.as_bytes(), text, &groups, magic)
+        };
+        assert_eq!(expand("<&>", true), b"<hello>");
+        assert_eq!(expand("<&>", false), b"<&>");
+        assert_eq!(expand("<\\&>", false), b"<hello>");
+        assert_eq!(expand("\\1\\2.", true), b"h.");
+        assert_eq!(expand("\\u&", true), b"Hello");
+        assert_eq!(expand("\\U&\\E!", true), b"HELLO!");
+        assert_eq!(expand("\\L\\uABC", true), b"Abc");
+    }
+
+    #[test]
+    fn test_join_text() {
+        let join = |a: &str, b: &str| {
+            let mut line = a.as_bytes().to_vec();
+            join_text(&mut line, b.as_bytes());
+            String::from_utf8(line).unwrap()
+        };
+        assert_eq!(join("a", "  b"), "a b");
+        assert_eq!(join("end.", "next"), "end.  next");
+        assert_eq!(join("(a", ")"), "(a)");
+        assert_eq!(join("a ", "b"), "a b");
+        assert_eq!(join("a", ""), "a");
+    }
+}
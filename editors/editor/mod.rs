@@ -0,0 +1,440 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The editing engine behind ex: a buffer of lines, the options, and the
+//! commands of ex command mode. A visual mode would drive the same
+//! `Editor`, running its commands by `execute_line`.
+
+mod buffer;
+mod commands;
+mod options;
+mod parse;
+mod regex;
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use buffer::{Buffer, Snapshot};
+use options::Options;
+use parse::{Address, Base, Flags, Parser, Range};
+use plib::diag::error_message;
+use plib::regex::Regex;
+
+type Result<T> = std::result::Result<T, String>;
+
+/// The register that deletes and yanks always fill.
+const UNNAMED: u8 = b'"';
+
+/// The mark of the line that was current before the last absolute move.
+const PREVIOUS_CONTEXT: u8 = b'\'';
+
+pub struct Editor {
+    buffer: Buffer,
+    options: Options,
+    /// The current and alternate file names, for `%` and `#`.
+    file: Option<String>,
+    alternate: Option<String>,
+    /// The argument list, and the index of the next file in it to edit.
+    args: Vec<String>,
+    next_arg: usize,
+    /// Whether quit has already warned about files left to edit.
+    warned_more: bool,
+    last_pattern: Option<Vec<u8>>,
+    last_replacement: Option<Vec<u8>>,
+    /// The pattern of the last substitute command, and its g and c flags.
+    last_substitute: Option<(Vec<u8>, bool, bool)>,
+    last_shell_command: Option<String>,
+    registers: HashMap<u8, Vec<Vec<u8>>>,
+    undo: Option<Snapshot>,
+    /// Set by undo, whose change is not itself saved for undo.
+    undone: bool,
+    /// Command lines queued by global and source, innermost last. While
+    /// any are queued, commands and their text input come from them
+    /// instead of standard input.
+    sources: Vec<VecDeque<Vec<u8>>>,
+    input: Box<dyn BufRead>,
+    out: plib::io::StdoutWriter,
+    /// Set in batch mode, by -s or when standard input is not a terminal,
+    /// where there are no prompts or informational messages, and an error
+    /// ends the editor.
+    batch: bool,
+    in_global: bool,
+    quit: bool,
+}
+
+impl Editor {
+    pub fn new(args: Vec<String>, silent: bool) -> Self {
+        let batch = silent || !io::stdin().is_terminal();
+        let mut options = Options::default();
+        if batch {
+            options.set_flag("autoprint", false);
+            options.set_flag("prompt", false);
+        }
+        Editor {
+            buffer: Buffer::new(),
+            options,
+            file: None,
+            alternate: None,
+            args,
+            next_arg: 0,
+            warned_more: false,
+            last_pattern: None,
+            last_replacement: None,
+            last_substitute: None,
+            last_shell_command: None,
+            registers: HashMap::new(),
+            undo: None,
+            undone: false,
+            sources: Vec::new(),
+            input: Box::new(io::BufReader::new(io::stdin())),
+            out: plib::io::stdout(),
+            batch,
+            in_global: false,
+            quit: false,
+        }
+    }
+
+    pub fn set_readonly(&mut self) {
+        self.options.set_flag("readonly", true);
+    }
+
+    pub fn set_window(&mut self, lines: usize) {
+        self.options.set_number("window", lines);
+    }
+
+    /// Runs the commands of EXINIT, or else of `$HOME/.exrc`, and then of
+    /// `./.exrc` if the exrc option is set.
+    pub fn initialize(&mut self) -> Result<()> {
+        if let Ok(exinit) = std::env::var("EXINIT") {
+            self.execute_line(exinit.as_bytes())?;
+        } else if let Some(home) = std::env::var_os("HOME") {
+            let exrc = std::path::Path::new(&home).join(".exrc");
+            if exrc.is_file() {
+                self.source(&exrc.to_string_lossy())?;
+            }
+        }
+        if self.options.flag("exrc") && std::path::Path::new(".exrc").is_file() {
+            self.source(".exrc")?;
+        }
+        Ok(())
+    }
+
+    /// Edits the first file of the argument list, if there is one.
+    pub fn edit_first(&mut self) -> Result<()> {
+        if self.args.is_empty() {
+            return Ok(());
+        }
+        self.next_arg = 1;
+        let name = self.args[0].clone();
+        self.edit(Some(name), None)
+    }
+
+    /// Reads and runs commands until quit or the end of input, returning
+    /// the exit status.
+    pub fn run(&mut self) -> i32 {
+        while !self.quit {
+            if self.options.flag("prompt") {
+                let _ = self.out.write_all(b":");
+            }
+            let _ = self.out.flush();
+            let Some(line) = self.read_line() else {
+                // The end of input is taken as a quit command
+                if let Err(e) = self.execute_top(b"quit") {
+                    self.report_error(&e);
+                    return 1;
+                }
+                break;
+            };
+            if let Err(e) = self.execute_top(&line) {
+                if !self.report_error(&e) {
+                    return 1;
+                }
+            }
+        }
+        match self.out.flush() {
+            Ok(()) => 0,
+            Err(e) => {
+                plib::diag!("{}", error_message(&e));
+                1
+            }
+        }
+    }
+
+    /// Reports an error, returning whether editing can continue: in batch
+    /// mode it cannot.
+    pub fn report_error(&mut self, e: &str) -> bool {
+        let _ = self.out.flush();
+        plib::diag!("{}", e);
+        if self.options.flag("errorbells") && !self.batch {
+            eprint!("\x07");
+        }
+        !self.batch
+    }
+
+    /// Runs a command line typed at the top level, as one undoable change.
+    pub fn execute_top(&mut self, line: &[u8]) -> Result<()> {
+        let snapshot = self.buffer.snapshot();
+        let changes = self.buffer.changes();
+        self.undone = false;
+        let result = self.execute_line(line);
+        if self.buffer.changes() != changes && !self.undone {
+            self.undo = Some(snapshot);
+        }
+        result
+    }
+
+    /// Reads a line of commands or text, from the innermost queued source
+    /// if there is one and otherwise from standard input. A queued source
+    /// that runs out gives no more lines, rather than falling through to
+    /// standard input.
+    fn read_line(&mut self) -> Option<Vec<u8>> {
+        if let Some(source) = self.sources.last_mut() {
+            return source.pop_front();
+        }
+        let mut line = Vec::new();
+        match self.input.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+                Some(line)
+            }
+        }
+    }
+
+    /// Reads text input, for append, insert and change, up to a line with
+    /// only a period or the end of input.
+    fn read_text(&mut self) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        while let Some(line) = self.read_line() {
+            if line == b"." {
+                break;
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Runs `lines` as commands, as global and source do.
+    fn run_source(&mut self, lines: VecDeque<Vec<u8>>) -> Result<()> {
+        self.sources.push(lines);
+        let mut result = Ok(());
+        while let Some(line) = self.read_line() {
+            result = self.execute_line(&line);
+            if result.is_err() || self.quit {
+                break;
+            }
+        }
+        self.sources.pop();
+        result
+    }
+
+    fn source(&mut self, path: &str) -> Result<()> {
+        let text = fs::read(path).map_err(|e| format!("{}: {}", path, error_message(&e)))?;
+        let lines = text.split(|&b| b == b'\n').map(<[u8]>::to_vec).collect();
+        self.run_source(lines)
+    }
+
+    /// Runs a command line, which may hold several commands separated by
+    /// `|`.
+    pub fn execute_line(&mut self, line: &[u8]) -> Result<()> {
+        let mut parser = Parser::new(line);
+        loop {
+            self.execute_command(&mut parser)?;
+            if self.quit || !parser.eat(b'|') {
+                return Ok(());
+            }
+        }
+    }
+
+    fn execute_command(&mut self, parser: &mut Parser) -> Result<()> {
+        while parser.eat(b':') || parser.eat(b' ') || parser.eat(b'\t') {}
+        if parser.eat(b'"') {
+            // A comment
+            parser.rest();
+            return Ok(());
+        }
+
+        let range = parser.range()?;
+        let addrs = self.resolve_range(&range)?;
+        let Some(name) = parser.command()? else {
+            return self.goto(&addrs, range.addresses.is_empty());
+        };
+        let bang = commands::takes_bang(name) && parser.eat(b'!');
+        let print = self.dispatch(name, parser, &addrs, bang)?;
+
+        if let commands::Print::Auto(flags) = print {
+            if flags.any() || (self.options.flag("autoprint") && !self.in_global) {
+                self.print_current(flags)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves to the line of a command with only addresses and prints it;
+    /// an empty command moves to the next line.
+    fn goto(&mut self, addrs: &[usize], empty: bool) -> Result<()> {
+        let n = match addrs.last() {
+            Some(&n) => n,
+            None if empty && self.buffer.current() < self.buffer.len() => self.buffer.current() + 1,
+            None => return Err(plib::tr!("At end-of-file")),
+        };
+        if n == 0 {
+            return Err(plib::tr!("Line 0 does not exist"));
+        }
+        if !empty && self.buffer.current() > 0 {
+            self.buffer
+                .set_mark(PREVIOUS_CONTEXT, self.buffer.current());
+        }
+        self.buffer.set_current(n);
+        self.print_current(Flags::default())
+    }
+
+    /// Resolves the addresses of a command to line numbers.
+    fn resolve_range(&mut self, range: &Range) -> Result<Vec<usize>> {
+        let mut addrs = Vec::with_capacity(range.addresses.len());
+        for (address, semicolon) in &range.addresses {
+            let n = self.resolve(address)?;
+            if *semicolon && n > 0 {
+                self.buffer.set_current(n);
+            }
+            addrs.push(n);
+        }
+        Ok(addrs)
+    }
+
+    fn resolve(&mut self, address: &Address) -> Result<usize> {
+        let base = match &address.base {
+            Base::Current => self.buffer.current(),
+            Base::Last => self.buffer.len(),
+            Base::Number(n) => *n,
+            Base::Mark(name) => self
+                .buffer
+                .mark(*name)
+                .ok_or_else(|| plib::tr!("Mark '{}' is not set", char::from(*name)))?,
+            Base::Search(pattern, forward) => self.search(pattern, *forward)?,
+        };
+        let n = i64::try_from(base).unwrap_or(i64::MAX) + address.offset;
+        if n < 0 {
+            Err(plib::tr!("Negative address; the first line is 1"))
+        } else if n as usize > self.buffer.len() {
+            Err(plib::tr!("Not that many lines in the buffer"))
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// Compiles a pattern, or the previous one if it is empty.
+    fn regex(&mut self, pattern: &[u8]) -> Result<Regex> {
+        if !pattern.is_empty() {
+            self.last_pattern = Some(pattern.to_vec());
+        }
+        let pattern = self
+            .last_pattern
+            .as_deref()
+            .ok_or_else(|| plib::tr!("No previous regular expression"))?;
+        regex::compile(
+            pattern,
+            self.options.flag("ignorecase"),
+            self.options.flag("magic"),
+            self.last_replacement.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// Finds the next line matching a pattern, after the current line or
+    /// before it, wrapping around the buffer if wrapscan is set.
+    fn search(&mut self, pattern: &[u8], forward: bool) -> Result<usize> {
+        let regex = self.regex(pattern)?;
+        let len = self.buffer.len();
+        let current = self.buffer.current();
+        let wrapscan = self.options.flag("wrapscan");
+        for i in 1..=len {
+            let n = if forward {
+                let n = current + i;
+                if n > len && !wrapscan {
+                    break;
+                }
+                (n - 1) % len + 1
+            } else {
+                if i >= current.max(1) && !wrapscan {
+                    break;
+                }
+                (current + len * 2 - i - 1) % len + 1
+            };
+            if regex.is_match(self.buffer.line(n)) {
+                return Ok(n);
+            }
+        }
+        if wrapscan {
+            Err(plib::tr!("Pattern not found"))
+        } else if forward {
+            Err(plib::tr!("Search hit BOTTOM without matching pattern"))
+        } else {
+            Err(plib::tr!("Search hit TOP without matching pattern"))
+        }
+    }
+
+    /// Formats a line as print, list or number shows it.
+    fn format_line(&self, n: usize, flags: Flags) -> Vec<u8> {
+        let mut out = Vec::new();
+        if flags.number || self.options.flag("number") {
+            out.extend(format!("{:6}  ", n).bytes());
+        }
+        let text = self.buffer.line(n);
+        if flags.list || self.options.flag("list") {
+            for &b in text {
+                match b {
+                    b'\t' => out.extend(b"^I"),
+                    0x7f => out.extend(b"^?"),
+                    0..=0x1f => out.extend([b'^', b + b'@']),
+                    0x80.. => out.extend(format!("\\{:03o}", b).bytes()),
+                    _ => out.push(b),
+                }
+            }
+            out.push(b'$');
+        } else {
+            out.extend_from_slice(text);
+        }
+        out.push(b'\n');
+        out
+    }
+
+    fn print_lines(&mut self, first: usize, last: usize, flags: Flags) -> Result<()> {
+        for n in first..=last {
+            let line = self.format_line(n, flags);
+            self.out.write_all(&line).map_err(|e| error_message(&e))?;
+        }
+        Ok(())
+    }
+
+    /// Prints the current line, after moving by the `+` and `-` of flags.
+    fn print_current(&mut self, flags: Flags) -> Result<()> {
+        if flags.offset != 0 {
+            let n = self.buffer.current() as i64 + flags.offset;
+            if n < 1 || n as usize > self.buffer.len() {
+                return Err(plib::tr!("Not that many lines in the buffer"));
+            }
+            self.buffer.set_current(n as usize);
+        }
+        let n = self.buffer.current();
+        if n == 0 {
+            return Ok(());
+        }
+        self.print_lines(n, n, flags)
+    }
+
+    /// Writes an informational message, unless in batch mode.
+    fn message(&mut self, message: &str) {
+        if !self.batch {
+            let _ = writeln!(self.out, "{}", message);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The edit options of the set command.
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(usize),
+    String(String),
+}
+
+struct OptionDef {
+    name: &'static str,
+    abbrev: &'static str,
+    default: fn() -> Value,
+}
+
+macro_rules! options {
+    ($(($name:literal, $abbrev:literal, $default:expr)),* $(,)?) => {
+        &[$(OptionDef { name: $name, abbrev: $abbrev, default: || $default }),*]
+    };
+}
+
+/// All options, in the order `set all` lists them.
+const OPTIONS: &[OptionDef] = options![
+    ("autoindent", "ai", Value::Bool(false)),
+    ("autoprint", "ap", Value::Bool(true)),
+    ("autowrite", "aw", Value::Bool(false)),
+    ("beautify", "bf", Value::Bool(false)),
+    ("directory", "dir", Value::String(String::from("/tmp"))),
+    ("edcompatible", "ed", Value::Bool(false)),
+    ("errorbells", "eb", Value::Bool(false)),
+    ("exrc", "ex", Value::Bool(false)),
+    ("ignorecase", "ic", Value::Bool(false)),
+    ("list", "list", Value::Bool(false)),
+    ("magic", "magic", Value::Bool(true)),
+    ("mesg", "mesg", Value::Bool(true)),
+    ("number", "nu", Value::Bool(false)),
+    (
+        "paragraphs",
+        "para",
+        Value::String(String::from("IPLPPPQPP LIpplpipbp"))
+    ),
+    ("prompt", "prompt", Value::Bool(true)),
+    ("readonly", "ro", Value::Bool(false)),
+    ("redraw", "redraw", Value::Bool(false)),
+    ("remap", "remap", Value::Bool(true)),
+    ("report", "report", Value::Number(5)),
+    ("scroll", "scr", Value::Number(12)),
+    (
+        "sections",
+        "sect",
+        Value::String(String::from("NHSHH HUnhsh"))
+    ),
+    ("shell", "sh", Value::String(default_shell())),
+    ("shiftwidth", "sw", Value::Number(8)),
+    ("showmatch", "sm", Value::Bool(false)),
+    ("showmode", "smd", Value::Bool(false)),
+    ("slowopen", "slow", Value::Bool(false)),
+    ("tabstop", "ts", Value::Number(8)),
+    ("taglength", "tl", Value::Number(0)),
+    ("tags", "tags", Value::String(String::from("tags"))),
+    (
+        "term",
+        "term",
+        Value::String(std::env::var("TERM").unwrap_or_default())
+    ),
+    ("terse", "terse", Value::Bool(false)),
+    ("warn", "warn", Value::Bool(true)),
+    ("window", "wi", Value::Number(24)),
+    ("wrapmargin", "wm", Value::Number(0)),
+    ("wrapscan", "ws", Value::Bool(true)),
+    ("writeany", "wa", Value::Bool(false)),
+];
+
+fn default_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| String::from("/bin/sh"))
+}
+
+fn find(name: &str) -> Option<usize> {
+    OPTIONS
+        .iter()
+        .position(|def| def.name == name || def.abbrev == name)
+}
+
+pub struct Options {
+    values: Vec<Value>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            values: OPTIONS.iter().map(|def| (def.default)()).collect(),
+        }
+    }
+}
+
+impl Options {
+    fn get(&self, name: &str) -> &Value {
+        &self.values[find(name).expect("option is defined")]
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        matches!(self.get(name), Value::Bool(true))
+    }
+
+    pub fn number(&self, name: &str) -> usize {
+        match self.get(name) {
+            Value::Number(n) => *n,
+            _ => 0,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> &str {
+        match self.get(name) {
+            Value::String(s) => s,
+            _ => "",
+        }
+    }
+
+    pub fn set_flag(&mut self, name: &str, on: bool) {
+        self.values[find(name).expect("option is defined")] = Value::Bool(on);
+    }
+
+    pub fn set_number(&mut self, name: &str, n: usize) {
+        self.values[find(name).expect("option is defined")] = Value::Number(n);
+    }
+
+    fn show(&self, i: usize) -> String {
+        let name = OPTIONS[i].name;
+        match &self.values[i] {
+            Value::Bool(true) => name.to_string(),
+            Value::Bool(false) => format!("no{}", name),
+            Value::Number(n) => format!("{}={}", name, n),
+            Value::String(s) => format!("{}={}", name, s),
+        }
+    }
+
+    /// Runs the arguments of set, returning what it displays: with none,
+    /// the options changed from their defaults; with `all`, every option.
+    /// `name` turns an option on, `noname` off, `name=value` sets a value
+    /// and `name?` displays it, as `name` also does for options with
+    /// values.
+    pub fn set(&mut self, args: &str) -> Result<Vec<String>, String> {
+        let mut shown = Vec::new();
+        let args: Vec<&str> = args.split_whitespace().collect();
+        if args.is_empty() {
+            for (i, def) in OPTIONS.iter().enumerate() {
+                if self.values[i] != (def.default)() {
+                    shown.push(self.show(i));
+                }
+            }
+            return Ok(shown);
+        }
+        if args == ["all"] {
+            return Ok((0..OPTIONS.len()).map(|i| self.show(i)).collect());
+        }
+
+        for arg in args {
+            if let Some((name, value)) = arg.split_once('=') {
+                let i = find(name).ok_or_else(|| plib::tr!("{}: no such option", name))?;
+                self.values[i] = match &self.values[i] {
+                    Value::Bool(_) => {
+                        return Err(plib::tr!("{}: option does not take a value", name));
+                    }
+                    Value::Number(_) => Value::Number(
+                        value
+                            .parse()
+                            .map_err(|_| plib::tr!("{}: illegal number", value))?,
+                    ),
+                    Value::String(_) => Value::String(value.to_string()),
+                };
+                continue;
+            }
+
+            let (name, query) = match arg.strip_suffix('?') {
+                Some(name) => (name, true),
+                None => (arg, false),
+            };
+            if let Some(i) = find(name) {
+                match &self.values[i] {
+                    Value::Bool(_) if !query => self.values[i] = Value::Bool(true),
+                    _ => shown.push(self.show(i)),
+                }
+                continue;
+            }
+            match name.strip_prefix("no").and_then(find) {
+                Some(i) if !query && matches!(self.values[i], Value::Bool(_)) => {
+                    self.values[i] = Value::Bool(false);
+                }
+                _ => return Err(plib::tr!("{}: no such option", name)),
+            }
+        }
+        Ok(shown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut options = Options::default();
+        assert_eq!(options.set(""), Ok(vec![]));
+        assert_eq!(options.set("nu sw=4 nows"), Ok(vec![]));
+        assert!(options.flag("number"));
+        assert!(!options.flag("wrapscan"));
+        assert_eq!(options.number("shiftwidth"), 4);
+        assert_eq!(
+            options.set(""),
+            Ok(vec![
+                String::from("number"),
+                String::from("shiftwidth=4"),
+                String::from("nowrapscan")
+            ])
+        );
+        assert_eq!(
+            options.set("ts nu?"),
+            Ok(vec![String::from("tabstop=8"), String::from("number")])
+        );
+        assert!(options.set("bogus").is_err());
+        assert!(options.set("nots").is_err());
+        assert!(options.set("sw=x").is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The syntax of ex command lines: addresses, command names, and the
+//! counts, flags and arguments that follow them.
+
+/// Where an address starts from, before its offset.
+pub enum Base {
+    Current,
+    Last,
+    Number(usize),
+    Mark(u8),
+    /// A pattern to search for, forward or backward; empty for the
+    /// previous one.
+    Search(Vec<u8>, bool),
+}
+
+pub struct Address {
+    pub base: Base,
+    pub offset: i64,
+}
+
+/// The addresses before a command. Each is followed by `;` if the second
+/// flag is set, making it the current line for those after it.
+#[derive(Default)]
+pub struct Range {
+    pub addresses: Vec<(Address, bool)>,
+}
+
+/// Print flags of a command, as its `#`, `l` and `p` suffixes set them.
+#[derive(Clone, Copy, Default)]
+pub struct Flags {
+    pub number: bool,
+    pub list: bool,
+    pub print: bool,
+    /// From `+` and `-`: lines to move past the current one before it
+    /// is printed.
+    pub offset: i64,
+}
+
+impl Flags {
+    pub fn any(&self) -> bool {
+        self.number || self.list || self.print
+    }
+}
+
+/// Command names, with the shortest abbreviation each may be given as.
+const COMMANDS: &[(&str, &str)] = &[
+    ("append", "a"),
+    ("args", "ar"),
+    ("cd", "cd"),
+    ("change", "c"),
+    ("chdir", "chd"),
+    ("copy", "co"),
+    ("delete", "d"),
+    ("edit", "e"),
+    ("file", "f"),
+    ("global", "g"),
+    ("insert", "i"),
+    ("join", "j"),
+    ("k", "k"),
+    ("list", "l"),
+    ("mark", "ma"),
+    ("move", "m"),
+    ("next", "n"),
+    ("number", "nu"),
+    ("open", "o"),
+    ("print", "p"),
+    ("put", "pu"),
+    ("quit", "q"),
+    ("read", "r"),
+    ("rewind", "rew"),
+    ("set", "se"),
+    ("shell", "sh"),
+    ("source", "so"),
+    ("substitute", "s"),
+    ("t", "t"),
+    ("undo", "u"),
+    ("v", "v"),
+    ("version", "ve"),
+    ("visual", "vi"),
+    ("write", "w"),
+    ("wq", "wq"),
+    ("xit", "x"),
+    ("yank", "ya"),
+    ("z", "z"),
+];
+
+/// A cursor over a command line.
+pub struct Parser<'a> {
+    line: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(line: &'a [u8]) -> Self {
+        Parser { line, pos: 0 }
+    }
+
+    pub fn peek(&self) -> Option<u8> {
+        self.line.get(self.pos).copied()
+    }
+
+    pub fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    /// Consumes `b` if it is next.
+    pub fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether the command ends here, at the end of the line or at a `|`
+    /// before another command.
+    pub fn at_command_end(&mut self) -> bool {
+        self.skip_blanks();
+        matches!(self.peek(), None | Some(b'|'))
+    }
+
+    /// The rest of the line, including any further commands.
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.line[self.pos..];
+        self.pos = self.line.len();
+        rest
+    }
+
+    pub fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.line[start..self.pos]).ok()?;
+        if digits.is_empty() {
+            None
+        } else {
+            Some(digits.parse().unwrap_or(usize::MAX))
+        }
+    }
+
+    /// The argument of a command, up to an unescaped `|` or the end of the
+    /// line, with blanks trimmed; `\|` stands for `|`.
+    pub fn argument(&mut self) -> Vec<u8> {
+        self.skip_blanks();
+        let mut arg = Vec::new();
+        while let Some(b) = self.peek() {
+            if b == b'|' {
+                break;
+            }
+            self.pos += 1;
+            if b == b'\\' && self.peek() == Some(b'|') {
+                arg.push(b'|');
+                self.pos += 1;
+            } else {
+                arg.push(b);
+            }
+        }
+        while arg.last().is_some_and(|b| b.is_ascii_whitespace()) {
+            arg.pop();
+        }
+        arg
+    }
+
+    /// Reads text up to an unescaped `delim`, which is consumed if
+    /// present. `\delim` stands for `delim`; other escapes are kept.
+    pub fn delimited(&mut self, delim: u8) -> Vec<u8> {
+        let mut text = Vec::new();
+        while let Some(b) = self.next() {
+            if b == delim {
+                break;
+            }
+            if b == b'\\' {
+                match self.next() {
+                    Some(b) if b == delim => text.push(b),
+                    Some(b) => text.extend([b'\\', b]),
+                    None => text.push(b'\\'),
+                }
+            } else {
+                text.push(b);
+            }
+        }
+        text
+    }
+
+    /// A `+` or `-` offset, which may be repeated, as in `--`, and may
+    /// carry a count, as in `+2`. A number following an address without
+    /// a sign is an offset too.
+    fn offset(&mut self) -> i64 {
+        let mut offset = 0i64;
+        loop {
+            self.skip_blanks();
+            let sign = match self.peek() {
+                Some(b'+') => 1,
+                Some(b'-' | b'^') => -1,
+                Some(b'0'..=b'9') => {
+                    let n = self.number().unwrap_or(0);
+                    offset = offset.saturating_add(n as i64);
+                    continue;
+                }
+                _ => return offset,
+            };
+            self.pos += 1;
+            let n = match self.peek() {
+                Some(b'0'..=b'9') => self.number().unwrap_or(0) as i64,
+                _ => 1,
+            };
+            offset = offset.saturating_add(sign * n);
+        }
+    }
+
+    fn address(&mut self) -> Result<Option<Address>, String> {
+        self.skip_blanks();
+        let base = match self.peek() {
+            Some(b'.') => {
+                self.pos += 1;
+                Base::Current
+            }
+            Some(b'$') => {
+                self.pos += 1;
+                Base::Last
+            }
+            Some(b'0'..=b'9') => Base::Number(self.number().unwrap_or(0)),
+            Some(b'\'') => {
+                self.pos += 1;
+                match self.next() {
+                    Some(c) if c.is_ascii_lowercase() || c == b'\'' => Base::Mark(c),
+                    _ => return Err(plib::tr!("Marks are ' and a-z")),
+                }
+            }
+            Some(delim @ (b'/' | b'?')) => {
+                self.pos += 1;
+                Base::Search(self.delimited(delim), delim == b'/')
+            }
+            Some(b'+' | b'-' | b'^') => Base::Current,
+            _ => return Ok(None),
+        };
+        let offset = self.offset();
+        Ok(Some(Address { base, offset }))
+    }
+
+    /// Parses the addresses before a command. `%` stands for `1,$`.
+    pub fn range(&mut self) -> Result<Range, String> {
+        let mut range = Range::default();
+        self.skip_blanks();
+        if self.eat(b'%') {
+            let first = Address {
+                base: Base::Number(1),
+                offset: 0,
+            };
+            let last = Address {
+                base: Base::Last,
+                offset: self.offset(),
+            };
+            range.addresses.push((first, false));
+            range.addresses.push((last, false));
+            return Ok(range);
+        }
+
+        loop {
+            let address = self.address()?;
+            self.skip_blanks();
+            let separator = match self.peek() {
+                Some(b',') => Some(false),
+                Some(b';') => Some(true),
+                _ => None,
+            };
+            match (address, separator) {
+                (Some(address), None) => {
+                    range.addresses.push((address, false));
+                    return Ok(range);
+                }
+                (None, None) => return Ok(range),
+                (address, Some(semicolon)) => {
+                    self.pos += 1;
+                    // A missing address before a separator is the
+                    // current line
+                    let address = address.unwrap_or(Address {
+                        base: Base::Current,
+                        offset: 0,
+                    });
+                    range.addresses.push((address, semicolon));
+                }
+            }
+        }
+    }
+
+    /// Parses a command name, returning its full name. `k` and `s` may be
+    /// followed directly by their arguments, as in `ka` and `s/x/y/`.
+    pub fn command(&mut self) -> Result<Option<&'static str>, String> {
+        self.skip_blanks();
+        let single = match self.peek() {
+            None | Some(b'|') => return Ok(None),
+            Some(b'!') => Some("!"),
+            Some(b'=') => Some("="),
+            Some(b'<') => Some("<"),
+            Some(b'>') => Some(">"),
+            Some(b'&') => Some("&"),
+            Some(b'~') => Some("~"),
+            Some(b'#') => Some("number"),
+            _ => None,
+        };
+        if let Some(name) = single {
+            self.pos += 1;
+            return Ok(Some(name));
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let word = std::str::from_utf8(&self.line[start..self.pos]).unwrap_or_default();
+        if word.is_empty() {
+            return Err(plib::tr!(
+                "{}: Not an editor command",
+                String::from_utf8_lossy(&self.line[start..])
+            ));
+        }
+
+        let found = COMMANDS
+            .iter()
+            .find(|(name, abbrev)| word.starts_with(abbrev) && name.starts_with(word));
+        match found {
+            Some((name, _)) => Ok(Some(name)),
+            None if word.len() == 2 && word.starts_with('k') => {
+                // The mark name of "ka"
+                self.pos -= 1;
+                Ok(Some("k"))
+            }
+            None if word.starts_with('s') && word[1..].bytes().all(|b| b"gcr".contains(&b)) => {
+                // "s" repeating the last substitution with flags, as in "sg"
+                self.pos = start + 1;
+                Ok(Some("substitute"))
+            }
+            None => Err(plib::tr!("{}: Not an editor command", word)),
+        }
+    }
+
+    /// A count after a command, such as the 3 of `d 3`.
+    pub fn count(&mut self) -> Result<Option<usize>, String> {
+        self.skip_blanks();
+        match self.number() {
+            Some(0) => Err(plib::tr!("Count must be positive")),
+            count => Ok(count),
+        }
+    }
+
+    /// The print flags after a command.
+    pub fn flags(&mut self) -> Flags {
+        let mut flags = Flags::default();
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                Some(b'#') => flags.number = true,
+                Some(b'l') => flags.list = true,
+                Some(b'p') => flags.print = true,
+                Some(b'+') => flags.offset += 1,
+                Some(b'-') => flags.offset -= 1,
+                _ => return flags,
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// A buffer name after a command, such as the `a` of `d a`.
+    pub fn buffer_name(&mut self) -> Option<u8> {
+        self.skip_blanks();
+        match self.peek() {
+            Some(b) if b.is_ascii_alphabetic() => {
+                self.pos += 1;
+                Some(b)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks that nothing but another command follows.
+    pub fn end(&mut self) -> Result<(), String> {
+        if self.at_command_end() {
+            Ok(())
+        } else {
+            Err(plib::tr!(
+                "Extra characters at end of command: '{}'",
+                String::from_utf8_lossy(&self.line[self.pos..])
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_names() {
+        let name = |line: &str| Parser::new(line.as_bytes()).command().unwrap();
+        assert_eq!(name("d"), Some("delete"));
+        assert_eq!(name("del"), Some("delete"));
+        assert_eq!(name("s/a/b/"), Some("substitute"));
+        assert_eq!(name("se nu"), Some("set"));
+        assert_eq!(name("ch"), Some("change"));
+        assert_eq!(name("chd /"), Some("chdir"));
+        assert_eq!(name("ka"), Some("k"));
+        assert_eq!(name("#"), Some("number"));
+        assert_eq!(name(""), None);
+        assert!(Parser::new(b"bogus").command().is_err());
+        assert!(Parser::new(b"dell").command().is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut parser = Parser::new(b"/a\\/b/+2;?c?-,'x d");
+        let range = parser.range().unwrap();
+        assert_eq!(range.addresses.len(), 3);
+        assert!(matches!(&range.addresses[0].0.base, Base::Search(re, true) if re == b"a/b"));
+        assert_eq!(range.addresses[0].0.offset, 2);
+        assert!(range.addresses[0].1);
+        assert!(matches!(&range.addresses[1].0.base, Base::Search(re, false) if re == b"c"));
+        assert_eq!(range.addresses[1].0.offset, -1);
+        assert!(matches!(range.addresses[2].0.base, Base::Mark(b'x')));
+        assert_eq!(parser.command().unwrap(), Some("delete"));
+    }
+
+    #[test]
+    fn test_argument() {
+        let mut parser = Parser::new(b" a\\|b  | p");
+        assert_eq!(parser.argument(), b"a|b");
+        assert!(parser.eat(b'|'));
+    }
+}
@@ -0,0 +1,102 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! ex patterns: basic regular expressions with the ex extensions, `~`
+//! for the previous replacement text, and the `magic` and `ignorecase`
+//! options.
+
+use plib::regex::Regex;
+
+/// Rewrites an ex pattern as a BRE: `~` becomes the previous replacement
+/// text, and without `magic`, `.`, `[` and `*` are special only when
+/// escaped, as `~` is.
+fn translate(pattern: &[u8], magic: bool, replacement: &[u8]) -> Vec<u8> {
+    let mut bre = Vec::with_capacity(pattern.len());
+    let mut bytes = pattern.iter().copied();
+    while let Some(b) = bytes.next() {
+        let escaped = b == b'\\';
+        let b = if escaped {
+            match bytes.next() {
+                Some(b) => b,
+                None => {
+                    bre.extend_from_slice(b"\\\\");
+                    break;
+                }
+            }
+        } else {
+            b
+        };
+        let special = escaped != magic;
+        match b {
+            b'~' if special => {
+                for &r in replacement {
+                    if b".[]*\\^$".contains(&r) {
+                        bre.push(b'\\');
+                    }
+                    bre.push(r);
+                }
+            }
+            b'~' => bre.push(b'~'),
+            b'.' | b'[' | b'*' if special => bre.push(b),
+            b'.' | b'[' | b'*' => bre.extend([b'\\', b]),
+            _ if escaped => bre.extend([b'\\', b]),
+            _ => bre.push(b),
+        }
+    }
+    bre
+}
+
+/// Compiles an ex pattern; `replacement` is the text `~` stands for.
+pub fn compile(
+    pattern: &[u8],
+    ignorecase: bool,
+    magic: bool,
+    replacement: &[u8],
+) -> Result<Regex, String> {
+    let invalid = || plib::tr!("Invalid pattern '{}'", String::from_utf8_lossy(pattern));
+    let bre = translate(pattern, magic, replacement);
+    if bre.contains(&0) {
+        return Err(invalid());
+    }
+    Regex::new(&bre, false, ignorecase).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    fn find(pattern: &str, magic: bool, text: &str) -> Option<Range<usize>> {
+        let re = compile(pattern.as_bytes(), false, magic, b"a.b").unwrap();
+        re.captures(text.as_bytes())
+            .and_then(|groups| groups[0].clone())
+    }
+
+    #[test]
+    fn test_magic() {
+        assert_eq!(find("b.d", true, "abcd"), Some(1..4));
+        assert_eq!(find("b.d", false, "abcd"), None);
+        assert_eq!(find("b\\.d", false, "abcd"), Some(1..4));
+        assert_eq!(find("x*", false, "ax*"), Some(1..3));
+    }
+
+    #[test]
+    fn test_previous_replacement() {
+        assert_eq!(find("~", true, "xa.by"), Some(1..4));
+        assert_eq!(find("~", true, "xacby"), None);
+        assert_eq!(find("\\~", false, "xa.by"), Some(1..4));
+    }
+
+    #[test]
+    fn test_not_at_line_start() {
+        let re = compile(b"^a", false, true, b"").unwrap();
+        assert!(re.captures_at(b"aa", 0).is_some());
+        assert!(re.captures_at(b"aa", 1).is_none());
+    }
+}
@@ -0,0 +1,100 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+// TODO:
+// - open and visual modes
+// - -r recovery, -t tags, and the tag commands
+// - autoindent in text input, abbreviations and maps
+//
+
+mod editor;
+
+use clap::Parser;
+use editor::Editor;
+
+/// ex - text editor
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Run the command once the first file is read.
+    #[arg(short = 'c', value_name = "COMMAND")]
+    command: Option<String>,
+
+    /// Recover the files after an editor or system crash.
+    #[arg(short = 'r')]
+    recover: bool,
+
+    /// Set the readonly option.
+    #[arg(short = 'R')]
+    readonly: bool,
+
+    /// Batch mode: no prompts or informational messages, and no EXINIT or
+    /// .exrc.
+    #[arg(short = 's')]
+    silent: bool,
+
+    /// Edit the file containing the tag.
+    #[arg(short = 't', value_name = "TAG")]
+    tag: Option<String>,
+
+    /// Begin in visual mode.
+    #[arg(short = 'v')]
+    visual: bool,
+
+    /// Set the window option to the number of lines.
+    #[arg(short = 'w', value_name = "SIZE")]
+    window: Option<usize>,
+
+    /// The files to edit.
+    files: Vec<String>,
+}
+
+fn main() {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    let mut editor = Editor::new(args.files, args.silent);
+    if args.readonly {
+        editor.set_readonly();
+    }
+    if let Some(window) = args.window {
+        editor.set_window(window);
+    }
+
+    let unsupported = if args.recover {
+        Some(plib::tr!("Recovery is not supported"))
+    } else if args.tag.is_some() {
+        Some(plib::tr!("Tags are not supported"))
+    } else if args.visual {
+        Some(plib::tr!("Open and visual modes are not supported"))
+    } else {
+        None
+    };
+    if let Some(message) = unsupported {
+        editor.report_error(&message);
+        std::process::exit(1);
+    }
+
+    let mut startup = if args.silent {
+        Ok(())
+    } else {
+        editor.initialize()
+    };
+    startup = startup.and_then(|()| editor.edit_first());
+    if let Some(command) = &args.command {
+        startup = startup.and_then(|()| editor.execute_top(command.as_bytes()));
+    }
+    if let Err(e) = startup {
+        if !editor.report_error(&e) {
+            std::process::exit(1);
+        }
+    }
+
+    std::process::exit(editor.run());
+}
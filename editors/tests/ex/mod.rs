@@ -0,0 +1,184 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+
+use plib::testing::{run_test_with_options, Fixture, TestOptions, TestPlan};
+
+const TEXT: &str = "one\ntwo\nthree\nfour\n";
+
+/// Runs `ex -s` with the script as standard input, in a directory holding
+/// the files f, a copy of TEXT, and g, returning the fixture to inspect
+/// the files afterwards.
+fn ex_test(
+    args: &[&str],
+    script: &str,
+    expected_out: &str,
+    expected_err: &str,
+    code: i32,
+) -> Fixture {
+    let fixture = Fixture::builder("ex")
+        .file("f", TEXT)
+        .file("g", "a\nb\n")
+        .build();
+    let mut all_args = vec![String::from("-s")];
+    all_args.extend(args.iter().map(|arg| String::from(*arg)));
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("ex"),
+            args: all_args,
+            stdin_data: String::from(script),
+            expected_out: String::from(expected_out),
+            expected_err: String::from(expected_err),
+            expected_exit_code: code,
+        },
+        TestOptions {
+            cwd: Some(fixture.root().to_path_buf()),
+            ..Default::default()
+        },
+    );
+    fixture
+}
+
+#[test]
+fn test_print_addresses() {
+    ex_test(
+        &["f"],
+        "1p\n$p\n/th/p\n?o?,$p\n2;+1p\n.=\n=\n%nu\n",
+        "one\nfour\nthree\ntwo\nthree\nfour\ntwo\nthree\n3\n4\n     1  one\n     2  two\n     3  three\n     4  four\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_marks_and_list() {
+    ex_test(&["f"], "2ka\n$\n'a,'a+1l\n", "four\ntwo$\nthree$\n", "", 0);
+}
+
+#[test]
+fn test_substitute_flags() {
+    let fixture = ex_test(
+        &["f"],
+        "%s/o/0/g\n1s/\\(n\\)e/\\U\\1/p\n4s/r$/[&]/#\ns/^/>/\n&\n3s/e/E/2\nw\n",
+        "0N\n     4  f0u[r]\n",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("f")).unwrap(),
+        "0N\ntw0\nthrEe\n>>f0u[r]\n"
+    );
+}
+
+#[test]
+fn test_global() {
+    let fixture = ex_test(
+        &["f"],
+        "g/o/s/$/!/\nv/!/d\ng/w/\ng/e/a\\\nX\nw\n",
+        "two!\n",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("f")).unwrap(),
+        "one!\nX\ntwo!\nfour!\n"
+    );
+}
+
+#[test]
+fn test_line_commands() {
+    let fixture = ex_test(
+        &["f"],
+        "1,2m$\n1t0\n2,3j\nd a\n$put a\n1c\nfirst\n.\nu\n2i\nsecond\n.\nwq\n",
+        "",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("f")).unwrap(),
+        "three\nsecond\none\ntwo\nthree four\n"
+    );
+}
+
+#[test]
+fn test_set() {
+    ex_test(
+        &["f"],
+        "set nu sw=4\nset sw?\n1p\nset nonu list\n1p\nset bogus\n",
+        "shiftwidth=4\n     1  one\none$\n",
+        "ex: bogus: no such option\n",
+        1,
+    );
+}
+
+#[test]
+fn test_file_commands() {
+    let fixture = ex_test(
+        &["f", "g"],
+        "ar\nn\nar\n%p\ne #\n1p\nrew\nr g\nw! out\nq!\n",
+        "[f] g\nf [g]\na\nb\none\n",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("out")).unwrap(),
+        "one\ntwo\nthree\nfour\na\nb\n"
+    );
+}
+
+#[test]
+fn test_write_checks() {
+    ex_test(
+        &["f"],
+        "1d\nw g\n",
+        "",
+        "ex: \"g\" exists (use w! to overwrite it)\n",
+        1,
+    );
+}
+
+#[test]
+fn test_quit_modified() {
+    ex_test(
+        &["f"],
+        "1d\nq\n",
+        "",
+        "ex: No write since last change (quit! overrides)\n",
+        1,
+    );
+    ex_test(&["f"], "1d\nq!\n", "", "", 0);
+}
+
+#[test]
+fn test_filter() {
+    ex_test(
+        &["f"],
+        "1,3!sort\n%p\nq!\n",
+        "one\nthree\ntwo\nfour\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_command_option() {
+    ex_test(&["-c", "2", "f"], "3p\n", "two\nthree\n", "", 0);
+}
+
+#[test]
+fn test_unknown_command() {
+    ex_test(
+        &["f"],
+        "frob\n1p\n",
+        "",
+        "ex: frob: Not an editor command\n",
+        1,
+    );
+}
@@ -0,0 +1,256 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The default system tests: position-sensitive tests for the binary
+//! formats `file` knows about, and context-sensitive tests that tell
+//! scripts and text in various encodings from data.
+
+/// Interpreters whose scripts are reported as plain "commands text".
+const SHELLS: [&str; 8] = ["sh", "ash", "bash", "dash", "ksh", "mksh", "yash", "zsh"];
+
+fn read_uint(buf: &[u8], offset: usize, size: usize, big_endian: bool) -> Option<u64> {
+    let bytes = buf.get(offset..offset.checked_add(size)?)?;
+    let fold = |n: u64, &b: &u8| (n << 8) | u64::from(b);
+    Some(if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    })
+}
+
+fn elf_machine(machine: u64) -> Option<&'static str> {
+    Some(match machine {
+        2 => "SPARC",
+        3 => "Intel 80386",
+        8 => "MIPS",
+        20 => "PowerPC",
+        21 => "64-bit PowerPC",
+        22 => "IBM S/390",
+        40 => "ARM",
+        43 => "SPARC V9",
+        62 => "x86-64",
+        183 => "ARM aarch64",
+        243 => "RISC-V",
+        258 => "LoongArch",
+        _ => return None,
+    })
+}
+
+/// Whether the ELF file in `buf` has a program interpreter, which tells a
+/// position-independent executable from a shared library.
+fn elf_has_interpreter(buf: &[u8], is_64: bool, big_endian: bool) -> bool {
+    const PT_INTERP: u64 = 3;
+    let (phoff, phentsize, phnum) = if is_64 {
+        (
+            read_uint(buf, 32, 8, big_endian),
+            read_uint(buf, 54, 2, big_endian),
+            read_uint(buf, 56, 2, big_endian),
+        )
+    } else {
+        (
+            read_uint(buf, 28, 4, big_endian),
+            read_uint(buf, 42, 2, big_endian),
+            read_uint(buf, 44, 2, big_endian),
+        )
+    };
+    let (Some(phoff), Some(phentsize), Some(phnum)) = (phoff, phentsize, phnum) else {
+        return false;
+    };
+    (0..phnum).any(|i| {
+        let offset = phoff.saturating_add(i * phentsize);
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| read_uint(buf, offset, 4, big_endian))
+            == Some(PT_INTERP)
+    })
+}
+
+fn elf(buf: &[u8]) -> Option<String> {
+    if !buf.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let is_64 = match buf.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = match buf.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let kind = match read_uint(buf, 16, 2, big_endian)? {
+        1 => "relocatable",
+        2 => "executable",
+        3 if elf_has_interpreter(buf, is_64, big_endian) => "pie executable",
+        3 => "shared object",
+        4 => "core file",
+        _ => "unknown type",
+    };
+    let mut result = format!(
+        "ELF {}-bit {} {}",
+        if is_64 { 64 } else { 32 },
+        if big_endian { "MSB" } else { "LSB" },
+        kind
+    );
+    if let Some(machine) = elf_machine(read_uint(buf, 18, 2, big_endian)?) {
+        result.push_str(", ");
+        result.push_str(machine);
+    }
+    Some(result)
+}
+
+/// Whether `buf` starts with a tar header: one with the ustar magic, or an
+/// old style one whose checksum is right.
+fn is_tar(buf: &[u8]) -> bool {
+    let Some(header) = buf.get(..512) else {
+        return false;
+    };
+    if header[257..263] == *b"ustar\0" || header[257..265] == *b"ustar  \0" {
+        return true;
+    }
+    if header[0] == 0 {
+        return false;
+    }
+    let field = &header[148..156];
+    let digits: String = field
+        .iter()
+        .skip_while(|&&b| b == b' ')
+        .take_while(|b| (b'0'..=b'7').contains(b))
+        .map(|&b| char::from(b))
+        .collect();
+    let Ok(checksum) = u32::from_str_radix(&digits, 8) else {
+        return false;
+    };
+    let sum: u32 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                u32::from(b' ')
+            } else {
+                u32::from(b)
+            }
+        })
+        .sum();
+    sum == checksum
+}
+
+/// Applies the position-sensitive default system tests to `buf`, the
+/// start of a file.
+pub fn position_sensitive(buf: &[u8]) -> Option<String> {
+    if let Some(elf) = elf(buf) {
+        return Some(elf);
+    }
+    let description = match buf {
+        [0x1f, 0x9d, flags, ..] => return Some(format!("compressed data, {} bits", flags & 0x1f)),
+        [0x1f, 0x8b, ..] => "gzip compressed data",
+        [0x1f, 0x1e, ..] => "packed data",
+        [0xfd, b'7', b'z', b'X', b'Z', 0, ..] => "xz compressed data",
+        [b'B', b'Z', b'h', ..] => "bzip2 compressed data",
+        [b'P', b'K', 3, 4, ..] => "Zip archive",
+        [0xc7, 0x71, ..] | [0x71, 0xc7, ..] => "cpio archive",
+        _ if buf.starts_with(b"070707")
+            || buf.starts_with(b"070701")
+            || buf.starts_with(b"070702") =>
+        {
+            "cpio archive"
+        }
+        _ if buf.starts_with(b"!<arch>\n") => "archive",
+        _ if is_tar(buf) => "tar archive",
+        _ => return None,
+    };
+    Some(String::from(description))
+}
+
+/// Whether `b` may appear in text besides the printable ASCII characters.
+fn is_text_control(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\r' | 0x07 | 0x08 | 0x0b | 0x0c | 0x1b)
+}
+
+fn is_text_char(c: char) -> bool {
+    !c.is_control() || (c.is_ascii() && is_text_control(c as u8))
+}
+
+fn is_utf8_text(buf: &[u8], truncated: bool) -> bool {
+    let text = match std::str::from_utf8(buf) {
+        Ok(text) => text,
+        // a character cut in two at the end of what was read
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&buf[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    text.chars().all(is_text_char)
+}
+
+fn is_utf16_text(buf: &[u8], big_endian: bool) -> bool {
+    let units = buf.chunks_exact(2).map(|pair| {
+        let pair = [pair[0], pair[1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    });
+    char::decode_utf16(units).all(|c| c.is_ok_and(is_text_char))
+}
+
+/// Names the character encoding of the text in `buf`, if it is text.
+/// `truncated` tells that the file goes on past `buf`.
+fn text_encoding(buf: &[u8], truncated: bool) -> Option<&'static str> {
+    if let Some(rest) = buf.strip_prefix(b"\xff\xfe") {
+        return is_utf16_text(rest, false).then_some("UTF-16 little-endian");
+    }
+    if let Some(rest) = buf.strip_prefix(b"\xfe\xff") {
+        return is_utf16_text(rest, true).then_some("UTF-16 big-endian");
+    }
+    if buf
+        .iter()
+        .all(|&b| (b' '..=b'~').contains(&b) || is_text_control(b))
+    {
+        return Some("ascii");
+    }
+    if is_utf8_text(buf.strip_prefix(b"\xef\xbb\xbf").unwrap_or(buf), truncated) {
+        return Some("UTF-8");
+    }
+    // the high half of the ISO 8859 character sets, without C1 controls
+    if buf
+        .iter()
+        .all(|&b| (b' '..=b'~').contains(&b) || is_text_control(b) || b >= 0xa0)
+    {
+        return Some("ISO-8859");
+    }
+    None
+}
+
+/// Names the interpreter of a script starting with `#!`: the last
+/// component of its path, or of the command run by `env`.
+fn interpreter(buf: &[u8]) -> Option<String> {
+    let line = buf.strip_prefix(b"#!")?.split(|&b| b == b'\n').next()?;
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    let mut name = words.next()?.rsplit('/').next()?;
+    if name == "env" {
+        name = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+        name = name.rsplit('/').next()?;
+    }
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Applies the context-sensitive default system tests to `buf`, the start
+/// of a file; `truncated` tells that the file goes on past `buf`.
+pub fn context_sensitive(buf: &[u8], truncated: bool) -> Option<String> {
+    let encoding = text_encoding(buf, truncated)?;
+    match interpreter(buf) {
+        Some(name) if SHELLS.contains(&name.as_str()) => Some(String::from("commands text")),
+        Some(name) => Some(format!("{} commands text", name)),
+        None => Some(format!("{} text", encoding)),
+    }
+}
@@ -12,13 +12,14 @@
 // - Questionable behavior:  if write_all() produces Err, the program will
 //   continue to the next file, rather than stopping.
 
-use std::io::{self, Read, Write};
+use std::fs::File;
+use std::io;
 use std::path::PathBuf;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream;
-use plib::BUFSZ;
+use plib::diag::Status;
+use plib::io::{copy_to_stdout, StdoutWriter};
 
 #[derive(Parser)]
 #[command(version, about = gettext("cat - concatenate and print files"))]
@@ -35,17 +36,12 @@ struct Args {
     files: Vec<PathBuf>,
 }
 
-fn cat_file(pathname: &PathBuf) -> io::Result<()> {
-    let mut file = input_stream(pathname, true)?;
-    let mut buffer = [0; BUFSZ];
-
-    loop {
-        let n_read = file.read(&mut buffer[..])?;
-        if n_read == 0 {
-            break;
-        }
-
-        io::stdout().write_all(&buffer[0..n_read])?;
+fn cat_file(pathname: &PathBuf, out: &mut StdoutWriter) -> io::Result<()> {
+    // The input is copied as is, so the kernel can do the copying.
+    if pathname.as_os_str() == "-" {
+        copy_to_stdout(&mut io::stdin().lock(), out)?;
+    } else {
+        copy_to_stdout(&mut File::open(pathname)?, out)?;
     }
 
     Ok(())
@@ -63,14 +59,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.files.push(PathBuf::from("-"));
     }
 
-    let mut exit_code = 0;
+    let mut status = Status::new();
+    let mut out = plib::io::stdout();
 
     for filename in &args.files {
-        if let Err(e) = cat_file(filename) {
-            exit_code = 1;
-            eprintln!("{}: {}", filename.display(), e);
+        if let Err(e) = cat_file(filename, &mut out) {
+            status.operand_error(filename.display(), &e);
         }
     }
 
-    std::process::exit(exit_code)
+    if let Err(e) = out.finish() {
+        status.error(&e);
+    }
+
+    status.exit()
 }
@@ -7,13 +7,13 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, ErrorKind, Read};
+use std::io::{self, BufRead};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use plib::io::input_reader;
+use plib::io::MappedReader;
 
 #[derive(Parser)]
 #[command(version, about = gettext("cmp - compare two files"))]
@@ -52,24 +52,6 @@ struct Args {
     file2: PathBuf,
 }
 
-/// Reads a single byte from a `BufReader`.
-///
-/// Returns the byte that was read as `Ok(Some(byte))`. When encountering EOF,
-/// this function returns `Ok(None)`.
-fn getc(reader: &mut io::BufReader<Box<dyn Read>>) -> io::Result<Option<u8>> {
-    let mut byte: u8 = 0;
-    match reader.read_exact(std::array::from_mut(&mut byte)) {
-        Ok(_) => Ok(Some(byte)),
-        Err(e) => {
-            if e.kind() == ErrorKind::UnexpectedEof {
-                Ok(None)
-            } else {
-                Err(e)
-            }
-        }
-    }
-}
-
 // Helper function to allow using `?` in error handling.
 fn cmp_main(args: &Args) -> io::Result<u8> {
     // Also guards against using stdin for both inputs
@@ -77,59 +59,68 @@ fn cmp_main(args: &Args) -> io::Result<u8> {
         return Ok(0);
     }
 
-    let mut reader1 = input_reader(&args.file1, true)?;
-    let mut reader2 = input_reader(&args.file2, true)?;
+    let mut reader1 = MappedReader::open(&args.file1, true)?;
+    let mut reader2 = MappedReader::open(&args.file2, true)?;
 
     let mut lines: u64 = 1;
     let mut bytes: u64 = 0;
 
     loop {
-        let c1 = getc(&mut reader1)?;
-        let c2 = getc(&mut reader2)?;
-
-        bytes += 1;
-
-        match (c1, c2) {
-            (Some(c1), Some(c2)) => {
-                if c1 != c2 {
-                    if args.silent {
-                        // Don't print anything
-                    } else if args.verbose {
-                        // `{:o}` for the required octal representation output
-                        println!("{} {:o} {:o}", &bytes, c1, c2);
-                    } else {
-                        println!(
-                            "{} {} differ: char {}, line {}",
-                            args.file1.as_os_str().to_string_lossy(),
-                            args.file2.as_os_str().to_string_lossy(),
-                            bytes,
-                            lines
-                        );
-                    }
-                    return Ok(1);
-                }
+        // Compare the data both readers have available, which for mapped
+        // files is the whole file.
+        let buf1 = reader1.fill_buf()?;
+        let buf2 = reader2.fill_buf()?;
+        let n = buf1.len().min(buf2.len());
+
+        if n == 0 {
+            if buf1.is_empty() && buf2.is_empty() {
+                break;
             }
-            (None, None) => break,
 
             // (Some, EOF) or (EOF, Some)
-            (c1, _) => {
-                eprintln!(
-                    "cmp: EOF on {}",
-                    if c1.is_none() {
-                        &args.file1
-                    } else {
-                        &args.file2
-                    }
-                    .as_os_str()
-                    .to_string_lossy()
+            eprintln!(
+                "cmp: EOF on {}",
+                if buf1.is_empty() {
+                    &args.file1
+                } else {
+                    &args.file2
+                }
+                .as_os_str()
+                .to_string_lossy()
+            );
+            return Ok(1);
+        }
+
+        if let Some(pos) = buf1[..n]
+            .iter()
+            .zip(&buf2[..n])
+            .position(|(c1, c2)| c1 != c2)
+        {
+            let (c1, c2) = (buf1[pos], buf2[pos]);
+            bytes += pos as u64 + 1;
+            lines += buf1[..pos].iter().filter(|&&c| c == b'\n').count() as u64;
+
+            if args.silent {
+                // Don't print anything
+            } else if args.verbose {
+                // `{:o}` for the required octal representation output
+                println!("{} {:o} {:o}", &bytes, c1, c2);
+            } else {
+                println!(
+                    "{} {} differ: char {}, line {}",
+                    args.file1.as_os_str().to_string_lossy(),
+                    args.file2.as_os_str().to_string_lossy(),
+                    bytes,
+                    lines
                 );
-                return Ok(1);
             }
+            return Ok(1);
         }
 
-        if c1.map(char::from) == Some('\n') {
-            lines += 1;
-        }
+        bytes += n as u64;
+        lines += buf1[..n].iter().filter(|&&c| c == b'\n').count() as u64;
+        reader1.consume(n);
+        reader2.consume(n);
     }
 
     Ok(0)
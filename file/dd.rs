@@ -7,13 +7,43 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::fs;
-use std::io::{self, Read, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use libc::c_int;
+use plib::diag::error_message;
+use plib::sizestr::parse_size_as;
+use plib::{diag, i18n, tr};
 
 const DEF_BLOCK_SIZE: usize = 512;
 
+/// Signals that ask for a report of the records copied so far.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+const PROGRESS_SIGNALS: [c_int; 2] = [libc::SIGUSR1, libc::SIGINFO];
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+const PROGRESS_SIGNALS: [c_int; 1] = [libc::SIGUSR1];
+
+/// Set by the handler of [`PROGRESS_SIGNALS`], cleared once the report is
+/// written.
+static PROGRESS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn progress_handler(_signal: c_int) {
+    PROGRESS_REQUESTED.store(true, Ordering::Relaxed);
+}
+
 const CONV_ASCII_IBM: [u8; 256] = [
     0x0, 0x1, 0x2, 0x3, 0x37, 0x2d, 0x2e, 0x2f, 0x16, 0x5, 0x25, 0xb, 0xc, 0xd, 0xe, 0xf, 0x10,
     0x11, 0x12, 0x13, 0x3c, 0x3d, 0x32, 0x26, 0x18, 0x19, 0x3f, 0x27, 0x1c, 0x1d, 0x1e, 0x1f, 0x40,
@@ -72,20 +102,23 @@ const CONV_ASCII_EBCDIC: [u8; 256] = [
 ];
 
 #[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, PartialEq)]
 enum AsciiConv {
     Ascii,
     EBCDIC,
     IBM,
 }
 
-enum Conversion {
-    Ascii(AsciiConv),
-    Lcase,
-    Ucase,
-    Swab,
+#[derive(Clone, Copy, PartialEq)]
+enum Case {
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Blocking {
     Block,
     Unblock,
-    Sync,
 }
 
 struct Config {
@@ -93,11 +126,18 @@ struct Config {
     ofile: String,
     ibs: usize,
     obs: usize,
+    /// Set by bs=, which makes each input block an output block of its
+    /// own unless there are conversions.
+    bs: bool,
     cbs: usize,
-    seek: usize,
-    skip: usize,
-    count: usize,
-    conversions: Vec<Conversion>,
+    seek: u64,
+    skip: u64,
+    count: Option<u64>,
+    translation: Option<AsciiConv>,
+    case: Option<Case>,
+    blocking: Option<Blocking>,
+    swab: bool,
+    sync: bool,
     noerror: bool,
     notrunc: bool,
 }
@@ -109,287 +149,520 @@ impl Default for Config {
             ofile: Default::default(),
             ibs: DEF_BLOCK_SIZE,
             obs: DEF_BLOCK_SIZE,
+            bs: false,
             cbs: Default::default(),
             seek: Default::default(),
             skip: Default::default(),
-            count: Default::default(),
-            conversions: Default::default(),
-            noerror: Default::default(),
-            notrunc: Default::default(),
+            count: None,
+            translation: None,
+            case: None,
+            blocking: None,
+            swab: false,
+            sync: false,
+            noerror: false,
+            notrunc: false,
         }
     }
 }
 
-fn convert_ascii(data: &mut [u8], ascii_conv: &AsciiConv) {
-    match ascii_conv {
-        AsciiConv::Ascii => {
-            for byte in data.iter_mut() {
-                *byte = CONV_EBCDIC_ASCII[*byte as usize];
-            }
-        }
-        AsciiConv::EBCDIC => {
-            for byte in data.iter_mut() {
-                *byte = CONV_ASCII_EBCDIC[*byte as usize];
-            }
-        }
-        AsciiConv::IBM => {
-            for byte in data.iter_mut() {
-                *byte = CONV_ASCII_IBM[*byte as usize];
-            }
-        }
+impl Config {
+    /// Whether input blocks are copied as they are, as opposed to being
+    /// converted and gathered into output blocks of obs bytes.
+    fn copies_blocks(&self) -> bool {
+        self.bs
+            && self.translation.is_none()
+            && self.case.is_none()
+            && self.blocking.is_none()
+            && !self.swab
+    }
+}
+
+fn convert_ascii(data: &mut [u8], ascii_conv: AsciiConv) {
+    let table = match ascii_conv {
+        AsciiConv::Ascii => &CONV_EBCDIC_ASCII,
+        AsciiConv::EBCDIC => &CONV_ASCII_EBCDIC,
+        AsciiConv::IBM => &CONV_ASCII_IBM,
+    };
+    for byte in data.iter_mut() {
+        *byte = table[*byte as usize];
     }
 }
 
+/// Swaps each pair of bytes; an odd byte at the end stays in place.
 fn convert_swab(data: &mut [u8]) {
     for chunk in data.chunks_exact_mut(2) {
         chunk.swap(0, 1);
     }
 }
 
-fn convert_lcase(data: &mut [u8]) {
-    for byte in data.iter_mut() {
-        if *byte >= b'A' && *byte <= b'Z' {
-            *byte += 32;
-        }
+fn convert_case(data: &mut [u8], case: Case) {
+    match case {
+        Case::Lower => data.make_ascii_lowercase(),
+        Case::Upper => data.make_ascii_uppercase(),
     }
 }
 
-fn convert_ucase(data: &mut [u8]) {
-    for byte in data.iter_mut() {
-        if *byte >= b'a' && *byte <= b'z' {
-            *byte -= 32;
+/// Numbers of full and partial blocks copied, as reported on standard
+/// error.
+#[derive(Default)]
+struct Stats {
+    full_in: u64,
+    partial_in: u64,
+    full_out: u64,
+    partial_out: u64,
+    /// Input lines cut short to fit in cbs bytes by conv=block.
+    truncated: u64,
+}
+
+impl Stats {
+    fn report(&self) {
+        let mut report = tr!("{}+{} records in", self.full_in, self.partial_in);
+        report.push('\n');
+        report.push_str(&tr!("{}+{} records out", self.full_out, self.partial_out));
+        report.push('\n');
+        if self.truncated > 0 {
+            report.push_str(&if self.truncated == 1 {
+                tr!("{} truncated record", self.truncated)
+            } else {
+                tr!("{} truncated records", self.truncated)
+            });
+            report.push('\n');
         }
+        let _ = io::stderr().write_all(report.as_bytes());
     }
 }
 
-fn convert_sync(data: &mut Vec<u8>, block_size: usize) {
-    let current_len = data.len();
-    if current_len < block_size {
-        data.resize(block_size, 0); // Pad with null bytes (0x00)
-    }
+/// Error from the file named `name`, for the diagnostic.
+fn file_error(name: &str, e: io::Error) -> String {
+    format!("{}: {}", name, error_message(&e))
 }
 
-fn convert_block(data: &mut Vec<u8>, cbs: usize) {
-    let mut result = Vec::new();
-    let mut line = Vec::new();
+fn is_seek_error(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::ESPIPE)
+}
 
-    for &byte in data.iter() {
-        if byte == b'\n' {
-            while line.len() < cbs {
-                line.push(b' ');
-            }
-            result.extend_from_slice(&line[..cbs]);
-            line.clear();
+struct Copier<'a> {
+    config: &'a Config,
+    input: File,
+    input_name: String,
+    output: File,
+    output_name: String,
+    /// Output waiting for a full output block.
+    obuf: Vec<u8>,
+    /// The line being blocked, or the record being unblocked.
+    record: Vec<u8>,
+    /// Whether the line being blocked was already counted as truncated.
+    record_truncated: bool,
+    stats: Stats,
+}
+
+impl<'a> Copier<'a> {
+    fn open(config: &'a Config) -> Result<Self, String> {
+        let (input, input_name) = if config.ifile.is_empty() {
+            let fd = plib::fd::dup(io::stdin()).map_err(|e| file_error("stdin", e))?;
+            (File::from(fd), tr!("standard input"))
         } else {
-            line.push(byte);
-        }
+            let file = File::open(&config.ifile).map_err(|e| file_error(&config.ifile, e))?;
+            (file, config.ifile.clone())
+        };
+        let (output, output_name) = if config.ofile.is_empty() {
+            let fd = plib::fd::dup(io::stdout()).map_err(|e| file_error("stdout", e))?;
+            (File::from(fd), tr!("standard output"))
+        } else {
+            // Truncated at the seek offset instead, unless conv=notrunc
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&config.ofile)
+                .map_err(|e| file_error(&config.ofile, e))?;
+            (file, config.ofile.clone())
+        };
+
+        Ok(Copier {
+            config,
+            input,
+            input_name,
+            output,
+            output_name,
+            obuf: Vec::with_capacity(config.obs),
+            record: Vec::new(),
+            record_truncated: false,
+            stats: Stats::default(),
+        })
+    }
+
+    fn input_error(&self, e: io::Error) -> String {
+        file_error(&self.input_name, e)
     }
 
-    if !line.is_empty() {
-        while line.len() < cbs {
-            line.push(b' ');
+    fn output_error(&self, e: io::Error) -> String {
+        file_error(&self.output_name, e)
+    }
+
+    /// Skips skip= input blocks, by seeking if the input allows it and by
+    /// reading them otherwise.
+    fn skip_input(&mut self) -> Result<(), String> {
+        let blocks = self.config.skip;
+        if blocks == 0 {
+            return Ok(());
+        }
+        let offset = blocks.saturating_mul(self.config.ibs as u64);
+        match self.input.seek(SeekFrom::Current(offset as i64)) {
+            Ok(_) => return Ok(()),
+            Err(e) if !is_seek_error(&e) => return Err(self.input_error(e)),
+            Err(_) => {}
         }
-        result.extend_from_slice(&line[..cbs]);
+        let mut buf = vec![0u8; self.config.ibs];
+        for _ in 0..blocks {
+            if self.read_block(&mut buf)? == 0 {
+                break;
+            }
+        }
+        Ok(())
     }
 
-    *data = result;
-}
+    /// Positions the output seek= output blocks from its start, filling
+    /// the gap with null bytes if it can't seek, and truncates it there
+    /// unless conv=notrunc.
+    fn seek_output(&mut self) -> Result<(), String> {
+        let offset = self.config.seek.saturating_mul(self.config.obs as u64);
+        let is_file = self
+            .output
+            .metadata()
+            .map_err(|e| self.output_error(e))?
+            .is_file();
+        if is_file && !self.config.ofile.is_empty() && !self.config.notrunc {
+            self.output
+                .set_len(offset)
+                .map_err(|e| self.output_error(e))?;
+        }
+        if offset == 0 {
+            return Ok(());
+        }
+        match self.output.seek(SeekFrom::Start(offset)) {
+            Ok(_) => Ok(()),
+            Err(e) if !is_seek_error(&e) => Err(self.output_error(e)),
+            Err(_) => {
+                let zeroes = vec![0u8; self.config.obs];
+                for _ in 0..self.config.seek {
+                    self.output
+                        .write_all(&zeroes)
+                        .map_err(|e| self.output_error(e))?;
+                }
+                Ok(())
+            }
+        }
+    }
 
-fn convert_unblock(data: &mut Vec<u8>, cbs: usize) {
-    let mut result = Vec::new();
-    for chunk in data.chunks(cbs) {
-        let trimmed_chunk = chunk
-            .iter()
-            .rposition(|&b| b != b' ')
-            .map_or(chunk, |pos| &chunk[..=pos]);
-        result.extend_from_slice(trimmed_chunk);
-        result.push(b'\n');
+    fn read_block(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        loop {
+            match self.input.read(buf) {
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.input_error(e)),
+            }
+        }
     }
-    *data = result;
-}
 
-fn apply_conversions(data: &mut Vec<u8>, config: &Config) {
-    for conversion in &config.conversions {
-        match conversion {
-            Conversion::Ascii(ascii_conv) => convert_ascii(data, ascii_conv),
-            Conversion::Lcase => convert_lcase(data),
-            Conversion::Ucase => convert_ucase(data),
-            Conversion::Swab => convert_swab(data),
-            Conversion::Sync => convert_sync(data, config.ibs),
-            Conversion::Block => convert_block(data, config.cbs),
-            Conversion::Unblock => convert_unblock(data, config.cbs),
+    fn write_block(&mut self, data: &[u8]) -> Result<(), String> {
+        if let Err(e) = self.output.write_all(data) {
+            return Err(self.output_error(e));
         }
+        if data.len() == self.config.obs {
+            self.stats.full_out += 1;
+        } else {
+            self.stats.partial_out += 1;
+        }
+        Ok(())
     }
-}
 
-fn copy_convert_file(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ifile: Box<dyn Read> = if config.ifile.is_empty() {
-        Box::new(io::stdin().lock())
-    } else {
-        Box::new(fs::File::open(&config.ifile)?)
-    };
-    let mut ofile: Box<dyn Write> = if config.ofile.is_empty() {
-        Box::new(io::stdout().lock())
-    } else {
-        Box::new(fs::File::create(&config.ofile)?)
-    };
+    /// Adds converted data to the output, writing each output block once
+    /// it is full.
+    fn emit(&mut self, data: &[u8]) -> Result<(), String> {
+        let translated;
+        let mut data = data;
+        if let Some(conv @ (AsciiConv::EBCDIC | AsciiConv::IBM)) = self.config.translation {
+            let mut copy = data.to_vec();
+            convert_ascii(&mut copy, conv);
+            translated = copy;
+            data = &translated;
+        }
 
-    let mut ibuf = vec![0u8; config.ibs];
-    let obuf = vec![0u8; config.obs];
+        while !data.is_empty() {
+            let room = self.config.obs - self.obuf.len();
+            let (now, rest) = data.split_at(room.min(data.len()));
+            self.obuf.extend_from_slice(now);
+            data = rest;
+            if self.obuf.len() == self.config.obs {
+                let block = std::mem::take(&mut self.obuf);
+                self.write_block(&block)?;
+                self.obuf = block;
+                self.obuf.clear();
+            }
+        }
+        Ok(())
+    }
 
-    let mut count = 0;
-    let mut skip = config.skip;
-    let mut seek = config.seek;
+    /// Ends the line being blocked, padding it with spaces to cbs bytes.
+    fn end_line(&mut self) -> Result<(), String> {
+        let mut line = std::mem::take(&mut self.record);
+        line.resize(self.config.cbs, b' ');
+        self.record_truncated = false;
+        self.emit(&line)
+    }
 
-    loop {
-        if skip > 0 {
-            let n = ifile.read(&mut ibuf)?;
-            if n == 0 {
-                break;
+    /// Ends the record being unblocked, without its trailing spaces.
+    fn end_record(&mut self) -> Result<(), String> {
+        let mut record = std::mem::take(&mut self.record);
+        let len = record.iter().rposition(|&b| b != b' ').map_or(0, |i| i + 1);
+        record.truncate(len);
+        record.push(b'\n');
+        self.emit(&record)
+    }
+
+    /// Turns newline terminated lines into records of cbs bytes.
+    fn block(&mut self, data: &[u8]) -> Result<(), String> {
+        for &byte in data {
+            if byte == b'\n' {
+                self.end_line()?;
+            } else if self.record.len() < self.config.cbs {
+                self.record.push(byte);
+            } else if !self.record_truncated {
+                self.record_truncated = true;
+                self.stats.truncated += 1;
             }
-            skip -= n;
-            continue;
         }
+        Ok(())
+    }
 
-        if seek > 0 {
-            let n = ifile.read(&mut ibuf)?;
-            if n == 0 {
-                break;
+    /// Turns records of cbs bytes into newline terminated lines.
+    fn unblock(&mut self, data: &[u8]) -> Result<(), String> {
+        for &byte in data {
+            self.record.push(byte);
+            if self.record.len() == self.config.cbs {
+                self.end_record()?;
             }
-            seek -= n;
-            continue;
         }
+        Ok(())
+    }
 
-        let n = ifile.read(&mut ibuf)?;
-        if n == 0 {
-            break;
+    /// Converts an input block and adds it to the output.
+    fn convert(&mut self, mut data: Vec<u8>) -> Result<(), String> {
+        let config = self.config;
+        if config.sync && data.len() < config.ibs {
+            let pad = if config.blocking.is_some() { b' ' } else { 0 };
+            data.resize(config.ibs, pad);
+        }
+        if config.copies_blocks() {
+            return self.write_block(&data);
+        }
+        if config.swab {
+            convert_swab(&mut data);
+        }
+        if config.translation == Some(AsciiConv::Ascii) {
+            convert_ascii(&mut data, AsciiConv::Ascii);
         }
+        if let Some(case) = config.case {
+            convert_case(&mut data, case);
+        }
+        match config.blocking {
+            Some(Blocking::Block) => self.block(&data),
+            Some(Blocking::Unblock) => self.unblock(&data),
+            None => self.emit(&data),
+        }
+    }
 
-        if config.count > 0 {
-            if count >= config.count {
-                break;
+    /// Writes what is left of the conversions as the last output block.
+    fn finish(&mut self) -> Result<(), String> {
+        if !self.record.is_empty() {
+            match self.config.blocking {
+                Some(Blocking::Block) => self.end_line()?,
+                Some(Blocking::Unblock) => self.end_record()?,
+                None => {}
             }
-            count += 1;
         }
+        if !self.obuf.is_empty() {
+            let block = std::mem::take(&mut self.obuf);
+            self.write_block(&block)?;
+        }
+        Ok(())
+    }
 
-        let mut ibuf = ibuf[..n].to_vec();
+    fn copy(&mut self) -> Result<(), String> {
+        self.skip_input()?;
+        self.seek_output()?;
 
-        apply_conversions(&mut ibuf, config);
+        let mut ibuf = vec![0u8; self.config.ibs];
+        let mut blocks = 0;
+        while self.config.count.map_or(true, |count| blocks < count) {
+            if PROGRESS_REQUESTED.swap(false, Ordering::Relaxed) {
+                self.stats.report();
+            }
 
-        if config.obs != 0 {
-            ofile.write_all(&ibuf)?;
-        } else {
-            ofile.write_all(&obuf[..n])?;
+            let n = match self.read_block(&mut ibuf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if self.config.noerror => {
+                    // report the error and go on past the bad block,
+                    // which conv=sync turns into a block of null bytes
+                    diag!("{}", e);
+                    self.stats.report();
+                    let _ = self.input.seek(SeekFrom::Current(self.config.ibs as i64));
+                    blocks += 1;
+                    self.stats.partial_in += 1;
+                    if self.config.sync {
+                        self.convert(Vec::new())?;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            blocks += 1;
+            if n == self.config.ibs {
+                self.stats.full_in += 1;
+            } else {
+                self.stats.partial_in += 1;
+            }
+            self.convert(ibuf[..n].to_vec())?;
         }
+
+        self.finish()
     }
+}
 
+/// Sets the conversions in the comma separated list of conv=.
+fn parse_conv_list(config: &mut Config, s: &str) -> Result<(), String> {
+    for convstr in s.split(',') {
+        let conflict = match convstr {
+            "ascii" => config.translation.replace(AsciiConv::Ascii).is_some(),
+            "ebcdic" => config.translation.replace(AsciiConv::EBCDIC).is_some(),
+            "ibm" => config.translation.replace(AsciiConv::IBM).is_some(),
+            "block" => config.blocking.replace(Blocking::Block).is_some(),
+            "unblock" => config.blocking.replace(Blocking::Unblock).is_some(),
+            "lcase" => config.case.replace(Case::Lower).is_some(),
+            "ucase" => config.case.replace(Case::Upper).is_some(),
+            "swab" => std::mem::replace(&mut config.swab, true),
+            "sync" => std::mem::replace(&mut config.sync, true),
+            "noerror" => std::mem::replace(&mut config.noerror, true),
+            "notrunc" => std::mem::replace(&mut config.notrunc, true),
+            _ => return Err(tr!("invalid conversion: {}", convstr)),
+        };
+        if conflict {
+            return Err(tr!("conflicting conversion: {}", convstr));
+        }
+    }
     Ok(())
 }
 
-fn parse_conv_list(config: &mut Config, s: &str) -> Result<(), Box<dyn std::error::Error>> {
-    for convstr in s.split(",") {
-        let conversion = match convstr {
-            "ascii" => Conversion::Ascii(AsciiConv::Ascii),
-            "ebcdic" => Conversion::Ascii(AsciiConv::EBCDIC),
-            "ibm" => Conversion::Ascii(AsciiConv::IBM),
-            "block" => Conversion::Block,
-            "unblock" => Conversion::Unblock,
-            "lcase" => Conversion::Lcase,
-            "ucase" => Conversion::Ucase,
-            "swab" => Conversion::Swab,
-            "sync" => Conversion::Sync,
-            "noerror" => {
-                config.noerror = true;
-                continue;
-            }
-            "notrunc" => {
-                config.notrunc = true;
-                continue;
-            }
-            _ => {
-                eprintln!("{}: {}", gettext("invalid conv option"), convstr);
-                return Err("invalid conv option".into());
-            }
+/// Parses a number of bytes: a decimal number with a size suffix, or `c`
+/// (bytes) or `w` (two-byte words), or several of those separated by `x`
+/// to be multiplied.
+fn parse_bytes(s: &str) -> Result<u64, String> {
+    let invalid = |e: &dyn std::fmt::Display| tr!("invalid number '{}': {}", s, e);
+    let mut product: u64 = 1;
+    for factor in s.split('x') {
+        let (factor, scale) = if let Some(factor) = factor.strip_suffix('c') {
+            (factor, 1)
+        } else if let Some(factor) = factor.strip_suffix('w') {
+            (factor, 2)
+        } else {
+            (factor, 1)
         };
-        config.conversions.push(conversion);
+        let value: u64 = parse_size_as(factor).map_err(|e| invalid(&e))?;
+        product = value
+            .checked_mul(scale)
+            .and_then(|value| product.checked_mul(value))
+            .ok_or_else(|| invalid(&tr!("value too large")))?;
     }
-    Ok(())
+    Ok(product)
 }
 
-fn parse_block_size(s: &str) -> Result<usize, Box<dyn std::error::Error>> {
-    let mut s = s.to_string();
-    let mut scale = 1;
-    let suffix = s.pop().unwrap();
-    if suffix.is_alphabetic() {
-        match suffix {
-            'c' => scale = 1,
-            'w' => scale = 2,
-            'b' => scale = 512,
-            'k' | 'K' => scale = 1024,
-            'm' | 'M' => scale = 1024 * 1024,
-            'g' | 'G' => scale = 1024 * 1024 * 1024,
-            _ => {
-                eprintln!("{}: {}", gettext("invalid block size suffix"), suffix);
-                return Err("invalid block size suffix".into());
-            }
-        }
-    } else {
-        s.push(suffix);
+fn parse_block_size(s: &str) -> Result<usize, String> {
+    match parse_bytes(s)?.try_into() {
+        Ok(0) | Err(_) => Err(tr!("invalid block size: {}", s)),
+        Ok(size) => Ok(size),
     }
-    let size = s.parse::<usize>()?;
-    Ok(size * scale)
 }
 
-fn parse_cmdline(args: &[String]) -> Result<Config, Box<dyn std::error::Error>> {
+fn parse_cmdline(args: &[String]) -> Result<Config, String> {
     let mut config = Config::default();
+    let mut ibs = None;
+    let mut obs = None;
+    let mut bs = None;
 
     for arg in args {
         // Split arg into option and argument
-        let (op, oparg) = {
-            match arg.split_once("=") {
-                None => {
-                    let msg = format!("{}: {}", gettext("invalid option"), arg);
-                    eprintln!("{}", msg);
-                    return Err(msg.into());
-                }
-                Some((opt, optarg)) => (opt, optarg.to_string()),
-            }
+        let Some((op, oparg)) = arg.split_once('=') else {
+            return Err(tr!("invalid operand: {}", arg));
         };
 
         // per-option processing
         match op {
-            "if" => config.ifile = oparg,
-            "of" => config.ofile = oparg,
-            "ibs" => config.ibs = parse_block_size(&oparg)?,
-            "obs" => config.obs = parse_block_size(&oparg)?,
-            "bs" => {
-                let block_sz = parse_block_size(&oparg)?;
-                config.ibs = block_sz;
-                config.obs = block_sz;
-            }
-            "cbs" => config.cbs = parse_block_size(&oparg)?,
-            "skip" => config.skip = oparg.parse::<usize>()?,
-            "seek" => config.seek = oparg.parse::<usize>()?,
-            "count" => config.count = oparg.parse::<usize>()?,
-            "conv" => parse_conv_list(&mut config, &oparg)?,
-
-            _ => {
-                eprintln!("{}: {}", gettext("invalid option"), op);
-            }
+            "if" => config.ifile = oparg.to_string(),
+            "of" => config.ofile = oparg.to_string(),
+            "ibs" => ibs = Some(parse_block_size(oparg)?),
+            "obs" => obs = Some(parse_block_size(oparg)?),
+            "bs" => bs = Some(parse_block_size(oparg)?),
+            "cbs" => config.cbs = parse_block_size(oparg)?,
+            "skip" => config.skip = parse_bytes(oparg)?,
+            "seek" => config.seek = parse_bytes(oparg)?,
+            "count" => config.count = Some(parse_bytes(oparg)?),
+            "conv" => parse_conv_list(&mut config, oparg)?,
+            _ => return Err(tr!("invalid operand: {}", arg)),
         }
     }
+
+    // bs= overrides ibs= and obs=
+    if let Some(bs) = bs {
+        config.ibs = bs;
+        config.obs = bs;
+        config.bs = true;
+    } else {
+        config.ibs = ibs.unwrap_or(DEF_BLOCK_SIZE);
+        config.obs = obs.unwrap_or(DEF_BLOCK_SIZE);
+    }
+
+    // with cbs=, ascii also unblocks and ebcdic and ibm also block
+    if config.cbs > 0 && config.blocking.is_none() {
+        config.blocking = match config.translation {
+            Some(AsciiConv::Ascii) => Some(Blocking::Unblock),
+            Some(AsciiConv::EBCDIC | AsciiConv::IBM) => Some(Blocking::Block),
+            None => None,
+        };
+    }
+    if config.cbs == 0 {
+        config.blocking = None;
+    }
+
     Ok(config)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    i18n::init();
 
     let args: Vec<String> = std::env::args().skip(1).collect();
-    let config = parse_cmdline(&args)?;
+    let config = match parse_cmdline(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            diag!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    copy_convert_file(&config)?;
+    let _ = plib::signal::set_handler(&PROGRESS_SIGNALS, progress_handler);
 
-    Ok(())
+    let mut copier = match Copier::open(&config) {
+        Ok(copier) => copier,
+        Err(e) => {
+            diag!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = copier.copy();
+    if let Err(e) = &result {
+        diag!("{}", e);
+    }
+    copier.stats.report();
+    std::process::exit(if result.is_ok() { 0 } else { 1 });
 }
@@ -7,17 +7,23 @@
 // SPDX-License-Identifier: MIT
 //
 
+mod builtin;
 mod magic;
 
-use std::fs::read_link;
+use std::fs::{self, read_link, File};
+use std::io::{self, Read};
 use std::os::unix::fs::FileTypeExt;
-use std::path::PathBuf;
-use std::{fs, io};
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use gettextrs::gettext;
+use plib::diag::error_message;
+use plib::{diag, i18n};
 
-use crate::magic::{get_type_from_magic_file_dbs, DEFAULT_MAGIC_FILE};
+use crate::magic::{MagicFile, DEFAULT_MAGIC_FILE};
+
+/// How much of a file the tests look at.
+const HEAD_SIZE: u64 = 1024 * 1024;
 
 #[derive(Parser)]
 #[command(
@@ -54,63 +60,113 @@ struct Args {
 
     #[arg(
         short = 'm',
-        help = gettext("File containing position-sensitive tests")
+        help = gettext("File containing position-sensitive tests to apply before the default tests")
     )]
     test_file1: Option<PathBuf>,
 
     #[arg(
         short = 'M',
-        help = gettext("File containing additional position-sensitive tests")
+        help = gettext("File containing position-sensitive tests to apply instead of the default tests")
     )]
     test_file2: Option<PathBuf>,
 
     files: Vec<String>,
 }
 
-fn get_magic_files(args: &Args) -> Vec<PathBuf> {
-    // set priority according to the occurence of flags in the args lowest index will get highest priority
-    let mut magic_files: Vec<PathBuf> = Vec::new();
+/// A set of position-sensitive tests.
+enum Tests {
+    Magic(MagicFile),
+    /// The default system tests, with those of the system magic file, if
+    /// there is one, taking priority over the built-in ones.
+    Default(Option<MagicFile>),
+}
 
-    if args.no_further_file_classification {
-        return magic_files;
+impl Tests {
+    fn classify(&self, buf: &[u8]) -> Option<String> {
+        match self {
+            Tests::Magic(magic) => magic.classify(buf),
+            Tests::Default(magic) => magic
+                .as_ref()
+                .and_then(|magic| magic.classify(buf))
+                .or_else(|| builtin::position_sensitive(buf)),
+        }
     }
+}
 
-    let default_magic_file = PathBuf::from(DEFAULT_MAGIC_FILE);
-
-    if let Some(test_file2) = &args.test_file2 {
-        magic_files.push(test_file2.clone());
-
-        if args.default_tests && args.test_file1.is_some() {
-            let raw_args: Vec<String> = std::env::args().collect();
-            let m_index = raw_args.iter().position(|x| x == "-m");
-            let h_index = raw_args.iter().position(|x| x == "-h");
+/// The tests applied to regular files, in order.
+struct Classifier {
+    position_sensitive: Vec<Tests>,
+    context_sensitive: bool,
+}
 
-            if m_index > h_index {
-                magic_files.push(args.test_file1.as_ref().unwrap().clone());
-                magic_files.push(default_magic_file);
-            } else {
-                magic_files.push(default_magic_file);
-                magic_files.push(args.test_file1.as_ref().unwrap().clone());
+impl Classifier {
+    /// Sets up the tests asked for by `-d`, `-m` and `-M`: the default
+    /// tests unless `-M` is given without `-d`, and the tests of the magic
+    /// files in the order the options appear, with the default
+    /// position-sensitive tests last if only `-m` is given.
+    fn new(args: &Args, matches: &clap::ArgMatches) -> Result<Self, String> {
+        let mut sources: Vec<(usize, Option<&PathBuf>)> = Vec::new();
+        for (id, file) in [
+            ("test_file1", &args.test_file1),
+            ("test_file2", &args.test_file2),
+        ] {
+            if let Some(file) = file {
+                sources.push((matches.index_of(id).unwrap_or(0), Some(file)));
             }
-        } else if args.test_file1.is_some() {
-            magic_files.push(args.test_file1.as_ref().unwrap().clone());
-        } else if args.default_tests {
-            magic_files.push(default_magic_file);
         }
-    } else if let Some(test_file1) = &args.test_file1 {
-        magic_files.push(test_file1.clone());
+        let defaults = args.default_tests || args.test_file2.is_none();
+        if defaults {
+            let index = if args.default_tests {
+                matches.index_of("default_tests").unwrap_or(0)
+            } else {
+                usize::MAX
+            };
+            sources.push((index, None));
+        }
+        sources.sort_by_key(|(index, _)| *index);
 
-        if args.test_file2.is_none() && !args.default_tests {
-            magic_files.push(default_magic_file);
+        let mut position_sensitive = Vec::new();
+        for (_, file) in sources {
+            position_sensitive.push(match file {
+                Some(file) => Tests::Magic(
+                    MagicFile::open(file)
+                        .map_err(|e| format!("{}: {}", file.display(), error_message(&e)))?,
+                ),
+                None => Tests::Default(MagicFile::open(Path::new(DEFAULT_MAGIC_FILE)).ok()),
+            });
         }
-    } else {
-        magic_files.push(default_magic_file);
+
+        Ok(Classifier {
+            position_sensitive,
+            context_sensitive: defaults,
+        })
     }
 
-    magic_files
+    fn classify(&self, buf: &[u8], truncated: bool) -> String {
+        self.position_sensitive
+            .iter()
+            .find_map(|tests| tests.classify(buf))
+            .or_else(|| {
+                self.context_sensitive
+                    .then(|| builtin::context_sensitive(buf, truncated))
+                    .flatten()
+            })
+            .unwrap_or_else(|| String::from("data"))
+    }
+}
+
+/// Classifies the regular file at `path`, of `len` bytes.
+fn classify_regular_file(path: &str, len: u64, classifier: &Classifier) -> io::Result<String> {
+    let mut buf = Vec::new();
+    File::open(path)?.take(HEAD_SIZE).read_to_end(&mut buf)?;
+    if buf.is_empty() && len == 0 {
+        return Ok(String::from("empty"));
+    }
+    let truncated = len > buf.len() as u64;
+    Ok(classifier.classify(&buf, truncated))
 }
 
-fn analyze_file(mut path: String, args: &Args, magic_files: &Vec<PathBuf>) {
+fn analyze_file(mut path: String, args: &Args, classifier: &Classifier) {
     if path == "-" {
         path = String::new();
         io::stdin().read_line(&mut path).unwrap();
@@ -172,35 +228,33 @@ fn analyze_file(mut path: String, args: &Args, magic_files: &Vec<PathBuf>) {
     }
     if file_type.is_file() {
         if args.no_further_file_classification {
-            assert!(magic_files.is_empty());
             println!("{path}: regular file");
             return;
         }
-        if met.len() == 0 {
-            println!("{path}: empty");
-            return;
-        }
-        match get_type_from_magic_file_dbs(&PathBuf::from(&path), magic_files) {
-            Some(f_type) => println!("{path}: {f_type}"),
-            None => println!("{path}: data"),
+        match classify_regular_file(&path, met.len(), classifier) {
+            Ok(f_type) => println!("{path}: {f_type}"),
+            Err(_) => println!("{path}: cannot open"),
         }
         return;
     }
     unreachable!();
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    i18n::init();
 
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    let magic_files = get_magic_files(&args);
+    let classifier = match Classifier::new(&args, &matches) {
+        Ok(classifier) => classifier,
+        Err(e) => {
+            diag!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
     for file in &args.files {
-        analyze_file(file.clone(), &args, &magic_files);
+        analyze_file(file.clone(), &args, &classifier);
     }
-
-    Ok(())
 }
@@ -7,38 +7,60 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::collections::HashSet;
-use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
-use std::path::PathBuf;
-use std::{env, fs};
+use std::cmp::Ordering;
+use std::ffi::{CString, OsStr, OsString};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
 
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use regex::Regex;
-use walkdir::{DirEntry, WalkDir};
+use plib::diag::{error_message, Status};
+use plib::modestr::{self, ChmodMode};
+use plib::timefmt::unix_seconds;
+use plib::userdb;
+use plib::walk::{Entry, EntryKind, Order, Symlinks, Walker};
+use plib::{diag, i18n, tr};
 
-#[derive(Clone)]
-enum Expr {
-    And(Box<Expr>),
-    Or(Box<Expr>),
-    Not(Box<Expr>),
-    Name(String),
-    MTime(i64),
-    Path(String),
-    Type(FileType),
-    NoUser,
-    NoGroup,
-    XDev,
-    Prune,
-    Perm(u32),
-    Links(u64),
-    User(String),
-    Group(String),
-    Size(u64, bool),
-    Print,
-    Newer(PathBuf),
+/// Upper bound of the size of the command lines built by `-exec ... {} +`.
+const MAX_ARGS_BYTES: usize = 128 * 1024;
+
+/// Room left in {ARG_MAX} for the utility to run commands of its own.
+const ARG_MAX_HEADROOM: usize = 2048;
+
+const SECS_PER_DAY: i64 = 86400;
+
+/// A numeric argument of a primary: `+n` for more than n, `-n` for less
+/// than n, or `n` for exactly n.
+#[derive(Clone, Copy)]
+struct Num {
+    ordering: Ordering,
+    n: i64,
 }
 
-#[derive(Clone)]
+impl Num {
+    fn parse(s: &str) -> Option<Num> {
+        let (ordering, digits) = if let Some(digits) = s.strip_prefix('+') {
+            (Ordering::Greater, digits)
+        } else if let Some(digits) = s.strip_prefix('-') {
+            (Ordering::Less, digits)
+        } else {
+            (Ordering::Equal, s)
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let n = digits.parse().ok()?;
+        Some(Num { ordering, n })
+    }
+
+    fn matches(&self, value: i64) -> bool {
+        value.cmp(&self.n) == self.ordering
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum FileType {
     BlockDevice,
     CharDevice,
@@ -47,455 +69,586 @@ enum FileType {
     Fifo,
     File,
     Socket,
-    Unknown,
 }
 
-/// Parses a list of tokens representing search criteria, the result is stored in enum Expr
-///
-/// # Arguments
-///
-/// * `tokens` - A vector of string slices representing the tokens to parse.
-///
-/// # Returns
-///
-/// * A vector of `Expr` expressions parsed from the tokens.
-fn parse_expression(tokens: &mut Vec<&str>) -> Vec<Expr> {
-    let mut stack: Vec<Expr> = Vec::new();
-
-    while let Some(&token) = tokens.last() {
-        match token {
-            "-name" => {
-                tokens.pop();
-                if let Some(name) = tokens.pop() {
-                    stack.push(Expr::Name(name.to_string()));
-                }
-            }
-            "-path" => {
-                tokens.pop();
-                if let Some(path) = tokens.pop() {
-                    stack.push(Expr::Path(path.to_string()));
-                }
-            }
-            "-mtime" => {
-                tokens.pop();
-                if let Some(mtime) = tokens.pop() {
-                    if let Ok(mtime) = mtime.parse::<i64>() {
-                        stack.push(Expr::MTime(mtime));
-                    }
-                }
+#[derive(Clone, Copy)]
+enum TimeField {
+    Access,
+    Change,
+    Modify,
+}
+
+/// The utility run by `-exec` or `-ok`.
+struct Exec {
+    /// The utility and its arguments, with `{}` standing for the
+    /// pathname. For the `{} +` form, the `{}` is left out.
+    argv: Vec<OsString>,
+    /// Pathnames collected by the `{} +` form.
+    batch: Option<Vec<OsString>>,
+    /// Ask before running the utility, as `-ok` does.
+    prompt: bool,
+}
+
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Name(CString),
+    Path(CString),
+    NoUser,
+    NoGroup,
+    /// Options that affect the walk, and otherwise evaluate to true.
+    Xdev,
+    Depth,
+    Prune,
+    /// Permission bits to match, and whether they must match exactly
+    /// rather than all be set.
+    Perm(u32, bool),
+    Type(FileType),
+    Links(Num),
+    /// `None` for a name that is neither a user nor a user ID, which no
+    /// file matches.
+    User(Option<u32>),
+    Group(Option<u32>),
+    /// Size in 512-byte blocks, or if set in bytes.
+    Size(Num, bool),
+    Time(TimeField, Num),
+    /// Modification time of the file given, as seconds and nanoseconds.
+    Newer(i64, i64),
+    Print,
+    Exec(Exec),
+}
+
+/// Parses the expression following the pathnames.
+struct ExprParser<'a> {
+    args: &'a [OsString],
+    pos: usize,
+    symlinks: Symlinks,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<&'a OsStr> {
+        self.args.get(self.pos).map(OsString::as_os_str)
+    }
+
+    fn next(&mut self) -> Option<&'a OsStr> {
+        let arg = self.peek()?;
+        self.pos += 1;
+        Some(arg)
+    }
+
+    fn operand(&mut self, primary: &str) -> Result<&'a OsStr, String> {
+        self.next()
+            .ok_or_else(|| tr!("missing argument to {}", primary))
+    }
+
+    fn str_operand(&mut self, primary: &str) -> Result<&'a str, String> {
+        let arg = self.operand(primary)?;
+        arg.to_str()
+            .ok_or_else(|| tr!("invalid argument {} to {}", arg.to_string_lossy(), primary))
+    }
+
+    fn num_operand(&mut self, primary: &str) -> Result<Num, String> {
+        let arg = self.str_operand(primary)?;
+        Num::parse(arg).ok_or_else(|| tr!("invalid argument {} to {}", arg, primary))
+    }
+
+    fn pattern_operand(&mut self, primary: &str) -> Result<CString, String> {
+        let arg = self.operand(primary)?;
+        Ok(CString::new(arg.as_bytes()).unwrap_or_default())
+    }
+
+    fn parse(&mut self) -> Result<Expr, String> {
+        let expr = self.parse_or()?;
+        match self.next() {
+            None => Ok(expr),
+            Some(arg) if arg == ")" => Err(tr!("unexpected )")),
+            Some(arg) => Err(tr!("unexpected argument {}", arg.to_string_lossy())),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(OsStr::new("-o")) {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        loop {
+            match self.peek() {
+                None => break,
+                Some(arg) if arg == "-o" || arg == ")" => break,
+                Some(arg) if arg == "-a" => self.pos += 1,
+                // Juxtaposed expressions are joined by -a
+                Some(_) => {}
             }
-            "-type" => {
-                tokens.pop();
-                if let Some(t) = tokens.pop() {
-                    if t.len() == 1 {
-                        let filetype = match t {
-                            "b" => FileType::BlockDevice,
-                            "c" => FileType::CharDevice,
-                            "d" => FileType::Dir,
-                            "l" => FileType::Symlink,
-                            "p" => FileType::Fifo,
-                            "f" => FileType::File,
-                            "s" => FileType::Socket,
-                            _ => FileType::Unknown,
-                        };
-                        stack.push(Expr::Type(filetype));
-                    }
+            let rhs = self.parse_not()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(OsStr::new("!")) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let arg = self
+            .next()
+            .ok_or_else(|| tr!("missing expression"))?
+            .to_string_lossy();
+
+        let expr = match arg.as_ref() {
+            "(" => {
+                let expr = self.parse_or()?;
+                if self.next() != Some(OsStr::new(")")) {
+                    return Err(tr!("missing )"));
                 }
+                expr
             }
-            "-nouser" => {
-                tokens.pop();
-                stack.push(Expr::NoUser);
-            }
-            "-nogroup" => {
-                tokens.pop();
-                stack.push(Expr::NoGroup);
-            }
-            "-xdev" => {
-                tokens.pop();
-                stack.push(Expr::XDev);
-            }
-            "-prune" => {
-                tokens.pop();
-                stack.push(Expr::Prune);
-            }
+            "-name" => Expr::Name(self.pattern_operand("-name")?),
+            "-path" => Expr::Path(self.pattern_operand("-path")?),
+            "-nouser" => Expr::NoUser,
+            "-nogroup" => Expr::NoGroup,
+            "-xdev" => Expr::Xdev,
+            "-depth" => Expr::Depth,
+            "-prune" => Expr::Prune,
             "-perm" => {
-                tokens.pop();
-                if let Some(perm) = tokens.pop() {
-                    if let Ok(perm) = u32::from_str_radix(perm, 8) {
-                        stack.push(Expr::Perm(perm));
-                    }
-                }
-            }
-            "-links" => {
-                tokens.pop();
-                if let Some(links) = tokens.pop() {
-                    if let Ok(links) = links.parse::<u64>() {
-                        stack.push(Expr::Links(links));
-                    }
-                }
-            }
-            "-user" => {
-                tokens.pop();
-                if let Some(user) = tokens.pop() {
-                    stack.push(Expr::User(user.to_string()));
-                }
+                let mode = self.str_operand("-perm")?;
+                let (mode, exact) = match mode.strip_prefix('-') {
+                    Some(mode) => (mode, false),
+                    None => (mode, true),
+                };
+                let bits = perm_bits(mode).ok_or_else(|| tr!("invalid mode {} to -perm", mode))?;
+                Expr::Perm(bits, exact)
             }
-            "-group" => {
-                tokens.pop();
-                if let Some(group) = tokens.pop() {
-                    stack.push(Expr::Group(group.to_string()));
-                }
+            "-type" => {
+                let t = self.str_operand("-type")?;
+                let file_type = match t {
+                    "b" => FileType::BlockDevice,
+                    "c" => FileType::CharDevice,
+                    "d" => FileType::Dir,
+                    "l" => FileType::Symlink,
+                    "p" => FileType::Fifo,
+                    "f" => FileType::File,
+                    "s" => FileType::Socket,
+                    _ => return Err(tr!("unknown argument to -type: {}", t)),
+                };
+                Expr::Type(file_type)
             }
+            "-links" => Expr::Links(self.num_operand("-links")?),
+            "-user" => Expr::User(userdb::parse_uid(self.str_operand("-user")?)),
+            "-group" => Expr::Group(userdb::parse_gid(self.str_operand("-group")?)),
             "-size" => {
-                tokens.pop();
-                if let Some(size) = tokens.pop() {
-                    let (size, in_bytes) = if let Some(st) = size.strip_suffix('c') {
-                        (st.parse::<u64>().unwrap_or(0), true)
-                    } else {
-                        (size.parse::<u64>().unwrap_or(0), false)
-                    };
-                    stack.push(Expr::Size(size, in_bytes));
-                }
+                let size = self.str_operand("-size")?;
+                let (num, in_bytes) = match size.strip_suffix('c') {
+                    Some(num) => (num, true),
+                    None => (size, false),
+                };
+                let num =
+                    Num::parse(num).ok_or_else(|| tr!("invalid argument {} to -size", size))?;
+                Expr::Size(num, in_bytes)
             }
+            "-atime" => Expr::Time(TimeField::Access, self.num_operand("-atime")?),
+            "-ctime" => Expr::Time(TimeField::Change, self.num_operand("-ctime")?),
+            "-mtime" => Expr::Time(TimeField::Modify, self.num_operand("-mtime")?),
             "-newer" => {
-                tokens.pop();
-                if let Some(file) = tokens.pop() {
-                    stack.push(Expr::Newer(PathBuf::from(file)));
-                }
-            }
-            "-print" => {
-                tokens.pop();
-                stack.push(Expr::Print);
+                let file = self.operand("-newer")?;
+                let metadata = if self.symlinks == Symlinks::Physical {
+                    std::fs::symlink_metadata(file)
+                } else {
+                    std::fs::metadata(file)
+                };
+                let metadata = metadata
+                    .map_err(|e| format!("{}: {}", file.to_string_lossy(), error_message(&e)))?;
+                Expr::Newer(metadata.mtime(), metadata.mtime_nsec())
             }
-            "-a" => {
-                tokens.pop();
-                let expr = parse_expression(tokens);
-                stack.push(Expr::And(Box::new(expr[0].clone())));
-            }
-            "-o" => {
-                tokens.pop();
-                let expr = parse_expression(tokens);
-                stack.push(Expr::Or(Box::new(expr[0].clone())));
+            "-print" => Expr::Print,
+            "-exec" | "-ok" => Expr::Exec(self.parse_exec(&arg)?),
+            _ => return Err(tr!("unknown primary or operator: {}", arg)),
+        };
+        Ok(expr)
+    }
+
+    /// Parses the utility and arguments of `-exec` or `-ok`, up to the
+    /// terminating `;`, or `+` directly after `{}` for `-exec`.
+    fn parse_exec(&mut self, primary: &str) -> Result<Exec, String> {
+        let prompt = primary == "-ok";
+        let mut argv = Vec::new();
+        loop {
+            let arg = self
+                .next()
+                .ok_or_else(|| tr!("missing argument to {}", primary))?;
+            if arg == ";" {
+                break;
             }
-            "!" => {
-                tokens.pop();
-                let expr = parse_expression(tokens);
-                stack.push(Expr::Not(Box::new(expr[0].clone())));
+            if arg == "+" && !prompt && argv.last().is_some_and(|last| last == "{}") {
+                argv.pop();
+                if argv.is_empty() {
+                    break;
+                }
+                return Ok(Exec {
+                    argv,
+                    batch: Some(Vec::new()),
+                    prompt,
+                });
             }
-            _ => {
-                tokens.pop();
-                stack.push(Expr::Path(
-                    PathBuf::from(token).to_string_lossy().to_string(),
-                ));
+            argv.push(arg.to_os_string());
+        }
+        if argv.is_empty() {
+            return Err(tr!("missing argument to {}", primary));
+        }
+        Ok(Exec {
+            argv,
+            batch: None,
+            prompt,
+        })
+    }
+}
+
+/// The permission bits given to `-perm`, in octal or as a symbolic mode
+/// applied to no permissions. The file mode creation mask is not applied
+/// to symbolic modes without a who list, unlike for chmod.
+fn perm_bits(mode: &str) -> Option<u32> {
+    match modestr::parse(mode).ok()? {
+        ChmodMode::Absolute(bits, _) => Some(bits & 0o7777),
+        ChmodMode::Symbolic(mut symbolic) => {
+            for clause in &mut symbolic.clauses {
+                if !(clause.user || clause.group || clause.others) {
+                    clause.user = true;
+                    clause.group = true;
+                    clause.others = true;
+                }
             }
+            Some(modestr::mutate(0, false, &symbolic) & 0o7777)
         }
     }
+}
 
-    stack
+fn fnmatch(pattern: &CString, name: &OsStr) -> bool {
+    let Ok(name) = CString::new(name.as_bytes()) else {
+        return false;
+    };
+    // SAFETY: both are valid C strings.
+    unsafe { libc::fnmatch(pattern.as_ptr(), name.as_ptr(), 0) == 0 }
 }
 
-/// Converts a shell pattern to a regular expression.
-///
-/// # Arguments
-///
-/// * `pattern` - A string slice representing the pattern to convert.
-///
-/// # Returns
-///
-/// * A `Regex` object representing the converted pattern.
-fn pattern_to_regex(pattern: &str) -> Regex {
-    let mut regex_pattern = pattern.replace('?', ".");
-    regex_pattern = regex_pattern.replace('*', ".*");
-
-    let bracket_regex = Regex::new(r"\[(?:[^\]]+)\]").unwrap();
-    regex_pattern = bracket_regex
-        .replace_all(&regex_pattern, |caps: &regex::Captures| {
-            let bracket_content = &caps[0][1..caps[0].len() - 1];
-            format!("[{}]", bracket_content)
-        })
-        .to_string();
+fn file_type(entry: &Entry) -> FileType {
+    let file_type = entry.metadata().file_type();
+    if file_type.is_block_device() {
+        FileType::BlockDevice
+    } else if file_type.is_char_device() {
+        FileType::CharDevice
+    } else if file_type.is_dir() {
+        FileType::Dir
+    } else if file_type.is_symlink() {
+        FileType::Symlink
+    } else if file_type.is_fifo() {
+        FileType::Fifo
+    } else if file_type.is_socket() {
+        FileType::Socket
+    } else {
+        FileType::File
+    }
+}
 
-    Regex::new(&format!("^{}$", regex_pattern)).unwrap()
+/// The size of command lines, as the system allows for.
+fn max_args_size() -> usize {
+    // SAFETY: sysconf has no preconditions.
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let env_size: usize = std::env::vars_os()
+        .map(|(name, value)| name.len() + value.len() + 2)
+        .sum();
+    usize::try_from(arg_max)
+        .unwrap_or(MAX_ARGS_BYTES)
+        .saturating_sub(env_size + ARG_MAX_HEADROOM)
+        .min(MAX_ARGS_BYTES)
 }
 
-/// Executes a command based on the list of expressions and returns the matching file paths.
-///
-/// # Arguments
-///
-/// * `expr` - A slice of `Expr` expressions to execute.
-/// * `files` - A vector of `DirEntry` objects representing the files to be evaluated.
-/// * `root_dev` - A u64 value representing the root device number for `-xdev` expression.
-///
-/// # Returns
-///
-/// * A `Result` containing a vector of `PathBuf` objects representing the paths of the files that match the expressions,
-///   or an error message as a `String`.
-fn evaluate_expression(
-    expr: &[Expr],
-    files: Vec<DirEntry>,
-    root_dev: u64,
-) -> Result<Vec<PathBuf>, String> {
-    let f_path = &expr[0];
-    let mut not_res: Vec<PathBuf> = Vec::new();
-    let mut or_res: Vec<PathBuf> = Vec::new();
-    let mut and_res: Vec<PathBuf> = Vec::new();
-    let mut c_files = files
-        .clone()
-        .into_iter()
-        .map(|f| f.path().to_path_buf())
-        .collect::<HashSet<PathBuf>>();
-    let mut result = Vec::new();
-    let mut first = true;
-    for expression in expr {
-        match expression {
-            Expr::Not(inner) => {
-                let i: Vec<Expr> = vec![f_path.clone(), *inner.clone()];
-                not_res = evaluate_expression(i.as_slice(), files.clone(), root_dev)?;
-            }
-            Expr::Or(inner) => {
-                let i: Vec<Expr> = vec![f_path.clone(), *inner.clone()];
-                or_res = evaluate_expression(i.as_slice(), files.clone(), root_dev)?;
-            }
-            Expr::And(inner) => {
-                let i: Vec<Expr> = vec![f_path.clone(), *inner.clone()];
-                and_res = evaluate_expression(i.as_slice(), files.clone(), root_dev)?;
+fn arg_size(arg: &OsStr) -> usize {
+    arg.len() + 1
+}
+
+/// State shared by the evaluation of the expression for all files.
+struct Context {
+    /// When find started, which `-atime` and the like measure from.
+    now: i64,
+    max_args_size: usize,
+    /// Set by `-prune` for the file being evaluated.
+    prune: bool,
+    status: Status,
+}
+
+/// Runs a utility, returning whether it exited with status zero.
+fn run(argv: &[OsString], ctx: &mut Context) -> bool {
+    // The utility's output goes after what was printed so far
+    let _ = io::stdout().flush();
+    match Command::new(&argv[0]).args(&argv[1..]).status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            ctx.status.operand_error(argv[0].to_string_lossy(), &e);
+            false
+        }
+    }
+}
+
+/// Asks whether to run a command, as `-ok` does, reading the answer from
+/// standard input.
+fn confirm(argv: &[OsString]) -> bool {
+    let cmdline: Vec<_> = argv.iter().map(|arg| arg.to_string_lossy()).collect();
+    eprint!("< {} >? ", cmdline.join(" "));
+    let mut response = String::new();
+    match io::stdin().read_line(&mut response) {
+        Ok(_) => response.to_lowercase().starts_with('y'),
+        Err(_) => false,
+    }
+}
+
+/// Replaces each `{}` in `arg` with `path`.
+fn substitute(arg: &OsStr, path: &OsStr) -> OsString {
+    let arg = arg.as_bytes();
+    let mut out = Vec::with_capacity(arg.len());
+    let mut i = 0;
+    while i < arg.len() {
+        if arg[i..].starts_with(b"{}") {
+            out.extend_from_slice(path.as_bytes());
+            i += 2;
+        } else {
+            out.push(arg[i]);
+            i += 1;
+        }
+    }
+    OsString::from_vec(out)
+}
+
+impl Exec {
+    fn eval(&mut self, path: &Path, ctx: &mut Context) -> bool {
+        let path = path.as_os_str();
+        if self.batch.is_none() {
+            let argv: Vec<_> = self.argv.iter().map(|a| substitute(a, path)).collect();
+            if self.prompt && !confirm(&argv) {
+                return false;
             }
-            _ => {}
+            return run(&argv, ctx);
         }
-        for file in &files {
-            match expression {
-                Expr::And(_) => {
-                    continue;
-                }
-                Expr::Or(_) => {
-                    continue;
-                }
-                Expr::Not(_) => {
-                    continue;
-                }
-                Expr::Name(name) => {
-                    let regex = pattern_to_regex(name);
-                    if !regex.is_match(&file.file_name().to_string_lossy()) {
-                        c_files.remove(file.path());
-                    }
-                }
-                Expr::Path(path) => {
-                    let regex = pattern_to_regex(path);
 
-                    if !regex.is_match(&file.path().to_string_lossy()) && !first {
-                        c_files.remove(file.path());
-                    }
-                }
-                Expr::MTime(days) => {
-                    if let Ok(metadata) = file.metadata() {
-                        let modified = metadata.modified().unwrap();
-                        let duration = std::time::SystemTime::now()
-                            .duration_since(modified)
-                            .unwrap();
-                        if ((duration.as_secs() / 86400) as i64) < (*days) {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::Type(t) => {
-                    let file_type = file.file_type();
-                    let r = match *t {
-                        FileType::BlockDevice => file_type.is_block_device(),
-                        FileType::CharDevice => file_type.is_char_device(),
-                        FileType::Dir => file_type.is_dir(),
-                        FileType::Symlink => file_type.is_symlink(),
-                        FileType::Fifo => file_type.is_fifo(),
-                        FileType::File => file_type.is_file(),
-                        FileType::Socket => file_type.is_socket(),
-                        FileType::Unknown => return Err("Unknown argument to -type".to_string()),
-                    };
-                    if !r {
-                        c_files.remove(file.path());
-                    }
-                }
-                Expr::NoUser => {
-                    if let Ok(metadata) = file.metadata() {
-                        let uid = metadata.uid();
-                        if users::get_user_by_uid(uid).is_some() {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::NoGroup => {
-                    if let Ok(metadata) = file.metadata() {
-                        let gid = metadata.gid();
-                        if users::get_group_by_gid(gid).is_some() {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::XDev => {
-                    if let Ok(metadata) = file.metadata() {
-                        if metadata.dev() != root_dev {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::Prune => {}
-                Expr::Perm(perm) => {
-                    if let Ok(metadata) = file.metadata() {
-                        if metadata.permissions().mode() & 0o777 != *perm {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::Links(links) => {
-                    if let Ok(metadata) = file.metadata() {
-                        if metadata.nlink() != *links {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::User(user) => {
-                    if let Ok(metadata) = file.metadata() {
-                        let uid = metadata.uid();
-                        if let Ok(parsed_uid) = user.parse::<u32>() {
-                            if uid != parsed_uid {
-                                c_files.remove(file.path());
-                            }
-                        }
-                    }
-                }
-                Expr::Group(group) => {
-                    if let Ok(metadata) = file.metadata() {
-                        let gid = metadata.gid();
-                        if let Ok(parsed_gid) = group.parse::<u32>() {
-                            if gid != parsed_gid {
-                                c_files.remove(file.path());
-                            }
-                        } else {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::Size(size, in_bytes) => {
-                    if let Ok(metadata) = file.metadata() {
-                        let file_size = if *in_bytes {
-                            metadata.len()
-                        } else {
-                            (metadata.len() + 511) / 512
-                        };
-                        if file_size < *size {
-                            c_files.remove(file.path());
-                        }
-                    }
-                }
-                Expr::Newer(f) => {
-                    if let Ok(metadata) = fs::metadata(f) {
-                        if let Ok(file_metadata) = file.metadata() {
-                            if file_metadata.modified().unwrap() <= metadata.modified().unwrap() {
-                                c_files.remove(file.path());
-                            }
-                        }
-                    }
-                }
-                Expr::Print if c_files.contains(file.path()) => {
-                    result.push(file.path().to_path_buf());
+        let base_size: usize = self.argv.iter().map(|a| arg_size(a)).sum();
+        let batch = self.batch.as_ref().unwrap();
+        let batch_size: usize = batch.iter().map(|a| arg_size(a)).sum();
+        if !batch.is_empty() && base_size + batch_size + arg_size(path) > ctx.max_args_size {
+            self.flush(ctx);
+        }
+        self.batch.as_mut().unwrap().push(path.to_os_string());
+        true
+    }
+
+    /// Runs the utility for the pathnames collected by the `{} +` form.
+    fn flush(&mut self, ctx: &mut Context) {
+        let Some(batch) = &mut self.batch else {
+            return;
+        };
+        if batch.is_empty() {
+            return;
+        }
+        let mut argv = self.argv.clone();
+        argv.append(batch);
+        if !run(&argv, ctx) {
+            ctx.status.fail();
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&mut self, entry: &Entry, ctx: &mut Context) -> bool {
+        let md = entry.metadata();
+        match self {
+            Expr::And(lhs, rhs) => lhs.eval(entry, ctx) && rhs.eval(entry, ctx),
+            Expr::Or(lhs, rhs) => lhs.eval(entry, ctx) || rhs.eval(entry, ctx),
+            Expr::Not(expr) => !expr.eval(entry, ctx),
+            Expr::Name(pattern) => {
+                let path = entry.path();
+                let name = path.file_name().unwrap_or(path.as_os_str());
+                fnmatch(pattern, name)
+            }
+            Expr::Path(pattern) => fnmatch(pattern, entry.path().as_os_str()),
+            Expr::NoUser => userdb::user_by_uid(md.uid()).is_none(),
+            Expr::NoGroup => userdb::group_by_gid(md.gid()).is_none(),
+            Expr::Xdev | Expr::Depth => true,
+            Expr::Prune => {
+                ctx.prune = true;
+                true
+            }
+            Expr::Perm(bits, exact) => {
+                let mode = md.mode() & 0o7777;
+                if *exact {
+                    mode == *bits
+                } else {
+                    mode & *bits == *bits
                 }
-                Expr::Print if !c_files.contains(file.path()) => {
-                    continue;
+            }
+            Expr::Type(t) => file_type(entry) == *t,
+            Expr::Links(num) => num.matches(i64::try_from(md.nlink()).unwrap_or(i64::MAX)),
+            Expr::User(uid) => *uid == Some(md.uid()),
+            Expr::Group(gid) => *gid == Some(md.gid()),
+            Expr::Size(num, in_bytes) => {
+                let size = if *in_bytes {
+                    md.size()
+                } else {
+                    md.size().div_ceil(512)
+                };
+                num.matches(i64::try_from(size).unwrap_or(i64::MAX))
+            }
+            Expr::Time(field, num) => {
+                let time = match field {
+                    TimeField::Access => md.atime(),
+                    TimeField::Change => md.ctime(),
+                    TimeField::Modify => md.mtime(),
+                };
+                num.matches((ctx.now - time).div_euclid(SECS_PER_DAY))
+            }
+            Expr::Newer(secs, nsecs) => (md.mtime(), md.mtime_nsec()) > (*secs, *nsecs),
+            Expr::Print => {
+                let mut out = io::stdout().lock();
+                let path = entry.path().as_os_str().as_bytes();
+                if let Err(e) = out.write_all(path).and_then(|_| out.write_all(b"\n")) {
+                    ctx.status.error(&e);
                 }
-                _ => return Err("Error: Invalid expression".to_string()),
+                true
             }
+            Expr::Exec(exec) => exec.eval(entry.path(), ctx),
         }
-        first = false;
     }
 
-    if result.is_empty() {
-        result.extend(c_files.clone());
+    /// Whether the expression has an action, without which matching
+    /// files are printed.
+    fn has_action(&self) -> bool {
+        match self {
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => lhs.has_action() || rhs.has_action(),
+            Expr::Not(expr) => expr.has_action(),
+            Expr::Print | Expr::Exec(_) => true,
+            _ => false,
+        }
     }
 
-    let set: std::collections::HashSet<_> = not_res.iter().cloned().collect();
-    result.retain(|x| !set.contains(x));
-
-    result.extend(or_res);
-
-    let and_set: std::collections::HashSet<_> = and_res.iter().cloned().collect();
-    if !and_set.is_empty() {
-        result.retain(|x| and_set.contains(x));
+    /// Whether the expression contains any of the primaries `f` picks.
+    fn contains(&self, f: fn(&Expr) -> bool) -> bool {
+        match self {
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => lhs.contains(f) || rhs.contains(f),
+            Expr::Not(expr) => expr.contains(f),
+            expr => f(expr),
+        }
     }
 
-    result.sort();
-    Ok(result)
+    fn flush(&mut self, ctx: &mut Context) {
+        match self {
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                lhs.flush(ctx);
+                rhs.flush(ctx);
+            }
+            Expr::Not(expr) => expr.flush(ctx),
+            Expr::Exec(exec) => exec.flush(ctx),
+            _ => {}
+        }
+    }
 }
 
-/// Retrieves the root path from a list of expressions.
-///
-/// # Arguments
-///
-/// * `expr` - A slice of `Expr` expressions.
-///
-/// # Returns
-///
-/// * A `String` representing the root path extracted from the expressions.
-fn get_root(expr: &[Expr]) -> String {
-    let mut first = true;
-
-    let path = expr
-        .iter()
-        .find_map(|i| match i {
-            Expr::Path(p) if first => {
-                first = false;
-                Some(p.to_string())
+fn find(args: &[OsString]) -> Result<Status, String> {
+    let mut symlinks = Symlinks::Physical;
+    let mut pos = 0;
+    while let Some(arg) = args.get(pos) {
+        match arg.to_str() {
+            Some("-H") => symlinks = Symlinks::CommandLine,
+            Some("-L") => symlinks = Symlinks::Logical,
+            Some("--") => {
+                pos += 1;
+                break;
             }
-            _ => None,
-        })
-        .unwrap_or_else(String::new);
+            _ => break,
+        }
+        pos += 1;
+    }
 
-    path
-}
+    let start = pos;
+    while let Some(arg) = args.get(pos) {
+        let bytes = arg.as_bytes();
+        if (bytes.starts_with(b"-") && bytes.len() > 1) || bytes == b"!" || bytes == b"(" {
+            break;
+        }
+        pos += 1;
+    }
+    let paths = &args[start..pos];
+    if paths.is_empty() {
+        return Err(tr!("missing path operand"));
+    }
 
-/// Executes the find command with the provided arguments.
-///
-/// # Arguments
-///
-/// * `args` - A vector of `String` representing the command-line arguments.
-///
-/// # Returns
-///
-/// * A `Result` indicating success or containing an error message as a `String`.
-fn find(args: Vec<String>) -> Result<(), String> {
-    let mut tokens: Vec<&str> = args.iter().skip(1).rev().map(|s| s.as_str()).collect();
-    let binding = parse_expression(&mut tokens);
-    let expr = binding.as_slice();
-    let path = get_root(expr);
-
-    let root_dev = if let Ok(metadata) = fs::metadata(path.clone()) {
-        metadata.dev()
+    let mut parser = ExprParser {
+        args: &args[pos..],
+        pos: 0,
+        symlinks,
+    };
+    let mut expr = if parser.peek().is_some() {
+        let expr = parser.parse()?;
+        if expr.has_action() {
+            expr
+        } else {
+            Expr::And(Box::new(expr), Box::new(Expr::Print))
+        }
     } else {
-        return Err("Error: Could not retrieve root device metadata".to_string());
+        Expr::Print
     };
 
-    let files = WalkDir::new(path)
-        .into_iter()
-        .map(|f| f.unwrap())
-        .collect::<Vec<DirEntry>>();
-    let result = evaluate_expression(expr, files, root_dev);
+    let depth = expr.contains(|e| matches!(e, Expr::Depth));
+    let xdev = expr.contains(|e| matches!(e, Expr::Xdev));
+    let mut ctx = Context {
+        now: unix_seconds(SystemTime::now()),
+        max_args_size: max_args_size(),
+        prune: false,
+        status: Status::new(),
+    };
 
-    for res in result? {
-        println!("{}", res.display())
+    for path in paths {
+        let mut walker = Walker::new(path)
+            .symlinks(symlinks)
+            .order(if depth {
+                Order::PostOrder
+            } else {
+                Order::PreOrder
+            })
+            .same_file_system(xdev);
+        while let Some(entry) = walker.next() {
+            ctx.prune = false;
+            expr.eval(&entry, &mut ctx);
+            if ctx.prune && entry.kind() == EntryKind::Dir {
+                walker.skip_current_dir();
+            }
+        }
+        if walker.failed() {
+            ctx.status.fail();
+        }
     }
-    Ok(())
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
 
-    let args: Vec<String> = env::args().collect();
+    expr.flush(&mut ctx);
+    Ok(ctx.status)
+}
 
-    let mut exit_code = 0;
+fn main() {
+    i18n::init();
 
-    if let Err(err) = find(args) {
-        exit_code = 1;
-        eprint!("{}", err);
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    match find(&args) {
+        Ok(status) => status.exit(),
+        Err(err) => {
+            diag!("{}", err);
+            std::process::exit(1);
+        }
     }
-
-    std::process::exit(exit_code)
 }
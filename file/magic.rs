@@ -7,14 +7,10 @@
 // SPDX-License-Identifier: MIT
 //
 
-use regex::Regex;
-use std::{
-    error::Error,
-    fmt,
-    fs::File,
-    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom},
-    path::PathBuf,
-};
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::Path;
 
 #[cfg(target_os = "macos")]
 /// Default raw (text based) magic file
@@ -23,15 +19,8 @@ pub const DEFAULT_MAGIC_FILE: &str = "/usr/share/file/magic/magic";
 /// Default raw (text based) magic file
 pub const DEFAULT_MAGIC_FILE: &str = "/etc/magic";
 
-/// Get type for the file from the magic file databases (traversed in order of argument)
-pub fn get_type_from_magic_file_dbs(
-    test_file: &PathBuf,
-    magic_file_dbs: &[PathBuf],
-) -> Option<String> {
-    magic_file_dbs.iter().find_map(|magic_file| {
-        parse_magic_file_and_test(&PathBuf::from(magic_file), &PathBuf::from(test_file)).ok()
-    })
-}
+/// Longest string printed for an `x` string test.
+const MAX_STRING_VALUE: usize = 64;
 
 /// Errors that can occur during parsing of a raw magic line.
 #[allow(clippy::enum_variant_names)]
@@ -43,318 +32,191 @@ enum RawMagicLineParseError {
     /// Indicates that the type format is invalid.
     InvalidTypeFormat,
 
-    /// Indicates that a regular expression used for parsing is invalid.
-    InvalidRegex,
-
     /// Indicates that a the value field is invalid
     InvalidValue,
 }
 
-impl Error for RawMagicLineParseError {}
-
-impl fmt::Display for RawMagicLineParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                RawMagicLineParseError::InvalidOffsetFormat => "Invalid offset!",
-                RawMagicLineParseError::InvalidTypeFormat => "Invalid type!",
-                RawMagicLineParseError::InvalidRegex => "Invalid Regex!",
-                RawMagicLineParseError::InvalidValue => "Invalid value!",
-            }
-        )
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum ComparisonOperator {
     Equal,
     LessThan,
     GreaterThan,
     AllSet,
     AnyUnset,
-    FileLargeEnough,
+    Any,
 }
 
 #[derive(Debug)]
 enum Value {
-    String(String),
+    String(Vec<u8>),
+    AnyString,
     Number(ComparisonOperator, u64),
 }
 
+/// What a test read from the file, for the message's printf conversion.
+enum Matched {
+    Bytes(Vec<u8>),
+    Signed(i64),
+    Unsigned(u64),
+}
+
 impl Value {
-    fn parse(mut input: String, _type: Type) -> Result<Value, RawMagicLineParseError> {
+    fn parse(input: &str, _type: Type) -> Result<Value, RawMagicLineParseError> {
         match _type {
-            Type::String => {
-                let mut result = String::new();
-                let mut chars = input.chars();
-
-                // replace character escape sequences with their characters
-                while let Some(c) = chars.next() {
-                    if c == '\\' {
-                        if let Some(escaped) = chars.next() {
-                            let replacement = match escaped {
-                                '\\' => '\\',
-                                'a' => '\x07', // alert
-                                'b' => '\x08', // backspace
-                                'f' => '\x0C', // form feed
-                                'n' => '\n',   // newline
-                                'r' => '\r',   // carriage return
-                                't' => '\t',   // horizontal tab
-                                'v' => '\x0B', // vertical tab
-                                ' ' => ' ',    // space
-                                _ => {
-                                    result.push('\\');
-                                    escaped
-                                } // Treat any other character as itself
-                            };
-                            result.push(replacement);
-                        } else {
-                            result.push('\\');
-                        }
-                    } else {
-                        result.push(c);
-                    }
+            Type::String if input == "x" => Ok(Value::AnyString),
+            Type::String => Ok(Value::String(unescape(input))),
+            Type::Decimal(..) | Type::Unsigned(..) => {
+                let (comp, rest) = match input.chars().next() {
+                    Some('=') => (ComparisonOperator::Equal, &input[1..]),
+                    Some('<') => (ComparisonOperator::LessThan, &input[1..]),
+                    Some('>') => (ComparisonOperator::GreaterThan, &input[1..]),
+                    Some('&') => (ComparisonOperator::AllSet, &input[1..]),
+                    Some('^') => (ComparisonOperator::AnyUnset, &input[1..]),
+                    _ if input == "x" => return Ok(Value::Number(ComparisonOperator::Any, 0)),
+                    _ => (ComparisonOperator::Equal, input),
+                };
+                let num = match rest.strip_prefix('-') {
+                    Some(digits) => parse_number(digits).map(|n| n.wrapping_neg()),
+                    None => parse_number(rest),
                 }
-
-                result = Self::replace_all_octal_sequences_with_their_coded_values(&input)?;
-
-                Ok(Value::String(result))
-            }
-            _ => {
-                let (comp, num) =
-                    Self::parse_number(&mut input).ok_or(RawMagicLineParseError::InvalidValue)?;
+                .ok_or(RawMagicLineParseError::InvalidValue)?;
                 Ok(Value::Number(comp, num))
             }
         }
     }
+}
 
-    /// Replace the octal sequences in the string with the value they represent
-    /// in utf8
-    fn replace_all_octal_sequences_with_their_coded_values(
-        input: &str,
-    ) -> Result<String, RawMagicLineParseError> {
-        // replace octal sequences with specific coded values
-        let re = Regex::new(r"\\([0-7]{1,3})").map_err(|_| RawMagicLineParseError::InvalidRegex)?;
-
-        let result = re
-            .replace_all(input, |capture: &regex::Captures| {
-                let mat = capture.get(1).unwrap().as_str();
-
-                let v = u32::from_str_radix(mat, 8).unwrap();
-                let c = char::from_u32(v).unwrap();
-                c.to_string()
-            })
-            .to_string();
-        Ok(result)
-    }
-
-    fn parse_number(input: &mut String) -> Option<(ComparisonOperator, u64)> {
-        let regex = Regex::new(r"^[=<>&^x]").unwrap();
-        let comparision_op = match regex.find(input) {
-            Some(mat) => {
-                let comp = mat.as_str().chars().next().unwrap();
-                input.replace_range(..1, ""); // Remove the matched operator
-                match comp {
-                    '=' => ComparisonOperator::Equal,
-                    '<' => ComparisonOperator::LessThan,
-                    '>' => ComparisonOperator::GreaterThan,
-                    '&' => ComparisonOperator::AllSet,
-                    '^' => ComparisonOperator::AnyUnset,
-                    'x' => ComparisonOperator::FileLargeEnough,
-                    _ => return None,
+/// Replaces the escape sequences of a string value with the bytes they
+/// stand for.
+fn unescape(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' || i + 1 == bytes.len() {
+            result.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        let escaped = bytes[i];
+        i += 1;
+        match escaped {
+            b'a' => result.push(b'\x07'),
+            b'b' => result.push(b'\x08'),
+            b'f' => result.push(b'\x0c'),
+            b'n' => result.push(b'\n'),
+            b'r' => result.push(b'\r'),
+            b't' => result.push(b'\t'),
+            b'v' => result.push(b'\x0b'),
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                let mut digits = 1;
+                while digits < 3 && i < bytes.len() && (b'0'..=b'7').contains(&bytes[i]) {
+                    value = value * 8 + u32::from(bytes[i] - b'0');
+                    digits += 1;
+                    i += 1;
                 }
+                result.push(value as u8);
             }
-            None => ComparisonOperator::Equal,
-        };
-
-        let num = parse_number(input)?;
-        Some((comparision_op, num))
+            // Treat any other character, `\\` and `\ ` included, as itself
+            _ => result.push(escaped),
+        }
     }
+    result
 }
 
 /// Parses Hexadecimal, Octal and Unsigned Decimal
-// TODO
-#[allow(clippy::manual_map)] // TODO remove this
-#[allow(clippy::needless_match)] // TODO remove this
-fn parse_number(input: &mut String) -> Option<u64> {
-    if let Some(hex_num) = parse_hexadecimal(input) {
-        Some(hex_num)
-    } else if let Some(dec_oct_num) = parse_decimal_octal(input) {
-        Some(dec_oct_num)
+fn parse_number(input: &str) -> Option<u64> {
+    if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if input.len() > 1 && input.starts_with('0') {
+        u64::from_str_radix(&input[1..], 8).ok()
     } else {
-        None
+        input.parse().ok()
     }
 }
 
-fn parse_hexadecimal(input: &mut String) -> Option<u64> {
-    let re = Regex::new(r"^0[xX]([0-9A-F]+)").ok()?;
-
-    let _input = input.clone();
-    let captures = re.captures(&_input)?;
-    let expr_match = captures.get(0)?;
-    let group_match = captures.get(1)?;
-
-    *input = input.replacen(expr_match.as_str(), "", 1);
-    u64::from_str_radix(group_match.as_str(), 16).ok()
-}
-
-fn parse_decimal_octal(input: &mut String) -> Option<u64> {
-    let re = Regex::new(r"^(0?[0-9]+)").ok()?;
-
-    let _input = input.clone();
-    let captures = re.captures(&_input)?;
-    let expr_match = captures.get(0)?;
-    let group_match = captures.get(1)?;
-
-    *input = input.replacen(expr_match.as_str(), "", 1);
-
-    let radix = if group_match.as_str().starts_with('0') {
-        8
-    } else {
-        10
-    };
-
-    u64::from_str_radix(group_match.as_str(), radix).ok()
-}
-
 /// Type field of the magic file
 ///
 /// It explains the type of hold by the "value" field
 #[derive(Debug, Clone, Copy)]
 enum Type {
-    Decimal(u64, Option<u64>),
-    Unsigned(u64, Option<u64>),
+    Decimal(usize, Option<u64>),
+    Unsigned(usize, Option<u64>),
     String,
 }
 
 impl Type {
-    fn parse(mut input: String) -> Result<Type, RawMagicLineParseError> {
-        Self::replace_verbose_type_string_with_short_ones(&mut input);
-
-        let tsc = Self::parse_type_specification_char(&mut input)
-            .ok_or(RawMagicLineParseError::InvalidTypeFormat)?;
-
-        let _type = match tsc {
-            'd' | 'u' => {
-                // parse decimal and unsigned type
-                let no_of_bytes = Self::parse_no_of_bytes_represented(&mut input)?;
-                let mask = Self::parse_mask(&mut input);
-                if tsc == 'u' {
-                    Ok(Type::Decimal(no_of_bytes, mask))
-                } else {
-                    Ok(Type::Unsigned(no_of_bytes, mask))
-                }
-            }
-            's' => Ok(Type::String),
-            _ => return Err(RawMagicLineParseError::InvalidTypeFormat),
+    fn parse(input: &str) -> Result<Type, RawMagicLineParseError> {
+        let (input, mask) = match input.split_once('&') {
+            Some((input, mask)) => (
+                input,
+                Some(parse_number(mask).ok_or(RawMagicLineParseError::InvalidTypeFormat)?),
+            ),
+            None => (input, None),
         };
 
-        // the input string should be empty after all the steps being followed
-        if !input.is_empty() {
-            return Err(RawMagicLineParseError::InvalidTypeFormat);
-        }
-
-        _type
-    }
+        // As strings "byte", "short", "long" and "string" can also be
+        // represented by dC, dS, dL and s
+        let input = match input {
+            "byte" => "dC",
+            "short" => "dS",
+            "long" => "dL",
+            "string" => "s",
+            _ => input,
+        };
 
-    /// As strings "byte", "short", "long" and "string" can also be represented by dC, dS, dL and s
-    /// So, we'll replace the verbose ones with short ones
-    fn replace_verbose_type_string_with_short_ones(input: &mut String) {
-        if input.starts_with("byte") {
-            input.replace_range(0..4, "dC");
-        } else if input.starts_with("short") {
-            input.replace_range(0..5, "dS");
-        } else if input.starts_with("long") {
-            input.replace_range(0..4, "dL");
-        } else if input.starts_with("string") {
-            input.replace_range(0..6, "s");
+        let mut chars = input.chars();
+        let tsc = chars.next();
+        let size = chars.as_str();
+        match tsc {
+            Some('s') if size.is_empty() && mask.is_none() => Ok(Type::String),
+            Some('d') => Ok(Type::Decimal(Self::parse_size(size)?, mask)),
+            Some('u') => Ok(Type::Unsigned(Self::parse_size(size)?, mask)),
+            _ => Err(RawMagicLineParseError::InvalidTypeFormat),
         }
     }
 
-    /// Get the type specification character
-    /// 'd', 's' or 'u'
-    fn parse_type_specification_char(input: &mut String) -> Option<char> {
-        input
-            .chars()
-            .next()
-            .and_then(|first_char| match first_char {
-                'd' | 's' | 'u' => {
-                    input.remove(0);
-                    Some(first_char)
-                }
-                _ => None,
-            })
-    }
-
     /// Parses the number of bytes represented by the type.
-    fn parse_no_of_bytes_represented(input: &mut String) -> Result<u64, RawMagicLineParseError> {
-        let re =
-            Regex::new(r"^([CSIL]|\d+)").map_err(|_| RawMagicLineParseError::InvalidTypeFormat)?;
-
-        let _input = input.clone();
-        let captures = re
-            .captures(&_input)
-            .ok_or(RawMagicLineParseError::InvalidTypeFormat)?;
-
-        let mat = captures
-            .get(1)
-            .ok_or(RawMagicLineParseError::InvalidTypeFormat)?;
-
-        let size = match mat.as_str() {
+    fn parse_size(input: &str) -> Result<usize, RawMagicLineParseError> {
+        let size = match input {
+            "" | "I" => size_of::<libc::c_int>(),
             "C" => 1,
-            "S" => 2,
-            "I" => 4,
-            "L" => 8,
-            _ => mat
-                .as_str()
-                .parse::<u64>()
+            "S" => size_of::<libc::c_short>(),
+            "L" => size_of::<libc::c_long>(),
+            _ => input
+                .parse()
                 .map_err(|_| RawMagicLineParseError::InvalidTypeFormat)?,
         };
-
-        input.replace_range(0..mat.as_str().len(), "");
-
-        Ok(size)
-    }
-
-    fn parse_mask(input: &mut String) -> Option<u64> {
-        if let Some(start) = input.chars().next() {
-            if start == '&' {
-                input.remove(0);
-                return parse_number(input);
-            }
+        match size {
+            1 | 2 | 4 | 8 => Ok(size),
+            _ => Err(RawMagicLineParseError::InvalidTypeFormat),
         }
-        None
     }
 }
 
 /// Offset field of the magic file
 #[derive(Debug)]
 struct Offset {
-    num: u64,
-    is_continuation: bool,
+    num: usize,
+    /// Number of `>` before the offset: the line is tested only if the
+    /// latest line of the level below matched.
+    level: usize,
 }
 
 impl Offset {
-    fn parse(mut input: String) -> Result<Self, RawMagicLineParseError> {
-        let is_continuation = match input.chars().next() {
-            Some('>') => {
-                input.remove(0);
-                true
-            }
-            _ => false,
-        };
-
-        let num = parse_number(&mut input).ok_or(RawMagicLineParseError::InvalidOffsetFormat)?;
-
-        if !input.is_empty() {
-            return Err(RawMagicLineParseError::InvalidOffsetFormat);
-        }
+    fn parse(input: &str) -> Result<Self, RawMagicLineParseError> {
+        let num = input.trim_start_matches('>');
+        let level = input.len() - num.len();
+        let num = parse_number(num).ok_or(RawMagicLineParseError::InvalidOffsetFormat)?;
 
         Ok(Offset {
-            num,
-            is_continuation,
+            num: usize::try_from(num).map_err(|_| RawMagicLineParseError::InvalidOffsetFormat)?,
+            level,
         })
     }
 }
@@ -367,15 +229,32 @@ struct RawMagicFileLine {
     message: String,
 }
 
+/// Splits off the first blank separated field of `input`; a backslash
+/// escapes a blank within the field.
+fn next_field(input: &str) -> Option<(&str, &str)> {
+    let input = input.trim_start_matches([' ', '\t']);
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            ' ' | '\t' if !escaped => return Some((&input[..i], &input[i..])),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    (!input.is_empty()).then_some((input, ""))
+}
+
 impl RawMagicFileLine {
-    fn parse(input: String) -> Result<Self, RawMagicLineParseError> {
-        let input = Self::normalize_whitespace(input)?;
-        let fields: Vec<&str> = input.splitn(4, " ").collect();
+    fn parse(input: &str) -> Result<Self, RawMagicLineParseError> {
+        let (offset, rest) =
+            next_field(input).ok_or(RawMagicLineParseError::InvalidOffsetFormat)?;
+        let (_type, rest) = next_field(rest).ok_or(RawMagicLineParseError::InvalidTypeFormat)?;
+        let (value, rest) = next_field(rest).ok_or(RawMagicLineParseError::InvalidValue)?;
 
-        let offset = Offset::parse(fields[0].to_string())?;
-        let _type = Type::parse(fields[1].to_string())?;
-        let value = Value::parse(fields[2].to_string(), _type)?;
-        let message = fields[3].to_string();
+        let offset = Offset::parse(offset)?;
+        let _type = Type::parse(_type)?;
+        let value = Value::parse(value, _type)?;
+        let message = rest.trim_start_matches([' ', '\t']).to_string();
 
         Ok(RawMagicFileLine {
             offset,
@@ -385,124 +264,223 @@ impl RawMagicFileLine {
         })
     }
 
-    fn normalize_whitespace(input: String) -> Result<String, RawMagicLineParseError> {
-        let re = Regex::new(r"[ \t]+").map_err(|_| RawMagicLineParseError::InvalidRegex)?;
-        Ok(re.replacen(&input, 3, " ").to_string())
-    }
-
-    fn test(&self, tf_reader: &mut BufReader<File>) -> Option<String> {
-        let offset = self.offset.num;
-
-        // put back the cursor position to beginning and start working
-        let _ = tf_reader.rewind();
-        let _ = tf_reader.seek(SeekFrom::Start(offset));
-
-        match self._type {
-            Type::Unsigned(size, mask) => {
-                if self.number_test(size, mask, tf_reader) {
-                    Some(self.message.clone())
-                } else {
-                    None
-                }
-            }
+    fn test(&self, buf: &[u8]) -> Option<String> {
+        let data = buf.get(self.offset.num..)?;
+        let matched = match self._type {
             Type::Decimal(size, mask) => {
-                if self.number_test(size, mask, tf_reader) {
-                    Some(self.message.clone())
-                } else {
-                    None
-                }
+                let raw = self.number_test(size, mask, true, data)?;
+                let shift = 64 - 8 * size;
+                Matched::Signed(((raw << shift) as i64) >> shift)
             }
-
-            Type::String => {
-                if self.string_test(tf_reader) {
-                    Some(self.message.clone())
-                } else {
-                    None
-                }
+            Type::Unsigned(size, mask) => {
+                Matched::Unsigned(self.number_test(size, mask, false, data)?)
             }
-        }
+            Type::String => Matched::Bytes(self.string_test(data)?),
+        };
+        Some(format_message(&self.message, &matched))
     }
 
-    fn string_test(&self, tf_reader: &mut BufReader<File>) -> bool {
-        if let Value::String(val) = &self.value {
-            let mut buf = vec![0u8; val.len()];
-            if let Err(_) = tf_reader.read_exact(&mut buf) {
-                return false;
-            }
-
-            if let Ok(tf_val) = String::from_utf8(buf.clone()) {
-                return tf_val.as_bytes() == val.as_bytes();
+    fn string_test(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match &self.value {
+            Value::String(val) => data.starts_with(val).then(|| val.clone()),
+            Value::AnyString => {
+                let end = data
+                    .iter()
+                    .take(MAX_STRING_VALUE)
+                    .position(|&b| b == 0 || b == b'\n')
+                    .unwrap_or(data.len().min(MAX_STRING_VALUE));
+                Some(data[..end].to_vec())
             }
+            Value::Number(..) => None,
         }
-        false
     }
 
-    fn number_test(&self, size: u64, mask: Option<u64>, tf_reader: &mut BufReader<File>) -> bool {
-        let mut buf = vec![0; size as usize];
-        if let Err(_) = tf_reader.read_exact(&mut buf) {
-            return false;
-        }
-
-        buf.reverse();
-
+    /// Reads a `size` byte number in native byte order from the start of
+    /// `data` and compares it with the value, returning it on a match.
+    fn number_test(
+        &self,
+        size: usize,
+        mask: Option<u64>,
+        signed: bool,
+        data: &[u8],
+    ) -> Option<u64> {
+        let bytes = data.get(..size)?;
         let mut array_buf = [0u8; 8];
-        array_buf[(8 - buf.len())..8].copy_from_slice(&buf);
-        let mut tf_val = u64::from_be_bytes(array_buf);
-
+        let mut tf_val = if cfg!(target_endian = "little") {
+            array_buf[..size].copy_from_slice(bytes);
+            u64::from_le_bytes(array_buf)
+        } else {
+            array_buf[8 - size..].copy_from_slice(bytes);
+            u64::from_be_bytes(array_buf)
+        };
         if let Some(mask) = mask {
             tf_val &= mask;
         }
 
-        match &self.value {
-            Value::Number(op, val) => match op {
-                ComparisonOperator::Equal => *val == tf_val,
-                ComparisonOperator::LessThan => *val < tf_val,
-                ComparisonOperator::GreaterThan => *val > tf_val,
-                ComparisonOperator::AllSet => *val == (*val & tf_val),
-                ComparisonOperator::AnyUnset => (*val ^ tf_val) != 0,
-                ComparisonOperator::FileLargeEnough => {
-                    // we'll directly return true here because
-                    // if the file wasn't large enough to hold the value at the given offset
-                    // then the read_exact will already thrown an error
-                    true
-                }
-            },
-            _ => false,
+        let Value::Number(op, val) = self.value else {
+            return None;
+        };
+        let val = if size == 8 {
+            val
+        } else {
+            val & ((1 << (8 * size)) - 1)
+        };
+        let ordering = if signed {
+            let shift = 64 - 8 * size;
+            (((tf_val << shift) as i64) >> shift).cmp(&(((val << shift) as i64) >> shift))
+        } else {
+            tf_val.cmp(&val)
+        };
+        let matched = match op {
+            ComparisonOperator::Equal => ordering == Ordering::Equal,
+            ComparisonOperator::LessThan => ordering == Ordering::Less,
+            ComparisonOperator::GreaterThan => ordering == Ordering::Greater,
+            ComparisonOperator::AllSet => tf_val & val == val,
+            ComparisonOperator::AnyUnset => tf_val & val != val,
+            ComparisonOperator::Any => true,
+        };
+        matched.then_some(tf_val)
+    }
+}
+
+/// Formats the value a test read with the printf conversion in `message`,
+/// if it has one.
+fn format_message(message: &str, matched: &Matched) -> String {
+    let mut result = String::new();
+    let mut chars = message.chars().peekable();
+    let mut converted = false;
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let mut left = false;
+        let mut zero = false;
+        while let Some(&flag @ ('-' | '0' | '#' | ' ' | '+')) = chars.peek() {
+            left |= flag == '-';
+            zero |= flag == '0';
+            chars.next();
+        }
+        let mut width = 0;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            chars.next();
+        }
+        // length modifiers say nothing here, the type field sets the size
+        while chars.next_if(|&c| matches!(c, 'h' | 'l')).is_some() {}
+
+        let Some(conversion) = chars.next() else {
+            result.push('%');
+            break;
+        };
+        if conversion == '%' || converted {
+            result.push('%');
+            continue;
+        }
+        converted = true;
+        let (number, text) = match matched {
+            Matched::Signed(n) => (*n as u64, n.to_string()),
+            Matched::Unsigned(n) => (*n, n.to_string()),
+            Matched::Bytes(b) => (0, String::from_utf8_lossy(b).into_owned()),
+        };
+        let formatted = match conversion {
+            'd' | 'i' | 'u' | 's' => text,
+            'x' => format!("{:x}", number),
+            'X' => format!("{:X}", number),
+            'o' => format!("{:o}", number),
+            'c' => char::from(number as u8).to_string(),
+            _ => continue,
+        };
+        let pad = width.saturating_sub(formatted.chars().count());
+        if left {
+            result.push_str(&formatted);
+            result.extend(std::iter::repeat(' ').take(pad));
+        } else {
+            let fill = if zero { '0' } else { ' ' };
+            result.extend(std::iter::repeat(fill).take(pad));
+            result.push_str(&formatted);
         }
     }
+    result
 }
 
-/// Parse a magic database file content line by line and test it against another file
-///
-/// Given paths to a magic file database and a test file, this function reads both files
-/// line by line. It parses each line of the magic database file and tests it against
-/// the content of the test file.
-fn parse_magic_file_and_test(
-    magic_file: &PathBuf,
-    test_file: &PathBuf,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let mf_reader = BufReader::new(File::open(magic_file)?);
-    let mut tf_reader = BufReader::new(File::open(test_file)?);
-
-    let mut result = None;
-    for line in mf_reader.lines() {
-        if let Ok(raw_magic_file_line) = RawMagicFileLine::parse(line?) {
-            if result.is_none() && !raw_magic_file_line.offset.is_continuation {
-                result = raw_magic_file_line.test(&mut tf_reader);
-            } else if let Some(ref r) = result {
-                if raw_magic_file_line.offset.is_continuation {
-                    result = raw_magic_file_line.test(&mut tf_reader).or(Some(r.clone()));
-                } else {
-                    return Ok(result.unwrap());
+/// A test line without `>` and the continuation lines that follow it.
+struct Entry {
+    line: RawMagicFileLine,
+    continuations: Vec<RawMagicFileLine>,
+}
+
+/// Position-sensitive tests read from a magic file.
+pub struct MagicFile {
+    entries: Vec<Entry>,
+}
+
+impl MagicFile {
+    /// Reads the tests in the magic file at `path`.
+    ///
+    /// Lines that are empty, comments or not valid tests are skipped, as
+    /// are the continuation lines of an invalid test.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let contents = fs::read(path)?;
+        Ok(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut skipping = false;
+        for line in contents.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let is_continuation = line.trim_start().starts_with('>');
+            match RawMagicFileLine::parse(line) {
+                Ok(line) if is_continuation => {
+                    if let (Some(entry), false) = (entries.last_mut(), skipping) {
+                        entry.continuations.push(line);
+                    }
+                }
+                Ok(line) => {
+                    skipping = false;
+                    entries.push(Entry {
+                        line,
+                        continuations: Vec::new(),
+                    });
                 }
+                Err(_) => skipping |= !is_continuation,
             }
-        } else {
-            // TODO: Empty branch
         }
+        MagicFile { entries }
     }
 
-    result.ok_or(Box::new(io::Error::new(
-        ErrorKind::NotFound,
-        "Couldn't match any magic number",
-    )))
+    /// Applies the tests to `buf`, the start of a file, returning the
+    /// message of the first test that matches with those of its matching
+    /// continuation lines.
+    pub fn classify(&self, buf: &[u8]) -> Option<String> {
+        self.entries.iter().find_map(|entry| {
+            let mut result = entry.line.test(buf)?;
+            let mut level = 1;
+            for line in &entry.continuations {
+                if line.offset.level > level {
+                    continue;
+                }
+                match line.test(buf) {
+                    Some(message) => {
+                        // a message starting with `\b` is not separated
+                        // from the previous one
+                        match message.strip_prefix("\\b") {
+                            Some(message) => result.push_str(message),
+                            None if message.is_empty() => {}
+                            None => {
+                                result.push(' ');
+                                result.push_str(&message);
+                            }
+                        }
+                        level = line.offset.level + 1;
+                    }
+                    None => level = line.offset.level,
+                }
+            }
+            Some(result)
+        })
+    }
 }
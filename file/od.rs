@@ -7,8 +7,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::fs::File;
-use std::io::{self, BufReader, Error, Read, Seek, SeekFrom};
+use std::io::{Error, ErrorKind, Read};
 use std::num::ParseIntError;
 use std::path::PathBuf;
 use std::slice::Chunks;
@@ -16,14 +15,14 @@ use std::str::FromStr;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-
-use crate::io::ErrorKind;
+use plib::dump::{concat_inputs, OffsetBase};
 
 #[derive(Parser)]
 #[command(version, about = gettext("od - dump files in octal and other formats"))]
 struct Args {
     #[arg(
         short = 'A',
+        value_parser = parse_address_base,
         help = gettext(
             "Address base (d for decimal, o for octal, x for hexadecimal, n for none)"
         )
@@ -82,32 +81,47 @@ struct Args {
     files: Vec<PathBuf>,
 
     #[arg(skip)]
-    /// Offset in the file where dumping is to commence, must start with "+"]
+    /// Offset in the file where dumping is to commence, given as the last
+    /// operand in the traditional form `[+]offset[.][b]`
     offset: Option<String>,
 }
 
+/// Validates the -A option-argument, one of `d`, `o`, `x` or `n`.
+fn parse_address_base(s: &str) -> Result<char, String> {
+    match s {
+        "d" | "o" | "x" | "n" => Ok(s.chars().next().unwrap()),
+        _ => Err(plib::tr!(
+            "invalid output address radix '{}'; it must be one character from [doxn]",
+            s
+        )),
+    }
+}
+
 impl Args {
     /// Validate the arguments for any conflicts or invalid combinations.
     fn validate_args(&mut self) -> Result<(), String> {
-        // Check if conflicting options are used together
-
-        for file in &self.files {
-            let string = file.to_str().unwrap();
-            if string.starts_with('+') {
-                self.offset = Some(string.to_string());
+        // The last operand is taken as an offset in the traditional form
+        // [+]offset[.][b] only when none of '-A', '-j', '-N', '-t', '-v' is
+        // used, there are at most two operands, and it starts with '+' or
+        // is the second operand and starts with a digit.
+        let traditional = self.address_base.is_none()
+            && self.skip.is_none()
+            && self.count.is_none()
+            && self.type_strings.is_empty()
+            && !self.verbose;
+        if traditional && self.files.len() <= 2 {
+            let is_offset = self.files.last().and_then(|file| file.to_str()).map(|s| {
+                s.starts_with('+')
+                    || (self.files.len() == 2 && s.starts_with(|c: char| c.is_ascii_digit()))
+            });
+            if is_offset == Some(true) {
+                let offset = self.files.pop().unwrap();
+                let offset = offset.to_str().unwrap();
+                self.offset = Some(offset.strip_prefix('+').unwrap_or(offset).to_string());
             }
         }
 
-        // '-A', '-j', '-N', '-t', '-v' should not be used with offset syntax [+]offset[.][b]
-        if (self.address_base.is_some()
-            || self.skip.is_some()
-            || self.count.is_some()
-            || !self.type_strings.is_empty()
-            || self.verbose)
-            && self.offset.is_some()
-        {
-            return Err("Options '-A', '-j', '-N', '-t', '-v' cannot be used together with offset syntax '[+]offset[.][b]'".to_string());
-        }
+        // Check if conflicting options are used together
 
         // '-b', '-c', '-d', '-o', '-s', '-x' should not be used with '-t' options
         if !self.type_strings.is_empty()
@@ -270,11 +284,14 @@ fn parse_offset(offset: &str) -> Result<u64, ParseIntError> {
     let mut base = 8;
     let mut multiplier = 1;
 
-    // Handle special suffixes
+    // Handle special suffixes, which may be combined as ".b"
     let offset = if let Some(offset) = offset.strip_suffix('b') {
         multiplier = 512;
         offset
-    } else if let Some(offset) = offset.strip_suffix('.') {
+    } else {
+        offset
+    };
+    let offset = if let Some(offset) = offset.strip_suffix('.') {
         base = 10;
         offset
     } else {
@@ -372,17 +389,7 @@ fn print_data<R: Read>(
         let local_buf_len = local_buf.len();
 
         // Print the address in the specified base format.
-        let offset_string = if let Some(base) = config.address_base {
-            match base {
-                'd' => format!("{:07}", offset),  // Decimal format
-                'o' => format!("{:07o}", offset), // Octal format
-                'x' => format!("{:06x}", offset), // Hexadecimal format (only six characters)
-                'n' => String::new(),             // No address printed
-                _ => format!("{:07o}", offset),   // Default to octal if invalid base
-            }
-        } else {
-            format!("{:07o}", offset) // Default to octal if no base is specified.
-        };
+        let offset_string = format_address(config.address_base, offset);
 
         // Process and print the buffer based on configuration.
         if config.bytes_char {
@@ -550,21 +557,26 @@ fn print_data<R: Read>(
     }
 
     // Print the final address in the specified base format.
-    if let Some(base) = config.address_base {
-        match base {
-            'd' => println!("{:07}", offset),  // Decimal format
-            'o' => println!("{:07o}", offset), // Octal format
-            'x' => println!("{:06x}", offset), // Hexadecimal format (only six characters)
-            'n' => (),                         // No address printed
-            _ => println!("{:07o}", offset),   // Default to octal if invalid base
-        }
-    } else {
-        println!("{:07o}", offset); // Default to octal if no base is specified.
+    if config.address_base != Some('n') {
+        println!("{}", format_address(config.address_base, offset));
     }
 
     Ok(())
 }
 
+/// Formats an address in the base given by -A: seven digits, or six in
+/// hexadecimal, and nothing for `n`. The default base is octal.
+fn format_address(address_base: Option<char>, offset: usize) -> String {
+    if address_base == Some('n') {
+        return String::new();
+    }
+    let base = address_base
+        .and_then(OffsetBase::from_char)
+        .unwrap_or(OffsetBase::Octal);
+    let width = if base == OffsetBase::Hex { 6 } else { 7 };
+    base.format(offset as u64, width, true)
+}
+
 fn process_res_string(
     offset_string: &str,
     previous_offset_string: &mut String,
@@ -1040,26 +1052,15 @@ fn get_named_char(byte: u8) -> Option<&'static str> {
 /// This function can return an error if:
 /// - There is an issue opening or reading from the specified files.
 /// - There is an error with the skip or offset options.
-/// - There is an issue chaining multiple file readers.
 ///
 /// # Behavior
 ///
-/// 1. Initializes `bytes_to_skip` and `bytes_skipped` to manage byte skipping.
-/// 2. Prepares a vector to hold all file readers.
-/// 3. Parses and sets the number of bytes to skip based on the `-j` option or offset.
-/// 4. Determines the source of the input:
-///     - If there is one file and it is "-" (stdin) or no files, reads from stdin and skips the specified bytes.
-///     - Otherwise, processes each specified file:
-///         - If the cumulative bytes skipped are less than the bytes to skip, skips entire files or parts of them.
-///         - Adds each file to the vector of readers.
-/// 5. Combines multiple file readers into a single reader if necessary.
-/// 6. Calls `print_data` to read and print the data from the combined reader according to the configuration in `args`.
+/// 1. Parses the number of bytes to skip from the `-j` option or offset.
+/// 2. Reads the files, or stdin if there are none, as a single stream past the skipped bytes.
+/// 3. Calls `print_data` to read and print the data from the stream according to the configuration in `args`.
 ///
 fn od(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     let mut bytes_to_skip = 0; // Initialize the number of bytes to skip.
-    let mut bytes_skipped = 0; // Initialize the number of bytes already skipped.
-
-    let mut all_files: Vec<Box<dyn Read>> = Vec::new(); // Vector to hold file readers.
 
     // Skip bytes if the -j option is specified.
     if let Some(skip) = &args.skip {
@@ -1073,63 +1074,8 @@ fn od(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let bytes_that_will_be_skipped = usize::try_from(bytes_to_skip)?;
 
-    let mut reader: Box<dyn Read> = if (args.files.len() == 1
-        && args.files[0] == PathBuf::from("-"))
-        || args.files.is_empty()
-    {
-        // If there is one file and it is "-" (stdin) or no files, read from stdin.
-        let mut stdin: Box<dyn Read> = Box::new(io::stdin().lock());
-
-        // Buffer of size 1 byte for reading char by char to skip bytes.
-        let mut empty_buffer = [0; 1];
-
-        // Skip the specified number of bytes from stdin.
-        while bytes_to_skip > 0 {
-            stdin.read_exact(&mut empty_buffer)?;
-            bytes_to_skip -= 1;
-        }
-        stdin // Use stdin as the reader.
-    } else {
-        // Otherwise, process each specified file.
-        for file in &args.files {
-            let mut file = File::open(file)?; // Open the file.
-
-            if bytes_skipped < bytes_to_skip {
-                // If the cumulative bytes skipped are less than the bytes to skip, process the file for skipping.
-                let metadata = file.metadata()?; // Get file metadata.
-                let file_size = metadata.len(); // Get file size.
-
-                if bytes_skipped + file_size <= bytes_to_skip {
-                    // Skip the entire file if it is within the range of bytes to skip.
-                    bytes_skipped += file_size;
-                    continue; // Move to the next file.
-                } else {
-                    // Skip part of the file if only a portion of it is within the range of bytes to skip.
-                    let remaining_skip = bytes_to_skip - bytes_skipped;
-                    file.seek(SeekFrom::Start(remaining_skip))?; // Seek to the remaining bytes.
-                    bytes_skipped = bytes_to_skip; // Update the bytes skipped.
-                }
-            }
-
-            // Add the file reader to the vector of readers.
-            all_files.push(Box::new(BufReader::new(file)));
-        }
-
-        if all_files.len() > 1 {
-            // Combine multiple file readers into a single reader.
-            all_files
-                .into_iter()
-                .reduce(|acc, file| Box::new(acc.chain(file)) as Box<dyn Read>)
-                .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No files to chain"))?
-        // Handle error if no files to chain.
-        } else {
-            // If only one file, use it as the reader.
-            match all_files.pop() {
-                None => return Ok(()), // Return Ok if no files.
-                Some(f) => f,          // Use the single file as the reader.
-            }
-        }
-    };
+    // Read the files, or stdin, as one stream past the skipped bytes.
+    let mut reader = concat_inputs(&args.files, bytes_to_skip)?;
 
     // Print the data using the reader.
     print_data(&mut reader, args, bytes_that_will_be_skipped)?;
@@ -1166,6 +1112,7 @@ mod tests {
         assert_eq!(parse_offset("777b"), Ok(0o777 * 512));
         assert_eq!(parse_offset("777."), Ok(777));
         assert_eq!(parse_offset("777"), Ok(0o777));
+        assert_eq!(parse_offset("10.b"), Ok(10 * 512));
     }
 
     #[test]
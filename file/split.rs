@@ -15,6 +15,7 @@ use std::path::PathBuf;
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use plib::io::{input_reader, input_stream};
+use plib::sizestr::parse_size_as;
 use plib::BUFSZ;
 
 #[derive(Parser)]
@@ -191,23 +192,8 @@ impl OutputState {
 }
 
 fn split_by_bytes(args: &Args, bytesplit: String) -> io::Result<()> {
-    let mul: u64 = {
-        if bytesplit.ends_with("k") {
-            1024
-        } else if bytesplit.ends_with("m") {
-            1024 * 1024
-        } else if bytesplit.ends_with("g") {
-            1024 * 1024 * 1024
-        } else {
-            1
-        }
-    };
-    let bytestr = match mul {
-        1 => &bytesplit[..],
-        _ => &bytesplit[0..bytesplit.len() - 1],
-    };
-    let boundary: u64 = match bytestr.parse::<u64>() {
-        Ok(n) => n * mul,
+    let boundary: u64 = match parse_size_as(&bytesplit) {
+        Ok(n) => n,
         Err(e) => {
             eprintln!("{}", e);
             return Err(Error::new(ErrorKind::Other, "invalid byte spec"));
@@ -100,9 +100,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     if args.ignore {
-        unsafe {
-            libc::signal(libc::SIGINT, libc::SIG_IGN);
-        }
+        plib::signal::ignore(libc::SIGINT)?;
     }
 
     let mut state = TeeInfo::default();
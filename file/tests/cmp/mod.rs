@@ -7,7 +7,8 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_with_options, TestOptions, TestPlan, TestStdin};
+use std::fs;
 
 fn run_test_helper(
     args: &[&str],
@@ -112,3 +113,51 @@ fn cmp_eof() {
         1,
     );
 }
+
+#[test]
+fn cmp_large_files() {
+    // Large enough to be memory-mapped, compared against a buffered stdin
+    let dir = std::env::temp_dir().join(format!("cmp_large_files_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let original = dir.join("original.txt");
+    let modified = dir.join("modified.txt");
+
+    let mut data = Vec::new();
+    for i in 0..50000 {
+        data.extend_from_slice(format!("line {i}\n").as_bytes());
+    }
+    fs::write(&original, &data).unwrap();
+    let pos = data.len() - 3;
+    data[pos] = b'X';
+    fs::write(&modified, &data).unwrap();
+
+    let original = original.to_str().unwrap();
+    let modified = modified.to_str().unwrap();
+    run_test_helper(
+        &[original, modified],
+        &format!(
+            "{original} {modified} differ: char {}, line 50000\n",
+            pos + 1
+        ),
+        "",
+        1,
+    );
+    run_test_helper(&[original, original], "", "", 0);
+
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("cmp"),
+            args: vec![String::from("-"), String::from(original)],
+            stdin_data: String::new(),
+            expected_out: format!("- {original} differ: char {}, line 50000\n", pos + 1),
+            expected_err: String::new(),
+            expected_exit_code: 1,
+        },
+        TestOptions {
+            stdin: Some(TestStdin::File(modified.into())),
+            ..Default::default()
+        },
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
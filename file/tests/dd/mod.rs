@@ -2,7 +2,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 
-use plib::testing::{run_test_u8, TestPlanU8};
+use plib::testing::{run_test_u8, run_test_u8_with_options, Fixture, TestOptions, TestPlanU8};
 
 fn get_test_file_path(filename: &str) -> PathBuf {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -12,6 +12,17 @@ fn get_test_file_path(filename: &str) -> PathBuf {
     path
 }
 
+fn dd_test(args: &[&str], stdin_data: &[u8], expected_output: &[u8], expected_error: &str) {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("dd"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: stdin_data.to_vec(),
+        expected_out: expected_output.to_vec(),
+        expected_err: expected_error.as_bytes().to_vec(),
+        expected_exit_code: 0,
+    });
+}
+
 #[test]
 fn test_ascii_to_ebcdic_conversion() {
     let input_file_path = get_test_file_path("dd.ascii");
@@ -35,7 +46,7 @@ fn test_ascii_to_ebcdic_conversion() {
         args: vec![String::from("conv=ebcdic")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -63,7 +74,7 @@ fn test_ebcdic_to_ascii_conversion() {
         args: vec![String::from("conv=ascii")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -91,7 +102,7 @@ fn test_ascii_to_ibm_conversion() {
         args: vec![String::from("conv=ibm")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -119,7 +130,7 @@ fn test_ascii_to_swab_conversion() {
         args: vec![String::from("conv=swab")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -147,7 +158,7 @@ fn test_ucase_conversion() {
         args: vec![String::from("conv=ucase")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -175,7 +186,7 @@ fn test_lcase_conversion() {
         args: vec![String::from("conv=lcase")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -200,7 +211,21 @@ fn test_skip_n() {
         ],
         stdin_data: input_data,
         expected_out: expected_output.to_vec(),
-        expected_err: Vec::new(),
+        expected_err: b"57+0 records in\n0+1 records out\n".to_vec(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn test_block_size_suffix() {
+    let input_data = b"0123456789".repeat(200);
+
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("dd"),
+        args: vec![String::from("bs=1k"), String::from("count=1")],
+        stdin_data: input_data.clone(),
+        expected_out: input_data[..1024].to_vec(),
+        expected_err: b"1+0 records in\n1+0 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -222,7 +247,7 @@ fn test_basic_block_processing() {
         args: vec![String::from("ibs=32"), String::from("obs=32")],
         stdin_data: input_data,
         expected_out: expected_output_data,
-        expected_err: Vec::new(),
+        expected_err: b"26+0 records in\n26+0 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -247,7 +272,7 @@ fn test_different_ibs_obs() {
         args: vec![String::from("ibs=32"), String::from("obs=64")],
         stdin_data: input_data,
         expected_out: expected_output_data,
-        expected_err: Vec::new(),
+        expected_err: b"26+0 records in\n13+0 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -280,7 +305,7 @@ fn test_conv_sync() {
         ],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n1+0 records out\n".to_vec(),
         expected_exit_code: 0,
     });
 }
@@ -308,7 +333,107 @@ fn test_conv_block() {
         args: vec![String::from("conv=block"), String::from("cbs=16")],
         stdin_data: input_data,
         expected_out: reference_data,
-        expected_err: Vec::new(),
+        expected_err: b"0+1 records in\n0+1 records out\n2 truncated records\n".to_vec(),
         expected_exit_code: 0,
     });
 }
+
+#[test]
+fn test_bs_copies_partial_blocks() {
+    dd_test(
+        &["bs=4"],
+        b"abcdefghij",
+        b"abcdefghij",
+        "2+1 records in\n2+1 records out\n",
+    );
+    dd_test(
+        &["bs=4", "conv=sync"],
+        b"abcdefghij",
+        b"abcdefghij\0\0",
+        "2+1 records in\n3+0 records out\n",
+    );
+}
+
+#[test]
+fn test_ibs_obs_gather_output_blocks() {
+    dd_test(
+        &["ibs=3", "obs=4"],
+        b"abcdefghij",
+        b"abcdefghij",
+        "3+1 records in\n2+1 records out\n",
+    );
+}
+
+#[test]
+fn test_size_products_and_count() {
+    dd_test(
+        &["bs=2x2", "count=2"],
+        b"0123456789",
+        b"01234567",
+        "2+0 records in\n2+0 records out\n",
+    );
+    dd_test(
+        &["count=0"],
+        b"0123456789",
+        b"",
+        "0+0 records in\n0+0 records out\n",
+    );
+}
+
+#[test]
+fn test_conv_unblock() {
+    dd_test(
+        &["cbs=4", "conv=unblock,ucase"],
+        b"ab  cd  ef",
+        b"AB\nCD\nEF\n",
+        "0+1 records in\n0+1 records out\n",
+    );
+}
+
+#[test]
+fn test_seek_with_and_without_notrunc() {
+    let fixture = Fixture::builder("dd_seek")
+        .file("kept", "0123456789")
+        .file("truncated", "0123456789")
+        .build();
+    let options = || TestOptions {
+        cwd: Some(fixture.root().to_path_buf()),
+        ..Default::default()
+    };
+
+    for (file, conv, expected) in [
+        ("of=kept", "conv=notrunc", "01ab456789"),
+        ("of=truncated", "conv=ucase", "01AB"),
+    ] {
+        run_test_u8_with_options(
+            TestPlanU8 {
+                cmd: String::from("dd"),
+                args: vec![
+                    String::from(file),
+                    String::from("bs=2"),
+                    String::from("seek=1"),
+                    String::from(conv),
+                ],
+                stdin_data: b"ab".to_vec(),
+                expected_out: Vec::new(),
+                expected_err: b"1+0 records in\n1+0 records out\n".to_vec(),
+                expected_exit_code: 0,
+            },
+            options(),
+        );
+        let path = fixture.path(file.trim_start_matches("of="));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_invalid_operand() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("dd"),
+        args: vec![String::from("conv=ucase,lcase")],
+        stdin_data: Vec::new(),
+        expected_out: Vec::new(),
+        expected_err: b"dd: conflicting conversion: lcase\n".to_vec(),
+        expected_exit_code: 1,
+    });
+}
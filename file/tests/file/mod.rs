@@ -9,7 +9,7 @@
 
 use std::{env, path::PathBuf};
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_with_options, Fixture, TestOptions, TestPlan};
 
 fn file_test(args: &[&str], expected_output: &str, expected_error: &str) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
@@ -24,6 +24,24 @@ fn file_test(args: &[&str], expected_output: &str, expected_error: &str) {
     });
 }
 
+/// Runs file in the root of `fixture`.
+fn file_test_in(fixture: &Fixture, args: &[&str], expected_output: &str) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("file"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: String::from(expected_output),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions {
+            cwd: Some(fixture.root().to_path_buf()),
+            ..Default::default()
+        },
+    );
+}
+
 #[test]
 fn file_doesnot_exist() {
     let file = "tests/file/this_file_doesnt_exist";
@@ -253,3 +271,103 @@ fn file_magic_file_priority_with_M_and_m_option_as_they_appear_using_cpio_archiv
         "",
     );
 }
+
+#[test]
+fn file_default_tests_on_binary_formats() {
+    let mut elf = vec![0u8; 64];
+    elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    elf[16] = 2; // ET_EXEC
+    elf[18] = 62; // EM_X86_64
+    let mut tar = vec![0u8; 1024];
+    tar[..4].copy_from_slice(b"name");
+    tar[257..263].copy_from_slice(b"ustar\0");
+
+    let fixture = Fixture::builder("file_binary_formats")
+        .file("elf", &elf)
+        .file("tar", &tar)
+        .file("compressed", b"\x1f\x9d\x90\x54\x00")
+        .file("cpio", b"070707000000")
+        .file("data", b"\x00\x01\x02\xff")
+        .build();
+
+    file_test_in(
+        &fixture,
+        &["elf", "tar", "compressed", "cpio", "data"],
+        "elf: ELF 64-bit LSB executable, x86-64\n\
+         tar: tar archive\n\
+         compressed: compressed data, 16 bits\n\
+         cpio: cpio archive\n\
+         data: data\n",
+    );
+}
+
+#[test]
+fn file_default_tests_on_scripts_and_text() {
+    let fixture = Fixture::builder("file_scripts_and_text")
+        .file("script", "#!/bin/sh\necho hello\n")
+        .file("env_script", "#! /usr/bin/env -S awk -f\n{ print }\n")
+        .file("ascii", "hello\tworld\n")
+        .file("utf8", "h\u{e9}llo w\u{f6}rld\n")
+        .file("latin1", b"h\xe9llo\n")
+        .file("utf16", b"\xff\xfeh\x00i\x00\n\x00")
+        .build();
+
+    file_test_in(
+        &fixture,
+        &["script", "env_script", "ascii", "utf8", "latin1", "utf16"],
+        "script: commands text\n\
+         env_script: awk commands text\n\
+         ascii: ascii text\n\
+         utf8: UTF-8 text\n\
+         latin1: ISO-8859 text\n\
+         utf16: UTF-16 little-endian text\n",
+    );
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn file_magic_file_syntax_and_M_without_default_tests() {
+    let magic = "# tests for the fixture files\n\
+                 0\tstring\tMAGIC\\ 1\tmagic one\n\
+                 >8\tbyte\tx\tversion %d\n\
+                 >9\tshort&0xff00\t>0x100\t\\b, large\n\
+                 >9\tbyte\t<0\tnever\n\
+                 0\tlong\t0\tzeroes\n";
+    let fixture = Fixture::builder("file_magic_syntax")
+        .file("magic", magic)
+        .file("one", b"MAGIC 1\x00\x07\x00\x02")
+        .file("text", "plain text\n")
+        .build();
+
+    file_test_in(
+        &fixture,
+        &["-M", "magic", "one", "text"],
+        "one: magic one version 7, large\n\
+         text: data\n",
+    );
+    file_test_in(
+        &fixture,
+        &["-M", "magic", "-d", "one", "text"],
+        "one: magic one version 7, large\n\
+         text: ascii text\n",
+    );
+    file_test_in(&fixture, &["-m", "magic", "text"], "text: ascii text\n");
+}
+
+#[test]
+fn file_magic_file_cannot_be_opened() {
+    run_test(TestPlan {
+        cmd: String::from("file"),
+        args: vec![
+            String::from("-m"),
+            String::from("tests/file/this_file_doesnt_exist"),
+            String::from("tests/file/regular_file.txt"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(
+            "file: tests/file/this_file_doesnt_exist: No such file or directory\n",
+        ),
+        expected_exit_code: 1,
+    });
+}
@@ -1,7 +1,12 @@
-use std::fs::{remove_file, File};
-use std::io::Write;
-
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_with_options, Fixture, TestOptions, TestPlan};
+
+/// Sorts the lines of output, as find lists the entries of a directory in
+/// the order the file system returns them.
+fn sort_lines(output: &str) -> String {
+    let mut lines: Vec<&str> = output.lines().collect();
+    lines.sort();
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
 
 fn run_test_find(
     args: &[&str],
@@ -11,14 +16,20 @@ fn run_test_find(
 ) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
 
-    run_test(TestPlan {
-        cmd: String::from("find"),
-        args: str_args,
-        stdin_data: String::new(),
-        expected_out: String::from(expected_output),
-        expected_err: String::from(expected_error),
-        expected_exit_code,
-    });
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("find"),
+            args: str_args,
+            stdin_data: String::new(),
+            expected_out: sort_lines(expected_output),
+            expected_err: String::from(expected_error),
+            expected_exit_code,
+        },
+        TestOptions {
+            normalize: Some(sort_lines),
+            ..Default::default()
+        },
+    );
 }
 
 #[test]
@@ -207,14 +218,170 @@ fn find_group_test() {
 
 #[test]
 fn find_newer_test() {
+    let fixture = Fixture::builder("find_newer")
+        .file("reference", "")
+        .mtime(1_000_000_000)
+        .file("tree/old.txt", "")
+        .mtime(999_999_000)
+        .file("tree/new.txt", "")
+        .mtime(1_000_000_100)
+        .dir("tree")
+        .mtime(999_999_999)
+        .build();
+    let test_dir = fixture.arg("tree");
+    let args = [&test_dir, "-newer", &fixture.arg("reference")];
+
+    run_test_find(&args, &format!("{}/new.txt\n", test_dir), "", 0);
+}
+
+#[test]
+fn find_size_units_test() {
     let project_root = env!("CARGO_MANIFEST_DIR");
-    let test_dir = format!("{}/tests/find/newer", project_root);
-    let path_to_test_file = format!("{}/qwe.txt", test_dir);
-    let mut file = File::create(&path_to_test_file).unwrap();
-    writeln!(file, "File content").unwrap();
-    let args = [&test_dir, "-newer", &path_to_test_file];
+    let test_dir = format!("{}/tests/find/other", project_root);
+
+    let expected_output = format!(
+        "{}/empty_file.txt\n{}/file with space.txt\n",
+        test_dir, test_dir
+    );
+    run_test_find(&[&test_dir, "-size", "-1"], &expected_output, "", 0);
+
+    let expected_output = format!("{}/rust_file.rs\n", test_dir);
+    run_test_find(&[&test_dir, "-size", "12c"], &expected_output, "", 0);
+}
+
+#[test]
+fn find_perm_symbolic_test() {
+    let fixture = Fixture::builder("find_perm_symbolic")
+        .file("tree/rw", "")
+        .mode(0o644)
+        .file("tree/rwx", "")
+        .mode(0o755)
+        .build();
+    let test_dir = fixture.arg("tree");
+
+    run_test_find(
+        &[&test_dir, "-perm", "u=rw,go=r"],
+        &format!("{}/rw\n", test_dir),
+        "",
+        0,
+    );
+    run_test_find(
+        &[&test_dir, "-type", "f", "-perm", "-u+x"],
+        &format!("{}/rwx\n", test_dir),
+        "",
+        0,
+    );
+    run_test_find(
+        &[&test_dir, "-type", "f", "-perm", "-444"],
+        &format!("{}/rw\n{}/rwx\n", test_dir, test_dir),
+        "",
+        0,
+    );
+}
+
+#[test]
+fn find_prune_test() {
+    let fixture = Fixture::builder("find_prune")
+        .file("tree/skip/a", "")
+        .file("tree/keep/b", "")
+        .build();
+    let test_dir = fixture.arg("tree");
+    let args = [&test_dir, "-name", "skip", "-prune", "-o", "-print"];
+
+    let expected_output = format!("{0}\n{0}/keep\n{0}/keep/b\n", test_dir);
+    run_test_find(&args, &expected_output, "", 0);
+}
+
+#[test]
+fn find_depth_test() {
+    let fixture = Fixture::builder("find_depth")
+        .file("tree/dir/file", "")
+        .build();
+    let test_dir = fixture.arg("tree");
+
+    run_test(TestPlan {
+        cmd: String::from("find"),
+        args: vec![test_dir.clone(), String::from("-depth")],
+        stdin_data: String::new(),
+        expected_out: format!("{0}/dir/file\n{0}/dir\n{0}\n", test_dir),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn find_exec_test() {
+    let fixture = Fixture::builder("find_exec")
+        .file("tree/a", "")
+        .file("tree/b", "")
+        .build();
+    let test_dir = fixture.arg("tree");
+
+    let args = [&test_dir, "-type", "f", "-exec", "echo", "file", "{}", ";"];
+    let expected_output = format!("file {0}/a\nfile {0}/b\n", test_dir);
+    run_test_find(&args, &expected_output, "", 0);
+
+    // All pathnames go to a single invocation
+    let args = [
+        &test_dir, "-type", "f", "-exec", "sh", "-c", "echo $#", "sh", "{}", "+",
+    ];
+    run_test_find(&args, "2\n", "", 0);
+
+    // -exec ... ; evaluates to the utility's exit status
+    let args = [&test_dir, "-exec", "test", "-d", "{}", ";", "-o", "-print"];
+    let expected_output = format!("{0}/a\n{0}/b\n", test_dir);
+    run_test_find(&args, &expected_output, "", 0);
+
+    // ... but the {} + form is true, and failures only show in the exit
+    // status
+    let args = [
+        &test_dir, "-type", "f", "-exec", "false", "{}", "+", "-print",
+    ];
+    let expected_output = format!("{0}/a\n{0}/b\n", test_dir);
+    run_test_find(&args, &expected_output, "", 1);
+}
 
-    run_test_find(&args, "", "", 0);
+#[test]
+fn find_ok_test() {
+    let fixture = Fixture::builder("find_ok").file("tree/a", "").build();
+    let test_dir = fixture.arg("tree");
+    let args: Vec<String> = [&test_dir, "-type", "f", "-ok", "echo", "run", "{}", ";"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let prompt = format!("< echo run {}/a >? ", test_dir);
+
+    run_test(TestPlan {
+        cmd: String::from("find"),
+        args: args.clone(),
+        stdin_data: String::from("y\n"),
+        expected_out: format!("run {}/a\n", test_dir),
+        expected_err: prompt.clone(),
+        expected_exit_code: 0,
+    });
+    run_test(TestPlan {
+        cmd: String::from("find"),
+        args,
+        stdin_data: String::from("n\n"),
+        expected_out: String::new(),
+        expected_err: prompt,
+        expected_exit_code: 0,
+    });
+}
 
-    remove_file(&path_to_test_file).unwrap();
+#[test]
+fn find_invalid_expression_test() {
+    run_test_find(
+        &[".", "-type", "x"],
+        "",
+        "find: unknown argument to -type: x\n",
+        1,
+    );
+    run_test_find(
+        &[".", "-exec", "echo"],
+        "",
+        "find: missing argument to -exec\n",
+        1,
+    );
+    run_test_find(&[".", "(", "-print"], "", "find: missing )\n", 1);
 }
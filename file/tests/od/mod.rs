@@ -306,3 +306,54 @@ fn test_od_a_x_t_a() {
 ",
     );
 }
+
+#[test]
+fn test_od_address_bases() {
+    od_test(
+        &["-A", "d", "-t", "x1"],
+        "abcdefghijklmnopqrstu",
+        "\
+0000000 61 62 63 64 65 66 67 68 69 6a 6b 6c 6d 6e 6f 70
+0000016 71 72 73 74 75
+0000021
+",
+    );
+    od_test(
+        &["-A", "o", "-t", "x1"],
+        "abcdefghijklmnopqrstu",
+        "\
+0000000 61 62 63 64 65 66 67 68 69 6a 6b 6c 6d 6e 6f 70
+0000020 71 72 73 74 75
+0000025
+",
+    );
+}
+
+#[test]
+fn test_od_invalid_address_base() {
+    run_test(TestPlan {
+        cmd: String::from("od"),
+        args: vec![String::from("-A"), String::from("q")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(
+            "error: invalid value 'q' for '-A <ADDRESS_BASE>': invalid output address radix 'q'; it must be one character from [doxn]\n\nFor more information, try '--help'.\n",
+        ),
+        expected_exit_code: 2,
+    });
+}
+
+#[test]
+fn test_od_offset_operand() {
+    let expected = "0000004   e   f   g   h   i   j   k   l\n0000014\n";
+    // octal by default
+    od_test(&["-c", "+4"], "abcdefghijkl", expected);
+    od_test(
+        &["-c", "+10"],
+        "abcdefghijklmnopqrstu",
+        "0000010   i   j   k   l   m   n   o   p   q   r   s   t   u\n0000025\n",
+    );
+    // decimal with a trailing '.'; the '+' is optional on a second operand
+    od_test(&["-c", "-", "4."], "abcdefghijkl", expected);
+    od_test(&["-c", "-", "+4."], "abcdefghijkl", expected);
+}
@@ -562,8 +562,18 @@ where
         Ok(true) => {
             let entry_metadata = entry.metadata.as_ref().unwrap();
             if entry_metadata.is_dir() {
+                // The file handler may have changed the mode of the directory, like
+                // `chmod -R u+x` does, so look again before giving up on it
+                if !entry_metadata.is_executable() {
+                    if let Ok(md) =
+                        Metadata::new(dir_fd.fd, entry.file_name(), entry.read_link.is_some())
+                    {
+                        entry.metadata = Some(md);
+                    }
+                }
+
                 // Is the directory searchable?
-                if entry_metadata.is_executable() {
+                if entry.metadata.as_ref().unwrap().is_executable() {
                     ProcessFileResult::ProcessedDirectory(entry)
                 } else {
                     // "Permission denied" error. `io::ErrorKind::PermissionDenied` uses
@@ -0,0 +1,465 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::collections::BTreeSet;
+use std::ffi::CStr;
+use std::fs;
+use std::path::Path;
+
+use clap::Parser;
+use libc::{c_char, lconv, nl_item};
+use plib::tdiag;
+
+/// locale - get locale-specific information
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Write the names of all the available public locales
+    #[arg(short = 'a', conflicts_with_all = ["charmaps", "names"])]
+    all: bool,
+
+    /// Write the names of the available charmaps
+    #[arg(short = 'm', conflicts_with = "names")]
+    charmaps: bool,
+
+    /// Write the names of the categories of the selected keywords
+    #[arg(short = 'c')]
+    category_names: bool,
+
+    /// Write the names of the selected keywords with their values
+    #[arg(short = 'k')]
+    keyword_names: bool,
+
+    /// Categories, or keywords of categories, to write the values of
+    names: Vec<String>,
+}
+
+/// The categories POSIX defines, in the order they are reported.
+const CATEGORIES: &[&str] = &[
+    "LC_CTYPE",
+    "LC_NUMERIC",
+    "LC_TIME",
+    "LC_COLLATE",
+    "LC_MONETARY",
+    "LC_MESSAGES",
+];
+
+/// Where the value of a keyword comes from.
+enum Item {
+    /// A string of nl_langinfo(3)
+    Text(nl_item),
+    /// Several strings of nl_langinfo(3)
+    List(&'static [nl_item]),
+    /// A string of localeconv(3)
+    Str(fn(&lconv) -> *mut c_char),
+    /// A number of localeconv(3)
+    Num(fn(&lconv) -> c_char),
+    /// A grouping of localeconv(3): digit group sizes, as bytes
+    Grouping(fn(&lconv) -> *mut c_char),
+}
+
+struct Keyword {
+    name: &'static str,
+    category: &'static str,
+    item: Item,
+}
+
+const DAY: &[nl_item] = &[
+    libc::DAY_1,
+    libc::DAY_2,
+    libc::DAY_3,
+    libc::DAY_4,
+    libc::DAY_5,
+    libc::DAY_6,
+    libc::DAY_7,
+];
+
+const ABDAY: &[nl_item] = &[
+    libc::ABDAY_1,
+    libc::ABDAY_2,
+    libc::ABDAY_3,
+    libc::ABDAY_4,
+    libc::ABDAY_5,
+    libc::ABDAY_6,
+    libc::ABDAY_7,
+];
+
+const MON: &[nl_item] = &[
+    libc::MON_1,
+    libc::MON_2,
+    libc::MON_3,
+    libc::MON_4,
+    libc::MON_5,
+    libc::MON_6,
+    libc::MON_7,
+    libc::MON_8,
+    libc::MON_9,
+    libc::MON_10,
+    libc::MON_11,
+    libc::MON_12,
+];
+
+const ABMON: &[nl_item] = &[
+    libc::ABMON_1,
+    libc::ABMON_2,
+    libc::ABMON_3,
+    libc::ABMON_4,
+    libc::ABMON_5,
+    libc::ABMON_6,
+    libc::ABMON_7,
+    libc::ABMON_8,
+    libc::ABMON_9,
+    libc::ABMON_10,
+    libc::ABMON_11,
+    libc::ABMON_12,
+];
+
+const AM_PM: &[nl_item] = &[libc::AM_STR, libc::PM_STR];
+
+const fn keyword(name: &'static str, category: &'static str, item: Item) -> Keyword {
+    Keyword {
+        name,
+        category,
+        item,
+    }
+}
+
+/// The keywords of each category, in the order they are reported.
+const KEYWORDS: &[Keyword] = &[
+    keyword("charmap", "LC_CTYPE", Item::Text(libc::CODESET)),
+    keyword(
+        "decimal_point",
+        "LC_NUMERIC",
+        Item::Str(|lc| lc.decimal_point),
+    ),
+    keyword(
+        "thousands_sep",
+        "LC_NUMERIC",
+        Item::Str(|lc| lc.thousands_sep),
+    ),
+    keyword("grouping", "LC_NUMERIC", Item::Grouping(|lc| lc.grouping)),
+    keyword("abday", "LC_TIME", Item::List(ABDAY)),
+    keyword("day", "LC_TIME", Item::List(DAY)),
+    keyword("abmon", "LC_TIME", Item::List(ABMON)),
+    keyword("mon", "LC_TIME", Item::List(MON)),
+    keyword("am_pm", "LC_TIME", Item::List(AM_PM)),
+    keyword("d_t_fmt", "LC_TIME", Item::Text(libc::D_T_FMT)),
+    keyword("d_fmt", "LC_TIME", Item::Text(libc::D_FMT)),
+    keyword("t_fmt", "LC_TIME", Item::Text(libc::T_FMT)),
+    keyword("t_fmt_ampm", "LC_TIME", Item::Text(libc::T_FMT_AMPM)),
+    keyword("era", "LC_TIME", Item::Text(libc::ERA)),
+    keyword("era_d_fmt", "LC_TIME", Item::Text(libc::ERA_D_FMT)),
+    keyword("alt_digits", "LC_TIME", Item::Text(libc::ALT_DIGITS)),
+    keyword("era_d_t_fmt", "LC_TIME", Item::Text(libc::ERA_D_T_FMT)),
+    keyword("era_t_fmt", "LC_TIME", Item::Text(libc::ERA_T_FMT)),
+    keyword(
+        "int_curr_symbol",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.int_curr_symbol),
+    ),
+    keyword(
+        "currency_symbol",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.currency_symbol),
+    ),
+    keyword(
+        "mon_decimal_point",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.mon_decimal_point),
+    ),
+    keyword(
+        "mon_thousands_sep",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.mon_thousands_sep),
+    ),
+    keyword(
+        "mon_grouping",
+        "LC_MONETARY",
+        Item::Grouping(|lc| lc.mon_grouping),
+    ),
+    keyword(
+        "positive_sign",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.positive_sign),
+    ),
+    keyword(
+        "negative_sign",
+        "LC_MONETARY",
+        Item::Str(|lc| lc.negative_sign),
+    ),
+    keyword(
+        "int_frac_digits",
+        "LC_MONETARY",
+        Item::Num(|lc| lc.int_frac_digits),
+    ),
+    keyword("frac_digits", "LC_MONETARY", Item::Num(|lc| lc.frac_digits)),
+    keyword(
+        "p_cs_precedes",
+        "LC_MONETARY",
+        Item::Num(|lc| lc.p_cs_precedes),
+    ),
+    keyword(
+        "p_sep_by_space",
+        "LC_MONETARY",
+        Item::Num(|lc| lc.p_sep_by_space),
+    ),
+    keyword(
+        "n_cs_precedes",
+        "LC_MONETARY",
+        Item::Num(|lc| lc.n_cs_precedes),
+    ),
+    keyword(
+        "n_sep_by_space",
+        "LC_MONETARY",
+        Item::Num(|lc| lc.n_sep_by_space),
+    ),
+    keyword("p_sign_posn", "LC_MONETARY", Item::Num(|lc| lc.p_sign_posn)),
+    keyword("n_sign_posn", "LC_MONETARY", Item::Num(|lc| lc.n_sign_posn)),
+    keyword("yesexpr", "LC_MESSAGES", Item::Text(libc::YESEXPR)),
+    keyword("noexpr", "LC_MESSAGES", Item::Text(libc::NOEXPR)),
+];
+
+enum Value {
+    Str(String),
+    Num(i32),
+    Strs(Vec<String>),
+    Nums(Vec<i32>),
+}
+
+fn c_string(s: *const c_char) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    // SAFETY: the C library returns NUL-terminated strings
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+}
+
+fn langinfo(item: nl_item) -> String {
+    // SAFETY: nl_langinfo accepts any item, and returns a string that
+    // stays valid until the next call
+    c_string(unsafe { libc::nl_langinfo(item) })
+}
+
+/// A number of localeconv, of which CHAR_MAX means unavailable.
+fn lconv_number(n: c_char) -> i32 {
+    if n == c_char::MAX {
+        -1
+    } else {
+        i32::from(n)
+    }
+}
+
+fn value(item: &Item) -> Value {
+    // SAFETY: localeconv returns a valid structure, which stays valid
+    // until the next call
+    let lc = || unsafe { &*libc::localeconv() };
+    match item {
+        Item::Text(item) => Value::Str(langinfo(*item)),
+        Item::List(items) => Value::Strs(items.iter().map(|&item| langinfo(item)).collect()),
+        Item::Str(field) => Value::Str(c_string(field(lc()))),
+        Item::Num(field) => Value::Num(lconv_number(field(lc()))),
+        Item::Grouping(field) => {
+            let grouping = c_string(field(lc()));
+            let mut sizes: Vec<i32> = grouping
+                .bytes()
+                .map(|size| lconv_number(size as c_char))
+                .collect();
+            if sizes.is_empty() {
+                sizes.push(-1);
+            }
+            Value::Nums(sizes)
+        }
+    }
+}
+
+fn write_keyword(keyword: &Keyword, with_name: bool) {
+    let quoted = |s: &String| format!("\"{}\"", s);
+    let join = |values: Vec<String>| values.join(";");
+    let text = match (value(&keyword.item), with_name) {
+        (Value::Str(s), true) => quoted(&s),
+        (Value::Str(s), false) => s,
+        (Value::Num(n), _) => n.to_string(),
+        (Value::Strs(list), true) => join(list.iter().map(quoted).collect()),
+        (Value::Strs(list), false) => join(list),
+        (Value::Nums(list), _) => join(list.iter().map(i32::to_string).collect()),
+    };
+    if with_name {
+        println!("{}={}", keyword.name, text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+/// Writes the values of a category, or of a keyword. Returns whether
+/// `name` is either.
+fn write_name(name: &str, args: &Args) -> bool {
+    if CATEGORIES.contains(&name) {
+        if args.category_names {
+            println!("{}", name);
+        }
+        for keyword in KEYWORDS.iter().filter(|keyword| keyword.category == name) {
+            write_keyword(keyword, args.keyword_names);
+        }
+        return true;
+    }
+
+    match KEYWORDS.iter().find(|keyword| keyword.name == name) {
+        Some(keyword) => {
+            if args.category_names {
+                println!("{}", keyword.category);
+            }
+            write_keyword(keyword, args.keyword_names);
+            true
+        }
+        None => false,
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Writes the locale of each category, as the environment selects it.
+/// Values implied by LC_ALL or LANG, rather than set with the variable
+/// of the category, are quoted.
+fn write_environment() {
+    let lang = env_var("LANG");
+    let lc_all = env_var("LC_ALL");
+
+    println!("LANG={}", lang.as_deref().unwrap_or(""));
+    for category in CATEGORIES {
+        match (&lc_all, env_var(category)) {
+            (None, Some(value)) => println!("{}={}", category, value),
+            (Some(value), _) => println!("{}=\"{}\"", category, value),
+            (None, None) => println!("{}=\"{}\"", category, lang.as_deref().unwrap_or("POSIX")),
+        }
+    }
+    println!("LC_ALL={}", lc_all.as_deref().unwrap_or(""));
+}
+
+/// Directories holding one subdirectory per compiled locale.
+#[cfg(target_os = "linux")]
+const LOCALE_DIRS: &[&str] = &["/usr/lib/locale"];
+
+#[cfg(not(target_os = "linux"))]
+const LOCALE_DIRS: &[&str] = &["/usr/share/locale"];
+
+/// The glibc archive of compiled locales.
+const LOCALE_ARCHIVE: &str = "/usr/lib/locale/locale-archive";
+
+const LOCALE_ARCHIVE_MAGIC: u32 = 0xde020109;
+
+/// The names of the locales of the glibc locale archive. Its header is
+/// a sequence of native-endian 32-bit words, of which the third and
+/// fifth give the offset and size of the table of names: entries of a
+/// hash value, the offset of the name and the offset of the locale
+/// record, zero for unused entries.
+fn archive_locales(path: &Path) -> Vec<String> {
+    let Ok(data) = fs::read(path) else {
+        return Vec::new();
+    };
+    let word = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    if word(0) != Some(LOCALE_ARCHIVE_MAGIC as usize) {
+        return Vec::new();
+    }
+    let (Some(table), Some(size)) = (word(8), word(16)) else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    for i in 0..size {
+        let entry = table + i * 12;
+        let (Some(name), Some(record)) = (word(entry + 4), word(entry + 8)) else {
+            break;
+        };
+        if name == 0 || record == 0 {
+            continue;
+        }
+        if let Some(name) = data
+            .get(name..)
+            .and_then(|bytes| CStr::from_bytes_until_nul(bytes).ok())
+        {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+    names
+}
+
+fn write_locales() {
+    let mut locales = BTreeSet::from([String::from("C"), String::from("POSIX")]);
+    for dir in LOCALE_DIRS {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().join("LC_CTYPE").exists() {
+                locales.insert(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    locales.extend(archive_locales(Path::new(LOCALE_ARCHIVE)));
+
+    for locale in locales {
+        println!("{}", locale);
+    }
+}
+
+/// Directory of the charmaps of localedef, possibly compressed.
+const CHARMAP_DIR: &str = "/usr/share/i18n/charmaps";
+
+fn write_charmaps() {
+    let mut charmaps = BTreeSet::new();
+    if let Ok(entries) = fs::read_dir(CHARMAP_DIR) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let name = name.strip_suffix(".gz").unwrap_or(&name).to_string();
+            charmaps.insert(name);
+        }
+    }
+    for charmap in charmaps {
+        println!("{}", charmap);
+    }
+}
+
+fn main() {
+    // The values written are those of the locale of the environment
+    // SAFETY: called with a valid C string, before any other thread
+    let supported = !unsafe { libc::setlocale(libc::LC_ALL, c"".as_ptr()) }.is_null();
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    if args.all {
+        write_locales();
+        return;
+    }
+    if args.charmaps {
+        write_charmaps();
+        return;
+    }
+    if !supported {
+        tdiag!("cannot set the locale of the environment, using the POSIX locale");
+    }
+    if args.names.is_empty() {
+        write_environment();
+        return;
+    }
+
+    let mut status = 0;
+    for name in &args.names {
+        if !write_name(name, &args) {
+            tdiag!("unknown name '{}'", name);
+            status = 1;
+        }
+    }
+    std::process::exit(status);
+}
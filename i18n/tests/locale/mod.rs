@@ -0,0 +1,130 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test_with_checker, run_test_with_options, TestOptions, TestPlan};
+
+fn plan(
+    args: &[&str],
+    expected_out: &str,
+    expected_err: &str,
+    expected_exit_code: i32,
+) -> TestPlan {
+    TestPlan {
+        cmd: String::from("locale"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    }
+}
+
+fn locale_test(
+    env: &[(&str, &str)],
+    args: &[&str],
+    expected_out: &str,
+    expected_err: &str,
+    expected_exit_code: i32,
+) {
+    let mut options = TestOptions::default()
+        .env("LANG", "")
+        .env("LC_ALL", "")
+        .env("LC_CTYPE", "")
+        .env("LC_NUMERIC", "")
+        .env("LC_TIME", "")
+        .env("LC_COLLATE", "")
+        .env("LC_MONETARY", "")
+        .env("LC_MESSAGES", "");
+    for (name, value) in env {
+        options = options.env(name, value);
+    }
+    run_test_with_options(
+        plan(args, expected_out, expected_err, expected_exit_code),
+        options,
+    );
+}
+
+#[test]
+fn test_locale_environment() {
+    locale_test(
+        &[],
+        &[],
+        "LANG=\nLC_CTYPE=\"POSIX\"\nLC_NUMERIC=\"POSIX\"\nLC_TIME=\"POSIX\"\n\
+         LC_COLLATE=\"POSIX\"\nLC_MONETARY=\"POSIX\"\nLC_MESSAGES=\"POSIX\"\nLC_ALL=\n",
+        "",
+        0,
+    );
+    locale_test(
+        &[("LANG", "C"), ("LC_TIME", "POSIX")],
+        &[],
+        "LANG=C\nLC_CTYPE=\"C\"\nLC_NUMERIC=\"C\"\nLC_TIME=POSIX\n\
+         LC_COLLATE=\"C\"\nLC_MONETARY=\"C\"\nLC_MESSAGES=\"C\"\nLC_ALL=\n",
+        "",
+        0,
+    );
+    locale_test(
+        &[("LC_ALL", "C"), ("LC_TIME", "POSIX")],
+        &[],
+        "LANG=\nLC_CTYPE=\"C\"\nLC_NUMERIC=\"C\"\nLC_TIME=\"C\"\n\
+         LC_COLLATE=\"C\"\nLC_MONETARY=\"C\"\nLC_MESSAGES=\"C\"\nLC_ALL=C\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_locale_keywords() {
+    let env = [("LC_ALL", "POSIX")];
+    locale_test(&env, &["decimal_point"], ".\n", "", 0);
+    locale_test(
+        &env,
+        &["-k", "decimal_point"],
+        "decimal_point=\".\"\n",
+        "",
+        0,
+    );
+    locale_test(
+        &env,
+        &["-c", "-k", "frac_digits", "am_pm"],
+        "LC_MONETARY\nfrac_digits=-1\nLC_TIME\nam_pm=\"AM\";\"PM\"\n",
+        "",
+        0,
+    );
+    locale_test(
+        &env,
+        &["-k", "LC_NUMERIC"],
+        "decimal_point=\".\"\nthousands_sep=\"\"\ngrouping=-1\n",
+        "",
+        0,
+    );
+    locale_test(
+        &env,
+        &["abmon"],
+        "Jan;Feb;Mar;Apr;May;Jun;Jul;Aug;Sep;Oct;Nov;Dec\n",
+        "",
+        0,
+    );
+    locale_test(
+        &env,
+        &["nonesuch"],
+        "",
+        "locale: unknown name 'nonesuch'\n",
+        1,
+    );
+}
+
+#[test]
+fn test_locale_available() {
+    run_test_with_checker(plan(&["-a"], "", "", 0), |_, output| {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let locales: Vec<&str> = stdout.lines().collect();
+        assert!(locales.contains(&"C"));
+        assert!(locales.contains(&"POSIX"));
+    });
+}
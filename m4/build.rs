@@ -86,7 +86,7 @@ impl Test {
             r##"#[test]
 fn test_{name}() {{
     init();
-    let output = run_command(&Path::new("{input}"));
+    let output = run_command(Path::new("{input}"));
 
     let test: TestSnapshot = read_test("{output}");
     assert_eq!(output.status, std::process::ExitStatus::from_raw(test.status), "status (\x1b[31mcurrent\x1b[0m|\x1b[32mexpected\x1b[0m)");
@@ -266,16 +266,11 @@ fn run_command(input: &Path) -> std::process::Output {
         }
         b"args" => {
             let args = input_string;
-            let _cargo_build_output = std::process::Command::new("cargo")
-                .arg("build")
-                .output()
-                .unwrap();
-
             log::info!("RUST_LOG is ignored for this test because it interferes with output");
             let output = std::process::Command::new("sh")
                 .env("RUST_LOG", "") // Disable rust log output because it interferes with the test.
                 .arg("-c")
-                .arg(format!("../target/debug/m4 {args}"))
+                .arg(format!("{} {args}", plib::testing::test_bin_path("m4").display()))
                 .output()
                 .unwrap();
 
@@ -0,0 +1 @@
+include(`fixtures/integration_tests/include_recursive.m4')dnl
@@ -0,0 +1 @@
+define(`f', `f(f($1))')f(1)
@@ -55,6 +55,7 @@ pub enum ErrorKind {
     NotEnoughArguments,
     UnclosedQuote,
     UnclosedParenthesis,
+    RecursionLimit(usize),
     /// NOTE: this isn't always an "error", if the code is 0, it indicates an intentional,
     /// successful, early program exit, just hijacking the [`Result`] semantics to help enable this
     /// in a purely functional manner.
@@ -76,6 +77,7 @@ impl std::fmt::Display for Error {
             ErrorKind::Exit(code) => write!(f, "Program requested an exit with code {code}"),
             ErrorKind::UnclosedQuote => write!(f, "Unclosed quote"),
             ErrorKind::UnclosedParenthesis => write!(f, "Unclosed parenthesis"),
+            ErrorKind::RecursionLimit(limit) => write!(f, "Recursion limit of {limit} exceeded"),
         }?;
 
         if f.alternate() {
@@ -14,6 +14,10 @@ mod output;
 mod precedence;
 mod state;
 pub const EOF: u8 = b'\0';
+/// Maximum depth of nested macro calls, reached by runaway recursive definitions.
+pub const NESTING_LIMIT: usize = 1024;
+/// Maximum depth of nested `include` and `sinclude` files.
+pub const INCLUDE_LIMIT: usize = 64;
 
 #[derive(Debug, Clone)]
 pub struct ArgumentDefine {
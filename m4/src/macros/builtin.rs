@@ -16,7 +16,7 @@ use crate::macros::user_defined::UserDefinedMacro;
 use crate::macros::MacroDefinition;
 use crate::output::DivertBufferNumber;
 use crate::state::{StackFrame, State};
-use crate::EOF;
+use crate::{EOF, INCLUDE_LIMIT};
 
 const AT_LEAST_ONE_MACRO_DEFINITION_EXPECT: &str =
     "There should always be at least one macro definition";
@@ -251,6 +251,12 @@ impl IncludeMacro {
     }
 
     fn include_impl(path: PathBuf, state: State) -> crate::error::Result<State> {
+        if state.input.input_len() > INCLUDE_LIMIT {
+            return Err(crate::Error::new(crate::ErrorKind::RecursionLimit(
+                INCLUDE_LIMIT,
+            )))
+            .add_context(|| format!("Error including file {path:?}"));
+        }
         let file = std::fs::File::open(&path)
             .map_err(crate::Error::from)
             .add_context(|| format!("Error opening file {path:?}"))?;
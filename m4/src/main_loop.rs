@@ -4,7 +4,7 @@ use crate::error::{Error, ErrorKind};
 use crate::lexer::{is_alpha, is_space};
 use crate::macros::MacroImplementation;
 use crate::state::{StackFrame, State};
-use crate::EOF;
+use crate::{EOF, NESTING_LIMIT};
 
 /// The main loop, the most important function in this program.
 pub(crate) fn main_loop(mut state: State, stderr: &mut dyn Write) -> crate::error::Result<State> {
@@ -103,6 +103,9 @@ pub(crate) fn main_loop(mut state: State, stderr: &mut dyn Write) -> crate::erro
                 let frame = StackFrame::new(0, definition.clone());
 
                 if l == b'(' {
+                    if state.output.stack.len() >= NESTING_LIMIT {
+                        return Err(Error::new(ErrorKind::RecursionLimit(NESTING_LIMIT)));
+                    }
                     state.output.stack.push(frame);
                 } else {
                     state = definition.implementation.evaluate(state, stderr, frame)?;
@@ -71,16 +71,14 @@ fn run_command(input: &Path) -> std::process::Output {
         }
         b"args" => {
             let args = input_string;
-            let _cargo_build_output = std::process::Command::new("cargo")
-                .arg("build")
-                .output()
-                .unwrap();
-
             log::info!("RUST_LOG is ignored for this test because it interferes with output");
             let output = std::process::Command::new("sh")
                 .env("RUST_LOG", "") // Disable rust log output because it interferes with the test.
                 .arg("-c")
-                .arg(format!("../target/debug/m4 {args}"))
+                .arg(format!(
+                    "{} {args}",
+                    plib::testing::test_bin_path("m4").display()
+                ))
                 .output()
                 .unwrap();
 
@@ -109,7 +107,7 @@ fn run_command(input: &Path) -> std::process::Output {
 #[test]
 fn test_bsd() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/bsd.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/bsd.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/bsd.out");
     assert_eq!(
@@ -135,7 +133,7 @@ fn test_bsd() {
 #[test]
 fn test_bsd_math() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/bsd_math.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/bsd_math.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/bsd_math.out");
     assert_eq!(
@@ -160,7 +158,7 @@ fn test_bsd_math() {
 #[test]
 fn test_changecom() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/changecom.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/changecom.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/changecom.out");
     assert_eq!(
@@ -185,7 +183,7 @@ fn test_changecom() {
 #[test]
 fn test_changequote() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/changequote.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/changequote.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/changequote.out");
     assert_eq!(
@@ -210,7 +208,7 @@ fn test_changequote() {
 #[test]
 fn test_decr() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/decr.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/decr.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/decr.out");
     assert_eq!(
@@ -235,7 +233,7 @@ fn test_decr() {
 #[test]
 fn test_define() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/define.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/define.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/define.out");
     assert_eq!(
@@ -260,7 +258,7 @@ fn test_define() {
 #[test]
 fn test_define_args() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/define_args.args"));
+    let output = run_command(Path::new("fixtures/integration_tests/define_args.args"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/define_args.out");
     assert_eq!(
@@ -286,7 +284,7 @@ fn test_define_args() {
 #[test]
 fn test_define_eval_order_unquoted() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_eval_order_unquoted.m4",
     ));
 
@@ -314,7 +312,7 @@ fn test_define_eval_order_unquoted() {
 #[test]
 fn test_define_eval_syntax_order_quoted_evaluated() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_eval_syntax_order_quoted_evaluated.m4",
     ));
 
@@ -342,7 +340,7 @@ fn test_define_eval_syntax_order_quoted_evaluated() {
 #[test]
 fn test_define_eval_syntax_order_quoted_unevaluated() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_eval_syntax_order_quoted_unevaluated.m4",
     ));
 
@@ -371,7 +369,7 @@ fn test_define_eval_syntax_order_quoted_unevaluated() {
 #[test]
 fn test_define_eval_syntax_order_unquoted() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_eval_syntax_order_unquoted.m4",
     ));
 
@@ -399,7 +397,7 @@ fn test_define_eval_syntax_order_unquoted() {
 #[test]
 fn test_define_hanging_quotes() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_hanging_quotes.m4",
     ));
 
@@ -426,7 +424,7 @@ fn test_define_hanging_quotes() {
 #[test]
 fn test_define_invalid_macro_name() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_invalid_macro_name.m4",
     ));
 
@@ -453,7 +451,7 @@ fn test_define_invalid_macro_name() {
 #[test]
 fn test_define_iterative() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/define_iterative.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/define_iterative.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/define_iterative.out");
     assert_eq!(
@@ -478,7 +476,7 @@ fn test_define_iterative() {
 #[test]
 fn test_define_iterative_2() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_iterative_2.m4",
     ));
 
@@ -505,7 +503,7 @@ fn test_define_iterative_2() {
 #[test]
 fn test_define_nested() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/define_nested.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/define_nested.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/define_nested.out");
     assert_eq!(
@@ -531,7 +529,7 @@ fn test_define_nested() {
 #[test]
 fn test_define_nested_first_arg() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_nested_first_arg.m4",
     ));
 
@@ -558,7 +556,7 @@ fn test_define_nested_first_arg() {
 #[test]
 fn test_define_number_parsing() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_number_parsing.m4",
     ));
 
@@ -583,7 +581,7 @@ fn test_define_number_parsing() {
 #[test]
 fn test_define_order_defined() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_order_defined.m4",
     ));
 
@@ -610,7 +608,7 @@ fn test_define_order_defined() {
 #[test]
 fn test_define_order_undefined() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_order_undefined.m4",
     ));
 
@@ -637,7 +635,7 @@ fn test_define_order_undefined() {
 #[test]
 fn test_define_parse_brackets() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_parse_brackets.m4",
     ));
 
@@ -662,7 +660,7 @@ fn test_define_parse_brackets() {
 #[test]
 fn test_define_pushpopdef_undefine() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_pushpopdef_undefine.m4",
     ));
 
@@ -689,7 +687,7 @@ fn test_define_pushpopdef_undefine() {
 #[test]
 fn test_define_quoted_number_stacked() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_quoted_number_stacked.m4",
     ));
 
@@ -715,7 +713,7 @@ fn test_define_quoted_number_stacked() {
 #[test]
 fn test_define_stacked() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/define_stacked.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/define_stacked.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/define_stacked.out");
     assert_eq!(
@@ -740,7 +738,7 @@ fn test_define_stacked() {
 #[test]
 fn test_define_undefine_order() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_undefine_order.m4",
     ));
 
@@ -767,7 +765,7 @@ fn test_define_undefine_order() {
 #[test]
 fn test_define_unquoted_number_arg() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/define_unquoted_number_arg.m4",
     ));
 
@@ -792,7 +790,7 @@ fn test_define_unquoted_number_arg() {
 #[test]
 fn test_defn() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/defn.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/defn.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/defn.out");
     assert_eq!(
@@ -817,7 +815,7 @@ fn test_defn() {
 #[test]
 fn test_divert() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/divert.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/divert.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/divert.out");
     assert_eq!(
@@ -842,7 +840,7 @@ fn test_divert() {
 #[test]
 fn test_divert_nested() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/divert_nested.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/divert_nested.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/divert_nested.out");
     assert_eq!(
@@ -867,7 +865,7 @@ fn test_divert_nested() {
 #[test]
 fn test_divert_nested_2() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/divert_nested_2.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/divert_nested_2.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/divert_nested_2.out");
     assert_eq!(
@@ -892,7 +890,7 @@ fn test_divert_nested_2() {
 #[test]
 fn test_divert_nested_3() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/divert_nested_3.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/divert_nested_3.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/divert_nested_3.out");
     assert_eq!(
@@ -917,7 +915,7 @@ fn test_divert_nested_3() {
 #[test]
 fn test_divert_nested_4() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/divert_nested_4.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/divert_nested_4.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/divert_nested_4.out");
     assert_eq!(
@@ -942,7 +940,7 @@ fn test_divert_nested_4() {
 #[test]
 fn test_dnl() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/dnl.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/dnl.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/dnl.out");
     assert_eq!(
@@ -967,7 +965,7 @@ fn test_dnl() {
 #[test]
 fn test_dnl_nested() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/dnl_nested.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/dnl_nested.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/dnl_nested.out");
     assert_eq!(
@@ -992,7 +990,7 @@ fn test_dnl_nested() {
 #[test]
 fn test_dumpdef() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/dumpdef.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/dumpdef.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/dumpdef.out");
     assert_eq!(
@@ -1017,7 +1015,7 @@ fn test_dumpdef() {
 #[test]
 fn test_dumpdef_notexist() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/dumpdef_notexist.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/dumpdef_notexist.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/dumpdef_notexist.out");
     assert_eq!(
@@ -1040,7 +1038,7 @@ fn test_dumpdef_notexist() {
 #[test]
 fn test_eval() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/eval.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/eval.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/eval.out");
     assert_eq!(
@@ -1065,7 +1063,7 @@ fn test_eval() {
 #[test]
 fn test_evaluation_order() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/evaluation_order.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/evaluation_order.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/evaluation_order.out");
     assert_eq!(
@@ -1090,7 +1088,7 @@ fn test_evaluation_order() {
 #[test]
 fn test_file() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/file.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/file.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/file.out");
     assert_eq!(
@@ -1115,7 +1113,7 @@ fn test_file() {
 #[test]
 fn test_forloop_nested() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/forloop_nested.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/forloop_nested.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/forloop_nested.out");
     assert_eq!(
@@ -1140,7 +1138,7 @@ fn test_forloop_nested() {
 #[test]
 fn test_forloop_simple() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/forloop_simple.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/forloop_simple.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/forloop_simple.out");
     assert_eq!(
@@ -1165,7 +1163,7 @@ fn test_forloop_simple() {
 #[test]
 fn test_ifdef() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/ifdef.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/ifdef.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/ifdef.out");
     assert_eq!(
@@ -1190,7 +1188,7 @@ fn test_ifdef() {
 #[test]
 fn test_ifelse() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/ifelse.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/ifelse.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/ifelse.out");
     assert_eq!(
@@ -1215,7 +1213,7 @@ fn test_ifelse() {
 #[test]
 fn test_include() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/include.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/include.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/include.out");
     assert_eq!(
@@ -1240,7 +1238,7 @@ fn test_include() {
 #[test]
 fn test_include_divert() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/include_divert.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/include_divert.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/include_divert.out");
     assert_eq!(
@@ -1262,10 +1260,33 @@ fn test_include_divert() {
     );
 }
 
+#[test]
+fn test_include_recursive() {
+    init();
+    let output = run_command(Path::new("fixtures/integration_tests/include_recursive.m4"));
+
+    let test: TestSnapshot = read_test("fixtures/integration_tests/include_recursive.out");
+    assert_eq!(
+        output.status,
+        std::process::ExitStatus::from_raw(test.status),
+        "status (\x1b[31mcurrent\x1b[0m|\x1b[32mexpected\x1b[0m)"
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        test.stdout,
+        "stdout (\x1b[31mcurrent\x1b[0m|\x1b[32mexpected\x1b[0m)"
+    );
+
+    if !test.stderr.is_empty() {
+        assert!(!output.stderr.is_empty());
+    }
+}
+
 #[test]
 fn test_incr() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/incr.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/incr.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/incr.out");
     assert_eq!(
@@ -1290,7 +1311,7 @@ fn test_incr() {
 #[test]
 fn test_index() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/index.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/index.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/index.out");
     assert_eq!(
@@ -1315,7 +1336,7 @@ fn test_index() {
 #[test]
 fn test_index_too_few_args() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/index_too_few_args.m4",
     ));
 
@@ -1340,7 +1361,7 @@ fn test_index_too_few_args() {
 #[test]
 fn test_len() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/len.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/len.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/len.out");
     assert_eq!(
@@ -1365,7 +1386,7 @@ fn test_len() {
 #[test]
 fn test_m4exit_error() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/m4exit_error.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/m4exit_error.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/m4exit_error.out");
     assert_eq!(
@@ -1390,7 +1411,7 @@ fn test_m4exit_error() {
 #[test]
 fn test_m4exit_no_args() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/m4exit_no_args.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/m4exit_no_args.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/m4exit_no_args.out");
     assert_eq!(
@@ -1415,7 +1436,7 @@ fn test_m4exit_no_args() {
 #[test]
 fn test_m4exit_success() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/m4exit_success.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/m4exit_success.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/m4exit_success.out");
     assert_eq!(
@@ -1440,7 +1461,7 @@ fn test_m4exit_success() {
 #[test]
 fn test_m4wrap() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/m4wrap.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/m4wrap.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/m4wrap.out");
     assert_eq!(
@@ -1465,7 +1486,7 @@ fn test_m4wrap() {
 #[test]
 fn test_macro_errprint_evaluation() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/macro_errprint_evaluation.m4",
     ));
 
@@ -1492,7 +1513,7 @@ fn test_macro_errprint_evaluation() {
 #[test]
 fn test_macro_errprint_no_evaluation() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/macro_errprint_no_evaluation.m4",
     ));
 
@@ -1520,7 +1541,7 @@ fn test_macro_errprint_no_evaluation() {
 #[test]
 fn test_macro_errprint_no_evaluation_quoted() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/macro_errprint_no_evaluation_quoted.m4",
     ));
 
@@ -1548,7 +1569,7 @@ fn test_macro_errprint_no_evaluation_quoted() {
 #[test]
 fn test_maketemp() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/maketemp.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/maketemp.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/maketemp.out");
     assert_eq!(
@@ -1574,7 +1595,7 @@ fn test_maketemp() {
 #[test]
 fn test_mkstemp() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/mkstemp.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/mkstemp.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/mkstemp.out");
     assert_eq!(
@@ -1600,7 +1621,7 @@ fn test_mkstemp() {
 #[test]
 fn test_quoted_nested_eof_in_string() {
     init();
-    let output = run_command(&Path::new(
+    let output = run_command(Path::new(
         "fixtures/integration_tests/quoted_nested_eof_in_string.m4",
     ));
 
@@ -1626,7 +1647,7 @@ fn test_quoted_nested_eof_in_string() {
 #[test]
 fn test_recurse() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/recurse.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/recurse.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/recurse.out");
     assert_eq!(
@@ -1648,12 +1669,33 @@ fn test_recurse() {
     );
 }
 
+#[test]
+fn test_recursion_limit() {
+    init();
+    let output = run_command(Path::new("fixtures/integration_tests/recursion_limit.m4"));
+
+    let test: TestSnapshot = read_test("fixtures/integration_tests/recursion_limit.out");
+    assert_eq!(
+        output.status,
+        std::process::ExitStatus::from_raw(test.status),
+        "status (\x1b[31mcurrent\x1b[0m|\x1b[32mexpected\x1b[0m)"
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        test.stdout,
+        "stdout (\x1b[31mcurrent\x1b[0m|\x1b[32mexpected\x1b[0m)"
+    );
+
+    if !test.stderr.is_empty() {
+        assert!(!output.stderr.is_empty());
+    }
+}
+
 #[test]
 fn test_recursive_defines() {
     init();
-    let output = run_command(&Path::new(
-        "fixtures/integration_tests/recursive_defines.m4",
-    ));
+    let output = run_command(Path::new("fixtures/integration_tests/recursive_defines.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/recursive_defines.out");
     assert_eq!(
@@ -1678,7 +1720,7 @@ fn test_recursive_defines() {
 #[test]
 fn test_redefine_inbuilt() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/redefine_inbuilt.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/redefine_inbuilt.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/redefine_inbuilt.out");
     assert_eq!(
@@ -1703,7 +1745,7 @@ fn test_redefine_inbuilt() {
 #[test]
 fn test_reverse() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/reverse.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/reverse.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/reverse.out");
     assert_eq!(
@@ -1728,7 +1770,7 @@ fn test_reverse() {
 #[test]
 fn test_shift() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/shift.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/shift.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/shift.out");
     assert_eq!(
@@ -1753,7 +1795,7 @@ fn test_shift() {
 #[test]
 fn test_sinclude() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/sinclude.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/sinclude.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/sinclude.out");
     assert_eq!(
@@ -1778,7 +1820,7 @@ fn test_sinclude() {
 #[test]
 fn test_substr() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/substr.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/substr.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/substr.out");
     assert_eq!(
@@ -1804,7 +1846,7 @@ fn test_substr() {
 #[test]
 fn test_synclines_1() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/synclines_1.args"));
+    let output = run_command(Path::new("fixtures/integration_tests/synclines_1.args"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/synclines_1.out");
     assert_eq!(
@@ -1830,7 +1872,7 @@ fn test_synclines_1() {
 #[test]
 fn test_synclines_2() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/synclines_2.args"));
+    let output = run_command(Path::new("fixtures/integration_tests/synclines_2.args"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/synclines_2.out");
     assert_eq!(
@@ -1856,7 +1898,7 @@ fn test_synclines_2() {
 #[test]
 fn test_syscmd_sysval() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/syscmd_sysval.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/syscmd_sysval.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/syscmd_sysval.out");
     assert_eq!(
@@ -1881,7 +1923,7 @@ fn test_syscmd_sysval() {
 #[test]
 fn test_trace() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/trace.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/trace.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/trace.out");
     assert_eq!(
@@ -1906,7 +1948,7 @@ fn test_trace() {
 #[test]
 fn test_translit() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/translit.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/translit.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/translit.out");
     assert_eq!(
@@ -1931,7 +1973,7 @@ fn test_translit() {
 #[test]
 fn test_two_files() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/two_files.args"));
+    let output = run_command(Path::new("fixtures/integration_tests/two_files.args"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/two_files.out");
     assert_eq!(
@@ -1956,7 +1998,7 @@ fn test_two_files() {
 #[test]
 fn test_undivert() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/undivert.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/undivert.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/undivert.out");
     assert_eq!(
@@ -1981,7 +2023,7 @@ fn test_undivert() {
 #[test]
 fn test_undivert_2() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/undivert_2.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/undivert_2.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/undivert_2.out");
     assert_eq!(
@@ -2006,7 +2048,7 @@ fn test_undivert_2() {
 #[test]
 fn test_undivert_current() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/undivert_current.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/undivert_current.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/undivert_current.out");
     assert_eq!(
@@ -2031,7 +2073,7 @@ fn test_undivert_current() {
 #[test]
 fn test_undivert_nested() {
     init();
-    let output = run_command(&Path::new("fixtures/integration_tests/undivert_nested.m4"));
+    let output = run_command(Path::new("fixtures/integration_tests/undivert_nested.m4"));
 
     let test: TestSnapshot = read_test("fixtures/integration_tests/undivert_nested.out");
     assert_eq!(
@@ -15,8 +15,9 @@ pub mod signal_handler;
 pub mod special_target;
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeSet, HashSet},
     fs::{self},
+    path::Path,
     time::SystemTime,
 };
 
@@ -40,6 +41,7 @@ const DEFAULT_SHELL: &str = "/bin/sh";
 pub struct Make {
     macros: Vec<VariableDefinition>,
     rules: Vec<Rule>,
+    inference_rules: Vec<Rule>,
     default_rule: Option<Rule>, // .DEFAULT
     pub config: Config,
 }
@@ -57,6 +59,89 @@ impl Make {
             .find(|rule| rule.targets().any(|t| t.as_ref() == target.as_ref()))
     }
 
+    /// Finds the inference rule to build the target with, if the target has no recipes of its own.
+    ///
+    /// Double-suffix rules (`.s1.s2`) build `stem.s2` from `stem.s1` and single-suffix rules
+    /// (`.s1`) build `name` from `name.s1`. Both suffixes must be listed in `.SUFFIXES` and the
+    /// source must either exist or be buildable by an explicit rule.
+    ///
+    /// # Returns
+    ///
+    /// - Some((rule, source, stem)) if an inference rule applies to the target.
+    /// - None if the target has its own recipes or no inference rule applies.
+    fn inference_rule_for(&self, target: &str) -> Option<(&Rule, String, String)> {
+        if self
+            .rule_by_target_name(target)
+            .is_some_and(|rule| rule.recipes().next().is_some())
+        {
+            return None;
+        }
+
+        let empty = BTreeSet::new();
+        let suffixes = self
+            .config
+            .rules
+            .get(SpecialTarget::Suffixes.as_ref())
+            .unwrap_or(&empty);
+        let candidates = || self.inference_rules.iter().chain(self.rules.iter());
+        let is_source =
+            |source: &str| Path::new(source).exists() || self.rule_by_target_name(source).is_some();
+
+        for suffix in suffixes.iter().filter(|s| target.ends_with(s.as_str())) {
+            let stem = &target[..target.len() - suffix.len()];
+            if stem.is_empty() {
+                continue;
+            }
+
+            for rule in candidates() {
+                for t in rule.targets() {
+                    let Target::Inference { from, to, .. } = t else {
+                        continue;
+                    };
+                    let from = format!(".{from}");
+                    if format!(".{to}") != *suffix || !suffixes.contains(&from) {
+                        continue;
+                    }
+
+                    let source = format!("{stem}{from}");
+                    if is_source(&source) {
+                        return Some((rule, source, stem.to_string()));
+                    }
+                }
+            }
+        }
+
+        for suffix in suffixes {
+            let source = format!("{target}{suffix}");
+            if !is_source(&source) {
+                continue;
+            }
+
+            for rule in candidates() {
+                if rule.targets().any(|t| t.as_ref() == suffix) {
+                    return Some((rule, source, target.to_string()));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Retrieves the prerequisites of the target, including the source file of the inference rule
+    /// that builds it.
+    fn prerequisites_of(&self, target: impl AsRef<str>) -> Vec<Prerequisite> {
+        let mut prerequisites: Vec<Prerequisite> = self
+            .rule_by_target_name(&target)
+            .map(|rule| rule.prerequisites().cloned().collect())
+            .unwrap_or_default();
+
+        if let Some((_, source, _)) = self.inference_rule_for(target.as_ref()) {
+            prerequisites.push(Prerequisite::new(source));
+        }
+
+        prerequisites
+    }
+
     pub fn first_target(&self) -> Result<&Target, ErrorCode> {
         let rule = self.rules.first().ok_or(NoTarget { target: None })?;
         rule.targets().next().ok_or(NoTarget { target: None })
@@ -69,8 +154,16 @@ impl Make {
     /// - Ok(false) if the target was already up to date.
     /// - Err(_) if any errors occur.
     pub fn build_target(&self, name: impl AsRef<str>) -> Result<bool, ErrorCode> {
+        let target = Target::new(name.as_ref());
+
+        if let Some((rule, source, stem)) = self.inference_rule_for(name.as_ref()) {
+            return self.run_rule_with_prerequisites(rule, &target, Some((&source, &stem)));
+        }
+
         let rule = match self.rule_by_target_name(&name) {
             Some(rule) => rule,
+            // an existing file without a rule is up to date
+            None if Path::new(name.as_ref()).exists() => return Ok(false),
             None => match &self.default_rule {
                 Some(rule) => rule,
                 None => {
@@ -80,18 +173,23 @@ impl Make {
                 }
             },
         };
-        let target = Target::new(name.as_ref());
 
-        self.run_rule_with_prerequisites(rule, &target)
+        self.run_rule_with_prerequisites(rule, &target, None)
     }
 
-    /// Runs the given rule.
+    /// Runs the given rule. `inferred` holds the source and the stem when the rule is an
+    /// inference rule applied to the target.
     ///
     /// # Returns
     /// - Ok(true) if the rule was run.
     /// - Ok(false) if the rule was already up to date.
     /// - Err(_) if any errors occur.
-    fn run_rule_with_prerequisites(&self, rule: &Rule, target: &Target) -> Result<bool, ErrorCode> {
+    fn run_rule_with_prerequisites(
+        &self,
+        rule: &Rule,
+        target: &Target,
+        inferred: Option<(&str, &str)>,
+    ) -> Result<bool, ErrorCode> {
         if self.are_prerequisites_recursive(target) {
             return Err(RecursivePrerequisite {
                 origin: target.to_string(),
@@ -111,22 +209,23 @@ impl Make {
         for prerequisite in &newer_prerequisites {
             self.build_target(prerequisite)?;
         }
-        rule.run(&self.config, &self.macros, target, up_to_date)?;
+        match inferred {
+            Some((source, stem)) => {
+                rule.run_inferred(&self.config, &self.macros, target, up_to_date, source, stem)?
+            }
+            None => rule.run(&self.config, &self.macros, target, up_to_date)?,
+        }
 
         Ok(true)
     }
 
     /// Retrieves the prerequisites of the target that are newer than the target.
     /// Recursively checks the prerequisites of the prerequisites.
-    /// Returns an empty vector if the target does not exist (or it's a file).
-    fn get_newer_prerequisites(&self, target: impl AsRef<str>) -> Vec<&Prerequisite> {
-        let Some(target_rule) = self.rule_by_target_name(&target) else {
-            return vec![];
-        };
+    /// Returns an empty vector if the target has no prerequisites (e.g. it's a plain file).
+    fn get_newer_prerequisites(&self, target: impl AsRef<str>) -> Vec<Prerequisite> {
+        let prerequisites = self.prerequisites_of(&target).into_iter();
         let target_modified = get_modified_time(target);
 
-        let prerequisites = target_rule.prerequisites();
-
         if let Some(target_modified) = target_modified {
             prerequisites
                 .filter(|prerequisite| {
@@ -205,6 +304,7 @@ impl TryFrom<(Makefile, Config)> for Make {
 
         let mut make = Self {
             rules,
+            inference_rules,
             macros: makefile.variable_definitions().collect(),
             default_rule: None,
             config,
@@ -75,6 +75,47 @@ impl Rule {
         macros: &[VariableDefinition],
         target: &Target,
         up_to_date: bool,
+    ) -> Result<(), ErrorCode> {
+        let files = match target {
+            Target::Inference { from, .. } => find_files_with_extension(from)?
+                .into_iter()
+                .map(|input| {
+                    let stem = input.with_extension("");
+                    (input, stem)
+                })
+                .collect::<Vec<_>>(),
+            _ => {
+                vec![(PathBuf::from(""), PathBuf::from(""))]
+            }
+        };
+
+        self.run_for_files(global_config, macros, target, up_to_date, files)
+    }
+
+    /// Runs the rule as an inference rule that builds `target` from `source`.
+    ///
+    /// In the recipes `$<` expands to `source` and `$*` to `stem`.
+    pub fn run_inferred(
+        &self,
+        global_config: &GlobalConfig,
+        macros: &[VariableDefinition],
+        target: &Target,
+        up_to_date: bool,
+        source: &str,
+        stem: &str,
+    ) -> Result<(), ErrorCode> {
+        let files = vec![(PathBuf::from(source), PathBuf::from(stem))];
+        self.run_for_files(global_config, macros, target, up_to_date, files)
+    }
+
+    /// Runs the recipes once for every `($<, $*)` pair in `files`.
+    fn run_for_files(
+        &self,
+        global_config: &GlobalConfig,
+        macros: &[VariableDefinition],
+        target: &Target,
+        up_to_date: bool,
+        files: Vec<(PathBuf, PathBuf)>,
     ) -> Result<(), ErrorCode> {
         let GlobalConfig {
             ignore: global_ignore,
@@ -97,20 +138,6 @@ impl Rule {
             phony: _,
         } = self.config;
 
-        let files = match target {
-            Target::Inference { from, to, .. } => find_files_with_extension(from)?
-                .into_iter()
-                .map(|input| {
-                    let mut output = input.clone();
-                    output.set_extension(to);
-                    (input, output)
-                })
-                .collect::<Vec<_>>(),
-            _ => {
-                vec![(PathBuf::from(""), PathBuf::from(""))]
-            }
-        };
-
         for inout in files {
             for recipe in self.recipes() {
                 let RecipeConfig {
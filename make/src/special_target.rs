@@ -183,7 +183,7 @@ pub fn process(rule: Rule, make: &mut Make) -> Result<(), ErrorCode> {
         Phony => this.process_phony(),
         Precious => this.process_precious(),
         SccsGet => this.process_sccs_get(),
-        unsupported => Err(Error::NotSupported(unsupported)),
+        Posix => this.process_posix(),
     }
     .map_err(|err| ErrorCode::SpecialTargetConstraintNotFulfilled {
         target: target.to_string(),
@@ -313,6 +313,14 @@ impl Processor<'_> {
             .insert(Precious.as_ref().to_string(), precious_set);
         Ok(())
     }
+    fn process_posix(self) -> Result<(), Error> {
+        self.without_prerequisites()?;
+        self.without_recipes()?;
+
+        // the behavior of this implementation is already POSIX-conforming
+        Ok(())
+    }
+
     fn process_sccs_get(self) -> Result<(), Error> {
         self.without_prerequisites()?;
 
@@ -12,7 +12,7 @@ use std::fs::{remove_file, File};
 use std::io::Write;
 use std::process::{Child, Command, Stdio};
 
-use plib::testing::{run_test, run_test_base, TestPlan};
+use plib::testing::{run_test, run_test_base, test_bin_path, TestPlan};
 
 use posixutils_make::error_code::ErrorCode;
 
@@ -96,22 +96,7 @@ fn run_test_helper_with_setup_and_destruct(
 }
 
 fn manual_test_helper(args: &[&str]) -> Child {
-    // Determine the binary path based on the build profile
-    let relpath = if cfg!(debug_assertions) {
-        format!("target/debug/{}", "make")
-    } else {
-        format!("target/release/{}", "make")
-    };
-
-    // Build the full path to the binary
-    let test_bin_path = env::current_dir()
-        .expect("failed to get current directory")
-        .parent()
-        .expect("failed to get parent directory")
-        .join(relpath);
-
-    // Create and spawn the command
-    Command::new(test_bin_path)
+    Command::new(test_bin_path("make"))
         .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -459,6 +444,34 @@ mod target_behavior {
         );
     }
 
+    #[test]
+    fn inference_rules() {
+        let remove_outputs = || {
+            let dir = "tests/makefiles/target_behavior/inference_rules";
+            let _ = remove_file(format!("{}/hello.out", dir));
+            let _ = remove_file(format!("{}/greet", dir));
+        };
+
+        run_test_helper_with_setup_and_destruct(
+            &["-C", "tests/makefiles/target_behavior/inference_rules"],
+            "hello.in -> hello.out (hello)\ngreet.sh -> greet\n",
+            "",
+            0,
+            remove_outputs,
+            || {},
+        );
+
+        // the outputs are now newer than their sources
+        run_test_helper_with_setup_and_destruct(
+            &["-C", "tests/makefiles/target_behavior/inference_rules"],
+            "",
+            "",
+            0,
+            || {},
+            remove_outputs,
+        );
+    }
+
     #[test]
     fn async_events() {
         let args = [
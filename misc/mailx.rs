@@ -0,0 +1,394 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+// TODO:
+// - receive mode, other than -e
+// - tilde escapes and the dot variable when reading from a terminal
+// - -F, recording the message in a file named after the recipient
+// - commands in the start-up file other than set and unset
+// - RFC 2047 encoding of non-ASCII header fields
+//
+
+mod smtp;
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+use clap::Parser;
+use plib::diag::error_message;
+
+/// Where sendmail is looked for, unless the sendmail variable names it.
+const SENDMAIL_PATHS: [&str; 2] = ["/usr/sbin/sendmail", "/usr/lib/sendmail"];
+
+/// The server a message is submitted to if there is no sendmail.
+const DEFAULT_SMTP_SERVER: &str = "localhost";
+
+/// mailx - process messages
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Exit with status 0 if there is mail to read, and 1 otherwise.
+    #[arg(short = 'e')]
+    check: bool,
+
+    /// Read messages from a file instead of the system mailbox.
+    #[arg(short = 'f')]
+    folder: bool,
+
+    /// Record the message in a file named after the first recipient.
+    #[arg(short = 'F')]
+    record: bool,
+
+    /// Write a header summary only.
+    #[arg(short = 'H')]
+    headers_only: bool,
+
+    /// Ignore interrupts.
+    #[arg(short = 'i')]
+    ignore_interrupts: bool,
+
+    /// Do not read the system start-up file.
+    #[arg(short = 'n')]
+    no_system_startup: bool,
+
+    /// Do not write the header summary.
+    #[arg(short = 'N')]
+    no_header_summary: bool,
+
+    /// The subject of the message.
+    #[arg(short = 's', value_name = "SUBJECT")]
+    subject: Option<String>,
+
+    /// Read the system mailbox of this user.
+    #[arg(short = 'u', value_name = "USER")]
+    user: Option<String>,
+
+    /// The addresses to send the message to.
+    addresses: Vec<String>,
+}
+
+/// The variables set by the start-up file. `save` is set by default.
+struct Variables(HashMap<String, String>);
+
+impl Variables {
+    fn new() -> Self {
+        Variables(HashMap::from([(String::from("save"), String::new())]))
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn is_set(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Applies `set` and `unset` commands, ignoring any others.
+    fn source(&mut self, text: &str) {
+        for line in text.lines() {
+            let words = split_words(line);
+            let Some((command, args)) = words.split_first() else {
+                continue;
+            };
+            match command.as_str() {
+                "se" | "set" => {
+                    for arg in args {
+                        match arg.split_once('=') {
+                            Some((name, value)) => {
+                                self.0.insert(String::from(name), String::from(value));
+                            }
+                            None => match arg.strip_prefix("no") {
+                                Some(name) => {
+                                    self.0.remove(name);
+                                }
+                                None => {
+                                    self.0.insert(arg.clone(), String::new());
+                                }
+                            },
+                        }
+                    }
+                }
+                "uns" | "unset" => {
+                    for arg in args {
+                        self.0.remove(arg);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Splits a start-up file line into words at blanks, with quotes
+/// protecting them, up to a `#` comment.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_with(String::new).push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            None if c == '#' && word.is_none() => break,
+            None if c == ' ' || c == '\t' => words.extend(word.take()),
+            None => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+fn home() -> PathBuf {
+    PathBuf::from(std::env::var_os("HOME").unwrap_or_default())
+}
+
+/// Reads the start-up file named by MAILRC, or `$HOME/.mailrc`, if there
+/// is one.
+fn read_startup(vars: &mut Variables) {
+    let path = match std::env::var_os("MAILRC") {
+        Some(path) => PathBuf::from(path),
+        None => home().join(".mailrc"),
+    };
+    if let Ok(text) = fs::read_to_string(path) {
+        vars.source(&text);
+    }
+}
+
+fn login_name() -> String {
+    // SAFETY: getuid cannot fail
+    let uid = unsafe { libc::getuid() };
+    match plib::userdb::user_by_uid(uid) {
+        Some(user) => user.name.clone(),
+        None => uid.to_string(),
+    }
+}
+
+/// The system mailbox: MAIL, or the mailbox of the user in the mail
+/// spool directory.
+fn mailbox() -> PathBuf {
+    match std::env::var_os("MAIL") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new("/var/mail").join(login_name()),
+    }
+}
+
+/// The message: its header fields, a blank line and the body.
+fn compose(headers: &[(&str, String)], body: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(body.len() + 256);
+    for (name, value) in headers {
+        message.extend_from_slice(format!("{}: {}\n", name, value).as_bytes());
+    }
+    message.push(b'\n');
+    message.extend_from_slice(body);
+    if !body.is_empty() && !body.ends_with(b"\n") {
+        message.push(b'\n');
+    }
+    message
+}
+
+enum Transport {
+    /// A program taking the message on its standard input and the
+    /// recipients as operands.
+    Sendmail(String),
+    /// An SMTP server, as `host` or `host:port`.
+    Smtp(String),
+}
+
+impl Transport {
+    /// The smtp variable if set, else the sendmail variable or the system
+    /// sendmail, and else the local SMTP server.
+    fn select(vars: &Variables) -> Self {
+        if let Some(server) = vars.get("smtp") {
+            return Transport::Smtp(String::from(server));
+        }
+        if let Some(program) = vars.get("sendmail") {
+            return Transport::Sendmail(String::from(program));
+        }
+        match SENDMAIL_PATHS.iter().find(|path| Path::new(path).exists()) {
+            Some(path) => Transport::Sendmail(String::from(*path)),
+            None => Transport::Smtp(String::from(DEFAULT_SMTP_SERVER)),
+        }
+    }
+}
+
+/// Hands the message to sendmail, which adds the header fields it lacks.
+fn sendmail(program: &str, recipients: &[String], message: &[u8]) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .arg("-i")
+        .arg("--")
+        .args(recipients)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", program, error_message(&e))))?;
+    let written = child.stdin.take().unwrap().write_all(message);
+    let status = child.wait()?;
+    written?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(plib::tr!(
+            "{}: failed with {}",
+            program,
+            status
+        )))
+    }
+}
+
+/// Appends an unsent message to DEAD, or `$HOME/dead.letter`.
+fn save_dead_letter(message: &[u8]) -> io::Result<()> {
+    let path = match std::env::var_os("DEAD") {
+        Some(path) => PathBuf::from(path),
+        None => home().join("dead.letter"),
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(message)
+}
+
+/// Reads the subject from the terminal if -s did not give it, then the
+/// body up to end-of-file.
+fn read_message(subject: Option<String>) -> io::Result<(String, Vec<u8>)> {
+    let mut stdin = io::stdin().lock();
+    let subject = match subject {
+        Some(subject) => subject,
+        None if stdin.is_terminal() => {
+            print!("{}", plib::tr!("Subject: "));
+            io::stdout().flush()?;
+            let mut line = String::new();
+            stdin.read_line(&mut line)?;
+            String::from(line.trim_end_matches('\n'))
+        }
+        None => String::new(),
+    };
+    let mut body = Vec::new();
+    stdin.read_to_end(&mut body)?;
+    Ok((subject, body))
+}
+
+fn send(args: Args, vars: &Variables) -> io::Result<()> {
+    let (subject, body) = read_message(args.subject)?;
+
+    let transport = Transport::select(vars);
+    let mut headers = Vec::new();
+    if let Transport::Smtp(_) = transport {
+        // Nothing on the way adds these for us
+        let date = plib::timefmt::format_local("%a, %d %b %Y %H:%M:%S %z", SystemTime::now());
+        headers.push(("Date", date));
+        headers.push(("From", from_address(vars)));
+    }
+    headers.push(("To", args.addresses.join(", ")));
+    if !subject.is_empty() {
+        headers.push(("Subject", subject));
+    }
+    let message = compose(&headers, &body);
+
+    let sent = match &transport {
+        Transport::Sendmail(program) => sendmail(program, &args.addresses, &message),
+        Transport::Smtp(server) => {
+            smtp::submit(server, &from_address(vars), &args.addresses, &message)
+        }
+    };
+    if sent.is_err() && vars.is_set("save") {
+        if let Err(e) = save_dead_letter(&message) {
+            plib::diag!("{}", error_message(&e));
+        }
+    }
+    sent
+}
+
+/// The from variable, or the login name at this host.
+fn from_address(vars: &Variables) -> String {
+    match vars.get("from") {
+        Some(from) => String::from(from),
+        None => format!("{}@{}", login_name(), plib::curuser::hostname()),
+    }
+}
+
+fn main() {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    if args.check {
+        let has_mail = fs::metadata(mailbox()).is_ok_and(|m| m.len() > 0);
+        std::process::exit(i32::from(!has_mail));
+    }
+    if args.addresses.is_empty()
+        || args.folder
+        || args.record
+        || args.headers_only
+        || args.no_header_summary
+        || args.user.is_some()
+    {
+        plib::diag!("{}", plib::tr!("receive mode is not supported"));
+        std::process::exit(1);
+    }
+
+    if args.ignore_interrupts {
+        if let Err(e) = plib::signal::ignore(libc::SIGINT) {
+            plib::diag!("{}", error_message(&e));
+        }
+    }
+
+    // There is no system start-up file, so -n changes nothing
+    let mut vars = Variables::new();
+    read_startup(&mut vars);
+
+    if let Err(e) = send(args, &vars) {
+        plib::diag!("{}", error_message(&e));
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("set smtp=mail:587 from='A B <a@b>' # comment"),
+            ["set", "smtp=mail:587", "from=A B <a@b>"]
+        );
+        assert_eq!(split_words("  # all comment"), Vec::<String>::new());
+        assert_eq!(split_words("set x=''"), ["set", "x="]);
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut vars = Variables::new();
+        assert!(vars.is_set("save"));
+        vars.source("set nosave sendmail=/bin/true\nunset sendmail\nset ask\n");
+        assert!(!vars.is_set("save"));
+        assert_eq!(vars.get("sendmail"), None);
+        assert_eq!(vars.get("ask"), Some(""));
+    }
+
+    #[test]
+    fn test_compose() {
+        let headers = [
+            ("To", String::from("a, b")),
+            ("Subject", String::from("hi")),
+        ];
+        assert_eq!(
+            compose(&headers, b"body"),
+            b"To: a, b\nSubject: hi\n\nbody\n"
+        );
+        assert_eq!(compose(&headers[..1], b""), b"To: a, b\n\n");
+    }
+}
@@ -0,0 +1,188 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Submission of a message to a mail server over SMTP (RFC 5321), for
+//! hosts without a sendmail program.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+const SMTP_PORT: u16 = 25;
+
+struct Session {
+    server: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Session {
+    /// Reads a reply, which may span several lines, and fails unless its
+    /// code is in the `expected` class: 2 for success, 3 for more input.
+    fn reply(&mut self, expected: u8) -> io::Result<()> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("{}: connection closed", self.server),
+                ));
+            }
+            // The last line of a reply has a space after the code
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        if line.as_bytes().first() == Some(&(b'0' + expected)) {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "{}: {}",
+                self.server,
+                line.trim_end()
+            )))
+        }
+    }
+
+    fn command(&mut self, command: &str, expected: u8) -> io::Result<()> {
+        write!(self.writer, "{}\r\n", command)?;
+        self.reply(expected)
+    }
+}
+
+/// The message with CRLF line endings, lines starting with a period
+/// doubled, and the terminating line of a single period.
+fn data(message: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(message.len() + message.len() / 32 + 5);
+    for line in message.split_inclusive(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.first() == Some(&b'.') {
+            data.push(b'.');
+        }
+        data.extend_from_slice(line);
+        data.extend_from_slice(b"\r\n");
+    }
+    data.extend_from_slice(b".\r\n");
+    data
+}
+
+/// Sends `message` from `from` to `recipients` through `server`, given as
+/// `host` or `host:port`.
+pub fn submit(server: &str, from: &str, recipients: &[String], message: &[u8]) -> io::Result<()> {
+    let (host, port) = match server.rsplit_once(':') {
+        Some((host, port)) => match port.parse() {
+            Ok(port) => (host, port),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    plib::tr!("{}: invalid port", server),
+                ))
+            }
+        },
+        None => (server, SMTP_PORT),
+    };
+    let stream = TcpStream::connect((host, port))?;
+    let mut session = Session {
+        server: String::from(server),
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+    };
+
+    session.reply(2)?;
+    let hostname = plib::curuser::hostname();
+    if session.command(&format!("EHLO {}", hostname), 2).is_err() {
+        session.command(&format!("HELO {}", hostname), 2)?;
+    }
+    session.command(&format!("MAIL FROM:<{}>", from), 2)?;
+    for recipient in recipients {
+        session.command(&format!("RCPT TO:<{}>", recipient), 2)?;
+    }
+    session.command("DATA", 3)?;
+    session.writer.write_all(&data(message))?;
+    session.reply(2)?;
+    session.command("QUIT", 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_data() {
+        assert_eq!(data(b"a\n.b\r\nc"), b"a\r\n..b\r\nc\r\n.\r\n");
+        assert_eq!(data(b""), b".\r\n");
+    }
+
+    #[test]
+    fn test_submit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        let peer = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let replies: [&[u8]; 7] = [
+                b"220 ready\r\n",
+                b"250-hello\r\n250 SIZE\r\n",
+                b"250 ok\r\n",
+                b"250 ok\r\n",
+                b"354 go ahead\r\n",
+                b"250 queued\r\n",
+                b"221 bye\r\n",
+            ];
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut transcript = Vec::new();
+            for (i, reply) in replies.iter().enumerate() {
+                stream.write_all(reply).unwrap();
+                if i == replies.len() - 1 {
+                    break;
+                }
+                let mut line = Vec::new();
+                if i == 4 {
+                    // The message, up to the final period
+                    while !line.ends_with(b"\r\n.\r\n") {
+                        let mut byte = [0];
+                        reader.read_exact(&mut byte).unwrap();
+                        line.push(byte[0]);
+                    }
+                } else {
+                    reader.read_until(b'\n', &mut line).unwrap();
+                }
+                transcript.extend(line);
+            }
+            transcript
+        });
+
+        submit(
+            &server,
+            "me@here",
+            &[String::from("you@there")],
+            b"To: you@there\n\nhi\n",
+        )
+        .unwrap();
+        let transcript = String::from_utf8(peer.join().unwrap()).unwrap();
+        let transcript: Vec<&str> = transcript.split("\r\n").skip(1).collect();
+        assert_eq!(
+            transcript,
+            [
+                "MAIL FROM:<me@here>",
+                "RCPT TO:<you@there>",
+                "DATA",
+                "To: you@there",
+                "",
+                "hi",
+                ".",
+                "QUIT",
+                "",
+            ]
+        );
+    }
+}
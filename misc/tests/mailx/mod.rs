@@ -0,0 +1,128 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+
+use plib::testing::{run_test_with_options, Fixture, TestOptions, TestPlan};
+
+/// A sendmail that records its operands and the message it is given.
+const SENDMAIL: &str = "#!/bin/sh\necho \"$@\" > args\ncat > message\n";
+
+/// Runs mailx in a directory holding the fake sendmail and a start-up
+/// file with the given commands, returning the fixture to inspect the
+/// files afterwards.
+fn mailx_test(
+    mailrc: &str,
+    env: &[(&str, &str)],
+    args: &[&str],
+    stdin: &str,
+    expected_err: &str,
+    code: i32,
+) -> Fixture {
+    let fixture = Fixture::builder("mailx")
+        .file("sendmail", SENDMAIL)
+        .mode(0o755)
+        .file("mailrc", mailrc)
+        .build();
+    let mut options = TestOptions {
+        cwd: Some(fixture.root().to_path_buf()),
+        ..Default::default()
+    }
+    .env("MAILRC", &fixture.arg("mailrc"))
+    .env("HOME", &fixture.arg(""));
+    for (key, value) in env {
+        options = options.env(key, value);
+    }
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("mailx"),
+            args: args.iter().map(|arg| String::from(*arg)).collect(),
+            stdin_data: String::from(stdin),
+            expected_out: String::new(),
+            expected_err: String::from(expected_err),
+            expected_exit_code: code,
+        },
+        options,
+    );
+    fixture
+}
+
+#[test]
+fn test_send_through_sendmail() {
+    let fixture = mailx_test(
+        "# test\nset sendmail=./sendmail\n",
+        &[],
+        &["-s", "Daily report", "alice", "bob@example.com"],
+        "all is well\n",
+        "",
+        0,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("args")).unwrap(),
+        "-i -- alice bob@example.com\n"
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("message")).unwrap(),
+        "To: alice, bob@example.com\nSubject: Daily report\n\nall is well\n"
+    );
+}
+
+#[test]
+fn test_send_without_subject() {
+    let fixture = mailx_test("set sendmail=./sendmail\n", &[], &["alice"], "hi", "", 0);
+    assert_eq!(
+        fs::read_to_string(fixture.path("message")).unwrap(),
+        "To: alice\n\nhi\n"
+    );
+}
+
+#[test]
+fn test_failure_saves_dead_letter() {
+    let fixture = mailx_test(
+        "set sendmail=./missing\n",
+        &[],
+        &["-s", "lost", "alice"],
+        "body\n",
+        "mailx: ./missing: No such file or directory\n",
+        1,
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("dead.letter")).unwrap(),
+        "To: alice\nSubject: lost\n\nbody\n"
+    );
+
+    let fixture = mailx_test(
+        "set sendmail=./missing nosave\n",
+        &[],
+        &["alice"],
+        "body\n",
+        "mailx: ./missing: No such file or directory\n",
+        1,
+    );
+    assert!(!fixture.path("dead.letter").exists());
+}
+
+#[test]
+fn test_check_for_mail() {
+    let fixture = mailx_test("", &[("MAIL", "none")], &["-e"], "", "", 1);
+    fs::write(fixture.path("mbox"), "From alice\n").unwrap();
+    mailx_test("", &[("MAIL", &fixture.arg("mbox"))], &["-e"], "", "", 0);
+}
+
+#[test]
+fn test_receive_mode_unsupported() {
+    mailx_test(
+        "",
+        &[],
+        &[],
+        "",
+        "mailx: receive mode is not supported\n",
+        1,
+    );
+}
@@ -8,5 +8,6 @@
 //
 
 mod r#false;
+mod mailx;
 mod test;
 mod r#true;
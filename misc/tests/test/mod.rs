@@ -74,3 +74,40 @@ fn test_str_basic() {
     test_test(&["-n", ""], 1);
     test_test(&["-n", "a"], 0);
 }
+
+fn test_error(args: &[&str], expected_err: &str) {
+    run_test(TestPlan {
+        cmd: String::from("test"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(expected_err),
+        expected_exit_code: 2,
+    });
+}
+
+#[test]
+fn test_files() {
+    test_test(&["-d", "/"], 0);
+    test_test(&["-f", "/"], 1);
+    test_test(&["-e", "/nonexistent"], 1);
+    test_test(&["-h", "/"], 1);
+    test_test(&["/", "-ef", "/."], 0);
+}
+
+#[test]
+fn test_expressions() {
+    test_test(&["!", "a", "=", "b"], 0);
+    test_test(&["(", "a", ")"], 0);
+    test_test(&["a", "-a", ""], 1);
+    test_test(&["", "-o", "a"], 0);
+    test_test(&["-n", "a", "-a", "(", "1", "-lt", "2", ")"], 0);
+    test_test(&["!", "-d", "/", "-o", "b", "=", "c"], 1);
+}
+
+#[test]
+fn test_errors() {
+    test_error(&["1", "-eq", "a"], "test: a: integer expression expected\n");
+    test_error(&["-q", "a"], "test: -q: unary operator expected\n");
+    test_error(&["(", "a", "=", "a", "-a", "b"], "test: ')' expected\n");
+}
@@ -0,0 +1,80 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! String ordering by the collating sequence of LC_COLLATE, once the
+//! locale is set up by [`crate::i18n::init`].
+//!
+//! Strings the locale considers equal, and strings containing NUL bytes
+//! past the part the C library can see, are ordered by their bytes, so
+//! that the order is total and sorting is deterministic.
+
+use std::cmp::Ordering;
+use std::ffi::CString;
+
+/// The part of `s` before its first NUL byte, as a C string.
+pub(crate) fn c_prefix(s: &[u8]) -> CString {
+    let end = s.iter().position(|&b| b == 0).unwrap_or(s.len());
+    CString::new(&s[..end]).unwrap()
+}
+
+/// Compares two strings in the collating sequence of the current locale.
+pub fn strcoll(a: &[u8], b: &[u8]) -> Ordering {
+    let (ca, cb) = (c_prefix(a), c_prefix(b));
+    // SAFETY: both are valid C strings.
+    let ordering = unsafe { libc::strcoll(ca.as_ptr(), cb.as_ptr()) };
+    ordering.cmp(&0).then_with(|| a.cmp(b))
+}
+
+/// Transforms `s` with strxfrm(3): comparing the keys of two strings byte
+/// by byte orders them as [`strcoll`] would, short of its tie-break.
+/// Cheaper than [`strcoll`] when each string is compared many times.
+pub fn sort_key(s: &[u8]) -> Vec<u8> {
+    let cs = c_prefix(s);
+    let mut key = vec![0u8; s.len() + 1];
+    loop {
+        // SAFETY: `key` has room for `key.len()` bytes.
+        let n = unsafe { libc::strxfrm(key.as_mut_ptr().cast(), cs.as_ptr(), key.len()) };
+        if n < key.len() {
+            key.truncate(n);
+            return key;
+        }
+        key.resize(n + 1, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests run in the C locale, where collation is byte order.
+
+    #[test]
+    fn test_strcoll_c_locale() {
+        assert_eq!(strcoll(b"a", b"b"), Ordering::Less);
+        assert_eq!(strcoll(b"B", b"a"), Ordering::Less);
+        assert_eq!(strcoll(b"abc", b"abc"), Ordering::Equal);
+        assert_eq!(strcoll(b"ab", b"a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_strcoll_nul_bytes() {
+        assert_eq!(strcoll(b"a\0b", b"a\0c"), Ordering::Less);
+        assert_eq!(strcoll(b"a\0b", b"a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_sort_key_orders_like_strcoll() {
+        let words: [&[u8]; 5] = [b"zeta", b"Alpha", b"alpha", b"", b"beta long word"];
+        for a in words {
+            for b in words {
+                assert_eq!(sort_key(a).cmp(&sort_key(b)), strcoll(a, b), "{a:?} {b:?}");
+            }
+        }
+    }
+}
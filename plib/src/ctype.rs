@@ -0,0 +1,131 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Character classification and case mapping by LC_CTYPE, once the locale
+//! is set up by [`crate::i18n::init`].
+
+use std::ffi::CStr;
+
+extern "C" {
+    fn iswlower(wc: libc::wchar_t) -> libc::c_int;
+    fn iswupper(wc: libc::wchar_t) -> libc::c_int;
+    fn towlower(wc: libc::wchar_t) -> libc::wchar_t;
+    fn towupper(wc: libc::wchar_t) -> libc::wchar_t;
+}
+
+/// Whether the codeset of LC_CTYPE is UTF-8, so that characters beyond
+/// ASCII are characters of the locale rather than sequences of bytes.
+pub fn utf8_codeset() -> bool {
+    // SAFETY: nl_langinfo accepts any item and returns a valid C string.
+    let codeset = unsafe { CStr::from_ptr(libc::nl_langinfo(libc::CODESET)) };
+    codeset.to_bytes().eq_ignore_ascii_case(b"UTF-8")
+}
+
+/// Length of `s` in characters of the locale, which are bytes unless its
+/// codeset is UTF-8.
+pub fn char_count(s: &str) -> usize {
+    if utf8_codeset() {
+        s.chars().count()
+    } else {
+        s.len()
+    }
+}
+
+/// Whether the locale classifies `c` as lowercase, with iswlower(3).
+pub fn is_lower(c: char) -> bool {
+    // SAFETY: iswlower accepts any value.
+    unsafe { iswlower(c as libc::wchar_t) != 0 }
+}
+
+/// Whether the locale classifies `c` as uppercase, with iswupper(3).
+pub fn is_upper(c: char) -> bool {
+    // SAFETY: iswupper accepts any value.
+    unsafe { iswupper(c as libc::wchar_t) != 0 }
+}
+
+/// The lowercase counterpart of `c` in the locale, with towlower(3).
+pub fn to_lower(c: char) -> char {
+    // SAFETY: towlower accepts any value.
+    let lower = unsafe { towlower(c as libc::wchar_t) };
+    char::from_u32(lower as u32).unwrap_or(c)
+}
+
+/// The uppercase counterpart of `c` in the locale, with towupper(3).
+pub fn to_upper(c: char) -> char {
+    // SAFETY: towupper accepts any value.
+    let upper = unsafe { towupper(c as libc::wchar_t) };
+    char::from_u32(upper as u32).unwrap_or(c)
+}
+
+/// Folds the case of `s`, mapping each character to lowercase as the
+/// locale does, so that strings differing only in case fold alike. In
+/// locales with a single-byte codeset only ASCII letters are folded.
+pub fn fold_case(s: &str) -> String {
+    let utf8 = utf8_codeset();
+    s.chars().map(|c| fold_char(c, utf8)).collect()
+}
+
+/// Like [`fold_case`], also returning the offset in `s` of each byte of
+/// the result, plus one for its end. Folding may change the length of a
+/// character's encoding, so that a match found in the result can be
+/// mapped back to the text it came from.
+pub fn fold_case_mapped(s: &str) -> (String, Vec<usize>) {
+    let utf8 = utf8_codeset();
+    let mut folded = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len() + 1);
+    for (i, c) in s.char_indices() {
+        folded.push(fold_char(c, utf8));
+        offsets.resize(folded.len(), i);
+    }
+    offsets.push(s.len());
+    (folded, offsets)
+}
+
+fn fold_char(c: char, utf8: bool) -> char {
+    if utf8 {
+        to_lower(c)
+    } else {
+        c.to_ascii_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests run in the C locale, whose codeset is ASCII.
+
+    #[test]
+    fn test_fold_case_c_locale() {
+        assert_eq!(fold_case("MiXeD 123"), "mixed 123");
+        assert_eq!(fold_case("\u{c9}t\u{c9}"), "\u{c9}t\u{c9}");
+    }
+
+    #[test]
+    fn test_fold_case_mapped_offsets() {
+        let (folded, offsets) = fold_case_mapped("AbC");
+        assert_eq!(folded, "abc");
+        assert_eq!(offsets, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_char_count_c_locale() {
+        assert_eq!(char_count("abc"), 3);
+        assert_eq!(char_count("\u{c9}"), 2);
+    }
+
+    #[test]
+    fn test_case_mapping() {
+        assert!(is_lower('a') && !is_upper('a'));
+        assert!(is_upper('Z') && !is_lower('Z'));
+        assert_eq!(to_upper('a'), 'A');
+        assert_eq!(to_lower('Z'), 'z');
+        assert_eq!(to_lower('1'), '1');
+    }
+}
@@ -51,3 +51,13 @@ pub fn tty() -> String {
 
     panic!("Failed to get tty name from any file descriptor");
 }
+
+/// The name of this host, or "localhost" if it has none.
+pub fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+        return String::from("localhost");
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
@@ -0,0 +1,81 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Parsing of single-character delimiters, as accepted by cut -d and
+//! join -t.
+//!
+//! The option-argument is decoded like the input lines the delimiter is
+//! looked for in, so a multibyte character is a single delimiter, just
+//! like each element of a paste -d list.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DelimiterError {
+    /// The option-argument is empty.
+    Empty,
+    /// The option-argument has more than one character.
+    MultipleCharacters(String),
+}
+
+impl fmt::Display for DelimiterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DelimiterError::Empty => write!(f, "the delimiter must not be empty"),
+            DelimiterError::MultipleCharacters(s) => {
+                write!(f, "the delimiter must be a single character: '{}'", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DelimiterError {}
+
+/// Parses an option-argument that must be exactly one character. Usable
+/// as a clap value parser.
+pub fn parse_delimiter(s: &str) -> Result<char, DelimiterError> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        (None, _) => Err(DelimiterError::Empty),
+        (Some(_), Some(_)) => Err(DelimiterError::MultipleCharacters(s.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_characters() {
+        assert_eq!(parse_delimiter(":"), Ok(':'));
+        assert_eq!(parse_delimiter("\t"), Ok('\t'));
+        assert_eq!(parse_delimiter("é"), Ok('é'));
+        assert_eq!(parse_delimiter("→"), Ok('→'));
+        assert_eq!(parse_delimiter("😀"), Ok('😀'));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse_delimiter(""), Err(DelimiterError::Empty));
+        assert_eq!(
+            parse_delimiter("ab"),
+            Err(DelimiterError::MultipleCharacters(String::from("ab")))
+        );
+        assert_eq!(
+            parse_delimiter("éé"),
+            Err(DelimiterError::MultipleCharacters(String::from("éé")))
+        );
+        // a base character and a combining accent are two characters
+        assert_eq!(
+            parse_delimiter("e\u{301}"),
+            Err(DelimiterError::MultipleCharacters(String::from("e\u{301}")))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Diagnostics in the usual `utility: operand: message` form, and exit
+//! status tracking for utilities that continue after an error.
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// An error occurred, such as an operand that could not be processed.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Incorrect usage, for utilities whose exit status distinguishes it from
+/// other errors (e.g. cmp, diff, grep).
+pub const EXIT_USAGE: i32 = 2;
+
+/// Name of the running utility, from `argv[0]`.
+pub fn progname() -> &'static str {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        std::env::args_os()
+            .next()
+            .as_deref()
+            .and_then(|arg0| Path::new(arg0).file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    })
+}
+
+/// The message for an I/O error, as strerror() would give it, without
+/// the "(os error N)" that `io::Error` appends.
+pub fn strerror(e: &io::Error) -> String {
+    match e.raw_os_error() {
+        Some(code) => errno::Errno(code).to_string(),
+        None => e.to_string(),
+    }
+}
+
+/// The message for any error, using [`strerror`] for I/O errors, also
+/// when boxed.
+pub fn error_message<E: fmt::Display + 'static>(err: &E) -> String {
+    let any = err as &dyn Any;
+    if let Some(e) = any.downcast_ref::<io::Error>() {
+        return strerror(e);
+    }
+    if let Some(e) = any
+        .downcast_ref::<Box<dyn Error>>()
+        .and_then(|e| e.downcast_ref::<io::Error>())
+    {
+        return strerror(e);
+    }
+    err.to_string()
+}
+
+/// Prints a diagnostic to stderr, prefixed with the utility name.
+#[macro_export]
+macro_rules! diag {
+    ($($arg:tt)*) => {
+        eprintln!("{}: {}", $crate::diag::progname(), format_args!($($arg)*))
+    };
+}
+
+/// Exit status of a utility that reports an error for an operand and
+/// carries on with the remaining operands.
+#[derive(Debug, Default)]
+pub struct Status {
+    code: i32,
+}
+
+impl Status {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prints `utility: operand: message` and records the failure.
+    pub fn operand_error<E: fmt::Display + 'static>(
+        &mut self,
+        operand: impl fmt::Display,
+        err: &E,
+    ) {
+        diag!("{}: {}", operand, error_message(err));
+        self.fail();
+    }
+
+    /// Prints `utility: message` and records the failure.
+    pub fn error<E: fmt::Display + 'static>(&mut self, err: &E) {
+        diag!("{}", error_message(err));
+        self.fail();
+    }
+
+    /// Records a failure that was already reported.
+    pub fn fail(&mut self) {
+        self.fail_with(EXIT_FAILURE);
+    }
+
+    /// Records a failure with a specific exit status; the highest status
+    /// recorded wins.
+    pub fn fail_with(&mut self, code: i32) {
+        self.code = self.code.max(code);
+    }
+
+    pub fn failed(&self) -> bool {
+        self.code != 0
+    }
+
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    pub fn exit(&self) -> ! {
+        std::process::exit(self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_message() {
+        let e = io::Error::from_raw_os_error(libc::ENOENT);
+        assert_eq!(error_message(&e), "No such file or directory");
+
+        let boxed: Box<dyn Error> = Box::new(io::Error::from_raw_os_error(libc::EACCES));
+        assert_eq!(error_message(&boxed), "Permission denied");
+
+        let custom = io::Error::other("custom");
+        assert_eq!(error_message(&custom), "custom");
+
+        assert_eq!(error_message(&"plain"), "plain");
+    }
+
+    #[test]
+    fn test_status() {
+        let mut status = Status::new();
+        assert!(!status.failed());
+        assert_eq!(status.code(), 0);
+
+        status.fail();
+        assert_eq!(status.code(), EXIT_FAILURE);
+        status.fail_with(EXIT_USAGE);
+        status.fail();
+        assert_eq!(status.code(), EXIT_USAGE);
+    }
+}
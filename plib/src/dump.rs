@@ -0,0 +1,148 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Reading the input of the utilities that dump bytes, od and strings,
+//! and writing the byte offsets they print.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// The base in which a byte offset is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetBase {
+    Decimal,
+    Octal,
+    Hex,
+}
+
+impl OffsetBase {
+    /// The base named by `d`, `o` or `x`.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(OffsetBase::Decimal),
+            'o' => Some(OffsetBase::Octal),
+            'x' => Some(OffsetBase::Hex),
+            _ => None,
+        }
+    }
+
+    /// Parses an option-argument naming a base, for use as a clap value
+    /// parser.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Self::from_char), chars.next()) {
+            (Some(base), None) => Ok(base),
+            _ => Err(crate::tr!("invalid offset format '{}'", s)),
+        }
+    }
+
+    /// Writes `offset` right-aligned in `width` columns, padded with zeros
+    /// as od does, or with spaces as strings does.
+    pub fn format(self, offset: u64, width: usize, zero_pad: bool) -> String {
+        match (self, zero_pad) {
+            (OffsetBase::Decimal, true) => format!("{:0width$}", offset),
+            (OffsetBase::Decimal, false) => format!("{:width$}", offset),
+            (OffsetBase::Octal, true) => format!("{:0width$o}", offset),
+            (OffsetBase::Octal, false) => format!("{:width$o}", offset),
+            (OffsetBase::Hex, true) => format!("{:0width$x}", offset),
+            (OffsetBase::Hex, false) => format!("{:width$x}", offset),
+        }
+    }
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Discards the first `skip` bytes of `reader`, returning how many there
+/// were.
+fn discard<R: Read>(reader: &mut R, skip: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(skip), &mut io::sink())
+}
+
+/// Opens one operand, `-` being standard input.
+pub fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if is_stdin(path) {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Reads all of one operand, `-` being standard input.
+pub fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open_input(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The operands read one after the other as a single stream, standard
+/// input if there are none, with the first `skip` bytes of the stream
+/// left out. Regular files wholly skipped are not read, and the rest of
+/// the skip within a regular file is a seek.
+pub fn concat_inputs(files: &[PathBuf], mut skip: u64) -> io::Result<Box<dyn Read>> {
+    if files.is_empty() {
+        let mut stdin = io::stdin().lock();
+        discard(&mut stdin, skip)?;
+        return Ok(Box::new(stdin));
+    }
+
+    let mut inputs: Vec<Box<dyn Read>> = Vec::new();
+    for path in files {
+        if is_stdin(path) {
+            let mut stdin = io::stdin().lock();
+            skip -= discard(&mut stdin, skip)?;
+            inputs.push(Box::new(stdin));
+            continue;
+        }
+
+        let mut file = File::open(path)?;
+        if skip > 0 {
+            let metadata = file.metadata()?;
+            if metadata.is_file() {
+                let len = metadata.len();
+                if len <= skip {
+                    skip -= len;
+                    continue;
+                }
+                file.seek(SeekFrom::Start(skip))?;
+                skip = 0;
+            } else {
+                skip -= discard(&mut file, skip)?;
+            }
+        }
+        inputs.push(Box::new(BufReader::new(file)));
+    }
+
+    Ok(inputs
+        .into_iter()
+        .reduce(|acc, input| Box::new(acc.chain(input)))
+        .unwrap_or_else(|| Box::new(io::empty())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(OffsetBase::parse("x"), Ok(OffsetBase::Hex));
+        assert!(OffsetBase::parse("n").is_err());
+        assert!(OffsetBase::parse("dd").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(OffsetBase::Octal.format(8, 7, true), "0000010");
+        assert_eq!(OffsetBase::Hex.format(13, 6, true), "00000d");
+        assert_eq!(OffsetBase::Decimal.format(9, 7, false), "      9");
+        assert_eq!(OffsetBase::Hex.format(28, 7, false), "     1c");
+    }
+}
@@ -0,0 +1,132 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! File descriptor plumbing on top of `OwnedFd`: pipes and duplication.
+//!
+//! Descriptors created here are close-on-exec, so they don't leak into
+//! the commands a utility runs. The descriptors set up by [`dup2`], which
+//! are there to be inherited, are the exception.
+
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use libc::c_int;
+
+fn check(ret: c_int) -> io::Result<c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Retries a call that failed with EINTR.
+fn retry(mut f: impl FnMut() -> c_int) -> io::Result<c_int> {
+    loop {
+        match check(f()) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+fn set_flag(fd: RawFd, get: c_int, set: c_int, flag: c_int, yes: bool) -> io::Result<()> {
+    // SAFETY: fcntl with these commands only reads and sets flags.
+    let flags = check(unsafe { libc::fcntl(fd, get) })?;
+    let new_flags = if yes { flags | flag } else { flags & !flag };
+    if new_flags != flags {
+        check(unsafe { libc::fcntl(fd, set, new_flags) })?;
+    }
+    Ok(())
+}
+
+pub fn set_cloexec(fd: impl AsFd, yes: bool) -> io::Result<()> {
+    let fd = fd.as_fd().as_raw_fd();
+    set_flag(fd, libc::F_GETFD, libc::F_SETFD, libc::FD_CLOEXEC, yes)
+}
+
+pub fn set_nonblocking(fd: impl AsFd, yes: bool) -> io::Result<()> {
+    let fd = fd.as_fd().as_raw_fd();
+    set_flag(fd, libc::F_GETFL, libc::F_SETFL, libc::O_NONBLOCK, yes)
+}
+
+/// Creates a pipe, returning its read and write ends.
+pub fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: fds has room for the two descriptors.
+    check(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+    // SAFETY: pipe returned two new descriptors that we now own.
+    let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    set_cloexec(&read, true)?;
+    set_cloexec(&write, true)?;
+    Ok((read, write))
+}
+
+/// Duplicates `fd` to the lowest free descriptor not below `min`, as
+/// shells do to keep saved descriptors out of the way of redirections.
+pub fn dup_at_least(fd: impl AsFd, min: RawFd) -> io::Result<OwnedFd> {
+    let fd = fd.as_fd().as_raw_fd();
+    // SAFETY: F_DUPFD_CLOEXEC returns a new descriptor that we own.
+    let new = check(unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, min) })?;
+    Ok(unsafe { OwnedFd::from_raw_fd(new) })
+}
+
+pub fn dup(fd: impl AsFd) -> io::Result<OwnedFd> {
+    dup_at_least(fd, 0)
+}
+
+/// Makes `target` refer to the same file as `fd`, closing what it
+/// referred to before. `target` is inherited by executed commands, also
+/// when it already is `fd`.
+pub fn dup2(fd: impl AsFd, target: RawFd) -> io::Result<()> {
+    let fd = fd.as_fd();
+    if fd.as_raw_fd() == target {
+        return set_cloexec(fd, false);
+    }
+    // SAFETY: dup2 only affects `target`, which the caller asks to
+    // replace.
+    retry(|| unsafe { libc::dup2(fd.as_raw_fd(), target) })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::{Read, Write};
+
+    fn is_cloexec(fd: RawFd) -> bool {
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        flags & libc::FD_CLOEXEC != 0
+    }
+
+    #[test]
+    fn test_pipe() {
+        let (read, write) = pipe().unwrap();
+        assert!(is_cloexec(read.as_raw_fd()));
+        assert!(is_cloexec(write.as_raw_fd()));
+
+        File::from(write).write_all(b"data").unwrap();
+        let mut buf = String::new();
+        File::from(read).read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "data");
+    }
+
+    #[test]
+    fn test_dup() {
+        let file = File::open("/dev/null").unwrap();
+        let copy = dup_at_least(&file, 20).unwrap();
+        assert!(copy.as_raw_fd() >= 20);
+        assert!(is_cloexec(copy.as_raw_fd()));
+
+        set_cloexec(&copy, true).unwrap();
+        dup2(&copy, copy.as_raw_fd()).unwrap();
+        assert!(!is_cloexec(copy.as_raw_fd()));
+    }
+}
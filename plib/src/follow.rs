@@ -0,0 +1,262 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Following a file as it grows, as tail -f does.
+//!
+//! Changes are noticed through the platform's file notification
+//! mechanism (inotify, kqueue, ...) where available. The file is also
+//! checked at a regular interval, so that changes are not missed where
+//! notifications are unavailable or unreliable, e.g. on network file
+//! systems.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How often the file is checked when no notification arrives.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to do when the pathname comes to refer to another file, e.g.
+/// after log rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowMode {
+    /// Keep reading the file that was opened, as POSIX requires of
+    /// tail -f.
+    Descriptor,
+    /// Switch to the file the pathname now refers to.
+    Name,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    /// Data was appended to the file.
+    Appended(Vec<u8>),
+    /// The file shrank; it is now followed from its start.
+    Truncated,
+    /// The pathname refers to a new file, which is now followed from its
+    /// start. Only with [`FollowMode::Name`].
+    Replaced,
+    /// The pathname no longer exists. Only with [`FollowMode::Name`];
+    /// it is followed again once it reappears.
+    Removed,
+}
+
+struct Watch {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+/// Waits for changes to a file and returns the data appended to it.
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    pos: u64,
+    mode: FollowMode,
+    removed: bool,
+    watch: Option<Watch>,
+    poll_interval: Duration,
+}
+
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+impl Follower {
+    /// Follows `file`, opened from `path`, from its current position.
+    pub fn new(path: impl AsRef<Path>, mut file: File, mode: FollowMode) -> io::Result<Follower> {
+        let pos = file.stream_position()?;
+        let path = path.as_ref().to_path_buf();
+
+        let (tx, rx) = mpsc::channel();
+        let watch = notify::recommended_watcher(tx).ok().map(|watcher| Watch {
+            watcher,
+            events: rx,
+        });
+
+        let mut follower = Follower {
+            path,
+            file,
+            pos,
+            mode,
+            removed: false,
+            watch,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        };
+        follower.watch_path();
+        Ok(follower)
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// (Re)starts watching the pathname. Without a watch, e.g. while the
+    /// file is missing, changes are still found by polling.
+    fn watch_path(&mut self) {
+        if let Some(watch) = &mut self.watch {
+            let _ = watch.watcher.unwatch(&self.path);
+            let _ = watch.watcher.watch(&self.path, RecursiveMode::NonRecursive);
+        }
+    }
+
+    /// Blocks until a notification arrives or the poll interval expires.
+    fn wait(&self) {
+        match &self.watch {
+            Some(watch) => {
+                let _ = watch.events.recv_timeout(self.poll_interval);
+                // Several notifications are usually sent for one write
+                while watch.events.try_recv().is_ok() {}
+            }
+            None => std::thread::sleep(self.poll_interval),
+        }
+    }
+
+    /// Checks the file for changes without blocking.
+    pub fn check(&mut self) -> io::Result<Option<Change>> {
+        let meta = self.file.metadata()?;
+
+        if meta.len() < self.pos {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.pos = 0;
+            return Ok(Some(Change::Truncated));
+        }
+
+        if meta.len() > self.pos {
+            let mut data = Vec::new();
+            self.file.read_to_end(&mut data)?;
+            self.pos += data.len() as u64;
+            if !data.is_empty() {
+                return Ok(Some(Change::Appended(data)));
+            }
+        }
+
+        // Only look for a new file once the old one has been read to
+        // its end.
+        if self.mode == FollowMode::Name {
+            match fs::metadata(&self.path) {
+                Ok(path_meta) if !same_file(&meta, &path_meta) => {
+                    self.file = File::open(&self.path)?;
+                    self.pos = 0;
+                    self.removed = false;
+                    self.watch_path();
+                    return Ok(Some(Change::Replaced));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    if !self.removed {
+                        self.removed = true;
+                        return Ok(Some(Change::Removed));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Blocks until the file changes.
+    pub fn next_change(&mut self) -> io::Result<Change> {
+        loop {
+            if let Some(change) = self.check()? {
+                return Ok(change);
+            }
+            self.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use std::io::Write;
+
+    fn append(path: &Path, data: &[u8]) {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_appended_and_truncated() {
+        let dir = Fixture::builder("follow_append")
+            .file("log", "old\n")
+            .build();
+        let path = dir.path("log");
+
+        let mut file = File::open(&path).unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        let mut follower = Follower::new(&path, file, FollowMode::Descriptor)
+            .unwrap()
+            .poll_interval(Duration::from_millis(10));
+        assert_eq!(follower.check().unwrap(), None);
+
+        append(&path, b"new\n");
+        assert_eq!(
+            follower.next_change().unwrap(),
+            Change::Appended(b"new\n".to_vec())
+        );
+
+        fs::write(&path, b"").unwrap();
+        assert_eq!(follower.next_change().unwrap(), Change::Truncated);
+        append(&path, b"again\n");
+        assert_eq!(
+            follower.next_change().unwrap(),
+            Change::Appended(b"again\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = Fixture::builder("follow_rotate").file("log", "").build();
+        let path = dir.path("log");
+        let rotated = dir.path("log.1");
+
+        let new_follower = |mode| {
+            Follower::new(&path, File::open(&path).unwrap(), mode)
+                .unwrap()
+                .poll_interval(Duration::from_millis(10))
+        };
+        let mut by_descriptor = new_follower(FollowMode::Descriptor);
+        let mut by_name = new_follower(FollowMode::Name);
+
+        append(&path, b"before\n");
+        fs::rename(&path, &rotated).unwrap();
+        append(&rotated, b"late\n");
+
+        assert_eq!(
+            by_name.next_change().unwrap(),
+            Change::Appended(b"before\nlate\n".to_vec())
+        );
+        assert_eq!(by_name.next_change().unwrap(), Change::Removed);
+
+        fs::write(&path, b"after\n").unwrap();
+        assert_eq!(by_name.next_change().unwrap(), Change::Replaced);
+        assert_eq!(
+            by_name.next_change().unwrap(),
+            Change::Appended(b"after\n".to_vec())
+        );
+
+        assert_eq!(
+            by_descriptor.next_change().unwrap(),
+            Change::Appended(b"before\nlate\n".to_vec())
+        );
+        assert_eq!(by_descriptor.check().unwrap(), None);
+    }
+}
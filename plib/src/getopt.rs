@@ -0,0 +1,287 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Option parsing following the POSIX Utility Syntax Guidelines, like
+//! getopt(3).
+//!
+//! Unlike clap, options are only recognized before the first operand, so
+//! that utilities which run another command (env, nice, nohup, ...) pass
+//! the options of that command through untouched. Arguments are taken as
+//! [`OsString`]s, so that operands which are not valid UTF-8 reach that
+//! command as they were given.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::os::unix::ffi::OsStrExt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GetOptError {
+    /// An option character not in the option string.
+    UnknownOption(char),
+    /// An option that takes an option-argument was the last argument.
+    MissingArgument(char),
+}
+
+impl fmt::Display for GetOptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetOptError::UnknownOption(c) => write!(f, "illegal option -- {}", c),
+            GetOptError::MissingArgument(c) => {
+                write!(f, "option requires an argument -- {}", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GetOptError {}
+
+/// Handles `--help` and `--version`, which clap provides for the other
+/// utilities: prints `help` or the version and exits. They are only
+/// recognized as the first argument, so that they are passed through to
+/// a utility being run.
+pub fn help_or_version(help: &str, version: &str) {
+    match std::env::args_os().nth(1) {
+        Some(arg) if arg == "--help" => {
+            println!("{}", help);
+            std::process::exit(0);
+        }
+        Some(arg) if arg == "--version" => {
+            println!("{} {}", crate::diag::progname(), version);
+            std::process::exit(0);
+        }
+        _ => {}
+    }
+}
+
+/// Iterates over the options in `args`, yielding each option character
+/// and, for options followed by `:` in the option string, its
+/// option-argument.
+///
+/// Parsing stops at the first operand, at `-` (which is an operand) or
+/// after `--`. The remaining arguments are then available from
+/// [`GetOpt::operands`].
+pub struct GetOpt<'a> {
+    args: Vec<OsString>,
+    optstring: &'a str,
+    /// Index of the argument being parsed.
+    index: usize,
+    /// Byte offset of the next option character within that argument,
+    /// or 0 if it has not been started.
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> GetOpt<'a> {
+    /// `args` excludes the program name, as in `env::args_os().skip(1)`.
+    pub fn new<I, S>(args: I, optstring: &'a str) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        GetOpt {
+            args: args.into_iter().map(Into::into).collect(),
+            optstring,
+            index: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Whether `c` is a valid option, and if so whether it takes an
+    /// option-argument.
+    fn lookup(&self, c: char) -> Option<bool> {
+        if c == ':' {
+            return None;
+        }
+        let pos = self.optstring.find(c)?;
+        Some(self.optstring[pos + c.len_utf8()..].starts_with(':'))
+    }
+
+    /// The arguments following the options. Call after the iterator is
+    /// exhausted.
+    pub fn operands(mut self) -> Vec<OsString> {
+        self.args.split_off(self.index.min(self.args.len()))
+    }
+}
+
+impl Iterator for GetOpt<'_> {
+    type Item = Result<(char, Option<OsString>), GetOptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.offset == 0 {
+            let arg = match self.args.get(self.index) {
+                Some(arg) => arg,
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            };
+            if arg == "--" {
+                self.index += 1;
+                self.done = true;
+                return None;
+            }
+            if !arg.as_bytes().starts_with(b"-") || arg == "-" {
+                self.done = true;
+                return None;
+            }
+            self.offset = 1;
+        }
+
+        let arg = self.args[self.index].clone();
+        let arg = arg.as_bytes();
+        let (c, len) = next_char(&arg[self.offset..]);
+        self.offset += len;
+        let rest = &arg[self.offset..];
+
+        let result = match self.lookup(c) {
+            None => Err(GetOptError::UnknownOption(c)),
+            Some(false) => Ok((c, None)),
+            Some(true) if !rest.is_empty() => {
+                self.offset = arg.len();
+                Ok((c, Some(OsStr::from_bytes(rest).to_os_string())))
+            }
+            Some(true) => match self.args.get(self.index + 1) {
+                Some(optarg) => {
+                    let optarg = optarg.clone();
+                    self.index += 1;
+                    Ok((c, Some(optarg)))
+                }
+                None => Err(GetOptError::MissingArgument(c)),
+            },
+        };
+
+        if self.offset >= arg.len() {
+            self.index += 1;
+            self.offset = 0;
+        }
+
+        Some(result)
+    }
+}
+
+/// The character at the start of `bytes`, which is not empty, and the
+/// length of its encoding. A byte that does not start a valid UTF-8
+/// sequence is taken alone, as U+FFFD, so that it is reported as an
+/// unknown option.
+fn next_char(bytes: &[u8]) -> (char, usize) {
+    let len = match bytes[0] {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    };
+    match std::str::from_utf8(&bytes[..len.min(bytes.len())]) {
+        Ok(s) => (s.chars().next().unwrap(), len),
+        Err(_) => (char::REPLACEMENT_CHARACTER, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str], optstring: &str) -> (Vec<(char, Option<OsString>)>, Vec<OsString>) {
+        let mut getopt = GetOpt::new(args, optstring);
+        let opts = getopt.by_ref().map(|opt| opt.unwrap()).collect();
+        (opts, getopt.operands())
+    }
+
+    fn strings(v: &[&str]) -> Vec<OsString> {
+        v.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_combined_flags() {
+        let (opts, operands) = parse(&["-ab", "-c", "file"], "abc");
+        assert_eq!(opts, vec![('a', None), ('b', None), ('c', None)]);
+        assert_eq!(operands, strings(&["file"]));
+    }
+
+    #[test]
+    fn test_option_arguments() {
+        let (opts, operands) = parse(&["-n5", "-an", "10", "-n", "-3", "x"], "an:");
+        assert_eq!(
+            opts,
+            vec![
+                ('n', Some(OsString::from("5"))),
+                ('a', None),
+                ('n', Some(OsString::from("10"))),
+                ('n', Some(OsString::from("-3"))),
+            ]
+        );
+        assert_eq!(operands, strings(&["x"]));
+    }
+
+    #[test]
+    fn test_stops_at_first_operand() {
+        let (opts, operands) = parse(&["-a", "ls", "-l", "--", "-a"], "a");
+        assert_eq!(opts, vec![('a', None)]);
+        assert_eq!(operands, strings(&["ls", "-l", "--", "-a"]));
+    }
+
+    #[test]
+    fn test_double_dash() {
+        let (opts, operands) = parse(&["-a", "--", "-b", "c"], "ab");
+        assert_eq!(opts, vec![('a', None)]);
+        assert_eq!(operands, strings(&["-b", "c"]));
+
+        let (opts, operands) = parse(&["--"], "a");
+        assert!(opts.is_empty());
+        assert!(operands.is_empty());
+    }
+
+    #[test]
+    fn test_single_dash_is_operand() {
+        let (opts, operands) = parse(&["-", "-a"], "a");
+        assert!(opts.is_empty());
+        assert_eq!(operands, strings(&["-", "-a"]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut getopt = GetOpt::new(strings(&["-ax", "b"]), "a");
+        assert_eq!(getopt.next(), Some(Ok(('a', None))));
+        assert_eq!(getopt.next(), Some(Err(GetOptError::UnknownOption('x'))));
+        assert_eq!(getopt.next(), None);
+        assert_eq!(getopt.operands(), strings(&["b"]));
+
+        let mut getopt = GetOpt::new(strings(&["-n"]), "n:");
+        assert_eq!(getopt.next(), Some(Err(GetOptError::MissingArgument('n'))));
+        assert_eq!(getopt.next(), None);
+
+        let mut getopt = GetOpt::new(strings(&["-:"]), "a:");
+        assert_eq!(getopt.next(), Some(Err(GetOptError::UnknownOption(':'))));
+    }
+
+    #[test]
+    fn test_non_utf8_arguments() {
+        let args = [
+            OsStr::from_bytes(b"-n\xff"),
+            OsStr::from_bytes(b"-\xffa"),
+            OsStr::from_bytes(b"\xfe"),
+        ];
+        let mut getopt = GetOpt::new(args, "an:");
+        assert_eq!(
+            getopt.next(),
+            Some(Ok(('n', Some(OsStr::from_bytes(b"\xff").to_os_string()))))
+        );
+        assert_eq!(
+            getopt.next(),
+            Some(Err(GetOptError::UnknownOption(char::REPLACEMENT_CHARACTER)))
+        );
+        assert_eq!(getopt.next(), Some(Ok(('a', None))));
+        assert_eq!(getopt.next(), None);
+        assert_eq!(getopt.operands(), [OsStr::from_bytes(b"\xfe")]);
+    }
+}
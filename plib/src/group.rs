@@ -11,6 +11,7 @@ use libc::{endgrent, getgrent, setgrent};
 use std::ffi::CStr;
 use std::ptr;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Group {
     pub name: String,
     pub passwd: String,
@@ -0,0 +1,119 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Locale setup and message translation.
+//!
+//! Messages for which the catalog has no translation, or when there is
+//! no catalog at all, are used untranslated.
+
+use std::fmt::{self, Write};
+use std::sync::Once;
+
+pub use gettextrs::gettext;
+use gettextrs::{bind_textdomain_codeset, textdomain};
+
+/// Text domain of the message catalog shared by all utilities.
+pub const TEXT_DOMAIN: &str = "posixutils-rs";
+
+/// Sets the locale from the LANG and LC_* environment variables and
+/// selects the message catalog, as every utility does first. Failures
+/// only leave the C locale or untranslated messages in place, so they
+/// are ignored.
+///
+/// Only the first call has an effect, so that tests running in parallel
+/// threads may each call it.
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        // The C library's locale, which governs the message catalog as
+        // well as strftime(3), strcoll(3) and the character
+        // classification functions.
+        // SAFETY: called with a valid C string. The Once keeps it from
+        // racing with itself; utilities call it before starting threads.
+        unsafe { libc::setlocale(libc::LC_ALL, c"".as_ptr()) };
+        let _ = textdomain(TEXT_DOMAIN);
+        let _ = bind_textdomain_codeset(TEXT_DOMAIN, "UTF-8");
+    });
+}
+
+/// Substitutes `args`, in order, for the `{}` placeholders of a message
+/// that was looked up at runtime. `{{` and `}}` stand for braces, and
+/// placeholders without an argument are left out.
+pub fn format_message(msg: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut out = String::with_capacity(msg.len());
+    let mut args = args.iter();
+    let mut rest = msg;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+        } else if rest.starts_with("{}") {
+            if let Some(arg) = args.next() {
+                let _ = write!(out, "{}", arg);
+            }
+            rest = &rest[2..];
+        } else {
+            out.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translates a message, substituting any further arguments for its `{}`
+/// placeholders like `format!`.
+#[macro_export]
+macro_rules! tr {
+    ($msgid:expr) => {
+        $crate::i18n::gettext($msgid)
+    };
+    ($msgid:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format_message(
+            &$crate::i18n::gettext($msgid),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+
+/// Prints a translated diagnostic to stderr, prefixed with the utility
+/// name. Takes the same arguments as [`tr!`].
+#[macro_export]
+macro_rules! tdiag {
+    ($($arg:tt)*) => {
+        $crate::diag!("{}", $crate::tr!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(format_message("plain", &[]), "plain");
+        assert_eq!(
+            format_message("{}: cannot open {}", &[&"cat", &"x"]),
+            "cat: cannot open x"
+        );
+        assert_eq!(format_message("{{{}}}", &[&1]), "{1}");
+        assert_eq!(format_message("{} and {}", &[&1]), "1 and ");
+        assert_eq!(format_message("{x} }", &[&1]), "{x} }");
+    }
+
+    #[test]
+    fn test_tr() {
+        init();
+        assert_eq!(tr!("no placeholders"), "no placeholders");
+        let name = String::from("file");
+        assert_eq!(tr!("{}: {} bytes", name, 42), "file: 42 bytes");
+    }
+}
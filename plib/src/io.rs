@@ -8,7 +8,8 @@
 //
 
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufRead, Read, Write};
+use std::os::fd::AsRawFd;
 use std::path::PathBuf;
 
 /// open file, or stdin
@@ -38,3 +39,519 @@ pub fn input_reader(
     let file = input_stream(pathname, dashed_stdin)?;
     Ok(io::BufReader::new(file))
 }
+
+/// A good buffer size for the reader given to [`RecordReader`], when it
+/// is not already buffered.
+pub const RECORD_BUFSZ: usize = 64 * 1024;
+
+/// Reads byte records separated by an arbitrary terminator byte,
+/// without any UTF-8 validation.
+///
+/// Each record includes its terminator, except possibly the last one
+/// if the input does not end with a terminator.
+pub struct RecordReader<R: BufRead> {
+    reader: R,
+    terminator: u8,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(reader: R, terminator: u8) -> Self {
+        RecordReader {
+            reader,
+            terminator,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Newline-terminated records.
+    pub fn lines(reader: R) -> Self {
+        Self::new(reader, b'\n')
+    }
+
+    /// NUL-terminated records.
+    pub fn nul_terminated(reader: R) -> Self {
+        Self::new(reader, b'\0')
+    }
+
+    pub fn terminator(&self) -> u8 {
+        self.terminator
+    }
+
+    /// Returns the next record, borrowed from an internal buffer that is
+    /// reused by the following call, or `None` at end of input.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buf.clear();
+        let n = self.reader.read_until(self.terminator, &mut self.buf)?;
+        if n == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(&self.buf))
+        }
+    }
+
+    /// Returns the next record without its terminator, as text for the
+    /// utilities that need it, or `None` at end of input.
+    ///
+    /// A record that is not valid UTF-8 fails with
+    /// [`io::ErrorKind::InvalidData`]; it is consumed all the same, so
+    /// that reading can go on with the next one.
+    pub fn next_text(&mut self) -> io::Result<Option<&str>> {
+        let terminator = self.terminator;
+        match self.next_record()? {
+            None => Ok(None),
+            Some(record) => {
+                let record = record.strip_suffix(&[terminator]).unwrap_or(record);
+                std::str::from_utf8(record).map(Some).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    )
+                })
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Vec::new();
+        match self.reader.read_until(self.terminator, &mut record) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(record)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Reads the lines of input that should be sorted, such as those of
+/// comm and join, and if checking is on, fails at the first line whose
+/// key sorts before that of the line preceding it.
+///
+/// Each line includes its newline, except possibly the last one; the key
+/// is taken from the line without it.
+pub struct OrderedLines<R: BufRead, K: Fn(&str) -> &str> {
+    reader: R,
+    name: String,
+    key: K,
+    compare: fn(&str, &str) -> std::cmp::Ordering,
+    check: bool,
+    line_no: u64,
+    prev_key: Option<String>,
+}
+
+impl<R: BufRead, K: Fn(&str) -> &str> OrderedLines<R, K> {
+    /// `name` is how the input is named in diagnostics.
+    pub fn new(
+        reader: R,
+        name: impl Into<String>,
+        key: K,
+        compare: fn(&str, &str) -> std::cmp::Ordering,
+        check: bool,
+    ) -> Self {
+        OrderedLines {
+            reader,
+            name: name.into(),
+            key,
+            compare,
+            check,
+            line_no: 0,
+            prev_key: None,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line_no += 1;
+        if self.check {
+            let key = (self.key)(line.strip_suffix('\n').unwrap_or(&line));
+            if let Some(prev) = &self.prev_key {
+                if (self.compare)(key, prev) == std::cmp::Ordering::Less {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        crate::tr!(
+                            "{}: line {} is not in sorted order",
+                            self.name,
+                            self.line_no
+                        ),
+                    ));
+                }
+            }
+            self.prev_key = Some(key.to_string());
+        }
+        Ok(Some(line))
+    }
+}
+
+impl<R: BufRead, K: Fn(&str) -> &str> Iterator for OrderedLines<R, K> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_line().transpose()
+    }
+}
+
+/// Regular files at least this large are mapped by [`MappedReader`];
+/// smaller ones are cheaper to read.
+pub const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// A read-only private mapping of a whole regular file.
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mmap {
+    /// Maps the `len` bytes of `file`, failing unless it is a regular
+    /// file of that size once mapped, as it may have changed since its
+    /// size was taken.
+    fn map(file: &fs::File, len: usize) -> io::Result<Mmap> {
+        // SAFETY: a new mapping is requested at an address of the
+        // kernel's choosing, so no existing memory is affected.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let map = Mmap { ptr, len };
+
+        let meta = file.metadata()?;
+        if !meta.is_file() || meta.len() != len as u64 {
+            return Err(io::Error::other("file changed while being mapped"));
+        }
+        // SAFETY: ptr and len describe the mapping just created. The
+        // advice is only a hint, so its result is ignored.
+        unsafe { libc::madvise(ptr, len, libc::MADV_SEQUENTIAL) };
+        Ok(map)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: the mapping is readable for len bytes until dropped.
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: ptr and len describe a mapping owned by self.
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+enum MappedInner {
+    Mapped { map: Mmap, pos: usize },
+    Buffered(io::BufReader<Box<dyn Read>>),
+}
+
+/// Reads a file through a memory mapping where possible, so that large
+/// files are not copied through a small buffer, and through a
+/// [`io::BufReader`] otherwise (stdin, pipes, small or special files).
+///
+/// When mapped, [`BufRead::fill_buf`] returns the whole remaining file.
+///
+/// Only regular files whose size is unchanged once mapped are mapped,
+/// privately and read-only. As with any mapping, a file truncated by
+/// another process while it is being read raises SIGBUS.
+pub struct MappedReader {
+    inner: MappedInner,
+}
+
+impl MappedReader {
+    /// Opens a file, or stdin, as [`input_stream`] does.
+    pub fn open(pathname: &PathBuf, dashed_stdin: bool) -> io::Result<Self> {
+        let path_str = pathname.as_os_str();
+        if (dashed_stdin && path_str == "-") || (!dashed_stdin && path_str.is_empty()) {
+            Ok(Self::from_reader(Box::new(io::stdin().lock())))
+        } else {
+            Ok(Self::from_file(fs::File::open(pathname)?))
+        }
+    }
+
+    /// Maps `file` if it is a large enough regular file, falling back to
+    /// buffered reads if it is not or mapping fails.
+    pub fn from_file(file: fs::File) -> Self {
+        let map = match file.metadata() {
+            Ok(meta) if meta.is_file() && meta.len() >= MMAP_THRESHOLD => {
+                usize::try_from(meta.len())
+                    .ok()
+                    .and_then(|len| Mmap::map(&file, len).ok())
+            }
+            _ => None,
+        };
+        match map {
+            Some(map) => MappedReader {
+                inner: MappedInner::Mapped { map, pos: 0 },
+            },
+            None => Self::from_reader(Box::new(file)),
+        }
+    }
+
+    pub fn from_reader(reader: Box<dyn Read>) -> Self {
+        MappedReader {
+            inner: MappedInner::Buffered(io::BufReader::new(reader)),
+        }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.inner, MappedInner::Mapped { .. })
+    }
+}
+
+impl Read for MappedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            MappedInner::Mapped { .. } => {
+                let n = {
+                    let mut rest = self.fill_buf()?;
+                    rest.read(buf)?
+                };
+                self.consume(n);
+                Ok(n)
+            }
+            MappedInner::Buffered(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl BufRead for MappedReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match &mut self.inner {
+            MappedInner::Mapped { map, pos } => Ok(&map.as_slice()[*pos..]),
+            MappedInner::Buffered(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match &mut self.inner {
+            MappedInner::Mapped { map, pos } => *pos = (*pos + amt).min(map.len),
+            MappedInner::Buffered(reader) => reader.consume(amt),
+        }
+    }
+}
+
+/// Ends the process quietly with status 0 if `e` is EPIPE, as the reader
+/// of our output has gone away and there is nobody left to tell; returns
+/// any other error.
+fn exit_on_broken_pipe(e: io::Error) -> io::Error {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        std::process::exit(0);
+    }
+    e
+}
+
+/// Buffered standard output, for utilities whose output is commonly
+/// piped into another command that may stop reading early, such as
+/// `yes | head`.
+///
+/// A write failing with EPIPE ends the process with status 0 rather than
+/// being reported as an error. Other errors, e.g. from a full disk, are
+/// returned as usual; call [`StdoutWriter::finish`] before exiting so that
+/// those in the final flush are not lost.
+pub struct StdoutWriter {
+    inner: io::BufWriter<io::Stdout>,
+}
+
+pub fn stdout() -> StdoutWriter {
+    StdoutWriter {
+        inner: io::BufWriter::new(io::stdout()),
+    }
+}
+
+impl StdoutWriter {
+    /// Flushes any buffered output.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl Write for StdoutWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf).map_err(exit_on_broken_pipe)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf).map_err(exit_on_broken_pipe)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .flush()
+            .and_then(|()| io::stdout().flush())
+            .map_err(exit_on_broken_pipe)
+    }
+}
+
+/// Copies the rest of `reader` to standard output, after what has been
+/// written to `out` so far, and returns the number of bytes copied.
+///
+/// Call this with a concrete reader type, such as a `File`, a `StdinLock`,
+/// a `BufReader` of one of those or a `Take` of any of them: on Linux,
+/// `io::copy` then has the kernel move the data with copy_file_range,
+/// splice or sendfile when both ends are regular files or pipes, instead
+/// of copying it through a buffer here. It falls back to the buffered copy
+/// by itself everywhere else.
+pub fn copy_to_stdout<R: Read + ?Sized>(reader: &mut R, out: &mut StdoutWriter) -> io::Result<u64> {
+    out.flush()?;
+    io::copy(reader, &mut io::stdout().lock()).map_err(exit_on_broken_pipe)
+}
+
+impl Drop for StdoutWriter {
+    fn drop(&mut self) {
+        // Errors other than EPIPE can only be reported by finish().
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_ordered_lines() {
+        fn whole(line: &str) -> &str {
+            line
+        }
+        fn second(line: &str) -> &str {
+            line.split(' ').nth(1).unwrap_or("")
+        }
+
+        let lines = OrderedLines::new(&b"a\nb\nb\nc"[..], "f", whole, str::cmp, true);
+        assert_eq!(
+            lines.collect::<io::Result<Vec<_>>>().unwrap(),
+            ["a\n", "b\n", "b\n", "c"]
+        );
+
+        let mut lines = OrderedLines::new(&b"a\nc\nb\n"[..], "f", whole, str::cmp, true);
+        assert!(lines.next().unwrap().is_ok());
+        assert!(lines.next().unwrap().is_ok());
+        let e = lines.next().unwrap().unwrap_err();
+        assert_eq!(e.to_string(), "f: line 3 is not in sorted order");
+
+        let unchecked = OrderedLines::new(&b"c\nb\n"[..], "f", whole, str::cmp, false);
+        assert_eq!(unchecked.count(), 2);
+
+        let lines = OrderedLines::new(&b"z a\ny b\n"[..], "f", second, str::cmp, true);
+        assert_eq!(lines.count(), 2);
+    }
+
+    fn collect(input: &[u8], terminator: u8) -> Vec<Vec<u8>> {
+        RecordReader::new(input, terminator)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_newline_records() {
+        assert_eq!(
+            collect(b"a\nbc\n\nd\n", b'\n'),
+            vec![
+                b"a\n".to_vec(),
+                b"bc\n".to_vec(),
+                b"\n".to_vec(),
+                b"d\n".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nul_records() {
+        assert_eq!(
+            collect(b"one\0two\nlines\0", b'\0'),
+            vec![b"one\0".to_vec(), b"two\nlines\0".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_missing_final_terminator() {
+        assert_eq!(
+            collect(b"a\nb", b'\n'),
+            vec![b"a\n".to_vec(), b"b".to_vec()]
+        );
+        assert!(collect(b"", b'\n').is_empty());
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut reader = RecordReader::lines(&b"\xff\xfe\n\x80"[..]);
+        assert_eq!(reader.next_record().unwrap(), Some(&b"\xff\xfe\n"[..]));
+        assert_eq!(reader.next_record().unwrap(), Some(&b"\x80"[..]));
+        assert_eq!(reader.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn test_text_records() {
+        let mut reader = RecordReader::lines(&b"a\n\xff\nb"[..]);
+        assert_eq!(reader.next_text().unwrap(), Some("a"));
+        let e = reader.next_text().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.next_text().unwrap(), Some("b"));
+        assert_eq!(reader.next_text().unwrap(), None);
+    }
+
+    #[test]
+    fn test_record_larger_than_buffer() {
+        let mut input = vec![b'x'; RECORD_BUFSZ * 3];
+        input.push(b'\n');
+        input.extend_from_slice(b"y\n");
+        let reader = io::BufReader::with_capacity(RECORD_BUFSZ, &input[..]);
+        let records = RecordReader::lines(reader)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].len(), RECORD_BUFSZ * 3 + 1);
+        assert_eq!(records[1], b"y\n");
+    }
+
+    fn temp_file(contents: &[u8]) -> fs::File {
+        let dir = Fixture::builder("mapped").file("file", contents).build();
+        fs::File::open(dir.path("file")).unwrap()
+    }
+
+    #[test]
+    fn test_mapped_reader_large_file() {
+        let mut contents = Vec::new();
+        for i in 0..MMAP_THRESHOLD {
+            contents.extend_from_slice(format!("{}\n", i).as_bytes());
+        }
+        let mut reader = MappedReader::from_file(temp_file(&contents));
+        assert!(reader.is_mapped());
+        assert_eq!(reader.fill_buf().unwrap().len(), contents.len());
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "0\n");
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, contents[2..]);
+        assert!(reader.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mapped_reader_fallback() {
+        let mut reader = MappedReader::from_file(temp_file(b"small\nfile\n"));
+        assert!(!reader.is_mapped());
+        let lines: Vec<String> = reader.by_ref().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec!["small", "file"]);
+
+        let mut reader = MappedReader::from_reader(Box::new(&b"pipe"[..]));
+        assert!(!reader.is_mapped());
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "pipe");
+    }
+}
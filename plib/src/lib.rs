@@ -7,15 +7,32 @@
 // SPDX-License-Identifier: MIT
 //
 
+pub mod collate;
+pub mod ctype;
 pub mod curuser;
+pub mod delimiter;
+pub mod diag;
+pub mod dump;
+pub mod fd;
+pub mod follow;
+pub mod getopt;
 pub mod group;
+pub mod i18n;
 pub mod io;
 pub mod lzw;
 pub mod modestr;
 pub mod platform;
+pub mod printf;
 pub mod priority;
+pub mod regex;
 pub mod sccsfile;
+pub mod signal;
+pub mod sizestr;
+pub mod testexpr;
 pub mod testing;
+pub mod timefmt;
+pub mod userdb;
 pub mod utmpx;
+pub mod walk;
 
 pub const BUFSZ: usize = 8 * 1024;
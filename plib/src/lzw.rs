@@ -10,652 +10,511 @@
 // adapted from FreeBSD's zopen.c.
 //
 
-use std::io::{self, Error, ErrorKind, Read};
+//! The adaptive Lempel-Ziv-Welch coding of compress(1) and its `.Z`
+//! files.
+//!
+//! Codes start at 9 bits and grow by one bit each time the table fills
+//! up, to at most the number of bits given in the header. They are
+//! packed into groups of eight, so a group of n-bit codes takes n bytes;
+//! when the code size changes, the rest of the current group is skipped.
+//! Once the table is full, the compressor watches the compression ratio
+//! and emits a CLEAR code to start over with an empty table when it
+//! drops.
+
+use std::io::{self, Read, Write};
 
-const INIT_BITS: u32 = 9;
-const HSIZE: usize = 69_001;
-const CHECK_GAP: usize = 10_000;
-const BITS: u32 = 16;
 const MAGIC_HEADER: [u8; 2] = [0x1F, 0x9D];
 const HDR_BIT_MASK: u8 = 0x1f;
 const HDR_BLOCK_MASK: u8 = 0x80;
-const FIRST: i32 = 257;
-const CLEAR: i32 = 256;
-
-const LMASK: [i32; 9] = [0xff, 0xfe, 0xfc, 0xf8, 0xf0, 0xe0, 0xc0, 0x80, 0x00];
-const RMASK: [i32; 9] = [0x00, 0x01, 0x03, 0x07, 0x0f, 0x1f, 0x3f, 0x7f, 0xff];
 
-/// A wrapper around the Read trait object used
-/// for reading the compressed file or
-/// file to be compressed
-struct CompReader {
-    inner_rdr: Box<dyn Read>,
-}
+/// Code sizes, in bits.
+pub const MIN_BITS: u32 = 9;
+pub const MAX_BITS: u32 = 16;
 
-impl CompReader {
-    fn new(rdr: Box<dyn Read>) -> Self {
-        Self { inner_rdr: rdr }
-    }
+/// Clears the table, in block mode.
+const CLEAR: u32 = 256;
+/// The first free entry of the table, in block mode.
+const FIRST: u32 = 257;
 
-    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let mut total_read = 0;
+/// Number of input bytes between checks of the compression ratio, once
+/// the table is full.
+const CHECK_GAP: u64 = 10_000;
 
-        while total_read < buf.len() {
-            match self.inner_rdr.read(&mut buf[total_read..]) {
-                Ok(n) => {
-                    if n == 0 {
-                        break;
-                    }
-                    total_read += n;
-                }
-                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-
-        if total_read == 0 {
-            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected EOF"));
-        }
-
-        Ok(total_read)
-    }
-}
+/// Size of the compressor's hash table, a prime about 10% larger than
+/// the number of 16-bit codes.
+const HSIZE: usize = 69_001;
 
 fn max_code(n_bits: u32) -> u32 {
-    (1 << (n_bits)) - 1
+    (1 << n_bits) - 1
 }
 
-pub struct UnixLZWReader {
-    /// the reader of the compressed file or the file to be compressed
-    rdr: CompReader,
-
-    /// if the compressed file has header or not
-    have_hdr: bool,
-
-    /// if the eof has been reached or not
-    eof: bool,
-
-    /// the max no of bits for the code(maxmaxcode is derived from this)
-    maxbits: u32,
+fn corrupt_input() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, crate::tr!("corrupt input"))
+}
 
-    /// the current max no of bits for the code
-    n_bits: u32,
+/// Reads until `buf` is full or the end of input, returning the number
+/// of bytes read.
+fn read_full(r: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(len) => n += len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
 
-    /// if BLOCK_MASK is enabled in the compressed file or not
+/// Decompresses a `.Z` stream.
+pub struct UnixLZWReader<R: Read> {
+    inner: R,
+    max_bits: u32,
     block_compress: bool,
-
-    /// It indicates if the buffer has to be cleared or not
+    n_bits: u32,
+    /// Largest code of the current size.
+    max_code: u32,
+    /// One past the largest code of `max_bits`.
+    max_max_code: u32,
+    free_ent: u32,
+    /// Set after a CLEAR code, to start the next group with 9-bit codes.
     clear: bool,
 
-    code: i32,
-
-    /// the previously recognized code
-    oldcode: i32,
-
-    incode: i32,
-
-    /// the max no of codes that can be created of n_bits
-    maxcode: i32,
-
-    /// the max value of maxcode
-    maxmaxcode: i32,
-
-    /// the next free entry on the table
-    free_ent: i32,
-
-    finchar: i32,
-
-    /// It's the current read offset
-    roffset: i32,
-
-    size: i32,
-
-    /// Buffer to fill as we go on read  from the read stream
-    gbuf: [u8; BITS as usize],
-
-    tab_suffix: [i32; HSIZE],
-
-    tab_prefix: [u16; HSIZE],
+    /// The current group of codes, its size in bits, and the offset of
+    /// the next code in it.
+    group: [u8; MAX_BITS as usize],
+    group_bits: usize,
+    offset: usize,
+
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    /// The previous code and the first byte of its string, or `None`
+    /// before the first code.
+    prev: Option<(u32, u8)>,
+    /// Decoded bytes not returned yet, in reverse order.
+    stack: Vec<u8>,
+    eof: bool,
 }
 
-impl UnixLZWReader {
-    pub fn new(rdr: Box<dyn Read>) -> UnixLZWReader {
-        UnixLZWReader {
-            rdr: CompReader::new(rdr),
-
-            have_hdr: false,
-
-            eof: false,
-
-            maxbits: 0,
-
-            n_bits: 0,
-
-            block_compress: false,
-
-            clear: false,
-
-            code: 0,
-
-            oldcode: 0,
-
-            incode: 0,
-
-            maxcode: 0,
-
-            maxmaxcode: 0,
-
-            free_ent: 0,
-
-            finchar: 0,
-
-            roffset: 0,
-
-            size: 0,
-
-            gbuf: [0; BITS as usize],
-
-            tab_suffix: [0; HSIZE],
-
-            tab_prefix: [0; HSIZE],
+impl<R: Read> UnixLZWReader<R> {
+    /// Reads the header of the stream.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0; 3];
+        if read_full(&mut inner, &mut header)? < header.len() || header[..2] != MAGIC_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                crate::tr!("not in compressed format"),
+            ));
+        }
+        let max_bits = u32::from(header[2] & HDR_BIT_MASK);
+        if !(MIN_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                crate::tr!(
+                    "compressed with {} bits, can only handle {} bits",
+                    max_bits,
+                    MAX_BITS
+                ),
+            ));
         }
+        let block_compress = header[2] & HDR_BLOCK_MASK != 0;
+
+        Ok(Self {
+            inner,
+            max_bits,
+            block_compress,
+            n_bits: MIN_BITS,
+            max_code: max_code(MIN_BITS),
+            max_max_code: 1 << max_bits,
+            free_ent: if block_compress { FIRST } else { 256 },
+            clear: false,
+            group: [0; MAX_BITS as usize],
+            group_bits: 0,
+            offset: 0,
+            prefix: vec![0; 1 << max_bits],
+            suffix: (0..1usize << max_bits).map(|i| i as u8).collect(),
+            prev: None,
+            stack: Vec::new(),
+            eof: false,
+        })
     }
 
-    #[allow(clippy::assign_op_pattern)]
-    fn getcode(&mut self) -> i32 {
-        if self.clear || self.roffset >= self.size || self.free_ent > self.maxcode {
-            // as free_ent represents the index of the next available entry that can be made
-            // on the table, so if its more than the self.maxcode (i.e max allowed no of codes),
-            // which is derived from the current no of bits of code, then we need to expand
-            // our entry by increasing the n_bits by 1 and then updating the max_code
-            // from that
-            if self.free_ent > self.maxcode {
+    /// Reads the next code, or `None` at the end of the stream.
+    fn next_code(&mut self) -> io::Result<Option<u32>> {
+        let n_bits = self.n_bits as usize;
+        if self.clear || self.offset + n_bits > self.group_bits || self.free_ent > self.max_code {
+            if self.free_ent > self.max_code {
                 self.n_bits += 1;
-                self.maxcode = if self.n_bits == self.maxbits {
-                    self.maxmaxcode
+                self.max_code = if self.n_bits == self.max_bits {
+                    self.max_max_code
                 } else {
-                    max_code(self.n_bits) as i32
+                    max_code(self.n_bits)
                 };
             }
-
-            // reset the table entry back to smallest one
             if self.clear {
-                self.n_bits = INIT_BITS;
-                self.maxcode = max_code(self.n_bits) as i32;
+                self.n_bits = MIN_BITS;
+                self.max_code = max_code(MIN_BITS);
                 self.clear = false;
             }
 
-            // the buffer for current max n of bits
-            let gbuf = &mut self.gbuf[0..self.n_bits as usize];
-
-            match self.rdr.read_exact(gbuf) {
-                Ok(n) => {
-                    self.size = n as i32;
-                }
-                Err(_) => return -1,
+            let len = self.n_bits as usize;
+            let n = read_full(&mut self.inner, &mut self.group[..len])?;
+            // A partial group ends with as many whole codes as fit
+            self.group_bits = n * 8;
+            self.offset = 0;
+            if self.group_bits < self.n_bits as usize {
+                return Ok(None);
             }
-
-            self.roffset = 0;
-            self.size = (self.size << 3) - (self.n_bits - 1) as i32;
         }
 
-        let mut r_off = self.roffset;
-        let mut bits = self.n_bits;
-        let mut bp: usize = 0;
-
-        bp = bp + (r_off >> 3) as usize;
-        r_off = r_off & 7;
-
-        let mut gcode: i32 = (self.gbuf[bp] as i32) >> r_off;
-        bp = bp + 1;
-        bits = bits - (8 - r_off) as u32;
-        r_off = 8 - r_off;
-
-        if bits >= 8 {
-            gcode = gcode | ((self.gbuf[bp] as i32) << r_off);
-            bp = bp + 1;
-            r_off = r_off + 8;
-            bits = bits - 8;
+        let mut code = 0;
+        let mut got = 0;
+        while got < self.n_bits as usize {
+            let byte = self.group[self.offset / 8];
+            let shift = self.offset % 8;
+            let take = (8 - shift).min(self.n_bits as usize - got);
+            let bits = (u32::from(byte) >> shift) & ((1 << take) - 1);
+            code |= bits << got;
+            got += take;
+            self.offset += take;
         }
-
-        gcode = gcode | (((self.gbuf[bp] as i32) & RMASK[bits as usize]) << r_off);
-        self.roffset = self.roffset + self.n_bits as i32;
-
-        gcode
+        Ok(Some(code))
     }
 
-    /// Read from the compressed stream of file
-    pub fn read(&mut self) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
-
-        if !self.have_hdr {
-            // 3-byte header: 2 byte magic, 1 byte a bitmask of options.
-            let mut header = [0; 3];
-            self.rdr.read_exact(&mut header)?;
-
-            let header_magic = &header[0..2];
-
-            if &MAGIC_HEADER[..] != header_magic {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "invalid file header: magic number",
-                ));
-            }
-
-            // the third byte has bitmask of options
-            // (Eg) if it has 10011111
-            // that means the first bit represents the BLOCK_MASK i.e block_compress
-            // has to be enabled or not
-            //
-            // the bit that we get onwards represent the bit position of the value we want as
-            // max no bits
-            // if it's at 5th position then it means 2^4 = 16
-            let options = header[2];
-
-            self.maxbits = (options & HDR_BIT_MASK) as u32;
-            self.block_compress = (options & HDR_BLOCK_MASK) != 0;
-
-            if self.maxbits > BITS {
-                return Err(Error::new(ErrorKind::Other, "invalid file header: bits"));
-            }
-
-            // the max value that self.maxcode can have, which is derived
-            // from the maxbits that codes can have
-            // hence, 2^(self.maxbits)
-            self.maxmaxcode = 1 << self.maxbits;
-
-            // the no of bits of code that we start with
-            // btw, this no of bits also represent the fact that there can be
-            // 2 ^ (self.n_bits) entries in the table initially
-            self.n_bits = INIT_BITS; // 9
-            self.maxcode = max_code(self.n_bits) as i32; // 511
-
-            for code in (0..=255).rev() {
-                let idx: usize = code as usize;
-                self.code = code;
-                self.tab_prefix[idx] = 0;
-                self.tab_suffix[idx] = code;
-            }
-
-            if self.block_compress {
-                // TODO: understand why we need to skip one index (i.e 256) (initial guess is that
-                // we need that index reserverd for CLEAR)
-                self.free_ent = FIRST;
-            } else {
-                self.free_ent = 256;
-            }
-
-            self.have_hdr = true;
-
-            self.oldcode = self.getcode();
-            self.finchar = self.oldcode;
-            if self.oldcode < 0 {
-                self.eof = true;
-                return Ok(Vec::new());
+    /// Decodes the next code onto the stack. Returns `false` at the end
+    /// of the stream.
+    fn decode(&mut self) -> io::Result<bool> {
+        let Some(mut code) = self.next_code()? else {
+            return Ok(false);
+        };
+        if code == CLEAR && self.block_compress {
+            self.clear = true;
+            self.free_ent = FIRST - 1;
+            match self.next_code()? {
+                Some(next) => code = next,
+                None => return Ok(false),
             }
-
-            outbytes.push(self.finchar as u8);
         }
 
-        loop {
-            let mut stack: Vec<u8> = Vec::new();
-
-            self.code = self.getcode();
-            if self.code < 0 {
-                self.eof = true;
-                break;
+        let Some((prev_code, prev_first)) = self.prev else {
+            // The first code is a byte of its own
+            if code > 255 {
+                return Err(corrupt_input());
             }
+            self.stack.push(code as u8);
+            self.prev = Some((code, code as u8));
+            return Ok(true);
+        };
 
-            if (self.code == CLEAR) && self.block_compress {
-                // clear the table and fill it again with value
-                for code in (0..=255).rev() {
-                    let idx: usize = code as usize;
-                    self.code = code;
-                    self.tab_prefix[idx] = 0;
-                }
-                self.clear = true;
-                self.free_ent = FIRST - 1;
-
-                self.code = self.getcode();
-                if self.code < 0 {
-                    self.eof = true;
-                    break;
-                }
+        // The string goes onto the stack back to front, to come off it
+        // in order
+        let incode = code;
+        if code >= self.free_ent {
+            // The string of the previous code followed by its own first
+            // byte, which is only just being added to the table
+            if code > self.free_ent {
+                return Err(corrupt_input());
             }
+            self.stack.push(prev_first);
+            code = prev_code;
+        }
+        while code >= 256 {
+            self.stack.push(self.suffix[code as usize]);
+            code = u32::from(self.prefix[code as usize]);
+        }
+        let first = code as u8;
+        self.stack.push(first);
 
-            self.incode = self.code;
-
-            if self.code >= self.free_ent {
-                stack.push(self.finchar as u8);
-                self.code = self.oldcode;
-            }
+        if self.free_ent < self.max_max_code {
+            self.prefix[self.free_ent as usize] = prev_code as u16;
+            self.suffix[self.free_ent as usize] = first;
+            self.free_ent += 1;
+        }
+        self.prev = Some((incode, first));
+        Ok(true)
+    }
+}
 
-            while self.code >= 256 {
-                let code = self.code as usize;
-                stack.push(self.tab_suffix[code] as u8);
-                self.code = self.tab_prefix[code] as i32;
+impl<R: Read> Read for UnixLZWReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            if let Some(byte) = self.stack.pop() {
+                buf[n] = byte;
+                n += 1;
+                continue;
             }
-            self.finchar = self.tab_suffix[self.code as usize];
-            stack.push(self.finchar as u8);
-
-            for stackchar in stack.iter().rev() {
-                outbytes.push(*stackchar);
+            if self.eof {
+                break;
             }
-
-            self.code = self.free_ent;
-            if self.code < self.maxmaxcode {
-                let code = self.code as usize;
-                self.tab_prefix[code] = self.oldcode as u16;
-                self.tab_suffix[code] = self.finchar;
-                self.free_ent = self.code + 1;
+            if !self.decode()? {
+                self.eof = true;
             }
-
-            self.oldcode = self.incode;
         }
-
-        Ok(outbytes)
+        Ok(n)
     }
 }
 
-/// Enum to store the current state of the writer
-#[derive(PartialEq)]
-enum WriterState {
-    Start,
-    Middle,
-}
-
-pub struct UnixLZWWriter {
-    /// The current state of the writer
-    state: WriterState,
-
-    /// Current number of bits per code
+/// Compresses data into a `.Z` stream.
+pub struct UnixLZWWriter<W: Write> {
+    inner: W,
+    max_bits: u32,
     n_bits: u32,
+    max_code: u32,
+    max_max_code: u32,
+    free_ent: u32,
+    /// Set when a CLEAR code is being written, to start the next group
+    /// with 9-bit codes.
+    clear: bool,
 
-    /// Maximum bits allowed for codes
-    maxbits: u32,
-
-    /// Maximum code for current number of bits
-    maxcode: i32,
-
-    // Maximum code for maximum bits
-    maxmaxcode: i32,
-
-    /// Hash table for codes
-    htab: Vec<i32>,
+    /// The current group of codes, and the offset of the next code in it.
+    group: [u8; MAX_BITS as usize],
+    offset: usize,
 
-    /// Size of the hash table
-    hsize: i32,
-    /// Code table
+    /// Hash table of the strings in the table, keyed by their prefix code
+    /// and final byte, and the codes of its entries.
+    htab: Vec<i64>,
     codetab: Vec<u16>,
-
-    /// Next free entry in the table
-    free_ent: i32,
-
-    /// Flag for block compression
-    block_compress: bool,
-
-    /// Flag to clear the dictionary
-    clear_flg: bool,
-
-    ratio: i32,
-
-    /// Next checkpoint for compression ratio
-    checkpoint: i32,
-
-    /// Offset for current byte
-    offset: u32,
-
-    in_count: i32,
-
-    bytes_out: i32,
-
-    out_count: i32,
-
-    /// Buffer for output bytes
-    buf: [u8; BITS as usize],
-
-    write_params: WriteParams,
-}
-
-struct WriteParams {
-    fcode: i32,
-
-    ent: i32,
-
-    hsize_reg: i32,
-
-    hshift: i32,
+    hshift: u32,
+
+    /// The code of the string matched so far, or `None` before any input.
+    ent: Option<u32>,
+    bytes_in: u64,
+    bytes_out: u64,
+    /// Compression ratio at the last check, and when to check next.
+    ratio: u64,
+    checkpoint: u64,
 }
 
-impl UnixLZWWriter {
-    pub fn new(mbits: Option<u32>) -> Self {
-        let maxbits = match mbits {
-            Some(m) => m.clamp(9, 16),
-            None => BITS,
-        };
-
-        let maxmaxcode = (1 << maxbits) as i32;
+impl<W: Write> UnixLZWWriter<W> {
+    /// Writes the header of a stream with codes of up to `max_bits` bits,
+    /// which is clamped to the sizes supported.
+    pub fn new(mut inner: W, max_bits: u32) -> io::Result<Self> {
+        let max_bits = max_bits.clamp(MIN_BITS, MAX_BITS);
+        inner.write_all(&MAGIC_HEADER)?;
+        inner.write_all(&[max_bits as u8 | HDR_BLOCK_MASK])?;
+
+        let mut hshift = 0;
+        let mut fcode = HSIZE;
+        while fcode < 65536 {
+            hshift += 1;
+            fcode *= 2;
+        }
 
-        Self {
-            state: WriterState::Start,
-            n_bits: INIT_BITS,
-            maxbits,
-            maxcode: max_code(INIT_BITS) as i32,
-            maxmaxcode,
+        Ok(Self {
+            inner,
+            max_bits,
+            n_bits: MIN_BITS,
+            max_code: max_code(MIN_BITS),
+            max_max_code: 1 << max_bits,
+            free_ent: FIRST,
+            clear: false,
+            group: [0; MAX_BITS as usize],
+            offset: 0,
             htab: vec![-1; HSIZE],
             codetab: vec![0; HSIZE],
-            hsize: HSIZE as i32,
-            free_ent: 0,
-            block_compress: true,
-            clear_flg: false,
+            hshift: 8 - hshift,
+            ent: None,
+            bytes_in: 0,
+            bytes_out: 3,
             ratio: 0,
-            checkpoint: CHECK_GAP as i32,
-            offset: 0,
-            in_count: 0,
-            bytes_out: 0,
-            out_count: 0,
-            buf: [0; BITS as usize],
-            write_params: WriteParams {
-                fcode: 0,
-                ent: 0,
-                hsize_reg: 0,
-                hshift: 0,
-            },
-        }
+            checkpoint: CHECK_GAP,
+        })
     }
 
-    fn clear_block(&mut self) -> io::Result<()> {
-        self.checkpoint = self.in_count + CHECK_GAP as i32;
+    /// Number of bytes compressed so far.
+    pub fn bytes_in(&self) -> u64 {
+        self.bytes_in
+    }
 
-        let rat = if self.in_count > 0x007fffff {
-            match self.bytes_out >> 8 {
-                0 => 0x7fffffff,
-                _ => self.in_count / (self.bytes_out >> 8),
-            }
-        } else {
-            (self.in_count << 8) / self.bytes_out
-        };
+    /// Number of bytes written so far, including the header.
+    pub fn bytes_out(&self) -> u64 {
+        self.bytes_out
+    }
 
-        if rat > self.ratio {
-            self.ratio = rat;
-        } else {
-            self.ratio = 0;
-            self.clear_hash();
-            self.free_ent = FIRST;
-            self.clear_flg = true;
-            self.output(CLEAR)?;
-        }
+    fn write_group(&mut self, len: usize) -> io::Result<()> {
+        self.inner.write_all(&self.group[..len])?;
+        self.bytes_out += len as u64;
+        self.group = [0; MAX_BITS as usize];
+        self.offset = 0;
         Ok(())
     }
 
-    fn clear_hash(&mut self) {
-        self.htab.fill(-1)
-    }
+    fn output(&mut self, mut code: u32) -> io::Result<()> {
+        let n_bits = self.n_bits as usize;
+        let mut left = n_bits;
+        while left > 0 {
+            let shift = self.offset % 8;
+            let take = (8 - shift).min(left);
+            self.group[self.offset / 8] |= ((code & ((1 << take) - 1)) << shift) as u8;
+            code >>= take;
+            left -= take;
+            self.offset += take;
+        }
+        if self.offset == n_bits * 8 {
+            self.write_group(n_bits)?;
+        }
 
-    fn output(&mut self, mut ocode: i32) -> io::Result<Vec<u8>> {
-        let mut r_off = self.offset;
-        let mut bits = self.n_bits;
-        let mut bp: usize = 0;
-        let mut outbytes: Vec<u8> = Vec::new();
-
-        if ocode >= 0 {
-            bp += (r_off >> 3) as usize;
-            r_off &= 7;
-
-            self.buf[bp] = ((self.buf[bp] as i32 & RMASK[r_off as usize] as i32) as u8)
-                | ((ocode << r_off) & LMASK[r_off as usize] as i32) as u8;
-            bp += 1;
-            bits -= 8 - r_off;
-            ocode >>= 8 - r_off;
-
-            if bits >= 8 {
-                self.buf[bp] = ocode as u8;
-                bp += 1;
-                ocode >>= 8;
-                bits -= 8;
+        if self.free_ent > self.max_code || self.clear {
+            // The reader skips the rest of the group on a change of size
+            if self.offset > 0 {
+                self.write_group(n_bits)?;
             }
-
-            if bits > 0 {
-                self.buf[bp] = ocode as u8;
+            if self.clear {
+                self.n_bits = MIN_BITS;
+                self.max_code = max_code(MIN_BITS);
+                self.clear = false;
+            } else {
+                self.n_bits += 1;
+                self.max_code = if self.n_bits == self.max_bits {
+                    self.max_max_code
+                } else {
+                    max_code(self.n_bits)
+                };
             }
+        }
+        Ok(())
+    }
 
-            self.offset += self.n_bits;
+    /// Starts over with an empty table if the compression ratio dropped
+    /// since the last check.
+    fn check_ratio(&mut self) -> io::Result<()> {
+        self.checkpoint = self.bytes_in + CHECK_GAP;
+        let ratio = (self.bytes_in << 8) / self.bytes_out.max(1);
+        if ratio > self.ratio {
+            self.ratio = ratio;
+            return Ok(());
+        }
+        self.ratio = 0;
+        self.htab.fill(-1);
+        self.free_ent = FIRST;
+        self.clear = true;
+        self.output(CLEAR)
+    }
 
-            if self.offset == (self.n_bits << 3) {
-                bits = self.n_bits;
-                self.bytes_out += bits as i32;
-                let temp_buf = &self.buf[..bits as usize];
-                outbytes.extend(temp_buf);
-                self.offset = 0;
-            }
+    /// Adds a byte to the string matched so far, writing out the string's
+    /// code when the longer string is not in the table.
+    fn push(&mut self, c: u8) -> io::Result<()> {
+        self.bytes_in += 1;
+        let Some(ent) = self.ent else {
+            self.ent = Some(u32::from(c));
+            return Ok(());
+        };
 
-            if self.free_ent > self.maxcode || self.clear_flg {
-                if self.offset > 0 {
-                    let temp_buf = &self.buf[..self.n_bits as usize];
-                    outbytes.extend(temp_buf);
-                    self.bytes_out += self.n_bits as i32;
+        let fcode = (i64::from(c) << self.max_bits) + i64::from(ent);
+        let mut i = ((usize::from(c) << self.hshift) ^ ent as usize) % HSIZE;
+        if self.htab[i] >= 0 {
+            // Secondary probe, as in the original
+            let disp = if i == 0 { 1 } else { HSIZE - i };
+            loop {
+                if self.htab[i] == fcode {
+                    self.ent = Some(u32::from(self.codetab[i]));
+                    return Ok(());
                 }
-                self.offset = 0;
-
-                if self.clear_flg {
-                    self.maxcode = max_code(INIT_BITS) as i32;
-                    self.clear_flg = false;
-                } else {
-                    self.n_bits += 1;
-                    self.maxcode = if self.n_bits == self.maxbits {
-                        self.maxmaxcode
-                    } else {
-                        max_code(self.n_bits) as i32
-                    };
+                if self.htab[i] < 0 {
+                    break;
                 }
+                i = (i + HSIZE - disp) % HSIZE;
             }
-        } else {
-            if self.offset > 0 {
-                self.offset = (self.offset + 7) / 8;
-                let temp_buf = &self.buf[..self.offset as usize];
-                outbytes.extend(temp_buf);
-                self.bytes_out += self.offset as i32;
-            }
-            self.offset = 0;
         }
 
-        Ok(outbytes)
+        self.output(ent)?;
+        self.ent = Some(u32::from(c));
+        if self.free_ent < self.max_max_code {
+            self.codetab[i] = self.free_ent as u16;
+            self.htab[i] = fcode;
+            self.free_ent += 1;
+        } else if self.bytes_in >= self.checkpoint {
+            self.check_ratio()?;
+        }
+        Ok(())
     }
 
-    pub fn write(&mut self, buffer: &[u8]) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
+    /// Writes out the string matched so far and the last partial group,
+    /// returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(ent) = self.ent.take() {
+            self.output(ent)?;
+        }
+        if self.offset > 0 {
+            let len = self.offset.div_ceil(8);
+            self.write_group(len)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
 
-        if self.state == WriterState::Start {
-            self.state = WriterState::Middle;
-            self.maxmaxcode = 1 << self.maxbits;
-            outbytes.extend(&[0x1F, 0x9D]);
-            let tmp = (self.maxbits as u8)
-                | if self.block_compress {
-                    HDR_BLOCK_MASK
-                } else {
-                    0
-                };
-            outbytes.push(tmp);
-
-            self.bytes_out = 3;
-            self.clear_flg = false;
-            self.in_count = 1;
-            self.free_ent = if self.block_compress { FIRST } else { 256 };
-
-            let mut fcode = self.hsize;
-            let mut hshift = 0;
-            while fcode < 65536 {
-                hshift += 1;
-                fcode *= 2;
-            }
-            self.write_params = WriteParams {
-                fcode,
-                ent: buffer[0] as i32,
-                hsize_reg: self.hsize,
-                hshift: 8 - hshift,
-            };
-
-            self.clear_hash();
+impl<W: Write> Write for UnixLZWWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &c in buf {
+            self.push(c)?;
         }
+        Ok(buf.len())
+    }
 
-        for &item in &buffer[1..] {
-            let c = item as i32;
-            self.in_count += 1;
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
 
-            self.write_params.fcode = ((c) << self.maxbits) + (self.write_params.ent);
-            let mut i = (c << self.write_params.hshift) ^ self.write_params.ent;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let mut skip_flag = false;
-            if self.htab[i as usize] as i32 == self.write_params.fcode {
-                self.write_params.ent = self.codetab[i as usize] as i32;
-                continue;
-            } else if self.htab[i as usize] as i32 >= 0 {
-                let mut disp = self.write_params.hsize_reg - i;
-                if i == 0 {
-                    disp = 1;
-                }
-                loop {
-                    i -= disp;
-                    if i < 0 {
-                        i += self.write_params.hsize_reg;
-                    }
-                    if self.htab[i as usize] as i32 == self.write_params.fcode {
-                        self.write_params.ent = self.codetab[i as usize] as i32;
-                        skip_flag = true;
-                        break;
-                    }
-                    if self.htab[i as usize] < 0 {
-                        break;
-                    }
-                }
-            }
-            if skip_flag {
-                continue;
-            }
+    fn compress(data: &[u8], bits: u32) -> Vec<u8> {
+        let mut writer = UnixLZWWriter::new(Vec::new(), bits).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn uncompress(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        UnixLZWReader::new(data)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        out
+    }
 
-            outbytes.extend(self.output(self.write_params.ent)?);
-            self.out_count += 1;
-            self.write_params.ent = c;
+    /// Pseudo-random bytes from a small alphabet, which fill the table
+    /// and make the compressor clear it.
+    fn sample(len: usize) -> Vec<u8> {
+        let mut x: u32 = 12345;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                b'a' + ((x >> 16) % 7) as u8
+            })
+            .collect()
+    }
 
-            if self.free_ent < self.maxmaxcode {
-                self.codetab[i as usize] = self.free_ent as u16;
-                self.free_ent += 1;
-                self.htab[i as usize] = self.write_params.fcode as i32;
-            } else if self.in_count >= self.checkpoint && self.block_compress {
-                self.clear_block()?;
-            }
+    #[test]
+    fn test_round_trip() {
+        for data in [&b""[..], b"a", b"abababababab", b"TOBEORNOTTOBEORTOBEORNOT"] {
+            assert_eq!(uncompress(&compress(data, 16)), data);
         }
-        Ok(outbytes)
+        let data = sample(300_000);
+        for bits in [9, 12, 16] {
+            assert_eq!(uncompress(&compress(&data, bits)), data);
+        }
+    }
+
+    #[test]
+    fn test_historical_stream() {
+        // "TOBEORNOTTOBEORTOBEORNOT\n" as compress(1) writes it
+        let stream = [
+            0x1f, 0x9d, 0x90, 0x54, 0x9e, 0x08, 0x29, 0xf2, 0x44, 0x8a, 0x93, 0x27, 0x54, 0x02,
+            0x0e, 0x2c, 0xa8, 0x90, 0xa0, 0x41, 0x84, 0x0a, 0x00,
+        ];
+        assert_eq!(uncompress(&stream), b"TOBEORNOTTOBEORTOBEORNOT\n");
+        assert_eq!(compress(b"TOBEORNOTTOBEORTOBEORNOT\n", 16), stream);
     }
 
-    /// Return the remaining bytes which could not be written from close()
-    pub fn close(&mut self) -> io::Result<Vec<u8>> {
-        let mut outbytes: Vec<u8> = Vec::new();
-        outbytes.extend(self.output(self.write_params.ent)?);
-        self.out_count += 1;
-        outbytes.extend(self.output(-1)?);
-        Ok(outbytes)
+    #[test]
+    fn test_invalid_header() {
+        assert!(UnixLZWReader::new(&b"\x1f\x8b\x08"[..]).is_err());
+        assert!(UnixLZWReader::new(&b"\x1f\x9d\x91"[..]).is_err());
     }
 }
@@ -115,6 +115,9 @@ pub fn parse(mode: &str) -> Result<ChmodMode, String> {
                         '-' => action.op = ChmodActionOp::Remove,
                         '=' => action.op = ChmodActionOp::Set,
                         _ => {
+                            if clause.actions.is_empty() {
+                                return Err("invalid mode string".to_string());
+                            }
                             action.dirty = false;
                             done_with_char = false;
                             symbolic.clauses.push(clause);
@@ -130,19 +133,17 @@ pub fn parse(mode: &str) -> Result<ChmodMode, String> {
                 },
 
                 ParseState::PermCopy => {
+                    // A "permcopy" is a single character
                     done_with_char = true;
                     match c {
                         'u' => action.copy_user = true,
                         'g' => action.copy_group = true,
-                        'o' => action.copy_others = true,
-                        _ => {
-                            done_with_char = false;
-                            clause.actions.push(action);
-                            clause.dirty = true;
-                            action = ChmodAction::default();
-                            state = ParseState::Actionlist;
-                        }
+                        _ => action.copy_others = true,
                     }
+                    clause.actions.push(action);
+                    clause.dirty = true;
+                    action = ChmodAction::default();
+                    state = ParseState::Actionlist;
                 }
 
                 ParseState::PermList => {
@@ -180,17 +181,21 @@ pub fn parse(mode: &str) -> Result<ChmodMode, String> {
         clause.dirty = true;
     }
     if clause.dirty {
+        if clause.actions.is_empty() {
+            return Err("invalid mode string".to_string());
+        }
         symbolic.clauses.push(clause);
     }
+    if symbolic.clauses.is_empty() {
+        return Err("invalid mode string".to_string());
+    }
 
     Ok(ChmodMode::Symbolic(symbolic))
 }
 
 // apply symbolic mutations to the given file at path
 pub fn mutate(init_mode: u32, is_dir: bool, symbolic: &ChmodSymbolic) -> u32 {
-    let mut user = init_mode & S_IRWXU as u32;
-    let mut group = init_mode & S_IRWXG as u32;
-    let mut others = init_mode & S_IRWXO as u32;
+    let mut perms = init_mode & (S_IRWXU | S_IRWXG | S_IRWXO) as u32;
     let mut special = init_mode & (S_ISUID | S_ISGID | S_ISVTX) as u32;
 
     let mut cached_umask = None;
@@ -216,10 +221,42 @@ pub fn mutate(init_mode: u32, is_dir: bool, symbolic: &ChmodSymbolic) -> u32 {
     // apply each clause
     for clause in &symbolic.clauses {
         let who_is_not_specified = !(clause.user || clause.group || clause.others);
+        let (user, group, others) = if who_is_not_specified {
+            (true, true, true)
+        } else {
+            (clause.user, clause.group, clause.others)
+        };
+
+        // The permission bits and special bits the "who" list refers to
+        let mut who_perms = 0;
+        let mut who_special = 0;
+        if user {
+            who_perms |= S_IRWXU as u32;
+            who_special |= S_ISUID as u32;
+        }
+        if group {
+            who_perms |= S_IRWXG as u32;
+            who_special |= S_ISGID as u32;
+        }
+        if others {
+            who_perms |= S_IRWXO as u32;
+            who_special |= S_ISVTX as u32;
+        }
 
         // apply each action
         for action in &clause.actions {
-            let mut rwx = 0;
+            // "permcopy": the 3 permission bits of one of the "who" parts, as
+            // they are before this action
+            let mut rwx = if action.copy_user {
+                (perms & S_IRWXU as u32) >> 6
+            } else if action.copy_group {
+                (perms & S_IRWXG as u32) >> 3
+            } else if action.copy_others {
+                perms & S_IRWXO as u32
+            } else {
+                0
+            };
+
             if action.read {
                 rwx |= 0b100;
             }
@@ -235,60 +272,35 @@ pub fn mutate(init_mode: u32, is_dir: bool, symbolic: &ChmodSymbolic) -> u32 {
             //
             // Upon testing the GNU chmod implementation, "current" here does not mean the initial
             // mode bits, but the mode bits built by the previous clauses.
-            let has_any_exec_bits =
-                ((user | group | others) & (S_IXUSR | S_IXGRP | S_IXOTH) as u32) != 0;
+            let has_any_exec_bits = perms & (S_IXUSR | S_IXGRP | S_IXOTH) as u32 != 0;
+            if action.execute_dir && (is_dir || has_any_exec_bits) {
+                rwx |= 0b001;
+            }
+
+            // Without a "who", the bits set in the umask are left alone
+            let mask = if who_is_not_specified { get_umask() } else { 0 };
+            let bits = (rwx << 6 | rwx << 3 | rwx) & who_perms & !mask;
+
+            let mut special_bits = 0;
+            if action.setuid {
+                special_bits |= who_special & (S_ISUID | S_ISGID) as u32;
+            }
+            if action.sticky {
+                // Not affected by the "who" list or the umask
+                special_bits |= S_ISVTX as u32;
+            }
 
             match action.op {
                 // add bits to the mode
                 ChmodActionOp::Add => {
-                    if clause.user {
-                        user |= rwx << 6;
-                    }
-                    if clause.group {
-                        group |= rwx << 3;
-                    }
-                    if clause.others {
-                        others |= rwx;
-                    }
-
-                    if who_is_not_specified {
-                        let umask = get_umask();
-
-                        user |= (rwx << 6) & !umask;
-                        group |= (rwx << 3) & !umask;
-                        others |= rwx & !umask;
-                    }
-
-                    if action.setuid {
-                        // If "who" is missing, set both `S_ISUID` and `S_ISGID`
-                        if clause.user || who_is_not_specified {
-                            special |= S_ISUID as u32;
-                        }
-                        if clause.group || who_is_not_specified {
-                            special |= S_ISGID as u32;
-                        }
-                    }
+                    perms |= bits;
+                    special |= special_bits;
                 }
 
                 // remove bits from the mode
                 ChmodActionOp::Remove => {
-                    if clause.user {
-                        user &= !(rwx << 6);
-                    }
-                    if clause.group {
-                        group &= !(rwx << 3);
-                    }
-                    if clause.others {
-                        others &= !rwx;
-                    }
-
-                    if who_is_not_specified {
-                        let umask = get_umask();
-
-                        user &= !(rwx << 6) & !umask;
-                        group &= !(rwx << 3) & !umask;
-                        others &= !rwx & !umask;
-                    }
+                    perms &= !bits;
+                    special &= !special_bits;
                 }
 
                 // set the mode bits
@@ -296,103 +308,23 @@ pub fn mutate(init_mode: u32, is_dir: bool, symbolic: &ChmodSymbolic) -> u32 {
                     // See the EXTENDED DESCRIPTION section of
                     // https://pubs.opengroup.org/onlinepubs/9699919799/utilities/chmod.html
                     // for the meaning of "permcopy" and "permlist"
-
-                    // The 3 permission bits to copy from "permcopy"
-                    let copy_value = match (action.copy_user, action.copy_group, action.copy_others)
-                    {
-                        (true, false, false) => user >> 6,
-                        (false, true, false) => group >> 3,
-                        (false, false, true) => others,
-                        (false, false, false) => {
-                            // Either a "permlist" was specified or nothing is
-                            0
-                        }
-                        _ => panic!(
-                            "Only one of 'u', 'g', or 'o' can be used as the source for copying"
-                        ),
-                    };
-
-                    // Should be at most 3 bits
-                    debug_assert!(copy_value <= 0b111);
-
-                    if clause.user {
-                        user = (copy_value | rwx) << 6;
-                    }
-                    if clause.group {
-                        group = (copy_value | rwx) << 3;
-                    }
-                    if clause.others {
-                        others = copy_value | rwx;
-                    }
-
-                    if who_is_not_specified {
-                        let umask = get_umask();
-
-                        user = (copy_value | rwx) << 6 & !umask;
-                        group = (copy_value | rwx) << 3 & !umask;
-                        others = (copy_value | rwx) & !umask;
-                    }
-
-                    // Always reset when "op" is "="
-                    special = 0;
-                }
-            }
-
-            if action.setuid {
-                match action.op {
-                    ChmodActionOp::Add | ChmodActionOp::Set => {
-                        // If "who" is missing, set both `S_ISUID` and `S_ISGID`
-                        if clause.user || who_is_not_specified {
-                            special |= S_ISUID as u32;
-                        }
-                        if clause.group || who_is_not_specified {
-                            special |= S_ISGID as u32;
-                        }
-                    }
-                    ChmodActionOp::Remove => {
-                        // If "who" is missing, remove both `S_ISUID` and `S_ISGID`
-                        if clause.user || who_is_not_specified {
-                            special &= !S_ISUID as u32;
-                        }
-                        if clause.group || who_is_not_specified {
-                            special &= !S_ISGID as u32;
-                        }
-                    }
-                }
-            }
-
-            if action.sticky {
-                // Not affected by the umask
-                match action.op {
-                    ChmodActionOp::Add | ChmodActionOp::Set => {
-                        special |= S_ISVTX as u32;
-                    }
-                    ChmodActionOp::Remove => {
-                        special &= !S_ISVTX as u32;
-                    }
-                }
-            }
-
-            if action.execute_dir && (is_dir || has_any_exec_bits) {
-                let mask = if who_is_not_specified { get_umask() } else { 0 };
-
-                match action.op {
-                    ChmodActionOp::Add | ChmodActionOp::Set => {
-                        user |= S_IXUSR as u32 & !mask;
-                        group |= S_IXGRP as u32 & !mask;
-                        others |= S_IXOTH as u32 & !mask;
-                    }
-                    ChmodActionOp::Remove => {
-                        user &= !S_IXUSR as u32 & !mask;
-                        group &= !S_IXGRP as u32 & !mask;
-                        others &= !S_IXOTH as u32 & !mask;
+                    perms = (perms & !who_perms) | bits;
+
+                    // Done to match the behavior of coreutils chmod: "For
+                    // directories chmod preserves set-user-ID and
+                    // set-group-ID bits unless you explicitly specify
+                    // otherwise"
+                    let mut cleared = who_special;
+                    if is_dir && !action.setuid {
+                        cleared &= S_ISVTX as u32;
                     }
+                    special = (special & !cleared) | special_bits;
                 }
             }
         }
     }
 
-    user | group | others | special
+    perms | special
 }
 
 #[cfg(test)]
@@ -576,4 +508,64 @@ mod tests {
             0o070
         );
     }
+
+    #[test]
+    fn test_parse_mode_invalid() {
+        for mode in ["", "u", "u+x,g", "u+x,,g+w", "u=go", "u+rk", "8"] {
+            assert!(parse(mode).is_err(), "{mode}");
+        }
+    }
+
+    #[test]
+    fn test_mutate_mode_exec_dir_wholist() {
+        // X only applies to the bits of the "who" list
+        assert_eq!(mutate(0o644, true, &parse_symbolic("u+X")), 0o744);
+        assert_eq!(mutate(0o744, false, &parse_symbolic("go+X")), 0o755);
+        assert_eq!(mutate(0o644, false, &parse_symbolic("go+X")), 0o644);
+        assert_eq!(mutate(0o755, true, &parse_symbolic("go-X")), 0o744);
+        assert_eq!(mutate(0o777, true, &parse_symbolic("u=rX")), 0o577);
+    }
+
+    #[test]
+    fn test_mutate_mode_copy() {
+        assert_eq!(mutate(0o640, false, &parse_symbolic("o+u")), 0o646);
+        assert_eq!(mutate(0o751, false, &parse_symbolic("g-o")), 0o741);
+        assert_eq!(mutate(0o700, false, &parse_symbolic("go=u-w")), 0o755);
+    }
+
+    #[test]
+    fn test_mutate_mode_special_bits() {
+        let setuid = S_ISUID as u32;
+        let setgid = S_ISGID as u32;
+        let sticky = S_ISVTX as u32;
+
+        assert_eq!(mutate(0o755, false, &parse_symbolic("u+s")), setuid | 0o755);
+        assert_eq!(mutate(0o755, false, &parse_symbolic("g+s")), setgid | 0o755);
+        assert_eq!(mutate(0o755, false, &parse_symbolic("o+s")), 0o755);
+        assert_eq!(mutate(0o777, true, &parse_symbolic("+t")), sticky | 0o777);
+        assert_eq!(
+            mutate(0o755, false, &parse_symbolic("ug+s,+t")),
+            setuid | setgid | sticky | 0o755
+        );
+        assert_eq!(
+            mutate(setuid | setgid | 0o755, false, &parse_symbolic("g-s")),
+            setuid | 0o755
+        );
+        assert_eq!(mutate(sticky | 0o777, true, &parse_symbolic("-t")), 0o777);
+
+        // "=" clears the special bits of the "who" list
+        assert_eq!(
+            mutate(setuid | setgid | 0o755, false, &parse_symbolic("u=rwx")),
+            setgid | 0o755
+        );
+        assert_eq!(
+            mutate(setgid | 0o755, false, &parse_symbolic("g=rxs")),
+            setgid | 0o755
+        );
+        // ... except for the set-ID bits of directories
+        assert_eq!(
+            mutate(setgid | 0o755, true, &parse_symbolic("g=rx")),
+            setgid | 0o755
+        );
+    }
 }
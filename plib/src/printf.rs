@@ -0,0 +1,482 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The format engine of printf, shared by the printf utility and the sh
+//! builtin so that both format alike.
+//!
+//! Numeric conversions are done by the C library, with the flags, width
+//! and precision of the format, so that they match printf(3) exactly.
+//! Numeric arguments are read as C constants, by strtoll(3), strtoull(3)
+//! and strtod(3); an argument starting with a quote stands for the value
+//! of the character after it.
+
+use std::ffi::{CStr, CString};
+
+use libc::c_char;
+
+/// A width or precision.
+#[derive(Clone, Copy)]
+enum Count {
+    Fixed(usize),
+    /// `*`: taken from the next argument
+    Argument,
+}
+
+struct Conversion {
+    flags: String,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: u8,
+}
+
+enum Token {
+    Literal(Vec<u8>),
+    Conversion(Conversion),
+    /// `\c`: no further output
+    Stop,
+}
+
+enum Escape {
+    Byte(u8),
+    /// An unknown escape, written as it is
+    Verbatim(u8),
+    Stop,
+}
+
+/// Reads the escape sequence following the backslash at `bytes[*pos]`,
+/// and moves `pos` past it. `%b` arguments have `\0ddd` octal escapes,
+/// and the format `\ddd`; both accept the other form too.
+fn escape(bytes: &[u8], pos: &mut usize, argument: bool) -> Result<Escape, String> {
+    *pos += 1;
+    let Some(&c) = bytes.get(*pos) else {
+        return Ok(Escape::Byte(b'\\'));
+    };
+    *pos += 1;
+
+    let digits = |pos: &mut usize, radix: u32, max: usize| {
+        let mut value = 0u32;
+        let mut count = 0;
+        while count < max {
+            let Some(digit) = bytes.get(*pos).and_then(|&b| char::from(b).to_digit(radix)) else {
+                break;
+            };
+            value = value * radix + digit;
+            *pos += 1;
+            count += 1;
+        }
+        (value, count)
+    };
+
+    let byte = match c {
+        b'0' if argument => digits(pos, 8, 3).0,
+        b'0'..=b'7' => {
+            *pos -= 1;
+            digits(pos, 8, 3).0
+        }
+        b'x' => match digits(pos, 16, 2) {
+            (_, 0) if argument => return Ok(Escape::Verbatim(b'x')),
+            (_, 0) => return Err(crate::tr!("missing hexadecimal number in escape")),
+            (value, _) => value,
+        },
+        b'\\' => u32::from(b'\\'),
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'c' => return Ok(Escape::Stop),
+        b'e' => 0x1b,
+        b'f' => 0x0c,
+        b'n' => u32::from(b'\n'),
+        b'r' => u32::from(b'\r'),
+        b't' => u32::from(b'\t'),
+        b'v' => 0x0b,
+        c => return Ok(Escape::Verbatim(c)),
+    };
+    // Values past a byte keep their low eight bits
+    Ok(Escape::Byte(byte as u8))
+}
+
+fn parse_count(bytes: &[u8], pos: &mut usize) -> Option<Count> {
+    if bytes.get(*pos) == Some(&b'*') {
+        *pos += 1;
+        return Some(Count::Argument);
+    }
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+        *pos += 1;
+    }
+    let digits = std::str::from_utf8(&bytes[start..*pos]).unwrap_or_default();
+    (!digits.is_empty()).then(|| Count::Fixed(digits.parse().unwrap_or(usize::MAX)))
+}
+
+fn parse_format(format: &[u8]) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut literal = Vec::new();
+    let mut pos = 0;
+    while pos < format.len() {
+        match format[pos] {
+            b'\\' => match escape(format, &mut pos, false)? {
+                Escape::Byte(b) => literal.push(b),
+                Escape::Verbatim(b) => literal.extend([b'\\', b]),
+                Escape::Stop => {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    tokens.push(Token::Stop);
+                    return Ok(tokens);
+                }
+            },
+            b'%' if format.get(pos + 1) == Some(&b'%') => {
+                literal.push(b'%');
+                pos += 2;
+            }
+            b'%' => {
+                let start = pos;
+                pos += 1;
+                let flags_start = pos;
+                while format.get(pos).is_some_and(|b| b"-+ #0".contains(b)) {
+                    pos += 1;
+                }
+                let flags = String::from_utf8_lossy(&format[flags_start..pos]).into_owned();
+                let width = parse_count(format, &mut pos);
+                let precision = if format.get(pos) == Some(&b'.') {
+                    pos += 1;
+                    Some(parse_count(format, &mut pos).unwrap_or(Count::Fixed(0)))
+                } else {
+                    None
+                };
+                match format.get(pos) {
+                    Some(&conversion) if b"diouxXfFeEgGaAcsb".contains(&conversion) => {
+                        pos += 1;
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                        tokens.push(Token::Conversion(Conversion {
+                            flags,
+                            width,
+                            precision,
+                            conversion,
+                        }));
+                    }
+                    _ => {
+                        let end = (pos + 1).min(format.len());
+                        return Err(crate::tr!(
+                            "{}: invalid conversion specification",
+                            String::from_utf8_lossy(&format[start..end])
+                        ));
+                    }
+                }
+            }
+            b => {
+                literal.push(b);
+                pos += 1;
+            }
+        }
+    }
+    tokens.push(Token::Literal(literal));
+    Ok(tokens)
+}
+
+enum Number {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+/// Calls snprintf(3) with a format for a single number.
+fn c_format(format: &str, number: Number) -> Vec<u8> {
+    let Ok(format) = CString::new(format) else {
+        return Vec::new();
+    };
+    let mut buf = vec![0u8; 64];
+    loop {
+        let (ptr, len) = (buf.as_mut_ptr() as *mut c_char, buf.len());
+        // SAFETY: buf has room for len bytes, and the format has a
+        // single conversion, of the type of the value passed.
+        let n = unsafe {
+            match number {
+                Number::Signed(n) => libc::snprintf(ptr, len, format.as_ptr(), n),
+                Number::Unsigned(n) => libc::snprintf(ptr, len, format.as_ptr(), n),
+                Number::Float(n) => libc::snprintf(ptr, len, format.as_ptr(), n),
+            }
+        };
+        let Ok(n) = usize::try_from(n) else {
+            return Vec::new();
+        };
+        if n < len {
+            buf.truncate(n);
+            return buf;
+        }
+        buf.resize(n + 1, 0);
+    }
+}
+
+/// The output of [`printf`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Printed {
+    pub output: Vec<u8>,
+    /// Diagnostics for arguments that aren't valid numbers. Those are
+    /// converted as far as they are valid, and the exit status is then
+    /// nonzero.
+    pub errors: Vec<String>,
+}
+
+struct Printer<'a> {
+    args: &'a [&'a [u8]],
+    next: usize,
+    printed: Printed,
+}
+
+impl<'a> Printer<'a> {
+    fn next_arg(&mut self) -> Option<&'a [u8]> {
+        let arg = self.args.get(self.next).copied();
+        self.next += 1;
+        arg
+    }
+
+    /// Reads a numeric argument for `conversion`; missing arguments are
+    /// zero.
+    fn number(&mut self, conversion: u8) -> Number {
+        let arg = self.next_arg().unwrap_or_default();
+
+        if let Some(rest) = arg.strip_prefix(b"'").or_else(|| arg.strip_prefix(b"\"")) {
+            let value = match std::str::from_utf8(rest) {
+                Ok(s) => s.chars().next().map_or(0, u32::from),
+                Err(e) => match std::str::from_utf8(&rest[..e.valid_up_to()]) {
+                    Ok(s) if !s.is_empty() => s.chars().next().map_or(0, u32::from),
+                    _ => rest.first().copied().map_or(0, u32::from),
+                },
+            };
+            return match conversion {
+                b'd' | b'i' => Number::Signed(value.into()),
+                b'o' | b'u' | b'x' | b'X' => Number::Unsigned(value.into()),
+                _ => Number::Float(value.into()),
+            };
+        }
+
+        let text = String::from_utf8_lossy(arg).into_owned();
+        let Ok(c_arg) = CString::new(arg) else {
+            self.printed
+                .errors
+                .push(crate::tr!("{}: expected a numeric value", text));
+            return Number::Signed(0);
+        };
+        let mut end: *mut c_char = std::ptr::null_mut();
+        errno::set_errno(errno::Errno(0));
+        // SAFETY: c_arg is a C string, end a valid pointer.
+        let number = unsafe {
+            match conversion {
+                b'd' | b'i' => Number::Signed(libc::strtoll(c_arg.as_ptr(), &mut end, 0)),
+                b'o' | b'u' | b'x' | b'X' => {
+                    Number::Unsigned(libc::strtoull(c_arg.as_ptr(), &mut end, 0))
+                }
+                _ => Number::Float(libc::strtod(c_arg.as_ptr(), &mut end)),
+            }
+        };
+        let range_error = errno::errno().0 == libc::ERANGE;
+
+        // SAFETY: end points into c_arg, at the first unconverted byte
+        let rest = unsafe { CStr::from_ptr(end) };
+        let converted = end as usize - c_arg.as_ptr() as usize;
+        if !arg.is_empty() && converted == 0 {
+            self.printed
+                .errors
+                .push(crate::tr!("{}: expected a numeric value", text));
+        } else if !rest.is_empty() {
+            self.printed
+                .errors
+                .push(crate::tr!("{}: value not completely converted", text));
+        } else if range_error {
+            self.printed
+                .errors
+                .push(format!("{}: {}", text, errno::Errno(libc::ERANGE)));
+        }
+        number
+    }
+
+    /// Resolves a `*` width or precision; negative widths left-justify,
+    /// negative precisions are as if there was none.
+    fn count(&mut self, count: Option<Count>) -> Option<i64> {
+        match count? {
+            Count::Fixed(n) => Some(i64::try_from(n).unwrap_or(i64::MAX)),
+            Count::Argument => match self.number(b'd') {
+                Number::Signed(n) => Some(n),
+                _ => None,
+            },
+        }
+    }
+
+    /// Writes a string conversion: at most `precision` bytes, padded
+    /// with spaces to `width`.
+    fn write_string(&mut self, flags: &str, width: Option<i64>, precision: Option<i64>, s: &[u8]) {
+        let s = match precision {
+            Some(precision) if precision >= 0 => &s[..s.len().min(precision as usize)],
+            _ => s,
+        };
+        let left = flags.contains('-') || width.is_some_and(|width| width < 0);
+        let width = width.map_or(0, |width| width.unsigned_abs() as usize);
+        let padding = vec![b' '; width.saturating_sub(s.len())];
+        let output = &mut self.printed.output;
+        if left {
+            output.extend_from_slice(s);
+            output.extend_from_slice(&padding);
+        } else {
+            output.extend_from_slice(&padding);
+            output.extend_from_slice(s);
+        }
+    }
+
+    /// Writes a conversion. Returns false at a `\c` of a `%b` argument,
+    /// after which nothing more is written.
+    fn convert(&mut self, conv: &Conversion) -> bool {
+        let width = self.count(conv.width);
+        let precision = self.count(conv.precision);
+
+        match conv.conversion {
+            b's' => {
+                let arg = self.next_arg().unwrap_or_default();
+                self.write_string(&conv.flags, width, precision, arg);
+            }
+            b'c' => {
+                let arg = self.next_arg().unwrap_or_default();
+                let len = match std::str::from_utf8(arg) {
+                    Ok(s) => s.chars().next().map_or(0, char::len_utf8),
+                    Err(_) => arg.len().min(1),
+                };
+                self.write_string(&conv.flags, width, None, &arg[..len]);
+            }
+            b'b' => {
+                let arg = self.next_arg().unwrap_or_default();
+                let mut s = Vec::with_capacity(arg.len());
+                let mut pos = 0;
+                let mut stop = false;
+                while pos < arg.len() {
+                    if arg[pos] != b'\\' {
+                        s.push(arg[pos]);
+                        pos += 1;
+                        continue;
+                    }
+                    match escape(arg, &mut pos, true) {
+                        Ok(Escape::Byte(b)) => s.push(b),
+                        Ok(Escape::Verbatim(b)) => s.extend([b'\\', b]),
+                        Ok(Escape::Stop) | Err(_) => {
+                            stop = true;
+                            break;
+                        }
+                    }
+                }
+                self.write_string(&conv.flags, width, precision, &s);
+                if stop {
+                    return false;
+                }
+            }
+            conversion => {
+                let mut format = format!("%{}", conv.flags);
+                if let Some(width) = width {
+                    format.push_str(&width.to_string());
+                }
+                if let Some(precision) = precision.filter(|&precision| precision >= 0) {
+                    format.push_str(&format!(".{}", precision));
+                }
+                let number = self.number(conversion);
+                if matches!(number, Number::Signed(_) | Number::Unsigned(_)) {
+                    format.push_str("ll");
+                }
+                format.push(char::from(conversion));
+                let text = c_format(&format, number);
+                self.printed.output.extend_from_slice(&text);
+            }
+        }
+        true
+    }
+}
+
+/// Formats `args` as printf(1) does: the format is reused as long as
+/// arguments remain, and missing arguments are empty strings or zero.
+/// An invalid format is an error, and nothing is written.
+pub fn printf(format: &[u8], args: &[&[u8]]) -> Result<Printed, String> {
+    let tokens = parse_format(format)?;
+    let mut printer = Printer {
+        args,
+        next: 0,
+        printed: Printed::default(),
+    };
+
+    'format: loop {
+        let start = printer.next;
+        for token in &tokens {
+            match token {
+                Token::Literal(literal) => printer.printed.output.extend_from_slice(literal),
+                Token::Conversion(conv) => {
+                    if !printer.convert(conv) {
+                        break 'format;
+                    }
+                }
+                Token::Stop => break 'format,
+            }
+        }
+        if printer.next >= args.len() || printer.next == start {
+            break;
+        }
+    }
+    Ok(printer.printed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(format: &str, args: &[&str]) -> String {
+        let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+        let printed = printf(format.as_bytes(), &args).unwrap();
+        assert_eq!(printed.errors, Vec::<String>::new());
+        String::from_utf8(printed.output).unwrap()
+    }
+
+    #[test]
+    fn test_reuse() {
+        assert_eq!(output("%s\n", &["a", "b", "c"]), "a\nb\nc\n");
+        assert_eq!(output("%s=%d ", &["a", "1", "b"]), "a=1 b=0 ");
+        assert_eq!(output("x\n", &["a"]), "x\n");
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(output("%05d|%-4d|%+d", &["-42", "7", "3"]), "-0042|7   |+3");
+        assert_eq!(
+            output("%x %X %o %#o", &["0x1f", "255", "8", "8"]),
+            "1f FF 10 010"
+        );
+        assert_eq!(output("%.3d %u", &["5", "-1"]), "005 18446744073709551615");
+        assert_eq!(output("%d %d", &["'A", "\"é"]), "65 233");
+        assert_eq!(
+            output("%.2f %e %g", &["3.14159", "1000", "0.5"]),
+            "3.14 1.000000e+03 0.5"
+        );
+        assert_eq!(output("%*d|%-*s|", &["4", "1", "3", "a"]), "   1|a  |");
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            output("%5s|%-5s|%.2s", &["ab", "cd", "efg"]),
+            "   ab|cd   |ef"
+        );
+        assert_eq!(output("%c%c", &["héllo", "é"]), "hé");
+        assert_eq!(output("%b|%s", &["a\\tb\\0101", "\\t"]), "a\tbA|\\t");
+        assert_eq!(output("%b", &["x\\cy"]), "x");
+        assert_eq!(output("a\\cb", &[]), "a");
+        assert_eq!(output("\\101\\x41\\q%%", &[]), "AA\\q%");
+    }
+
+    #[test]
+    fn test_errors() {
+        let printed = printf(b"%d %d|", &[b"12abc", b"x"]).unwrap();
+        assert_eq!(printed.output, b"12 0|");
+        assert_eq!(printed.errors.len(), 2);
+
+        assert!(printf(b"%y", &[]).is_err());
+        assert!(printf(b"abc%", &[]).is_err());
+        assert!(printf(b"\\x", &[]).is_err());
+    }
+}
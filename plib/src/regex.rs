@@ -0,0 +1,219 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! POSIX regular expressions, compiled by regcomp(3) and matched by
+//! regexec(3), so that basic and extended regular expressions mean what
+//! the C library of the system says they mean.
+
+use std::ffi::CStr;
+use std::ops::Range;
+
+use libc::{
+    regcomp, regerror, regex_t, regexec, regfree, regmatch_t, REG_EXTENDED, REG_ICASE, REG_NOTBOL,
+};
+
+use crate::collate::c_prefix;
+
+pub struct Regex {
+    regex: regex_t,
+    /// The number of subexpressions, which libc keeps private in regex_t.
+    groups: usize,
+}
+
+/// Counts the subexpressions of a pattern: `\\(` in a BRE, `(` in an ERE,
+/// but not in bracket expressions.
+fn count_groups(pattern: &[u8], extended: bool) -> usize {
+    let mut groups = 0;
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            b'\\' => {
+                if !extended && pattern.get(i + 1) == Some(&b'(') {
+                    groups += 1;
+                }
+                i += 1;
+            }
+            b'(' if extended => groups += 1,
+            b'[' => {
+                // Skip to the closing ], which may come first as a literal,
+                // and over [:class:], [=equiv=] and [.symbol.]
+                i += 1;
+                if pattern.get(i) == Some(&b'^') {
+                    i += 1;
+                }
+                if pattern.get(i) == Some(&b']') {
+                    i += 1;
+                }
+                while i < pattern.len() && pattern[i] != b']' {
+                    if pattern[i] == b'[' && matches!(pattern.get(i + 1), Some(b':' | b'=' | b'.'))
+                    {
+                        let delim = pattern[i + 1];
+                        i += 2;
+                        while i + 1 < pattern.len()
+                            && !(pattern[i] == delim && pattern[i + 1] == b']')
+                        {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    groups
+}
+
+impl Regex {
+    /// Compiles a basic regular expression.
+    pub fn bre(pattern: &[u8]) -> Result<Self, String> {
+        Self::new(pattern, false, false)
+    }
+
+    /// Compiles an extended regular expression.
+    pub fn ere(pattern: &[u8]) -> Result<Self, String> {
+        Self::new(pattern, true, false)
+    }
+
+    /// Compiles a basic regular expression, or an extended one if
+    /// `extended` is set, which ignores case if `ignore_case` is set.
+    pub fn new(pattern: &[u8], extended: bool, ignore_case: bool) -> Result<Self, String> {
+        let mut cflags = 0;
+        if extended {
+            cflags |= REG_EXTENDED;
+        }
+        if ignore_case {
+            cflags |= REG_ICASE;
+        }
+        let c_pattern = c_prefix(pattern);
+        // SAFETY: regex_t is plain data, initialized by regcomp
+        let mut regex: regex_t = unsafe { std::mem::zeroed() };
+        // SAFETY: c_pattern is a C string
+        let status = unsafe { regcomp(&mut regex, c_pattern.as_ptr(), cflags) };
+        if status == 0 {
+            let groups = count_groups(pattern, extended);
+            return Ok(Regex { regex, groups });
+        }
+
+        let mut message = [0u8; 256];
+        // SAFETY: the buffer has room for the length given, and regerror
+        // NUL-terminates what it writes
+        unsafe { regerror(status, &regex, message.as_mut_ptr().cast(), message.len()) };
+        let message = CStr::from_bytes_until_nul(&message).unwrap_or_default();
+        Err(message.to_string_lossy().into_owned())
+    }
+
+    /// The number of parenthesized subexpressions.
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    /// Finds the leftmost match in `text`, which ends at its first NUL
+    /// byte. Returns the ranges of the match and of each subexpression,
+    /// which are `None` for those that did not take part in it.
+    pub fn captures(&self, text: &[u8]) -> Option<Vec<Option<Range<usize>>>> {
+        self.captures_at(text, 0)
+    }
+
+    /// Like [`Regex::captures`], but finds the leftmost match from offset
+    /// `start`, where `^` doesn't match unless `start` is 0. The ranges
+    /// are offsets into `text`.
+    pub fn captures_at(&self, text: &[u8], start: usize) -> Option<Vec<Option<Range<usize>>>> {
+        let c_text = c_prefix(&text[start..]);
+        let eflags = if start > 0 { REG_NOTBOL } else { 0 };
+        let mut matches = vec![
+            regmatch_t {
+                rm_so: -1,
+                rm_eo: -1
+            };
+            self.groups() + 1
+        ];
+        // SAFETY: the regex is compiled, c_text is a C string, and matches
+        // has room for the number of entries given
+        let status = unsafe {
+            regexec(
+                &self.regex,
+                c_text.as_ptr(),
+                matches.len(),
+                matches.as_mut_ptr(),
+                eflags,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        Some(
+            matches
+                .iter()
+                .map(|m| {
+                    let so = usize::try_from(m.rm_so).ok()?;
+                    let eo = usize::try_from(m.rm_eo).ok()?;
+                    Some(start + so..start + eo)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        self.captures(text).is_some()
+    }
+}
+
+impl Drop for Regex {
+    fn drop(&mut self) {
+        // SAFETY: the regex was compiled by regcomp, and is freed once
+        unsafe { regfree(&mut self.regex) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bre_captures() {
+        let re = Regex::bre(b"a\\(b*\\)c").unwrap();
+        assert_eq!(re.groups(), 1);
+        assert_eq!(re.captures(b"xabbc"), Some(vec![Some(1..5), Some(2..4)]));
+        assert_eq!(re.captures(b"xac"), Some(vec![Some(1..3), Some(2..2)]));
+        assert!(!re.is_match(b"ab"));
+        // + is literal in a BRE
+        assert!(Regex::bre(b"a+").unwrap().is_match(b"a+"));
+    }
+
+    #[test]
+    fn test_ere() {
+        let re = Regex::ere(b"(x)|y+").unwrap();
+        assert_eq!(re.captures(b"yy"), Some(vec![Some(0..2), None]));
+    }
+
+    #[test]
+    fn test_captures_at() {
+        let re = Regex::new(b"^A", false, true).unwrap();
+        assert_eq!(re.captures_at(b"aa", 0), Some(vec![Some(0..1)]));
+        assert_eq!(re.captures_at(b"aa", 1), None);
+        let re = Regex::bre(b"a").unwrap();
+        assert_eq!(re.captures_at(b"aba", 1), Some(vec![Some(2..3)]));
+    }
+
+    #[test]
+    fn test_count_groups() {
+        assert_eq!(count_groups(b"\\(a\\)\\(b\\)", false), 2);
+        assert_eq!(count_groups(b"(a)\\(", false), 1);
+        assert_eq!(count_groups(b"[\\(]x[](][[:alpha:](]", false), 0);
+        assert_eq!(count_groups(b"(a)[(]\\(", true), 1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Regex::bre(b"a\\(").is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Wrappers around sigaction(2), signal masks and a self-pipe, so that
+//! utilities don't need their own unsafe signal code.
+//!
+//! Handlers run in signal context: they may only use async-signal-safe
+//! functions, e.g. store to atomics or write(2).
+
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use libc::c_int;
+
+use crate::fd;
+
+/// A signal handler, as called by the kernel.
+pub type Handler = extern "C" fn(c_int);
+
+/// What happens when a signal is delivered.
+#[derive(Clone, Copy)]
+pub enum Disposition {
+    Default,
+    Ignore,
+    /// Call the handler. Interrupted system calls are restarted.
+    Handler(Handler),
+}
+
+/// Sets the disposition of `signal`.
+pub fn set_disposition(signal: c_int, disposition: Disposition) -> io::Result<()> {
+    let (handler, flags) = match disposition {
+        Disposition::Default => (libc::SIG_DFL, 0),
+        Disposition::Ignore => (libc::SIG_IGN, 0),
+        Disposition::Handler(f) => (f as libc::sighandler_t, libc::SA_RESTART),
+    };
+
+    // SAFETY: the sigaction struct is zero-initialized, which is valid,
+    // and its mask is initialized by sigemptyset.
+    unsafe {
+        let mut action: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        action.sa_sigaction = handler;
+        action.sa_flags = flags;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal, &action, std::ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Installs `handler` for each of `signals`.
+pub fn set_handler(signals: &[c_int], handler: Handler) -> io::Result<()> {
+    for &signal in signals {
+        set_disposition(signal, Disposition::Handler(handler))?;
+    }
+    Ok(())
+}
+
+pub fn ignore(signal: c_int) -> io::Result<()> {
+    set_disposition(signal, Disposition::Ignore)
+}
+
+pub fn set_default(signal: c_int) -> io::Result<()> {
+    set_disposition(signal, Disposition::Default)
+}
+
+/// A set of signals, as used for the signal mask.
+#[derive(Clone, Copy)]
+pub struct SigSet(libc::sigset_t);
+
+impl SigSet {
+    pub fn empty() -> Self {
+        let mut set = MaybeUninit::uninit();
+        // SAFETY: sigemptyset initializes the set.
+        unsafe {
+            libc::sigemptyset(set.as_mut_ptr());
+            SigSet(set.assume_init())
+        }
+    }
+
+    pub fn from_signals(signals: &[c_int]) -> Self {
+        let mut set = Self::empty();
+        for &signal in signals {
+            set.add(signal);
+        }
+        set
+    }
+
+    pub fn add(&mut self, signal: c_int) {
+        // SAFETY: the set is initialized.
+        unsafe { libc::sigaddset(&mut self.0, signal) };
+    }
+
+    pub fn remove(&mut self, signal: c_int) {
+        // SAFETY: the set is initialized.
+        unsafe { libc::sigdelset(&mut self.0, signal) };
+    }
+
+    pub fn contains(&self, signal: c_int) -> bool {
+        // SAFETY: the set is initialized.
+        unsafe { libc::sigismember(&self.0, signal) == 1 }
+    }
+
+    fn change_mask(how: c_int, set: Option<&SigSet>) -> io::Result<SigSet> {
+        let mut old = SigSet::empty();
+        let set = set.map_or(std::ptr::null(), |set| &set.0 as *const _);
+        // SAFETY: both pointers refer to initialized sets or are null.
+        if unsafe { libc::sigprocmask(how, set, &mut old.0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(old)
+    }
+
+    /// The signal mask of the calling thread.
+    pub fn current_mask() -> io::Result<SigSet> {
+        Self::change_mask(libc::SIG_BLOCK, None)
+    }
+
+    /// Adds these signals to the signal mask, returning the previous mask.
+    pub fn block(&self) -> io::Result<SigSet> {
+        Self::change_mask(libc::SIG_BLOCK, Some(self))
+    }
+
+    /// Removes these signals from the signal mask, returning the previous
+    /// mask.
+    pub fn unblock(&self) -> io::Result<SigSet> {
+        Self::change_mask(libc::SIG_UNBLOCK, Some(self))
+    }
+
+    /// Makes this set the signal mask, returning the previous mask. This
+    /// is async-signal-safe, so may be used between fork and exec.
+    pub fn set_mask(&self) -> io::Result<SigSet> {
+        Self::change_mask(libc::SIG_SETMASK, Some(self))
+    }
+
+    /// Waits for a signal with this set temporarily as the signal mask,
+    /// see sigsuspend(2).
+    pub fn suspend(&self) {
+        // SAFETY: the set is initialized. sigsuspend always returns -1
+        // with EINTR once a handler has run.
+        unsafe { libc::sigsuspend(&self.0) };
+    }
+}
+
+/// Write end of the pipe of the installed [`SignalPipe`], or -1.
+static PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn pipe_handler(signal: c_int) {
+    let fd = PIPE_WRITE_FD.load(Ordering::Relaxed);
+    if fd < 0 {
+        return;
+    }
+    let byte = signal as u8;
+    // errno is preserved for the interrupted code
+    let saved_errno = errno::errno();
+    // SAFETY: write(2) is async-signal-safe. If the pipe is full, the
+    // signal is already pending for the reader and dropping it is fine.
+    unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+    errno::set_errno(saved_errno);
+}
+
+fn pipe_nonblocking() -> io::Result<(OwnedFd, OwnedFd)> {
+    let (read, write) = fd::pipe()?;
+    fd::set_nonblocking(&read, true)?;
+    fd::set_nonblocking(&write, true)?;
+    Ok((read, write))
+}
+
+/// Turns signals into readable data on a pipe (the self-pipe trick), so
+/// they can be waited for with poll(2) alongside other descriptors and
+/// handled outside of signal context.
+///
+/// Only one `SignalPipe` may exist at a time.
+pub struct SignalPipe {
+    read: OwnedFd,
+    /// Kept open for the handler, which writes to it by number.
+    _write: OwnedFd,
+    /// The signals reported on the pipe, reset to their default
+    /// disposition when it is dropped.
+    signals: Mutex<Vec<c_int>>,
+}
+
+impl SignalPipe {
+    /// Installs handlers for `signals` that report them on the pipe.
+    pub fn new(signals: &[c_int]) -> io::Result<SignalPipe> {
+        let (read, write) = pipe_nonblocking()?;
+        if PIPE_WRITE_FD
+            .compare_exchange(-1, write.as_raw_fd(), Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a signal pipe is already installed",
+            ));
+        }
+
+        let pipe = SignalPipe {
+            read,
+            _write: write,
+            signals: Mutex::new(signals.to_vec()),
+        };
+        set_handler(signals, pipe_handler)?;
+        Ok(pipe)
+    }
+
+    /// Reports `signal` on the pipe as well, as shells do for signals
+    /// that get trapped after startup.
+    pub fn add(&self, signal: c_int) -> io::Result<()> {
+        set_disposition(signal, Disposition::Handler(pipe_handler))?;
+        let mut signals = self.signals.lock().unwrap_or_else(PoisonError::into_inner);
+        if !signals.contains(&signal) {
+            signals.push(signal);
+        }
+        Ok(())
+    }
+
+    /// Returns the next signal received, or `None` if there is none
+    /// pending.
+    pub fn try_recv(&self) -> io::Result<Option<c_int>> {
+        let mut byte = 0u8;
+        loop {
+            // SAFETY: reads at most one byte into `byte`.
+            let n = unsafe {
+                libc::read(
+                    self.read.as_raw_fd(),
+                    &mut byte as *mut u8 as *mut libc::c_void,
+                    1,
+                )
+            };
+            match n {
+                1 => return Ok(Some(c_int::from(byte))),
+                0 => return Ok(None),
+                _ => {
+                    let e = io::Error::last_os_error();
+                    match e.kind() {
+                        io::ErrorKind::Interrupted => continue,
+                        io::ErrorKind::WouldBlock => return Ok(None),
+                        _ => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits for the next signal.
+    pub fn recv(&self) -> io::Result<c_int> {
+        loop {
+            if let Some(signal) = self.try_recv()? {
+                return Ok(signal);
+            }
+            let mut pollfd = libc::pollfd {
+                fd: self.read.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: pollfd is a valid array of one element.
+            if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() != io::ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+impl AsFd for SignalPipe {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.read.as_fd()
+    }
+}
+
+impl AsRawFd for SignalPipe {
+    fn as_raw_fd(&self) -> RawFd {
+        self.read.as_raw_fd()
+    }
+}
+
+impl Drop for SignalPipe {
+    fn drop(&mut self) {
+        let signals = self
+            .signals
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for &signal in signals.iter() {
+            let _ = set_default(signal);
+        }
+        PIPE_WRITE_FD.store(-1, Ordering::SeqCst);
+    }
+}
+
+/// The signals defined by POSIX, by name without the `SIG` prefix, in the
+/// order `kill -l` lists them.
+pub const SIGNALS: [(&str, c_int); 27] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("SYS", libc::SIGSYS),
+];
+
+/// Names of signals POSIX doesn't define, and other names for those it
+/// does. They are accepted, but not listed.
+const OTHER_SIGNALS: &[(&str, c_int)] = &[
+    ("IOT", libc::SIGABRT),
+    ("IO", libc::SIGIO),
+    ("WINCH", libc::SIGWINCH),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ("POLL", libc::SIGPOLL),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ("PWR", libc::SIGPWR),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ("STKFLT", libc::SIGSTKFLT),
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    ("EMT", libc::SIGEMT),
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    ("INFO", libc::SIGINFO),
+];
+
+/// Looks up a signal by name, with or without the `SIG` prefix and in any
+/// case, or by number. "0" is the null signal, which only checks that a
+/// process could be signaled.
+pub fn signal_number(name: &str) -> Option<c_int> {
+    if let Ok(number) = name.parse::<c_int>() {
+        let valid = number == 0 || signal_name(number).is_some();
+        return valid.then_some(number);
+    }
+    let upper = name.to_ascii_uppercase();
+    let upper = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .chain(OTHER_SIGNALS)
+        .find(|(signame, _)| *signame == upper)
+        .map(|&(_, number)| number)
+}
+
+/// The name of a signal, without the `SIG` prefix.
+pub fn signal_name(number: c_int) -> Option<&'static str> {
+    SIGNALS
+        .iter()
+        .chain(OTHER_SIGNALS)
+        .find(|&&(_, signo)| signo == number)
+        .map(|&(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigset() {
+        let mut set = SigSet::from_signals(&[libc::SIGUSR1, libc::SIGUSR2]);
+        assert!(set.contains(libc::SIGUSR1));
+        assert!(set.contains(libc::SIGUSR2));
+        assert!(!set.contains(libc::SIGTERM));
+        set.remove(libc::SIGUSR1);
+        assert!(!set.contains(libc::SIGUSR1));
+    }
+
+    #[test]
+    fn test_block_and_unblock() {
+        let set = SigSet::from_signals(&[libc::SIGWINCH]);
+        let old = set.block().unwrap();
+        assert!(SigSet::current_mask().unwrap().contains(libc::SIGWINCH));
+        old.set_mask().unwrap();
+        assert_eq!(
+            SigSet::current_mask().unwrap().contains(libc::SIGWINCH),
+            old.contains(libc::SIGWINCH)
+        );
+    }
+
+    #[test]
+    fn test_signal_pipe() {
+        let pipe = SignalPipe::new(&[libc::SIGUSR1]).unwrap();
+        assert!(SignalPipe::new(&[libc::SIGUSR2]).is_err());
+        assert_eq!(pipe.try_recv().unwrap(), None);
+
+        // SAFETY: raise is safe to call; SIGUSR1 is handled by the pipe.
+        unsafe { libc::raise(libc::SIGUSR1) };
+        assert_eq!(pipe.recv().unwrap(), libc::SIGUSR1);
+        assert_eq!(pipe.try_recv().unwrap(), None);
+
+        pipe.add(libc::SIGUSR2).unwrap();
+        errno::set_errno(errno::Errno(libc::ENOENT));
+        // SAFETY: as above, for SIGUSR2.
+        unsafe { libc::raise(libc::SIGUSR2) };
+        assert_eq!(errno::errno().0, libc::ENOENT);
+        assert_eq!(pipe.recv().unwrap(), libc::SIGUSR2);
+    }
+
+    #[test]
+    fn test_signal_names() {
+        assert_eq!(signal_number("TERM"), Some(libc::SIGTERM));
+        assert_eq!(signal_number("sigkill"), Some(libc::SIGKILL));
+        assert_eq!(signal_number("SIGHUP"), Some(libc::SIGHUP));
+        assert_eq!(signal_number("Winch"), Some(libc::SIGWINCH));
+        assert_eq!(signal_number("9"), Some(libc::SIGKILL));
+        assert_eq!(signal_number("0"), Some(0));
+        assert_eq!(signal_number("SIG"), None);
+        assert_eq!(signal_number("BOGUS"), None);
+        assert_eq!(signal_number("-1"), None);
+        assert_eq!(signal_number("1000"), None);
+
+        assert_eq!(signal_name(libc::SIGABRT), Some("ABRT"));
+        assert_eq!(signal_name(libc::SIGWINCH), Some("WINCH"));
+        assert_eq!(signal_name(0), None);
+        for (name, number) in SIGNALS {
+            assert_eq!(signal_number(name), Some(number));
+            assert_eq!(signal_name(number), Some(name));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Parsing of sizes such as `10k` or `2MiB`, as accepted by
+//! split -b, head -c, tail -c and dd.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SizeError {
+    /// The number part is missing or not a decimal number.
+    InvalidNumber,
+    /// The suffix is not one of the recognized multipliers.
+    InvalidSuffix(String),
+    /// The value does not fit in a `u128`.
+    Overflow,
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeError::InvalidNumber => write!(f, "invalid number"),
+            SizeError::InvalidSuffix(suffix) => write!(f, "invalid suffix '{}'", suffix),
+            SizeError::Overflow => write!(f, "value too large"),
+        }
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+/// The multiplier for a size suffix, or `None` if it is not recognized.
+///
+/// `b` is a 512-byte block. `k`, `m`, `g`, `t` and their `KiB`-style
+/// spellings are powers of 1024; `kB`, `MB`, `GB` and `TB` are powers
+/// of 1000.
+pub fn suffix_multiplier(suffix: &str) -> Option<u128> {
+    let multiplier = match suffix {
+        "" => 1,
+        "b" => 512,
+        "k" | "K" | "KiB" => 1 << 10,
+        "m" | "M" | "MiB" => 1 << 20,
+        "g" | "G" | "GiB" => 1 << 30,
+        "t" | "T" | "TiB" => 1 << 40,
+        "kB" | "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return None,
+    };
+    Some(multiplier)
+}
+
+/// Parses a decimal number followed by an optional size suffix, see
+/// [`suffix_multiplier`].
+pub fn parse_size(s: &str) -> Result<u128, SizeError> {
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, suffix) = s.split_at(digits_end);
+    if digits.is_empty() {
+        return Err(SizeError::InvalidNumber);
+    }
+
+    let multiplier =
+        suffix_multiplier(suffix).ok_or_else(|| SizeError::InvalidSuffix(suffix.to_string()))?;
+
+    let mut value: u128 = 0;
+    for digit in digits.bytes() {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add(u128::from(digit - b'0')))
+            .ok_or(SizeError::Overflow)?;
+    }
+
+    value.checked_mul(multiplier).ok_or(SizeError::Overflow)
+}
+
+/// Like [`parse_size`], but also fails with [`SizeError::Overflow`] if the
+/// value does not fit in `T`.
+pub fn parse_size_as<T: TryFrom<u128>>(s: &str) -> Result<T, SizeError> {
+    T::try_from(parse_size(s)?).map_err(|_| SizeError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_numbers() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("42"), Ok(42));
+        assert_eq!(parse_size("007"), Ok(7));
+    }
+
+    #[test]
+    fn test_suffixes() {
+        assert_eq!(parse_size("3b"), Ok(1536));
+        assert_eq!(parse_size("2k"), Ok(2048));
+        assert_eq!(parse_size("2K"), Ok(2048));
+        assert_eq!(parse_size("2KiB"), Ok(2048));
+        assert_eq!(parse_size("2kB"), Ok(2000));
+        assert_eq!(parse_size("1m"), Ok(1 << 20));
+        assert_eq!(parse_size("1MB"), Ok(1_000_000));
+        assert_eq!(parse_size("1g"), Ok(1 << 30));
+        assert_eq!(parse_size("1GiB"), Ok(1 << 30));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse_size(""), Err(SizeError::InvalidNumber));
+        assert_eq!(parse_size("k"), Err(SizeError::InvalidNumber));
+        assert_eq!(parse_size("-1"), Err(SizeError::InvalidNumber));
+        assert_eq!(
+            parse_size("1x"),
+            Err(SizeError::InvalidSuffix(String::from("x")))
+        );
+        assert_eq!(
+            parse_size("1 k"),
+            Err(SizeError::InvalidSuffix(String::from(" k")))
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            parse_size("340282366920938463463374607431768211455"),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            parse_size("340282366920938463463374607431768211456"),
+            Err(SizeError::Overflow)
+        );
+        assert_eq!(
+            parse_size("340282366920938463463374607431768211455k"),
+            Err(SizeError::Overflow)
+        );
+        assert_eq!(parse_size_as::<u64>("16777216T"), Err(SizeError::Overflow));
+        assert_eq!(parse_size_as::<u64>("16777215T"), Ok(16777215 << 40));
+    }
+}
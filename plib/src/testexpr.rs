@@ -0,0 +1,299 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The expressions of test, shared by the test utility and the sh
+//! builtin so that both evaluate alike.
+//!
+//! Up to four arguments are evaluated by the rules POSIX gives for each
+//! number of arguments; longer expressions by precedence, `!` binding
+//! tighter than `-a`, and `-a` tighter than `-o`, with `(` `)` grouping.
+
+use std::cmp::Ordering;
+use std::ffi::CString;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+use crate::collate::strcoll;
+
+fn is_unary_op(s: &str) -> bool {
+    matches!(
+        s,
+        "-b" | "-c"
+            | "-d"
+            | "-e"
+            | "-f"
+            | "-g"
+            | "-h"
+            | "-L"
+            | "-n"
+            | "-p"
+            | "-r"
+            | "-S"
+            | "-s"
+            | "-t"
+            | "-u"
+            | "-w"
+            | "-x"
+            | "-z"
+    )
+}
+
+fn is_binary_op(s: &str) -> bool {
+    matches!(
+        s,
+        "=" | "!="
+            | "<"
+            | ">"
+            | "-eq"
+            | "-ne"
+            | "-lt"
+            | "-gt"
+            | "-ge"
+            | "-le"
+            | "-ef"
+            | "-nt"
+            | "-ot"
+    )
+}
+
+/// Whether the effective user may access `path` as `mode` says.
+fn accessible(path: &str, mode: libc::c_int) -> bool {
+    let Ok(path) = CString::new(path) else {
+        return false;
+    };
+    // SAFETY: path is a C string
+    unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+fn integer(s: &str) -> Result<i64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| crate::tr!("{}: integer expression expected", s))
+}
+
+fn mtime(md: &Metadata) -> (i64, i64) {
+    (md.mtime(), md.mtime_nsec())
+}
+
+struct Evaluator<'a> {
+    args: &'a [String],
+    is_terminal: &'a dyn Fn(i32) -> bool,
+}
+
+impl Evaluator<'_> {
+    fn unary(&self, op: &str, operand: &str) -> Result<bool, String> {
+        let metadata = || fs::metadata(operand).ok();
+        let mode = || metadata().map_or(0, |md| md.mode());
+        let result = match op {
+            "-n" => !operand.is_empty(),
+            "-z" => operand.is_empty(),
+            "-t" => (self.is_terminal)(integer(operand)?.try_into().unwrap_or(-1)),
+            "-h" | "-L" => {
+                fs::symlink_metadata(operand).is_ok_and(|md| md.file_type().is_symlink())
+            }
+            "-r" => accessible(operand, libc::R_OK),
+            "-w" => accessible(operand, libc::W_OK),
+            "-x" => accessible(operand, libc::X_OK),
+            "-e" => metadata().is_some(),
+            "-b" => metadata().is_some_and(|md| md.file_type().is_block_device()),
+            "-c" => metadata().is_some_and(|md| md.file_type().is_char_device()),
+            "-d" => metadata().is_some_and(|md| md.is_dir()),
+            "-f" => metadata().is_some_and(|md| md.is_file()),
+            "-p" => metadata().is_some_and(|md| md.file_type().is_fifo()),
+            "-S" => metadata().is_some_and(|md| md.file_type().is_socket()),
+            "-s" => metadata().is_some_and(|md| md.len() > 0),
+            "-g" => mode() & 0o2000 != 0,
+            "-u" => mode() & 0o4000 != 0,
+            _ => return Err(crate::tr!("{}: unary operator expected", op)),
+        };
+        Ok(result)
+    }
+
+    fn binary(&self, left: &str, op: &str, right: &str) -> Result<bool, String> {
+        let result = match op {
+            "=" => left == right,
+            "!=" => left != right,
+            "<" => strcoll(left.as_bytes(), right.as_bytes()) == Ordering::Less,
+            ">" => strcoll(left.as_bytes(), right.as_bytes()) == Ordering::Greater,
+            "-eq" => integer(left)? == integer(right)?,
+            "-ne" => integer(left)? != integer(right)?,
+            "-lt" => integer(left)? < integer(right)?,
+            "-gt" => integer(left)? > integer(right)?,
+            "-ge" => integer(left)? >= integer(right)?,
+            "-le" => integer(left)? <= integer(right)?,
+            _ => {
+                let (left, right) = (fs::metadata(left).ok(), fs::metadata(right).ok());
+                match (op, left, right) {
+                    ("-ef", Some(left), Some(right)) => {
+                        left.dev() == right.dev() && left.ino() == right.ino()
+                    }
+                    ("-nt", Some(left), Some(right)) => mtime(&left) > mtime(&right),
+                    ("-nt", Some(_), None) => true,
+                    ("-ot", Some(left), Some(right)) => mtime(&left) < mtime(&right),
+                    ("-ot", None, Some(_)) => true,
+                    ("-ef" | "-nt" | "-ot", _, _) => false,
+                    _ => return Err(crate::tr!("{}: binary operator expected", op)),
+                }
+            }
+        };
+        Ok(result)
+    }
+
+    /// Evaluates an expression of up to four arguments.
+    fn short(&self, words: &[String]) -> Result<bool, String> {
+        let args: Vec<&str> = words.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            [] => Ok(false),
+            [s] => Ok(!s.is_empty()),
+            ["!", s] => Ok(s.is_empty()),
+            [op, operand] => self.unary(op, operand),
+            [left, op, right] if is_binary_op(op) => self.binary(left, op, right),
+            ["!", _, _] | ["!", _, _, _] => Ok(!self.short(&words[1..])?),
+            ["(", s, ")"] => Ok(!s.is_empty()),
+            ["(", op, operand, ")"] => self.unary(op, operand),
+            _ => Evaluator {
+                args: words,
+                is_terminal: self.is_terminal,
+            }
+            .long(),
+        }
+    }
+
+    /// Evaluates an expression by precedence.
+    fn long(&self) -> Result<bool, String> {
+        let mut pos = 0;
+        let result = self.or(&mut pos)?;
+        match self.args.get(pos) {
+            None => Ok(result),
+            Some(arg) => Err(crate::tr!("{}: unexpected argument", arg)),
+        }
+    }
+
+    fn or(&self, pos: &mut usize) -> Result<bool, String> {
+        let mut result = self.and(pos)?;
+        while self.args.get(*pos).is_some_and(|arg| arg == "-o") {
+            *pos += 1;
+            // Both sides are evaluated, for their errors
+            result |= self.and(pos)?;
+        }
+        Ok(result)
+    }
+
+    fn and(&self, pos: &mut usize) -> Result<bool, String> {
+        let mut result = self.not(pos)?;
+        while self.args.get(*pos).is_some_and(|arg| arg == "-a") {
+            *pos += 1;
+            result &= self.not(pos)?;
+        }
+        Ok(result)
+    }
+
+    fn not(&self, pos: &mut usize) -> Result<bool, String> {
+        if self.args.get(*pos).is_some_and(|arg| arg == "!") && *pos + 1 < self.args.len() {
+            *pos += 1;
+            return Ok(!self.not(pos)?);
+        }
+        self.primary(pos)
+    }
+
+    fn primary(&self, pos: &mut usize) -> Result<bool, String> {
+        let args = self.args;
+        let Some(arg) = args.get(*pos) else {
+            return Err(crate::tr!("argument expected"));
+        };
+
+        if let (Some(op), Some(right)) = (args.get(*pos + 1), args.get(*pos + 2)) {
+            if is_binary_op(op) {
+                *pos += 3;
+                return self.binary(arg, op, right);
+            }
+        }
+        if arg == "(" && *pos + 1 < args.len() {
+            *pos += 1;
+            let result = self.or(pos)?;
+            if args.get(*pos).map(String::as_str) != Some(")") {
+                return Err(crate::tr!("')' expected"));
+            }
+            *pos += 1;
+            return Ok(result);
+        }
+        if is_unary_op(arg) {
+            if let Some(operand) = args.get(*pos + 1) {
+                *pos += 2;
+                return self.unary(arg, operand);
+            }
+        }
+        *pos += 1;
+        Ok(!arg.is_empty())
+    }
+}
+
+/// Evaluates the arguments of test, without the `]` of `[`. `-t` asks
+/// `is_terminal` whether a file descriptor is a terminal. Errors are for
+/// expressions that aren't valid, for which test exits with status 2.
+pub fn test(args: &[String], is_terminal: &dyn Fn(i32) -> bool) -> Result<bool, String> {
+    let evaluator = Evaluator { args, is_terminal };
+    evaluator.short(args)
+}
+
+/// Whether a file descriptor of the process is a terminal.
+pub fn isatty(fd: i32) -> bool {
+    // SAFETY: isatty accepts any integer
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(args: &[&str]) -> Result<bool, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        test(&args, &|_| false)
+    }
+
+    #[test]
+    fn test_short() {
+        assert_eq!(eval(&[]), Ok(false));
+        assert_eq!(eval(&["-n"]), Ok(true));
+        assert_eq!(eval(&["!", ""]), Ok(true));
+        assert_eq!(eval(&["-z", ""]), Ok(true));
+        assert_eq!(eval(&["!", "-z", ""]), Ok(false));
+        assert_eq!(eval(&["(", "x", ")"]), Ok(true));
+        assert_eq!(eval(&["!", "=", "!"]), Ok(true));
+        assert_eq!(eval(&["(", "-n", "", ")"]), Ok(false));
+        assert_eq!(eval(&["!", "1", "-lt", "2"]), Ok(false));
+        assert!(eval(&["-q", "x"]).is_err());
+        assert!(eval(&["a", "b", "c"]).is_err());
+    }
+
+    #[test]
+    fn test_long() {
+        assert_eq!(eval(&["a", "=", "a", "-a", "b", "=", "c"]), Ok(false));
+        assert_eq!(eval(&["a", "=", "a", "-o", "b", "=", "c"]), Ok(true));
+        assert_eq!(eval(&["", "-o", "x", "-a", ""]), Ok(false));
+        assert_eq!(eval(&["!", "", "-a", "(", "1", "-eq", "1", ")"]), Ok(true));
+        assert!(eval(&["(", "a", "=", "a", "-a", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(eval(&[" 3 ", "-gt", "-2"]), Ok(true));
+        assert!(eval(&["3a", "-gt", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_files() {
+        assert_eq!(eval(&["-d", "/"]), Ok(true));
+        assert_eq!(eval(&["-f", "/"]), Ok(false));
+        assert_eq!(eval(&["-e", "/nonexistent/path"]), Ok(false));
+        assert_eq!(eval(&["/", "-ef", "/."]), Ok(true));
+        assert_eq!(eval(&["/", "-nt", "/nonexistent/path"]), Ok(true));
+        assert_eq!(eval(&["/nonexistent/path", "-ot", "/"]), Ok(true));
+    }
+}
@@ -7,10 +7,18 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::Write;
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct TestPlan {
     pub cmd: String,
@@ -30,83 +38,552 @@ pub struct TestPlanU8 {
     pub expected_exit_code: i32,
 }
 
-pub fn run_test_base(cmd: &str, args: &Vec<String>, stdin_data: &[u8]) -> Output {
-    let relpath = if cfg!(debug_assertions) {
-        format!("target/debug/{}", cmd)
+/// Standard input for a test, overriding the plan's `stdin_data`.
+pub enum TestStdin {
+    /// Raw bytes, which need not be valid UTF-8.
+    Bytes(Vec<u8>),
+    /// A fixture file, relative to the package directory. The file itself
+    /// becomes the command's stdin, so it is seekable.
+    File(PathBuf),
+}
+
+/// How the output of a command is compared with the expected output of
+/// a [`TestPlan`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputMatch {
+    /// The output must equal the expected output.
+    #[default]
+    Exact,
+    /// The expected output must occur somewhere in the output.
+    Contains,
+    /// The expected output is a regular expression that must match
+    /// somewhere in the output; anchor it with `^` and `$` as needed.
+    Regex,
+}
+
+impl OutputMatch {
+    fn assert_matches(self, stream: &str, actual: &str, expected: &str) {
+        match self {
+            OutputMatch::Exact => assert_eq!(actual, expected, "unexpected {stream}"),
+            OutputMatch::Contains => assert!(
+                actual.contains(expected),
+                "{stream} {actual:?} does not contain {expected:?}"
+            ),
+            OutputMatch::Regex => {
+                let re = Regex::new(expected)
+                    .unwrap_or_else(|e| panic!("invalid {stream} regex {expected:?}: {e}"));
+                assert!(
+                    re.is_match(actual),
+                    "{stream} {actual:?} does not match {expected:?}"
+                );
+            }
+        }
+    }
+}
+
+/// How long a test command may run before it is killed, unless
+/// [`TestOptions::timeout`] says otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long to wait for the rest of the output of a command that timed
+/// out, or exited while processes it started still hold its output open.
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Less common test settings, see [`run_test_with_options`].
+#[derive(Default)]
+pub struct TestOptions {
+    pub stdin: Option<TestStdin>,
+    /// On expiry the command is killed and the test fails, showing the
+    /// output captured so far. Defaults to [`DEFAULT_TIMEOUT`].
+    pub timeout: Option<Duration>,
+    /// Variables set in the command's environment, on top of the
+    /// environment inherited from the test process.
+    pub env: Vec<(String, String)>,
+    /// Working directory of the command, instead of the package directory.
+    pub cwd: Option<PathBuf>,
+    /// Applied to stdout and stderr before they are compared, to mask
+    /// output that legitimately varies between runs, such as timestamps.
+    pub normalize: Option<fn(&str) -> String>,
+    pub stdout_match: OutputMatch,
+    pub stderr_match: OutputMatch,
+    /// The command is expected to be terminated by this signal, instead
+    /// of exiting with the plan's exit code.
+    pub expected_signal: Option<i32>,
+}
+
+impl TestOptions {
+    /// Adds `key=value` to the command's environment.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+/// Path of the workspace binary `cmd`, as already built by cargo for the
+/// running test.
+///
+/// Test executables live in `<target dir>/<profile>/deps`, next to the
+/// binaries in `<target dir>/<profile>`, so this works for any profile and
+/// `CARGO_TARGET_DIR` without spawning cargo.
+pub fn test_bin_path(cmd: &str) -> PathBuf {
+    let exe = std::env::current_exe().expect("failed to locate test executable");
+    let dir = exe
+        .parent()
+        .expect("test executable has no parent directory");
+    let dir = if dir.ends_with("deps") {
+        dir.parent().unwrap()
     } else {
-        format!("target/release/{}", cmd)
+        dir
+    };
+    dir.join(cmd)
+}
+
+pub fn run_test_base(cmd: &str, args: &Vec<String>, stdin_data: &[u8]) -> Output {
+    run_test_base_with_options(cmd, args, stdin_data, &TestOptions::default())
+}
+
+pub fn run_test_base_with_options(
+    cmd: &str,
+    args: &Vec<String>,
+    stdin_data: &[u8],
+    options: &TestOptions,
+) -> Output {
+    let test_bin_path = test_bin_path(cmd);
+
+    let (stdin, stdin_data) = match &options.stdin {
+        None => (Stdio::piped(), stdin_data),
+        Some(TestStdin::Bytes(data)) => (Stdio::piped(), data.as_slice()),
+        Some(TestStdin::File(path)) => {
+            let file = File::open(path)
+                .unwrap_or_else(|e| panic!("failed to open {}: {}", path.display(), e));
+            (Stdio::from(file), &[][..])
+        }
     };
-    let test_bin_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap() // Move up to the workspace root from the current package directory
-        .join(relpath); // Adjust the path to the binary
 
     let mut command = Command::new(test_bin_path);
+    command.envs(options.env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = &options.cwd {
+        command.current_dir(cwd);
+    }
     let mut child = command
         .args(args)
-        .stdin(Stdio::piped())
+        .stdin(stdin)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .unwrap_or_else(|_| panic!("failed to spawn command {cmd}"));
 
-    // Separate the mutable borrow of stdin from the child process
-    if let Some(mut stdin) = child.stdin.take() {
-        let chunk_size = 1024; // Arbitrary chunk size, adjust if needed
-        for chunk in stdin_data.chunks(chunk_size) {
-            // Write each chunk
-            if let Err(e) = stdin.write_all(chunk) {
-                eprintln!("Error writing to stdin: {}", e);
-                break;
-            }
-            // Flush after writing each chunk
-            if let Err(e) = stdin.flush() {
-                eprintln!("Error flushing stdin: {}", e);
-                break;
+    // Feed stdin from a separate thread, so that a child which stops
+    // reading cannot block us past the timeout
+    let writer = child.stdin.take().map(|mut stdin| {
+        let stdin_data = stdin_data.to_vec();
+        thread::spawn(move || {
+            let chunk_size = 1024; // Arbitrary chunk size, adjust if needed
+            for chunk in stdin_data.chunks(chunk_size) {
+                // Write each chunk
+                if let Err(e) = stdin.write_all(chunk) {
+                    eprintln!("Error writing to stdin: {}", e);
+                    break;
+                }
+                // Flush after writing each chunk
+                if let Err(e) = stdin.flush() {
+                    eprintln!("Error flushing stdin: {}", e);
+                    break;
+                }
+
+                // Sleep briefly to avoid CPU spinning
+                thread::sleep(Duration::from_millis(10));
             }
+            // stdin is dropped here, closing the pipe
+        })
+    });
 
-            // Sleep briefly to avoid CPU spinning
-            thread::sleep(Duration::from_millis(10));
+    let stdout = read_in_background(child.stdout.take().unwrap());
+    let stderr = read_in_background(child.stderr.take().unwrap());
+
+    let timeout = options.timeout.unwrap_or(DEFAULT_TIMEOUT);
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("failed to wait for child") {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            // Processes the command left behind may still hold the pipes
+            // open, so only wait a little for the end of its output
+            let stdout = stdout.recv_timeout(OUTPUT_GRACE).unwrap_or_default();
+            let stderr = stderr.recv_timeout(OUTPUT_GRACE).unwrap_or_default();
+            panic!(
+                "command {cmd} timed out after {:?}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+                timeout,
+                String::from_utf8_lossy(&stdout),
+                String::from_utf8_lossy(&stderr)
+            );
         }
-        // Explicitly drop stdin to close the pipe
-        drop(stdin);
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    if let Some(writer) = writer {
+        writer.join().unwrap();
+    }
+
+    let output_deadline = deadline.max(Instant::now() + OUTPUT_GRACE);
+    let collect = |receiver: Receiver<Vec<u8>>| {
+        receiver
+            .recv_timeout(output_deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_else(|_| {
+                panic!("command {cmd} exited, but its output was still open after {timeout:?}")
+            })
+    };
+    Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
     }
+}
 
-    // Ensure we wait for the process to complete after writing to stdin
-    let output = child.wait_with_output().expect("failed to wait for child");
-    output
+/// Reads `reader` to the end from another thread, and sends the result.
+fn read_in_background<R: Read + Send + 'static>(mut reader: R) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = reader.read_to_end(&mut buf);
+        let _ = sender.send(buf);
+    });
+    receiver
 }
 
 pub fn run_test(plan: TestPlan) {
-    let output = run_test_base(&plan.cmd, &plan.args, plan.stdin_data.as_bytes());
+    run_test_with_options(plan, TestOptions::default());
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    assert_eq!(stdout, plan.expected_out);
+pub fn run_test_with_options(plan: TestPlan, options: TestOptions) {
+    let output =
+        run_test_base_with_options(&plan.cmd, &plan.args, plan.stdin_data.as_bytes(), &options);
 
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    assert_eq!(stderr, plan.expected_err);
+    let (stdout, stderr) = normalized_output(&output, &options);
+    options
+        .stdout_match
+        .assert_matches("stdout", &stdout, &plan.expected_out);
+    options
+        .stderr_match
+        .assert_matches("stderr", &stderr, &plan.expected_err);
 
-    assert_eq!(output.status.code(), Some(plan.expected_exit_code));
-    if plan.expected_exit_code == 0 {
-        assert!(output.status.success());
-    }
+    assert_exit_status(&output, plan.expected_exit_code, &options);
 }
 
 pub fn run_test_u8(plan: TestPlanU8) {
-    let output = run_test_base(&plan.cmd, &plan.args, &plan.stdin_data);
+    run_test_u8_with_options(plan, TestOptions::default());
+}
+
+pub fn run_test_u8_with_options(plan: TestPlanU8, options: TestOptions) {
+    let output = run_test_base_with_options(&plan.cmd, &plan.args, &plan.stdin_data, &options);
 
     assert_eq!(output.stdout, plan.expected_out);
 
     assert_eq!(output.stderr, plan.expected_err);
 
-    assert_eq!(output.status.code(), Some(plan.expected_exit_code));
-    if plan.expected_exit_code == 0 {
-        assert!(output.status.success());
-    }
+    assert_exit_status(&output, plan.expected_exit_code, &options);
 }
 
 pub fn run_test_with_checker<F: FnMut(&TestPlan, &Output)>(plan: TestPlan, mut checker: F) {
     let output = run_test_base(&plan.cmd, &plan.args, plan.stdin_data.as_bytes());
     checker(&plan, &output);
 }
+
+fn normalized_output(output: &Output, options: &TestOptions) -> (String, String) {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match options.normalize {
+        Some(normalize) => (normalize(&stdout), normalize(&stderr)),
+        None => (stdout.into_owned(), stderr.into_owned()),
+    }
+}
+
+/// Environment variable which, when set to `1`, makes [`run_test_golden`]
+/// rewrite the golden files from the actual output instead of comparing.
+pub const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// Runs `cmd` and compares its stdout with the golden file `<golden>.out`
+/// and its stderr with `<golden>.err`. A missing golden file stands for
+/// empty output. Paths are relative to the package directory.
+///
+/// Run the tests with `UPDATE_GOLDEN=1` to create or refresh the golden
+/// files after an intended change in output, then review the diff.
+pub fn run_test_golden(
+    cmd: &str,
+    args: &[&str],
+    expected_exit_code: i32,
+    golden: impl AsRef<Path>,
+    options: TestOptions,
+) {
+    let args = args.iter().map(|s| s.to_string()).collect();
+    let output = run_test_base_with_options(cmd, &args, &[], &options);
+    let (stdout, stderr) = normalized_output(&output, &options);
+
+    let golden = golden.as_ref();
+    let out_path = golden.with_extension("out");
+    let err_path = golden.with_extension("err");
+
+    if std::env::var(UPDATE_GOLDEN_VAR).is_ok_and(|v| v == "1") {
+        update_golden_file(&out_path, &stdout);
+        update_golden_file(&err_path, &stderr);
+    } else {
+        assert_eq!(
+            stdout,
+            read_golden_file(&out_path),
+            "stdout differs from {} (rerun with {UPDATE_GOLDEN_VAR}=1 to update)",
+            out_path.display()
+        );
+        assert_eq!(
+            stderr,
+            read_golden_file(&err_path),
+            "stderr differs from {} (rerun with {UPDATE_GOLDEN_VAR}=1 to update)",
+            err_path.display()
+        );
+    }
+
+    assert_exit_status(&output, expected_exit_code, &options);
+}
+
+fn read_golden_file(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => panic!("failed to read {}: {}", path.display(), e),
+    }
+}
+
+fn update_golden_file(path: &Path, content: &str) {
+    let result = if content.is_empty() {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        }
+    } else {
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, content))
+    };
+    result.unwrap_or_else(|e| panic!("failed to update {}: {}", path.display(), e));
+}
+
+fn assert_exit_status(output: &Output, expected_exit_code: i32, options: &TestOptions) {
+    let signal = output.status.signal();
+    match options.expected_signal {
+        Some(expected) => assert_eq!(
+            signal,
+            Some(expected),
+            "expected termination by signal {expected}, got {}",
+            output.status
+        ),
+        None => {
+            assert_eq!(
+                signal, None,
+                "unexpected termination by signal, {}",
+                output.status
+            );
+            assert_eq!(output.status.code(), Some(expected_exit_code));
+            if expected_exit_code == 0 {
+                assert!(output.status.success());
+            }
+        }
+    }
+}
+
+enum FixtureKind {
+    File(Vec<u8>),
+    Dir,
+    Symlink(PathBuf),
+}
+
+struct FixtureEntry {
+    path: PathBuf,
+    kind: FixtureKind,
+    mode: Option<u32>,
+    mtime: Option<i64>,
+}
+
+/// Describes a directory tree for a test, created by
+/// [`FixtureBuilder::build`] in a fresh temporary directory:
+///
+/// ```ignore
+/// let fixture = Fixture::builder("find_newer")
+///     .file("old.txt", "old")
+///     .mtime(1_000_000_000)
+///     .dir("sub")
+///     .mode(0o700)
+///     .symlink("sub/link", "../old.txt")
+///     .build();
+/// ```
+///
+/// Parent directories are created as needed. [`FixtureBuilder::mode`] and
+/// [`FixtureBuilder::mtime`] apply to the entry added last; they are set
+/// once the whole tree exists, so a read-only directory can still have
+/// contents.
+pub struct FixtureBuilder {
+    name: String,
+    entries: Vec<FixtureEntry>,
+}
+
+impl FixtureBuilder {
+    fn push(mut self, path: &str, kind: FixtureKind) -> Self {
+        self.entries.push(FixtureEntry {
+            path: PathBuf::from(path),
+            kind,
+            mode: None,
+            mtime: None,
+        });
+        self
+    }
+
+    fn last(&mut self) -> &mut FixtureEntry {
+        self.entries
+            .last_mut()
+            .expect("fixture attribute given before any entry")
+    }
+
+    pub fn file(self, path: &str, contents: impl AsRef<[u8]>) -> Self {
+        self.push(path, FixtureKind::File(contents.as_ref().to_vec()))
+    }
+
+    pub fn dir(self, path: &str) -> Self {
+        self.push(path, FixtureKind::Dir)
+    }
+
+    pub fn symlink(self, path: &str, target: &str) -> Self {
+        self.push(path, FixtureKind::Symlink(PathBuf::from(target)))
+    }
+
+    /// Permission bits of the last entry. Not applicable to symlinks.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.last().mode = Some(mode);
+        self
+    }
+
+    /// Modification (and access) time of the last entry, in seconds
+    /// since the Epoch.
+    pub fn mtime(mut self, secs: i64) -> Self {
+        self.last().mtime = Some(secs);
+        self
+    }
+
+    pub fn build(self) -> Fixture {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "posixutils-{}-{}-{}",
+            self.name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)
+            .unwrap_or_else(|e| panic!("failed to create {}: {}", root.display(), e));
+
+        for entry in &self.entries {
+            let path = root.join(&entry.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            let result = match &entry.kind {
+                FixtureKind::File(contents) => fs::write(&path, contents),
+                FixtureKind::Dir => fs::create_dir_all(&path),
+                FixtureKind::Symlink(target) => std::os::unix::fs::symlink(target, &path),
+            };
+            result.unwrap_or_else(|e| panic!("failed to create {}: {}", path.display(), e));
+        }
+
+        // Deepest first, as changing a directory's contents changes its
+        // mtime and a mode may make it unwritable
+        let mut entries = self.entries;
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.path.components().count()));
+        for entry in &entries {
+            let path = root.join(&entry.path);
+            if let Some(mode) = entry.mode {
+                fs::set_permissions(&path, fs::Permissions::from_mode(mode))
+                    .unwrap_or_else(|e| panic!("failed to chmod {}: {}", path.display(), e));
+            }
+            if let Some(secs) = entry.mtime {
+                set_mtime(&path, secs);
+            }
+        }
+
+        Fixture {
+            root,
+            protected: entries
+                .into_iter()
+                .filter(|entry| matches!(entry.kind, FixtureKind::Dir) && entry.mode.is_some())
+                .map(|entry| entry.path)
+                .collect(),
+        }
+    }
+}
+
+fn set_mtime(path: &Path, secs: i64) {
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+    let time = libc::timespec {
+        tv_sec: libc::time_t::try_from(secs).expect("mtime out of range"),
+        tv_nsec: 0,
+    };
+    let times = [time, time];
+    // SAFETY: c_path is a C string and times holds two timespecs.
+    let ret = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if ret != 0 {
+        panic!(
+            "failed to set mtime of {}: {}",
+            path.display(),
+            io::Error::last_os_error()
+        );
+    }
+}
+
+/// A temporary directory tree, removed when dropped.
+pub struct Fixture {
+    root: PathBuf,
+    /// Directories whose mode was set, which may need permissions
+    /// restored before they can be removed.
+    protected: Vec<PathBuf>,
+}
+
+impl Fixture {
+    /// Starts describing a fixture; `name` becomes part of the directory
+    /// name, to tell fixtures apart.
+    pub fn builder(name: &str) -> FixtureBuilder {
+        FixtureBuilder {
+            name: name.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// The directory containing the tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of an entry of the tree.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Path of an entry of the tree, as a string for command arguments.
+    pub fn arg(&self, path: &str) -> String {
+        self.path(path).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        for path in &self.protected {
+            let _ = fs::set_permissions(self.root.join(path), fs::Permissions::from_mode(0o700));
+        }
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
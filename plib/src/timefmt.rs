@@ -0,0 +1,192 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Time formatting with strftime(3), so that day and month names and the
+//! `%c`, `%x` and `%X` formats follow LC_TIME, once the locale is set up
+//! by [`crate::i18n::init`].
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libc::c_char;
+
+extern "C" {
+    fn tzset();
+}
+
+/// Seconds since the Epoch, rounded down.
+pub fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => {
+            let d = e.duration();
+            -(d.as_secs() as i64) - i64::from(d.subsec_nanos() > 0)
+        }
+    }
+}
+
+/// A broken-down time, as from localtime(3).
+#[derive(Clone, Copy)]
+pub struct Tm(libc::tm);
+
+impl Tm {
+    /// The time in the time zone given by TZ. `None` if it can't be
+    /// represented.
+    pub fn local(t: libc::time_t) -> Option<Tm> {
+        let mut tm = MaybeUninit::uninit();
+        // SAFETY: both pointers are valid; tzset makes sure changes to
+        // TZ are picked up, which localtime_r need not do.
+        unsafe {
+            tzset();
+            if libc::localtime_r(&t, tm.as_mut_ptr()).is_null() {
+                return None;
+            }
+            Some(Tm(tm.assume_init()))
+        }
+    }
+
+    /// The time in UTC, named "UTC" by `%Z` as POSIX requires of date -u.
+    pub fn utc(t: libc::time_t) -> Option<Tm> {
+        let mut tm = MaybeUninit::uninit();
+        // SAFETY: both pointers are valid.
+        let mut tm = unsafe {
+            if libc::gmtime_r(&t, tm.as_mut_ptr()).is_null() {
+                return None;
+            }
+            tm.assume_init()
+        };
+        tm.tm_zone = c"UTC".as_ptr();
+        Some(Tm(tm))
+    }
+
+    /// Seconds since the Epoch. Unlike mktime(3), this doesn't assume
+    /// the time is in the time zone given by TZ.
+    // tm_gmtoff is a C long, which is 32 bits wide on some targets
+    #[allow(clippy::useless_conversion)]
+    pub fn seconds(&self) -> i64 {
+        let tm = &self.0;
+        // Days since the Epoch of the civil date, counting from March so
+        // that leap days end the year
+        let (year, month) = (i64::from(tm.tm_year) + 1900, i64::from(tm.tm_mon) + 1);
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(tm.tm_mday) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+
+        days * 86400
+            + i64::from(tm.tm_hour) * 3600
+            + i64::from(tm.tm_min) * 60
+            + i64::from(tm.tm_sec)
+            - i64::from(tm.tm_gmtoff)
+    }
+
+    /// Formats the time as strftime(3) does, with the `%s` extension for
+    /// seconds since the Epoch. The format ends at a NUL character, as it
+    /// would in C.
+    pub fn format(&self, fmt: &str) -> String {
+        let fmt = fmt.split('\0').next().unwrap_or_default();
+        // strftime computes %s with mktime, which is wrong for times not
+        // in the local time zone
+        let mut expanded = String::with_capacity(fmt.len());
+        let mut chars = fmt.chars();
+        while let Some(c) = chars.next() {
+            expanded.push(c);
+            if c == '%' {
+                match chars.next() {
+                    Some('s') => {
+                        expanded.pop();
+                        expanded.push_str(&self.seconds().to_string());
+                    }
+                    Some(c) => expanded.push(c),
+                    None => {}
+                }
+            }
+        }
+        // strftime returns 0 both for an empty result and when the buffer
+        // is too small; a trailing space tells them apart.
+        let fmt = CString::new(format!("{} ", expanded)).unwrap();
+        let mut buf: Vec<u8> = vec![0; 128 + fmt.as_bytes().len() * 4];
+        loop {
+            // SAFETY: buf has room for buf.len() bytes, fmt is a C string
+            // and self.0 a valid tm.
+            let n = unsafe {
+                libc::strftime(
+                    buf.as_mut_ptr() as *mut c_char,
+                    buf.len(),
+                    fmt.as_ptr(),
+                    &self.0,
+                )
+            };
+            if n > 0 {
+                buf.truncate(n - 1);
+                return String::from_utf8_lossy(&buf).into_owned();
+            }
+            let len = buf.len() * 2;
+            buf.resize(len, 0);
+        }
+    }
+}
+
+/// Formats `time` in the local time zone. Times that can't be broken
+/// down are written as seconds since the Epoch.
+pub fn format_local(fmt: &str, time: SystemTime) -> String {
+    let secs = unix_seconds(time);
+    match libc::time_t::try_from(secs).ok().and_then(Tm::local) {
+        Some(tm) => tm.format(fmt),
+        None => secs.to_string(),
+    }
+}
+
+/// Formats `time` in UTC. Times that can't be broken down are written as
+/// seconds since the Epoch.
+pub fn format_utc(fmt: &str, time: SystemTime) -> String {
+    let secs = unix_seconds(time);
+    match libc::time_t::try_from(secs).ok().and_then(Tm::utc) {
+        Some(tm) => tm.format(fmt),
+        None => secs.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format() {
+        let tm = Tm::utc(0).unwrap();
+        assert_eq!(
+            tm.format("%a %b %e %H:%M:%S %Z %Y"),
+            "Thu Jan  1 00:00:00 UTC 1970"
+        );
+        assert_eq!(tm.format(""), "");
+        assert_eq!(tm.format("%%%d%%"), "%01%");
+        assert_eq!(tm.format("%Y\0ignored"), "1970");
+        assert_eq!(tm.format("%s %%s"), "0 %s");
+        assert_eq!(Tm::utc(951827696).unwrap().format("%s %j"), "951827696 060");
+        assert_eq!(Tm::utc(-86401).unwrap().format("%s"), "-86401");
+
+        let long = "%A ".repeat(100);
+        assert_eq!(tm.format(&long), "Thursday ".repeat(100));
+    }
+
+    #[test]
+    fn test_unix_seconds() {
+        assert_eq!(unix_seconds(UNIX_EPOCH + Duration::from_millis(1500)), 1);
+        assert_eq!(unix_seconds(UNIX_EPOCH - Duration::from_millis(500)), -1);
+        assert_eq!(unix_seconds(UNIX_EPOCH - Duration::from_secs(2)), -2);
+        assert_eq!(
+            format_utc("%Y-%m-%d %H:%M:%S", UNIX_EPOCH - Duration::from_secs(1)),
+            "1969-12-31 23:59:59"
+        );
+    }
+}
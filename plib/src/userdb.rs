@@ -0,0 +1,231 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Lookups in the user and group databases, i.e. getpwnam(3) and
+//! friends.
+//!
+//! Results, including failed lookups, are cached per thread, as
+//! utilities like `ls -l` look up the same few IDs over and over.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::rc::Rc;
+
+use libc::{c_char, c_int, gid_t, uid_t};
+
+use crate::group::Group;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub name: String,
+    pub passwd: String,
+    pub uid: uid_t,
+    pub gid: gid_t,
+    pub gecos: String,
+    pub dir: String,
+    pub shell: String,
+}
+
+/// Initial size of the buffer for the strings of an entry. It is grown
+/// as needed.
+const ENTRY_BUFSZ: usize = 1024;
+
+unsafe fn string_from_ptr(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
+/// Calls one of the reentrant lookup functions, growing the buffer while
+/// it reports ERANGE, and converts the entry found. Returns `None` if
+/// there is no such entry or the lookup failed.
+fn lookup_r<T, R>(
+    mut lookup: impl FnMut(*mut T, *mut c_char, usize, *mut *mut T) -> c_int,
+    convert: impl FnOnce(&T) -> R,
+) -> Option<R> {
+    let mut buf: Vec<c_char> = vec![0; ENTRY_BUFSZ];
+    loop {
+        let mut entry = MaybeUninit::<T>::uninit();
+        let mut result: *mut T = ptr::null_mut();
+        let ret = lookup(entry.as_mut_ptr(), buf.as_mut_ptr(), buf.len(), &mut result);
+        if ret == libc::ERANGE {
+            let len = buf.len() * 2;
+            buf.resize(len, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: on success result points to the filled in entry, whose
+        // strings point into buf.
+        return Some(convert(unsafe { &*result }));
+    }
+}
+
+fn user_from_passwd(pw: &libc::passwd) -> User {
+    // SAFETY: the strings of a passwd entry are valid C strings.
+    unsafe {
+        User {
+            name: string_from_ptr(pw.pw_name),
+            passwd: string_from_ptr(pw.pw_passwd),
+            uid: pw.pw_uid,
+            gid: pw.pw_gid,
+            gecos: string_from_ptr(pw.pw_gecos),
+            dir: string_from_ptr(pw.pw_dir),
+            shell: string_from_ptr(pw.pw_shell),
+        }
+    }
+}
+
+fn group_from_entry(gr: &libc::group) -> Group {
+    let mut members = Vec::new();
+    // SAFETY: gr_mem is a null-terminated array of C strings.
+    unsafe {
+        let mut member = gr.gr_mem;
+        // read_unaligned, as in group::load
+        while !member.is_null() {
+            let name: *mut c_char = ptr::read_unaligned(member);
+            if name.is_null() {
+                break;
+            }
+            members.push(string_from_ptr(name));
+            member = member.add(1);
+        }
+        Group {
+            name: string_from_ptr(gr.gr_name),
+            passwd: string_from_ptr(gr.gr_passwd),
+            gid: gr.gr_gid,
+            members,
+        }
+    }
+}
+
+// SAFETY, for the lookups below: all pointers passed are valid for the
+// duration of the call.
+
+fn fetch_user_by_uid(uid: uid_t) -> Option<User> {
+    lookup_r(
+        |pw, buf, len, result| unsafe { libc::getpwuid_r(uid, pw, buf, len, result) },
+        user_from_passwd,
+    )
+}
+
+fn fetch_user_by_name(name: &str) -> Option<User> {
+    let name = CString::new(name).ok()?;
+    lookup_r(
+        |pw, buf, len, result| unsafe { libc::getpwnam_r(name.as_ptr(), pw, buf, len, result) },
+        user_from_passwd,
+    )
+}
+
+fn fetch_group_by_gid(gid: gid_t) -> Option<Group> {
+    lookup_r(
+        |gr, buf, len, result| unsafe { libc::getgrgid_r(gid, gr, buf, len, result) },
+        group_from_entry,
+    )
+}
+
+fn fetch_group_by_name(name: &str) -> Option<Group> {
+    let name = CString::new(name).ok()?;
+    lookup_r(
+        |gr, buf, len, result| unsafe { libc::getgrnam_r(name.as_ptr(), gr, buf, len, result) },
+        group_from_entry,
+    )
+}
+
+type Cache<K, V> = RefCell<HashMap<K, Option<Rc<V>>>>;
+
+thread_local! {
+    static USERS_BY_UID: Cache<uid_t, User> = RefCell::new(HashMap::new());
+    static USERS_BY_NAME: Cache<String, User> = RefCell::new(HashMap::new());
+    static GROUPS_BY_GID: Cache<gid_t, Group> = RefCell::new(HashMap::new());
+    static GROUPS_BY_NAME: Cache<String, Group> = RefCell::new(HashMap::new());
+}
+
+fn cached<K: std::hash::Hash + Eq, V>(
+    cache: &'static std::thread::LocalKey<Cache<K, V>>,
+    key: K,
+    fetch: impl FnOnce(&K) -> Option<V>,
+) -> Option<Rc<V>> {
+    cache.with(|cache| {
+        if let Some(entry) = cache.borrow().get(&key) {
+            return entry.clone();
+        }
+        let entry = fetch(&key).map(Rc::new);
+        cache.borrow_mut().insert(key, entry.clone());
+        entry
+    })
+}
+
+pub fn user_by_uid(uid: uid_t) -> Option<Rc<User>> {
+    cached(&USERS_BY_UID, uid, |&uid| fetch_user_by_uid(uid))
+}
+
+pub fn user_by_name(name: &str) -> Option<Rc<User>> {
+    cached(&USERS_BY_NAME, name.to_string(), |name| {
+        fetch_user_by_name(name)
+    })
+}
+
+pub fn group_by_gid(gid: gid_t) -> Option<Rc<Group>> {
+    cached(&GROUPS_BY_GID, gid, |&gid| fetch_group_by_gid(gid))
+}
+
+pub fn group_by_name(name: &str) -> Option<Rc<Group>> {
+    cached(&GROUPS_BY_NAME, name.to_string(), |name| {
+        fetch_group_by_name(name)
+    })
+}
+
+/// Looks up a user by name, or failing that takes `user` as a numeric
+/// user ID, as chown and the like do.
+pub fn parse_uid(user: &str) -> Option<uid_t> {
+    user_by_name(user)
+        .map(|u| u.uid)
+        .or_else(|| user.parse().ok())
+}
+
+/// Looks up a group by name, or failing that takes `group` as a numeric
+/// group ID.
+pub fn parse_gid(group: &str) -> Option<gid_t> {
+    group_by_name(group)
+        .map(|g| g.gid)
+        .or_else(|| group.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root() {
+        let root = user_by_uid(0).unwrap();
+        assert_eq!(root.uid, 0);
+        assert_eq!(user_by_name(&root.name).unwrap(), root);
+        assert_eq!(parse_uid(&root.name), Some(0));
+        assert_eq!(parse_uid("0"), Some(0));
+
+        let group = group_by_gid(root.gid).unwrap();
+        assert_eq!(group_by_name(&group.name).unwrap().gid, root.gid);
+    }
+
+    #[test]
+    fn test_missing() {
+        assert!(user_by_name("no such user, surely").is_none());
+        assert!(group_by_name("no such group, surely").is_none());
+        assert_eq!(parse_uid("no such user, surely"), None);
+        // Failed lookups are cached too
+        assert!(user_by_name("no such user, surely").is_none());
+    }
+}
@@ -0,0 +1,726 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Walking a directory tree, like nftw(3), for the utilities with a
+//! recursive mode (find, du, chmod -R, rm -r, ...).
+//!
+//! Directories are opened relative to the descriptor of their parent and
+//! entries are examined with fstatat(2), so that a directory higher up
+//! being renamed or replaced by a symbolic link during the walk cannot
+//! redirect it to another part of the file system.
+
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString, OsStr};
+use std::fmt;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub use ftw::Metadata;
+
+/// Which symbolic links are followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symlinks {
+    /// None, as with -P.
+    Physical,
+    /// Only the starting point, as with -H.
+    CommandLine,
+    /// All of them, as with -L.
+    Logical,
+}
+
+/// The order in which entries are visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Directories before their contents.
+    PreOrder,
+    /// Directories after their contents, as rm -r and du need.
+    PostOrder,
+    /// All entries at one depth before those at the next.
+    BreadthFirst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    /// A symbolic link that was not followed.
+    Symlink,
+    /// A symbolic link that was to be followed, but whose target does not
+    /// exist.
+    DanglingSymlink,
+    /// Anything else.
+    File,
+}
+
+/// An error encountered during the walk, for the error callback.
+#[derive(Debug)]
+pub struct Error {
+    path: PathBuf,
+    inner: io::Error,
+}
+
+impl Error {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn io_error(&self) -> &io::Error {
+        &self.inner
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.path.display(),
+            crate::diag::strerror(&self.inner)
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Where a directory of the walk is, and which directory it is, as
+/// checked when it is opened.
+struct Location {
+    name: CString,
+    /// Whether the name may be a symbolic link to the directory.
+    follow: bool,
+    dev: u64,
+    ino: u64,
+    /// The directory containing it, or `None` for the starting point.
+    parent: Option<Rc<Location>>,
+}
+
+/// An open directory of the walk.
+struct Dir {
+    fd: OwnedFd,
+    location: Rc<Location>,
+}
+
+/// Descriptor of the directory containing an entry; the starting point
+/// is relative to the working directory.
+fn parent_fd(parent: &Option<Rc<Dir>>) -> RawFd {
+    parent
+        .as_ref()
+        .map_or(libc::AT_FDCWD, |dir| dir.fd.as_raw_fd())
+}
+
+/// An entry yet to be examined.
+struct Pending {
+    parent: Option<Rc<Dir>>,
+    name: CString,
+    path: PathBuf,
+    depth: usize,
+}
+
+impl Pending {
+    fn child(parent: &Option<Rc<Dir>>, path: &Path, depth: usize, name: CString) -> Pending {
+        Pending {
+            parent: parent.clone(),
+            path: path.join(OsStr::from_bytes(name.as_bytes())),
+            name,
+            depth,
+        }
+    }
+}
+
+/// A directory to be read.
+struct ToRead {
+    entry: Pending,
+    dev: u64,
+    ino: u64,
+    follow: bool,
+}
+
+/// A directory whose entries are yet to be walked breadth-first. It is
+/// not kept open meanwhile, so that a wide tree doesn't use up the
+/// descriptors, and is reopened from the starting point when its turn
+/// comes.
+struct Queued {
+    location: Rc<Location>,
+    path: PathBuf,
+    depth: usize,
+}
+
+/// A directory whose entries are being walked.
+struct Frame {
+    dir: Option<Rc<Dir>>,
+    path: PathBuf,
+    depth: usize,
+    children: std::vec::IntoIter<CString>,
+    /// The directory itself, visited once its contents are done.
+    post: Option<Entry>,
+}
+
+/// An entry in the tree.
+pub struct Entry {
+    parent: Option<Rc<Dir>>,
+    name: CString,
+    path: PathBuf,
+    depth: usize,
+    kind: EntryKind,
+    metadata: Metadata,
+}
+
+impl Entry {
+    /// Path of the entry, starting with the starting point.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Name of the entry, relative to [`Entry::dir_fd`].
+    pub fn file_name(&self) -> &CStr {
+        &self.name
+    }
+
+    /// Descriptor of the directory containing the entry, for use with
+    /// the `*at` functions, or `AT_FDCWD` for the starting point. It is
+    /// open for as long as the entry is alive.
+    pub fn dir_fd(&self) -> RawFd {
+        parent_fd(&self.parent)
+    }
+
+    /// Depth below the starting point, which is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Dir
+    }
+
+    /// Metadata of the entry, or of its target if it is a followed
+    /// symbolic link.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+/// Iterator over the entries of a directory tree. Errors are passed to
+/// the error callback, and the walk carries on with the next entry.
+pub struct Walker {
+    symlinks: Symlinks,
+    order: Order,
+    max_depth: Option<usize>,
+    same_file_system: bool,
+    on_error: Box<dyn FnMut(&Error)>,
+    failed: bool,
+    root_dev: u64,
+    /// The starting point, until it is visited.
+    root: Option<Pending>,
+    /// The directories to be walked breadth-first.
+    queue: VecDeque<Queued>,
+    /// The directories being walked, innermost last. A breadth-first
+    /// walk has at most one.
+    stack: Vec<Frame>,
+    /// The directory just visited in pre-order, read on the next call
+    /// unless skipped.
+    descend: Option<ToRead>,
+}
+
+impl Walker {
+    /// Walks the tree at `path` in pre-order, not following symbolic
+    /// links, and reporting errors as `utility: path: message`.
+    pub fn new(path: impl AsRef<Path>) -> Walker {
+        let path = path.as_ref().to_path_buf();
+        // A path containing a NUL byte names no file; as the empty name
+        // it fails to be examined like one.
+        let name = CString::new(path.as_os_str().as_bytes()).unwrap_or_default();
+        let root = Pending {
+            parent: None,
+            name,
+            path,
+            depth: 0,
+        };
+        Walker {
+            symlinks: Symlinks::Physical,
+            order: Order::PreOrder,
+            max_depth: None,
+            same_file_system: false,
+            on_error: Box::new(|e| crate::diag!("{}", e)),
+            failed: false,
+            root_dev: 0,
+            root: Some(root),
+            queue: VecDeque::new(),
+            stack: Vec::new(),
+            descend: None,
+        }
+    }
+
+    pub fn symlinks(mut self, symlinks: Symlinks) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Don't visit entries deeper than `depth` below the starting point.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Don't descend into directories on other file systems than the
+    /// starting point. They are still visited.
+    pub fn same_file_system(mut self, yes: bool) -> Self {
+        self.same_file_system = yes;
+        self
+    }
+
+    /// Calls `f` for each error instead of printing it.
+    pub fn on_error(mut self, f: impl FnMut(&Error) + 'static) -> Self {
+        self.on_error = Box::new(f);
+        self
+    }
+
+    /// Whether any error was encountered so far.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Don't descend into the directory just visited. Only has an effect
+    /// when directories are visited before their contents.
+    pub fn skip_current_dir(&mut self) {
+        self.descend = None;
+    }
+
+    fn report(&mut self, path: &Path, e: io::Error) {
+        self.failed = true;
+        (self.on_error)(&Error {
+            path: path.to_path_buf(),
+            inner: e,
+        });
+    }
+
+    fn is_ancestor(dir: &Option<Rc<Dir>>, dev: u64, ino: u64) -> bool {
+        let mut location = dir.as_ref().map(|dir| &dir.location);
+        while let Some(l) = location {
+            if l.dev == dev && l.ino == ino {
+                return true;
+            }
+            location = l.parent.as_ref();
+        }
+        false
+    }
+
+    /// Examines an entry, returning it unless it is to be visited later
+    /// (post-order) or could not be examined.
+    fn visit(&mut self, pending: Pending) -> Option<Entry> {
+        let follow = match self.symlinks {
+            Symlinks::Physical => false,
+            Symlinks::CommandLine => pending.depth == 0,
+            Symlinks::Logical => true,
+        };
+        let dirfd = parent_fd(&pending.parent);
+
+        let (metadata, kind) = match Metadata::new(dirfd, &pending.name, follow) {
+            Ok(m) if m.is_dir() => (m, EntryKind::Dir),
+            Ok(m) if m.is_symlink() => (m, EntryKind::Symlink),
+            Ok(m) => (m, EntryKind::File),
+            Err(e) => match Metadata::new(dirfd, &pending.name, false) {
+                Ok(m) if follow && m.is_symlink() && e.kind() == io::ErrorKind::NotFound => {
+                    (m, EntryKind::DanglingSymlink)
+                }
+                _ => {
+                    self.report(&pending.path, e);
+                    return None;
+                }
+            },
+        };
+
+        if pending.depth == 0 {
+            self.root_dev = metadata.dev();
+        }
+        if kind != EntryKind::Dir {
+            return Some(Entry {
+                parent: pending.parent,
+                name: pending.name,
+                path: pending.path,
+                depth: pending.depth,
+                kind,
+                metadata,
+            });
+        }
+
+        let (dev, ino) = (metadata.dev(), metadata.ino());
+        if follow && Self::is_ancestor(&pending.parent, dev, ino) {
+            let e = io::Error::other("file system loop detected");
+            self.report(&pending.path, e);
+            return None;
+        }
+        let descend = self.max_depth.map_or(true, |max| pending.depth < max)
+            && !(self.same_file_system && dev != self.root_dev);
+
+        let entry = Entry {
+            parent: pending.parent.clone(),
+            name: pending.name.clone(),
+            path: pending.path.clone(),
+            depth: pending.depth,
+            kind,
+            metadata,
+        };
+        let to_read = ToRead {
+            entry: pending,
+            dev,
+            ino,
+            follow,
+        };
+        match self.order {
+            Order::PostOrder if descend => {
+                self.read_dir(to_read, Some(entry));
+                None
+            }
+            Order::PostOrder => Some(entry),
+            Order::PreOrder | Order::BreadthFirst => {
+                if descend {
+                    self.descend = Some(to_read);
+                }
+                Some(entry)
+            }
+        }
+    }
+
+    /// Reads a directory, or for breadth-first walks queues it to be
+    /// read. `post` is the directory itself, for post-order walks.
+    fn read_dir(&mut self, to_read: ToRead, post: Option<Entry>) {
+        let ToRead {
+            entry,
+            dev,
+            ino,
+            follow,
+        } = to_read;
+        let location = Rc::new(Location {
+            name: entry.name,
+            follow,
+            dev,
+            ino,
+            parent: entry.parent.as_ref().map(|dir| Rc::clone(&dir.location)),
+        });
+
+        if self.order == Order::BreadthFirst {
+            self.queue.push_back(Queued {
+                location,
+                path: entry.path,
+                depth: entry.depth,
+            });
+            return;
+        }
+
+        let opened = open_at(parent_fd(&entry.parent), &location);
+        self.push_frame(opened, location, entry.path, entry.depth, post);
+    }
+
+    /// Lists the entries of a directory just opened, to be walked next.
+    fn push_frame(
+        &mut self,
+        opened: io::Result<OwnedFd>,
+        location: Rc<Location>,
+        path: PathBuf,
+        depth: usize,
+        post: Option<Entry>,
+    ) {
+        let listed = opened.and_then(|fd| Ok((read_names(&fd)?, fd)));
+        let (dir, names) = match listed {
+            Ok((names, fd)) => (Some(Rc::new(Dir { fd, location })), names),
+            Err(e) => {
+                self.report(&path, e);
+                (None, Vec::new())
+            }
+        };
+        self.stack.push(Frame {
+            dir,
+            path,
+            depth,
+            children: names.into_iter(),
+            post,
+        });
+    }
+}
+
+/// Opens a directory relative to `dirfd`, making sure that it is the
+/// directory that was examined, and not one that was put in its place
+/// since.
+fn open_at(dirfd: RawFd, location: &Location) -> io::Result<OwnedFd> {
+    let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+    if !location.follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+
+    // SAFETY: the name is a valid C string and the returned descriptor
+    // is owned by us.
+    let fd = unsafe { libc::openat(dirfd, location.name.as_ptr(), flags) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut st = MaybeUninit::<libc::stat>::uninit();
+    // SAFETY: st is large enough for the result.
+    if unsafe { libc::fstat(fd.as_raw_fd(), st.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let st = unsafe { st.assume_init() };
+    let (dev, ino): (u64, u64) = (st.st_dev as _, st.st_ino as _);
+    if dev != location.dev || ino != location.ino {
+        return Err(io::Error::other("directory changed during traversal"));
+    }
+    Ok(fd)
+}
+
+/// Opens a directory again, one component at a time from the starting
+/// point, with the same checks as when it was first opened.
+fn reopen(location: &Location) -> io::Result<OwnedFd> {
+    let parent = location.parent.as_deref().map(reopen).transpose()?;
+    let dirfd = parent.as_ref().map_or(libc::AT_FDCWD, |fd| fd.as_raw_fd());
+    open_at(dirfd, location)
+}
+
+/// Lists the entries of an open directory, other than `.` and `..`.
+fn read_names(fd: &OwnedFd) -> io::Result<Vec<CString>> {
+    // fdopendir takes ownership of the descriptor it is given, which
+    // would leave nothing for the *at functions.
+    // SAFETY: fd is a valid descriptor.
+    let dup = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0) };
+    if dup < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: dup is a valid directory descriptor.
+    let dirp = unsafe { libc::fdopendir(dup) };
+    if dirp.is_null() {
+        let e = io::Error::last_os_error();
+        unsafe { libc::close(dup) };
+        return Err(e);
+    }
+
+    let mut names = Vec::new();
+    let result = loop {
+        errno::set_errno(errno::Errno(0));
+        // SAFETY: dirp is an open directory stream.
+        let ent = unsafe { libc::readdir(dirp) };
+        if ent.is_null() {
+            match errno::errno().0 {
+                0 => break Ok(()),
+                code => break Err(io::Error::from_raw_os_error(code)),
+            }
+        }
+        // SAFETY: d_name is a C string within the entry returned.
+        let name = unsafe { CStr::from_ptr((*ent).d_name.as_ptr()) };
+        if name.to_bytes() != b"." && name.to_bytes() != b".." {
+            names.push(name.to_owned());
+        }
+    };
+    // SAFETY: dirp is an open directory stream, closed only here.
+    unsafe { libc::closedir(dirp) };
+    result?;
+    Ok(names)
+}
+
+impl Iterator for Walker {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        if let Some(to_read) = self.descend.take() {
+            self.read_dir(to_read, None);
+        }
+
+        loop {
+            let pending = if let Some(frame) = self.stack.last_mut() {
+                match frame.children.next() {
+                    Some(name) => Pending::child(&frame.dir, &frame.path, frame.depth + 1, name),
+                    None => {
+                        let frame = self.stack.pop().unwrap();
+                        match frame.post {
+                            Some(entry) => return Some(entry),
+                            None => continue,
+                        }
+                    }
+                }
+            } else if let Some(root) = self.root.take() {
+                root
+            } else {
+                let queued = self.queue.pop_front()?;
+                let opened = reopen(&queued.location);
+                self.push_frame(opened, queued.location, queued.path, queued.depth, None);
+                continue;
+            };
+
+            if let Some(entry) = self.visit(pending) {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, FixtureBuilder};
+    use std::cell::RefCell;
+
+    fn fixture(name: &str) -> FixtureBuilder {
+        Fixture::builder(name)
+            .file("a/f", "")
+            .file("a/b/g", "")
+            .dir("c")
+    }
+
+    /// Paths of the entries visited, relative to the fixture.
+    fn walk(dir: &Fixture, walker: Walker) -> Vec<String> {
+        walker
+            .map(|e| {
+                let path = e.path().strip_prefix(dir.root()).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect()
+    }
+
+    fn position(paths: &[String], path: &str) -> usize {
+        paths.iter().position(|p| p == path).unwrap()
+    }
+
+    #[test]
+    fn test_orders() {
+        let dir = fixture("orders").build();
+
+        let mut paths = walk(&dir, Walker::new(dir.root()));
+        assert_eq!(paths[0], "");
+        assert!(position(&paths, "a") < position(&paths, "a/b"));
+        assert!(position(&paths, "a/b") < position(&paths, "a/b/g"));
+        paths.sort();
+        assert_eq!(paths, ["", "a", "a/b", "a/b/g", "a/f", "c"]);
+
+        let paths = walk(&dir, Walker::new(dir.root()).order(Order::PostOrder));
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths[5], "");
+        assert!(position(&paths, "a/b/g") < position(&paths, "a/b"));
+        assert!(position(&paths, "a/f") < position(&paths, "a"));
+
+        let walker = Walker::new(dir.root()).order(Order::BreadthFirst);
+        let depths: Vec<usize> = walker.map(|e| e.depth()).collect();
+        assert_eq!(depths, [0, 1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let dir = fixture("prune").build();
+
+        let mut paths = walk(&dir, Walker::new(dir.root()).max_depth(1));
+        paths.sort();
+        assert_eq!(paths, ["", "a", "c"]);
+
+        let mut walker = Walker::new(dir.root());
+        let mut paths = Vec::new();
+        while let Some(entry) = walker.next() {
+            if entry.path().ends_with("a") {
+                walker.skip_current_dir();
+            }
+            paths.push(entry.path().strip_prefix(dir.root()).unwrap().to_owned());
+        }
+        paths.sort();
+        assert_eq!(paths, [Path::new(""), Path::new("a"), Path::new("c")]);
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let dir = fixture("symlinks")
+            .symlink("c/link", "../a")
+            .symlink("c/dangling", "missing")
+            .build();
+
+        let kinds = |walker: Walker| {
+            let mut kinds: Vec<(String, EntryKind)> = walker
+                .filter(|e| e.depth() == 1)
+                .map(|e| (e.file_name().to_string_lossy().into_owned(), e.kind()))
+                .collect();
+            kinds.sort_by(|a, b| a.0.cmp(&b.0));
+            kinds
+        };
+
+        let physical = kinds(Walker::new(dir.path("c")));
+        assert_eq!(
+            physical,
+            [
+                (String::from("dangling"), EntryKind::Symlink),
+                (String::from("link"), EntryKind::Symlink)
+            ]
+        );
+
+        let logical = kinds(Walker::new(dir.path("c")).symlinks(Symlinks::Logical));
+        assert_eq!(
+            logical,
+            [
+                (String::from("dangling"), EntryKind::DanglingSymlink),
+                (String::from("link"), EntryKind::Dir)
+            ]
+        );
+
+        // The starting point itself is followed with -H
+        let root = dir.path("c/link");
+        let walker = Walker::new(&root).symlinks(Symlinks::CommandLine);
+        assert_eq!(walker.count(), 4);
+        assert_eq!(Walker::new(&root).count(), 1);
+    }
+
+    #[test]
+    fn test_loop() {
+        let dir = fixture("loop").symlink("a/b/up", "..").build();
+
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let errors_clone = Rc::clone(&errors);
+        let mut walker = Walker::new(dir.path("a"))
+            .symlinks(Symlinks::Logical)
+            .on_error(move |e| errors_clone.borrow_mut().push(e.to_string()));
+        assert_eq!(walker.by_ref().count(), 4);
+        assert!(walker.failed());
+        assert_eq!(
+            *errors.borrow(),
+            [format!(
+                "{}: file system loop detected",
+                dir.path("a/b/up").display()
+            )]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_breadth_first_descriptors() {
+        let mut builder = Fixture::builder("wide");
+        for i in 0..200 {
+            builder = builder.dir(&format!("d{}/sub", i));
+        }
+        let dir = builder.build();
+
+        let open_fds = || std::fs::read_dir("/proc/self/fd").unwrap().count();
+        let before = open_fds();
+        let mut most = 0;
+        let walker = Walker::new(dir.root()).order(Order::BreadthFirst);
+        let mut count = 0;
+        for _ in walker {
+            most = most.max(open_fds());
+            count += 1;
+        }
+        assert_eq!(count, 401);
+        // Other tests running meanwhile open a few too
+        assert!(most < before + 50, "{} descriptors open", most - before);
+    }
+}
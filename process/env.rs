@@ -9,38 +9,57 @@
 
 use std::collections::HashMap;
 use std::env;
-use std::io;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
 
-use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::getopt::{self, GetOpt, GetOptError};
 
-#[derive(Parser)]
-#[command(version, about = gettext("env - set the environment for command invocation"))]
 struct Args {
-    #[arg(
-        short,
-        long,
-        help = gettext(
-            "Invoke utility with exactly the environment specified by the arguments; the inherited environment shall be ignored completely"
-        )
-    )]
     ignore_env: bool,
+    /// NAME=VALUE pairs, the utility to invoke, and its arguments
+    operands: Vec<OsString>,
+}
+
+impl Args {
+    // Options end at the first operand, so that the options of the
+    // utility are passed to it rather than taken by env.
+    fn parse() -> Result<Self, GetOptError> {
+        let mut ignore_env = false;
+        let mut getopt = GetOpt::new(env::args_os().skip(1), "i");
+        for opt in &mut getopt {
+            match opt? {
+                ('i', _) => ignore_env = true,
+                _ => unreachable!(),
+            }
+        }
 
-    #[arg(help = gettext("NAME=VALUE pairs, the utility to invoke, and its arguments"))]
-    operands: Vec<String>,
+        let mut operands = getopt.operands();
+        // Obsolescent: a leading "-" operand is equivalent to -i
+        if operands.first().is_some_and(|s| s == "-") {
+            operands.remove(0);
+            ignore_env = true;
+        }
+
+        Ok(Args {
+            ignore_env,
+            operands,
+        })
+    }
 }
 
-fn separate_ops(sv: &Vec<String>) -> (Vec<String>, Vec<String>) {
+fn separate_ops(sv: &Vec<OsString>) -> (Vec<OsString>, Vec<OsString>) {
     let mut envs = Vec::new();
     let mut util_args = Vec::new();
     let mut in_envs = true;
 
     for s in sv {
         if in_envs {
-            if s.contains('=') {
-                envs.push(String::from(s));
+            if s.as_bytes().contains(&b'=') {
+                envs.push(s.clone());
                 continue;
             }
 
@@ -49,38 +68,46 @@ fn separate_ops(sv: &Vec<String>) -> (Vec<String>, Vec<String>) {
             // fall through
         }
 
-        util_args.push(String::from(s));
+        util_args.push(s.clone());
     }
 
     (envs, util_args)
 }
 
-fn merge_env(new_env: &Vec<String>, clear: bool) -> HashMap<String, String> {
+fn merge_env(new_env: &Vec<OsString>, clear: bool) -> HashMap<OsString, OsString> {
     let mut map = HashMap::new();
 
     if !clear {
-        for (key, value) in env::vars() {
+        for (key, value) in env::vars_os() {
             map.insert(key, value);
         }
     }
 
     for env_op in new_env {
-        let (key, value) = env_op.split_once('=').unwrap();
-        map.insert(String::from(key), String::from(value));
+        let env_op = env_op.as_bytes();
+        let eq = env_op.iter().position(|&b| b == b'=').unwrap();
+        map.insert(
+            OsStr::from_bytes(&env_op[..eq]).to_os_string(),
+            OsStr::from_bytes(&env_op[eq + 1..]).to_os_string(),
+        );
     }
 
     map
 }
 
-fn print_env(envs: HashMap<String, String>) -> Result<(), Box<dyn std::error::Error>> {
+fn print_env(envs: HashMap<OsString, OsString>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = io::stdout().lock();
     for (key, value) in envs {
-        println!("{}={}", key, value);
+        out.write_all(key.as_bytes())?;
+        out.write_all(b"=")?;
+        out.write_all(value.as_bytes())?;
+        out.write_all(b"\n")?;
     }
 
     Ok(())
 }
 
-fn exec_util(envs: HashMap<String, String>, util_args: Vec<String>) -> io::Result<()> {
+fn exec_util(envs: HashMap<OsString, OsString>, util_args: Vec<OsString>) -> io::Result<()> {
     Err(Command::new(&util_args[0])
         .args(&util_args[1..])
         .stdin(Stdio::inherit())
@@ -96,7 +123,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain("posixutils-rs")?;
     bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
 
-    let args = Args::parse();
+    let usage = gettext("usage: env [-i] [name=value]... [utility [argument...]]");
+    getopt::help_or_version(
+        &format!(
+            "{}\n\n{}",
+            usage,
+            gettext("  -i  invoke the utility with only the environment given by the arguments")
+        ),
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("env: {}", e);
+            eprintln!("{}", usage);
+            std::process::exit(125);
+        }
+    };
 
     let (envs, util_args) = separate_ops(&args.operands);
     let new_env = merge_env(&envs, args.ignore_env);
@@ -139,10 +139,35 @@ impl Names {
     }
 }
 
+/// A way of finding the processes using files on a platform.
+trait ProcessSource {
+    /// The processes using each of the files, or the file systems they are
+    /// on if `mount` is set.
+    fn matched_procs(
+        &self,
+        files: Vec<PathBuf>,
+        mount: bool,
+    ) -> Result<Vec<Names>, Box<dyn std::error::Error>>;
+}
+
 #[cfg(target_os = "linux")]
 mod linux {
     use super::*;
 
+    /// Scans the open files, mappings and directories of each process in
+    /// /proc.
+    pub struct ProcFs;
+
+    impl ProcessSource for ProcFs {
+        fn matched_procs(
+            &self,
+            files: Vec<PathBuf>,
+            mount: bool,
+        ) -> Result<Vec<Names>, Box<dyn std::error::Error>> {
+            Ok(get_matched_procs(files, mount)?)
+        }
+    }
+
     use libc::fstat;
     use std::{
         env,
@@ -1227,6 +1252,19 @@ mod macos {
     use libc::{c_char, c_int, c_void};
     use std::{ffi::CString, os::unix::ffi::OsStrExt, ptr};
 
+    /// Asks libproc for the processes using each path.
+    pub struct Libproc;
+
+    impl ProcessSource for Libproc {
+        fn matched_procs(
+            &self,
+            files: Vec<PathBuf>,
+            mount: bool,
+        ) -> Result<Vec<Names>, Box<dyn std::error::Error>> {
+            get_matched_procs(files, mount)
+        }
+    }
+
     // similar to list_pids_ret() below, there are two cases when 0 is returned, one when there are
     // no pids, and the other when there is an error
     // when `errno` is set to indicate an error in the input type, the return value is 0
@@ -1351,6 +1389,9 @@ struct Args {
     /// The user name, in parentheses, associated with each process ID written to standard output shall be written to standard error.
     #[arg(short = 'u')]
     user: bool,
+    /// Kill the processes found with SIGKILL.
+    #[arg(short = 'k')]
+    kill: bool,
 
     #[arg(required = true, name = "FILE", num_args(0..))]
     /// A pathname on which the file or file system is to be reported.
@@ -1440,6 +1481,37 @@ fn print_matches(name: &mut Names, user: bool) -> Result<(), io::Error> {
     Ok(())
 }
 
+/// Sends SIGKILL to each process found, other than this one, once.
+fn kill_matches(names: &[Names]) -> bool {
+    let own_pid = std::process::id() as i32;
+    let mut pids: Vec<i32> = names
+        .iter()
+        .flat_map(|name| name.matched_procs.iter().map(|procs| procs.pid))
+        .filter(|&pid| pid != own_pid)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+
+    let mut ok = true;
+    for pid in pids {
+        if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+            let e = io::Error::last_os_error();
+            plib::diag!("{}: {}", pid, plib::diag::error_message(&e));
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// The way processes using files are found on this platform.
+fn process_source() -> Box<dyn ProcessSource> {
+    #[cfg(target_os = "linux")]
+    return Box::new(linux::ProcFs);
+
+    #[cfg(target_os = "macos")]
+    return Box::new(macos::Libproc);
+}
+
 /// Adds a new process to the `Names` object with specified access and process type.
 fn add_process(names: &mut Names, pid: i32, uid: u32, access: Access, proc_type: ProcType) {
     let proc = Procs::new(pid, uid, access, proc_type);
@@ -1487,7 +1559,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
 
     let Args {
-        mount, user, file, ..
+        mount,
+        user,
+        kill,
+        file,
+        ..
     } = Args::try_parse().unwrap_or_else(|err| match err.kind() {
         clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion => {
             print!("{err}");
@@ -1502,15 +1578,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    #[cfg(target_os = "linux")]
-    let mut names = linux::get_matched_procs(file, mount)?;
-
-    #[cfg(target_os = "macos")]
-    let mut names = macos::get_matched_procs(file, mount)?;
+    let mut names = process_source().matched_procs(file, mount)?;
 
     for name in names.iter_mut() {
         print_matches(name, user)?;
     }
 
+    if kill && !kill_matches(&names) {
+        std::process::exit(1);
+    }
+
     std::process::exit(0);
 }
@@ -7,96 +7,128 @@
 // SPDX-License-Identifier: MIT
 //
 
-mod signal;
-
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-
-use crate::signal::{list_signals, lookup_signum};
-
-enum ConfigMode {
-    Signal(i32),
+use plib::diag::{strerror, EXIT_USAGE};
+use plib::signal::{signal_name, signal_number, SIGNALS};
+use plib::{diag, tdiag, tr};
+
+enum Action {
+    /// Send a signal to the processes, or process groups, of the operands
+    Send { signal: i32, pids: Vec<String> },
+    /// List the signal names
     List,
+    /// Write the name of the signal of a signal number or exit status
+    Name(String),
 }
 
-struct Config {
-    mode: ConfigMode,
-    pids: Vec<u32>,
+/// Skips the `--` that may end the options.
+fn operands(args: &[String]) -> &[String] {
+    match args.first() {
+        Some(arg) if arg == "--" => &args[1..],
+        _ => args,
+    }
 }
 
-fn parse_cmdline() -> Result<Config, &'static str> {
-    let mut pids = Vec::new();
-    let mut mode = ConfigMode::Signal(libc::SIGTERM);
-    let mut in_args = true;
-    let mut in_s_arg = false;
-    for arg in std::env::args().skip(1) {
-        if in_args {
-            if in_s_arg {
-                let sig_no = lookup_signum(&arg)?;
-                mode = ConfigMode::Signal(sig_no);
-                in_s_arg = false;
-            } else if arg == "-s" || arg == "--signal" {
-                in_s_arg = true;
-            } else if arg == "-l" || arg == "--list" {
-                mode = ConfigMode::List;
-            } else if arg == "--" {
-                in_args = false;
-            } else if let Some(st) = arg.strip_prefix("-") {
-                let sig_no: i32 = match st.parse() {
-                    Ok(signo) => signo,
-                    Err(_) => lookup_signum(st)?,
-                };
-                mode = ConfigMode::Signal(sig_no);
-            } else {
-                in_args = false;
-            }
-
-            if in_args || arg == "--" {
-                continue;
-            }
-
-            // fall through to process non-option arguments
+/// Parses the command line. The syntax is too irregular for clap: `-9`,
+/// `-KILL` and `-SIGKILL` are options, while after them a negative
+/// operand is a process group.
+fn parse_args(args: &[String]) -> Result<Action, String> {
+    let parse_signal =
+        |name: &str| signal_number(name).ok_or_else(|| tr!("invalid signal name '{}'", name));
+
+    let (signal, rest) = match args.first().map(String::as_str) {
+        None => return Err(tr!("missing operand")),
+        Some("-l" | "--list") => {
+            return match operands(&args[1..]) {
+                [] => Ok(Action::List),
+                [status] => Ok(Action::Name(status.clone())),
+                _ => Err(tr!("too many operands")),
+            };
         }
-
-        match arg.parse::<u32>() {
-            Ok(pid) => pids.push(pid),
-            Err(_) => {
-                return Err("Invalid PID");
-            }
+        Some("-s" | "--signal") => match args.get(1) {
+            Some(name) => (parse_signal(name)?, &args[2..]),
+            None => return Err(tr!("option requires an argument -- 's'")),
+        },
+        Some("--") => (libc::SIGTERM, args),
+        Some(arg) if arg.len() > 1 && arg.starts_with('-') => {
+            (parse_signal(&arg[1..])?, &args[1..])
         }
-    }
+        Some(_) => (libc::SIGTERM, args),
+    };
 
-    Ok(Config { mode, pids })
+    let pids = operands(rest);
+    if pids.is_empty() {
+        return Err(tr!("missing operand"));
+    }
+    Ok(Action::Send {
+        signal,
+        pids: pids.to_vec(),
+    })
 }
 
-fn send_signal(prog_cfg: &Config, sig_no: i32) -> u32 {
-    let mut exit_code = 0;
-
-    for pid in &prog_cfg.pids {
-        let res = unsafe { libc::kill(*pid as libc::pid_t, sig_no as i32) };
-        if res != 0 {
-            let err = std::io::Error::last_os_error();
-            eprintln!("kill pid {}: {}", pid, err);
-            exit_code = 1;
+/// Sends `signal` to each process or, for negative operands, process
+/// group. Returns whether all of them could be signaled.
+fn send_signal(signal: i32, pids: &[String]) -> bool {
+    let mut success = true;
+    for pid in pids {
+        let Ok(pid_num) = pid.parse::<libc::pid_t>() else {
+            tdiag!("{}: invalid process ID", pid);
+            success = false;
+            continue;
+        };
+        if unsafe { libc::kill(pid_num, signal) } != 0 {
+            diag!("{}: {}", pid, strerror(&std::io::Error::last_os_error()));
+            success = false;
         }
     }
-
-    exit_code
+    success
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+/// Writes the name of the signal of `status`: a signal number, or the
+/// exit status of a process terminated by a signal, as `$?` gives it.
+fn write_signal_name(status: &str) -> bool {
+    let name = status
+        .parse::<i32>()
+        .ok()
+        .map(|number| if number > 128 { number - 128 } else { number })
+        .and_then(signal_name);
+    match name {
+        Some(name) => {
+            println!("{}", name);
+            true
+        }
+        None => {
+            tdiag!("{}: invalid signal number", status);
+            false
+        }
+    }
+}
 
-    let prog_cfg = parse_cmdline()?;
+fn main() {
+    plib::i18n::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let action = match parse_args(&args) {
+        Ok(action) => action,
+        Err(e) => {
+            diag!("{}", e);
+            eprintln!(
+                "{}",
+                tr!("usage: kill -s signal_name pid...\n       kill -l [exit_status]")
+            );
+            std::process::exit(EXIT_USAGE);
+        }
+    };
 
-    let exit_code = match prog_cfg.mode {
-        ConfigMode::List => {
-            list_signals();
-            0
+    let success = match action {
+        Action::Send { signal, pids } => send_signal(signal, &pids),
+        Action::List => {
+            for (name, _) in SIGNALS {
+                println!("{}", name);
+            }
+            true
         }
-        ConfigMode::Signal(sig_no) => send_signal(&prog_cfg, sig_no),
+        Action::Name(status) => write_signal_name(&status),
     };
 
-    std::process::exit(exit_code as i32)
+    std::process::exit(if success { 0 } else { 1 })
 }
@@ -7,41 +7,60 @@
 // SPDX-License-Identifier: MIT
 //
 
+use std::ffi::{OsStr, OsString};
 use std::io;
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
 
-use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::getopt::{self, GetOpt};
 
-#[derive(Parser)]
-#[command(
-    version,
-    about = gettext("nice - invoke a utility with an altered nice value")
-)]
 struct Args {
-    #[arg(
-        short,
-        long,
-        default_value_t = 10,
-        value_parser = clap::value_parser!(i32).range(-30..30),
-        help = gettext(
-            "A positive or negative decimal integer which shall have \
-             the same effect on the execution of the utility as if the \
-             utility had called the nice() function with the numeric \
-             value of the increment option-argument"
-        )
-    )]
+    /// The increment to the nice value
     niceval: i32,
+    util: OsString,
+    util_args: Vec<OsString>,
+}
+
+impl Args {
+    // Options end at the first operand, so that the options of the
+    // utility are passed to it rather than taken by nice.
+    fn parse() -> Result<Self, String> {
+        let mut niceval = 10;
+        let mut getopt = GetOpt::new(std::env::args_os().skip(1), "n:");
+        for opt in &mut getopt {
+            match opt.map_err(|e| e.to_string())? {
+                ('n', Some(increment)) => {
+                    niceval = increment
+                        .to_str()
+                        .and_then(|s| s.parse::<i32>().ok())
+                        .filter(|n| (-30..30).contains(n))
+                        .ok_or_else(|| {
+                            format!(
+                                "{}: {}",
+                                gettext("invalid increment"),
+                                increment.to_string_lossy()
+                            )
+                        })?;
+                }
+                _ => unreachable!(),
+            }
+        }
 
-    #[arg(help = gettext("Utility to invoke"))]
-    util: String,
+        let mut operands = getopt.operands().into_iter();
+        let util = operands
+            .next()
+            .ok_or_else(|| gettext("missing utility operand"))?;
 
-    #[arg(help = gettext("Utility arguments"))]
-    util_args: Vec<String>,
+        Ok(Args {
+            niceval,
+            util,
+            util_args: operands.collect(),
+        })
+    }
 }
 
-fn exec_util(util: &str, util_args: Vec<String>) -> io::Result<()> {
+fn exec_util(util: &OsStr, util_args: Vec<OsString>) -> io::Result<()> {
     Err(Command::new(util)
         .args(util_args)
         .stdin(Stdio::inherit())
@@ -55,7 +74,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain("posixutils-rs")?;
     bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
 
-    let args = Args::parse();
+    let usage = gettext("usage: nice [-n increment] utility [argument...]");
+    getopt::help_or_version(
+        &format!(
+            "{}\n\n{}",
+            usage,
+            gettext("  -n increment  add increment to the nice value, 10 by default")
+        ),
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("nice: {}", e);
+            eprintln!("{}", usage);
+            std::process::exit(125);
+        }
+    };
 
     let res = unsafe { libc::nice(args.niceval) };
     if res < 0 {
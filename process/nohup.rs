@@ -7,139 +7,118 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::env;
 use std::fs::{File, OpenOptions};
 use std::io::{self, IsTerminal};
-use std::os::unix::io::AsRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
 use std::process::{self, Command};
 
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use libc::{dup, dup2, signal, SIGHUP, SIG_IGN};
+use plib::diag::error_message;
+use plib::fd;
+use plib::getopt::GetOpt;
 
-enum NohupDir {
-    Current,
-    Home,
-}
+/// The utility was found but could not be invoked.
+const EXIT_CANNOT_INVOKE: i32 = 126;
+
+/// The utility was not found, or nohup itself failed.
+const EXIT_ERROR: i32 = 127;
+
+const NOHUP_OUT: &str = "nohup.out";
+
+/// Opens nohup.out for appending, creating it readable and writable only
+/// by the user, in the current directory or else in HOME.
+fn open_nohup_out() -> io::Result<(File, PathBuf)> {
+    let open = |path: &PathBuf| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(path)
+    };
 
-fn get_nohup_out_file() -> Result<(File, NohupDir), io::Error> {
-    // Attempting to open or create a nohup.out file in the current directory
-    match OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("nohup.out")
-    {
-        Ok(file) => Ok((file, NohupDir::Current)),
-        Err(_) => {
-            // If unsuccessful, attempt to create a nohup.out file in the home directory
-            if let Some(home_dir) = dirs::home_dir() {
-                let mut home_nohup_path = home_dir;
-                home_nohup_path.push("nohup.out");
-                let file = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(home_nohup_path)?;
-                Ok((file, NohupDir::Home))
-            } else {
-                Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Home directory not found",
-                ))
-            }
+    let path = PathBuf::from(NOHUP_OUT);
+    match open(&path) {
+        Ok(file) => Ok((file, path)),
+        Err(e) => {
+            let Some(home) = std::env::var_os("HOME") else {
+                return Err(e);
+            };
+            let path = PathBuf::from(home).join(NOHUP_OUT);
+            let file = open(&path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("{}: {}", path.display(), error_message(&e)),
+                )
+            })?;
+            Ok((file, path))
         }
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
-
-    unsafe {
-        // Ignore the SIGHUP signal
-        signal(SIGHUP, SIG_IGN);
+/// Sends standard output to nohup.out if it is a terminal, and standard
+/// error to wherever standard output goes if it is one. Messages are still
+/// written to the original standard error, which is returned.
+fn redirect_output() -> io::Result<Option<OwnedFd>> {
+    let stdout_tty = io::stdout().is_terminal();
+    let stderr_tty = io::stderr().is_terminal();
+
+    if stdout_tty {
+        let (file, path) = open_nohup_out()?;
+        plib::diag!("{}", plib::tr!("appending output to '{}'", path.display()));
+        fd::dup2(&file, libc::STDOUT_FILENO)?;
     }
-
-    // Save the original stderr
-    let original_stderr = unsafe { dup(libc::STDERR_FILENO) };
-    if original_stderr == -1 {
-        eprintln!("Failed to duplicate stderr");
-        process::exit(127);
+    if !stderr_tty {
+        return Ok(None);
     }
 
-    // Getting the command and arguments
-    let mut args = env::args().skip(1);
-    let command = match args.next() {
-        Some(cmd) => cmd,
-        None => {
-            eprintln!("Usage: nohup <command> [args...]");
-            process::exit(127);
-        }
-    };
-
-    // Redirecting stdout and stderr to the nohup.out file if they are connected to a terminal
-    if io::stdout().is_terminal() || io::stderr().is_terminal() {
-        let nohup_out_file =
-            get_nohup_out_file().expect("Failed to open nohup.out in current or home directory");
-
-        if io::stdout().is_terminal() {
-            let fd = nohup_out_file.0.as_raw_fd();
-
-            if unsafe { dup2(fd, libc::STDOUT_FILENO) } == -1 {
-                eprintln!("Failed to redirect stdout");
-                process::exit(127);
-            }
-
-            match nohup_out_file.1 {
-                NohupDir::Current => {
-                    eprintln!(
-                        "Name of the file to which the output is being appended: `nohup.out`"
-                    );
-                }
-                NohupDir::Home => {
-                    eprintln!(
-                        "Name of the file to which the output is being appended: `$HOME/nohup.out`"
-                    );
-                }
-            }
-        }
+    // Keep the original standard error for reporting exec failures, closed
+    // once the utility is running
+    let saved = fd::dup_at_least(io::stderr(), 3)?;
+    fd::dup2(io::stdout(), libc::STDERR_FILENO)?;
+    Ok(Some(saved))
+}
 
-        if io::stderr().is_terminal() {
-            let fd = nohup_out_file.0.as_raw_fd();
+fn main() {
+    plib::i18n::init();
 
-            if unsafe { dup2(fd, libc::STDERR_FILENO) } == -1 {
-                eprintln!("Failed to redirect stderr");
-                process::exit(127);
-            }
+    let mut getopt = GetOpt::new(std::env::args_os().skip(1), "");
+    for opt in &mut getopt {
+        if let Err(e) = opt {
+            plib::diag!("{}", e);
+            process::exit(EXIT_ERROR);
         }
     }
+    let mut operands = getopt.operands().into_iter();
+    let Some(utility) = operands.next() else {
+        plib::diag!("{}", plib::tr!("usage: nohup utility [argument...]"));
+        process::exit(EXIT_ERROR);
+    };
 
-    match Command::new(command).args(args).spawn() {
-        Ok(mut process) => {
-            process::exit(process.wait()?.code().unwrap_or(127));
-        }
-        Err(error) => {
-            use std::io::ErrorKind;
-
-            // Restore the original stderr
-
-            if unsafe { dup2(original_stderr, libc::STDERR_FILENO) } == -1 {
-                eprintln!("Failed to restore stderr");
-                process::exit(127);
-            }
-
-            // Close the duplicated descriptor as it's no longer needed
-            unsafe { libc::close(original_stderr) };
-
-            match error.kind() {
-                ErrorKind::NotFound => {
-                    eprintln!("Error: command not found");
-                    process::exit(127);
-                }
-                _ => {
-                    eprintln!("Error: command found but could not be invoked");
-                    process::exit(126);
-                }
-            }
+    // The utility inherits the disposition
+    if let Err(e) = plib::signal::ignore(libc::SIGHUP) {
+        plib::diag!("{}", error_message(&e));
+        process::exit(EXIT_ERROR);
+    }
+
+    let saved_stderr = match redirect_output() {
+        Ok(saved) => saved,
+        Err(e) => {
+            plib::diag!("{}", error_message(&e));
+            process::exit(EXIT_ERROR);
         }
+    };
+
+    let e = Command::new(&utility).args(operands).exec();
+
+    if let Some(saved) = saved_stderr {
+        let _ = fd::dup2(&saved, libc::STDERR_FILENO);
     }
+    plib::diag!("{}: {}", utility.to_string_lossy(), error_message(&e));
+    process::exit(if e.kind() == io::ErrorKind::NotFound {
+        EXIT_ERROR
+    } else {
+        EXIT_CANNOT_INVOKE
+    });
 }
@@ -7,12 +7,10 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::ffi::CString;
-
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use libc::{getpwnam, passwd};
 use plib::priority::{getpriority, setpriority};
+use plib::userdb;
 
 const PRIO_MIN: i32 = -20;
 const PRIO_MAX: i32 = 20;
@@ -68,15 +66,10 @@ struct Args {
 }
 
 fn lookup_uid(username: &str) -> Result<u32, &'static str> {
-    let c_username = CString::new(username).expect("CString::new failed");
-    let passwd = unsafe { getpwnam(c_username.as_ptr()) };
-
-    if passwd.is_null() {
-        return Err("User not found");
+    match userdb::user_by_name(username) {
+        Some(user) => Ok(user.uid),
+        None => Err("User not found"),
     }
-
-    let passwd: &passwd = unsafe { &*passwd };
-    Ok(passwd.pw_uid)
 }
 
 fn parse_id(which: u32, input: &str) -> Result<u32, &'static str> {
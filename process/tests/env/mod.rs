@@ -0,0 +1,90 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, test_bin_path, TestPlan};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::process::Command;
+
+fn env_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("env"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn test_env_ignore_environment() {
+    env_test(&["-i", "A=1"], "A=1\n", "", 0);
+    env_test(&["-", "A=1"], "A=1\n", "", 0);
+}
+
+#[test]
+fn test_env_passes_options_to_utility() {
+    let env = test_bin_path("env");
+    env_test(
+        &["-i", "A=1", env.to_str().unwrap(), "-i", "B=2"],
+        "B=2\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_env_invalid_option() {
+    env_test(
+        &["-x"],
+        "",
+        "env: illegal option -- x\nusage: env [-i] [name=value]... [utility [argument...]]\n",
+        125,
+    );
+}
+
+#[test]
+fn test_env_help_and_version() {
+    env_test(
+        &["--help"],
+        "usage: env [-i] [name=value]... [utility [argument...]]\n\n  -i  invoke the utility with only the environment given by the arguments\n",
+        "",
+        0,
+    );
+    env_test(
+        &["--version"],
+        concat!("env ", env!("CARGO_PKG_VERSION"), "\n"),
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_env_non_utf8_operands() {
+    let env = test_bin_path("env");
+    let output = Command::new(&env)
+        .args(["-i", "A=1"])
+        .arg(OsStr::from_bytes(b"\xff=\xfe"))
+        .arg(&env)
+        .arg("-i")
+        .arg(OsStr::from_bytes(b"B=\xfd"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"B=\xfd\n");
+
+    let output = Command::new(&env)
+        .args(["-i"])
+        .arg(OsStr::from_bytes(b"\xff=\xfe"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"\xff=\xfe\n");
+}
@@ -0,0 +1,44 @@
+use crate::fuser::fuser_test;
+use std::{fs::File, os::unix::process::ExitStatusExt, process::Command, str};
+
+/// Tests `fuser` with the `-k` flag to ensure it kills the processes found.
+///
+/// **Setup:**
+/// - Starts a process running `tail -f` on a temporary file.
+///
+/// **Assertions:**
+/// - Verifies that the PID is reported and the process dies of SIGKILL.
+#[test]
+fn test_fuser_kill() {
+    let temp_file_path = std::env::temp_dir().join("test_file_kill");
+    File::create(&temp_file_path).expect("Failed to create temporary file");
+
+    let mut process = Command::new("tail")
+        .arg("-f")
+        .arg(&temp_file_path)
+        .spawn()
+        .expect("Failed to start process");
+
+    let pid = process.id();
+
+    fuser_test(
+        vec![
+            "-k".to_string(),
+            temp_file_path.to_str().unwrap().to_string(),
+        ],
+        "",
+        0,
+        |_, output| {
+            let stdout_str = str::from_utf8(&output.stdout).expect("Invalid UTF-8 in stdout");
+            assert!(
+                stdout_str.contains(&pid.to_string()),
+                "PID {} not found in the output.",
+                pid
+            );
+        },
+    );
+
+    let status = process.wait().expect("Failed to wait for the process");
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+    std::fs::remove_file(temp_file_path).expect("Failed to remove temporary file");
+}
@@ -0,0 +1,131 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command};
+
+use plib::testing::{run_test, TestPlan};
+
+fn kill_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("kill"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+fn sleeper() -> Child {
+    Command::new("sleep").arg("60").spawn().unwrap()
+}
+
+/// Waits for `child` and returns the signal that terminated it.
+fn terminating_signal(mut child: Child) -> Option<i32> {
+    child.wait().unwrap().signal()
+}
+
+#[test]
+fn kill_list() {
+    let mut expected = String::new();
+    for name in [
+        "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "BUS", "FPE", "KILL", "USR1", "SEGV", "USR2",
+        "PIPE", "ALRM", "TERM", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU", "URG", "XCPU",
+        "XFSZ", "VTALRM", "PROF", "SYS",
+    ] {
+        expected.push_str(name);
+        expected.push('\n');
+    }
+    kill_test(&["-l"], &expected, "", 0);
+}
+
+#[test]
+fn kill_list_exit_status() {
+    kill_test(&["-l", "9"], "KILL\n", "", 0);
+    kill_test(&["-l", "--", "15"], "TERM\n", "", 0);
+    // the exit status of a process killed by SIGINT
+    kill_test(&["-l", "130"], "INT\n", "", 0);
+    kill_test(
+        &["-l", "1000"],
+        "",
+        "kill: 1000: invalid signal number\n",
+        1,
+    );
+}
+
+#[test]
+fn kill_signal_forms() {
+    for args in [
+        &["-s", "KILL"][..],
+        &["-s", "sigkill"],
+        &["-KILL"],
+        &["-SIGKILL"],
+        &["-9"],
+        &["-9", "--"],
+    ] {
+        let child = sleeper();
+        let pid = child.id().to_string();
+        let mut args = args.to_vec();
+        args.push(&pid);
+        kill_test(&args, "", "", 0);
+        assert_eq!(terminating_signal(child), Some(libc::SIGKILL), "{args:?}");
+    }
+
+    // SIGTERM by default
+    let child = sleeper();
+    kill_test(&[&child.id().to_string()], "", "", 0);
+    assert_eq!(terminating_signal(child), Some(libc::SIGTERM));
+}
+
+#[test]
+fn kill_null_signal() {
+    let child = sleeper();
+    let pid = child.id().to_string();
+    kill_test(&["-0", &pid], "", "", 0);
+    kill_test(&["-s", "0", &pid], "", "", 0);
+    kill_test(&["-s", "KILL", &pid], "", "", 0);
+    assert_eq!(terminating_signal(child), Some(libc::SIGKILL));
+}
+
+#[test]
+fn kill_process_group() {
+    let spawn_in_group = |pgid: i32| {
+        Command::new("sleep")
+            .arg("60")
+            .process_group(pgid)
+            .spawn()
+            .unwrap()
+    };
+    let leader = spawn_in_group(0);
+    let pgid = leader.id() as i32;
+    let group = [leader, spawn_in_group(pgid)];
+
+    kill_test(&["-s", "USR1", "--", &format!("-{pgid}")], "", "", 0);
+    for child in group {
+        assert_eq!(terminating_signal(child), Some(libc::SIGUSR1));
+    }
+}
+
+#[test]
+fn kill_invalid_arguments() {
+    kill_test(
+        &["-s", "BOGUS", "1"],
+        "",
+        "kill: invalid signal name 'BOGUS'\nusage: kill -s signal_name pid...\n       kill -l [exit_status]\n",
+        2,
+    );
+    kill_test(
+        &["-TERM"],
+        "",
+        "kill: missing operand\nusage: kill -s signal_name pid...\n       kill -l [exit_status]\n",
+        2,
+    );
+    kill_test(&["-0", "12x"], "", "kill: 12x: invalid process ID\n", 1);
+}
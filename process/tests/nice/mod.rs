@@ -0,0 +1,85 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, test_bin_path, TestPlan};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::process::Command;
+
+fn nice_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("nice"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+#[test]
+fn test_nice_passes_options_to_utility() {
+    let env = test_bin_path("env");
+    nice_test(
+        &["-n", "1", env.to_str().unwrap(), "-i", "A=1"],
+        "A=1\n",
+        "",
+        0,
+    );
+    nice_test(&["-n1", env.to_str().unwrap(), "-i", "A=1"], "A=1\n", "", 0);
+}
+
+#[test]
+fn test_nice_invalid_increment() {
+    nice_test(
+        &["-n", "x", "true"],
+        "",
+        "nice: invalid increment: x\nusage: nice [-n increment] utility [argument...]\n",
+        125,
+    );
+}
+
+#[test]
+fn test_nice_missing_utility() {
+    nice_test(
+        &["-n", "1"],
+        "",
+        "nice: missing utility operand\nusage: nice [-n increment] utility [argument...]\n",
+        125,
+    );
+}
+
+#[test]
+fn test_nice_help_and_version() {
+    nice_test(
+        &["--help"],
+        "usage: nice [-n increment] utility [argument...]\n\n  -n increment  add increment to the nice value, 10 by default\n",
+        "",
+        0,
+    );
+    nice_test(
+        &["--version"],
+        concat!("nice ", env!("CARGO_PKG_VERSION"), "\n"),
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_nice_non_utf8_operands() {
+    let output = Command::new(test_bin_path("nice"))
+        .args(["-n", "1"])
+        .arg(test_bin_path("env"))
+        .args(["-i"])
+        .arg(OsStr::from_bytes(b"A=\xff"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"A=\xff\n");
+}
@@ -0,0 +1,79 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test_with_options, Fixture, TestOptions, TestPlan};
+
+/// Runs nohup in a directory holding a file that is not executable.
+fn nohup_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    let fixture = Fixture::builder("nohup")
+        .file("script", "echo hi\n")
+        .mode(0o644)
+        .build();
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("nohup"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: String::from(expected_out),
+            expected_err: String::from(expected_err),
+            expected_exit_code,
+        },
+        TestOptions {
+            cwd: Some(fixture.root().to_path_buf()),
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn nohup_redirected_output_is_kept() {
+    // Output that is not a terminal goes where it was going, and no
+    // nohup.out is made
+    nohup_test(
+        &["sh", "-c", "echo out; echo err >&2; ls"],
+        "out\nscript\n",
+        "err\n",
+        0,
+    );
+}
+
+#[test]
+fn nohup_exit_status_of_utility() {
+    nohup_test(&["--", "sh", "-c", "exit 3"], "", "", 3);
+}
+
+#[test]
+fn nohup_ignores_sighup() {
+    nohup_test(&["sh", "-c", "kill -HUP $$; echo alive"], "alive\n", "", 0);
+}
+
+#[test]
+fn nohup_not_found() {
+    nohup_test(
+        &["./missing"],
+        "",
+        "nohup: ./missing: No such file or directory\n",
+        127,
+    );
+}
+
+#[test]
+fn nohup_cannot_invoke() {
+    nohup_test(
+        &["./script"],
+        "",
+        "nohup: ./script: Permission denied\n",
+        126,
+    );
+}
+
+#[test]
+fn nohup_missing_utility() {
+    nohup_test(&[], "", "nohup: usage: nohup utility [argument...]\n", 127);
+}
@@ -1,3 +1,7 @@
+mod env;
 mod fuser;
+mod kill;
+mod nice;
+mod nohup;
 mod timeout;
 mod xargs;
@@ -14,6 +14,7 @@ use std::{
     time::Duration,
 };
 
+use plib::testing::test_bin_path;
 use sysinfo::System;
 
 pub struct TestPlan {
@@ -27,18 +28,7 @@ pub struct TestPlan {
 }
 
 fn run_test_base(cmd: &str, args: &Vec<String>, stdin_data: &[u8]) -> (Output, i32) {
-    let relpath = if cfg!(debug_assertions) {
-        format!("target/debug/{}", cmd)
-    } else {
-        format!("target/release/{}", cmd)
-    };
-    let test_bin_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap() // Move up to the workspace root from the current package directory
-        .join(relpath); // Adjust the path to the binary
-
-    let mut command = Command::new(test_bin_path);
+    let mut command = Command::new(test_bin_path(cmd));
     let mut child = command
         .args(args)
         .stdin(Stdio::piped())
@@ -73,3 +73,146 @@ fn xargs_trace() {
         expected_exit_code: 0,
     });
 }
+
+#[test]
+fn xargs_quoted_args() {
+    xargs_test(
+        "'one two' \"three\" fo\\ ur\n",
+        "one two\nthree\nfo ur\n",
+        vec!["-n", "1", "echo"],
+    );
+}
+
+#[test]
+fn xargs_unmatched_quote() {
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: vec!["echo".to_string()],
+        stdin_data: String::from("one 'two\n"),
+        expected_out: String::from(""),
+        expected_err: String::from("xargs: unmatched single quote\n"),
+        expected_exit_code: 1,
+    });
+}
+
+#[test]
+fn xargs_default_utility() {
+    xargs_test("one two\n", "one two\n", vec![]);
+}
+
+#[test]
+fn xargs_utility_options() {
+    xargs_test(
+        "one two\n",
+        "[one two]\n",
+        vec!["sh", "-c", "echo \"[$*]\"", "sh"],
+    );
+}
+
+#[test]
+fn xargs_with_lines() {
+    xargs_test(
+        "one two\n\nthree \nfour\nfive\n",
+        "one two three four\nfive\n",
+        vec!["-L", "2", "echo"],
+    );
+}
+
+#[test]
+fn xargs_insert_mode() {
+    xargs_test(
+        "one\n  two words\n\nthree\n",
+        "[one] x-one-x\n[two words] x-two words-x\n[three] x-three-x\n",
+        vec!["-I", "{}", "echo", "[{}]", "x-{}-x"],
+    );
+}
+
+#[test]
+fn xargs_exit_too_long() {
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: vec!["-x", "-n", "3", "-s", "15", "echo"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        stdin_data: String::from("one two three\n"),
+        expected_out: String::from(""),
+        expected_err: String::from("xargs: argument line too long\n"),
+        expected_exit_code: 1,
+    });
+}
+
+#[test]
+fn xargs_parallel() {
+    // The first invocation sleeps the longest, so its output only comes
+    // last if the others run alongside it
+    let script = "sleep $0; printf '%s ' $0";
+    xargs_test(
+        "0.5 0 0\n",
+        "0 0 0.5 ",
+        vec!["-P", "3", "-n", "1", "sh", "-c", script],
+    );
+    xargs_test(
+        "0.2 0\n",
+        "0.2 0 ",
+        vec!["-P", "1", "-n", "1", "sh", "-c", script],
+    );
+}
+
+#[test]
+fn xargs_utility_failed() {
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: vec!["-P", "2", "-n", "1", "sh", "-c", "exit $0"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        stdin_data: String::from("0 3 0\n"),
+        expected_out: String::from(""),
+        expected_err: String::from(""),
+        expected_exit_code: 123,
+    });
+}
+
+#[test]
+fn xargs_utility_255() {
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: vec!["-n", "1", "sh", "-c", "echo $0; exit $0"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        stdin_data: String::from("0 255 0\n"),
+        expected_out: String::from("0\n255\n"),
+        expected_err: String::from("xargs: sh: exited with status 255; aborting\n"),
+        expected_exit_code: 124,
+    });
+}
+
+#[test]
+fn xargs_utility_not_found() {
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: vec!["posixutils-no-such-utility".to_string()],
+        stdin_data: String::from("one\n"),
+        expected_out: String::from(""),
+        expected_err: String::from(
+            "xargs: posixutils-no-such-utility: No such file or directory\n",
+        ),
+        expected_exit_code: 127,
+    });
+}
+
+#[test]
+fn xargs_empty_input() {
+    xargs_test("", "hi\n", vec!["echo", "hi"]);
+    xargs_test(" \n\t\n", "hi\n", vec!["echo", "hi"]);
+    xargs_test("", "\n", vec![]);
+}
+
+#[test]
+fn xargs_empty_input_no_run() {
+    xargs_test("", "", vec!["-r", "echo", "hi"]);
+    xargs_test(" \n", "", vec!["-r", "echo", "hi"]);
+    xargs_test("a\n", "hi a\n", vec!["-r", "echo", "hi"]);
+}
@@ -7,8 +7,6 @@
 // SPDX-License-Identifier: MIT
 //
 
-mod signal;
-
 use std::error::Error;
 use std::os::unix::fs::PermissionsExt;
 use std::os::unix::process::{CommandExt, ExitStatusExt};
@@ -20,8 +18,7 @@ use std::time::Duration;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-
-use crate::signal::parse_signal;
+use plib::signal::{signal_number, SigSet};
 
 static FOREGROUND: AtomicBool = AtomicBool::new(false);
 static FIRST_SIGNAL: AtomicI32 = AtomicI32::new(libc::SIGTERM);
@@ -54,6 +51,23 @@ struct Args {
     arguments: Vec<String>,
 }
 
+/// Parses string slice into a signal number.
+///
+/// # Arguments
+///
+/// * `s` - [str] that represents the signal name, with or without the `SIG` prefix.
+///
+/// # Returns
+///
+/// Returns the parsed signal number.
+///
+/// # Errors
+///
+/// Returns a [String] error if passed invalid signal name.
+fn parse_signal(s: &str) -> Result<i32, String> {
+    signal_number(s.trim()).ok_or_else(|| format!("invalid signal name '{s}'"))
+}
+
 /// Parses string slice into [Duration].
 ///
 /// # Arguments
@@ -107,7 +121,7 @@ fn set_timeout(duration: Duration) {
 /// * `signal` - [i32] value of signal tat must be sent.
 fn send_signal(pid: i32, signal: i32) {
     if pid == 0 {
-        unsafe { libc::signal(signal, libc::SIG_IGN) };
+        let _ = plib::signal::ignore(signal);
     }
     unsafe {
         libc::kill(pid, signal);
@@ -154,126 +168,22 @@ extern "C" fn handler(mut signal: i32) {
     }
 }
 
-/// Returns empty set of signals.
-///
-/// # Returns:
-///
-/// Returns [libc::sigset_t] empty set of signal.
-fn get_empty_sig_set() -> libc::sigset_t {
-    let mut sig_set = std::mem::MaybeUninit::uninit();
-    let _ = unsafe { libc::sigemptyset(sig_set.as_mut_ptr()) };
-    unsafe { sig_set.assume_init() }
-}
-
-/// Unblocks incoming signal by adding it to empty signals mask.
-///
-/// # Arguments
-///
-/// `signal` - [i32] value of signal that needs to be unblocked.
-fn unblock_signal(signal: i32) {
-    unsafe {
-        let mut sig_set = get_empty_sig_set();
-
-        libc::sigaddset(&mut sig_set, signal);
-        if libc::sigprocmask(
-            libc::SIG_UNBLOCK,
-            &sig_set,
-            std::ptr::null_mut::<libc::sigset_t>(),
-        ) != 0
-        {
-            eprintln!("timeout: failed to set unblock signals mask");
-            std::process::exit(125)
-        }
-    }
-}
-
-/// Installs handler for [libc::SIGCHLD] signal to receive child's exit status code from parent (timeout).
-fn set_chld() {
-    unsafe {
-        let mut sig_action = std::mem::MaybeUninit::<libc::sigaction>::uninit();
-        let p_sa = sig_action.as_mut_ptr();
-        (*p_sa).sa_sigaction = chld_handler as *const extern "C" fn(libc::c_int) as usize;
-        (*p_sa).sa_flags = libc::SA_RESTART;
-        libc::sigemptyset(&mut (*p_sa).sa_mask);
-        let sig_action = sig_action.assume_init();
-
-        libc::sigaction(
-            libc::SIGCHLD,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-    }
-
-    unblock_signal(libc::SIGCHLD);
-}
-
-/// Installs handler ([handler]) for incoming signal and other signals.
-///
-/// # Arguments
-///
-/// `signal` - [i32] value of signal that needs to be handled.
-fn set_handler(signal: i32) {
-    unsafe {
-        let mut sig_action = std::mem::MaybeUninit::<libc::sigaction>::uninit();
-        let p_sa = sig_action.as_mut_ptr();
-        (*p_sa).sa_sigaction = handler as *const extern "C" fn(libc::c_int) as usize;
-        (*p_sa).sa_flags = libc::SA_RESTART;
-        libc::sigemptyset(&mut (*p_sa).sa_mask);
-        let sig_action = sig_action.assume_init();
-
-        libc::sigaction(
-            libc::SIGALRM,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-        libc::sigaction(
-            libc::SIGINT,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-        libc::sigaction(
-            libc::SIGQUIT,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-        libc::sigaction(
-            libc::SIGHUP,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-        libc::sigaction(
-            libc::SIGTERM,
-            &sig_action,
-            std::ptr::null_mut::<libc::sigaction>(),
-        );
-        libc::sigaction(signal, &sig_action, std::ptr::null_mut::<libc::sigaction>());
-    }
-}
-
-/// Blocks incoming signal and stores previous signals mask.
-///
-/// # Arguments
-///
-/// `signal` - [i32] value of signal that needs to be handled.
-/// `old_set` - [libc::sigset_t] mutable reference to set of signals into which will be placed previous mask.
-fn block_handler_and_chld(signal: i32, old_set: &mut libc::sigset_t) {
-    unsafe {
-        let mut block_set = get_empty_sig_set();
-
-        libc::sigaddset(&mut block_set, libc::SIGALRM);
-        libc::sigaddset(&mut block_set, libc::SIGINT);
-        libc::sigaddset(&mut block_set, libc::SIGQUIT);
-        libc::sigaddset(&mut block_set, libc::SIGHUP);
-        libc::sigaddset(&mut block_set, libc::SIGTERM);
-        libc::sigaddset(&mut block_set, signal);
-
-        libc::sigaddset(&mut block_set, libc::SIGCHLD);
-
-        if libc::sigprocmask(libc::SIG_BLOCK, &block_set, old_set) != 0 {
-            eprintln!("timeout: failed to set block signals mask");
-            std::process::exit(125)
-        }
-    }
+/// Signals that [handler] is installed for, besides the one given with `-s`.
+const HANDLED_SIGNALS: [i32; 5] = [
+    libc::SIGALRM,
+    libc::SIGINT,
+    libc::SIGQUIT,
+    libc::SIGHUP,
+    libc::SIGTERM,
+];
+
+/// Exits with status 125 if changing signal dispositions or the signal
+/// mask failed.
+fn check_signal_setup<T>(result: std::io::Result<T>, what: &str) -> T {
+    result.unwrap_or_else(|_| {
+        eprintln!("timeout: failed to {what}");
+        std::process::exit(125)
+    })
 }
 
 /// Tries to disable core dumps for current process.
@@ -361,31 +271,34 @@ fn timeout(args: Args) -> i32 {
     }
 
     // Setup handlers before to catch signals before fork()
-    set_handler(signal_name);
-    unsafe {
-        libc::signal(libc::SIGTTIN, libc::SIG_IGN);
-        libc::signal(libc::SIGTTOU, libc::SIG_IGN);
-    }
-    set_chld();
+    check_signal_setup(
+        plib::signal::set_handler(&HANDLED_SIGNALS, handler)
+            .and_then(|()| plib::signal::ignore(libc::SIGTTIN))
+            .and_then(|()| plib::signal::ignore(libc::SIGTTOU))
+            .and_then(|()| plib::signal::set_handler(&[libc::SIGCHLD], chld_handler)),
+        "install signal handlers",
+    );
+    // SIGKILL and SIGSTOP can't be caught, but can still be sent.
+    let _ = plib::signal::set_handler(&[signal_name], handler);
 
     // To be able to handle SIGALRM (will be send after timeout)
-    unblock_signal(libc::SIGALRM);
+    check_signal_setup(
+        SigSet::from_signals(&[libc::SIGCHLD, libc::SIGALRM]).unblock(),
+        "set unblock signals mask",
+    );
 
-    let mut original_set = get_empty_sig_set();
-    block_handler_and_chld(signal_name, &mut original_set);
+    let mut block_set = SigSet::from_signals(&HANDLED_SIGNALS);
+    block_set.add(signal_name);
+    block_set.add(libc::SIGCHLD);
+    let original_set = check_signal_setup(block_set.block(), "set block signals mask");
 
     let spawn_result = unsafe {
         Command::new(&utility_path)
             .args(arguments)
             .pre_exec(move || {
-                libc::sigprocmask(
-                    libc::SIG_SETMASK,
-                    &original_set,
-                    std::ptr::null_mut::<libc::sigset_t>(),
-                );
-
-                libc::signal(libc::SIGTTIN, libc::SIG_DFL);
-                libc::signal(libc::SIGTTOU, libc::SIG_DFL);
+                original_set.set_mask()?;
+                plib::signal::set_default(libc::SIGTTIN)?;
+                plib::signal::set_default(libc::SIGTTOU)?;
                 Ok(())
             })
             .spawn()
@@ -419,7 +332,7 @@ fn timeout(args: Args) -> i32 {
 
         let es = ExitStatus::from_raw(status);
         if wait_status == 0 || es.continued() {
-            unsafe { libc::sigsuspend(&original_set) };
+            original_set.suspend();
         } else if es.stopped_signal().is_some() {
             send_signal(MONITORED_PID.load(Ordering::SeqCst), libc::SIGCONT);
             TIMED_OUT.store(true, Ordering::SeqCst);
@@ -441,8 +354,8 @@ fn timeout(args: Args) -> i32 {
                 return 125;
             }
             if !TIMED_OUT.load(Ordering::SeqCst) && disable_core_dumps() {
-                unsafe { libc::signal(signal, libc::SIG_DFL) };
-                unblock_signal(signal);
+                let _ = plib::signal::set_default(signal);
+                let _ = SigSet::from_signals(&[signal]).unblock();
                 unsafe { libc::raise(signal) };
             }
             if TIMED_OUT.load(Ordering::SeqCst) && signal as i32 == libc::SIGKILL {
@@ -6,23 +6,36 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - prompt mode (-p)
-// - insert mode (-I)
-// - split by lines (-L)
-// - exit feature (-x)
-// - write tests
-//
 
-use std::io::{self, Read};
-use std::process::{Command, Stdio};
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::mem;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus, Stdio};
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use plib::BUFSZ;
-
-const ARG_MAX: i32 = 131072; // arbitrary.  todo: discover actual value
-const MAX_ARGS_BYTES: usize = ARG_MAX as usize - 2048;
+use gettextrs::gettext;
+use plib::diag::{error_message, strerror, Status};
+use plib::{diag, i18n, tdiag, tr};
+
+/// Upper bound of the default command line size, like other
+/// implementations use, even if {ARG_MAX} allows for more.
+const MAX_ARGS_BYTES: usize = 128 * 1024;
+
+/// Room left in {ARG_MAX} for the utility to run commands of its own, as
+/// POSIX suggests.
+const ARG_MAX_HEADROOM: usize = 2048;
+
+/// An invocation of the utility exited with a status of 1 through 125.
+const EXIT_UTILITY_FAILED: i32 = 123;
+/// An invocation of the utility exited with status 255.
+const EXIT_UTILITY_255: i32 = 124;
+/// An invocation of the utility was killed by a signal.
+const EXIT_UTILITY_SIGNALED: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
 
 #[derive(Parser)]
 #[command(
@@ -33,6 +46,7 @@ struct Args {
     #[arg(
         short = 'L',
         long,
+        overrides_with_all = ["maxnum", "replstr"],
         help = gettext(
             "The utility shall be executed for each non-empty number lines of arguments from standard input"
         )
@@ -42,6 +56,7 @@ struct Args {
     #[arg(
         short = 'n',
         long,
+        overrides_with_all = ["lines", "replstr"],
         help = gettext(
             "Invoke utility using as many standard input arguments as possible, up to number"
         )
@@ -65,7 +80,12 @@ struct Args {
     )]
     eofstr: String,
 
-    #[arg(short = 'I', long, help = gettext("Insert mode"))]
+    #[arg(
+        short = 'I',
+        long,
+        overrides_with_all = ["lines", "maxnum"],
+        help = gettext("Insert mode: run utility for each line of input, replacing replstr in its arguments with the line")
+    )]
     replstr: Option<String>,
 
     #[arg(short, long, help = gettext("Prompt mode"))]
@@ -86,274 +106,474 @@ struct Args {
     )]
     exit: bool,
 
-    #[arg(help = gettext("Utility to invoke"))]
+    #[arg(
+        short = 'r',
+        long = "no-run-if-empty",
+        help = gettext("Do not invoke utility if standard input has no arguments")
+    )]
+    no_run_if_empty: bool,
+
+    #[arg(
+        short = 'P',
+        long,
+        default_value_t = 1,
+        help = gettext("Run up to maxprocs invocations of utility at once, or as many as possible if 0")
+    )]
+    maxprocs: usize,
+
+    #[arg(default_value = "echo", help = gettext("Utility to invoke"))]
     util: String,
 
-    #[arg(help = gettext("Utility arguments"))]
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = gettext("Utility arguments")
+    )]
     util_args: Vec<String>,
 }
 
-// find a string in a vector of strings
-fn find_str(needle: &str, haystack: &[String]) -> Option<usize> {
-    haystack.iter().position(|s| s == needle)
+fn too_long() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, tr!("argument line too long"))
+}
+
+/// Space an argument takes up in a command line.
+fn arg_size(arg: &OsStr) -> usize {
+    arg.len() + 1 // +1 for the terminating null
 }
 
-// execute the utility
-fn exec_util(util: &str, util_args: Vec<String>, trace: bool) -> io::Result<()> {
-    // if tracing, Each generated command line shall be written to
-    // standard error just prior to invocation.
-    if trace {
-        eprintln!("{} {}", util, util_args.join(" "));
+/// The size of command lines, as given by -s or else what the system
+/// allows for.
+fn max_size(args: &Args) -> usize {
+    // SAFETY: sysconf has no preconditions.
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let env_size: usize = std::env::vars_os()
+        .map(|(name, value)| name.len() + value.len() + 2)
+        .sum();
+    let limit = usize::try_from(arg_max)
+        .unwrap_or(MAX_ARGS_BYTES)
+        .saturating_sub(env_size + ARG_MAX_HEADROOM)
+        .min(MAX_ARGS_BYTES);
+    match args.maxsize {
+        Some(size) => size.min(limit),
+        None => limit,
     }
+}
 
-    let _output = Command::new(util)
-        .args(util_args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()?;
+/// Replaces each occurrence of `from` in `arg` with `to`.
+fn replace(arg: &OsStr, from: &[u8], to: &[u8]) -> OsString {
+    let arg = arg.as_bytes();
+    if from.is_empty() {
+        return OsString::from_vec(arg.to_vec());
+    }
+    let mut out = Vec::with_capacity(arg.len());
+    let mut i = 0;
+    while i < arg.len() {
+        if arg[i..].starts_with(from) {
+            out.extend_from_slice(to);
+            i += from.len();
+        } else {
+            out.push(arg[i]);
+            i += 1;
+        }
+    }
+    OsString::from_vec(out)
+}
 
-    Ok(())
+/// Splits a line of input into arguments at unquoted blanks, removing
+/// quotes and backslashes. If `whole`, the line is a single argument, as
+/// for -I, and only leading blanks are skipped. Also returns whether the
+/// line ends in an unquoted blank, which continues it for -L.
+fn split_line(line: &[u8], whole: bool) -> io::Result<(Vec<OsString>, bool)> {
+    let mut args = Vec::new();
+    let mut arg = Vec::new();
+    let mut in_arg = false;
+    let mut trailing_blank = false;
+    let mut bytes = line.iter().copied();
+
+    while let Some(b) = bytes.next() {
+        trailing_blank = false;
+        match b {
+            b' ' | b'\t' if !(whole && in_arg) => {
+                if in_arg {
+                    args.push(OsString::from_vec(mem::take(&mut arg)));
+                    in_arg = false;
+                }
+                trailing_blank = true;
+            }
+            b'\'' | b'"' => {
+                in_arg = true;
+                loop {
+                    match bytes.next() {
+                        Some(c) if c == b => break,
+                        Some(c) => arg.push(c),
+                        None if b == b'\'' => {
+                            let msg = tr!("unmatched single quote");
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                        }
+                        None => {
+                            let msg = tr!("unmatched double quote");
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                        }
+                    }
+                }
+            }
+            b'\\' => {
+                in_arg = true;
+                if let Some(c) = bytes.next() {
+                    arg.push(c);
+                }
+            }
+            _ => {
+                in_arg = true;
+                arg.push(b);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(OsString::from_vec(arg));
+    }
+    Ok((args, trailing_blank && !whole))
 }
 
-struct ParseState {
-    // cmdline-related state
-    util_size: usize,
-    util_n_args: usize,
-
-    // input state
-    tmp_arg: String,
-    in_arg: bool,
-    in_quote: bool,
-    in_escape: bool,
-    quote_char: char,
-    skip_remainder: bool,
-    null_slop: Vec<u8>,
-
-    // output state
-    max_bytes: usize,
-    max_args: Option<usize>,
-
-    // parsed args, ready for exec
-    args: Vec<String>,
+/// The arguments on one line of input.
+struct Line {
+    args: Vec<OsString>,
+    /// The line ended in a blank, so for -L it continues on the next line.
+    continued: bool,
 }
 
-impl ParseState {
-    fn new(args: &Args) -> ParseState {
-        let mut total = args.util.len();
-        for arg in &args.util_args {
-            total += arg.len() + 1; // +1 for space
-        }
+/// Standard input, read a line at a time.
+struct Input<R> {
+    reader: R,
+    null_mode: bool,
+    eofstr: Option<Vec<u8>>,
+    eof: bool,
+}
 
-        ParseState {
-            util_size: total,
-            util_n_args: args.util_args.len(),
-            tmp_arg: String::new(),
-            in_arg: false,
-            in_quote: false,
-            in_escape: false,
-            quote_char: '"',
-            skip_remainder: false,
-            null_slop: Vec::new(),
-            max_bytes: args.maxsize.unwrap_or(MAX_ARGS_BYTES),
-            max_args: args.maxnum,
-            args: Vec::new(),
+impl<R: BufRead> Input<R> {
+    fn new(reader: R, args: &Args) -> Self {
+        Input {
+            reader,
+            null_mode: args.null_mode,
+            eofstr: Some(args.eofstr.as_bytes().to_vec()).filter(|s| !s.is_empty()),
+            eof: false,
         }
     }
 
-    fn full(&self) -> bool {
-        let mut total = self.util_size;
-        for arg in &self.args {
-            total += arg.len() + 1; // +1 for space
+    /// Reads the next line and splits it into arguments; see
+    /// [`split_line`]. Returns `None` at the end of input, or after the
+    /// logical end-of-file string.
+    ///
+    /// With -0, lines end in null bytes instead and are taken as they are.
+    fn next_line(&mut self, whole: bool) -> io::Result<Option<Line>> {
+        if self.eof {
+            return Ok(None);
         }
 
-        if total > self.max_bytes {
-            true
-        } else if let Some(max_args) = self.max_args {
-            (self.util_n_args + self.args.len()) >= max_args
-        } else {
-            false
+        let delim = if self.null_mode { b'\0' } else { b'\n' };
+        let mut buf = Vec::new();
+        if self.reader.read_until(delim, &mut buf)? == 0 {
+            self.eof = true;
+            return Ok(None);
+        }
+        if buf.last() == Some(&delim) {
+            buf.pop();
+        }
+
+        if self.null_mode {
+            return Ok(Some(Line {
+                args: vec![OsString::from_vec(buf)],
+                continued: false,
+            }));
         }
-    }
 
-    fn remove_args(&mut self) -> Vec<String> {
-        let mut total = self.util_size;
-        let mut ret = Vec::new();
-        while !self.args.is_empty() {
-            // stop if adding the next arg would exceed the max size
-            if (total + self.args[0].len() + 1) > self.max_bytes {
-                break;
+        let (mut args, continued) = split_line(&buf, whole)?;
+        if let Some(eofstr) = &self.eofstr {
+            if let Some(pos) = args.iter().position(|arg| arg.as_bytes() == eofstr) {
+                args.truncate(pos);
+                self.eof = true;
             }
+        }
+        Ok(Some(Line { args, continued }))
+    }
+}
 
-            // add the next arg
-            let arg = self.args.remove(0);
-            total += arg.len() + 1; // +1 for space
-            ret.push(arg);
+/// Runs the constructed command lines, up to -P of them at once, and
+/// keeps track of how they went.
+struct Runner<'a> {
+    args: &'a Args,
+    /// Process IDs of the invocations still running.
+    running: Vec<u32>,
+    status: Status,
+    /// Set once no further invocations are to be made.
+    stopped: bool,
+    tty: Option<BufReader<File>>,
+}
 
-            // stop if we have reached the max number of args
-            if let Some(max_args) = self.max_args {
-                if (ret.len() + self.util_n_args) == max_args {
-                    break;
-                }
-            }
+impl<'a> Runner<'a> {
+    fn new(args: &'a Args) -> Self {
+        Runner {
+            args,
+            running: Vec::new(),
+            status: Status::new(),
+            stopped: false,
+            tty: None,
         }
+    }
 
-        ret
+    fn stop(&mut self, code: i32) {
+        self.stopped = true;
+        self.status.fail_with(code);
     }
 
-    // args are null-separated, without any further processing.
-    // if the input data crosses a null boundary, the remainder is
-    // stored as state for the next call to parse_buf_null.
-    fn parse_buf_null(&mut self, in_buf: &[u8]) -> io::Result<()> {
-        if self.skip_remainder {
-            return Ok(());
+    /// Accounts for an invocation that ended. Exit status 255 or a signal
+    /// stops xargs from making further invocations.
+    fn finished(&mut self, status: ExitStatus) {
+        match status.code() {
+            Some(0) => {}
+            Some(255) => {
+                tdiag!("{}: exited with status 255; aborting", self.args.util);
+                self.stop(EXIT_UTILITY_255);
+            }
+            Some(_) => self.status.fail_with(EXIT_UTILITY_FAILED),
+            None => {
+                let signal = status.signal().unwrap_or_default();
+                tdiag!("{}: terminated by signal {}", self.args.util, signal);
+                self.stop(EXIT_UTILITY_SIGNALED);
+            }
         }
+    }
 
-        // pull prior state into current buffer
-        let mut buf = Vec::with_capacity(self.null_slop.len() + in_buf.len());
-        buf.extend_from_slice(&self.null_slop);
-        buf.extend_from_slice(in_buf);
-        self.null_slop.clear();
-
-        // divide buffer into null-terminated strings, with remainder
-        let mut start = 0;
-        let mut end = 0;
-        while end < buf.len() {
-            if buf[end] == 0 {
-                let s = String::from_utf8_lossy(&buf[start..end]).to_string();
-                self.args.push(s);
-                start = end + 1;
+    /// Waits for one of the running invocations to end.
+    fn wait_one(&mut self) {
+        let mut status = 0;
+        // SAFETY: status is a valid pointer. The only children of xargs
+        // are the invocations it made.
+        let pid = loop {
+            let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+            if pid >= 0 || io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                break pid;
             }
-            end += 1;
-        }
+        };
+        let Ok(pid) = u32::try_from(pid) else {
+            // No children left to wait for
+            self.running.clear();
+            return;
+        };
+        self.running.retain(|&id| id != pid);
+        self.finished(ExitStatus::from_raw(status));
+    }
 
-        // remember remainder, if any, for next call
-        if start < buf.len() {
-            self.null_slop.extend_from_slice(&buf[start..]);
+    /// Asks on the terminal whether to run a command, as -p does.
+    fn confirm(&mut self, cmdline: &str) -> io::Result<bool> {
+        eprint!("{} ?...", cmdline);
+        if self.tty.is_none() {
+            self.tty = Some(BufReader::new(File::open("/dev/tty")?));
         }
-
-        Ok(())
+        let mut response = String::new();
+        self.tty.as_mut().unwrap().read_line(&mut response)?;
+        Ok(response.to_lowercase().starts_with('y'))
     }
 
-    fn parse_buf(&mut self, buf: &[u8]) -> io::Result<()> {
-        if self.skip_remainder {
+    /// Runs the utility with `argv`, after waiting for a free slot if -P
+    /// invocations are running already.
+    fn run(&mut self, argv: Vec<OsString>) -> io::Result<()> {
+        let max_procs = self.args.maxprocs;
+        while !self.running.is_empty() && max_procs != 0 && self.running.len() >= max_procs {
+            self.wait_one();
+        }
+        if self.stopped {
             return Ok(());
         }
 
-        for &c8 in buf {
-            let ch = c8 as char;
-
-            if self.in_quote {
-                if ch == self.quote_char {
-                    self.in_quote = false;
-                    self.in_arg = false;
-                    self.args.push(self.tmp_arg.clone());
-                    self.tmp_arg.clear();
-                } else {
-                    self.tmp_arg.push(ch);
+        if self.args.trace || self.args.prompt {
+            let cmdline: Vec<_> = argv.iter().map(|arg| arg.to_string_lossy()).collect();
+            let cmdline = cmdline.join(" ");
+            if self.args.prompt {
+                if !self.confirm(&cmdline)? {
+                    return Ok(());
                 }
-            } else if self.in_escape {
-                self.in_escape = false;
-                self.tmp_arg.push(ch);
-            } else if self.in_arg && ch.is_whitespace() {
-                self.in_arg = false;
-                self.args.push(self.tmp_arg.clone());
-                self.tmp_arg.clear();
-            } else if ch == '\'' || ch == '"' {
-                self.in_arg = true;
-                self.in_quote = true;
-                self.quote_char = ch;
-            } else if ch == '\\' {
-                self.in_escape = true;
-            } else if ch.is_whitespace() {
-                // ignore whitespace
             } else {
-                self.in_arg = true;
-                self.tmp_arg.push(ch);
+                eprintln!("{}", cmdline);
             }
         }
 
+        let spawned = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .spawn();
+        match spawned {
+            // The child is reaped by wait_one, not through its handle
+            Ok(child) => self.running.push(child.id()),
+            Err(e) => {
+                diag!("{}: {}", self.args.util, strerror(&e));
+                let code = if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_INVOKE
+                };
+                self.stop(code);
+            }
+        }
         Ok(())
     }
 
-    fn parse_finalize(&mut self) {
-        if self.in_arg {
-            self.in_arg = false;
-            self.args.push(self.tmp_arg.clone());
-            self.tmp_arg.clear();
+    /// Waits for all invocations still running, returning the exit status
+    /// of xargs.
+    fn finish(mut self) -> Status {
+        while !self.running.is_empty() {
+            self.wait_one();
         }
+        self.status
+    }
+}
 
-        if !self.null_slop.is_empty() {
-            let s = String::from_utf8_lossy(&self.null_slop).to_string();
-            self.args.push(s);
-            self.null_slop.clear();
+/// A command line under construction.
+struct Batch {
+    argv: Vec<OsString>,
+    /// Number of leading entries of `argv` that are the utility and its
+    /// arguments.
+    base_len: usize,
+    base_size: usize,
+    size: usize,
+    /// Number of complete non-empty input lines in the batch.
+    lines: usize,
+}
+
+impl Batch {
+    fn new(base: Vec<OsString>) -> Self {
+        let size = base.iter().map(|arg| arg_size(arg)).sum();
+        Batch {
+            base_len: base.len(),
+            base_size: size,
+            size,
+            argv: base,
+            lines: 0,
         }
     }
 
-    fn postprocess(&mut self, args: &Args) -> io::Result<()> {
-        if !args.eofstr.is_empty() {
-            if let Some(pos) = find_str(&args.eofstr, &self.args) {
-                self.args.truncate(pos);
-                self.skip_remainder = true;
-            }
-        }
+    fn n_args(&self) -> usize {
+        self.argv.len() - self.base_len
+    }
 
-        Ok(())
+    /// Takes the command line, leaving the utility and its arguments for
+    /// the next one.
+    fn take(&mut self) -> Vec<OsString> {
+        let base = self.argv[..self.base_len].to_vec();
+        self.size = self.base_size;
+        self.lines = 0;
+        mem::replace(&mut self.argv, base)
     }
 }
 
-fn read_and_spawn(args: &Args) -> io::Result<()> {
-    let mut state = ParseState::new(args);
-
-    let mut buffer = [0; BUFSZ];
+/// Runs the utility for each line of input, as -I does.
+fn insert_mode(
+    args: &Args,
+    replstr: &str,
+    input: &mut Input<impl BufRead>,
+    runner: &mut Runner,
+) -> io::Result<()> {
+    let max_size = max_size(args);
+    while let Some(line) = input.next_line(true)? {
+        // Empty lines are skipped
+        let Some(arg) = line.args.into_iter().next() else {
+            continue;
+        };
+
+        let mut argv = vec![OsString::from(&args.util)];
+        argv.extend(
+            args.util_args
+                .iter()
+                .map(|a| replace(a.as_ref(), replstr.as_bytes(), arg.as_bytes())),
+        );
+        // -I implies -x
+        if argv.iter().map(|arg| arg_size(arg)).sum::<usize>() > max_size {
+            return Err(too_long());
+        }
 
-    // read stdin until EOF
-    loop {
-        // read a chunk of input
-        let n_read = io::stdin().read(&mut buffer)?;
-        if n_read == 0 {
+        runner.run(argv)?;
+        if runner.stopped {
             break;
         }
+    }
+    Ok(())
+}
 
-        if args.null_mode {
-            state.parse_buf_null(&buffer[..n_read])?;
-        } else {
-            // parse the line
-            state.parse_buf(&buffer[..n_read])?;
+fn read_and_spawn(args: &Args, runner: &mut Runner) -> io::Result<()> {
+    let mut input = Input::new(io::stdin().lock(), args);
+
+    if let Some(replstr) = &args.replstr {
+        return insert_mode(args, replstr, &mut input, runner);
+    }
 
-            // handle eofstr and other details
-            state.postprocess(args)?;
+    let max_size = max_size(args);
+    let mut base = vec![OsString::from(&args.util)];
+    base.extend(args.util_args.iter().map(OsString::from));
+    let mut batch = Batch::new(base);
+    if batch.base_size > max_size {
+        return Err(too_long());
+    }
+
+    // Whether the current logical line, which for -L may span lines
+    // ending in blanks, has any arguments
+    let mut line_has_args = false;
+    let mut any_args = false;
+
+    while let Some(line) = input.next_line(false)? {
+        for arg in line.args {
+            line_has_args = true;
+            any_args = true;
+            let size = arg_size(&arg);
+            if batch.base_size + size > max_size {
+                return Err(too_long());
+            }
+            if batch.size + size > max_size {
+                // The size limit cuts the command line short of -n or -L
+                if args.exit && (args.maxnum.is_some() || args.lines.is_some()) {
+                    return Err(too_long());
+                }
+                runner.run(batch.take())?;
+            }
+            batch.argv.push(arg);
+            batch.size += size;
+
+            if args.maxnum == Some(batch.n_args()) {
+                runner.run(batch.take())?;
+            }
         }
 
-        // if enough args, spawn the utility
-        while state.full() {
-            let mut util_args = args.util_args.clone();
-            util_args.append(&mut state.remove_args());
-            exec_util(&args.util, util_args, args.trace)?;
+        if !line.continued && line_has_args {
+            line_has_args = false;
+            batch.lines += 1;
+            if args.lines == Some(batch.lines) {
+                runner.run(batch.take())?;
+            }
         }
-    }
 
-    // finalize parsing
-    state.parse_finalize();
+        if runner.stopped {
+            return Ok(());
+        }
+    }
 
-    // if there are any remaining args, spawn the utility
-    if !state.args.is_empty() {
-        let mut util_args = args.util_args.clone();
-        util_args.append(&mut state.remove_args());
-        exec_util(&args.util, util_args, args.trace)?;
+    // The utility is run once even without any arguments, unless -r
+    if batch.n_args() > 0 || !(any_args || args.no_run_if_empty) {
+        runner.run(batch.take())?;
     }
 
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    i18n::init();
 
     let args = Args::parse();
 
-    read_and_spawn(&args)?;
+    let mut runner = Runner::new(&args);
+    if let Err(e) = read_and_spawn(&args, &mut runner) {
+        diag!("{}", error_message(&e));
+        runner.stop(plib::diag::EXIT_FAILURE);
+    }
 
-    Ok(())
+    runner.finish().exit();
 }
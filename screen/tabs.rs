@@ -7,31 +7,34 @@
 // SPDX-License-Identifier: MIT
 //
 // TODO:
-// - (efficiency): collect all output in a buffer, then write to stdout
-// - Research if 100 is a POSIX-compliant limit for MAX_STOPS
-// - gettext("Specify repetitive tab stops separated by ({}) columns")
+// - the +m[n] margin option
 //
 
-use std::io::{self, Error, ErrorKind, Write};
+use std::io::{self, Write};
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::diag::error_message;
+use plib::tdiag;
 use terminfo::{capability as cap, Database};
 
-// arbitrarily chosen.  todo: search if POSIX-ly correct.
-const MAX_STOPS: usize = 100;
+/// Width assumed for a terminal that tells none.
+const DEFAULT_COLUMNS: usize = 80;
 
+/// tabs - set terminal tabs
 #[derive(Parser)]
-#[command(version, about = gettext("tabs - set terminal tabs"))]
+#[command(version, about)]
 struct Args {
-    #[arg(short = 'T', long, help = gettext("Indicate the type of terminal"))]
+    /// Indicate the type of terminal, instead of the TERM environment
+    /// variable.
+    #[arg(short = 'T', long)]
     term: Option<String>,
 
-    #[arg(
-        short = '1',
-        long,
-        help = gettext("Specify repetitive tab stops separated by (1) columns")
-    )]
+    /// Clear all tab stops.
+    #[arg(short = '0', long)]
+    rep_0: bool,
+
+    /// Specify repetitive tab stops separated by (1) columns
+    #[arg(short = '1', long)]
     rep_1: bool,
 
     /// Specify repetitive tab stops separated by (2) columns
@@ -58,7 +61,7 @@ struct Args {
     #[arg(short = '7', long)]
     rep_7: bool,
 
-    /// Specify repetitive tab stops separated by (8) columns
+    /// Specify repetitive tab stops separated by (8) columns, the default
     #[arg(short = '8', long)]
     rep_8: bool,
 
@@ -69,14 +72,14 @@ struct Args {
     /// Assembler, applicable to some mainframes.
     /// 1=[1,10,16,36,72]
     /// 2=[1,10,16,40,72]
-    #[arg(short, long, default_missing_value="1", value_parser = clap::value_parser!(u8).range(1..=2))]
+    #[arg(short, long, num_args = 0..=1, default_missing_value="1", value_parser = clap::value_parser!(u8).range(1..=2))]
     assembler: Option<u8>,
 
     /// COBOL, normal and compact formats.
     /// 1=[1,8,12,16,20,55]
     /// 2=[1,6,10,14,49]
     /// 3=[1,6,10,14,18,22,26,30,34,38,42,46,50,54,58,62,67]
-    #[arg(short, long, default_missing_value="1", value_parser = clap::value_parser!(u8).range(1..=3))]
+    #[arg(short, long, num_args = 0..=1, default_missing_value="1", value_parser = clap::value_parser!(u8).range(1..=3))]
     cobol: Option<u8>,
 
     /// FORTAN: [1,7,11,15,19,23]
@@ -95,134 +98,172 @@ struct Args {
     #[arg(short, long)]
     pl1: bool,
 
-    /// Optional: A single command line argument that consists of one or more tab-stop values (n) separated by a separator character
-    tabstops: Option<String>,
+    /// Tab stops as column numbers separated by commas or blanks, each
+    /// after the first optionally given as +n, n columns after the
+    /// previous one.
+    tabstops: Vec<String>,
+}
+
+/// Parses a tab-stop list into ascending column numbers, counted from 1.
+fn parse_tabstops(operands: &[String]) -> Result<Vec<usize>, String> {
+    let mut tabstops: Vec<usize> = Vec::new();
+    let values = operands
+        .iter()
+        .flat_map(|operand| operand.split([',', ' ', '\t']))
+        .filter(|value| !value.is_empty());
+
+    for value in values {
+        let (increment, digits) = match value.strip_prefix('+') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+        let n: usize = match digits.parse() {
+            Ok(n) if digits.bytes().all(|b| b.is_ascii_digit()) => n,
+            _ => return Err(plib::tr!("invalid tab stop '{}'", value)),
+        };
+        let stop = match tabstops.last() {
+            Some(&last) if increment => last + n,
+            _ => n,
+        };
+        if stop == 0 || tabstops.last().is_some_and(|&last| stop <= last) {
+            return Err(plib::tr!("tab stops must be in strictly ascending order"));
+        }
+        tabstops.push(stop);
+    }
+
+    Ok(tabstops)
 }
 
-fn parse_cmd_line(args: &Args) -> Result<Vec<u16>, &'static str> {
-    let mut tabstops: Vec<u16> = Vec::new();
-    let mut repeating_stop: Option<u16> = None;
-
-    if args.rep_9 {
-        repeating_stop = Some(9);
-    } else if args.rep_8 {
-        repeating_stop = Some(8);
-    } else if args.rep_7 {
-        repeating_stop = Some(7);
-    } else if args.rep_6 {
-        repeating_stop = Some(6);
-    } else if args.rep_5 {
-        repeating_stop = Some(5);
-    } else if args.rep_4 {
-        repeating_stop = Some(4);
-    } else if args.rep_3 {
-        repeating_stop = Some(3);
-    } else if args.rep_2 {
-        repeating_stop = Some(2);
-    } else if args.rep_1 {
-        repeating_stop = Some(1);
+/// The tab stops the options or operands ask for.
+fn tabstops(args: &Args, columns: usize) -> Result<Vec<usize>, String> {
+    let reps = [
+        args.rep_0, args.rep_1, args.rep_2, args.rep_3, args.rep_4, args.rep_5, args.rep_6,
+        args.rep_7, args.rep_8, args.rep_9,
+    ];
+    let repeating = reps.iter().rposition(|&rep| rep);
+
+    let tabstops = if let Some(n) = repeating {
+        // Stops every n columns, starting after column 1; -0 clears all
+        if n == 0 {
+            Vec::new()
+        } else {
+            (1..)
+                .map(|i| 1 + i * n)
+                .take_while(|&stop| stop <= columns)
+                .collect()
+        }
     } else if let Some(variant) = args.assembler {
         match variant {
-            1 => tabstops = vec![1, 10, 16, 36, 72],
-            2 => tabstops = vec![1, 10, 16, 40, 72],
-            _ => unreachable!(),
+            1 => vec![1, 10, 16, 36, 72],
+            _ => vec![1, 10, 16, 40, 72],
         }
     } else if let Some(variant) = args.cobol {
         match variant {
-            1 => tabstops = vec![1, 8, 12, 16, 20, 55],
-            2 => tabstops = vec![1, 6, 10, 14, 49],
-            3 => {
-                tabstops = vec![
-                    1, 6, 10, 14, 18, 22, 26, 30, 34, 38, 42, 46, 50, 54, 58, 62, 67,
-                ]
-            }
-            _ => unreachable!(),
+            1 => vec![1, 8, 12, 16, 20, 55],
+            2 => vec![1, 6, 10, 14, 49],
+            _ => vec![
+                1, 6, 10, 14, 18, 22, 26, 30, 34, 38, 42, 46, 50, 54, 58, 62, 67,
+            ],
         }
     } else if args.fortran {
-        tabstops = vec![1, 7, 11, 15, 19, 23];
+        vec![1, 7, 11, 15, 19, 23]
     } else if args.pl1 {
-        tabstops = vec![1, 5, 9, 13, 17, 21, 25, 29, 33, 37, 41, 45, 49, 53, 57, 61];
+        vec![1, 5, 9, 13, 17, 21, 25, 29, 33, 37, 41, 45, 49, 53, 57, 61]
     } else if args.snobol {
-        tabstops = vec![1, 10, 55];
+        vec![1, 10, 55]
     } else if args.assembler_u {
-        tabstops = vec![1, 12, 20, 44];
-    } else if let Some(ref tabstops_str) = args.tabstops {
-        for stop in tabstops_str.split(',') {
-            tabstops.push(
-                stop.parse()
-                    .unwrap_or_else(|_| panic!("{}", gettext("Invalid tabstop value."))),
-            );
-        }
-    }
-
-    // handle repetitive tab stops
-    if let Some(stop_n) = repeating_stop {
-        for _i in 0..MAX_STOPS {
-            tabstops.push(stop_n);
-        }
-    }
-
-    // validate that stops are in strictly ascending order
-    for i in 1..tabstops.len() {
-        if tabstops[i] <= tabstops[i - 1] {
-            return Err("Tabstops must be in strictly ascending order.");
-        }
-    }
+        vec![1, 12, 20, 44]
+    } else if !args.tabstops.is_empty() {
+        parse_tabstops(&args.tabstops)?
+    } else {
+        (1..)
+            .map(|i| 1 + i * 8)
+            .take_while(|&stop| stop <= columns)
+            .collect()
+    };
 
     Ok(tabstops)
 }
 
-// set hardware tabs.
-fn set_hw_tabs(info: &Database, tabstops: &Vec<u16>) -> io::Result<()> {
-    let clear_cap = info.get::<cap::ClearAllTabs>();
-    let set_cap = info.get::<cap::SetTab>();
-
-    if clear_cap.is_none() || set_cap.is_none() {
-        let msg = gettext("Terminal does not support hardware tabs.");
-        return Err(Error::new(ErrorKind::Other, msg));
-    }
-    let clear_cap = clear_cap.unwrap();
-    let set_cap = set_cap.unwrap();
-
-    // clear existing tabs
-    if let Err(e) = clear_cap.expand().to(io::stdout()) {
-        let msg = format!("{}: {}", gettext("Failed to clear tabs"), e);
-        return Err(Error::new(ErrorKind::Other, msg));
+/// The width of the terminal on standard output, else as terminfo gives
+/// it.
+fn columns(info: &Database) -> usize {
+    // SAFETY: ws is a valid winsize for TIOCGWINSZ to fill in
+    let ws = unsafe {
+        let mut ws: libc::winsize = std::mem::zeroed();
+        libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws);
+        ws
+    };
+    if ws.ws_col > 0 {
+        return usize::from(ws.ws_col);
     }
+    info.get::<cap::Columns>()
+        .and_then(|cols| usize::try_from(Into::<i32>::into(cols)).ok())
+        .filter(|&cols| cols > 0)
+        .unwrap_or(DEFAULT_COLUMNS)
+}
 
-    // set new tabs
-    let mut col = 0;
-    for stop in tabstops {
-        let stop = *stop as usize;
-
-        while col < stop {
-            io::stdout().write_all(b" ")?;
-            col += 1;
-        }
-
-        if let Err(e) = set_cap.expand().to(io::stdout()) {
-            let msg = format!("{}: {}", gettext("Failed to set tab"), e);
-            return Err(Error::new(ErrorKind::Other, msg));
+/// Writes the sequences that clear the tab stops of the terminal and set
+/// `tabstops`, moving to each stop from the start of the line.
+fn set_hw_tabs(info: &Database, tabstops: &[usize]) -> Result<Vec<u8>, String> {
+    let (Some(clear_cap), Some(set_cap)) =
+        (info.get::<cap::ClearAllTabs>(), info.get::<cap::SetTab>())
+    else {
+        return Err(plib::tr!("terminal '{}' cannot set tab stops", info.name()));
+    };
+    let column_address = info.get::<cap::ColumnAddress>();
+
+    let error = |e: terminfo::Error| error_message(&e);
+    let mut out = b"\r".to_vec();
+    clear_cap.expand().to(&mut out).map_err(error)?;
+
+    let mut col = 1;
+    for &stop in tabstops {
+        match &column_address {
+            Some(column_address) => {
+                column_address
+                    .expand()
+                    .parameters(stop as u32 - 1)
+                    .to(&mut out)
+                    .map_err(error)?;
+            }
+            None => out.extend(std::iter::repeat(b' ').take(stop - col)),
         }
+        col = stop;
+        set_cap.expand().to(&mut out).map_err(error)?;
     }
+    out.push(b'\r');
 
-    Ok(())
+    Ok(out)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
-    let info = match args.term {
-        None => Database::from_env().unwrap(),
-        Some(ref termtype) => Database::from_name(termtype).unwrap(),
+    let info = match args.term.as_deref() {
+        Some(term) => Database::from_name(term),
+        None => Database::from_env(),
+    };
+    let Ok(info) = info else {
+        let term = args.term.or_else(|| std::env::var("TERM").ok());
+        tdiag!("unknown terminal '{}'", term.unwrap_or_default());
+        std::process::exit(1);
     };
 
-    let tabstops = parse_cmd_line(&args)?;
-    set_hw_tabs(&info, &tabstops)?;
+    let result = tabstops(&args, columns(&info)).and_then(|tabstops| set_hw_tabs(&info, &tabstops));
+    let out = match result {
+        Ok(out) => out,
+        Err(e) => {
+            plib::diag!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    Ok(())
+    let mut stdout = io::stdout();
+    if let Err(e) = stdout.write_all(&out).and_then(|_| stdout.flush()) {
+        plib::diag!("{}", error_message(&e));
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,82 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+
+fn tabs_test(args: &[&str], expected_out: &str, expected_err: &str, code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("tabs"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code: code,
+    });
+}
+
+#[test]
+fn tabs_list() {
+    tabs_test(
+        &["-T", "xterm", "1,+4,10"],
+        "\r\x1b[3g\x1b[1G\x1bH\x1b[5G\x1bH\x1b[10G\x1bH\r",
+        "",
+        0,
+    );
+    tabs_test(
+        &["-T", "xterm", "3 7", "9"],
+        "\r\x1b[3g\x1b[3G\x1bH\x1b[7G\x1bH\x1b[9G\x1bH\r",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn tabs_canned() {
+    tabs_test(
+        &["-T", "xterm", "-s"],
+        "\r\x1b[3g\x1b[1G\x1bH\x1b[10G\x1bH\x1b[55G\x1bH\r",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn tabs_clear() {
+    tabs_test(&["-T", "xterm", "-0"], "\r\x1b[3g\r", "", 0);
+}
+
+#[test]
+fn tabs_not_ascending() {
+    tabs_test(
+        &["-T", "xterm", "5,3"],
+        "",
+        "tabs: tab stops must be in strictly ascending order\n",
+        1,
+    );
+}
+
+#[test]
+fn tabs_invalid() {
+    tabs_test(
+        &["-T", "xterm", "1,x"],
+        "",
+        "tabs: invalid tab stop 'x'\n",
+        1,
+    );
+}
+
+#[test]
+fn tabs_unsupported_terminal() {
+    tabs_test(
+        &["-T", "dumb"],
+        "",
+        "tabs: terminal 'dumb' cannot set tab stops\n",
+        1,
+    );
+}
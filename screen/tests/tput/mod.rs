@@ -0,0 +1,94 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, run_test_with_options, TestOptions, TestPlan};
+
+fn tput_test(args: &[&str], expected_out: &str, expected_err: &str, code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("tput"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code: code,
+    });
+}
+
+#[test]
+fn tput_clear() {
+    tput_test(&["-T", "xterm", "clear"], "\x1b[H\x1b[2J", "", 0);
+}
+
+#[test]
+fn tput_string_parameters() {
+    tput_test(&["-T", "xterm", "cup", "5", "10"], "\x1b[6;11H", "", 0);
+    tput_test(
+        &["-T", "xterm", "setaf", "1", "sgr0"],
+        "\x1b[31m\x1b(B\x1b[m",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn tput_padding() {
+    // The delay in flash isn't written
+    tput_test(&["-T", "xterm", "flash"], "\x1b[?5h\x1b[?5l", "", 0);
+}
+
+#[test]
+fn tput_number() {
+    tput_test(&["-T", "xterm", "colors"], "8\n", "", 0);
+    tput_test(&["-T", "xterm", "lm"], "-1\n", "", 0);
+}
+
+#[test]
+fn tput_boolean() {
+    tput_test(&["-T", "xterm", "am"], "", "", 0);
+    tput_test(&["-T", "xterm", "hz"], "", "", 1);
+}
+
+#[test]
+fn tput_columns_from_environment() {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("tput"),
+            args: vec![
+                String::from("-T"),
+                String::from("xterm"),
+                String::from("cols"),
+            ],
+            stdin_data: String::new(),
+            expected_out: String::from("123\n"),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions::default().env("COLUMNS", "123"),
+    );
+}
+
+#[test]
+fn tput_unknown_capability() {
+    tput_test(
+        &["-T", "xterm", "bogus"],
+        "",
+        "tput: unknown terminfo capability 'bogus'\n",
+        4,
+    );
+}
+
+#[test]
+fn tput_unknown_terminal() {
+    tput_test(
+        &["-T", "no-such-terminal", "clear"],
+        "",
+        "tput: unknown terminal 'no-such-terminal'\n",
+        3,
+    );
+}
@@ -6,92 +6,265 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - eliminate unwrap. more error checking.
-// - read init-file and reset-file data from filesystem
-//
 
-use std::io;
+use std::fs;
+use std::io::{self, Write};
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use terminfo::{capability as cap, Database};
+use plib::diag::{error_message, Status};
+use plib::tdiag;
+use terminfo::expand::{Context, Parameter};
+use terminfo::{names, Database, Expand, Value};
 
+/// tput - change terminal characteristics
 #[derive(Parser)]
-#[command(version, about = gettext("tput - change terminal characteristics"))]
+#[command(version, about)]
 struct Args {
-    #[arg(short = 'T', long, help = gettext("Indicate the type of terminal"))]
+    /// Indicate the type of terminal, instead of the TERM environment
+    /// variable.
+    #[arg(short = 'T', long)]
     term: Option<String>,
 
-    #[arg(help = gettext("Terminal operand to execute"))]
-    operand: String,
+    /// clear, init, reset, or a terminfo capability name, followed by the
+    /// parameters of the capability if it takes any.
+    #[arg(required = true)]
+    operands: Vec<String>,
 }
 
-fn tput_init(info: Database) -> terminfo::Result<()> {
-    if let Some(cap) = info.get::<cap::Init1String>() {
-        cap.expand().to(io::stdout())?;
-    }
-    if let Some(cap) = info.get::<cap::Init2String>() {
-        cap.expand().to(io::stdout())?;
+/// A boolean capability is false, or a string one isn't set.
+const EXIT_UNSET: i32 = 1;
+const EXIT_NO_TERMINAL: i32 = 3;
+const EXIT_BAD_OPERAND: i32 = 4;
+const EXIT_ERROR: i32 = 5;
+
+/// The value of a capability.
+enum Capability<'a> {
+    Boolean(bool),
+    Number(Option<i32>),
+    String(Option<&'a [u8]>),
+}
+
+/// Looks up a capability by its terminfo name. Predefined capabilities
+/// have a type even when the terminal doesn't set them; extended ones
+/// are known only by their value.
+fn capability<'a>(info: &'a Database, capname: &str) -> Option<Capability<'a>> {
+    let value = info.raw(capname);
+    match value {
+        Some(Value::True) => return Some(Capability::Boolean(true)),
+        Some(Value::Number(n)) => return Some(Capability::Number(Some(*n))),
+        Some(Value::String(s)) => return Some(Capability::String(Some(s))),
+        None => {}
     }
-    if let Some(cap) = info.get::<cap::InitFile>() {
-        // FIXME
-        cap.expand().to(io::stdout())?;
+
+    let (&name, _) = names::TERMINFO.entries().find(|(_, &c)| c == capname)?;
+    if names::BOOLEAN.values().any(|&n| n == name) {
+        Some(Capability::Boolean(false))
+    } else if names::NUMBER.values().any(|&n| n == name) {
+        Some(Capability::Number(None))
+    } else if names::STRING.values().any(|&n| n == name) {
+        Some(Capability::String(None))
+    } else {
+        None
     }
-    if let Some(cap) = info.get::<cap::Init3String>() {
-        cap.expand().to(io::stdout())?;
+}
+
+/// The number of parameters a string capability takes: the highest
+/// `%pN` it pushes.
+fn parameter_count(s: &[u8]) -> usize {
+    s.windows(3)
+        .filter(|w| w[0] == b'%' && w[1] == b'p' && (b'1'..=b'9').contains(&w[2]))
+        .map(|w| usize::from(w[2] - b'0'))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Removes the `$<n>` padding delays of an expanded string, which only
+/// matter to slow terminals.
+fn strip_delays(s: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.windows(2).position(|w| w == b"$<") {
+        let delay = &rest[pos + 2..];
+        let end = delay.iter().position(|&b| b == b'>');
+        let is_delay = end.is_some_and(|end| {
+            end > 0
+                && delay[..end]
+                    .iter()
+                    .all(|&b| b.is_ascii_digit() || b".*/".contains(&b))
+        });
+        result.extend_from_slice(&rest[..pos]);
+        match end {
+            Some(end) if is_delay => rest = &delay[end + 1..],
+            _ => {
+                result.extend_from_slice(b"$<");
+                rest = delay;
+            }
+        }
     }
+    result.extend_from_slice(rest);
+    result
+}
 
+fn expand(s: &[u8], parameters: &[Parameter], out: &mut Vec<u8>) -> terminfo::Result<()> {
+    let mut expanded = Vec::new();
+    s.expand(&mut expanded, parameters, &mut Context::default())?;
+    out.extend(strip_delays(&expanded));
     Ok(())
 }
 
-fn tput_reset(info: Database) -> terminfo::Result<()> {
-    if let Some(cap) = info.get::<cap::Reset1String>() {
-        cap.expand().to(io::stdout())?;
-    }
-    if let Some(cap) = info.get::<cap::Reset2String>() {
-        cap.expand().to(io::stdout())?;
+/// The size of the terminal on the standard streams, as (lines, columns).
+fn window_size() -> Option<(i32, i32)> {
+    for fd in [libc::STDOUT_FILENO, libc::STDIN_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: ws is a valid winsize for TIOCGWINSZ to fill in
+        let ws = unsafe {
+            let mut ws: libc::winsize = std::mem::zeroed();
+            if libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) != 0 {
+                continue;
+            }
+            ws
+        };
+        if ws.ws_row > 0 && ws.ws_col > 0 {
+            return Some((i32::from(ws.ws_row), i32::from(ws.ws_col)));
+        }
     }
-    if let Some(cap) = info.get::<cap::ResetFile>() {
-        // FIXME
-        cap.expand().to(io::stdout())?;
+    None
+}
+
+/// The number of lines or columns: from LINES or COLUMNS, else from the
+/// terminal itself, else from terminfo.
+fn screen_size(capname: &str, terminfo: Option<i32>) -> Option<i32> {
+    let (var, lines) = match capname {
+        "lines" => ("LINES", true),
+        "cols" | "columns" => ("COLUMNS", false),
+        _ => return terminfo,
+    };
+    if let Some(n) = std::env::var(var)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n: &i32| n > 0)
+    {
+        return Some(n);
     }
-    if let Some(cap) = info.get::<cap::Reset3String>() {
-        cap.expand().to(io::stdout())?;
+    match window_size() {
+        Some((rows, cols)) => Some(if lines { rows } else { cols }),
+        None => terminfo,
     }
+}
 
+/// Writes the first of `capnames` that the terminal has.
+fn first_string(info: &Database, capnames: &[&str], out: &mut Vec<u8>) -> terminfo::Result<()> {
+    for capname in capnames {
+        if let Some(Value::String(s)) = info.raw(capname) {
+            return expand(s, &[], out);
+        }
+    }
     Ok(())
 }
 
-fn tput_clear(info: Database) -> terminfo::Result<()> {
-    if let Some(clear) = info.get::<cap::ClearScreen>() {
-        clear.expand().to(io::stdout())?;
+/// Writes the initialization strings, or with `reset`, the reset
+/// strings, falling back to the initialization ones where those are
+/// missing.
+/// The init-file and reset-file capabilities name files to be copied.
+fn tput_init(info: &Database, reset: bool, out: &mut Vec<u8>) -> terminfo::Result<()> {
+    let pick = |reset_cap, init_cap| {
+        if reset {
+            vec![reset_cap, init_cap]
+        } else {
+            vec![init_cap]
+        }
+    };
+
+    first_string(info, &pick("rs1", "is1"), out)?;
+    first_string(info, &pick("rs2", "is2"), out)?;
+    for capname in pick("rf", "if") {
+        if let Some(Value::String(path)) = info.raw(capname) {
+            let path = String::from_utf8_lossy(path).into_owned();
+            out.extend(fs::read(path)?);
+            break;
+        }
     }
+    first_string(info, &pick("rs3", "is3"), out)
+}
 
-    Ok(())
+fn tput(info: &Database, operands: &[String], out: &mut Vec<u8>) -> Result<Status, String> {
+    let mut status = Status::new();
+    let mut operands = operands.iter();
+
+    while let Some(operand) = operands.next() {
+        let capname = operand.as_str();
+        if capname == "init" || capname == "reset" {
+            tput_init(info, capname == "reset", out).map_err(|e| error_message(&e))?;
+            continue;
+        }
+
+        match capability(info, capname) {
+            None => {
+                tdiag!("unknown terminfo capability '{}'", capname);
+                status.fail_with(EXIT_BAD_OPERAND);
+                break;
+            }
+            Some(Capability::Boolean(set)) => {
+                if !set {
+                    status.fail_with(EXIT_UNSET);
+                }
+            }
+            Some(Capability::Number(n)) => {
+                let n = screen_size(capname, n).unwrap_or(-1);
+                out.extend(format!("{}\n", n).into_bytes());
+            }
+            Some(Capability::String(None)) => status.fail_with(EXIT_UNSET),
+            Some(Capability::String(Some(s))) => {
+                let parameters: Vec<Parameter> = operands
+                    .by_ref()
+                    .take(parameter_count(s))
+                    .map(|arg| match arg.parse::<i32>() {
+                        Ok(n) => Parameter::Number(n),
+                        Err(_) => Parameter::String(arg.clone().into_bytes()),
+                    })
+                    .collect();
+                expand(s, &parameters, out)
+                    .map_err(|e| format!("{}: {}", capname, error_message(&e)))?;
+            }
+        }
+    }
+    Ok(status)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
-    let info = match args.term {
-        None => Database::from_env().unwrap(),
-        Some(termtype) => Database::from_name(termtype).unwrap(),
+    let Some(term) = args
+        .term
+        .or_else(|| std::env::var("TERM").ok())
+        .filter(|term| !term.is_empty())
+    else {
+        tdiag!("no terminal type specified");
+        std::process::exit(EXIT_NO_TERMINAL);
+    };
+    let info = match Database::from_name(&term) {
+        Ok(info) => info,
+        Err(_) => {
+            tdiag!("unknown terminal '{}'", term);
+            std::process::exit(EXIT_NO_TERMINAL);
+        }
     };
 
-    match args.operand.as_str() {
-        "clear" => tput_clear(info)?,
-        "init" => tput_init(info)?,
-        "reset" => tput_reset(info)?,
-        _ => {
-            eprintln!("{}", gettext("Unknown terminal command"));
-            std::process::exit(1);
+    let mut out = Vec::new();
+    let result = tput(&info, &args.operands, &mut out);
+
+    // What was expanded before an error is still written
+    let mut stdout = io::stdout();
+    if let Err(e) = stdout.write_all(&out).and_then(|_| stdout.flush()) {
+        plib::diag!("{}", error_message(&e));
+        std::process::exit(EXIT_ERROR);
+    }
+    match result {
+        Ok(status) => status.exit(),
+        Err(e) => {
+            plib::diag!("{}", e);
+            std::process::exit(EXIT_ERROR);
         }
     }
-
-    Ok(())
 }
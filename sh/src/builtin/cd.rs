@@ -7,14 +7,13 @@
 // SPDX-License-Identifier: MIT
 //
 
-use crate::builtin::{BuiltinResult, BuiltinUtility};
+use crate::builtin::{BuiltinError, BuiltinResult, BuiltinUtility};
 use crate::option_parser::OptionParser;
 use crate::shell::opened_files::OpenedFiles;
 use crate::shell::Shell;
-use std::ffi::{OsStr, OsString};
-use std::fmt::Display;
-use std::os::unix::ffi::{OsStrExt, OsStringExt};
-use std::path::PathBuf;
+use nix::unistd::getcwd;
+use std::ffi::OsString;
+use std::path::Path;
 
 #[derive(Debug, PartialEq, Eq)]
 enum CdArgs<'a> {
@@ -22,7 +21,9 @@ enum CdArgs<'a> {
         directory: Option<&'a str>,
         handle_dot_dot_physically: bool,
     },
-    GoBack,
+    GoBack {
+        handle_dot_dot_physically: bool,
+    },
 }
 
 impl<'a> CdArgs<'a> {
@@ -33,7 +34,7 @@ impl<'a> CdArgs<'a> {
 
         while let Some(option) = option_parser
             .next_option()
-            .map_err(|err| format!("cd: invalid option ({err})"))?
+            .map_err(|err| format!("invalid option ({err})"))?
         {
             match option {
                 'L' => {
@@ -43,14 +44,16 @@ impl<'a> CdArgs<'a> {
                     handle_dot_dot_physically = true;
                 }
                 _ => {
-                    return Err(format!("cd: invalid option -{option}"));
+                    return Err(format!("invalid option -{option}"));
                 }
             }
         }
 
         let directory = args.get(option_parser.next_argument()).map(|s| s.as_str());
         if directory.is_some_and(|op| op == "-") {
-            return Ok(Self::GoBack);
+            return Ok(Self::GoBack {
+                handle_dot_dot_physically,
+            });
         }
         Ok(Self::ChangeDir {
             directory,
@@ -59,8 +62,98 @@ impl<'a> CdArgs<'a> {
     }
 }
 
-fn io_err_to_string<Err: Display>(err: Err) -> String {
-    format!("cd: io error ({err})\n")
+/// Whether the first component of `dir` is dot or dot-dot
+fn starts_with_dot_component(dir: &str) -> bool {
+    matches!(dir.split('/').next(), Some(".") | Some(".."))
+}
+
+/// Looks for `dir` in the directories of `cdpath`, an empty entry being the
+/// current directory. Returns the path found, and whether it was found
+/// through a non-empty entry, in which case the new directory is written.
+fn search_cdpath(dir: &str, cdpath: &str) -> Option<(String, bool)> {
+    cdpath.split(':').find_map(|entry| {
+        let path = if entry.is_empty() {
+            format!("./{dir}")
+        } else if entry.ends_with('/') {
+            format!("{entry}{dir}")
+        } else {
+            format!("{entry}/{dir}")
+        };
+        Path::new(&path)
+            .is_dir()
+            .then_some((path, !entry.is_empty()))
+    })
+}
+
+/// Removes dot components, dot-dot components together with the components
+/// before them, and redundant slashes from the absolute `path`, without
+/// resolving symbolic links. Returns `None` if what comes before a dot-dot
+/// is not a directory.
+fn canonicalize_logically(path: &str) -> Option<String> {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                let preceding = format!("/{}", components.join("/"));
+                if !Path::new(&preceding).is_dir() {
+                    return None;
+                }
+                components.pop();
+            }
+            _ => components.push(component),
+        }
+    }
+    Some(format!("/{}", components.join("/")))
+}
+
+/// Changes the working directory to `dir` and updates `PWD` and `OLDPWD`,
+/// returning the new value of `PWD`, and whether it has to be written.
+fn change_directory(
+    dir: &str,
+    handle_dot_dot_physically: bool,
+    shell: &mut Shell,
+) -> Result<(String, bool), BuiltinError> {
+    let old_pwd = shell
+        .environment
+        .get_str_value("PWD")
+        .filter(|pwd| pwd.starts_with('/'))
+        .map(|pwd| pwd.to_string())
+        .unwrap_or_else(|| shell.current_directory.to_string_lossy().into_owned());
+
+    let mut found_in_cdpath = false;
+    let mut curr_path = dir.to_string();
+    if !dir.starts_with('/') && !starts_with_dot_component(dir) {
+        if let Some(cdpath) = shell.environment.get_str_value("CDPATH") {
+            if let Some((path, non_empty_entry)) = search_cdpath(dir, cdpath) {
+                curr_path = path;
+                found_in_cdpath = non_empty_entry;
+            }
+        }
+    }
+
+    let new_pwd = if handle_dot_dot_physically {
+        nix::unistd::chdir(curr_path.as_str())
+            .map_err(|err| format!("cd: {dir}: {}", err.desc()))?;
+        getcwd()
+            .map_err(|err| format!("cd: {}", err.desc()))?
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        if !curr_path.starts_with('/') {
+            curr_path = format!("{old_pwd}/{curr_path}");
+        }
+        let curr_path = canonicalize_logically(&curr_path)
+            .ok_or_else(|| format!("cd: {dir}: No such file or directory"))?;
+        nix::unistd::chdir(curr_path.as_str())
+            .map_err(|err| format!("cd: {dir}: {}", err.desc()))?;
+        curr_path
+    };
+
+    shell.current_directory = OsString::from(&new_pwd);
+    shell.assign_global("PWD".to_string(), new_pwd.clone())?;
+    shell.assign_global("OLDPWD".to_string(), old_pwd)?;
+    Ok((new_pwd, found_in_cdpath))
 }
 
 pub struct Cd;
@@ -75,101 +168,44 @@ impl BuiltinUtility for Cd {
         let args = match CdArgs::parse(args) {
             Ok(args) => args,
             Err(err) => {
-                return Err(format!("cd: {}\n", err).into());
+                return Err(format!("cd: {}", err).into());
             }
         };
 
-        match args {
+        let (dir, handle_dot_dot_physically, go_back) = match args {
             CdArgs::ChangeDir {
                 directory,
                 handle_dot_dot_physically,
             } => {
                 let dir = if let Some(dir) = directory {
-                    dir
+                    dir.to_string()
                 } else if let Some(home_dir) = shell.environment.get_str_value("HOME") {
-                    home_dir
+                    home_dir.to_string()
                 } else {
                     // behaviour is implementation defined, bash just returns 0
                     // and doesn't change directory
                     return Ok(0);
                 };
-                let mut curr_path = OsString::new();
-
-                if !dir.starts_with('/') {
-                    if !dir.starts_with('.') && !dir.starts_with("..") {
-                        if let Some(cdpath) = shell.environment.get_str_value("CDPATH") {
-                            for component in cdpath.split(':') {
-                                let mut path = PathBuf::from(component);
-                                path.push(dir);
-                                if path.exists() {
-                                    curr_path = path.into_os_string();
-                                }
-                            }
-                        } else {
-                            let path = PathBuf::from(dir);
-                            if path.exists() {
-                                curr_path = path.into_os_string();
-                            }
-                        }
-                    }
-                    if curr_path.is_empty() {
-                        curr_path = OsString::from_vec(dir.as_bytes().to_vec());
-                    }
-                } else {
-                    curr_path = OsString::from_vec(dir.as_bytes().to_vec());
-                }
-
-                if !handle_dot_dot_physically {
-                    if !curr_path.as_bytes().first().is_some_and(|c| *c == b'/') {
-                        let mut new_curr_path = shell
-                            .environment
-                            .get_str_value("PWD")
-                            .unwrap_or_default()
-                            .as_bytes()
-                            .to_vec();
-                        if new_curr_path.last().is_some_and(|c| *c != b'/') {
-                            new_curr_path.push(b'/');
-                        }
-                        new_curr_path.extend(curr_path.as_bytes());
-                        curr_path = OsString::from_vec(new_curr_path)
-                    }
-                    curr_path = PathBuf::from(curr_path)
-                        .canonicalize()
-                        .map_err(io_err_to_string)?
-                        .into_os_string();
-                }
-
-                let old_working_dir = std::env::current_dir().map_err(io_err_to_string)?;
-                nix::unistd::chdir(AsRef::<OsStr>::as_ref(&curr_path)).map_err(io_err_to_string)?;
-                shell.current_directory = curr_path.clone();
-
-                shell.assign_global("PWD".to_string(), curr_path.to_string_lossy().into_owned())?;
-                shell.assign_global(
-                    "OLDPWD".to_string(),
-                    old_working_dir.to_string_lossy().into_owned(),
-                )?;
+                (dir, handle_dot_dot_physically, false)
             }
-            CdArgs::GoBack => {
-                if let Some(oldpwd) = shell
-                    .environment
-                    .get_str_value("OLDPWD")
-                    .map(|s| s.to_string())
-                {
-                    let old_working_dir = std::env::current_dir().unwrap();
-                    nix::unistd::chdir(oldpwd.as_str()).map_err(io_err_to_string)?;
-                    shell.current_directory = OsString::from_vec(oldpwd.as_bytes().to_vec());
-                    shell.assign_global("PWD".to_string(), oldpwd.clone())?;
-                    shell.assign_global(
-                        "OLDPWD".to_string(),
-                        old_working_dir.to_string_lossy().into_owned(),
-                    )?;
-                    opened_files.write_out(format!("{}\n", oldpwd));
-                    return Ok(0);
-                }
-                return Err("cd: OLDPWD not set\n".into());
+            CdArgs::GoBack {
+                handle_dot_dot_physically,
+            } => {
+                // `cd -` is `cd "$OLDPWD" && pwd`
+                let Some(oldpwd) = shell.environment.get_str_value("OLDPWD") else {
+                    return Err("cd: OLDPWD not set".into());
+                };
+                (oldpwd.to_string(), handle_dot_dot_physically, true)
             }
-        }
+        };
 
+        if dir.is_empty() {
+            return Err("cd: empty directory".into());
+        }
+        let (new_pwd, found_in_cdpath) = change_directory(&dir, handle_dot_dot_physically, shell)?;
+        if go_back || found_in_cdpath {
+            opened_files.write_out(format!("{new_pwd}\n"));
+        }
         Ok(0)
     }
 }
@@ -258,7 +294,33 @@ mod tests {
 
     #[test]
     fn parse_go_back_no_args() {
-        parse_and_check_eq(vec!["-"], CdArgs::GoBack);
+        parse_and_check_eq(
+            vec!["-"],
+            CdArgs::GoBack {
+                handle_dot_dot_physically: false,
+            },
+        );
+        parse_and_check_eq(
+            vec!["-P", "-"],
+            CdArgs::GoBack {
+                handle_dot_dot_physically: true,
+            },
+        );
+    }
+
+    #[test]
+    fn canonicalize_removes_dot_and_dot_dot_components() {
+        assert_eq!(canonicalize_logically("/").as_deref(), Some("/"));
+        assert_eq!(
+            canonicalize_logically("//usr/./bin/").as_deref(),
+            Some("/usr/bin")
+        );
+        assert_eq!(
+            canonicalize_logically("/usr/bin/..").as_deref(),
+            Some("/usr")
+        );
+        assert_eq!(canonicalize_logically("/../..").as_deref(), Some("/"));
+        assert_eq!(canonicalize_logically("/nonexistent/.."), None);
     }
 
     #[test]
@@ -284,7 +346,12 @@ mod tests {
                 handle_dot_dot_physically: false,
             },
         );
-        parse_and_check_eq(vec!["--", "-"], CdArgs::GoBack);
+        parse_and_check_eq(
+            vec!["--", "-"],
+            CdArgs::GoBack {
+                handle_dot_dot_physically: false,
+            },
+        );
         parse_and_check_eq(
             vec!["-P", "--", "some_dir"],
             CdArgs::ChangeDir {
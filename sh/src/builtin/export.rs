@@ -11,6 +11,7 @@ use crate::builtin::{skip_option_terminator, BuiltinResult, SpecialBuiltinUtilit
 use crate::parse::command_parser::is_valid_name;
 use crate::shell::opened_files::OpenedFiles;
 use crate::shell::Shell;
+use crate::utils::single_quote;
 
 pub struct Export;
 
@@ -21,7 +22,7 @@ impl SpecialBuiltinUtility for Export {
         shell: &mut Shell,
         opened_files: &mut OpenedFiles,
     ) -> BuiltinResult {
-        if args.first().is_some_and(|arg| arg == "-p") {
+        if args.is_empty() || args[0] == "-p" {
             if args.len() > 1 && !(args.len() == 2 && args[1] == "--") {
                 return Err("export: too many arguments".into());
             }
@@ -34,7 +35,7 @@ impl SpecialBuiltinUtility for Export {
             pairs.sort_by_key(|(k, _)| k.as_str());
             for (var, var_value) in pairs {
                 if let Some(val) = &var_value.value {
-                    opened_files.write_out(format!("export {}='{}'\n", var, val));
+                    opened_files.write_out(format!("export {}={}\n", var, single_quote(val)));
                 } else {
                     opened_files.write_out(format!("export {}\n", var));
                 }
@@ -43,10 +44,6 @@ impl SpecialBuiltinUtility for Export {
         }
 
         let args = skip_option_terminator(args);
-        if args.is_empty() {
-            return Err("export: too few arguments".into());
-        }
-
         for arg in args {
             let (name, value) = if let Some(pos) = arg.find('=') {
                 let (name, value) = arg.split_at(pos);
@@ -56,7 +53,7 @@ impl SpecialBuiltinUtility for Export {
                 (name.to_string(), Some(value[1..].to_string()))
             } else {
                 if !is_valid_name(arg) {
-                    return Err(format!("export: '{arg}' is not a valid name\n").into());
+                    return Err(format!("export: '{arg}' is not a valid name").into());
                 }
                 (arg.clone(), None)
             };
@@ -10,20 +10,30 @@
 use crate::builtin::{parse_pid, skip_option_terminator, BuiltinResult, BuiltinUtility};
 use crate::shell::opened_files::OpenedFiles;
 use crate::shell::Shell;
-use crate::signals::{Signal, SIGNALS};
 use nix::sys::signal::kill;
 use nix::sys::signal::Signal as NixSignal;
-use std::str::FromStr;
+use plib::signal::{signal_name, signal_number, SIGNALS};
 
 enum KillArgs<'a> {
     SendSignal {
-        signal: Option<Signal>,
+        signal: Option<NixSignal>,
         pids: &'a [String],
     },
     ListAllSignals,
     NumberToSignal(&'a str),
 }
 
+/// Parses a signal name or number; the null signal "0" is `None`.
+fn parse_signal(name: &str) -> Result<Option<NixSignal>, String> {
+    match signal_number(name) {
+        Some(0) => Ok(None),
+        Some(number) => NixSignal::try_from(number)
+            .map(Some)
+            .map_err(|_| format!("kill: invalid signal '{}'", name)),
+        None => Err(format!("kill: invalid signal '{}'", name)),
+    }
+}
+
 fn get_pids(args: &[String]) -> Result<&[String], String> {
     let pids = skip_option_terminator(args);
     if pids.is_empty() {
@@ -50,24 +60,13 @@ impl<'a> KillArgs<'a> {
         }
 
         if args[0] == "-s" && args.len() > 2 {
-            let signal = Signal::from_str(&args[1])
-                .map_err(|_| format!("kill: invalid signal '{}'", args[1]))?;
+            let signal = parse_signal(&args[1])?;
             let pids = get_pids(&args[2..])?;
-            return Ok(KillArgs::SendSignal {
-                pids,
-                signal: Some(signal),
-            });
+            return Ok(KillArgs::SendSignal { pids, signal });
         }
 
         if args[0].starts_with('-') && &args[0] != "--" {
-            let signal = if &args[0][1..] == "0" {
-                None
-            } else {
-                Some(
-                    Signal::from_str(&args[0][1..])
-                        .map_err(|_| format!("kill: invalid signal '{}'", args[0]))?,
-                )
-            };
+            let signal = parse_signal(&args[0][1..])?;
             let pids = get_pids(&args[1..])?;
             return Ok(KillArgs::SendSignal { pids, signal });
         }
@@ -75,7 +74,7 @@ impl<'a> KillArgs<'a> {
         let pids = get_pids(args)?;
         Ok(KillArgs::SendSignal {
             pids,
-            signal: Some(Signal::SigTerm),
+            signal: Some(NixSignal::SIGTERM),
         })
     }
 }
@@ -95,13 +94,13 @@ impl BuiltinUtility for Kill {
             KillArgs::SendSignal { signal, pids } => {
                 for pid in pids {
                     let pid = parse_pid(pid, shell).map_err(|err| format!("kill: {err}"))?;
-                    kill(pid, signal.map(NixSignal::from))
+                    kill(pid, signal)
                         .map_err(|err| format!("kill: failed to send signal ({})", err))?;
                 }
             }
             KillArgs::ListAllSignals => {
-                for signal in SIGNALS {
-                    opened_files.write_out(format!("{}\n", signal));
+                for (name, _) in SIGNALS {
+                    opened_files.write_out(format!("{}\n", name));
                 }
             }
             KillArgs::NumberToSignal(signal) => {
@@ -109,8 +108,8 @@ impl BuiltinUtility for Kill {
                 if signal > 128 {
                     signal -= 128;
                 }
-                if let Ok(signal) = Signal::try_from(signal) {
-                    opened_files.write_out(format!("{}\n", signal));
+                if let Some(name) = signal_name(signal) {
+                    opened_files.write_out(format!("{}\n", name));
                 } else {
                     return Err(format!("kill: invalid signal '{}'", signal).into());
                 }
@@ -23,10 +23,12 @@ use crate::builtin::getopts::GetOpts;
 use crate::builtin::hash::Hash;
 use crate::builtin::jobs::Jobs;
 use crate::builtin::kill::Kill;
+use crate::builtin::printf::Printf;
 use crate::builtin::read::BuiltinRead;
 use crate::builtin::readonly::ReadOnly;
 use crate::builtin::set::SetSpecialBuiltin;
 use crate::builtin::shift::Shift;
+use crate::builtin::test::Test;
 use crate::builtin::times::Times;
 use crate::builtin::trap::Trap;
 use crate::builtin::type_::Type_;
@@ -60,10 +62,12 @@ mod getopts;
 mod hash;
 mod jobs;
 mod kill;
+mod printf;
 mod read;
 mod readonly;
 pub mod set;
 mod shift;
+mod test;
 mod times;
 pub mod trap;
 mod type_;
@@ -184,6 +188,9 @@ pub fn get_builtin_utility(name: &str) -> Option<&dyn BuiltinUtility> {
         "jobs" => Some(&Jobs),
         "type" => Some(&Type_),
         "unalias" => Some(&Unalias),
+        "printf" => Some(&Printf),
+        "test" => Some(&Test { bracket: false }),
+        "[" => Some(&Test { bracket: true }),
         _ => None,
     }
 }
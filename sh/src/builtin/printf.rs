@@ -0,0 +1,36 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::builtin::{BuiltinResult, BuiltinUtility};
+use crate::shell::opened_files::OpenedFiles;
+use crate::shell::Shell;
+
+pub struct Printf;
+
+impl BuiltinUtility for Printf {
+    fn exec(
+        &self,
+        args: &[String],
+        _: &mut Shell,
+        opened_files: &mut OpenedFiles,
+    ) -> BuiltinResult {
+        let Some((format, args)) = args.split_first() else {
+            return Err("printf: missing format operand".into());
+        };
+        let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+        let printed = plib::printf::printf(format.as_bytes(), &args)
+            .map_err(|err| format!("printf: {err}"))?;
+
+        opened_files.write_out_bytes(&printed.output);
+        for error in &printed.errors {
+            opened_files.write_err(format!("printf: {error}\n"));
+        }
+        Ok(if printed.errors.is_empty() { 0 } else { 1 })
+    }
+}
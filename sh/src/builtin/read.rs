@@ -161,44 +161,6 @@ fn read_from_stdin(
     }
 }
 
-fn read_from_here_document(content: &str, delimiter: u8, backslash_escape: bool) -> ReadResult {
-    let mut buffer = String::new();
-    let mut result = ExpandedWord::default();
-    let mut reached_eof = true;
-    let mut escape_next = false;
-    for c in content.chars() {
-        if c == delimiter as char {
-            reached_eof = false;
-            break;
-        }
-        if backslash_escape && c == '\\' {
-            if escape_next {
-                buffer.push('\\');
-                escape_next = false;
-            } else {
-                escape_next = true
-            }
-            continue;
-        }
-
-        if escape_next {
-            result.append(buffer, false, true);
-            result.append(c.to_string(), true, true);
-            buffer = String::new();
-            escape_next = false;
-        } else {
-            buffer.push(c);
-        }
-    }
-    if !buffer.is_empty() {
-        result.append(buffer, false, true);
-    }
-    ReadResult {
-        contents: result,
-        reached_eof,
-    }
-}
-
 fn read_until(
     shell: &mut Shell,
     file: &OpenedFile,
@@ -213,11 +175,6 @@ fn read_until(
         OpenedFile::ReadWriteFile(file) => {
             read_until_from_file(file.as_raw_fd(), delimiter, backslash_escape)
         }
-        OpenedFile::HereDocument(contents) => Ok(read_from_here_document(
-            contents,
-            delimiter,
-            backslash_escape,
-        )),
         _ => Err("read: invalid standard input".into()),
     }
 }
@@ -277,7 +234,7 @@ impl BuiltinUtility for BuiltinRead {
         let fields = split_fields(
             input.contents,
             shell.environment.get_str_value("IFS"),
-            args.len(),
+            vars.len(),
         );
 
         for i in 0..fields.len() {
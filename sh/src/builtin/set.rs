@@ -130,6 +130,7 @@ impl SetOptions {
         match long_option {
             "allexport" => self.allexport = value,
             "errexit" => self.errexit = value,
+            "hashall" => self.hashall = value,
             "ignoreeof" => self.ignoreeof = value,
             "monitor" => self.monitor = value,
             "noclobber" => self.noclobber = value,
@@ -138,6 +139,7 @@ impl SetOptions {
             "nolog" => self.nolog = value,
             "notify" => self.notify = value,
             "nounset" => self.nounset = value,
+            "verbose" => self.verbose = value,
             "vi" => self.vi = value,
             "xtrace" => self.xtrace = value,
             _ => return Err(format!("invalid option '{}'", long_option)),
@@ -185,6 +187,8 @@ impl SetOptions {
         result
     }
 
+    /// The commands that restore the current options, every option the
+    /// shell supports being listed.
     pub fn to_string_shell_readable(&self) -> String {
         use std::fmt::Write;
 
@@ -251,13 +255,8 @@ impl SetOptions {
         if args.is_empty() {
             return Ok(ParsedArgs::PrintVars);
         }
-        if args.len() == 1 {
-            match args[0].as_str() {
-                "-o" => return Ok(ParsedArgs::PrintSettingsHumanReadable),
-                "+o" => return Ok(ParsedArgs::PrintSettingsShellReadable),
-                "--" => return Ok(ParsedArgs::ResetPositionalParameters),
-                _ => {}
-            }
+        if args.len() == 1 && args[0] == "--" {
+            return Ok(ParsedArgs::ResetPositionalParameters);
         }
         let mut i = 0;
         while i < args.len() {
@@ -266,13 +265,13 @@ impl SetOptions {
                     i += 1;
                     break;
                 }
+                // without an option name, as the last argument, the
+                // options are printed after any others are set
+                "-o" if i == args.len() - 1 => return Ok(ParsedArgs::PrintSettingsHumanReadable),
+                "+o" if i == args.len() - 1 => return Ok(ParsedArgs::PrintSettingsShellReadable),
                 "-o" | "+o" => {
                     i += 1;
-                    let option = args
-                        .get(i)
-                        .ok_or_else(|| "expected option".to_string())?
-                        .as_str();
-                    self.set_long(option, args[i - 1] == "-o")?
+                    self.set_long(&args[i], args[i - 1] == "-o")?
                 }
                 s if s.starts_with('-') || s.starts_with('+') => {
                     let option_value = s.starts_with('-');
@@ -420,6 +419,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn print_after_setting_options() {
+        let (options, parse_result) = parse_args(vec!["-e", "+o"]);
+        assert_eq!(parse_result, ParsedArgs::PrintSettingsShellReadable);
+        assert!(options.errexit);
+    }
+
+    #[test]
+    fn shell_readable_output_restores_options() {
+        let options = SetOptions {
+            allexport: true,
+            notify: true,
+            noclobber: true,
+            errexit: true,
+            noglob: true,
+            hashall: false,
+            monitor: true,
+            noexec: true,
+            nounset: true,
+            verbose: true,
+            xtrace: true,
+            ignoreeof: true,
+            nolog: true,
+            vi: true,
+        };
+        for expected in [options, SetOptions::default()] {
+            let mut restored = SetOptions {
+                hashall: !expected.hashall,
+                ..Default::default()
+            };
+            for line in expected.to_string_shell_readable().lines() {
+                let args = line
+                    .split(' ')
+                    .skip(1)
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                restored.parse_args_and_update(&args).unwrap();
+            }
+            assert_eq!(restored, expected);
+        }
+    }
+
     #[test]
     fn parse_long_options_with_args() {
         let (options, parse_result) =
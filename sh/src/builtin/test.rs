@@ -0,0 +1,48 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::builtin::{BuiltinResult, BuiltinUtility};
+use crate::shell::opened_files::OpenedFiles;
+use crate::shell::Shell;
+
+/// `test`, or with `bracket`, `[`, whose arguments end with `]`.
+pub struct Test {
+    pub bracket: bool,
+}
+
+impl BuiltinUtility for Test {
+    fn exec(
+        &self,
+        args: &[String],
+        _: &mut Shell,
+        opened_files: &mut OpenedFiles,
+    ) -> BuiltinResult {
+        let (name, args) = if self.bracket {
+            match args.split_last() {
+                Some((last, args)) if last == "]" => ("[", args),
+                _ => {
+                    opened_files.write_err("[: missing ']'\n");
+                    return Ok(2);
+                }
+            }
+        } else {
+            ("test", args)
+        };
+
+        let is_terminal = |fd: i32| fd >= 0 && opened_files.is_terminal(fd as u32);
+        match plib::testexpr::test(args, &is_terminal) {
+            Ok(true) => Ok(0),
+            Ok(false) => Ok(1),
+            Err(err) => {
+                opened_files.write_err(format!("{name}: {err}\n"));
+                Ok(2)
+            }
+        }
+    }
+}
@@ -13,6 +13,7 @@ use crate::shell::opened_files::OpenedFiles;
 use crate::shell::Shell;
 use nix::libc::{rlim_t, RLIM_INFINITY};
 use nix::sys::resource::Resource;
+use plib::sizestr::{parse_size_as, SizeError};
 use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -41,14 +42,15 @@ enum LimitQuantity {
 }
 
 impl LimitQuantity {
-    fn parse(value: &str) -> Result<Self, ()> {
+    /// Parses a limit, a decimal count of the units of its resource. Size
+    /// suffixes are not accepted.
+    fn parse(value: &str) -> Result<Self, SizeError> {
         if value == "unlimited" {
             Ok(LimitQuantity::Unlimited)
+        } else if !value.bytes().all(|b| b.is_ascii_digit()) {
+            Err(SizeError::InvalidNumber)
         } else {
-            value
-                .parse::<rlim_t>()
-                .map(LimitQuantity::Number)
-                .map_err(|_| ())
+            parse_size_as(value).map(LimitQuantity::Number)
         }
     }
 
@@ -131,7 +133,7 @@ impl UlimitArgs {
         let newlimit = if let Some(arg) = args.get(first_operant) {
             Some(
                 LimitQuantity::parse(arg)
-                    .map_err(|_| format!("ulimit: '{arg}' is not a valid limit"))?,
+                    .map_err(|err| format!("ulimit: '{arg}' is not a valid limit ({err})"))?,
             )
         } else {
             None
@@ -46,9 +46,7 @@ impl SpecialBuiltinUtility for BuiltinUnset {
         for name in &args[parser.next_argument()..] {
             if unset_var {
                 if shell.environment.unset(name).is_err() {
-                    return Err(
-                        format!("unset: cannot unset readonly variable '{}'\n", name).into(),
-                    );
+                    return Err(format!("unset: cannot unset readonly variable '{}'", name).into());
                 }
             } else {
                 shell.functions.remove(name.as_str());
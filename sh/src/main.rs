@@ -11,13 +11,10 @@ use crate::cli::args::{parse_args, ExecutionMode};
 use crate::cli::terminal::is_attached_to_terminal;
 use crate::cli::{clear_line, set_cursor_pos};
 use crate::shell::Shell;
-use crate::signals::{
-    handle_signal_ignore, handle_signal_write_to_signal_buffer, setup_signal_handling, Signal,
-};
+use crate::signals::{handle_signal_ignore, handle_signal_write_to_signal_buffer, Signal};
 use crate::utils::is_process_in_foreground;
 use cli::terminal::read_nonblocking_char;
 use cli::vi::{Action, ViEditor};
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use std::error::Error;
 use std::io;
 use std::io::Write;
@@ -232,14 +229,14 @@ fn interactive_shell(shell: &mut Shell) {
         nix::unistd::tcsetpgrp(io::stdin().as_fd(), pgid).unwrap();
     }
     shell.terminal.set_nonblocking_no_echo();
-    unsafe { handle_signal_ignore(Signal::SigQuit) }
-    unsafe { handle_signal_ignore(Signal::SigTerm) }
-    unsafe { handle_signal_write_to_signal_buffer(Signal::SigInt) }
+    handle_signal_ignore(Signal::SigQuit);
+    handle_signal_ignore(Signal::SigTerm);
+    handle_signal_write_to_signal_buffer(Signal::SigInt);
     if shell.set_options.monitor {
         // job control signals
-        unsafe { handle_signal_ignore(Signal::SigTtin) }
-        unsafe { handle_signal_ignore(Signal::SigTtou) }
-        unsafe { handle_signal_ignore(Signal::SigTstp) }
+        handle_signal_ignore(Signal::SigTtin);
+        handle_signal_ignore(Signal::SigTtou);
+        handle_signal_ignore(Signal::SigTstp);
     }
     loop {
         if shell.set_options.vi {
@@ -251,9 +248,7 @@ fn interactive_shell(shell: &mut Shell) {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    plib::i18n::init();
 
     let args = match parse_args(std::env::args().collect(), is_attached_to_terminal()) {
         Ok(args) => args,
@@ -268,7 +263,6 @@ fn main() -> Result<(), Box<dyn Error>> {
         args.set_options,
         args.execution_mode == ExecutionMode::Interactive,
     );
-    unsafe { setup_signal_handling() };
     match args.execution_mode {
         ExecutionMode::Interactive => interactive_shell(&mut shell),
         ExecutionMode::ReadCommandsFromStdin => {
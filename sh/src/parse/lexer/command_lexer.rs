@@ -7,8 +7,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use crate::parse::lexer::{is_blank, remove_delimiter_from_here_document, HereDocument, Lexer};
-use crate::parse::ParseResult;
+use crate::parse::lexer::word_lexer::remove_quotes;
+use crate::parse::lexer::{is_blank, Lexer};
+use crate::parse::{ParseResult, ParserError};
 use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 
@@ -421,6 +422,9 @@ fn char_to_operator_token(c: char) -> Option<CommandToken<'static>> {
 pub struct CommandLexer<'src> {
     source: SourceString<'src>,
     prev_read_state: SourceReadState,
+    // end delimiter, whether leading tabs are removed and starting line of
+    // the here-documents whose contents follow the current line
+    pending_here_documents: Vec<(String, bool, u32)>,
 }
 
 impl Lexer for CommandLexer<'_> {
@@ -471,38 +475,107 @@ impl<'src> CommandLexer<'src> {
         Ok(result)
     }
 
-    fn read_here_document(&mut self, remove_leading_tabs: bool) -> ParseResult<CommandToken<'src>> {
-        let start = self.source.read_state.clone();
-        let is_quoted = self.skip_here_document()?;
-        let here_document = remove_delimiter_from_here_document(
-            self.source.substr(&start, &self.source.read_state),
-        );
+    /// Moves the read position to the start of the next line.
+    fn skip_to_next_line(&mut self) {
+        while !self.reached_eof() {
+            let c = self.source.lookahead();
+            self.source.advance_char();
+            if c == '\n' {
+                break;
+            }
+        }
+    }
 
-        let here_document = if remove_leading_tabs {
-            let mut contents = String::new();
-            for line in here_document.contents.lines() {
-                contents.push_str(line.trim_start_matches('\t'));
-                contents.push('\n');
+    /// Reads the lines of a here-document up to the line containing only
+    /// `end_delimiter`, which is consumed but not returned.
+    fn read_here_document_contents(
+        &mut self,
+        end_delimiter: &str,
+        remove_leading_tabs: bool,
+        start_lineno: u32,
+    ) -> ParseResult<Cow<'src, str>> {
+        let start = self.source.read_state.clone();
+        let mut contents = String::new();
+        loop {
+            if self.reached_eof() {
+                return Err(ParserError::new(
+                    start_lineno,
+                    "unterminated here-document",
+                    true,
+                ));
             }
-            HereDocument {
-                contents: contents.into(),
-                start_delimiter: here_document.start_delimiter,
-                end_delimiter: here_document.end_delimiter,
+            let line_start = self.source.read_state.clone();
+            self.skip_to_next_line();
+            let raw_line = self.source.substr(&line_start, &self.source.read_state);
+            let line = if remove_leading_tabs {
+                raw_line.trim_start_matches('\t')
+            } else {
+                raw_line.as_ref()
+            };
+            if line.strip_suffix('\n').unwrap_or(line) == end_delimiter {
+                if remove_leading_tabs {
+                    return Ok(contents.into());
+                }
+                return Ok(self.source.substr(&start, &line_start));
             }
-        } else {
-            here_document
-        };
+            if remove_leading_tabs {
+                contents.push_str(line);
+            }
+        }
+    }
+
+    /// Skips the contents of the here-documents started on the line that
+    /// was just terminated.
+    fn skip_pending_here_documents(&mut self) -> ParseResult<()> {
+        for (end_delimiter, remove_leading_tabs, start_lineno) in
+            std::mem::take(&mut self.pending_here_documents)
+        {
+            self.read_here_document_contents(&end_delimiter, remove_leading_tabs, start_lineno)?;
+        }
+        Ok(())
+    }
+
+    fn read_here_document(&mut self, remove_leading_tabs: bool) -> ParseResult<CommandToken<'src>> {
+        let start_lineno = self.line_no();
+        self.skip_blanks();
+        let start_delimiter = self.read_word_token()?;
+        if start_delimiter.is_empty() {
+            return Err(ParserError::new(
+                start_lineno,
+                "missing here-document delimiter",
+                self.reached_eof(),
+            ));
+        }
+        let (is_quoted, end_delimiter) = remove_quotes(start_delimiter.as_ref());
+
+        // the contents start on the next line, after the contents of any
+        // previous here-document on the same line
+        let after_delimiter = self.source.read_state.clone();
+        self.skip_to_next_line();
+        for (end_delimiter, remove_leading_tabs, start_lineno) in
+            self.pending_here_documents.clone()
+        {
+            self.read_here_document_contents(&end_delimiter, remove_leading_tabs, start_lineno)?;
+        }
+        let contents =
+            self.read_here_document_contents(&end_delimiter, remove_leading_tabs, start_lineno)?;
+        self.source.read_state = after_delimiter;
+        self.pending_here_documents.push((
+            end_delimiter.clone(),
+            remove_leading_tabs,
+            start_lineno,
+        ));
+
         if is_quoted {
             Ok(CommandToken::QuotedHereDocument {
-                start_delimiter: here_document.start_delimiter,
-                end_delimiter: here_document.end_delimiter,
-                contents: here_document.contents,
+                start_delimiter,
+                end_delimiter: end_delimiter.into(),
+                contents,
             })
         } else {
-            assert_eq!(here_document.start_delimiter, here_document.end_delimiter);
             Ok(CommandToken::HereDocument {
-                delimiter: here_document.start_delimiter,
-                contents: here_document.contents,
+                delimiter: start_delimiter,
+                contents,
             })
         }
     }
@@ -556,6 +629,10 @@ impl<'src> CommandLexer<'src> {
                     '|' => advance_and_return(self, CommandToken::Clobber),
                     _ => CommandToken::Greater,
                 },
+                CommandToken::Newline => {
+                    self.skip_pending_here_documents()?;
+                    CommandToken::Newline
+                }
                 other => other,
             };
             return Ok((complete_token, line_no));
@@ -607,6 +684,7 @@ impl<'src> CommandLexer<'src> {
         Self {
             source,
             prev_read_state: initial_read_state,
+            pending_here_documents: Vec::new(),
         }
     }
 }
@@ -625,13 +703,6 @@ mod tests {
         }
     }
 
-    fn lex_token(token: &str) -> CommandToken {
-        let mut lex = CommandLexer::new(token);
-        let token = lex.next_token().unwrap().0;
-        assert_eq!(lex.next_token().unwrap().0, CommandToken::Eof);
-        token
-    }
-
     #[test]
     fn lex_empty_string() {
         let mut lex = CommandLexer::new("");
@@ -780,28 +851,43 @@ mod tests {
         lex_word("test${var:-$(cmd)}'quoted'$(command with args)$((1 + 1))");
     }
 
+    fn lex_here_document(text: &str) -> CommandToken {
+        let mut lex = CommandLexer::new(text);
+        let token = lex.next_token().unwrap().0;
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Newline);
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Eof);
+        token
+    }
+
     #[test]
     fn here_document() {
         assert_eq!(
-            lex_token("<<end\nthis\nis\n\ta\ntest\nend\n"),
+            lex_here_document("<<end\nthis\nis\n\ta\ntest\nend\n"),
             CommandToken::HereDocument {
                 delimiter: "end".into(),
                 contents: "this\nis\n\ta\ntest\n".into()
             }
         );
         assert_eq!(
-            lex_token("<<-end\nthis\nis\n\ta\n\t\t\t\ttest\nend\n"),
+            lex_here_document("<<-end\nthis\nis\n\ta\n\t\t\t\ttest\n\tend\n"),
             CommandToken::HereDocument {
                 delimiter: "end".into(),
                 contents: "this\nis\na\ntest\n".into()
             }
-        )
+        );
+        assert_eq!(
+            lex_here_document("<< end\nthis\nend\n"),
+            CommandToken::HereDocument {
+                delimiter: "end".into(),
+                contents: "this\n".into()
+            }
+        );
     }
 
     #[test]
     fn quoted_here_document() {
         assert_eq!(
-            lex_token("<<\\end\nthis\nis\n\ta\ntest\nend\n"),
+            lex_here_document("<<\\end\nthis\nis\n\ta\ntest\nend\n"),
             CommandToken::QuotedHereDocument {
                 start_delimiter: "\\end".into(),
                 end_delimiter: "end".into(),
@@ -809,7 +895,7 @@ mod tests {
             }
         );
         assert_eq!(
-            lex_token("<<-\\end\nthis\nis\n\ta\n\t\t\t\ttest\nend\n"),
+            lex_here_document("<<-\\end\nthis\nis\n\ta\n\t\t\t\ttest\nend\n"),
             CommandToken::QuotedHereDocument {
                 start_delimiter: "\\end".into(),
                 end_delimiter: "end".into(),
@@ -818,6 +904,55 @@ mod tests {
         )
     }
 
+    #[test]
+    fn here_document_followed_by_tokens() {
+        let mut lex = CommandLexer::new("<<end | cmd\nthis\nend\nnext");
+        assert_eq!(
+            lex.next_token().unwrap().0,
+            CommandToken::HereDocument {
+                delimiter: "end".into(),
+                contents: "this\n".into()
+            }
+        );
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Pipe);
+        assert_eq!(
+            lex.next_token().unwrap().0,
+            CommandToken::Word("cmd".into())
+        );
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Newline);
+        assert_eq!(
+            lex.next_token().unwrap(),
+            (CommandToken::Word("next".into()), 4)
+        );
+    }
+
+    #[test]
+    fn multiple_here_documents_on_one_line() {
+        let mut lex = CommandLexer::new("<<a <<-b\nfirst\na\n\tsecond\n\tb\n");
+        assert_eq!(
+            lex.next_token().unwrap().0,
+            CommandToken::HereDocument {
+                delimiter: "a".into(),
+                contents: "first\n".into()
+            }
+        );
+        assert_eq!(
+            lex.next_token().unwrap().0,
+            CommandToken::HereDocument {
+                delimiter: "b".into(),
+                contents: "second\n".into()
+            }
+        );
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Newline);
+        assert_eq!(lex.next_token().unwrap().0, CommandToken::Eof);
+    }
+
+    #[test]
+    fn unterminated_here_document() {
+        assert!(CommandLexer::new("<<end\nthis\n").next_token().is_err());
+        assert!(CommandLexer::new("<<\n").next_token().is_err());
+    }
+
     #[test]
     fn lex_io_number() {
         let mut lex = CommandLexer::new("123>");
@@ -57,8 +57,11 @@ trait Lexer {
         Ok(())
     }
 
-    fn skip_here_document(&mut self) -> ParseResult<bool> {
+    fn skip_here_document(&mut self, remove_leading_tabs: bool) -> ParseResult<bool> {
         let start_lineno = self.line_no();
+        while is_blank(self.lookahead()) {
+            self.advance();
+        }
         let (quoted_terminator, end) = remove_quotes(self.next_word()?.as_ref());
         loop {
             if self.reached_eof() {
@@ -69,6 +72,11 @@ trait Lexer {
                 ));
             }
             let line = self.next_line();
+            let line = if remove_leading_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line.as_ref()
+            };
             if line.trim_end_matches('\n') == end {
                 break;
             }
@@ -97,7 +105,7 @@ trait Lexer {
     }
 
     fn skip_parameter_expansion(&mut self) -> ParseResult<()> {
-        self.skip_word_token(Some('}'), false)?;
+        self.skip_word_token(Some('}'), true)?;
         if self.lookahead() != '}' {
             return Err(ParserError::new(
                 self.line_no(),
@@ -155,9 +163,9 @@ trait Lexer {
                         self.advance();
                         if self.lookahead() == '-' {
                             self.advance();
-                            self.skip_here_document()?;
+                            self.skip_here_document(true)?;
                         } else {
-                            self.skip_here_document()?;
+                            self.skip_here_document(false)?;
                         }
                     }
                     // don't advance char
@@ -317,28 +325,3 @@ trait Lexer {
         Ok(())
     }
 }
-
-struct HereDocument<'src> {
-    start_delimiter: Cow<'src, str>,
-    end_delimiter: Cow<'src, str>,
-    contents: Cow<'src, str>,
-}
-
-fn remove_delimiter_from_here_document(here_document: Cow<str>) -> HereDocument {
-    let start_delimiter_end = here_document.find('\n').unwrap();
-    let end_delimiter_start = here_document.trim_end_matches('\n').rfind('\n').unwrap();
-    match here_document {
-        Cow::Borrowed(str) => HereDocument {
-            start_delimiter: str[0..start_delimiter_end].into(),
-            end_delimiter: str[end_delimiter_start + 1..].trim_end_matches('\n').into(),
-            contents: str[start_delimiter_end + 1..=end_delimiter_start].into(),
-        },
-        Cow::Owned(str) => HereDocument {
-            start_delimiter: str[0..start_delimiter_end].to_owned().into(),
-            end_delimiter: str[end_delimiter_start + 1..].to_owned().into(),
-            contents: str[start_delimiter_end + 1..=end_delimiter_start]
-                .to_owned()
-                .into(),
-        },
-    }
-}
@@ -86,10 +86,17 @@ impl Lexer for WordLexer<'_> {
 
     fn next_line(&mut self) -> Cow<str> {
         let start = self.position;
-        while self.lookahead != '\n' {
+        while self.lookahead != '\n' && !self.reached_eof {
             self.advance()
         }
-        self.source[start..self.position].into()
+        let end = if self.reached_eof {
+            self.source.len()
+        } else {
+            self.position
+        };
+        // skip the newline
+        self.advance();
+        self.source[start..end].into()
     }
 
     fn next_word(&mut self) -> ParseResult<Cow<str>> {
@@ -135,7 +142,13 @@ impl<'src> WordLexer<'src> {
                         self.advance();
                         let start = self.position;
                         self.skip_arithmetic_expansion().expect("invalid word");
-                        WordToken::ArithmeticExpansion(&self.source[start..self.position - 1])
+                        // the expansion ends before the closing "))"
+                        let end = if self.reached_eof {
+                            self.source.len()
+                        } else {
+                            self.position
+                        };
+                        WordToken::ArithmeticExpansion(&self.source[start..end - 2])
                     } else {
                         let start = self.position;
                         self.skip_command_substitution().expect("invalid word");
@@ -314,5 +327,8 @@ mod tests {
             lex_token("$(((1) + (1)))"),
             WordToken::ArithmeticExpansion("(1) + (1)")
         );
+        let mut lex = WordLexer::new("$((1 + 1))x");
+        assert_eq!(lex.next_token(), WordToken::ArithmeticExpansion("1 + 1"));
+        assert_eq!(lex.next_token(), WordToken::Char('x'));
     }
 }
@@ -103,24 +103,19 @@ impl<'src> WordParser<'src> {
         if self.lookahead == WordToken::Char('{') {
             self.advance();
 
-            if self.lookahead == WordToken::Char('#') {
+            let parameter = if self.lookahead == WordToken::Char('#') {
                 self.advance();
-                let parameter = self.parse_parameter(false)?;
-                let expansion = match parameter {
-                    Parameter::Special(SpecialParameter::Asterisk)
-                    | Parameter::Special(SpecialParameter::At) => {
-                        return Err(ParserError::new(
-                            self.line_no,
-                            "length of '*' or '@' is unspecified",
-                            false,
-                        ))
-                    }
-                    other => ParameterExpansion::StrLen(other),
-                };
-                self.match_token(WordToken::Char('}'))?;
-                return Ok(expansion);
-            }
-            let parameter = self.parse_parameter(false)?;
+                if matches!(self.lookahead, WordToken::Char('}') | WordToken::Char(':')) {
+                    // ${#} or ${#:...}, the '#' is the special parameter
+                    Parameter::Special(SpecialParameter::Hash)
+                } else {
+                    let parameter = self.parse_parameter(false)?;
+                    self.match_token(WordToken::Char('}'))?;
+                    return Ok(ParameterExpansion::StrLen(parameter));
+                }
+            } else {
+                self.parse_parameter(false)?
+            };
 
             let operator_loc = self.line_no;
             match self.advance() {
@@ -686,6 +681,30 @@ mod tests {
             parse_unquoted_parameter_expansion("${#test}"),
             ParameterExpansion::StrLen(Parameter::Variable(Rc::from("test")))
         );
+        assert_eq!(
+            parse_unquoted_parameter_expansion("${#1}"),
+            ParameterExpansion::StrLen(Parameter::Number(1))
+        );
+        assert_eq!(
+            parse_unquoted_parameter_expansion("${#@}"),
+            ParameterExpansion::StrLen(Parameter::Special(SpecialParameter::At))
+        );
+        assert_eq!(
+            parse_unquoted_parameter_expansion("${##}"),
+            ParameterExpansion::StrLen(Parameter::Special(SpecialParameter::Hash))
+        );
+        assert_eq!(
+            parse_unquoted_parameter_expansion("${#}"),
+            ParameterExpansion::Simple(Parameter::Special(SpecialParameter::Hash))
+        );
+        assert_eq!(
+            parse_unquoted_parameter_expansion("${#:-default}"),
+            ParameterExpansion::UnsetUseDefault {
+                parameter: Parameter::Special(SpecialParameter::Hash),
+                word: unquoted_literal("default"),
+                default_on_null: true,
+            }
+        );
         assert_eq!(
             parse_unquoted_parameter_expansion("${test%pattern}"),
             ParameterExpansion::RemovePattern {
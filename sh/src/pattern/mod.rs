@@ -30,100 +30,64 @@ impl Pattern {
         })
     }
 
+    /// Returns true if the pattern matches the whole string
     pub fn matches(&self, s: &CStr) -> bool {
-        self.regex.matches(s)
+        self.regex.matches_all(s)
     }
 
-    pub fn remove_largest_prefix(&self, s: String) -> String {
-        if self.pattern_string.is_empty() || s.is_empty() {
-            return s;
-        }
+    fn matches_str(&self, s: &str) -> bool {
         let cstring = CString::new(s).expect("trying to match a string containing null");
-        let mut prefix_end = 0;
-        if let Some(regex_match) = self.regex.match_locations(&cstring).next() {
-            if regex_match.start == 0 {
-                prefix_end = regex_match.end;
-            }
+        self.matches(&cstring)
+    }
+
+    /// Returns the length of the shortest (or largest) prefix of `s` matched
+    /// by the pattern, if any
+    fn prefix_len(&self, s: &str, largest: bool) -> Option<usize> {
+        let mut ends = (0..=s.len()).filter(|i| s.is_char_boundary(*i));
+        if largest {
+            ends.rev().find(|end| self.matches_str(&s[..*end]))
+        } else {
+            ends.find(|end| self.matches_str(&s[..*end]))
         }
-        let mut bytes = cstring.into_bytes();
-        bytes.drain(..prefix_end);
-        String::from_utf8(bytes).expect("failed to create string after removing largest prefix")
     }
 
-    pub fn remove_shortest_prefix(&self, s: String) -> String {
-        if self.pattern_string.is_empty() || s.is_empty() {
-            return s;
+    /// Returns the start of the shortest (or largest) suffix of `s` matched
+    /// by the pattern, if any
+    fn suffix_start(&self, s: &str, largest: bool) -> Option<usize> {
+        let mut starts = (0..=s.len()).filter(|i| s.is_char_boundary(*i));
+        if largest {
+            starts.find(|start| self.matches_str(&s[*start..]))
+        } else {
+            starts.rev().find(|start| self.matches_str(&s[*start..]))
         }
-        assert!(
-            !s.as_bytes().contains(&b'\0'),
-            "trying to match a string containing null"
-        );
-        let mut bytes = s.into_bytes();
-        bytes.push(b'\0');
-        let mut prefix_end = 0;
-        for i in 1..bytes.len() - 1 {
-            let t = bytes[i];
-            bytes[i] = b'\0';
-            // we know there is a null, so this unwrap will never fail
-            if self
-                .regex
-                .matches(CStr::from_bytes_until_nul(&bytes).unwrap())
-            {
-                prefix_end = i;
-                bytes[i] = t;
-                break;
-            }
-            bytes[i] = t;
+    }
+
+    pub fn remove_largest_prefix(&self, s: String) -> String {
+        match self.prefix_len(&s, true) {
+            Some(len) => s[len..].to_string(),
+            None => s,
         }
-        // remove '\0'
-        bytes.pop();
-        bytes.drain(..prefix_end);
-        String::from_utf8(bytes).expect("failed to create string after removing shortest prefix")
     }
 
-    pub fn remove_largest_suffix(&self, s: String) -> String {
-        if self.pattern_string.is_empty() || s.is_empty() {
-            return s;
+    pub fn remove_shortest_prefix(&self, s: String) -> String {
+        match self.prefix_len(&s, false) {
+            Some(len) => s[len..].to_string(),
+            None => s,
         }
-        let cstring = CString::new(s).expect("trying to match a string containing null");
-        let len = cstring.as_bytes().len();
-        let mut suffix_start = len - 1;
-        for regex_match in self.regex.match_locations(&cstring) {
-            if regex_match.end == len {
-                suffix_start = regex_match.start;
-                break;
-            }
+    }
+
+    pub fn remove_largest_suffix(&self, s: String) -> String {
+        match self.suffix_start(&s, true) {
+            Some(start) => s[..start].to_string(),
+            None => s,
         }
-        let mut bytes = cstring.into_bytes();
-        bytes.drain(suffix_start..);
-        String::from_utf8(bytes).expect("failed to create string after removing largest suffix")
     }
 
     pub fn remove_shortest_suffix(&self, s: String) -> String {
-        if self.pattern_string.is_empty() || s.is_empty() {
-            return s;
+        match self.suffix_start(&s, false) {
+            Some(start) => s[..start].to_string(),
+            None => s,
         }
-        assert!(
-            !s.as_bytes().contains(&b'\0'),
-            "trying to match a string containing null"
-        );
-        let mut bytes = s.into_bytes();
-        bytes.push(b'\0');
-        let mut suffix_start = bytes.len();
-        for i in (1..bytes.len() - 1).rev() {
-            // we know there is a null, so this unwrap will never fail
-            if self
-                .regex
-                .matches(CStr::from_bytes_until_nul(&bytes[i..]).unwrap())
-            {
-                suffix_start = i;
-                break;
-            }
-        }
-        // remove terminating '\0'
-        bytes.pop();
-        bytes.drain(suffix_start..);
-        String::from_utf8(bytes).expect("failed to create string after removing shortest suffix")
     }
 }
 
@@ -338,6 +302,41 @@ pub mod tests {
         )
     }
 
+    #[test]
+    fn pattern_matches_the_whole_string() {
+        let pattern = pattern_from_str("b");
+        assert!(pattern.matches(&cstring_from_str("b")));
+        assert!(!pattern.matches(&cstring_from_str("abc")));
+        assert!(pattern_from_str("*").matches(&cstring_from_str("")));
+    }
+
+    #[test]
+    fn remove_nothing_if_pattern_does_not_match() {
+        let pattern = pattern_from_str("x");
+        assert_eq!(pattern.remove_largest_prefix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_shortest_prefix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_largest_suffix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_shortest_suffix("abc".to_string()), "abc");
+    }
+
+    #[test]
+    fn pattern_is_anchored_when_removing_prefix_or_suffix() {
+        let pattern = pattern_from_str("b");
+        assert_eq!(pattern.remove_shortest_prefix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_shortest_suffix("abc".to_string()), "abc");
+        let pattern = pattern_from_str("aa");
+        assert_eq!(pattern.remove_largest_suffix("aaa".to_string()), "a");
+    }
+
+    #[test]
+    fn shortest_match_of_asterisk_is_empty() {
+        let pattern = pattern_from_str("*");
+        assert_eq!(pattern.remove_shortest_prefix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_shortest_suffix("abc".to_string()), "abc");
+        assert_eq!(pattern.remove_largest_prefix("abc".to_string()), "");
+        assert_eq!(pattern.remove_largest_suffix("abc".to_string()), "");
+    }
+
     #[test]
     fn filename_pattern_matches_simple_components_in_path() {
         let pattern = filename_pattern_from_str("/path/to/file");
@@ -117,8 +117,29 @@ impl Regex {
         }
     }
 
-    pub fn matches(&self, string: &CStr) -> bool {
-        self.match_locations(string).next().is_some()
+    /// Returns true if the regex matches the whole string
+    pub fn matches_all(&self, string: &CStr) -> bool {
+        let Some(raw_regex) = &self.raw_regex else {
+            return string.is_empty();
+        };
+        let mut match_range = libc::regmatch_t {
+            rm_so: -1,
+            rm_eo: -1,
+        };
+        let exec_status = unsafe {
+            libc::regexec(
+                ptr::from_ref(raw_regex),
+                string.as_ptr(),
+                1,
+                ptr::from_mut(&mut match_range),
+                0,
+            )
+        };
+        // the match is the longest one starting at the leftmost position,
+        // so if the whole string matches, this match covers it
+        exec_status == 0
+            && match_range.rm_so == 0
+            && match_range.rm_eo as usize == string.count_bytes()
     }
 }
 
@@ -113,6 +113,9 @@ impl Environment {
     }
 
     pub fn set(&mut self, name: String, value: String) -> Result<(), CannotModifyReadonly> {
+        if self.global_scope.get(&name).is_some_and(|var| var.readonly) {
+            return Err(CannotModifyReadonly(name));
+        }
         if let Some(innermost_scope) = self.local_scopes.last_mut() {
             innermost_scope.insert(name, value);
         } else {
@@ -45,6 +45,7 @@ use std::fs::File;
 use std::io;
 use std::io::{read_to_string, Read};
 use std::os::fd::{AsFd, AsRawFd, IntoRawFd};
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use std::rc::Rc;
 use std::time::Duration;
@@ -106,6 +107,18 @@ impl Display for CommandExecutionError {
 
 type CommandExecutionResult<T> = Result<T, CommandExecutionError>;
 
+/// Whether `pwd` is an absolute pathname without dot or dot-dot components
+/// of the current working directory, which then names it logically.
+fn is_logical_current_directory(pwd: &str) -> bool {
+    if !pwd.starts_with('/') || pwd.split('/').any(|c| c == "." || c == "..") {
+        return false;
+    }
+    match (std::fs::metadata(pwd), std::fs::metadata(".")) {
+        (Ok(pwd), Ok(dot)) => pwd.dev() == dot.dev() && pwd.ino() == dot.ino(),
+        _ => false,
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ControlFlowState {
     Break(u32),
@@ -274,6 +287,12 @@ impl Shell {
         match err {
             CommandExecutionError::CommandNotFound(_) => 127,
             CommandExecutionError::OsError(_) => self.exit(1),
+            CommandExecutionError::ExpansionError(_)
+            | CommandExecutionError::VariableAssignmentError(_)
+                if !self.is_interactive =>
+            {
+                self.exit(1)
+            }
             _ => 1,
         }
     }
@@ -344,6 +363,20 @@ impl Shell {
         Ok(())
     }
 
+    /// Pushes a new scope containing `assignments`. If an assignment fails
+    /// the scope is popped before returning the error.
+    fn push_scope_with_assignments(
+        &mut self,
+        assignments: &[Assignment],
+    ) -> CommandExecutionResult<()> {
+        self.environment.push_scope();
+        let result = self.assign_locals(assignments);
+        if result.is_err() {
+            self.environment.pop_scope();
+        }
+        result
+    }
+
     fn exec_special_builtin(
         &mut self,
         simple_command: &SimpleCommand,
@@ -380,9 +413,7 @@ impl Shell {
         function_body: &CompoundCommand,
         ignore_errexit: bool,
     ) -> CommandExecutionResult<i32> {
-        self.environment.push_scope();
-
-        self.assign_locals(&simple_command.assignments)?;
+        self.push_scope_with_assignments(&simple_command.assignments)?;
 
         let mut previous_opened_files = self.opened_files.clone();
         previous_opened_files.redirect(&simple_command.redirections, self)?;
@@ -417,8 +448,7 @@ impl Shell {
         let mut opened_files = self.opened_files.clone();
         opened_files.redirect(&simple_command.redirections, self)?;
 
-        self.environment.push_scope();
-        self.assign_locals(&simple_command.assignments)?;
+        self.push_scope_with_assignments(&simple_command.assignments)?;
         let status = match builtin_utility.exec(args, self, &mut opened_files) {
             Ok(status) => status,
             Err(err) => {
@@ -514,8 +544,7 @@ impl Shell {
                     expanded_words[0].to_string(),
                 ))?;
 
-            self.environment.push_scope();
-            self.assign_locals(&simple_command.assignments)?;
+            self.push_scope_with_assignments(&simple_command.assignments)?;
             let mut opened_files = self.opened_files.clone();
             opened_files.redirect(&simple_command.redirections, self)?;
             let result = self
@@ -992,6 +1021,22 @@ impl Shell {
                 std::process::exit(1);
             }
         };
+        // > If a value for PWD is passed to the shell in the environment when it
+        // > is executed, the value is an absolute pathname of the current working
+        // > directory [...] and the value does not contain any components that are
+        // > dot or dot-dot, then the shell shall set PWD to the value from the
+        // > environment. Otherwise, [...] the shell shall set PWD to the pathname
+        // > that would be output by pwd -P.
+        let current_directory = match environment.get_str_value("PWD") {
+            Some(pwd) if is_logical_current_directory(pwd) => OsString::from(pwd),
+            _ => current_directory,
+        };
+        environment
+            .set_global_forced(
+                "PWD".to_string(),
+                current_directory.to_string_lossy().into_owned(),
+            )
+            .export_or(true);
         Shell {
             environment,
             program_name,
@@ -11,11 +11,12 @@ use crate::parse::command::{IORedirectionKind, Redirection, RedirectionKind};
 use crate::shell::{CommandExecutionError, Shell};
 use crate::wordexp::expand_word_to_string;
 use nix::libc;
+use nix::unistd::mkstemp;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::Write;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
 use std::os::unix::fs::OpenOptionsExt;
-use std::path::Path;
 use std::rc::Rc;
 
 pub const STDIN_FILENO: u32 = libc::STDIN_FILENO as u32;
@@ -32,13 +33,64 @@ pub enum OpenedFile {
     ReadFile(Rc<File>),
     WriteFile(Rc<File>),
     ReadWriteFile(Rc<File>),
-    HereDocument(String),
 }
 
 fn io_err_to_redirection_err(err: std::io::Error) -> CommandExecutionError {
     CommandExecutionError::RedirectionError(format!("sh: io error ({})", err))
 }
 
+/// Stores the contents of a here-document in an unlinked temporary file, so
+/// that they are read through a file descriptor like any other input and
+/// consumed as they are read. The file is created in `tmpdir`, the value of
+/// TMPDIR, or else in /tmp.
+fn here_document_file(contents: &str, tmpdir: Option<&str>) -> Result<File, CommandExecutionError> {
+    let dir = tmpdir.filter(|dir| !dir.is_empty()).unwrap_or("/tmp");
+    let template = format!("{}/sh-heredoc.XXXXXX", dir.trim_end_matches('/'));
+    let (raw_fd, path) = mkstemp(template.as_str()).map_err(|err| {
+        CommandExecutionError::RedirectionError(format!(
+            "sh: failed to create temporary file for here-document ({err})"
+        ))
+    })?;
+    // SAFETY: mkstemp returned a new descriptor, which nothing else owns.
+    let mut file = unsafe { File::from_raw_fd(raw_fd) };
+    std::fs::remove_file(path).map_err(io_err_to_redirection_err)?;
+    file.write_all(contents.as_bytes())
+        .map_err(io_err_to_redirection_err)?;
+    file.seek(SeekFrom::Start(0))
+        .map_err(io_err_to_redirection_err)?;
+    Ok(file)
+}
+
+/// Opens `target` for `>` with noclobber set. The file is created
+/// exclusively, so that a file created concurrently is not overwritten
+/// either. An existing file is only opened if it is not a regular file,
+/// like `/dev/null`, and it is not truncated.
+fn open_without_clobbering(target: &str, mode: u32) -> Result<File, CommandExecutionError> {
+    match File::options()
+        .mode(mode)
+        .write(true)
+        .create_new(true)
+        .open(target)
+    {
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+        result => return result.map_err(io_err_to_redirection_err),
+    }
+    let file = File::options()
+        .write(true)
+        .open(target)
+        .map_err(io_err_to_redirection_err)?;
+    let is_regular_file = file
+        .metadata()
+        .map_err(io_err_to_redirection_err)?
+        .is_file();
+    if is_regular_file {
+        return Err(CommandExecutionError::RedirectionError(format!(
+            "sh: {target}: cannot overwrite existing file"
+        )));
+    }
+    Ok(file)
+}
+
 #[derive(Clone)]
 pub struct OpenedFiles {
     pub opened_files: HashMap<u32, OpenedFile>,
@@ -56,24 +108,20 @@ impl OpenedFiles {
             IORedirectionKind::RedirectOutput
             | IORedirectionKind::RedirectOutputClobber
             | IORedirectionKind::RedirectOuputAppend => {
-                if *kind != IORedirectionKind::RedirectOutputClobber
-                    && shell.set_options.noclobber
-                    && Path::new(target).exists()
-                {
-                    return Err(CommandExecutionError::RedirectionError(format!(
-                        "sh: redirection would overwrite existing file {target}",
-                    )));
-                }
-
-                let append = *kind == IORedirectionKind::RedirectOuputAppend;
-                let file = File::options()
-                    .mode(shell.umask)
-                    .write(true)
-                    .truncate(!append)
-                    .append(append)
-                    .create(true)
-                    .open(target)
-                    .map_err(io_err_to_redirection_err)?;
+                let file =
+                    if *kind == IORedirectionKind::RedirectOutput && shell.set_options.noclobber {
+                        open_without_clobbering(target, shell.umask)?
+                    } else {
+                        let append = *kind == IORedirectionKind::RedirectOuputAppend;
+                        File::options()
+                            .mode(shell.umask)
+                            .write(true)
+                            .truncate(!append)
+                            .append(append)
+                            .create(true)
+                            .open(target)
+                            .map_err(io_err_to_redirection_err)?
+                    };
 
                 let source_fd = file_descriptor.unwrap_or(STDOUT_FILENO);
                 self.opened_files
@@ -104,9 +152,7 @@ impl OpenedFiles {
                                 "sh: '{source_fd}' is not opened for reading"
                             )));
                         }
-                        Some(OpenedFile::ReadFile(_))
-                        | Some(OpenedFile::Stdin)
-                        | Some(OpenedFile::HereDocument(_))
+                        Some(OpenedFile::ReadFile(_)) | Some(OpenedFile::Stdin)
                             if !duplicate_input =>
                         {
                             return Err(CommandExecutionError::RedirectionError(format!(
@@ -165,13 +211,19 @@ impl OpenedFiles {
                     let contents = expand_word_to_string(&contents.word, false, shell)?;
                     self.opened_files.insert(
                         redir.file_descriptor.unwrap_or(STDIN_FILENO),
-                        OpenedFile::HereDocument(contents),
+                        OpenedFile::ReadFile(Rc::new(here_document_file(
+                            &contents,
+                            shell.environment.get_str_value("TMPDIR"),
+                        )?)),
                     );
                 }
                 RedirectionKind::QuotedHereDocument { contents, .. } => {
                     self.opened_files.insert(
                         redir.file_descriptor.unwrap_or(STDIN_FILENO),
-                        OpenedFile::HereDocument(contents.clone()),
+                        OpenedFile::ReadFile(Rc::new(here_document_file(
+                            contents,
+                            shell.environment.get_str_value("TMPDIR"),
+                        )?)),
                     );
                 }
             }
@@ -179,12 +231,12 @@ impl OpenedFiles {
         Ok(())
     }
 
-    fn write_file(&self, fileno: u32, contents: &str) {
+    fn write_file(&self, fileno: u32, contents: &[u8]) {
         let result = match self.opened_files.get(&fileno) {
-            Some(OpenedFile::Stdout) => std::io::stdout().write_all(contents.as_bytes()),
-            Some(OpenedFile::Stderr) => std::io::stderr().write_all(contents.as_bytes()),
+            Some(OpenedFile::Stdout) => std::io::stdout().write_all(contents),
+            Some(OpenedFile::Stderr) => std::io::stderr().write_all(contents),
             Some(OpenedFile::WriteFile(file)) | Some(OpenedFile::ReadWriteFile(file)) => {
-                nix::unistd::write(file, contents.as_bytes())
+                nix::unistd::write(file, contents)
                     .map_err(std::io::Error::from)
                     .map(|_| ())
             }
@@ -199,11 +251,30 @@ impl OpenedFiles {
     }
 
     pub fn write_out<S: AsRef<str>>(&self, string: S) {
-        self.write_file(STDOUT_FILENO, string.as_ref());
+        self.write_file(STDOUT_FILENO, string.as_ref().as_bytes());
+    }
+
+    pub fn write_out_bytes(&self, bytes: &[u8]) {
+        self.write_file(STDOUT_FILENO, bytes);
     }
 
     pub fn write_err<S: AsRef<str>>(&self, string: S) {
-        self.write_file(STDERR_FILENO, string.as_ref());
+        self.write_file(STDERR_FILENO, string.as_ref().as_bytes());
+    }
+
+    /// Whether a file descriptor, as redirected for the command, is a
+    /// terminal.
+    pub fn is_terminal(&self, fileno: u32) -> bool {
+        let fd = match self.opened_files.get(&fileno) {
+            Some(OpenedFile::Stdin) => libc::STDIN_FILENO,
+            Some(OpenedFile::Stdout) => libc::STDOUT_FILENO,
+            Some(OpenedFile::Stderr) => libc::STDERR_FILENO,
+            Some(OpenedFile::ReadFile(file))
+            | Some(OpenedFile::WriteFile(file))
+            | Some(OpenedFile::ReadWriteFile(file)) => file.as_raw_fd(),
+            None => fileno as libc::c_int,
+        };
+        plib::testexpr::isatty(fd)
     }
 
     pub fn get_file(&self, fileno: u32) -> Option<&OpenedFile> {
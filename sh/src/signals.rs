@@ -8,13 +8,12 @@
 //
 
 use crate::builtin::trap::TrapAction;
-use nix::errno::Errno;
 use nix::libc;
-use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal as NixSignal};
-use nix::unistd::{read, write};
+use nix::sys::signal::Signal as NixSignal;
+use plib::signal::SignalPipe;
 use std::fmt::{Display, Formatter};
-use std::os::fd::{AsRawFd, BorrowedFd, IntoRawFd, RawFd};
 use std::str::FromStr;
+use std::sync::OnceLock;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Signal {
@@ -257,79 +256,31 @@ pub const SIGNALS: &[Signal] = &[
     Signal::SigSys,
 ];
 
-static mut SIGNAL_WRITE: Option<RawFd> = None;
-static mut SIGNAL_READ: Option<RawFd> = None;
-
-extern "C" fn write_signal_to_buffer(signal: libc::c_int) {
-    // SIGNAL_WRITE is never modified after the initial
-    // setup, and is a valid file descriptor, so this is safe
-    let fd = unsafe { BorrowedFd::borrow_raw(SIGNAL_WRITE.unwrap()) };
-    write(fd, &signal.to_ne_bytes()).expect("failed to write to signal buffer");
-}
-
-/// # Safety
-/// cannot be called by multiple threads
-pub unsafe fn setup_signal_handling() {
-    let (read_pipe, write_pipe) = nix::unistd::pipe().expect("could not create signal buffer pipe");
-    nix::fcntl::fcntl(
-        read_pipe.as_raw_fd(),
-        nix::fcntl::FcntlArg::F_SETFL(nix::fcntl::OFlag::O_NONBLOCK),
-    )
-    .expect("signal buffer pipe could not be set as non-blocking");
-    SIGNAL_WRITE = Some(write_pipe.into_raw_fd());
-    SIGNAL_READ = Some(read_pipe.into_raw_fd());
+/// Reports the signals that the shell handles itself.
+fn signal_pipe() -> &'static SignalPipe {
+    static SIGNAL_PIPE: OnceLock<SignalPipe> = OnceLock::new();
+    SIGNAL_PIPE.get_or_init(|| SignalPipe::new(&[]).expect("could not create signal buffer pipe"))
 }
 
 fn get_pending_signal() -> Option<Signal> {
-    // SIGNAL_READ is never modified after the initial
-    // setup, so this is safe
-    let fd = unsafe { SIGNAL_READ.unwrap() };
-    let mut buf = [0u8; size_of::<libc::c_int>()];
-    match read(fd, &mut buf) {
-        Err(err) => {
-            if err == Errno::EAGAIN {
-                None
-            } else {
-                panic!("failed to write to signal pipe ({err})");
-            }
-        }
-        Ok(size) => {
-            if size == 0 {
-                None
-            } else {
-                let signal = libc::c_int::from_ne_bytes(buf);
-                Some(Signal::try_from(signal).unwrap())
-            }
-        }
+    match signal_pipe().try_recv() {
+        Ok(signal) => signal.map(|signal| Signal::try_from(signal).unwrap()),
+        Err(err) => panic!("failed to read from signal pipe ({err})"),
     }
 }
 
-pub unsafe fn handle_signal_ignore(signal: Signal) {
-    sigaction(
-        signal.into(),
-        &SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty()),
-    )
-    .unwrap();
+pub fn handle_signal_ignore(signal: Signal) {
+    plib::signal::ignore(NixSignal::from(signal) as libc::c_int).unwrap();
 }
 
-pub unsafe fn handle_signal_default(signal: Signal) {
-    sigaction(
-        signal.into(),
-        &SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty()),
-    )
-    .unwrap();
+pub fn handle_signal_default(signal: Signal) {
+    plib::signal::set_default(NixSignal::from(signal) as libc::c_int).unwrap();
 }
 
-pub unsafe fn handle_signal_write_to_signal_buffer(signal: Signal) {
-    sigaction(
-        signal.into(),
-        &SigAction::new(
-            SigHandler::Handler(write_signal_to_buffer),
-            SaFlags::empty(),
-            SigSet::empty(),
-        ),
-    )
-    .unwrap();
+pub fn handle_signal_write_to_signal_buffer(signal: Signal) {
+    signal_pipe()
+        .add(NixSignal::from(signal) as libc::c_int)
+        .unwrap();
 }
 
 #[derive(Clone)]
@@ -359,14 +310,10 @@ impl SignalManager {
                 // we also reset default actions because ignore could have been
                 // set at startup for an interactive shell, but its not registered
                 // as a trap action
-                TrapAction::Commands(_) | TrapAction::Default => unsafe {
-                    sigaction(
-                        signal.into(),
-                        &SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty()),
-                    )
-                    .unwrap();
+                TrapAction::Commands(_) | TrapAction::Default => {
+                    handle_signal_default(signal);
                     *action = TrapAction::Default;
-                },
+                }
                 TrapAction::Ignore => {}
             }
         }
@@ -380,19 +327,19 @@ impl SignalManager {
             && (action == TrapAction::Ignore || action == TrapAction::Default)
         {
             // in interactive mode we always want catch sigint
-            unsafe { handle_signal_write_to_signal_buffer(Signal::SigInt) };
+            handle_signal_write_to_signal_buffer(Signal::SigInt);
             self.actions[signal as usize] = action;
             return;
         }
         match action {
             TrapAction::Default => {
-                unsafe { handle_signal_default(signal) };
+                handle_signal_default(signal);
             }
             TrapAction::Ignore => {
-                unsafe { handle_signal_ignore(signal) };
+                handle_signal_ignore(signal);
             }
             TrapAction::Commands(_) => {
-                unsafe { handle_signal_write_to_signal_buffer(signal) };
+                handle_signal_write_to_signal_buffer(signal);
             }
         }
         self.actions[signal as usize] = action;
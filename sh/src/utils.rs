@@ -18,7 +18,7 @@ use std::convert::Infallible;
 use std::ffi::{CStr, CString, OsString};
 use std::fmt::{Display, Formatter};
 use std::io;
-use std::os::fd::{AsFd, AsRawFd, OwnedFd, RawFd};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
 use std::os::unix::ffi::OsStringExt;
 use std::path::PathBuf;
 
@@ -40,6 +40,10 @@ impl OsError {
     pub fn new(command: &'static str, errno: Errno) -> Self {
         Self { command, errno }
     }
+
+    fn from_io(command: &'static str, err: io::Error) -> Self {
+        Self::new(command, Errno::from_raw(err.raw_os_error().unwrap_or(0)))
+    }
 }
 
 impl Display for OsError {
@@ -54,6 +58,11 @@ impl Display for OsError {
 
 pub type OsResult<T> = Result<T, OsError>;
 
+/// Quotes `value` so that it is read back unchanged as a single word.
+pub fn single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 pub fn fork() -> OsResult<ForkResult> {
     // fork in general is not safe for multithreaded programs, but all code in this module is single
     // threaded, so this is safe
@@ -61,11 +70,13 @@ pub fn fork() -> OsResult<ForkResult> {
 }
 
 pub fn pipe() -> OsResult<(OwnedFd, OwnedFd)> {
-    nix::unistd::pipe().map_err(|err| OsError::new("pipe", err))
+    plib::fd::pipe().map_err(|err| OsError::from_io("pipe", err))
 }
 
-pub fn dup2(old_fd: RawFd, new_fd: RawFd) -> OsResult<RawFd> {
-    nix::unistd::dup2(old_fd, new_fd).map_err(|err| OsError::new("dup2", err))
+pub fn dup2(old_fd: RawFd, new_fd: RawFd) -> OsResult<()> {
+    // the caller guarantees that old_fd is open
+    let old_fd = unsafe { BorrowedFd::borrow_raw(old_fd) };
+    plib::fd::dup2(old_fd, new_fd).map_err(|err| OsError::from_io("dup2", err))
 }
 
 pub fn waitpid(pid: Pid, options: Option<WaitPidFlag>) -> OsResult<WaitStatus> {
@@ -126,13 +137,6 @@ pub fn exec(
             OpenedFile::ReadFile(file)
             | OpenedFile::WriteFile(file)
             | OpenedFile::ReadWriteFile(file) => file.as_raw_fd(),
-            OpenedFile::HereDocument(contents) => {
-                let (read_pipe, write_pipe) = pipe()?;
-                nix::unistd::write(write_pipe, contents.as_bytes())
-                    .map_err(|err| OsError::new("write", err))?;
-                dup2(read_pipe.as_raw_fd(), dest)?;
-                continue;
-            }
         };
         dup2(src, dest)?;
     }
@@ -64,6 +64,12 @@ enum Expr<'src> {
         variable: &'src str,
         value: Box<Expr<'src>>,
     },
+    /// `++var`, `--var`, `var++` or `var--`.
+    Increment {
+        variable: &'src str,
+        delta: i64,
+        prefix: bool,
+    },
     CompoundAssignment {
         variable: &'src str,
         operator: BinaryOperator,
@@ -107,6 +113,8 @@ enum ExprToken<'src> {
     Number(i64),
     Plus,
     Minus,
+    Increment,
+    Decrement,
     BitwiseNot,
     Not,
     Mul,
@@ -151,6 +159,8 @@ impl Display for ExprToken<'_> {
             ExprToken::Number(num) => write!(f, "{num}"),
             ExprToken::Plus => write!(f, "+"),
             ExprToken::Minus => write!(f, "-"),
+            ExprToken::Increment => write!(f, "++"),
+            ExprToken::Decrement => write!(f, "--"),
             ExprToken::BitwiseNot => write!(f, "~"),
             ExprToken::Not => write!(f, "!"),
             ExprToken::Mul => write!(f, "*"),
@@ -344,8 +354,14 @@ impl<'src> ExpressionParser<'src> {
         self.skip_whitespace();
         let start_pos = self.source_position;
         match self.advance_char() {
-            Some('+') => Ok(self.multichar_token(ExprToken::Plus, &[('=', ExprToken::AddAssign)])),
-            Some('-') => Ok(self.multichar_token(ExprToken::Minus, &[('=', ExprToken::SubAssign)])),
+            Some('+') => Ok(self.multichar_token(
+                ExprToken::Plus,
+                &[('=', ExprToken::AddAssign), ('+', ExprToken::Increment)],
+            )),
+            Some('-') => Ok(self.multichar_token(
+                ExprToken::Minus,
+                &[('=', ExprToken::SubAssign), ('-', ExprToken::Decrement)],
+            )),
             Some('~') => Ok(ExprToken::BitwiseNot),
             Some('!') => Ok(self.multichar_token(ExprToken::Not, &[('=', ExprToken::Neq)])),
             Some('*') => Ok(self.multichar_token(ExprToken::Mul, &[('=', ExprToken::MulAssign)])),
@@ -397,7 +413,7 @@ impl<'src> ExpressionParser<'src> {
             Some(')') => Ok(ExprToken::RParen),
             Some(c) if c.is_ascii_digit() => {
                 if c == '0' {
-                    if self.peek() == Some('x') {
+                    if matches!(self.peek(), Some('x' | 'X')) {
                         self.advance_char();
                         self.lex_hex_number(self.source_position + 1)
                             .map(ExprToken::Number)
@@ -412,7 +428,7 @@ impl<'src> ExpressionParser<'src> {
                 Ok(ExprToken::Variable(self.lex_variable(start_pos)))
             }
             None => Ok(ExprToken::Eof),
-            Some(other) => Err(format!("unexpected character '{other}")),
+            Some(other) => Err(format!("unexpected character '{other}'")),
         }
     }
 
@@ -442,9 +458,29 @@ impl<'src> ExpressionParser<'src> {
         tokens.iter().find(|t| self.matches(**t)).copied()
     }
 
+    /// The change made by an increment or decrement operator in the
+    /// lookahead, if there is one.
+    fn increment_delta(&mut self) -> Option<i64> {
+        match self.lookahead {
+            ExprToken::Increment => Some(1),
+            ExprToken::Decrement => Some(-1),
+            _ => None,
+        }
+    }
+
     fn parse_literal(&mut self) -> ExprParseResult<Expr<'src>> {
         match self.advance_token()? {
-            ExprToken::Variable(var) => Ok(Expr::Variable(var)),
+            ExprToken::Variable(variable) => match self.increment_delta() {
+                Some(delta) => {
+                    self.advance_token()?;
+                    Ok(Expr::Increment {
+                        variable,
+                        delta,
+                        prefix: false,
+                    })
+                }
+                None => Ok(Expr::Variable(variable)),
+            },
             ExprToken::Number(num) => Ok(Expr::Number(num)),
             ExprToken::LParen => {
                 let expr = self.parse_expr()?;
@@ -456,9 +492,19 @@ impl<'src> ExpressionParser<'src> {
     }
 
     fn parse_unary(&mut self) -> ExprParseResult<Expr<'src>> {
-        if let Some(op) = self.matches_alternatives(UNARY_OPERATORS) {
+        if let Some(delta) = self.increment_delta() {
+            let operator = self.advance_token()?;
+            match self.advance_token()? {
+                ExprToken::Variable(variable) => Ok(Expr::Increment {
+                    variable,
+                    delta,
+                    prefix: true,
+                }),
+                other => Err(format!("expected variable after {operator}, got {other}")),
+            }
+        } else if let Some(op) = self.matches_alternatives(UNARY_OPERATORS) {
             self.advance_token()?;
-            let operand = self.parse_literal()?;
+            let operand = self.parse_unary()?;
             Ok(Expr::UnaryOp {
                 operator: op.into(),
                 operand: operand.into(),
@@ -525,18 +571,37 @@ fn parse_expression(expr: &str) -> Result<Expr, String> {
         lookahead: ExprToken::Eof,
     };
     parser.advance_token()?;
-    parser.parse_expr()
+    let expr = parser.parse_expr()?;
+    parser.match_token(ExprToken::Eof)?;
+    Ok(expr)
+}
+
+/// Variables can hold expressions, which are evaluated when the variable is
+/// used. This bounds how deeply they can refer to each other.
+const MAX_VARIABLE_DEPTH: usize = 1024;
+
+fn overflow() -> String {
+    "arithmetic overflow".to_string()
 }
 
-fn binary_operation(operator: &BinaryOperator, lhs_value: i64, rhs_value: i64) -> i64 {
-    match operator {
-        BinaryOperator::Mul => lhs_value * rhs_value,
-        BinaryOperator::Div => lhs_value / rhs_value,
-        BinaryOperator::Mod => lhs_value % rhs_value,
-        BinaryOperator::Add => lhs_value + rhs_value,
-        BinaryOperator::Sub => lhs_value - rhs_value,
-        BinaryOperator::ShiftLeft => lhs_value << rhs_value,
-        BinaryOperator::ShiftRight => lhs_value >> rhs_value,
+/// Arithmetic is on signed long integers. Results that don't fit, which
+/// are undefined in C, are an error.
+fn binary_operation(
+    operator: &BinaryOperator,
+    lhs_value: i64,
+    rhs_value: i64,
+) -> Result<i64, String> {
+    Ok(match operator {
+        BinaryOperator::Mul => lhs_value.checked_mul(rhs_value).ok_or_else(overflow)?,
+        BinaryOperator::Div | BinaryOperator::Mod if rhs_value == 0 => {
+            return Err("division by zero".to_string())
+        }
+        BinaryOperator::Div => lhs_value.checked_div(rhs_value).ok_or_else(overflow)?,
+        BinaryOperator::Mod => lhs_value.checked_rem(rhs_value).ok_or_else(overflow)?,
+        BinaryOperator::Add => lhs_value.checked_add(rhs_value).ok_or_else(overflow)?,
+        BinaryOperator::Sub => lhs_value.checked_sub(rhs_value).ok_or_else(overflow)?,
+        BinaryOperator::ShiftLeft => lhs_value.wrapping_shl(rhs_value as u32),
+        BinaryOperator::ShiftRight => lhs_value.wrapping_shr(rhs_value as u32),
         BinaryOperator::Le => (lhs_value < rhs_value) as i64,
         BinaryOperator::Leq => (lhs_value <= rhs_value) as i64,
         BinaryOperator::Ge => (lhs_value > rhs_value) as i64,
@@ -547,81 +612,110 @@ fn binary_operation(operator: &BinaryOperator, lhs_value: i64, rhs_value: i64) -
         BinaryOperator::BitwiseXor => lhs_value ^ rhs_value,
         BinaryOperator::BitwiseOr => lhs_value | rhs_value,
         BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => unreachable!(),
+    })
+}
+
+/// The value of a variable: 0 if it is unset or empty, and else its
+/// contents evaluated as an expression. Unset variables are an error with
+/// `set -u`.
+fn variable_value(var: &str, shell: &mut Shell, depth: usize) -> Result<i64, String> {
+    let value = match shell.environment.get_str_value(var) {
+        Some(value) => value.to_string(),
+        None if shell.set_options.nounset => return Err(format!("{var}: parameter is unset")),
+        None => String::new(),
+    };
+    if value.trim().is_empty() {
+        return Ok(0);
     }
+    if depth == MAX_VARIABLE_DEPTH {
+        return Err(format!("{var}: expression recursion level exceeded"));
+    }
+    let expr = parse_expression(&value).map_err(|err| format!("{var}: {err}"))?;
+    interpret_expression(&expr, shell, depth + 1)
 }
 
-fn interpret_expression(expr: &Expr, shell: &mut Shell) -> ExpansionResult<i64> {
+fn interpret_expression(expr: &Expr, shell: &mut Shell, depth: usize) -> Result<i64, String> {
     match expr {
-        Expr::Variable(var) => {
-            let value = shell.environment.get_str_value(var).unwrap_or_default();
-            Ok(value.parse().unwrap_or(0))
-        }
+        Expr::Variable(var) => variable_value(var, shell, depth),
         Expr::Number(num) => Ok(*num),
         Expr::UnaryOp { operator, operand } => {
-            let value = interpret_expression(operand, shell)?;
+            let value = interpret_expression(operand, shell, depth)?;
             match operator {
                 UnaryOperator::Plus => Ok(value),
-                UnaryOperator::Minus => Ok(-value),
+                UnaryOperator::Minus => value.checked_neg().ok_or_else(overflow),
                 UnaryOperator::Not => Ok((value == 0) as i64),
                 UnaryOperator::BitwiseNot => Ok(!value),
             }
         }
         Expr::BinaryOp { lhs, operator, rhs } => {
-            let lhs_value = interpret_expression(lhs, shell)?;
+            let lhs_value = interpret_expression(lhs, shell, depth)?;
             match operator {
                 BinaryOperator::LogicalAnd => {
                     return if lhs_value != 0 {
-                        Ok((interpret_expression(rhs, shell)? != 0) as i64)
+                        Ok((interpret_expression(rhs, shell, depth)? != 0) as i64)
                     } else {
                         Ok(0)
                     }
                 }
                 BinaryOperator::LogicalOr => {
                     return if lhs_value == 0 {
-                        Ok((interpret_expression(rhs, shell)? != 0) as i64)
+                        Ok((interpret_expression(rhs, shell, depth)? != 0) as i64)
                     } else {
                         Ok(1)
                     }
                 }
                 _ => {}
             }
-            let rhs_value = interpret_expression(rhs, shell)?;
-            Ok(binary_operation(operator, lhs_value, rhs_value))
+            let rhs_value = interpret_expression(rhs, shell, depth)?;
+            binary_operation(operator, lhs_value, rhs_value)
         }
         Expr::Conditional {
             condition,
             true_expr,
             false_expr,
         } => {
-            if interpret_expression(condition, shell)? != 0 {
-                interpret_expression(true_expr, shell)
+            if interpret_expression(condition, shell, depth)? != 0 {
+                interpret_expression(true_expr, shell, depth)
             } else {
-                interpret_expression(false_expr, shell)
+                interpret_expression(false_expr, shell, depth)
             }
         }
         Expr::Assignment { variable, value } => {
-            let value = interpret_expression(value, shell)?;
-            shell.assign_global(variable.to_string(), value.to_string())?;
+            let value = interpret_expression(value, shell, depth)?;
+            assign(shell, variable, value)?;
             Ok(value)
         }
+        Expr::Increment {
+            variable,
+            delta,
+            prefix,
+        } => {
+            let current_value = variable_value(variable, shell, depth)?;
+            let new_value = current_value.checked_add(*delta).ok_or_else(overflow)?;
+            assign(shell, variable, new_value)?;
+            Ok(if *prefix { new_value } else { current_value })
+        }
         Expr::CompoundAssignment {
             variable,
             operator,
             value,
         } => {
-            let value = interpret_expression(value, shell)?;
-            let current_value = shell
-                .environment
-                .get_str_value(variable)
-                .map(|val| val.parse().unwrap_or(0))
-                .unwrap_or(0);
-            let new_value = binary_operation(operator, current_value, value);
-            shell.assign_global(variable.to_string(), new_value.to_string())?;
+            let value = interpret_expression(value, shell, depth)?;
+            let current_value = variable_value(variable, shell, depth)?;
+            let new_value = binary_operation(operator, current_value, value)?;
+            assign(shell, variable, new_value)?;
             Ok(new_value)
         }
     }
 }
 
+fn assign(shell: &mut Shell, variable: &str, value: i64) -> Result<(), String> {
+    shell
+        .assign_global(variable.to_string(), value.to_string())
+        .map(|_| ())
+        .map_err(|_| format!("cannot set readonly variable {variable}"))
+}
+
 pub fn expand_arithmetic_expression_into(
     expanded_word: &mut ExpandedWord,
     expr: &Word,
@@ -629,8 +723,9 @@ pub fn expand_arithmetic_expression_into(
     shell: &mut Shell,
 ) -> ExpansionResult<()> {
     let expr = expand_word_to_string(expr, false, shell)?;
-    let expr = parse_expression(&expr).map_err(CommandExecutionError::ExpansionError)?;
-    let value = interpret_expression(&expr, shell)?;
+    let value = parse_expression(&expr)
+        .and_then(|parsed| interpret_expression(&parsed, shell, 0))
+        .map_err(|err| CommandExecutionError::ExpansionError(format!("sh: {expr}: {err}")))?;
     expanded_word.append(value.to_string(), inside_double_quotes, true);
     Ok(())
 }
@@ -649,6 +744,16 @@ mod tests {
         result.to_string()
     }
 
+    fn expr_error(s: &str) -> String {
+        let mut shell = Shell::default();
+        let mut result = ExpandedWord::default();
+        match expand_arithmetic_expression_into(&mut result, &quoted_literal(s), false, &mut shell)
+        {
+            Err(CommandExecutionError::ExpansionError(err)) => err,
+            _ => panic!("expected expansion error"),
+        }
+    }
+
     fn test_assignment_with_initial_value(expr: &str, var: &str, initial_value: &str) -> String {
         let mut shell = Shell::default();
         shell
@@ -747,6 +852,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn nested_unary_operations() {
+        assert_eq!(execute_expr("- -1"), "1");
+        assert_eq!(execute_expr("!!5"), "1");
+        assert_eq!(execute_expr("-~0"), "1");
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert_eq!(
+            expr_error("9223372036854775807 + 1"),
+            "sh: 9223372036854775807 + 1: arithmetic overflow"
+        );
+        assert_eq!(
+            expr_error("(-9223372036854775807 - 1) / -1"),
+            "sh: (-9223372036854775807 - 1) / -1: arithmetic overflow"
+        );
+        assert_eq!(
+            expr_error("(-9223372036854775807 - 1) % -1"),
+            "sh: (-9223372036854775807 - 1) % -1: arithmetic overflow"
+        );
+        assert_eq!(
+            expr_error("-(-9223372036854775807 - 1)"),
+            "sh: -(-9223372036854775807 - 1): arithmetic overflow"
+        );
+        assert_eq!(
+            execute_expr("-9223372036854775807 - 1"),
+            "-9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        let mut shell = Shell::default();
+        let mut eval = |expr: &str| {
+            let mut result = ExpandedWord::default();
+            expand_arithmetic_expression_into(
+                &mut result,
+                &quoted_literal(expr),
+                false,
+                &mut shell,
+            )
+            .expect("invalid expression");
+            result.to_string()
+        };
+        assert_eq!(eval("i = 1"), "1");
+        assert_eq!(eval("i++ + ++i"), "4");
+        assert_eq!(eval("i"), "3");
+        assert_eq!(eval("i-- - --i"), "2");
+        assert_eq!(eval("i"), "1");
+
+        assert_eq!(
+            expr_error("++1"),
+            "sh: ++1: expected variable after ++, got 1"
+        );
+        assert_eq!(expr_error("1--1"), "sh: 1--1: expected <EOF>, got --");
+    }
+
+    #[test]
+    fn variables_hold_expressions() {
+        let mut shell = Shell::default();
+        for (name, value) in [("x", "3 + 4"), ("y", "x * 2"), ("z", "0x10")] {
+            shell
+                .environment
+                .set_global(name.to_string(), value.to_string())
+                .expect("variable is readonly");
+        }
+        let mut result = ExpandedWord::default();
+        expand_arithmetic_expression_into(&mut result, &quoted_literal("y + z"), false, &mut shell)
+            .expect("invalid expression");
+        assert_eq!(result.to_string(), "30");
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert_eq!(expr_error("1 / 0"), "sh: 1 / 0: division by zero");
+        assert_eq!(expr_error("1 % 0"), "sh: 1 % 0: division by zero");
+        assert_eq!(expr_error("1 2"), "sh: 1 2: expected <EOF>, got 2");
+        assert_eq!(expr_error("1 @ 2"), "sh: 1 @ 2: unexpected character '@'");
+    }
+
     #[test]
     fn precedence() {
         assert_eq!(
@@ -7,11 +7,11 @@
 // SPDX-License-Identifier: MIT
 //
 
-use crate::parse::word::{Parameter, ParameterExpansion, SpecialParameter};
+use crate::parse::word::{Parameter, ParameterExpansion, SpecialParameter, Word};
 use crate::shell::{CommandExecutionError, Shell};
 use crate::wordexp::{
     expand_word_to_string, simple_word_expansion_into, word_to_pattern, ExpandedWord,
-    ExpansionResult,
+    ExpandedWordPart, ExpansionResult,
 };
 
 #[derive(PartialEq, Eq)]
@@ -65,6 +65,68 @@ fn add_split_parameters_to_expanded_word(
     word.append(&parameters[i], quoted, true);
 }
 
+/// Expands the word of a `${parameter<op>word}` expansion. The result is
+/// quoted if the expansion itself is inside double quotes, otherwise it is
+/// subject to field splitting and pathname expansion like any other expansion.
+fn expand_nested_word_into(
+    expanded_word: &mut ExpandedWord,
+    word: &Word,
+    inside_double_quotes: bool,
+    shell: &mut Shell,
+) -> ExpansionResult<()> {
+    let mut expanded_nested_word = ExpandedWord::default();
+    simple_word_expansion_into(&mut expanded_nested_word, word, false, shell)?;
+    for part in expanded_nested_word {
+        match part {
+            ExpandedWordPart::QuotedLiteral(lit) => expanded_word.append(lit, true, true),
+            ExpandedWordPart::UnquotedLiteral(lit)
+            | ExpandedWordPart::GeneratedUnquotedLiteral(lit) => {
+                expanded_word.append(lit, inside_double_quotes, true)
+            }
+            ExpandedWordPart::FieldEnd => {
+                if !expanded_word.is_empty() {
+                    expanded_word.end_field()
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parameter_name(parameter: &Parameter) -> String {
+    match parameter {
+        Parameter::Number(n) => n.to_string(),
+        Parameter::Variable(name) => name.to_string(),
+        Parameter::Special(special_parameter) => match special_parameter {
+            SpecialParameter::At => "@",
+            SpecialParameter::Asterisk => "*",
+            SpecialParameter::Hash => "#",
+            SpecialParameter::QuestionMark => "?",
+            SpecialParameter::Minus => "-",
+            SpecialParameter::Dollar => "$",
+            SpecialParameter::Bang => "!",
+            SpecialParameter::Zero => "0",
+        }
+        .to_string(),
+    }
+}
+
+/// With `set -u`, expanding an unset parameter is an error, except in the
+/// expansions that test whether it is set.
+fn check_nounset(
+    parameter: &Parameter,
+    parameter_type: ParameterExpansionResult,
+    shell: &Shell,
+) -> ExpansionResult<()> {
+    if parameter_type.is_unset() && shell.set_options.nounset {
+        return Err(CommandExecutionError::ExpansionError(format!(
+            "sh: {}: parameter is unset",
+            parameter_name(parameter)
+        )));
+    }
+    Ok(())
+}
+
 fn expand_simple_parameter_into(
     expanded_word: &mut ExpandedWord,
     parameter: &Parameter,
@@ -160,8 +222,20 @@ fn expand_simple_parameter_into(
                     expanded_word.append(shell.program_name.clone(), inside_double_quotes, true);
                 }
             }
-            // special parameters are always set
-            ParameterExpansionResult::Set
+            match special_parameter {
+                // $@ and $* are null when there are no positional parameters
+                SpecialParameter::At | SpecialParameter::Asterisk
+                    if shell.positional_parameters.iter().all(|p| p.is_empty()) =>
+                {
+                    ParameterExpansionResult::Null
+                }
+                // $! is unset until a command is run in the background
+                SpecialParameter::Bang if shell.background_jobs.current().is_none() => {
+                    ParameterExpansionResult::Unset
+                }
+                // other special parameters are always set
+                _ => ParameterExpansionResult::Set,
+            }
         }
     }
 }
@@ -175,13 +249,14 @@ pub fn expand_parameter_into(
 ) -> ExpansionResult<()> {
     match parameter_expansion {
         ParameterExpansion::Simple(parameter) => {
-            expand_simple_parameter_into(
+            let parameter_type = expand_simple_parameter_into(
                 expanded_word,
                 parameter,
                 inside_double_quotes,
                 field_splitting_will_be_performed,
                 shell,
             );
+            check_nounset(parameter, parameter_type, shell)?;
         }
         ParameterExpansion::UnsetUseDefault {
             parameter,
@@ -197,7 +272,7 @@ pub fn expand_parameter_into(
                 shell,
             );
             if parameter_type.is_unset() || (*default_on_null && parameter_type.is_null()) {
-                simple_word_expansion_into(expanded_word, default, false, shell)?;
+                expand_nested_word_into(expanded_word, default, inside_double_quotes, shell)?;
             }
             expanded_word.extend(expanded_parameter);
         }
@@ -206,19 +281,19 @@ pub fn expand_parameter_into(
             word,
             assign_on_null,
         } => {
-            let value = expand_word_to_string(word, false, shell)?;
-
-            if let Some(current_value) = shell.environment.get_str_value(variable_name) {
-                if current_value.is_empty() && *assign_on_null {
+            let current_value = shell
+                .environment
+                .get_str_value(variable_name)
+                .map(|v| v.to_string());
+            let value = match current_value {
+                Some(value) if !value.is_empty() || !*assign_on_null => value,
+                _ => {
+                    let value = expand_word_to_string(word, false, shell)?;
                     shell.assign_global(variable_name.to_string(), value.clone())?;
-                    expanded_word.append(value, inside_double_quotes, true);
-                } else {
-                    expanded_word.append(current_value.to_string(), inside_double_quotes, true);
+                    value
                 }
-            } else {
-                shell.assign_global(variable_name.to_string(), value.clone())?;
-                expanded_word.append(value, inside_double_quotes, true);
-            }
+            };
+            expanded_word.append(value, inside_double_quotes, true);
         }
         ParameterExpansion::UnsetError {
             parameter,
@@ -234,18 +309,17 @@ pub fn expand_parameter_into(
                 shell,
             );
             if parameter_type.is_unset() || (*error_on_null && parameter_type.is_null()) {
-                return if word.parts.is_empty() {
-                    let message = expand_word_to_string(word, false, shell)?;
-                    Err(CommandExecutionError::ExpansionError(message))
+                let message = if !word.parts.is_empty() {
+                    expand_word_to_string(word, false, shell)?
                 } else if *error_on_null {
-                    Err(CommandExecutionError::ExpansionError(
-                        "parameter is unset or null".to_string(),
-                    ))
+                    "parameter is unset or null".to_string()
                 } else {
-                    Err(CommandExecutionError::ExpansionError(
-                        "parameter is unset".to_string(),
-                    ))
+                    "parameter is unset".to_string()
                 };
+                return Err(CommandExecutionError::ExpansionError(format!(
+                    "sh: {}: {message}",
+                    parameter_name(parameter)
+                )));
             }
             expanded_word.extend(expanded_parameter);
         }
@@ -265,9 +339,20 @@ pub fn expand_parameter_into(
             if !parameter_type.is_unset()
                 && (!parameter_type.is_null() || *substitute_null_with_word)
             {
-                simple_word_expansion_into(expanded_word, word, false, shell)?
+                expand_nested_word_into(expanded_word, word, inside_double_quotes, shell)?
             }
         }
+        ParameterExpansion::StrLen(Parameter::Special(
+            SpecialParameter::At | SpecialParameter::Asterisk,
+        )) => {
+            // the standard leaves this unspecified, like bash and ksh we
+            // expand to the number of positional parameters
+            expanded_word.append(
+                shell.positional_parameters.len().to_string(),
+                inside_double_quotes,
+                true,
+            );
+        }
         ParameterExpansion::StrLen(parameter) => {
             let mut expanded_parameter = ExpandedWord::default();
             let parameter_type = expand_simple_parameter_into(
@@ -277,13 +362,9 @@ pub fn expand_parameter_into(
                 false,
                 shell,
             );
-            if parameter_type.is_unset() && shell.set_options.nounset {
-                return Err(CommandExecutionError::ExpansionError(
-                    "sh: parameter is unset".to_string(),
-                ));
-            }
+            check_nounset(parameter, parameter_type, shell)?;
             expanded_word.append(
-                expanded_parameter.to_string().len().to_string(),
+                plib::ctype::char_count(&expanded_parameter.to_string()).to_string(),
                 inside_double_quotes,
                 true,
             );
@@ -302,11 +383,7 @@ pub fn expand_parameter_into(
                 field_splitting_will_be_performed,
                 shell,
             );
-            if parameter_type.is_unset() && shell.set_options.nounset {
-                return Err(CommandExecutionError::ExpansionError(
-                    "sh: parameter is unset".to_string(),
-                ));
-            }
+            check_nounset(parameter, parameter_type, shell)?;
             let param_str = expanded_parameter.to_string();
 
             let pattern = word_to_pattern(pattern, shell)?;
@@ -332,8 +409,6 @@ mod tests {
     use super::*;
     use crate::jobs::JobState;
     use crate::parse::word::test_utils::unquoted_literal;
-    use crate::parse::word::Word;
-    use crate::wordexp::expanded_word::ExpandedWordPart;
     use nix::unistd::Pid;
 
     fn shell_with_env(env: &[(&str, &str)]) -> Shell {
@@ -609,6 +684,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unset_error_parameter_expansion() {
+        let mut shell = shell_with_env(&[("HOME", "/home/test"), ("NULL", "")]);
+        let mut expand = |parameter: &str, word: Word, error_on_null: bool| {
+            let mut expanded_word = ExpandedWord::default();
+            expand_parameter_into(
+                &mut expanded_word,
+                &ParameterExpansion::UnsetError {
+                    parameter: Parameter::Variable(parameter.into()),
+                    word,
+                    error_on_null,
+                },
+                false,
+                false,
+                &mut shell,
+            )
+            .map(|_| expanded_word.to_string())
+            .map_err(|err| err.to_string())
+        };
+        assert_eq!(
+            expand("HOME", Word::default(), true),
+            Ok("/home/test".to_string())
+        );
+        assert_eq!(expand("NULL", Word::default(), false), Ok("".to_string()));
+        assert_eq!(
+            expand("NULL", Word::default(), true),
+            Err("sh: NULL: parameter is unset or null\n".to_string())
+        );
+        assert_eq!(
+            expand("unset_var", Word::default(), false),
+            Err("sh: unset_var: parameter is unset\n".to_string())
+        );
+        assert_eq!(
+            expand("unset_var", unquoted_literal("custom message"), false),
+            Err("sh: unset_var: custom message\n".to_string())
+        );
+    }
+
+    #[test]
+    fn expand_unset_parameter_with_nounset() {
+        let mut shell = shell_with_env(&[("NULL", "")]);
+        shell.set_options.nounset = true;
+        let mut expand = |parameter: Parameter| {
+            let mut expanded_word = ExpandedWord::default();
+            expand_parameter_into(
+                &mut expanded_word,
+                &ParameterExpansion::Simple(parameter),
+                false,
+                false,
+                &mut shell,
+            )
+            .map(|_| expanded_word.to_string())
+            .map_err(|err| err.to_string())
+        };
+        assert_eq!(
+            expand(Parameter::Variable("NULL".into())),
+            Ok("".to_string())
+        );
+        assert_eq!(
+            expand(Parameter::Special(SpecialParameter::At)),
+            Ok("".to_string())
+        );
+        assert_eq!(
+            expand(Parameter::Variable("unset_var".into())),
+            Err("sh: unset_var: parameter is unset\n".to_string())
+        );
+        assert_eq!(
+            expand(Parameter::Number(1)),
+            Err("sh: 1: parameter is unset\n".to_string())
+        );
+    }
+
+    #[test]
+    fn use_default_without_positional_parameters() {
+        let mut shell = shell_with_positional_arguments(vec![]);
+        for special_parameter in [SpecialParameter::At, SpecialParameter::Asterisk] {
+            assert_eq!(
+                expand_parameter_to_string(
+                    ParameterExpansion::UnsetUseDefault {
+                        parameter: Parameter::Special(special_parameter),
+                        word: unquoted_literal("default"),
+                        default_on_null: true,
+                    },
+                    &mut shell
+                ),
+                "default"
+            );
+        }
+    }
+
     #[test]
     fn string_length_parameter_expansion() {
         let mut shell = shell_with_env(&[("HOME", "/home/test_user")]);
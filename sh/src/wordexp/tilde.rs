@@ -9,8 +9,7 @@
 
 use crate::parse::word::{Word, WordPart};
 use crate::shell::environment::Environment;
-use nix::libc;
-use std::ffi::{c_char, CStr, CString};
+use plib::userdb;
 
 fn is_portable_filename_character(c: char) -> bool {
     // https://pubs.opengroup.org/onlinepubs/9699919799/basedefs/V1_chap03.html#tag_03_282
@@ -26,16 +25,7 @@ struct DefaultUsersHomeDirs;
 impl UsersHomeDirs for DefaultUsersHomeDirs {
     /// `login_name` has to be a valid login name
     fn get_user_home(&self, login_name: &str) -> Option<String> {
-        // it cannot contain a null char as part of the method's contract
-        let login_name = CString::new(login_name).unwrap();
-        let passwd = unsafe { libc::getpwnam(login_name.as_ptr()) };
-        if passwd.is_null() {
-            return None;
-        }
-        // this is safe, since the pointer is not null
-        // https://pubs.opengroup.org/onlinepubs/9699919799/functions/getpwnam.html
-        let user_home_dir = unsafe { CStr::from_ptr((*passwd).pw_dir as *const c_char) };
-        Some(String::from_utf8(user_home_dir.to_bytes().to_vec()).unwrap())
+        userdb::user_by_name(login_name).map(|user| user.dir.clone())
     }
 }
 
@@ -1,4 +1,6 @@
-use plib::testing::{run_test, run_test_with_checker, TestPlan};
+use plib::testing::{
+    run_test, run_test_with_checker, run_test_with_options, TestOptions, TestPlan,
+};
 use std::path::Path;
 use std::process::Output;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -77,6 +79,21 @@ pub fn test_script(script: &str, expected_output: &str) {
     });
 }
 
+fn test_script_with_options(script: &str, expected_output: &str, options: TestOptions) {
+    set_env_vars();
+    run_test_with_options(
+        TestPlan {
+            cmd: "sh".to_string(),
+            args: vec![],
+            stdin_data: script.to_string(),
+            expected_out: expected_output.to_string(),
+            expected_err: String::default(),
+            expected_exit_code: 0,
+        },
+        options,
+    );
+}
+
 fn test_script_expect_stderr_and_stdout(script: &str, expected_output: &str) {
     set_env_vars();
     run_test_with_checker(
@@ -272,6 +289,14 @@ set +o vi
         );
     }
 
+    #[test]
+    fn shell_readable_options_restore_the_options() {
+        test_script(
+            "set -Cu +h; saved=$(set +o); set +Cu -h -o noglob; eval \"$saved\"; echo $-",
+            "Cu\n",
+        );
+    }
+
     #[test]
     fn read_from_file() {
         test_cli(
@@ -659,6 +684,57 @@ mod word_expansion {
         test_script_expect_stderr_and_stdout("echo ${x:?error}", "");
         test_script("x=; echo ${x?error}", "\n");
         test_script_expect_stderr_and_stdout("x=; echo ${x:?error}", "");
+        test_script_expect_error_status_stderr_and_stdout(
+            "echo before; echo ${x:?custom message}; echo after",
+            Some("before\n"),
+        );
+    }
+
+    #[test]
+    fn assigning_readonly_variable_with_parameter_expansion_aborts_the_shell() {
+        test_script_expect_error_status_stderr_and_stdout(
+            "readonly x=; echo ${x:=value}; echo after",
+            Some(""),
+        );
+    }
+
+    #[test]
+    fn length_of_special_and_positional_parameters() {
+        test_script(
+            "set -- a bc def; echo ${#} ${#*} ${#@} ${#1} ${#3} ${#4} ${##}",
+            "3 3 3 1 3 0 1\n",
+        );
+    }
+
+    #[test]
+    fn string_length_counts_characters_in_utf8_locale() {
+        test_script_with_options(
+            "x=h\u{e9}llo; echo ${#x}",
+            "5\n",
+            TestOptions::default().env("LC_ALL", "C.UTF-8"),
+        );
+        test_script_with_options(
+            "x=h\u{e9}llo; echo ${#x}",
+            "6\n",
+            TestOptions::default().env("LC_ALL", "C"),
+        );
+    }
+
+    #[test]
+    fn expanding_unset_parameters_with_nounset_aborts_the_shell() {
+        test_script_expect_error_status_stderr_and_stdout(
+            "set -u; echo before; echo $UNSET; echo after",
+            Some("before\n"),
+        );
+        test_script_expect_error_status_stderr_and_stdout("set -u; echo $1; echo after", Some(""));
+        test_script_expect_error_status_stderr_and_stdout(
+            "set -u; echo $((UNSET + 1)); echo after",
+            Some(""),
+        );
+        test_script(
+            "set -u; echo $@ $* ${UNSET-default} ${UNSET+alt} $#; x=; echo \"[$x]\"",
+            "default 0\n[]\n",
+        );
     }
 
     #[test]
@@ -802,6 +878,14 @@ mod word_expansion {
         );
     }
     #[test]
+    fn tilde_expansion_uses_inherited_home() {
+        test_script_with_options(
+            "echo ~ ~/dir",
+            "/home/inherited /home/inherited/dir\n",
+            TestOptions::default().env("HOME", "/home/inherited"),
+        );
+    }
+    #[test]
     fn variable_expansion() {
         test_script(
             include_str!("sh/word_expansion/variable_expansion.sh"),
@@ -834,6 +918,27 @@ mod redirection {
         );
     }
 
+    #[test]
+    fn noclobber_only_refuses_to_truncate_regular_files() {
+        run_script_with_checker(
+            r#"
+            set -C
+            cd $TEST_WRITE_DIR
+            rm -f noclobber_only_refuses_to_truncate_regular_files.txt
+            echo test1 > noclobber_only_refuses_to_truncate_regular_files.txt
+            echo test2 >> noclobber_only_refuses_to_truncate_regular_files.txt
+            echo test3 > /dev/null
+            cat noclobber_only_refuses_to_truncate_regular_files.txt
+            rm noclobber_only_refuses_to_truncate_regular_files.txt
+            "#,
+            |output| {
+                assert!(output.status.success());
+                assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+                assert_eq!(String::from_utf8_lossy(&output.stdout), "test1\ntest2\n");
+            },
+        );
+    }
+
     #[test]
     fn duplicate_stderr_to_stdout() {
         run_script_with_checker("echo test 1>&2", |output| {
@@ -859,6 +964,14 @@ mod redirection {
         );
     }
 
+    #[test]
+    fn here_document_is_created_in_tmpdir() {
+        test_script(
+            include_str!("sh/redirection/here_document_is_created_in_tmpdir.sh"),
+            include_str!("sh/redirection/here_document_is_created_in_tmpdir.out"),
+        );
+    }
+
     #[test]
     fn contents_of_here_document_are_not_expanded_if_delimiter_is_quoted() {
         test_script(include_str!("sh/redirection/contents_of_here_document_are_not_expanded_if_delimiter_is_quoted.sh"), include_str!("sh/redirection/contents_of_here_document_are_not_expanded_if_delimiter_is_quoted.out"));
@@ -888,6 +1001,14 @@ mod redirection {
         );
     }
 
+    #[test]
+    fn here_document_is_consumed_by_reads() {
+        test_script(
+            include_str!("sh/redirection/here_document_is_consumed_by_reads.sh"),
+            include_str!("sh/redirection/here_document_is_consumed_by_reads.out"),
+        );
+    }
+
     #[test]
     fn input_redirection() {
         test_script(
@@ -896,6 +1017,14 @@ mod redirection {
         );
     }
 
+    #[test]
+    fn multiple_here_documents() {
+        test_script(
+            include_str!("sh/redirection/multiple_here_documents.sh"),
+            include_str!("sh/redirection/multiple_here_documents.out"),
+        );
+    }
+
     #[test]
     fn open_file_descriptor_for_read_and_write() {
         test_script(
@@ -961,6 +1090,18 @@ mod commands {
         test_script_expect_error_status_stderr_and_stdout("readonly x=1; x=2", None);
     }
 
+    #[test]
+    fn assigning_to_readonly_var_before_command_is_error() {
+        test_script_expect_error_status_stderr_and_stdout(
+            "readonly x=1; x=2 /bin/true; echo after",
+            Some(""),
+        );
+        test_script_expect_error_status_stderr_and_stdout(
+            "readonly x=1; f() { echo called; }; x=2 f; echo after",
+            Some(""),
+        );
+    }
+
     #[test]
     fn for_loop_without_iterable_iterates_over_shell_args() {
         test_cli(
@@ -1151,6 +1292,14 @@ mod builtin {
         test_script_expect_error_status_stderr_and_stdout("readonly x=1; readonly x=1", None);
     }
 
+    #[test]
+    fn cd_builtin() {
+        test_script(
+            include_str!("sh/builtin/cd.sh"),
+            include_str!("sh/builtin/cd.out"),
+        );
+    }
+
     #[test]
     fn break_builtin() {
         test_script(
@@ -1340,6 +1489,41 @@ mod builtin {
         )
     }
 
+    #[test]
+    fn kill_self_terminates_shell() {
+        set_env_vars();
+        run_test_with_options(
+            TestPlan {
+                cmd: "sh".to_string(),
+                args: vec![],
+                stdin_data: "kill -s TERM $$\necho not reached\n".to_string(),
+                expected_out: String::default(),
+                expected_err: String::default(),
+                expected_exit_code: 0,
+            },
+            TestOptions {
+                expected_signal: Some(nix::sys::signal::Signal::SIGTERM as i32),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn printf() {
+        test_script(
+            include_str!("sh/builtin/printf.sh"),
+            include_str!("sh/builtin/printf.out"),
+        )
+    }
+
+    #[test]
+    fn test() {
+        test_script(
+            include_str!("sh/builtin/test.sh"),
+            include_str!("sh/builtin/test.out"),
+        )
+    }
+
     #[test]
     fn umask() {
         test_script(
@@ -1348,6 +1532,27 @@ mod builtin {
         )
     }
 
+    #[test]
+    fn ulimit() {
+        test_script(
+            include_str!("sh/builtin/ulimit.sh"),
+            include_str!("sh/builtin/ulimit.out"),
+        )
+    }
+
+    #[test]
+    fn pwd_in_working_directory() {
+        let dir = std::env::current_dir().unwrap().join("tests");
+        test_script_with_options(
+            "pwd",
+            &format!("{}\n", dir.display()),
+            TestOptions {
+                cwd: Some(dir.clone()),
+                ..Default::default()
+            },
+        );
+    }
+
     #[test]
     fn read() {
         test_script(
@@ -0,0 +1,37 @@
+cd -P "$TEST_WRITE_DIR"
+base=$PWD
+rm -rf cd_builtin
+mkdir -p cd_builtin/real/sub cd_builtin/cdpath/inner
+ln -s real cd_builtin/link
+cd cd_builtin
+
+# -L keeps symbolic links in PWD and resolves dot-dot lexically
+cd link/sub
+echo "${PWD#$base}"
+cd ..
+echo "${PWD#$base}"
+pwd -L | sed "s|$base||"
+cd ..
+cd -P link
+echo "${PWD#$base}"
+
+# cd - goes back to OLDPWD and writes it
+cd - | sed "s|$base||"
+cd - >/dev/null
+echo "${PWD#$base}"
+echo "${OLDPWD#$base}"
+cd ..
+
+# directories found through a non-empty entry of CDPATH are written
+CDPATH=:$base/cd_builtin/cdpath
+cd inner | sed "s|$base||"
+cd inner >/dev/null
+echo "${PWD#$base}"
+cd ..
+cd inner
+echo "${PWD#$base}"
+cd "$base/cd_builtin"
+cd ./inner 2>/dev/null || echo "./inner is not searched for"
+
+cd "$base"
+rm -rf cd_builtin
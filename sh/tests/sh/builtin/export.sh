@@ -12,4 +12,15 @@ echo $?
 awk 'BEGIN { print ENVIRON["export_test_var3"] }'
 
 export -p | grep export_test_var
-export -p -- | grep export_test_var
\ No newline at end of file
+export -p -- | grep export_test_var
+export export_test_quote="it's a \$value"
+export -p | grep export_test_quote
+saved=$(export -p | grep export_test_quote)
+unset export_test_quote
+eval "$saved"
+echo "$export_test_quote"
+awk 'BEGIN { print ENVIRON["export_test_quote"] }'
+
+export export_test_unset
+export | grep export_test_unset
+awk 'BEGIN { print "export_test_unset" in ENVIRON }'
@@ -0,0 +1,13 @@
+printf '%s\n' one two three
+printf '%s=%d\n' a 1 b
+printf '%5s|%-5s|%.2s\n' ab cd efg
+printf '%05d %x %o %c\n' -42 255 8 xyz
+printf '%b\n' 'tab\there'
+printf 'stop\c here\n'
+printf '\n'
+printf '%d\n' 12abc 2>/dev/null
+echo $?
+printf '%d\n' "'A"
+printf '%s\n' > $TEST_WRITE_DIR/printf.txt
+read line < $TEST_WRITE_DIR/printf.txt
+echo "[$line]"
@@ -23,4 +23,12 @@ readonly -p
 echo $var2
 readonly var2
 echo $var2
-readonly -p
\ No newline at end of file
+readonly -p
+readonly quoted="it's"
+readonly -p | grep quoted
+readonly | grep quoted
+
+(quoted=value true) 2>/dev/null
+echo $?
+(quoted=value /bin/true) 2>/dev/null
+echo $?
@@ -0,0 +1,12 @@
+test a = a && echo equal
+test a = b || echo different
+[ 10 -gt 9 ] && echo greater
+[ -d / -a ! -f / ] && echo directory
+[ "" -o x ] && echo or
+[ \( a = b \) -o \( c = c \) ] && echo grouped
+test -z "" && echo empty
+[ -n x 2>/dev/null
+echo $?
+test 1 -eq x 2>/dev/null
+echo $?
+test -t 1 > $TEST_WRITE_DIR/test.txt || echo not a terminal
@@ -0,0 +1,20 @@
+(
+  ulimit -S -n 16
+  echo $?
+  ulimit -S -n
+)
+
+(
+  ulimit -S -f 1024
+  echo $?
+  ulimit -S -f
+)
+
+ulimit -S -n 12x 2>&1
+echo $?
+ulimit -S -f 1k 2>&1
+echo $?
+ulimit -S -n +5 2>&1
+echo $?
+ulimit -S -n 99999999999999999999999 2>&1
+echo $?
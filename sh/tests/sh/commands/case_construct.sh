@@ -25,4 +25,10 @@ esac
 case value in
   (value) echo correct;;
   ($(echo incorrect)) echo incorrect;;
-esac
\ No newline at end of file
+esac
+
+case value in
+    (alu) echo incorrect;;
+    (val | lue) echo incorrect;;
+    (v*e) echo correct;;
+esac
@@ -0,0 +1,16 @@
+while read -r line; do
+    echo "read: $line"
+done <<end
+first line
+second line
+third line
+end
+
+{
+    read -r a
+    read -r b
+    echo "$b $a"
+} <<end
+one
+two
+end
@@ -0,0 +1,11 @@
+TMPDIR=/dev/null
+(
+cat <<EOF
+unreachable
+EOF
+) 2>/dev/null
+echo $?
+unset TMPDIR
+cat <<EOF
+reachable
+EOF
@@ -0,0 +1,15 @@
+cat <<first; cat <<second | tr a-z A-Z
+first here-document
+first
+second here-document
+second
+cat <<-a /dev/fd/3 3<<-b
+	not read
+	a
+		from file descriptor 3
+	b
+x=$(cat <<end
+inside ) command substitution
+end
+)
+echo "$x"
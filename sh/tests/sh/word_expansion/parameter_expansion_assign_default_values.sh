@@ -16,3 +16,5 @@ echo ${NULL=default}
 echo $NULL
 echo ${UNSET=default}
 echo $UNSET
+
+echo ${VAR:=$(echo not expanded >&2; echo ignored)}
@@ -10,4 +10,13 @@ echo ${VAR%%b*}
 echo ${VAR#}
 echo ${VAR#*b}
 echo ${VAR##}
-echo ${VAR##*b}
\ No newline at end of file
+echo ${VAR##*b}
+
+echo ${VAR%a} ${VAR%%x} ${VAR#c} ${VAR##x}
+echo ${VAR%*} ${VAR#*} [${VAR%%*}] [${VAR##*}]
+echo ${VAR%"b*"} ${VAR#\*}
+PATTERN='*b'
+echo ${VAR#$PATTERN} ${VAR#"$PATTERN"}
+FILE=/usr/lib/archive.tar.gz
+echo ${FILE%.*} ${FILE%%.*} ${FILE#*/} ${FILE##*/}
+echo "${FILE%.gz}"
@@ -8,4 +8,8 @@ echo $VAR $NULL
 echo ${VAR-default}
 echo ${NULL-default}
 echo ${unset-default}
-echo $VAR $NULL
\ No newline at end of file
+echo $VAR $NULL
+echo ${unset:-default   with   spaces}
+echo "${unset:-default   with   spaces}"
+echo "${unset:-*}"
+echo ${@:-no positional parameters} ${*-is set}
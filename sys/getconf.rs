@@ -6,343 +6,564 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - How to obtain a complete list of sysconf and pathconf variables,
-//   POSIX spec, OS headers, or another source?
-// - Proper -v specification support.  is it even necessary?
-//
 
-use std::collections::HashMap;
 use std::ffi::CString;
+use std::io;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use libc::{pathconf, sysconf};
+use libc::{c_char, c_int};
+use plib::diag::error_message;
+use plib::{diag, tdiag};
 
+/// getconf - get configuration values
 #[derive(Parser)]
-#[command(version, about = gettext("getconf - get configuration values"))]
+#[command(version, about)]
 struct Args {
-    #[arg(help = gettext("Variable to get the value of"))]
+    /// Programming environment the value is for, such as
+    /// POSIX_V7_LP64_OFF64
+    #[arg(short = 'v')]
+    specification: Option<String>,
+
+    /// System variable, or with a pathname, path variable
     var: String,
 
-    #[arg(help = gettext("Pathname for path configuration variables"))]
+    /// Pathname for path configuration variables
     pathname: Option<String>,
-
-    #[arg(
-        short = 'v',
-        long,
-        help = gettext("Specification for the variable (optional)")
-    )]
-    specification: Option<String>,
 }
 
-fn get_mapping(
-    prefix: &str,
-    var: &str,
-    mapping: &HashMap<&'static str, libc::c_int>,
-) -> Option<libc::c_int> {
-    let mut key = var.to_string();
-    if !mapping.contains_key(&key.as_str()) {
-        key = format!("{}{}", prefix, var);
-        if !mapping.contains_key(&key.as_str()) {
-            return None;
-        }
-    }
+/// Variables of sysconf(3).
+const SYSCONF_VARS: &[(&str, c_int)] = &[
+    ("AIO_LISTIO_MAX", libc::_SC_AIO_LISTIO_MAX),
+    ("AIO_MAX", libc::_SC_AIO_MAX),
+    ("AIO_PRIO_DELTA_MAX", libc::_SC_AIO_PRIO_DELTA_MAX),
+    ("ARG_MAX", libc::_SC_ARG_MAX),
+    ("ATEXIT_MAX", libc::_SC_ATEXIT_MAX),
+    ("BC_BASE_MAX", libc::_SC_BC_BASE_MAX),
+    ("BC_DIM_MAX", libc::_SC_BC_DIM_MAX),
+    ("BC_SCALE_MAX", libc::_SC_BC_SCALE_MAX),
+    ("BC_STRING_MAX", libc::_SC_BC_STRING_MAX),
+    ("CHILD_MAX", libc::_SC_CHILD_MAX),
+    ("CLK_TCK", libc::_SC_CLK_TCK),
+    ("COLL_WEIGHTS_MAX", libc::_SC_COLL_WEIGHTS_MAX),
+    ("DELAYTIMER_MAX", libc::_SC_DELAYTIMER_MAX),
+    ("EXPR_NEST_MAX", libc::_SC_EXPR_NEST_MAX),
+    ("GETGR_R_SIZE_MAX", libc::_SC_GETGR_R_SIZE_MAX),
+    ("GETPW_R_SIZE_MAX", libc::_SC_GETPW_R_SIZE_MAX),
+    ("HOST_NAME_MAX", libc::_SC_HOST_NAME_MAX),
+    ("IOV_MAX", libc::_SC_IOV_MAX),
+    ("LINE_MAX", libc::_SC_LINE_MAX),
+    ("LOGIN_NAME_MAX", libc::_SC_LOGIN_NAME_MAX),
+    ("MQ_OPEN_MAX", libc::_SC_MQ_OPEN_MAX),
+    ("MQ_PRIO_MAX", libc::_SC_MQ_PRIO_MAX),
+    ("NGROUPS_MAX", libc::_SC_NGROUPS_MAX),
+    ("NPROCESSORS_CONF", libc::_SC_NPROCESSORS_CONF),
+    ("NPROCESSORS_ONLN", libc::_SC_NPROCESSORS_ONLN),
+    ("OPEN_MAX", libc::_SC_OPEN_MAX),
+    ("PAGESIZE", libc::_SC_PAGESIZE),
+    ("PAGE_SIZE", libc::_SC_PAGE_SIZE),
+    ("RE_DUP_MAX", libc::_SC_RE_DUP_MAX),
+    ("RTSIG_MAX", libc::_SC_RTSIG_MAX),
+    ("SEM_NSEMS_MAX", libc::_SC_SEM_NSEMS_MAX),
+    ("SEM_VALUE_MAX", libc::_SC_SEM_VALUE_MAX),
+    ("SIGQUEUE_MAX", libc::_SC_SIGQUEUE_MAX),
+    ("SS_REPL_MAX", libc::_SC_SS_REPL_MAX),
+    ("STREAM_MAX", libc::_SC_STREAM_MAX),
+    ("SYMLOOP_MAX", libc::_SC_SYMLOOP_MAX),
+    (
+        "THREAD_DESTRUCTOR_ITERATIONS",
+        libc::_SC_THREAD_DESTRUCTOR_ITERATIONS,
+    ),
+    ("THREAD_KEYS_MAX", libc::_SC_THREAD_KEYS_MAX),
+    ("THREAD_STACK_MIN", libc::_SC_THREAD_STACK_MIN),
+    ("THREAD_THREADS_MAX", libc::_SC_THREAD_THREADS_MAX),
+    ("TIMER_MAX", libc::_SC_TIMER_MAX),
+    ("TRACE_EVENT_NAME_MAX", libc::_SC_TRACE_EVENT_NAME_MAX),
+    ("TRACE_NAME_MAX", libc::_SC_TRACE_NAME_MAX),
+    ("TRACE_SYS_MAX", libc::_SC_TRACE_SYS_MAX),
+    ("TRACE_USER_EVENT_MAX", libc::_SC_TRACE_USER_EVENT_MAX),
+    ("TTY_NAME_MAX", libc::_SC_TTY_NAME_MAX),
+    ("TZNAME_MAX", libc::_SC_TZNAME_MAX),
+    ("_POSIX_ADVISORY_INFO", libc::_SC_ADVISORY_INFO),
+    ("_POSIX_ASYNCHRONOUS_IO", libc::_SC_ASYNCHRONOUS_IO),
+    ("_POSIX_BARRIERS", libc::_SC_BARRIERS),
+    ("_POSIX_CLOCK_SELECTION", libc::_SC_CLOCK_SELECTION),
+    ("_POSIX_CPUTIME", libc::_SC_CPUTIME),
+    ("_POSIX_FSYNC", libc::_SC_FSYNC),
+    ("_POSIX_IPV6", libc::_SC_IPV6),
+    ("_POSIX_JOB_CONTROL", libc::_SC_JOB_CONTROL),
+    ("_POSIX_MAPPED_FILES", libc::_SC_MAPPED_FILES),
+    ("_POSIX_MEMLOCK", libc::_SC_MEMLOCK),
+    ("_POSIX_MEMLOCK_RANGE", libc::_SC_MEMLOCK_RANGE),
+    ("_POSIX_MEMORY_PROTECTION", libc::_SC_MEMORY_PROTECTION),
+    ("_POSIX_MESSAGE_PASSING", libc::_SC_MESSAGE_PASSING),
+    ("_POSIX_MONOTONIC_CLOCK", libc::_SC_MONOTONIC_CLOCK),
+    ("_POSIX_PRIORITIZED_IO", libc::_SC_PRIORITIZED_IO),
+    ("_POSIX_PRIORITY_SCHEDULING", libc::_SC_PRIORITY_SCHEDULING),
+    ("_POSIX_RAW_SOCKETS", libc::_SC_RAW_SOCKETS),
+    ("_POSIX_READER_WRITER_LOCKS", libc::_SC_READER_WRITER_LOCKS),
+    ("_POSIX_REALTIME_SIGNALS", libc::_SC_REALTIME_SIGNALS),
+    ("_POSIX_REGEXP", libc::_SC_REGEXP),
+    ("_POSIX_SAVED_IDS", libc::_SC_SAVED_IDS),
+    ("_POSIX_SEMAPHORES", libc::_SC_SEMAPHORES),
+    (
+        "_POSIX_SHARED_MEMORY_OBJECTS",
+        libc::_SC_SHARED_MEMORY_OBJECTS,
+    ),
+    ("_POSIX_SHELL", libc::_SC_SHELL),
+    ("_POSIX_SPAWN", libc::_SC_SPAWN),
+    ("_POSIX_SPIN_LOCKS", libc::_SC_SPIN_LOCKS),
+    ("_POSIX_SPORADIC_SERVER", libc::_SC_SPORADIC_SERVER),
+    ("_POSIX_SYNCHRONIZED_IO", libc::_SC_SYNCHRONIZED_IO),
+    ("_POSIX_THREADS", libc::_SC_THREADS),
+    (
+        "_POSIX_THREAD_ATTR_STACKADDR",
+        libc::_SC_THREAD_ATTR_STACKADDR,
+    ),
+    (
+        "_POSIX_THREAD_ATTR_STACKSIZE",
+        libc::_SC_THREAD_ATTR_STACKSIZE,
+    ),
+    ("_POSIX_THREAD_CPUTIME", libc::_SC_THREAD_CPUTIME),
+    ("_POSIX_THREAD_PRIO_INHERIT", libc::_SC_THREAD_PRIO_INHERIT),
+    ("_POSIX_THREAD_PRIO_PROTECT", libc::_SC_THREAD_PRIO_PROTECT),
+    (
+        "_POSIX_THREAD_PRIORITY_SCHEDULING",
+        libc::_SC_THREAD_PRIORITY_SCHEDULING,
+    ),
+    (
+        "_POSIX_THREAD_PROCESS_SHARED",
+        libc::_SC_THREAD_PROCESS_SHARED,
+    ),
+    (
+        "_POSIX_THREAD_SAFE_FUNCTIONS",
+        libc::_SC_THREAD_SAFE_FUNCTIONS,
+    ),
+    (
+        "_POSIX_THREAD_SPORADIC_SERVER",
+        libc::_SC_THREAD_SPORADIC_SERVER,
+    ),
+    ("_POSIX_TIMEOUTS", libc::_SC_TIMEOUTS),
+    ("_POSIX_TIMERS", libc::_SC_TIMERS),
+    ("_POSIX_TRACE", libc::_SC_TRACE),
+    ("_POSIX_TRACE_EVENT_FILTER", libc::_SC_TRACE_EVENT_FILTER),
+    ("_POSIX_TRACE_INHERIT", libc::_SC_TRACE_INHERIT),
+    ("_POSIX_TRACE_LOG", libc::_SC_TRACE_LOG),
+    (
+        "_POSIX_TYPED_MEMORY_OBJECTS",
+        libc::_SC_TYPED_MEMORY_OBJECTS,
+    ),
+    ("_POSIX_V6_ILP32_OFF32", libc::_SC_V6_ILP32_OFF32),
+    ("_POSIX_V6_ILP32_OFFBIG", libc::_SC_V6_ILP32_OFFBIG),
+    ("_POSIX_V6_LP64_OFF64", libc::_SC_V6_LP64_OFF64),
+    ("_POSIX_V6_LPBIG_OFFBIG", libc::_SC_V6_LPBIG_OFFBIG),
+    ("_POSIX_VERSION", libc::_SC_VERSION),
+    ("POSIX2_C_BIND", libc::_SC_2_C_BIND),
+    ("POSIX2_C_DEV", libc::_SC_2_C_DEV),
+    ("POSIX2_CHAR_TERM", libc::_SC_2_CHAR_TERM),
+    ("POSIX2_FORT_DEV", libc::_SC_2_FORT_DEV),
+    ("POSIX2_FORT_RUN", libc::_SC_2_FORT_RUN),
+    ("POSIX2_LOCALEDEF", libc::_SC_2_LOCALEDEF),
+    ("POSIX2_PBS", libc::_SC_2_PBS),
+    ("POSIX2_PBS_ACCOUNTING", libc::_SC_2_PBS_ACCOUNTING),
+    ("POSIX2_PBS_CHECKPOINT", libc::_SC_2_PBS_CHECKPOINT),
+    ("POSIX2_PBS_LOCATE", libc::_SC_2_PBS_LOCATE),
+    ("POSIX2_PBS_MESSAGE", libc::_SC_2_PBS_MESSAGE),
+    ("POSIX2_PBS_TRACK", libc::_SC_2_PBS_TRACK),
+    ("POSIX2_SW_DEV", libc::_SC_2_SW_DEV),
+    ("POSIX2_UPE", libc::_SC_2_UPE),
+    ("POSIX2_VERSION", libc::_SC_2_VERSION),
+    ("_XOPEN_CRYPT", libc::_SC_XOPEN_CRYPT),
+    ("_XOPEN_ENH_I18N", libc::_SC_XOPEN_ENH_I18N),
+    ("_XOPEN_LEGACY", libc::_SC_XOPEN_LEGACY),
+    ("_XOPEN_REALTIME", libc::_SC_XOPEN_REALTIME),
+    ("_XOPEN_REALTIME_THREADS", libc::_SC_XOPEN_REALTIME_THREADS),
+    ("_XOPEN_SHM", libc::_SC_XOPEN_SHM),
+    ("_XOPEN_STREAMS", libc::_SC_XOPEN_STREAMS),
+    ("_XOPEN_UNIX", libc::_SC_XOPEN_UNIX),
+    ("_XOPEN_VERSION", libc::_SC_XOPEN_VERSION),
+];
 
-    Some(*mapping.get(&key.as_str()).unwrap())
-}
+#[cfg(target_os = "linux")]
+const PLATFORM_SYSCONF_VARS: &[(&str, c_int)] = &[
+    ("_POSIX_V7_ILP32_OFF32", libc::_SC_V7_ILP32_OFF32),
+    ("_POSIX_V7_ILP32_OFFBIG", libc::_SC_V7_ILP32_OFFBIG),
+    ("_POSIX_V7_LP64_OFF64", libc::_SC_V7_LP64_OFF64),
+    ("_POSIX_V7_LPBIG_OFFBIG", libc::_SC_V7_LPBIG_OFFBIG),
+];
 
-fn handle_sysconf(
-    var: &str,
-    sysconf_mappings: &HashMap<&'static str, libc::c_int>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let value = match get_mapping("_SC_", var, sysconf_mappings) {
-        Some(value) => value,
-        None => {
-            let errstr = format!(
-                "{}: {}",
-                gettext("Error: Unknown system configuration variable"),
-                var
-            );
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                errstr,
-            )));
-        }
-    };
+#[cfg(not(target_os = "linux"))]
+const PLATFORM_SYSCONF_VARS: &[(&str, c_int)] = &[];
 
-    // Get the value using sysconf
-    let value = unsafe { sysconf(value) };
-    if value == -1 {
-        eprintln!(
-            "{}: {}",
-            gettext("Error: Unknown system configuration variable"),
-            var
-        );
-        std::process::exit(1);
-    } else {
-        println!("{}", value);
-    }
+/// Variables of confstr(3).
+#[cfg(target_os = "linux")]
+const CONFSTR_VARS: &[(&str, c_int)] = &[
+    ("PATH", libc::_CS_PATH),
+    (
+        "POSIX_V6_WIDTH_RESTRICTED_ENVS",
+        libc::_CS_POSIX_V6_WIDTH_RESTRICTED_ENVS,
+    ),
+    (
+        "POSIX_V7_WIDTH_RESTRICTED_ENVS",
+        libc::_CS_POSIX_V7_WIDTH_RESTRICTED_ENVS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFF32_CFLAGS",
+        libc::_CS_POSIX_V6_ILP32_OFF32_CFLAGS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFF32_LDFLAGS",
+        libc::_CS_POSIX_V6_ILP32_OFF32_LDFLAGS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFF32_LIBS",
+        libc::_CS_POSIX_V6_ILP32_OFF32_LIBS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFFBIG_CFLAGS",
+        libc::_CS_POSIX_V6_ILP32_OFFBIG_CFLAGS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFFBIG_LDFLAGS",
+        libc::_CS_POSIX_V6_ILP32_OFFBIG_LDFLAGS,
+    ),
+    (
+        "POSIX_V6_ILP32_OFFBIG_LIBS",
+        libc::_CS_POSIX_V6_ILP32_OFFBIG_LIBS,
+    ),
+    (
+        "POSIX_V6_LP64_OFF64_CFLAGS",
+        libc::_CS_POSIX_V6_LP64_OFF64_CFLAGS,
+    ),
+    (
+        "POSIX_V6_LP64_OFF64_LDFLAGS",
+        libc::_CS_POSIX_V6_LP64_OFF64_LDFLAGS,
+    ),
+    (
+        "POSIX_V6_LP64_OFF64_LIBS",
+        libc::_CS_POSIX_V6_LP64_OFF64_LIBS,
+    ),
+    (
+        "POSIX_V6_LPBIG_OFFBIG_CFLAGS",
+        libc::_CS_POSIX_V6_LPBIG_OFFBIG_CFLAGS,
+    ),
+    (
+        "POSIX_V6_LPBIG_OFFBIG_LDFLAGS",
+        libc::_CS_POSIX_V6_LPBIG_OFFBIG_LDFLAGS,
+    ),
+    (
+        "POSIX_V6_LPBIG_OFFBIG_LIBS",
+        libc::_CS_POSIX_V6_LPBIG_OFFBIG_LIBS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFF32_CFLAGS",
+        libc::_CS_POSIX_V7_ILP32_OFF32_CFLAGS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFF32_LDFLAGS",
+        libc::_CS_POSIX_V7_ILP32_OFF32_LDFLAGS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFF32_LIBS",
+        libc::_CS_POSIX_V7_ILP32_OFF32_LIBS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFFBIG_CFLAGS",
+        libc::_CS_POSIX_V7_ILP32_OFFBIG_CFLAGS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFFBIG_LDFLAGS",
+        libc::_CS_POSIX_V7_ILP32_OFFBIG_LDFLAGS,
+    ),
+    (
+        "POSIX_V7_ILP32_OFFBIG_LIBS",
+        libc::_CS_POSIX_V7_ILP32_OFFBIG_LIBS,
+    ),
+    (
+        "POSIX_V7_LP64_OFF64_CFLAGS",
+        libc::_CS_POSIX_V7_LP64_OFF64_CFLAGS,
+    ),
+    (
+        "POSIX_V7_LP64_OFF64_LDFLAGS",
+        libc::_CS_POSIX_V7_LP64_OFF64_LDFLAGS,
+    ),
+    (
+        "POSIX_V7_LP64_OFF64_LIBS",
+        libc::_CS_POSIX_V7_LP64_OFF64_LIBS,
+    ),
+    (
+        "POSIX_V7_LPBIG_OFFBIG_CFLAGS",
+        libc::_CS_POSIX_V7_LPBIG_OFFBIG_CFLAGS,
+    ),
+    (
+        "POSIX_V7_LPBIG_OFFBIG_LDFLAGS",
+        libc::_CS_POSIX_V7_LPBIG_OFFBIG_LDFLAGS,
+    ),
+    (
+        "POSIX_V7_LPBIG_OFFBIG_LIBS",
+        libc::_CS_POSIX_V7_LPBIG_OFFBIG_LIBS,
+    ),
+];
 
-    Ok(())
-}
+#[cfg(target_os = "macos")]
+const CONFSTR_VARS: &[(&str, c_int)] = &[
+    ("PATH", libc::_CS_PATH),
+    ("DARWIN_USER_DIR", libc::_CS_DARWIN_USER_DIR),
+    ("DARWIN_USER_TEMP_DIR", libc::_CS_DARWIN_USER_TEMP_DIR),
+    ("DARWIN_USER_CACHE_DIR", libc::_CS_DARWIN_USER_CACHE_DIR),
+];
+
+/// Variables of pathconf(3).
+const PATHCONF_VARS: &[(&str, c_int)] = &[
+    ("FILESIZEBITS", libc::_PC_FILESIZEBITS),
+    ("LINK_MAX", libc::_PC_LINK_MAX),
+    ("MAX_CANON", libc::_PC_MAX_CANON),
+    ("MAX_INPUT", libc::_PC_MAX_INPUT),
+    ("NAME_MAX", libc::_PC_NAME_MAX),
+    ("PATH_MAX", libc::_PC_PATH_MAX),
+    ("PIPE_BUF", libc::_PC_PIPE_BUF),
+    ("POSIX2_SYMLINKS", libc::_PC_2_SYMLINKS),
+    ("POSIX_ALLOC_SIZE_MIN", libc::_PC_ALLOC_SIZE_MIN),
+    ("POSIX_REC_INCR_XFER_SIZE", libc::_PC_REC_INCR_XFER_SIZE),
+    ("POSIX_REC_MAX_XFER_SIZE", libc::_PC_REC_MAX_XFER_SIZE),
+    ("POSIX_REC_MIN_XFER_SIZE", libc::_PC_REC_MIN_XFER_SIZE),
+    ("POSIX_REC_XFER_ALIGN", libc::_PC_REC_XFER_ALIGN),
+    ("SYMLINK_MAX", libc::_PC_SYMLINK_MAX),
+    ("_POSIX_ASYNC_IO", libc::_PC_ASYNC_IO),
+    ("_POSIX_CHOWN_RESTRICTED", libc::_PC_CHOWN_RESTRICTED),
+    ("_POSIX_NO_TRUNC", libc::_PC_NO_TRUNC),
+    ("_POSIX_PRIO_IO", libc::_PC_PRIO_IO),
+    ("_POSIX_SYNC_IO", libc::_PC_SYNC_IO),
+    ("_POSIX_VDISABLE", libc::_PC_VDISABLE),
+    // Without the prefix, as earlier versions accepted them
+    ("CHOWN_RESTRICTED", libc::_PC_CHOWN_RESTRICTED),
+    ("NO_TRUNC", libc::_PC_NO_TRUNC),
+    ("VDISABLE", libc::_PC_VDISABLE),
+];
 
 #[cfg(target_os = "linux")]
-fn handle_confstr(
-    _var: &str,
-    _confstr_mappings: &HashMap<&'static str, libc::c_int>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    Err(Box::new(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        "Not implemented (pls update libc crate)",
-    )))
-}
+const MB_LEN_MAX: i128 = 16;
 
 #[cfg(not(target_os = "linux"))]
-fn handle_confstr(
-    var: &str,
-    confstr_mappings: &HashMap<&'static str, libc::c_int>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let value = match get_mapping("_CS_", var, confstr_mappings) {
-        Some(value) => value,
-        None => {
-            let errstr = format!(
-                "{}: {}",
-                gettext("Error: Unknown configuration string variable"),
-                var
-            );
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                errstr,
-            )));
-        }
-    };
+const MB_LEN_MAX: i128 = 6;
 
-    // Get the value using confstr
-    let buflen = unsafe { libc::confstr(value, std::ptr::null_mut(), 0) };
-    if buflen == 0 {
-        eprintln!(
-            "{}: {}",
-            gettext("Error: Unknown configuration string variable"),
-            var
-        );
-        std::process::exit(1);
-    }
+#[cfg(target_os = "linux")]
+const NL_LIMITS: [i128; 5] = [
+    4096,
+    2048,
+    i32::MAX as i128,
+    i32::MAX as i128,
+    i32::MAX as i128,
+];
 
-    let mut buf = vec![0u8; buflen as usize];
-    let value = unsafe {
-        libc::confstr(
-            value,
-            buf.as_mut_ptr() as *mut libc::c_char,
-            buf.len() as libc::size_t,
-        )
-    };
-    if value == 0 {
-        eprintln!(
-            "{}: {}",
-            gettext("Error: Unknown configuration string variable"),
-            var
-        );
-        std::process::exit(1);
-    } else {
-        let s = std::str::from_utf8(&buf).unwrap();
-        println!("{}", s);
-    }
+#[cfg(not(target_os = "linux"))]
+const NL_LIMITS: [i128; 5] = [9, 14, 32767, 255, 2048];
+
+/// The constants of <limits.h>: the limits of the C types, and the
+/// minimum values POSIX allows for the other limits.
+fn limits() -> Vec<(&'static str, i128)> {
+    use libc::{c_char, c_long, c_schar, c_short, c_uchar, c_uint, c_ulong, c_ushort, ssize_t};
 
-    Ok(())
+    let [nl_argmax, nl_langmax, nl_msgmax, nl_setmax, nl_textmax] = NL_LIMITS;
+    vec![
+        ("CHAR_BIT", 8),
+        ("CHAR_MAX", c_char::MAX.into()),
+        ("CHAR_MIN", c_char::MIN.into()),
+        ("INT_MAX", c_int::MAX.into()),
+        ("INT_MIN", c_int::MIN.into()),
+        ("LONG_BIT", c_long::BITS.into()),
+        ("LONG_MAX", c_long::MAX.into()),
+        ("LONG_MIN", c_long::MIN.into()),
+        ("MB_LEN_MAX", MB_LEN_MAX),
+        ("SCHAR_MAX", c_schar::MAX.into()),
+        ("SCHAR_MIN", c_schar::MIN.into()),
+        ("SHRT_MAX", c_short::MAX.into()),
+        ("SHRT_MIN", c_short::MIN.into()),
+        ("SSIZE_MAX", ssize_t::MAX as i128),
+        ("UCHAR_MAX", c_uchar::MAX.into()),
+        ("UINT_MAX", c_uint::MAX.into()),
+        ("ULONG_MAX", c_ulong::MAX.into()),
+        ("USHRT_MAX", c_ushort::MAX.into()),
+        ("WORD_BIT", c_int::BITS.into()),
+        ("NL_ARGMAX", nl_argmax),
+        ("NL_LANGMAX", nl_langmax),
+        ("NL_MSGMAX", nl_msgmax),
+        ("NL_SETMAX", nl_setmax),
+        ("NL_TEXTMAX", nl_textmax),
+        ("NZERO", 20),
+        ("_POSIX_AIO_LISTIO_MAX", 2),
+        ("_POSIX_AIO_MAX", 1),
+        ("_POSIX_ARG_MAX", 4096),
+        ("_POSIX_CHILD_MAX", 25),
+        ("_POSIX_DELAYTIMER_MAX", 32),
+        ("_POSIX_HOST_NAME_MAX", 255),
+        ("_POSIX_LINK_MAX", 8),
+        ("_POSIX_LOGIN_NAME_MAX", 9),
+        ("_POSIX_MAX_CANON", 255),
+        ("_POSIX_MAX_INPUT", 255),
+        ("_POSIX_MQ_OPEN_MAX", 8),
+        ("_POSIX_MQ_PRIO_MAX", 32),
+        ("_POSIX_NAME_MAX", 14),
+        ("_POSIX_NGROUPS_MAX", 8),
+        ("_POSIX_OPEN_MAX", 20),
+        ("_POSIX_PATH_MAX", 256),
+        ("_POSIX_PIPE_BUF", 512),
+        ("_POSIX_RE_DUP_MAX", 255),
+        ("_POSIX_RTSIG_MAX", 8),
+        ("_POSIX_SEM_NSEMS_MAX", 256),
+        ("_POSIX_SEM_VALUE_MAX", 32767),
+        ("_POSIX_SIGQUEUE_MAX", 32),
+        ("_POSIX_SSIZE_MAX", 32767),
+        ("_POSIX_STREAM_MAX", 8),
+        ("_POSIX_SYMLINK_MAX", 255),
+        ("_POSIX_SYMLOOP_MAX", 8),
+        ("_POSIX_THREAD_DESTRUCTOR_ITERATIONS", 4),
+        ("_POSIX_THREAD_KEYS_MAX", 128),
+        ("_POSIX_THREAD_THREADS_MAX", 64),
+        ("_POSIX_TIMER_MAX", 32),
+        ("_POSIX_TTY_NAME_MAX", 9),
+        ("_POSIX_TZNAME_MAX", 6),
+        ("_POSIX2_BC_BASE_MAX", 99),
+        ("_POSIX2_BC_DIM_MAX", 2048),
+        ("_POSIX2_BC_SCALE_MAX", 99),
+        ("_POSIX2_BC_STRING_MAX", 1000),
+        ("_POSIX2_CHARCLASS_NAME_MAX", 14),
+        ("_POSIX2_COLL_WEIGHTS_MAX", 2),
+        ("_POSIX2_EXPR_NEST_MAX", 32),
+        ("_POSIX2_LINE_MAX", 2048),
+        ("_POSIX2_RE_DUP_MAX", 255),
+        ("_XOPEN_IOV_MAX", 16),
+        ("_XOPEN_NAME_MAX", 255),
+        ("_XOPEN_PATH_MAX", 1024),
+    ]
 }
 
-fn handle_pathconf(
-    var: &str,
-    pathname: &str,
-    pathconf_mappings: &HashMap<&'static str, libc::c_int>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let value = match get_mapping("_PC_", var, pathconf_mappings) {
-        Some(value) => value,
-        None => {
-            let errstr = format!(
-                "{}: {}",
-                gettext("Error: Unknown path configuration variable"),
-                var
-            );
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                errstr,
-            )));
-        }
-    };
+/// The programming environments of `-v`.
+const SPECIFICATIONS: &[&str] = &[
+    "POSIX_V7_ILP32_OFF32",
+    "POSIX_V7_ILP32_OFFBIG",
+    "POSIX_V7_LP64_OFF64",
+    "POSIX_V7_LPBIG_OFFBIG",
+    "POSIX_V6_ILP32_OFF32",
+    "POSIX_V6_ILP32_OFFBIG",
+    "POSIX_V6_LP64_OFF64",
+    "POSIX_V6_LPBIG_OFFBIG",
+];
 
-    // Get the value using pathconf
-    let c_path = CString::new(pathname)?;
-    let value = unsafe { pathconf(c_path.as_ptr(), value) };
-    if value == -1 {
-        let errstr = format!(
-            "{}: {}",
-            gettext("Error: Unknown path configuration variable"),
-            var
-        );
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            errstr,
-        )));
-    } else {
-        println!("{}", value);
-    }
+/// A variable and where its value comes from.
+enum Variable {
+    Sysconf(c_int),
+    Confstr(c_int),
+    Limit(i128),
+    Pathconf(c_int),
+}
 
-    Ok(())
+/// Finds `name` in `vars`, also with the prefix of the constants of the
+/// C interface, as `_SC_ARG_MAX` for `ARG_MAX`.
+fn lookup(vars: &[(&str, c_int)], prefix: &str, name: &str) -> Option<c_int> {
+    let name = name.strip_prefix(prefix).unwrap_or(name);
+    vars.iter()
+        .find(|(var, _)| *var == name)
+        .map(|&(_, value)| value)
 }
 
-fn load_confstr_mapping() -> HashMap<&'static str, libc::c_int> {
-    #[cfg(target_os = "macos")]
+fn system_variable(name: &str) -> Option<Variable> {
+    if let Some(value) =
+        lookup(SYSCONF_VARS, "_SC_", name).or_else(|| lookup(PLATFORM_SYSCONF_VARS, "_SC_", name))
     {
-        HashMap::from([
-            ("_CS_DARWIN_USER_DIR", libc::_CS_DARWIN_USER_DIR),
-            ("_CS_DARWIN_USER_TEMP_DIR", libc::_CS_DARWIN_USER_TEMP_DIR),
-            ("_CS_DARWIN_USER_CACHE_DIR", libc::_CS_DARWIN_USER_CACHE_DIR),
-            ("_CS_PATH", libc::_CS_PATH),
-        ])
+        return Some(Variable::Sysconf(value));
     }
-
-    // upstream libc crate needs Linux confstr definitions
-    #[cfg(target_os = "linux")]
-    {
-        HashMap::new()
+    if let Some(value) = lookup(CONFSTR_VARS, "_CS_", name) {
+        return Some(Variable::Confstr(value));
     }
+    limits()
+        .into_iter()
+        .find(|(var, _)| *var == name)
+        .map(|(_, value)| Variable::Limit(value))
+}
 
-    //    #[cfg(target_os = "linux")]
-    //    {
-    //        HashMap::from([
-    //            ("_CS_GNU_LIBC_VERSION", libc::_CS_GNU_LIBC_VERSION),
-    //            (
-    //                "_CS_GNU_LIBPTHREAD_VERSION",
-    //                libc::_CS_GNU_LIBPTHREAD_VERSION,
-    //            ),
-    //            ("_CS_PATH", libc::_CS_PATH),
-    //        ])
-    //    }
+fn clear_errno() {
+    errno::set_errno(errno::Errno(0));
 }
 
-fn is_confstr_var(var: &str, mapping: &HashMap<&'static str, libc::c_int>) -> bool {
-    let mut key = var.to_string();
-    if !mapping.contains_key(&key.as_str()) {
-        key = format!("_CS_{}", var);
-        if !mapping.contains_key(&key.as_str()) {
-            return false;
-        }
+/// The value of a sysconf or pathconf variable: `None` if it has no
+/// limit, or the option is unsupported.
+fn number(value: libc::c_long) -> io::Result<Option<libc::c_long>> {
+    if value != -1 {
+        return Ok(Some(value));
+    }
+    match io::Error::last_os_error() {
+        e if e.raw_os_error() == Some(0) => Ok(None),
+        e => Err(e),
     }
-
-    true
 }
 
-fn load_sysconf_mapping() -> HashMap<&'static str, libc::c_int> {
-    HashMap::from([
-        ("_SC_ARG_MAX", libc::_SC_ARG_MAX),
-        ("_SC_CHILD_MAX", libc::_SC_CHILD_MAX),
-        ("_SC_CLK_TCK", libc::_SC_CLK_TCK),
-        ("_SC_NGROUPS_MAX", libc::_SC_NGROUPS_MAX),
-        ("_SC_OPEN_MAX", libc::_SC_OPEN_MAX),
-        ("_SC_STREAM_MAX", libc::_SC_STREAM_MAX),
-        ("_SC_TZNAME_MAX", libc::_SC_TZNAME_MAX),
-        ("_SC_JOB_CONTROL", libc::_SC_JOB_CONTROL),
-        ("_SC_SAVED_IDS", libc::_SC_SAVED_IDS),
-        ("_SC_REALTIME_SIGNALS", libc::_SC_REALTIME_SIGNALS),
-        ("_SC_PRIORITY_SCHEDULING", libc::_SC_PRIORITY_SCHEDULING),
-        ("_SC_TIMERS", libc::_SC_TIMERS),
-        ("_SC_ASYNCHRONOUS_IO", libc::_SC_ASYNCHRONOUS_IO),
-        ("_SC_PRIORITIZED_IO", libc::_SC_PRIORITIZED_IO),
-        ("_SC_SYNCHRONIZED_IO", libc::_SC_SYNCHRONIZED_IO),
-        ("_SC_FSYNC", libc::_SC_FSYNC),
-        ("_SC_MAPPED_FILES", libc::_SC_MAPPED_FILES),
-        ("_SC_MEMLOCK", libc::_SC_MEMLOCK),
-        ("_SC_MEMLOCK_RANGE", libc::_SC_MEMLOCK_RANGE),
-        ("_SC_MEMORY_PROTECTION", libc::_SC_MEMORY_PROTECTION),
-        ("_SC_MESSAGE_PASSING", libc::_SC_MESSAGE_PASSING),
-        ("_SC_SEMAPHORES", libc::_SC_SEMAPHORES),
-        ("_SC_SHARED_MEMORY_OBJECTS", libc::_SC_SHARED_MEMORY_OBJECTS),
-        ("_SC_AIO_LISTIO_MAX", libc::_SC_AIO_LISTIO_MAX),
-        ("_SC_AIO_MAX", libc::_SC_AIO_MAX),
-        ("_SC_AIO_PRIO_DELTA_MAX", libc::_SC_AIO_PRIO_DELTA_MAX),
-        ("_SC_DELAYTIMER_MAX", libc::_SC_DELAYTIMER_MAX),
-        ("_SC_MQ_OPEN_MAX", libc::_SC_MQ_OPEN_MAX),
-        ("_SC_MQ_PRIO_MAX", libc::_SC_MQ_PRIO_MAX),
-        ("_SC_RTSIG_MAX", libc::_SC_RTSIG_MAX),
-        ("_SC_SEM_NSEMS_MAX", libc::_SC_SEM_NSEMS_MAX),
-        ("_SC_SEM_VALUE_MAX", libc::_SC_SEM_VALUE_MAX),
-        ("_SC_SIGQUEUE_MAX", libc::_SC_SIGQUEUE_MAX),
-        ("_SC_TIMER_MAX", libc::_SC_TIMER_MAX),
-        ("_SC_BC_BASE_MAX", libc::_SC_BC_BASE_MAX),
-        ("_SC_BC_DIM_MAX", libc::_SC_BC_DIM_MAX),
-        ("_SC_BC_SCALE_MAX", libc::_SC_BC_SCALE_MAX),
-        ("_SC_BC_STRING_MAX", libc::_SC_BC_STRING_MAX),
-        ("_SC_COLL_WEIGHTS_MAX", libc::_SC_COLL_WEIGHTS_MAX),
-        ("_SC_EXPR_NEST_MAX", libc::_SC_EXPR_NEST_MAX),
-        ("_SC_LINE_MAX", libc::_SC_LINE_MAX),
-        ("_SC_RE_DUP_MAX", libc::_SC_RE_DUP_MAX),
-        ("_SC_2_VERSION", libc::_SC_2_VERSION),
-        ("_SC_2_C_BIND", libc::_SC_2_C_BIND),
-        ("_SC_2_C_DEV", libc::_SC_2_C_DEV),
-        ("_SC_2_CHAR_TERM", libc::_SC_2_CHAR_TERM),
-        ("_SC_2_FORT_DEV", libc::_SC_2_FORT_DEV),
-        ("_SC_2_FORT_RUN", libc::_SC_2_FORT_RUN),
-        ("_SC_2_LOCALEDEF", libc::_SC_2_LOCALEDEF),
-        ("_SC_2_SW_DEV", libc::_SC_2_SW_DEV),
-        ("_SC_2_UPE", libc::_SC_2_UPE),
-        ("_SC_2_PBS", libc::_SC_2_PBS),
-        ("_SC_2_PBS_ACCOUNTING", libc::_SC_2_PBS_ACCOUNTING),
-        ("_SC_2_PBS_CHECKPOINT", libc::_SC_2_PBS_CHECKPOINT),
-        ("_SC_2_PBS_LOCATE", libc::_SC_2_PBS_LOCATE),
-        ("_SC_2_PBS_MESSAGE", libc::_SC_2_PBS_MESSAGE),
-        ("_SC_2_PBS_TRACK", libc::_SC_2_PBS_TRACK),
-        ("_SC_ADVISORY_INFO", libc::_SC_ADVISORY_INFO),
-    ])
+fn confstr(name: c_int) -> io::Result<Option<String>> {
+    clear_errno();
+    let len = unsafe { libc::confstr(name, std::ptr::null_mut(), 0) };
+    if len == 0 {
+        return match io::Error::last_os_error() {
+            e if e.raw_os_error() == Some(0) => Ok(None),
+            e => Err(e),
+        };
+    }
+
+    let mut buf = vec![0u8; len];
+    unsafe { libc::confstr(name, buf.as_mut_ptr() as *mut c_char, buf.len()) };
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    buf.truncate(end);
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
 }
 
-fn load_pathconf_mapping() -> HashMap<&'static str, libc::c_int> {
-    HashMap::from([
-        ("LINK_MAX", libc::_PC_LINK_MAX),
-        ("MAX_CANON", libc::_PC_MAX_CANON),
-        ("MAX_INPUT", libc::_PC_MAX_INPUT),
-        ("NAME_MAX", libc::_PC_NAME_MAX),
-        ("PATH_MAX", libc::_PC_PATH_MAX),
-        ("PIPE_BUF", libc::_PC_PIPE_BUF),
-        ("CHOWN_RESTRICTED", libc::_PC_CHOWN_RESTRICTED),
-        ("NO_TRUNC", libc::_PC_NO_TRUNC),
-        ("VDISABLE", libc::_PC_VDISABLE),
-        ("POSIX_ALLOC_SIZE_MIN", libc::_PC_ALLOC_SIZE_MIN),
-        ("POSIX_REC_INCR_XFER_SIZE", libc::_PC_REC_INCR_XFER_SIZE),
-        ("POSIX_REC_MAX_XFER_SIZE", libc::_PC_REC_MAX_XFER_SIZE),
-        ("POSIX_REC_MIN_XFER_SIZE", libc::_PC_REC_MIN_XFER_SIZE),
-        ("POSIX_REC_XFER_ALIGN", libc::_PC_REC_XFER_ALIGN),
-        ("SYMLINK_MAX", libc::_PC_SYMLINK_MAX),
-        ("FILESIZEBITS", libc::_PC_FILESIZEBITS),
-        ("_POSIX_CHOWN_RESTRICTED", libc::_PC_CHOWN_RESTRICTED),
-        ("_POSIX_NO_TRUNC", libc::_PC_NO_TRUNC),
-        ("_POSIX_VDISABLE", libc::_PC_VDISABLE),
-        ("_POSIX_ASYNC_IO", libc::_PC_ASYNC_IO),
-        ("_POSIX_PRIO_IO", libc::_PC_PRIO_IO),
-    ])
+fn value(var: &Variable, pathname: Option<&str>) -> io::Result<Option<String>> {
+    match *var {
+        Variable::Sysconf(name) => {
+            clear_errno();
+            Ok(number(unsafe { libc::sysconf(name) })?.map(|n| n.to_string()))
+        }
+        Variable::Confstr(name) => confstr(name),
+        Variable::Limit(value) => Ok(Some(value.to_string())),
+        Variable::Pathconf(name) => {
+            let path = CString::new(pathname.unwrap_or("."))?;
+            clear_errno();
+            Ok(number(unsafe { libc::pathconf(path.as_ptr(), name) })?.map(|n| n.to_string()))
+        }
+    }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
-    if let Some(pathname) = args.pathname {
-        let pathconf_mappings = load_pathconf_mapping();
-        handle_pathconf(&args.var, &pathname, &pathconf_mappings)?;
-    } else {
-        let confstr_mappings = load_confstr_mapping();
+    if let Some(spec) = &args.specification {
+        if !SPECIFICATIONS.contains(&spec.as_str()) {
+            tdiag!("unsupported programming environment '{}'", spec);
+            std::process::exit(1);
+        }
+    }
 
-        if is_confstr_var(&args.var, &confstr_mappings) {
-            handle_confstr(&args.var, &confstr_mappings)?;
+    let var = match &args.pathname {
+        Some(_) => lookup(PATHCONF_VARS, "_PC_", &args.var).map(Variable::Pathconf),
+        None => system_variable(&args.var),
+    };
+    let Some(var) = var else {
+        if args.pathname.is_none() && lookup(PATHCONF_VARS, "_PC_", &args.var).is_some() {
+            tdiag!("{}: a path variable needs a pathname", args.var);
         } else {
-            let sysconf_mappings = load_sysconf_mapping();
-            handle_sysconf(&args.var, &sysconf_mappings)?;
+            tdiag!("unrecognized variable '{}'", args.var);
         }
-    }
+        std::process::exit(1);
+    };
 
-    Ok(())
+    match value(&var, args.pathname.as_deref()) {
+        Ok(Some(value)) => println!("{}", value),
+        Ok(None) => println!("undefined"),
+        Err(e) => {
+            match &args.pathname {
+                Some(pathname) => diag!("{}: {}", pathname, error_message(&e)),
+                None => diag!("{}: {}", args.var, error_message(&e)),
+            }
+            std::process::exit(1);
+        }
+    }
 }
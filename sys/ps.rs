@@ -14,18 +14,25 @@ mod psmacos;
 mod pslinux;
 
 use std::collections::HashMap;
+use std::io::{self, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::time::SystemTime;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use libc::{gid_t, pid_t, uid_t};
+use plib::diag::error_message;
+use plib::timefmt::{unix_seconds, Tm};
+use plib::userdb::{group_by_gid, parse_uid, user_by_uid};
+use plib::{diag, tdiag, tr};
 
 #[cfg(target_os = "macos")]
 mod platform {
-    pub use crate::psmacos::*;
+    pub use crate::psmacos::Sysctl as Source;
 }
 
 #[cfg(target_os = "linux")]
 mod platform {
-    pub use crate::pslinux::*;
+    pub use crate::pslinux::Procfs as Source;
 }
 
 /// ps - report process status
@@ -58,125 +65,538 @@ struct Args {
 
     /// Custom output format (-o)
     #[arg(short = 'o', value_parser = clap::builder::NonEmptyStringValueParser::new())]
-    output_format: Option<String>,
-}
-
-// Parse the -o format option into a list of fields
-fn parse_output_format<'a>(
-    format: &'a str,
-    posix_fields: &'a HashMap<&'a str, &'a str>,
-) -> Vec<&'a str> {
-    format
-        .split([' ', ','])
-        .map(|s| {
-            let field = s.split('=').next().unwrap_or("").trim();
-            if posix_fields.contains_key(field) {
-                field
-            } else {
-                panic!("Invalid field specified in -o option: {}", field);
-            }
+    output_format: Vec<String>,
+
+    /// List the processes whose process ID is in the list
+    #[arg(short = 'p')]
+    pids: Vec<String>,
+
+    /// List the processes whose controlling terminal is in the list
+    #[arg(short = 't')]
+    terminals: Vec<String>,
+
+    /// List the processes whose effective user is in the list
+    #[arg(short = 'u')]
+    users: Vec<String>,
+
+    /// List the processes whose real user is in the list
+    #[arg(short = 'U')]
+    real_users: Vec<String>,
+}
+
+/// What ps knows about a process, whatever the platform.
+pub struct ProcessInfo {
+    pub pid: pid_t,
+    pub ppid: pid_t,
+    pub pgid: pid_t,
+    pub sid: pid_t,
+    pub uid: uid_t,
+    pub ruid: uid_t,
+    pub gid: gid_t,
+    pub rgid: gid_t,
+    /// Device number of the controlling terminal
+    pub tty: Option<u64>,
+    pub state: char,
+    pub flags: u64,
+    pub priority: i64,
+    pub nice: i64,
+    /// Size of the virtual memory, in kilobytes
+    pub vsz: u64,
+    /// Kernel function the process sleeps in, if known
+    pub wchan: Option<String>,
+    /// User and system CPU time, in seconds
+    pub cpu_time: u64,
+    /// Start time, in seconds since the Epoch
+    pub start_time: i64,
+    pub comm: String,
+    /// The command line, empty for kernel threads and zombies
+    pub args: Vec<String>,
+}
+
+/// A source of process information: /proc on Linux, sysctl(3) and
+/// libproc on macOS.
+pub trait ProcessSource {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>>;
+}
+
+/// The values `-o` can write.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Ruser,
+    User,
+    Rgroup,
+    Group,
+    Pid,
+    Ppid,
+    Pgid,
+    Pcpu,
+    Vsz,
+    Nice,
+    Etime,
+    Time,
+    Tty,
+    Comm,
+    Args,
+    Flags,
+    State,
+    Uid,
+    Cpu,
+    Priority,
+    Addr,
+    Size,
+    Wchan,
+    Stime,
+}
+
+/// Names of the fields for `-o`, with their default headers: the POSIX
+/// ones, then those used by `-f` and `-l`.
+const FIELDS: [(&str, &str, Field); 24] = [
+    ("ruser", "RUSER", Field::Ruser),
+    ("user", "USER", Field::User),
+    ("rgroup", "RGROUP", Field::Rgroup),
+    ("group", "GROUP", Field::Group),
+    ("pid", "PID", Field::Pid),
+    ("ppid", "PPID", Field::Ppid),
+    ("pgid", "PGID", Field::Pgid),
+    ("pcpu", "%CPU", Field::Pcpu),
+    ("vsz", "VSZ", Field::Vsz),
+    ("nice", "NI", Field::Nice),
+    ("etime", "ELAPSED", Field::Etime),
+    ("time", "TIME", Field::Time),
+    ("tty", "TT", Field::Tty),
+    ("comm", "COMMAND", Field::Comm),
+    ("args", "COMMAND", Field::Args),
+    ("f", "F", Field::Flags),
+    ("s", "S", Field::State),
+    ("uid", "UID", Field::Uid),
+    ("c", "C", Field::Cpu),
+    ("pri", "PRI", Field::Priority),
+    ("addr", "ADDR", Field::Addr),
+    ("sz", "SZ", Field::Size),
+    ("wchan", "WCHAN", Field::Wchan),
+    ("stime", "STIME", Field::Stime),
+];
+
+impl Field {
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Field::Pid
+                | Field::Ppid
+                | Field::Pgid
+                | Field::Pcpu
+                | Field::Vsz
+                | Field::Nice
+                | Field::Etime
+                | Field::Time
+                | Field::Flags
+                | Field::Uid
+                | Field::Cpu
+                | Field::Priority
+                | Field::Size
+        )
+    }
+}
+
+/// A column of the output.
+struct Column {
+    field: Field,
+    header: String,
+}
+
+fn columns(fields: &[(Field, &str)]) -> Vec<Column> {
+    fields
+        .iter()
+        .map(|&(field, header)| Column {
+            field,
+            header: header.to_string(),
         })
         .collect()
 }
 
-// Lookup table for POSIX-compliant output fields
-fn posix_field_map() -> HashMap<&'static str, &'static str> {
-    HashMap::from([
-        ("ruser", "RUSER"),
-        ("user", "USER"),
-        ("rgroup", "RGROUP"),
-        ("group", "GROUP"),
-        ("pid", "PID"),
-        ("ppid", "PPID"),
-        ("pgid", "PGID"),
-        ("pcpu", "%CPU"),
-        ("vsz", "VSZ"),
-        ("nice", "NI"),
-        ("etime", "ELAPSED"),
-        ("time", "TIME"),
-        ("tty", "TTY"),
-        ("comm", "COMMAND"),
-        ("args", "COMMAND"),
-    ])
+/// Parses the `-o` option-arguments: field names separated by commas or
+/// blanks, each optionally followed by `=` and a header, which takes up
+/// the rest of the option-argument.
+fn parse_output_format(formats: &[String]) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::new();
+    for format in formats {
+        let mut rest = format.as_str();
+        loop {
+            rest = rest.trim_start_matches([',', ' ', '\t']);
+            if rest.is_empty() {
+                break;
+            }
+            let end = rest.find([',', ' ', '\t', '=']).unwrap_or(rest.len());
+            let name = &rest[..end];
+            let Some(&(_, header, field)) = FIELDS.iter().find(|(n, _, _)| *n == name) else {
+                return Err(tr!("unknown output field '{}'", name));
+            };
+            if let Some(header) = rest[end..].strip_prefix('=') {
+                columns.push(Column {
+                    field,
+                    header: header.to_string(),
+                });
+                break;
+            }
+            columns.push(Column {
+                field,
+                header: header.to_string(),
+            });
+            rest = &rest[end..];
+        }
+    }
+    if columns.is_empty() {
+        return Err(tr!("no output fields specified"));
+    }
+    Ok(columns)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+/// Splits the lists of `-p`, `-t`, `-u` and `-U`, whose items are
+/// separated by commas or blanks.
+fn list_items(lists: &[String]) -> impl Iterator<Item = &str> {
+    lists
+        .iter()
+        .flat_map(|list| list.split([',', ' ', '\t']))
+        .filter(|item| !item.is_empty())
+}
 
-    let mut args = Args::parse();
+/// Which processes to write.
+struct Selection {
+    all: bool,
+    terminal_processes: bool,
+    exclude_session_leaders: bool,
+    pids: Vec<pid_t>,
+    terminals: Vec<String>,
+    uids: Vec<uid_t>,
+    ruids: Vec<uid_t>,
+}
 
-    if args.all2 {
-        args.all = true;
+impl Selection {
+    fn from_args(args: &Args) -> Result<Self, String> {
+        let parse_users = |lists: &[String]| {
+            list_items(lists)
+                .map(|user| parse_uid(user).ok_or_else(|| tr!("invalid user name '{}'", user)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            all: args.all || args.all2,
+            terminal_processes: args.terminal_processes,
+            exclude_session_leaders: args.exclude_session_leaders,
+            pids: list_items(&args.pids)
+                .map(|pid| pid.parse().map_err(|_| tr!("invalid process ID '{}'", pid)))
+                .collect::<Result<_, _>>()?,
+            terminals: list_items(&args.terminals)
+                .map(|tty| tty.trim_start_matches("/dev/").to_string())
+                .collect(),
+            uids: parse_users(&args.users)?,
+            ruids: parse_users(&args.real_users)?,
+        })
     }
 
-    let processes = match platform::list_processes() {
-        Ok(processes) => processes,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return Ok(());
+    fn is_empty(&self) -> bool {
+        !(self.all
+            || self.terminal_processes
+            || self.exclude_session_leaders
+            || !self.pids.is_empty()
+            || !self.terminals.is_empty()
+            || !self.uids.is_empty()
+            || !self.ruids.is_empty())
+    }
+
+    /// Whether `proc` is selected by any of the options.
+    fn selects(&self, proc: &ProcessInfo, tty_names: &TtyNames) -> bool {
+        let is_session_leader = proc.pid == proc.sid;
+        self.all
+            || (self.terminal_processes && proc.tty.is_some() && !is_session_leader)
+            || (self.exclude_session_leaders && !is_session_leader)
+            || self.pids.contains(&proc.pid)
+            || (!self.terminals.is_empty()
+                && self
+                    .terminals
+                    .iter()
+                    .any(|tty| *tty == tty_names.name(proc.tty)))
+            || self.uids.contains(&proc.uid)
+            || self.ruids.contains(&proc.ruid)
+    }
+}
+
+/// Names of the terminal devices, by device number.
+struct TtyNames(HashMap<u64, String>);
+
+impl TtyNames {
+    fn new() -> Self {
+        let mut names = HashMap::new();
+        for dir in ["/dev/pts", "/dev"] {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(md) = entry.metadata() else {
+                    continue;
+                };
+                if !md.file_type().is_char_device() {
+                    continue;
+                }
+                let path = entry.path();
+                let name = path.strip_prefix("/dev").unwrap_or(&path);
+                names
+                    .entry(md.rdev())
+                    .or_insert_with(|| name.display().to_string());
+            }
         }
-    };
+        Self(names)
+    }
+
+    fn name(&self, tty: Option<u64>) -> String {
+        match tty {
+            Some(dev) => self
+                .0
+                .get(&dev)
+                .cloned()
+                .unwrap_or_else(|| String::from("?")),
+            None => String::from("?"),
+        }
+    }
+}
+
+/// Formats a duration as `[dd-]hh:mm:ss`.
+fn format_time(secs: u64) -> String {
+    let (days, hours) = (secs / 86400, secs / 3600 % 24);
+    let (minutes, secs) = (secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+    }
+}
 
-    // Filter processes based on arguments
-    let filtered_processes = if args.terminal_processes && args.exclude_session_leaders {
-        processes
-            .into_iter()
-            .filter(|p| p.tty.is_some() && p.pid != p.sid) // -a and -d logic
-            .collect::<Vec<_>>()
-    } else if args.terminal_processes {
-        processes
-            .into_iter()
-            .filter(|p| p.tty.is_some()) // -a logic
-            .collect::<Vec<_>>()
-    } else if args.exclude_session_leaders {
-        processes
-            .into_iter()
-            .filter(|p| p.pid != p.sid) // -d logic
-            .collect::<Vec<_>>()
+/// Formats an elapsed time as `[[dd-]hh:]mm:ss`.
+fn format_etime(secs: u64) -> String {
+    if secs < 3600 {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
     } else {
-        processes
+        format_time(secs)
+    }
+}
+
+fn user_name(uid: uid_t) -> String {
+    user_by_uid(uid).map_or_else(|| uid.to_string(), |user| user.name.clone())
+}
+
+fn group_name(gid: gid_t) -> String {
+    group_by_gid(gid).map_or_else(|| gid.to_string(), |group| group.name.clone())
+}
+
+/// What the values of the fields of a process depend on besides the
+/// process itself.
+struct Context {
+    now: i64,
+    page_size: u64,
+    tty_names: TtyNames,
+}
+
+impl Context {
+    fn value(&self, proc: &ProcessInfo, field: Field) -> String {
+        let elapsed = (self.now - proc.start_time).max(0) as u64;
+        let pcpu = if elapsed > 0 {
+            proc.cpu_time as f64 * 100.0 / elapsed as f64
+        } else {
+            0.0
+        };
+        match field {
+            Field::Ruser => user_name(proc.ruid),
+            Field::User => user_name(proc.uid),
+            Field::Rgroup => group_name(proc.rgid),
+            Field::Group => group_name(proc.gid),
+            Field::Pid => proc.pid.to_string(),
+            Field::Ppid => proc.ppid.to_string(),
+            Field::Pgid => proc.pgid.to_string(),
+            Field::Pcpu => format!("{:.1}", pcpu),
+            Field::Vsz => proc.vsz.to_string(),
+            Field::Nice => proc.nice.to_string(),
+            Field::Etime => format_etime(elapsed),
+            Field::Time => format_time(proc.cpu_time),
+            Field::Tty => self.tty_names.name(proc.tty),
+            Field::Comm => proc.comm.clone(),
+            Field::Args if proc.args.is_empty() => format!("[{}]", proc.comm),
+            Field::Args => proc.args.join(" "),
+            Field::Flags => format!("{:o}", proc.flags),
+            Field::State => proc.state.to_string(),
+            Field::Uid => proc.uid.to_string(),
+            Field::Cpu => (pcpu as u64).to_string(),
+            Field::Priority => proc.priority.to_string(),
+            Field::Addr => String::from("-"),
+            Field::Size => (proc.vsz * 1024 / self.page_size).to_string(),
+            Field::Wchan => proc.wchan.clone().unwrap_or_else(|| String::from("-")),
+            Field::Stime => {
+                let today = Tm::local(self.now).map(|tm| tm.format("%Y%m%d"));
+                match Tm::local(proc.start_time) {
+                    Some(tm) if Some(tm.format("%Y%m%d")) == today => tm.format("%H:%M"),
+                    Some(tm) => tm.format("%b%d"),
+                    None => String::from("-"),
+                }
+            }
+        }
+    }
+}
+
+/// Writes the table of `rows`: columns are as wide as their header and
+/// their widest value, numbers aligned to the right and text to the left.
+fn write_table(out: &mut impl Write, columns: &[Column], rows: &[Vec<String>]) -> io::Result<()> {
+    let width = |s: &str| s.chars().count();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| width(&row[i]))
+                .fold(width(&column.header), usize::max)
+        })
+        .collect();
+
+    let write_row = |out: &mut dyn Write, row: &[&str]| -> io::Result<()> {
+        let mut line = String::new();
+        for (i, (value, column)) in row.iter().zip(columns).enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            let pad = " ".repeat(widths[i] - width(value));
+            if column.field.is_numeric() {
+                line.push_str(&pad);
+                line.push_str(value);
+            } else {
+                line.push_str(value);
+                if i + 1 < columns.len() {
+                    line.push_str(&pad);
+                }
+            }
+        }
+        writeln!(out, "{}", line)
     };
 
-    // Define a lookup table for POSIX-compliant fields
-    let posix_fields = posix_field_map();
+    // No header line if all of them are empty
+    if columns.iter().any(|column| !column.header.is_empty()) {
+        let headers: Vec<&str> = columns.iter().map(|c| c.header.as_str()).collect();
+        write_row(out, &headers)?;
+    }
+    for row in rows {
+        let row: Vec<&str> = row.iter().map(String::as_str).collect();
+        write_row(out, &row)?;
+    }
+    Ok(())
+}
 
-    // Build output based on -o, -f, -l, or default
-    let output_fields = if let Some(ref format) = args.output_format {
-        parse_output_format(format, &posix_fields)
-    } else if args.full_format {
-        vec!["uid", "pid", "ppid", "C", "time", "comm"]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    plib::i18n::init();
+
+    let args = Args::parse();
+
+    let columns = if !args.output_format.is_empty() {
+        match parse_output_format(&args.output_format) {
+            Ok(columns) => columns,
+            Err(e) => {
+                diag!("{}", e);
+                std::process::exit(1);
+            }
+        }
     } else if args.long_format {
-        vec!["nice", "vsz", "WCHAN", "tty", "comm"]
+        columns(&[
+            (Field::Flags, "F"),
+            (Field::State, "S"),
+            (Field::User, "UID"),
+            (Field::Pid, "PID"),
+            (Field::Ppid, "PPID"),
+            (Field::Cpu, "C"),
+            (Field::Priority, "PRI"),
+            (Field::Nice, "NI"),
+            (Field::Addr, "ADDR"),
+            (Field::Size, "SZ"),
+            (Field::Wchan, "WCHAN"),
+            (Field::Tty, "TTY"),
+            (Field::Time, "TIME"),
+            (
+                if args.full_format {
+                    Field::Args
+                } else {
+                    Field::Comm
+                },
+                "CMD",
+            ),
+        ])
+    } else if args.full_format {
+        columns(&[
+            (Field::User, "UID"),
+            (Field::Pid, "PID"),
+            (Field::Ppid, "PPID"),
+            (Field::Cpu, "C"),
+            (Field::Stime, "STIME"),
+            (Field::Tty, "TTY"),
+            (Field::Time, "TIME"),
+            (Field::Args, "CMD"),
+        ])
     } else {
-        vec!["pid", "ppid", "tty", "time", "comm"] // Default format
+        columns(&[
+            (Field::Pid, "PID"),
+            (Field::Tty, "TTY"),
+            (Field::Time, "TIME"),
+            (Field::Comm, "CMD"),
+        ])
     };
 
-    // Print the header
-    for field in &output_fields {
-        let header = posix_fields.get(*field).unwrap_or(field);
-        print!("{:<10} ", header);
-    }
-    println!();
-
-    // Print each process
-    for proc in filtered_processes {
-        for field in &output_fields {
-            match *field {
-                "pid" => print!("{:<10} ", proc.pid),
-                "ppid" => print!("{:<10} ", proc.ppid),
-                "group" => print!("{:<10} ", proc.gid),
-                "tty" => print!("{:<10} ", proc.tty.as_deref().unwrap_or("-")),
-                // "time" => print!("{:<10} ", proc.time),
-                "comm" => print!("{:<10} ", proc.path),
-                "user" => print!("{:<10} ", proc.uid), // Example for user field, would need to resolve UID -> username
-                // Add cases for more fields as needed...
-                _ => print!("{:<10} ", "-"),
-            }
+    let selection = match Selection::from_args(&args) {
+        Ok(selection) => selection,
+        Err(e) => {
+            diag!("{}", e);
+            std::process::exit(1);
         }
-        println!();
-    }
+    };
 
-    Ok(())
+    let mut processes = match platform::Source.processes() {
+        Ok(processes) => processes,
+        Err(e) => {
+            tdiag!("cannot read the process table: {}", error_message(&e));
+            std::process::exit(1);
+        }
+    };
+    processes.sort_by_key(|proc| proc.pid);
+
+    let context = Context {
+        now: unix_seconds(SystemTime::now()),
+        page_size: (unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).max(1) as u64,
+        tty_names: TtyNames::new(),
+    };
+
+    // Without options, the processes of the same effective user and the
+    // same controlling terminal as ps itself
+    let me = std::process::id() as pid_t;
+    let my_tty = processes
+        .iter()
+        .find(|proc| proc.pid == me)
+        .and_then(|proc| proc.tty);
+    let my_uid = unsafe { libc::geteuid() };
+
+    let rows: Vec<Vec<String>> = processes
+        .iter()
+        .filter(|proc| {
+            if selection.is_empty() {
+                proc.uid == my_uid && proc.tty == my_tty
+            } else {
+                selection.selects(proc, &context.tty_names)
+            }
+        })
+        .map(|proc| {
+            columns
+                .iter()
+                .map(|column| context.value(proc, column.field))
+                .collect()
+        })
+        .collect();
+
+    let mut out = plib::io::stdout();
+    let mut exit_code = 0;
+    if let Err(e) = write_table(&mut out, &columns, &rows).and_then(|()| out.finish()) {
+        diag!("{}", error_message(&e));
+        exit_code = 1;
+    }
+    std::process::exit(exit_code)
 }
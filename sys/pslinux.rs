@@ -9,93 +9,127 @@
 
 use std::fs;
 use std::fs::read_to_string;
-use std::io::Error;
-use std::path::{Path, PathBuf};
+use std::io::{self, Error};
+use std::path::Path;
 
-pub struct ProcessInfo {
-    pub pid: i32,
-    pub ppid: i32,
-    pub uid: u32,
-    pub gid: u32,
-    pub path: String,
-    pub tty: Option<String>, // Add TTY field for -a option
-    pub sid: i32,            // Add session ID (SID) for -d option
-}
+use crate::{ProcessInfo, ProcessSource};
+
+/// Reads the process table from /proc.
+pub struct Procfs;
+
+impl ProcessSource for Procfs {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>> {
+        let boot_time = boot_time()?;
+        let ticks = (unsafe { libc::sysconf(libc::_SC_CLK_TCK) }).max(1) as u64;
 
-pub fn list_processes() -> Result<Vec<ProcessInfo>, Error> {
-    let mut processes = Vec::new();
-    for entry in fs::read_dir("/proc")? {
-        let entry = entry?;
-        let path = entry.path();
-        if let Ok(pid) = entry.file_name().to_str().unwrap_or("").parse::<i32>() {
-            if pid > 0 {
-                if let Some(info) = get_process_info(pid, &path) {
-                    processes.push(info);
+        let mut processes = Vec::new();
+        for entry in fs::read_dir("/proc")? {
+            let entry = entry?;
+            let path = entry.path();
+            if let Ok(pid) = entry.file_name().to_str().unwrap_or("").parse::<i32>() {
+                if pid > 0 {
+                    // The process may have exited since the directory was read
+                    if let Some(info) = get_process_info(pid, &path, boot_time, ticks) {
+                        processes.push(info);
+                    }
                 }
             }
         }
+        Ok(processes)
     }
-    Ok(processes)
 }
 
-fn get_process_info(pid: i32, proc_path: &Path) -> Option<ProcessInfo> {
-    let status_path = proc_path.join("status");
-    let cmdline_path = proc_path.join("cmdline");
-    let stat_path = proc_path.join("stat");
-    let exe_path = proc_path.join("exe");
+/// Time of the last boot, in seconds since the Epoch, from /proc/stat.
+fn boot_time() -> io::Result<i64> {
+    read_to_string("/proc/stat")?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|btime| btime.trim().parse().ok())
+        .ok_or_else(|| Error::new(io::ErrorKind::InvalidData, "/proc/stat: no btime"))
+}
 
-    let status = read_to_string(status_path).ok()?;
-    let cmdline = read_to_string(cmdline_path).unwrap_or_default();
-    let exe = fs::read_link(exe_path).unwrap_or_else(|_| PathBuf::from("[Permission denied]"));
-    let comm = String::new();
+/// The real and effective IDs of a "Uid:" or "Gid:" line of
+/// /proc/<pid>/status.
+fn real_and_effective(line: &str) -> (u32, u32) {
+    let mut ids = line
+        .split_whitespace()
+        .skip(1)
+        .map(|id| id.parse().unwrap_or(0));
+    let real = ids.next().unwrap_or(0);
+    (real, ids.next().unwrap_or(real))
+}
 
-    // Read from /proc/<pid>/stat to get the session ID and TTY number
-    let stat = read_to_string(stat_path).ok()?;
-    let stat_fields: Vec<&str> = stat.split_whitespace().collect();
-    let sid = stat_fields[5].parse().unwrap_or(0); // Session ID (SID)
-    let tty_nr = stat_fields[6].parse::<i32>().unwrap_or(0);
+fn get_process_info(pid: i32, proc_path: &Path, boot_time: i64, ticks: u64) -> Option<ProcessInfo> {
+    let status = read_to_string(proc_path.join("status")).ok()?;
+    let stat = read_to_string(proc_path.join("stat")).ok()?;
+    let cmdline = fs::read(proc_path.join("cmdline")).unwrap_or_default();
+    let wchan = read_to_string(proc_path.join("wchan"))
+        .ok()
+        .filter(|wchan| !wchan.is_empty() && wchan != "0");
 
-    let tty = if tty_nr > 0 {
-        Some(format!("tty{}", tty_nr)) // Simplified TTY representation
-    } else {
-        None
+    // The command name is in parentheses, and may contain blanks and
+    // parentheses itself
+    let comm_start = stat.find('(')?;
+    let comm_end = stat.rfind(')')?;
+    let comm = stat[comm_start + 1..comm_end].to_string();
+
+    // stat_fields[0] is field 3 of proc(5), the state
+    let stat_fields: Vec<&str> = stat[comm_end + 1..].split_whitespace().collect();
+    let field = |n: usize| -> i64 {
+        stat_fields
+            .get(n - 3)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0)
     };
 
-    let mut ppid = 0;
-    let mut uid = 0;
-    let mut gid = 0;
+    let tty_nr = field(7) as u32;
+    let tty = (tty_nr != 0).then(|| {
+        let major = (tty_nr >> 8) & 0xfff;
+        let minor = (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00);
+        libc::makedev(major, minor)
+    });
 
+    let mut uids = (0, 0);
+    let mut gids = (0, 0);
     for line in status.lines() {
-        if line.starts_with("PPid:") {
-            if let Some(val) = line.split_whitespace().nth(1) {
-                ppid = val.parse().unwrap_or(0);
-            }
-        } else if line.starts_with("Uid:") {
-            if let Some(val) = line.split_whitespace().nth(1) {
-                uid = val.parse().unwrap_or(0);
-            }
+        if line.starts_with("Uid:") {
+            uids = real_and_effective(line);
         } else if line.starts_with("Gid:") {
-            if let Some(val) = line.split_whitespace().nth(1) {
-                gid = val.parse().unwrap_or(0);
-            }
+            gids = real_and_effective(line);
         }
     }
 
-    let path = if !cmdline.is_empty() {
-        cmdline.replace('\0', " ")
-    } else if !comm.is_empty() {
-        format!("[{}]", comm)
-    } else {
-        exe.to_string_lossy().to_string()
+    let args = cmdline
+        .split(|&b| b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect::<Vec<_>>();
+    // The arguments are NUL-terminated
+    let args = match args.split_last() {
+        Some((last, args)) if last.is_empty() => args.to_vec(),
+        _ => args,
     };
 
     Some(ProcessInfo {
         pid,
-        ppid,
-        uid,
-        gid,
-        path,
+        ppid: field(4) as i32,
+        pgid: field(5) as i32,
+        sid: field(6) as i32,
+        uid: uids.1,
+        ruid: uids.0,
+        gid: gids.1,
+        rgid: gids.0,
         tty,
-        sid, // Return the session ID (SID)
+        state: stat_fields.first()?.chars().next()?,
+        // Of the kernel flags, the ones ps traditionally shows: 1 for
+        // forked but not exec'd, 4 for used super-user privileges
+        flags: (field(9) as u64 >> 6) & 0o5,
+        priority: field(18),
+        nice: field(19),
+        vsz: field(23) as u64 / 1024,
+        wchan,
+        cpu_time: (field(14) + field(15)) as u64 / ticks,
+        start_time: boot_time + (field(22) as u64 / ticks) as i64,
+        comm,
+        args,
     })
 }
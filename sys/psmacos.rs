@@ -7,118 +7,182 @@
 // SPDX-License-Identifier: MIT
 //
 
-use libc::{c_int, c_void, getsid, pid_t, proc_listallpids, proc_pidinfo, proc_pidpath};
+use libc::{c_int, c_void, getsid, pid_t, proc_listallpids, proc_pidinfo};
 use std::ffi::CStr;
-use std::fs;
-use std::io::Error;
-use std::os::unix::fs::MetadataExt;
-
-const PROC_PIDPATHINFO_MAXSIZE: usize = 4096;
-
-pub struct ProcessInfo {
-    pub pid: pid_t,
-    pub ppid: pid_t,
-    pub uid: u32,
-    pub gid: u32,
-    pub path: String,
-    pub tty: Option<String>, // Add TTY field for -a option
-    pub sid: pid_t,          // Session ID (SID) for -d option
-}
+use std::io::{self, Error};
+use std::mem;
+use std::ptr;
+
+use crate::{ProcessInfo, ProcessSource};
+
+/// Device number of processes without a controlling terminal.
+const NODEV: u32 = u32::MAX;
+
+/// Reads the process table with libproc, and the command lines with
+/// sysctl(3).
+pub struct Sysctl;
+
+impl ProcessSource for Sysctl {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>> {
+        let mut pids: Vec<pid_t> = vec![0; 4096];
+        let num_pids = unsafe {
+            proc_listallpids(
+                pids.as_mut_ptr() as *mut c_void,
+                (pids.len() * mem::size_of::<pid_t>()) as c_int,
+            )
+        };
+
+        if num_pids < 0 {
+            return Err(Error::last_os_error());
+        }
 
-pub fn list_processes() -> Result<Vec<ProcessInfo>, Error> {
-    let mut pids: Vec<pid_t> = vec![0; 1024];
-    let num_pids = unsafe {
-        proc_listallpids(
-            pids.as_mut_ptr() as *mut c_void,
-            (pids.len() * std::mem::size_of::<pid_t>()) as c_int,
-        )
-    };
+        let timebase = timebase();
+        let mut processes = Vec::new();
 
-    if num_pids < 0 {
-        return Err(Error::last_os_error());
-    }
+        for &pid in &pids[0..num_pids as usize] {
+            if pid == 0 {
+                continue;
+            }
+            if let Some(info) = get_process_info(pid, timebase) {
+                processes.push(info);
+            }
+        }
 
-    let mut processes = Vec::new();
+        Ok(processes)
+    }
+}
 
-    for &pid in &pids[0..num_pids as usize] {
-        if pid == 0 {
-            continue;
-        }
-        if let Some(info) = get_process_info(pid) {
-            processes.push(info);
-        }
+/// Nanoseconds per unit of the CPU times of proc_taskinfo, which are in
+/// Mach absolute time units.
+fn timebase() -> f64 {
+    let mut info = libc::mach_timebase_info { numer: 1, denom: 1 };
+    #[allow(deprecated)]
+    let ret = unsafe { libc::mach_timebase_info(&mut info) };
+    if ret != 0 || info.denom == 0 {
+        return 1.0;
     }
+    f64::from(info.numer) / f64::from(info.denom)
+}
 
-    Ok(processes)
+fn pidinfo<T>(pid: pid_t, flavor: c_int) -> Option<T> {
+    let mut info = mem::MaybeUninit::<T>::uninit();
+    let size = mem::size_of::<T>() as c_int;
+    let res = unsafe { proc_pidinfo(pid, flavor, 0, info.as_mut_ptr() as *mut c_void, size) };
+    if res != size {
+        return None;
+    }
+    Some(unsafe { info.assume_init() })
 }
 
-fn get_process_info(pid: pid_t) -> Option<ProcessInfo> {
-    let mut proc_info = std::mem::MaybeUninit::<libc::proc_bsdinfo>::uninit();
-    let proc_info_size = std::mem::size_of::<libc::proc_bsdinfo>();
-    let res = unsafe {
-        proc_pidinfo(
-            pid,
-            libc::PROC_PIDTBSDINFO,
+/// The arguments of a process, from the KERN_PROCARGS2 sysctl: the
+/// argument count, the path of the executable, then the arguments, each
+/// NUL-terminated.
+fn process_args(pid: pid_t) -> Vec<String> {
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    let mut argmax: c_int = 0;
+    let mut size = mem::size_of::<c_int>();
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            2,
+            &mut argmax as *mut c_int as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
             0,
-            proc_info.as_mut_ptr() as *mut c_void,
-            proc_info_size as c_int,
         )
     };
-
-    if res <= 0 {
-        return None;
+    if ret != 0 || argmax <= 0 {
+        return Vec::new();
     }
 
-    let proc_info = unsafe { proc_info.assume_init() };
-
-    let mut path_buf = vec![0u8; PROC_PIDPATHINFO_MAXSIZE];
-    let path_len = unsafe {
-        proc_pidpath(
-            pid,
-            path_buf.as_mut_ptr() as *mut c_void,
-            PROC_PIDPATHINFO_MAXSIZE as u32,
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    let mut buf = vec![0u8; argmax as usize];
+    let mut size = buf.len();
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            3,
+            buf.as_mut_ptr() as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
         )
     };
+    if ret != 0 || size < mem::size_of::<c_int>() {
+        return Vec::new();
+    }
+    buf.truncate(size);
+
+    let (argc, rest) = buf.split_at(mem::size_of::<c_int>());
+    let argc = c_int::from_ne_bytes(argc.try_into().unwrap()).max(0) as usize;
+
+    // Skip the path of the executable, and the NULs padding it
+    let path_end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    let rest = &rest[path_end..];
+    let args_start = rest.iter().position(|&b| b != 0).unwrap_or(rest.len());
+
+    rest[args_start..]
+        .split(|&b| b == 0)
+        .take(argc)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
 
-    let path = if path_len > 0 {
-        unsafe { CStr::from_ptr(path_buf.as_ptr() as *const i8) }
-            .to_string_lossy()
-            .into_owned()
-    } else {
-        String::new()
+fn get_process_info(pid: pid_t, timebase: f64) -> Option<ProcessInfo> {
+    // The task information is only available for processes of the same
+    // user, unless running as root
+    let (bsd, task) = match pidinfo::<libc::proc_taskallinfo>(pid, libc::PROC_PIDTASKALLINFO) {
+        Some(all) => (all.pbsd, Some(all.ptinfo)),
+        None => (
+            pidinfo::<libc::proc_bsdinfo>(pid, libc::PROC_PIDTBSDINFO)?,
+            None,
+        ),
     };
 
-    // Retrieve the terminal device ID (TTY)
-    let tty_dev = proc_info.e_tdev;
+    let comm = unsafe { CStr::from_ptr(bsd.pbi_comm.as_ptr()) }
+        .to_string_lossy()
+        .into_owned();
+
+    let state = match bsd.pbi_status {
+        libc::SIDL => 'I',
+        libc::SRUN => 'R',
+        libc::SSLEEP => 'S',
+        libc::SSTOP => 'T',
+        libc::SZOMB => 'Z',
+        _ => '?',
+    };
 
-    // Map the terminal device ID to the TTY name
-    let tty = get_tty_name(tty_dev);
+    let (vsz, cpu_time, priority) = match task {
+        Some(task) => {
+            let cpu_ns = (task.pti_total_user + task.pti_total_system) as f64 * timebase;
+            (
+                task.pti_virtual_size / 1024,
+                (cpu_ns / 1e9) as u64,
+                i64::from(task.pti_priority),
+            )
+        }
+        None => (0, 0, 0),
+    };
 
     Some(ProcessInfo {
-        pid: proc_info.pbi_pid as pid_t,
-        ppid: proc_info.pbi_ppid as pid_t,
-        uid: proc_info.pbi_uid,
-        gid: proc_info.pbi_gid,
-        path,
-        tty,                         // Add the terminal (TTY) name
-        sid: unsafe { getsid(pid) }, // Add session ID (SID)
+        pid: bsd.pbi_pid as pid_t,
+        ppid: bsd.pbi_ppid as pid_t,
+        pgid: bsd.pbi_pgid as pid_t,
+        sid: unsafe { getsid(pid) },
+        uid: bsd.pbi_uid,
+        ruid: bsd.pbi_ruid,
+        gid: bsd.pbi_gid,
+        rgid: bsd.pbi_rgid,
+        tty: (bsd.e_tdev != NODEV).then_some(u64::from(bsd.e_tdev)),
+        state,
+        flags: u64::from(bsd.pbi_flags),
+        priority,
+        nice: i64::from(bsd.pbi_nice),
+        vsz,
+        wchan: None,
+        cpu_time,
+        start_time: bsd.pbi_start_tvsec as i64,
+        comm,
+        args: process_args(pid),
     })
 }
-
-// Function to map a device ID to a TTY name
-fn get_tty_name(tty_dev: u32) -> Option<String> {
-    let dev_dir = "/dev/";
-    if let Ok(entries) = fs::read_dir(dev_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Ok(metadata) = fs::metadata(&path) {
-                    if metadata.rdev() == tty_dev as u64 {
-                        return Some(path.file_name()?.to_string_lossy().into_owned());
-                    }
-                }
-            }
-        }
-    }
-    None
-}
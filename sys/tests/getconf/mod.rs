@@ -42,3 +42,46 @@ fn sysconf_arg_max() {
 fn pathconf_link_max() {
     run_getconf_test(vec!["LINK_MAX", "/"], 0, check_output_is_positive_integer);
 }
+
+#[test]
+fn limits_char_bit() {
+    run_getconf_test(vec!["CHAR_BIT"], 0, |_, output| {
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "8\n");
+    });
+}
+
+#[test]
+fn limits_posix_minimum() {
+    run_getconf_test(vec!["_POSIX_ARG_MAX"], 0, |_, output| {
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "4096\n");
+    });
+}
+
+#[test]
+fn confstr_path() {
+    run_getconf_test(vec!["PATH"], 0, |_, output| {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.ends_with('\n') && stdout.trim().split(':').all(|dir| !dir.is_empty()));
+    });
+}
+
+#[test]
+fn sysconf_prefixed_name() {
+    run_getconf_test(vec!["_SC_ARG_MAX"], 0, check_output_is_positive_integer);
+}
+
+#[test]
+fn unknown_variable() {
+    run_getconf_test(vec!["NO_SUCH_VARIABLE"], 1, |_, output| {
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+    });
+}
+
+#[test]
+fn pathconf_without_pathname() {
+    run_getconf_test(vec!["NAME_MAX"], 1, |_, output| {
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+    });
+}
@@ -0,0 +1,98 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, run_test_with_checker, TestPlan};
+
+fn ps_test(args: &[&str], expected_out: &str, expected_err: &str, expected_exit_code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("ps"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code,
+    });
+}
+
+/// Runs ps and returns its output.
+fn ps_output(args: &[&str]) -> String {
+    let mut stdout = String::new();
+    let plan = TestPlan {
+        cmd: String::from("ps"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    };
+    run_test_with_checker(plan, |_, output| {
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+        assert_eq!(output.status.code(), Some(0));
+        stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    });
+    stdout
+}
+
+#[test]
+fn ps_output_format_without_headers() {
+    let pid = std::process::id().to_string();
+    let ppid = unsafe { libc::getppid() }.to_string();
+    let out = ps_output(&["-p", &pid, "-o", "pid=", "-o", "ppid="]);
+    let fields: Vec<&str> = out.split_whitespace().collect();
+    assert_eq!(fields, [pid.as_str(), ppid.as_str()]);
+}
+
+#[test]
+fn ps_output_format_headers() {
+    let pid = std::process::id().to_string();
+    let out = ps_output(&["-p", &pid, "-o", "pid,user", "-o", "ppid=PARENT PROCESS"]);
+    let mut lines = out.lines();
+    let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert_eq!(header, ["PID", "USER", "PARENT", "PROCESS"]);
+    assert_eq!(lines.count(), 1);
+
+    // columns are as wide as their header
+    let out = ps_output(&["-p", &pid, "-o", "pid=PROCESS_ID"]);
+    assert_eq!(out, format!("PROCESS_ID\n{:>10}\n", pid));
+}
+
+#[test]
+fn ps_select_pids() {
+    let pid = std::process::id();
+    let out = ps_output(&["-p", &format!("1,{pid}"), "-o", "pid="]);
+    let pids: Vec<u32> = out.lines().map(|l| l.trim().parse().unwrap()).collect();
+    assert_eq!(pids, [1, pid]);
+
+    let out = ps_output(&["-e", "-o", "pid="]);
+    assert!(out.lines().any(|l| l.trim() == "1"));
+}
+
+#[test]
+fn ps_select_users() {
+    let pid = std::process::id().to_string();
+    let uid = unsafe { libc::geteuid() }.to_string();
+    let out = ps_output(&["-u", &uid, "-o", "pid=", "-o", "uid="]);
+    assert!(out
+        .lines()
+        .all(|l| l.split_whitespace().nth(1) == Some(&uid)));
+    assert!(out
+        .lines()
+        .any(|l| l.split_whitespace().next() == Some(&pid)));
+}
+
+#[test]
+fn ps_invalid_arguments() {
+    ps_test(
+        &["-o", "pid,bogus"],
+        "",
+        "ps: unknown output field 'bogus'\n",
+        1,
+    );
+    ps_test(&["-p", "12x"], "", "ps: invalid process ID '12x'\n", 1);
+}
@@ -1 +1,2 @@
 mod getconf;
+mod ps;
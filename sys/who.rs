@@ -14,9 +14,9 @@
 use std::path::PathBuf;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use gettextrs::gettext;
 use plib::utmpx::Utmpx;
-use plib::{curuser, platform, utmpx};
+use plib::{curuser, i18n, platform, tdiag, timefmt, utmpx};
 
 /// who - display who is on the system
 #[derive(Parser)]
@@ -84,8 +84,10 @@ struct Args {
 
 // convert timestamp into POSIX-specified strftime format
 fn fmt_timestamp(ts: libc::time_t) -> String {
-    let dt = chrono::DateTime::from_timestamp(ts, 0).unwrap();
-    dt.format("%b %e %H:%M").to_string()
+    match timefmt::Tm::local(ts) {
+        Some(tm) => tm.format("%b %e %H:%M"),
+        None => ts.to_string(),
+    }
 }
 
 fn print_fmt_short(entry: &Utmpx, line: &str) {
@@ -183,9 +185,7 @@ fn show_utmpx_summary() {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    i18n::init();
 
     let args: Vec<String> = std::env::args().skip(1).collect();
     let am_i = args.len() == 2 && args[0] == "am" && (args[1] == "i" || args[1] == "I");
@@ -215,7 +215,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut exit_code = 0;
 
     if args.file.is_some() {
-        eprintln!("{}", gettext("who: -f option not yet implemented"));
+        tdiag!("-f option not yet implemented");
         exit_code = 1;
     } else if args.summary {
         show_utmpx_summary();
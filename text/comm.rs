@@ -10,9 +10,11 @@
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
+use plib::io::OrderedLines;
+
 const NO1: u32 = 1 << 0;
 const NO2: u32 = 1 << 1;
 const NODUP: u32 = 1 << 2;
@@ -33,6 +35,14 @@ struct Args {
     #[arg(short = '3', long)]
     no_dup: bool,
 
+    /// Fail if an input file is not sorted.
+    #[arg(long, overrides_with = "nocheck_order")]
+    check_order: bool,
+
+    /// Do not check that the input files are sorted.
+    #[arg(long, overrides_with = "check_order")]
+    nocheck_order: bool,
+
     /// Comparison file1
     file1: PathBuf,
 
@@ -40,14 +50,20 @@ struct Args {
     file2: PathBuf,
 }
 
-fn line_out(lead_dup: &'static str, outmask: u32, curtype: u32, s: &str) -> io::Result<()> {
+fn line_out(
+    out: &mut impl Write,
+    lead_dup: &'static str,
+    outmask: u32,
+    curtype: u32,
+    s: &str,
+) -> io::Result<()> {
     if (outmask & curtype) != 0 {
         return Ok(());
     }
 
     match curtype {
         NO1 => {
-            io::stdout().write_all(s.as_bytes())?;
+            out.write_all(s.as_bytes())?;
         }
         NO2 => {
             let lead_f2 = {
@@ -58,11 +74,11 @@ fn line_out(lead_dup: &'static str, outmask: u32, curtype: u32, s: &str) -> io::
                 }
             };
             let newstr = format!("{}{}", lead_f2, s);
-            io::stdout().write_all(newstr.as_bytes())?;
+            out.write_all(newstr.as_bytes())?;
         }
         NODUP => {
             let newstr = format!("{}{}", lead_dup, s);
-            io::stdout().write_all(newstr.as_bytes())?;
+            out.write_all(newstr.as_bytes())?;
         }
         _ => {
             panic!("should never occur");
@@ -81,10 +97,23 @@ fn comm_file(
     lead_dup: &'static str,
     file1name: &PathBuf,
     file2name: &PathBuf,
+    check_order: bool,
 ) -> io::Result<()> {
-    // open files, or stdin
-    let mut rdr1 = open_file(file1name)?;
-    let mut rdr2 = open_file(file2name)?;
+    let mut rdr1 = OrderedLines::new(
+        open_file(file1name)?,
+        file1name.display().to_string(),
+        |line| line,
+        str::cmp,
+        check_order,
+    );
+    let mut rdr2 = OrderedLines::new(
+        open_file(file2name)?,
+        file2name.display().to_string(),
+        |line| line,
+        str::cmp,
+        check_order,
+    );
+    let mut out = plib::io::stdout();
 
     let mut buf1 = String::new();
     let mut buf2 = String::new();
@@ -93,13 +122,15 @@ fn comm_file(
 
     loop {
         if want1 && buf1.is_empty() {
-            if rdr1.read_line(&mut buf1)? == 0 {
-                want1 = false;
+            match rdr1.next().transpose()? {
+                Some(line) => buf1 = line,
+                None => want1 = false,
             }
         }
         if want2 && buf2.is_empty() {
-            if rdr2.read_line(&mut buf2)? == 0 {
-                want2 = false;
+            match rdr2.next().transpose()? {
+                Some(line) => buf2 = line,
+                None => want2 = false,
             }
         }
 
@@ -108,25 +139,25 @@ fn comm_file(
         }
 
         if buf1.is_empty() {
-            line_out(lead_dup, mask, NO2, &buf2)?;
+            line_out(&mut out, lead_dup, mask, NO2, &buf2)?;
             buf2.clear();
         } else if buf2.is_empty() {
-            line_out(lead_dup, mask, NO1, &buf1)?;
+            line_out(&mut out, lead_dup, mask, NO1, &buf1)?;
             buf1.clear();
         } else if buf1 < buf2 {
-            line_out(lead_dup, mask, NO1, &buf1)?;
+            line_out(&mut out, lead_dup, mask, NO1, &buf1)?;
             buf1.clear();
         } else if buf2 < buf1 {
-            line_out(lead_dup, mask, NO2, &buf2)?;
+            line_out(&mut out, lead_dup, mask, NO2, &buf2)?;
             buf2.clear();
         } else {
-            line_out(lead_dup, mask, NODUP, &buf1)?;
+            line_out(&mut out, lead_dup, mask, NODUP, &buf1)?;
             buf1.clear();
             buf2.clear();
         }
     }
 
-    Ok(())
+    out.finish()
 }
 
 fn args_mask(args: &Args) -> u32 {
@@ -162,9 +193,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut exit_code = 0;
 
-    if let Err(e) = comm_file(mask, lead_dup, &args.file1, &args.file2) {
+    if let Err(e) = comm_file(mask, lead_dup, &args.file1, &args.file2, args.check_order) {
         exit_code = 1;
-        eprintln!("{}", e);
+        plib::diag!("{}", plib::diag::error_message(&e));
     }
 
     std::process::exit(exit_code)
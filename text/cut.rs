@@ -7,10 +7,12 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, BufRead, Error, ErrorKind, Read};
+use std::io::{self, Error, ErrorKind, Read, Write};
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::delimiter::parse_delimiter;
+use plib::io::{RecordReader, RECORD_BUFSZ};
 use std::path::PathBuf;
 
 /// Cut - cut out selected fields of each line of a file
@@ -30,7 +32,7 @@ struct Args {
     fields: Option<String>,
 
     /// Set the field delimiter
-    #[arg(short = 'd', long)]
+    #[arg(short = 'd', long, value_parser = parse_delimiter)]
     delimiter: Option<char>,
 
     /// Suppress lines with no delimiter characters
@@ -236,13 +238,7 @@ fn cut_characters(line: &str, delim: Option<char>, ranges: &Vec<(i32, i32)>) ->
 fn cut_fields(line: &str, delim: char, ranges: &Vec<(i32, i32)>, suppress: bool) -> (String, bool) {
     let mut result = String::new();
     let mut skip = false;
-    let delim_escaped = delim.escape_debug().to_string();
-    let mut fields: Vec<&str>;
-    if delim_escaped.len() > 1 {
-        fields = line.split(&delim_escaped).collect();
-    } else {
-        fields = line.split(delim).collect();
-    }
+    let mut fields: Vec<&str> = line.split(delim).collect();
 
     if fields.len() == 1 {
         fields = vec![];
@@ -302,9 +298,11 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
             bufs
         };
 
+    let mut stdout = plib::io::stdout();
+
     // Process each file
     for file in readers {
-        let reader = io::BufReader::new(file);
+        let mut records = RecordReader::lines(io::BufReader::with_capacity(RECORD_BUFSZ, file));
 
         let parse_option;
 
@@ -326,32 +324,35 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
             return Err(Box::new(Error::new(ErrorKind::Other, "Invalid arguments")));
         }
 
-        for line in reader.lines() {
-            let line = line?;
-            match parse_option.clone() {
-                ParseVariat::Bytes(ranges) => {
-                    let bytes = cut_bytes(line.as_bytes(), args.delimiter, &ranges, args.no_split);
-                    match String::from_utf8(bytes) {
-                        Ok(string) => println!("{}", string),
-                        Err(e) => eprintln!("Conversion error to string: {}", e),
-                    }
-                }
+        if let ParseVariat::Bytes(ranges) = &parse_option {
+            while let Some(record) = records.next_record()? {
+                let line = record.strip_suffix(b"\n").unwrap_or(record);
+                stdout.write_all(&cut_bytes(line, args.delimiter, ranges, args.no_split))?;
+                stdout.write_all(b"\n")?;
+            }
+            continue;
+        }
+
+        while let Some(line) = records.next_text()? {
+            match &parse_option {
+                ParseVariat::Bytes(_) => unreachable!(),
                 ParseVariat::Characters(ranges) => {
-                    println!("{}", cut_characters(&line, args.delimiter, &ranges))
+                    writeln!(stdout, "{}", cut_characters(line, args.delimiter, ranges))?
                 }
                 ParseVariat::Fields(ranges) => {
                     if let Some(delim) = args.delimiter {
-                        let result = cut_fields(&line, delim, &ranges, args.suppress);
+                        let result = cut_fields(line, delim, ranges, args.suppress);
                         if !result.1 {
-                            println!("{}", result.0)
+                            writeln!(stdout, "{}", result.0)?
                         }
                     } else {
-                        println!("{}", line);
+                        writeln!(stdout, "{}", line)?;
                     }
                 }
             }
         }
     }
+    stdout.finish()?;
     Ok(())
 }
 
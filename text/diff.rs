@@ -30,14 +30,26 @@ use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 #[derive(Parser, Clone)]
 #[command(version, about)]
 struct Args {
+    /// Treat all files as text, even those that look binary
+    #[arg(short = 'a', long)]
+    text: bool,
+
     /// Cause EOL whitespace to be treated as blanks
     #[arg(short = 'b', long = "ignore-space-change")]
     ignore_eol_space: bool,
 
+    /// Strip a carriage return at the end of each input line
+    #[arg(long)]
+    strip_trailing_cr: bool,
+
     /// Output 3 lines of copied context
     #[arg(short)]
     context3: bool,
 
+    /// Report only whether the files differ
+    #[arg(short = 'q', long)]
+    brief: bool,
+
     /// Output <N> lines of copied context
     #[arg(short='C', value_parser = clap::value_parser!(u32).range(1..))]
     context: Option<u32>,
@@ -87,7 +99,9 @@ impl From<&Args> for OutputFormat {
             args.unified = Some(3);
         }
 
-        if args.ed {
+        if args.brief {
+            OutputFormat::Brief
+        } else if args.ed {
             OutputFormat::EditScript
         } else if args.fed {
             OutputFormat::ForwardEditScript
@@ -113,14 +127,12 @@ fn check_difference(args: Args) -> io::Result<DiffExitStatus> {
         return Ok(DiffExitStatus::Trouble);
     }
 
-    if path1 == path2 {
-        return Ok(DiffExitStatus::Trouble);
-    }
-
     let output_format: OutputFormat = (&args).into();
 
     let format_options = FormatOptions::try_new(
         args.ignore_eol_space,
+        args.strip_trailing_cr,
+        args.text,
         output_format,
         args.label,
         args.label2,
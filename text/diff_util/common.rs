@@ -1,5 +1,7 @@
 pub struct FormatOptions {
     pub ignore_trailing_white_spaces: bool,
+    pub strip_trailing_cr: bool,
+    pub text: bool,
     pub output_format: OutputFormat,
     label1: Option<String>,
     label2: Option<String>,
@@ -8,6 +10,8 @@ pub struct FormatOptions {
 impl FormatOptions {
     pub fn try_new(
         ignore_trailing_white_spaces: bool,
+        strip_trailing_cr: bool,
+        text: bool,
         output_format: OutputFormat,
         label1: Option<String>,
         label2: Option<String>,
@@ -18,6 +22,8 @@ impl FormatOptions {
 
         Ok(Self {
             ignore_trailing_white_spaces,
+            strip_trailing_cr,
+            text,
             output_format,
             label1,
             label2,
@@ -37,6 +43,7 @@ impl FormatOptions {
 pub enum OutputFormat {
     Debug,
     Default,
+    Brief,
     Context(usize),
     EditScript,
     ForwardEditScript,
@@ -2,8 +2,10 @@ use std::process::Termination;
 
 use super::constants::{EXIT_STATUS_DIFFERENCE, EXIT_STATUS_NO_DIFFERENCE, EXIT_STATUS_TROUBLE};
 
+/// Ordered by severity, so that the status of several comparisons is the
+/// greatest of their statuses.
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DiffExitStatus {
     NotDifferent,
     Different,
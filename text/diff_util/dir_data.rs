@@ -6,7 +6,7 @@ use std::{
     path::PathBuf,
 };
 
-use super::constants::*;
+use super::{constants::*, functions::error_with_path};
 
 pub struct DirData {
     path: PathBuf,
@@ -17,7 +17,7 @@ impl DirData {
     pub fn load(path: PathBuf) -> io::Result<Self> {
         let mut files: HashMap<OsString, DirEntry> = Default::default();
 
-        let entries = fs::read_dir(&path)?;
+        let entries = fs::read_dir(&path).map_err(|err| error_with_path(&path, err))?;
 
         for entry in entries {
             let entry = entry?;
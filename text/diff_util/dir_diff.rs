@@ -1,4 +1,4 @@
-use std::{collections::HashSet, ffi::OsString, io, path::PathBuf};
+use std::{cmp::max, collections::HashSet, ffi::OsString, io, path::PathBuf};
 
 use crate::diff_util::{
     constants::COULD_NOT_UNWRAP_FILENAME, diff_exit_status::DiffExitStatus, file_diff::FileDiff,
@@ -94,6 +94,9 @@ impl<'a> DirDiff<'a> {
                                 show_if_different.push_str("--debug ")
                             }
                             crate::diff_util::common::OutputFormat::Default => {}
+                            crate::diff_util::common::OutputFormat::Brief => {
+                                show_if_different.push_str("-q ")
+                            }
                             crate::diff_util::common::OutputFormat::Context(ctx) => {
                                 show_if_different.push_str(format!("-C {} ", ctx).as_str())
                             }
@@ -140,24 +143,41 @@ impl<'a> DirDiff<'a> {
                             show_if_different.push(' ');
                         }
 
+                        // -q reports differing files without the command
+                        let show_if_different = (!matches!(
+                            self.format_options.output_format,
+                            crate::diff_util::common::OutputFormat::Brief
+                        ))
+                        .then_some(show_if_different);
+
+                        // A file that can't be compared is reported, and the
+                        // other files are still compared
                         let inner_exit_status = FileDiff::file_diff(
                             path1,
                             path2,
                             self.format_options,
-                            Some(show_if_different),
-                        )?;
-
-                        if exit_status.status_code() < inner_exit_status.status_code() {
-                            exit_status = inner_exit_status;
-                        }
+                            show_if_different,
+                        )
+                        .unwrap_or_else(|err| {
+                            eprintln!("diff: {}", err);
+                            DiffExitStatus::Trouble
+                        });
+
+                        exit_status = max(exit_status, inner_exit_status);
                     } else if !in_dir1_is_file && !in_dir2_is_file {
                         if self.recursive {
-                            Self::dir_diff(
+                            let inner_exit_status = Self::dir_diff(
                                 self.dir1.path().join(file_name),
                                 self.dir2.path().join(file_name),
                                 self.format_options,
                                 self.recursive,
-                            )?;
+                            )
+                            .unwrap_or_else(|err| {
+                                eprintln!("diff: {}", err);
+                                DiffExitStatus::Trouble
+                            });
+
+                            exit_status = max(exit_status, inner_exit_status);
                         } else {
                             println!(
                                 "Common subdirectories: \"{}\" and \"{}\"",
@@ -190,6 +210,7 @@ impl<'a> DirDiff<'a> {
                             "File \"{}\" is a directory while file \"{}\" is a regular file",
                             dir, file
                         );
+                        exit_status = max(exit_status, DiffExitStatus::Different);
                     }
                 }
                 (true, false) => {
@@ -197,14 +218,16 @@ impl<'a> DirDiff<'a> {
                         "Only in {}: {}",
                         self.dir1.path_str(),
                         file_name.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
-                    )
+                    );
+                    exit_status = max(exit_status, DiffExitStatus::Different);
                 }
                 (false, true) => {
                     println!(
                         "Only in {}: {}",
                         self.dir2.path_str(),
                         file_name.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
-                    )
+                    );
+                    exit_status = max(exit_status, DiffExitStatus::Different);
                 }
                 (false, false) => {
                     eprintln!(
@@ -61,14 +61,16 @@ impl<'a> FileData<'a> {
 pub struct LineReader<'a> {
     content: &'a [u8],
     ends_with_newline: bool,
+    strip_trailing_cr: bool,
 }
 
 impl<'a> LineReader<'a> {
-    pub fn new(content: &'a [u8]) -> Self {
+    pub fn new(content: &'a [u8], strip_trailing_cr: bool) -> Self {
         let ends_with_newline = content.last() == Some(&b'\n');
         Self {
             content,
             ends_with_newline,
+            strip_trailing_cr,
         }
     }
     pub fn ends_with_newline(&self) -> bool {
@@ -80,24 +82,20 @@ impl<'a> Iterator for LineReader<'a> {
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut carriage = false;
-        let mut iter = self.content.iter().enumerate();
-        let mut line_len = loop {
-            match iter.next() {
-                Some((i, b'\n')) => break i + 1,
-                None => {
-                    return (!self.content.is_empty()).then(|| {
-                        from_utf8(take(&mut self.content)).expect("Failed to convert to str")
-                    });
-                }
-                Some((_, &it)) => carriage = it == b'\r',
+        if self.content.is_empty() {
+            return None;
+        }
+        let mut line = match self.content.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                let (line, rest) = self.content.split_at(i + 1);
+                self.content = rest;
+                &line[..i]
             }
+            None => take(&mut self.content),
         };
-        let (line, rest) = self.content.split_at(line_len);
-        if carriage {
-            line_len -= 1;
+        if self.strip_trailing_cr {
+            line = line.strip_suffix(b"\r").unwrap_or(line);
         }
-        self.content = rest;
-        Some(from_utf8(&line[..line_len - 1]).expect("Failed to convert to str"))
+        Some(from_utf8(line).expect("Failed to convert to str"))
     }
 }
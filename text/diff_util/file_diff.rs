@@ -3,7 +3,7 @@ use super::{
     constants::COULD_NOT_UNWRAP_FILENAME,
     diff_exit_status::DiffExitStatus,
     file_data::{FileData, LineReader},
-    functions::{check_existance, is_binary, system_time_to_rfc2822},
+    functions::{check_existance, error_with_path, is_binary, system_time_to_rfc2822},
     hunks::Hunks,
 };
 
@@ -13,7 +13,7 @@ use std::{
     cmp::Reverse,
     collections::HashMap,
     fmt::Write,
-    fs::{read_to_string, File},
+    fs::{self, read_to_string, File},
     io::{self, BufReader, Read},
     os::unix::fs::MetadataExt,
     path::PathBuf,
@@ -48,32 +48,13 @@ impl<'a> FileDiff<'a> {
         format_options: &FormatOptions,
         show_if_different: Option<String>,
     ) -> io::Result<DiffExitStatus> {
-        if is_binary(&path1)? || is_binary(&path2)? {
-            Self::binary_file_diff(&path1, &path2)
+        if !format_options.text && (is_binary(&path1)? || is_binary(&path2)?) {
+            Self::binary_file_diff(&path1, &path2, format_options)
         } else {
-            let content1 = read_to_string(&path1)?.into_bytes();
-            let linereader1 = LineReader::new(&content1);
-            let ends_with_newline1 = linereader1.ends_with_newline();
-            let mut lines1 = Vec::new();
-            for line in linereader1 {
-                if !format_options.ignore_trailing_white_spaces {
-                    lines1.push(line);
-                } else {
-                    lines1.push(line.trim_end());
-                }
-            }
-
-            let content2 = read_to_string(&path2)?.into_bytes();
-            let linereader2 = LineReader::new(&content2);
-            let ends_with_newline2 = linereader2.ends_with_newline();
-            let mut lines2 = Vec::new();
-            for line in linereader2 {
-                if !format_options.ignore_trailing_white_spaces {
-                    lines2.push(line);
-                } else {
-                    lines2.push(line.trim_end());
-                }
-            }
+            let content1 = Self::read_content(&path1, format_options)?;
+            let (lines1, ends_with_newline1) = Self::split_lines(&content1, format_options);
+            let content2 = Self::read_content(&path2, format_options)?;
+            let (lines2, ends_with_newline2) = Self::split_lines(&content2, format_options);
             let mut file1 = FileData::get_file(path1, lines1, ends_with_newline1)?;
             let mut file2 = FileData::get_file(path2, lines2, ends_with_newline2)?;
 
@@ -110,6 +91,35 @@ impl<'a> FileDiff<'a> {
         }
     }
 
+    /// Reads a file to compare, which with -a may hold bytes that are not
+    /// UTF-8; those are replaced rather than refused.
+    fn read_content(path: &PathBuf, format_options: &FormatOptions) -> io::Result<Vec<u8>> {
+        if format_options.text {
+            let content = fs::read(path).map_err(|err| error_with_path(path, err))?;
+            Ok(String::from_utf8_lossy(&content).into_owned().into_bytes())
+        } else {
+            let content = read_to_string(path).map_err(|err| error_with_path(path, err))?;
+            Ok(content.into_bytes())
+        }
+    }
+
+    /// Splits the content of a file into the lines compared, and tells
+    /// whether it ends with a newline.
+    fn split_lines<'c>(content: &'c [u8], format_options: &FormatOptions) -> (Vec<&'c str>, bool) {
+        let linereader = LineReader::new(content, format_options.strip_trailing_cr);
+        let ends_with_newline = linereader.ends_with_newline();
+        let lines = linereader
+            .map(|line| {
+                if format_options.ignore_trailing_white_spaces {
+                    line.trim_end()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        (lines, ends_with_newline)
+    }
+
     pub fn file_dir_diff(
         path1: PathBuf,
         path2: PathBuf,
@@ -140,9 +150,18 @@ impl<'a> FileDiff<'a> {
         }
     }
 
-    fn binary_file_diff(file1_path: &PathBuf, file2_path: &PathBuf) -> io::Result<DiffExitStatus> {
+    fn binary_file_diff(
+        file1_path: &PathBuf,
+        file2_path: &PathBuf,
+        format_options: &FormatOptions,
+    ) -> io::Result<DiffExitStatus> {
+        let kind = match format_options.output_format {
+            OutputFormat::Brief => "Files",
+            _ => "Binary files",
+        };
         let differ_report = format!(
-            "Binary files {} and {} differ",
+            "{} {} and {} differ",
+            kind,
             file1_path.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME),
             file2_path.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
         );
@@ -172,7 +191,15 @@ impl<'a> FileDiff<'a> {
     fn print(&mut self) -> io::Result<DiffExitStatus> {
         self.order_hunks_by_output_format();
 
-        if let OutputFormat::Context(context) = self.format_options.output_format {
+        if let OutputFormat::Brief = self.format_options.output_format {
+            if self.are_different {
+                println!(
+                    "Files {} and {} differ",
+                    self.file1.path(),
+                    self.file2.path()
+                );
+            }
+        } else if let OutputFormat::Context(context) = self.format_options.output_format {
             let _ = self.print_context(context);
         } else if let OutputFormat::Unified(unified) = self.format_options.output_format {
             let _ = self.print_unified(unified);
@@ -183,6 +210,10 @@ impl<'a> FileDiff<'a> {
                 let hunk = self.hunks.hunk_at_mut(hunk_index);
                 match self.format_options.output_format {
                     OutputFormat::Debug => hunk.print_debug(self.file1, self.file2),
+                    OutputFormat::Brief => {
+                        eprintln!("OutputFormat::Brief should be handled in other place");
+                        return Ok(DiffExitStatus::Trouble);
+                    }
                     OutputFormat::Default => {
                         hunk.print_default(self.file1, self.file2, hunk_index == hunks_count - 1)
                     }
@@ -294,6 +325,7 @@ impl<'a> FileDiff<'a> {
         match self.format_options.output_format {
             OutputFormat::Debug => self.order_hunks_ascending(),
             OutputFormat::Default => self.order_hunks_ascending(),
+            OutputFormat::Brief => self.order_hunks_ascending(),
             OutputFormat::Context(_) => self.order_hunks_ascending(),
             OutputFormat::EditScript => self.order_hunks_descending(),
             OutputFormat::ForwardEditScript => self.order_hunks_ascending(),
@@ -13,8 +13,13 @@ pub fn system_time_to_rfc2822(system_time: SystemTime) -> String {
     Into::<DateTime<Local>>::into(system_time).to_rfc2822()
 }
 
+/// Adds the path of the file to an error reading it.
+pub fn error_with_path(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
 pub fn is_binary(file_path: &PathBuf) -> io::Result<bool> {
-    let mut file = File::open(file_path)?;
+    let mut file = File::open(file_path).map_err(|err| error_with_path(file_path, err))?;
     let mut buffer = [0; 1024];
 
     if let Ok(count) = file.read(&mut buffer) {
@@ -30,7 +35,7 @@ pub fn is_binary(file_path: &PathBuf) -> io::Result<bool> {
 
 pub fn check_existance(path_buf: &Path) -> io::Result<bool> {
     if !path_buf.exists() {
-        println!(
+        eprintln!(
             "diff: {}: No such file or directory",
             path_buf.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
         );
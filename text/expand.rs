@@ -7,7 +7,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
@@ -58,7 +58,7 @@ fn parse_tablist(tablist: &str) -> Result<TabList, &'static str> {
     Ok(TabList::Stops(v))
 }
 
-fn space_out(column: &mut usize, writer: &mut BufWriter<dyn Write>) -> io::Result<()> {
+fn space_out(column: &mut usize, writer: &mut impl Write) -> io::Result<()> {
     *column += 1;
 
     writer.write_all(b" ")?;
@@ -71,7 +71,7 @@ fn expand_file(tablist: &TabList, pathname: &PathBuf) -> io::Result<()> {
     let mut file = input_stream(pathname, false)?;
 
     let mut raw_buffer = [0; BUFSZ];
-    let mut writer = BufWriter::new(io::stdout());
+    let mut writer = plib::io::stdout();
     let mut column: usize = 1;
     let mut cur_stop = 0;
 
@@ -126,9 +126,7 @@ fn expand_file(tablist: &TabList, pathname: &PathBuf) -> io::Result<()> {
         }
     }
 
-    writer.flush()?;
-
-    Ok(())
+    writer.finish()
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -12,7 +12,7 @@ use std::path::PathBuf;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream;
+use plib::io::{input_stream, StdoutWriter};
 use plib::BUFSZ;
 
 const TABSTOP: usize = 8;
@@ -41,6 +41,7 @@ struct OutputState {
     args: Args,
     column: usize,
     data: Vec<u8>,
+    out: StdoutWriter,
 }
 
 impl OutputState {
@@ -49,6 +50,7 @@ impl OutputState {
             args: args.clone(),
             column: 0,
             data: Vec::new(),
+            out: plib::io::stdout(),
         }
     }
 
@@ -80,7 +82,7 @@ impl OutputState {
     }
 
     fn write_line(&mut self) -> io::Result<()> {
-        io::stdout().write_all(&self.data)?;
+        self.out.write_all(&self.data)?;
 
         self.column = 0;
         self.data.clear();
@@ -163,7 +165,7 @@ fn fold_file(args: &Args, pathname: &PathBuf) -> io::Result<()> {
         }
     }
 
-    Ok(())
+    state.out.finish()
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
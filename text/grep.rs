@@ -8,12 +8,17 @@
 //
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use libc::{regcomp, regex_t, regexec, regfree, REG_EXTENDED, REG_ICASE, REG_NOMATCH};
+use libc::{
+    regcomp, regex_t, regexec, regfree, regmatch_t, REG_EXTENDED, REG_ICASE, REG_NOMATCH,
+    REG_NOTBOL,
+};
+use plib::ctype::{fold_case, fold_case_mapped};
+use plib::io::{MappedReader, RecordReader};
 use std::{
     ffi::CString,
     fs::File,
     io::{self, BufRead, BufReader},
+    ops::Range,
     path::{Path, PathBuf},
     ptr,
 };
@@ -54,6 +59,10 @@ struct Args {
     #[arg(short = 'n', long)]
     line_number: bool,
 
+    /// Write only the parts of selected lines that match, each on its own line.
+    #[arg(short, long)]
+    only_matching: bool,
+
     /// Write only the names of input_files containing selected lines to standard output.
     #[arg(short, long)]
     quiet: bool,
@@ -191,6 +200,7 @@ impl Args {
             any_matches: false,
             any_errors: self.any_errors,
             line_number: self.line_number,
+            only_matching: self.only_matching,
             no_messages: self.no_messages,
             invert_match: self.invert_match,
             multiple_inputs: self.input_files.len() > 1,
@@ -236,7 +246,7 @@ impl Patterns {
             Ok(Self::Fixed(
                 patterns
                     .into_iter()
-                    .map(|p| if ignore_case { p.to_lowercase() } else { p })
+                    .map(|p| if ignore_case { fold_case(&p) } else { p })
                     .collect(),
                 ignore_case,
                 line_regexp,
@@ -302,7 +312,7 @@ impl Patterns {
         match self {
             Patterns::Fixed(patterns, ignore_case, line_regexp) => {
                 let input = if *ignore_case {
-                    input.to_lowercase()
+                    fold_case(input)
                 } else {
                     input.to_string()
                 };
@@ -322,6 +332,82 @@ impl Patterns {
             }
         }
     }
+
+    /// Finds the leftmost, then longest, match of any of the patterns in
+    /// `input` at or after byte `start`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the byte range of the match, if there is one.
+    fn find_at(&self, input: &str, start: usize) -> Option<Range<usize>> {
+        let mut best: Option<Range<usize>> = None;
+        let mut consider = |found: Range<usize>| {
+            if best.as_ref().map_or(true, |b| {
+                found.start < b.start || (found.start == b.start && found.end > b.end)
+            }) {
+                best = Some(found);
+            }
+        };
+        match self {
+            Patterns::Fixed(patterns, _, line_regexp) => {
+                for p in patterns {
+                    if *line_regexp {
+                        if start == 0 && input == p {
+                            consider(0..input.len());
+                        }
+                    } else if let Some(i) = input[start..].find(p.as_str()) {
+                        consider(start + i..start + i + p.len());
+                    }
+                }
+            }
+            Patterns::Regex(patterns) => {
+                let c_input = CString::new(&input[start..]).unwrap();
+                let eflags = if start > 0 { REG_NOTBOL } else { 0 };
+                for p in patterns {
+                    let mut pmatch = unsafe { std::mem::zeroed::<regmatch_t>() };
+                    if unsafe { regexec(p, c_input.as_ptr(), 1, &mut pmatch, eflags) } == 0 {
+                        consider(start + pmatch.rm_so as usize..start + pmatch.rm_eo as usize);
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Finds the non-empty, non-overlapping matches in `input`, for `-o`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the matched parts of `input`.
+    fn find_all(&self, input: &str) -> Vec<String> {
+        // Fixed strings ignoring case are looked for in the case folded
+        // input, and the matches mapped back to the input
+        let folded = matches!(self, Patterns::Fixed(_, true, _)).then(|| fold_case_mapped(input));
+        let haystack = folded.as_ref().map_or(input, |(folded, _)| folded.as_str());
+        let original = |range: Range<usize>| match &folded {
+            Some((_, offsets)) => offsets[range.start]..offsets[range.end],
+            None => range,
+        };
+
+        let mut found = vec![];
+        let mut start = 0;
+        while start <= haystack.len() {
+            let Some(range) = self.find_at(haystack, start) else {
+                break;
+            };
+            if range.is_empty() {
+                start = range.end
+                    + haystack[range.end..]
+                        .chars()
+                        .next()
+                        .map_or(1, char::len_utf8);
+            } else {
+                start = range.end;
+                found.push(input[original(range)].to_string());
+            }
+        }
+        found
+    }
 }
 
 impl Drop for Patterns {
@@ -351,6 +437,7 @@ struct GrepModel {
     any_matches: bool,
     any_errors: bool,
     line_number: bool,
+    only_matching: bool,
     no_messages: bool,
     invert_match: bool,
     multiple_inputs: bool,
@@ -373,7 +460,7 @@ impl GrepModel {
             } else {
                 match File::open(&input_name) {
                     Ok(file) => {
-                        let reader = Box::new(BufReader::new(file));
+                        let reader = Box::new(MappedReader::from_file(file));
                         self.process_input(&input_name, reader)
                     }
                     Err(err) => {
@@ -404,22 +491,14 @@ impl GrepModel {
     ///
     /// * `input_name` - [str](str) that represents content source name.
     /// * `reader` - [Box](Box) that contains object that implements [BufRead] and reads lines.
-    fn process_input(&mut self, input_name: &str, mut reader: Box<dyn BufRead>) {
+    fn process_input(&mut self, input_name: &str, reader: Box<dyn BufRead>) {
+        let mut records = RecordReader::lines(reader);
         let mut line_number: u64 = 0;
         loop {
-            let mut line = String::new();
             line_number += 1;
-            match reader.read_line(&mut line) {
-                Ok(n_read) => {
-                    if n_read == 0 {
-                        break;
-                    }
-                    let trimmed = if line.ends_with('\n') {
-                        &line[..line.len() - 1]
-                    } else {
-                        &line
-                    };
-
+            match records.next_text() {
+                Ok(None) => break,
+                Ok(Some(trimmed)) => {
                     let init_matches = self.patterns.matches(trimmed);
                     let matches = if self.invert_match {
                         !init_matches
@@ -440,8 +519,8 @@ impl GrepModel {
                                 return;
                             }
                             OutputMode::Default => {
-                                let result = format!(
-                                    "{}{}{}",
+                                let prefix = format!(
+                                    "{}{}",
                                     if self.multiple_inputs {
                                         format!("{input_name}:")
                                     } else {
@@ -452,13 +531,18 @@ impl GrepModel {
                                     } else {
                                         String::new()
                                     },
-                                    trimmed
                                 );
-                                println!("{result}");
+                                if !self.only_matching {
+                                    println!("{prefix}{trimmed}");
+                                } else if !self.invert_match {
+                                    // Lines selected by -v have no matching parts
+                                    for part in self.patterns.find_all(trimmed) {
+                                        println!("{prefix}{part}");
+                                    }
+                                }
                             }
                         }
                     }
-                    line.clear();
                 }
                 Err(err) => {
                     self.any_errors = true;
@@ -468,6 +552,11 @@ impl GrepModel {
                             input_name, line_number, err
                         );
                     }
+                    // Only a line that isn't text can be skipped, other errors
+                    // would repeat for every following read
+                    if err.kind() != io::ErrorKind::InvalidData {
+                        break;
+                    }
                 }
             }
         }
@@ -487,9 +576,7 @@ impl GrepModel {
 //     1 - No lines were selected.
 //     >1 - An error occurred.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    plib::i18n::init();
 
     let mut args = Args::parse();
 
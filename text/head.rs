@@ -8,12 +8,15 @@
 //
 
 use std::error::Error;
-use std::io::{self, Read, StdoutLock, Write};
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream;
+use plib::diag::Status;
+use plib::io::{copy_to_stdout, input_stream, StdoutWriter};
+use plib::sizestr::parse_size_as;
 use plib::BUFSZ;
 
 const N_C_GROUP: &str = "N_C_GROUP";
@@ -31,7 +34,7 @@ struct Args {
     // https://pubs.opengroup.org/onlinepubs/9799919799/utilities/head.html
     //
     /// The first <N> bytes of each input file shall be copied to standard output (mutually exclusive with -n)
-    #[arg(long = "bytes", short = 'c', value_parser = clap::value_parser!(usize), group = N_C_GROUP)]
+    #[arg(long = "bytes", short = 'c', value_parser = parse_size_as::<usize>, group = N_C_GROUP)]
     bytes_to_copy: Option<usize>,
 
     /// Files to read as input.
@@ -48,7 +51,7 @@ fn head_file(
     pathname: &PathBuf,
     first: bool,
     want_header: bool,
-    stdout_lock: &mut StdoutLock,
+    stdout_lock: &mut StdoutWriter,
 ) -> Result<(), Box<dyn Error>> {
     const BUFFER_SIZE: usize = BUFSZ;
 
@@ -61,40 +64,22 @@ fn head_file(
         }
     }
 
-    // open file, or stdin
-    let mut file = input_stream(pathname, false)?;
-
     let mut raw_buffer = [0_u8; BUFFER_SIZE];
 
     match *count_type {
         CountType::Bytes(bytes_to_copy) => {
-            let mut bytes_remaining = bytes_to_copy;
-
-            loop {
-                let number_of_bytes_read = {
-                    // Do not read more bytes than necessary
-                    let read_up_to_n_bytes = BUFFER_SIZE.min(bytes_remaining);
-
-                    file.read(&mut raw_buffer[..read_up_to_n_bytes])?
-                };
-
-                if number_of_bytes_read == 0_usize {
-                    // Reached EOF
-                    break;
-                }
-
-                let bytes_to_write = &raw_buffer[..number_of_bytes_read];
-
-                stdout_lock.write_all(bytes_to_write)?;
-
-                bytes_remaining -= number_of_bytes_read;
-
-                if bytes_remaining == 0_usize {
-                    break;
-                }
+            // Copied as is, so the kernel can do the copying.
+            let limit = bytes_to_copy as u64;
+            if pathname.as_os_str().is_empty() {
+                copy_to_stdout(&mut io::stdin().lock().take(limit), stdout_lock)?;
+            } else {
+                copy_to_stdout(&mut File::open(pathname)?.take(limit), stdout_lock)?;
             }
         }
         CountType::Lines(n) => {
+            // open file, or stdin
+            let mut file = input_stream(pathname, false)?;
+
             let mut nl = 0_usize;
 
             loop {
@@ -192,19 +177,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let want_header = files.len() > 1;
 
-    let mut exit_code = 0;
+    let mut status = Status::new();
     let mut first = true;
 
-    let mut stdout_lock = io::stdout().lock();
+    let mut stdout_lock = plib::io::stdout();
 
     for filename in files {
         if let Err(e) = head_file(&count_type, filename, first, want_header, &mut stdout_lock) {
-            exit_code = 1;
-            eprintln!("{}: {}", filename.display(), e);
+            status.operand_error(filename.display(), &e);
         }
 
         first = false;
     }
 
-    std::process::exit(exit_code)
+    if let Err(e) = stdout_lock.finish() {
+        status.error(&e);
+    }
+
+    status.exit()
 }
@@ -9,11 +9,16 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::path::PathBuf;
 
+use plib::collate::strcoll;
+use plib::delimiter::parse_delimiter;
+use plib::io::OrderedLines;
+
 /// join - relational database operator
 #[derive(Parser)]
 #[command(version, about)]
@@ -31,7 +36,7 @@ struct Args {
     order: Option<Vec<String>>,
 
     /// Field separator character
-    #[arg(short = 't', default_value_t = ' ')]
+    #[arg(short = 't', default_value_t = ' ', value_parser = parse_delimiter)]
     separator: char,
 
     /// Output only unpairable lines from file_number
@@ -46,6 +51,14 @@ struct Args {
     #[arg(short = '2', default_value_t = 1)]
     field2: usize,
 
+    /// Fail if an input file is not sorted on its join field
+    #[arg(long, overrides_with = "nocheck_order")]
+    check_order: bool,
+
+    /// Do not check that the input files are sorted
+    #[arg(long, overrides_with = "check_order")]
+    nocheck_order: bool,
+
     /// File 1
     file1: PathBuf,
 
@@ -53,6 +66,10 @@ struct Args {
     file2: PathBuf,
 }
 
+fn collate(a: &str, b: &str) -> Ordering {
+    strcoll(a.as_bytes(), b.as_bytes())
+}
+
 fn parse_fields(line: &str, sep: char) -> Vec<String> {
     line.split(sep).map(|s| s.to_string()).collect()
 }
@@ -67,7 +84,11 @@ fn process_files2(
     e: Option<String>,
     o: Option<Vec<String>>,
     v: u8,
+    check_order: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // output fields are separated by the input field separator
+    let output_sep = sep.to_string();
+
     // code to support stdin
     let stdin = io::stdin();
     let file1: Box<dyn BufRead> = if file1_path.to_str() == Some("-") {
@@ -76,10 +97,19 @@ fn process_files2(
         Box::new(BufReader::new(File::open(file1_path)?))
     };
 
+    let file1 = OrderedLines::new(
+        file1,
+        file1_path.display().to_string(),
+        |line| line.split(sep).nth(field1 - 1).unwrap_or(""),
+        collate,
+        check_order,
+    );
+
     let mut matched_keys = HashMap::new();
-    for line1 in file1.lines() {
+    for line1 in file1 {
         let line1 = line1?;
-        let fields1 = parse_fields(&line1, sep);
+        let line1 = line1.strip_suffix('\n').unwrap_or(&line1);
+        let fields1 = parse_fields(line1, sep);
         let key1 = &fields1[field1 - 1];
 
         let mut found_match = false;
@@ -89,9 +119,16 @@ fn process_files2(
         } else {
             Box::new(BufReader::new(File::open(file2_path)?))
         };
-        for line2 in file2.lines() {
+        let file2 = OrderedLines::new(
+            file2,
+            file2_path.display().to_string(),
+            |line| line.split(sep).nth(field2 - 1).unwrap_or(""),
+            collate,
+            check_order,
+        );
+        for line2 in file2 {
             let line = line2?;
-            let fields2 = parse_fields(&line, sep);
+            let fields2 = parse_fields(line.strip_suffix('\n').unwrap_or(&line), sep);
             let key2 = &fields2[field2 - 1];
 
             if key1 == key2 {
@@ -127,18 +164,23 @@ fn process_files2(
                         }
                     }
                     if v == 0 {
-                        println!("{}", res.join(" "));
+                        println!("{}", res.join(&output_sep));
                     }
                 } else {
                     if v == 0 {
-                        println!("{} {}", fields1.join(" "), fields2[1..].join(" "));
+                        println!(
+                            "{}{}{}",
+                            fields1.join(&output_sep),
+                            output_sep,
+                            fields2[1..].join(&output_sep)
+                        );
                     }
                 }
             }
         }
 
         if !found_match && a == 1 {
-            println!("{}", fields1.join(" "));
+            println!("{}", fields1.join(&output_sep));
         }
     }
 
@@ -150,7 +192,7 @@ fn process_files2(
             let fields1 = parse_fields(&line1, sep);
             let key1 = &fields1[field1 - 1];
             if !matched_keys.contains_key(key1) {
-                println!("{}", fields1.join(" "));
+                println!("{}", fields1.join(&output_sep));
             }
         }
     } else if v == 2 {
@@ -159,7 +201,7 @@ fn process_files2(
             let fields2 = parse_fields(&line2, sep);
             let key2 = &fields2[field2 - 1];
             if !matched_keys.contains_key(key2) {
-                println!("{}", fields2.join(" "));
+                println!("{}", fields2.join(&output_sep));
             }
         }
     }
@@ -178,6 +220,7 @@ fn join(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         args.empty,
         args.order,
         args.unpairable,
+        args.check_order,
     )?;
 
     Ok(())
@@ -194,7 +237,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Err(err) = join(args) {
         exit_code = 1;
-        eprint!("{}", err);
+        plib::diag!("{}", plib::diag::error_message(&err));
     }
 
     std::process::exit(exit_code)
@@ -52,7 +52,7 @@ struct Args {
     header_numbering: LineNumberingStyle,
 
     /// Specify the increment value used to number logical page lines.
-    #[arg(short = 'i', long, default_value_t = 1)]
+    #[arg(short = 'i', long, default_value_t = 1, allow_negative_numbers = true)]
     line_increment: i64,
 
     /// Specify the number of blank lines to be considered as one. For example,
@@ -78,10 +78,11 @@ struct Args {
     number_separator: String,
 
     /// Specify the initial value used to number logical page lines.
-    #[arg(short = 'v', long, default_value_t = 1)]
+    #[arg(short = 'v', long, default_value_t = 1, allow_negative_numbers = true)]
     starting_line_number: i64,
 
     /// Specify the number of characters to be used for the line number.
+    /// Line numbers that don't fit are printed in full, wider than this.
     #[arg(short = 'w', long, default_value_t = 6, value_parser = clap::value_parser!(i64).range(1..))]
     number_width: i64,
 
@@ -196,8 +197,9 @@ fn nl_main(args: &Args) -> io::Result<()> {
                     args.number_separator,
                     width = args.number_width as usize
                 ),
+                // the sign of negative numbers comes before the leading zeros
                 NumberFormat::Rz => print!(
-                    "{:0>width$}{}",
+                    "{:0width$}{}",
                     line_number,
                     args.number_separator,
                     width = args.number_width as usize
@@ -292,7 +292,7 @@ fn paste_files_serial(
     mut paste_info: PasteInfo,
     mut delimiter_state: DelimiterState,
 ) -> Result<(), Box<dyn Error>> {
-    let mut stdout_lock = io::stdout().lock();
+    let mut stdout_lock = plib::io::stdout();
 
     // Re-use buffers to avoid repeated allocations
     let mut buffer = Vec::new();
@@ -349,6 +349,8 @@ fn paste_files_serial(
         delimiter_state.reset();
     }
 
+    stdout_lock.finish()?;
+
     Ok(())
 }
 
@@ -361,6 +363,7 @@ fn paste_files(
     // Re-use buffers to avoid repeated allocations
     let mut buffer = Vec::new();
     let mut output = Vec::new();
+    let mut stdout = plib::io::stdout();
 
     loop {
         // Equivalent to allocating a new Vec here
@@ -410,11 +413,13 @@ fn paste_files(
         }
 
         // output all segments to stdout at once (one write per line)
-        io::stdout().write_all(output.as_slice())?;
+        stdout.write_all(output.as_slice())?;
 
         delimiter_state.reset();
     }
 
+    stdout.finish()?;
+
     Ok(())
 }
 
@@ -14,10 +14,11 @@ use std::fs;
 use std::io::{self, Read};
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::SystemTime;
 
-use chrono::{DateTime, Local};
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use plib::io::input_stream;
+use plib::timefmt;
 
 use self::pr_util::{line_transform, Args, PageIterator, Parameters};
 
@@ -196,8 +197,7 @@ fn write_line_content(
 
 /// Get the current date as a string formatted according to pr's spec.
 fn datetime_now() -> String {
-    let dt = Local::now().to_utc();
-    dt.format(DATE_TIME_FORMAT).to_string()
+    timefmt::format_utc(DATE_TIME_FORMAT, SystemTime::now())
 }
 
 /// Calculate the column width.
@@ -218,8 +218,7 @@ fn pr_serial(path: &PathBuf, params: &Parameters) -> io::Result<()> {
     } else {
         let metadata = fs::metadata(path)?;
         let last_modified_time = metadata.modified()?;
-        let dt: DateTime<Local> = last_modified_time.into();
-        dt.format(DATE_TIME_FORMAT).to_string()
+        timefmt::format_local(DATE_TIME_FORMAT, last_modified_time)
     };
 
     let stream = input_stream(path, true)?;
@@ -19,13 +19,14 @@ use std::{
     ffi::CString,
     fmt::{self, Debug},
     fs::File,
-    io::{BufRead, BufReader, Error, ErrorKind, Write},
+    io::{self, BufRead, BufReader, BufWriter, Error, ErrorKind, Write},
     mem::MaybeUninit,
     ops::Range,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 static ERE: Mutex<bool> = Mutex::new(false);
+static EXTENSIONS: Mutex<bool> = Mutex::new(false);
 
 #[derive(Parser, Debug, Clone)]
 #[command(version, about = gettext("sed - stream editor"))]
@@ -33,6 +34,9 @@ struct Args {
     #[arg(short = 'E', help=gettext("Match using extended regular expressions."))]
     ere: bool,
 
+    #[arg(long, help=gettext("Accept the address forms first~step, 0,/regex/ and addr1,+N, and the R and W commands."))]
+    extensions: bool,
+
     #[arg(short = 'n', help=gettext("Suppress the default output. Only lines explicitly selected for output are written."))]
     quiet: bool,
 
@@ -97,9 +101,7 @@ impl Args {
             } else {
                 // Neither [-e script] nor [-f script_file] is supplied and [file...] is not empty
                 // then consider first [file...] as single script.
-                for raw_script_line in self.file.remove(0).split('\n') {
-                    raw_script.push_str(raw_script_line);
-                }
+                raw_script = self.file.remove(0);
             }
         }
 
@@ -109,8 +111,10 @@ impl Args {
             self.file.push("-".to_string());
         }
 
+        *EXTENSIONS.lock().unwrap() = self.extensions;
         let script = Script::parse(raw_script)?;
         script.check_labels()?;
+        let wfiles = script.open_wfiles()?;
 
         Ok(Sed {
             ere: self.ere,
@@ -126,6 +130,10 @@ impl Args {
             is_last_line: false,
             current_end: None,
             next_line: String::new(),
+            appended: vec![],
+            missing_newline: false,
+            wfiles,
+            rfiles: HashMap::new(),
         })
     }
 }
@@ -166,6 +174,10 @@ enum AddressToken {
     /// Context related line number that
     /// calculated from this BRE match
     Pattern(regex_t, String),
+    /// Every step-th line starting with line first, as `first~step`
+    Step(usize, usize),
+    /// The line N lines after the start of the range, as `+N`
+    Relative(usize),
     /// Used for handling char related exceptions, when parsing [`AddressRange`]
     Delimiter,
 }
@@ -176,6 +188,8 @@ impl PartialEq for AddressToken {
             (AddressToken::Number(a), AddressToken::Number(b)) => a == b,
             (AddressToken::Last, AddressToken::Last) => true,
             (AddressToken::Pattern(..), AddressToken::Pattern(..)) => true,
+            (AddressToken::Step(a, b), AddressToken::Step(c, d)) => a == c && b == d,
+            (AddressToken::Relative(a), AddressToken::Relative(b)) => a == b,
             (AddressToken::Delimiter, AddressToken::Delimiter) => true,
             _ => false,
         }
@@ -195,6 +209,15 @@ impl Debug for AddressToken {
             AddressToken::Pattern(_, pattern) => f
                 .debug_struct(&format!("AddressToken::Pattern({pattern})"))
                 .finish(),
+            AddressToken::Step(first, step) => f
+                .debug_struct("AddressToken::Step")
+                .field("0", first)
+                .field("1", step)
+                .finish(),
+            AddressToken::Relative(n) => f
+                .debug_struct("AddressToken::Relative")
+                .field("0", n)
+                .finish(),
             AddressToken::Delimiter => f.debug_struct("AddressToken::Delimiter").finish(),
         }
     }
@@ -205,34 +228,36 @@ impl Debug for AddressToken {
 struct AddressRange {
     /// Address range limits
     limits: Vec<AddressToken>,
-    /// Defines what range limits is passed
-    /// in current processing file for current [`Command`]
-    passed: Option<(bool, bool)>,
-    /// Defines what range limits is currently raised
-    /// in current processing file for current [`Command`]
-    on_limits: Option<(bool, bool)>,
+    /// Line on which the range started, while
+    /// it is selecting lines for current [`Command`]
+    started_at: Option<usize>,
     /// Inverse fulfillment of [`AddressRange`] conditions
     is_negative: bool,
 }
 
 impl AddressRange {
     fn new(limits: Vec<AddressToken>, is_negative: bool) -> Result<Option<Self>, SedError> {
-        let state = match limits.len() {
+        match limits.len() {
             i if i > 2 => {
                 return Err(SedError::ScriptParse(
                     "address isn't empty, position or range".to_string(),
                     None,
                 ))
             }
-            2 => Some((false, false)),
             0 => return Ok(None),
-            _ => None,
+            _ => {}
+        }
+
+        // `0,/re/` is in the range before the first line is read
+        let started_at = if limits.len() == 2 && limits[0] == AddressToken::Number(0) {
+            Some(0)
+        } else {
+            None
         };
 
         Ok(Some(Self {
             limits,
-            passed: state,
-            on_limits: state,
+            started_at,
             is_negative,
         }))
     }
@@ -329,6 +354,8 @@ enum Command {
     Quit(Option<Address>), // q
     /// Copy the contents of rfile to standard output
     PrintFile(Option<Address>, PathBuf), // r
+    /// Copy the next line of rfile to standard output
+    PrintFileLine(Option<Address>, PathBuf), // R
     /// Substitute the replacement string for instances
     /// of the BRE in the pattern space
     Replace(Option<Address>, Regex, String, String, Vec<ReplaceFlag>), // s
@@ -339,6 +366,9 @@ enum Command {
     Test(Option<Address>, Option<String>), // t
     /// Append (write) the pattern space to wfile
     AppendPatternToFile(Option<Address>, PathBuf), // w
+    /// Append (write) the pattern space, up to the
+    /// first <newline>, to wfile
+    AppendFirstLineToFile(Option<Address>, PathBuf), // W
     /// Exchange the contents of the pattern and hold spaces
     ExchangeSpaces(Option<Address>), // x
     /// Replace all occurrences of characters in string1
@@ -374,9 +404,11 @@ impl Command {
             Command::PrintPattern(address, ..) => (address, 2),
             Command::Quit(address) => (address, 1),
             Command::PrintFile(address, ..) => (address, 1),
+            Command::PrintFileLine(address, ..) => (address, 2),
             Command::Replace(address, ..) => (address, 2),
             Command::Test(address, ..) => (address, 2),
             Command::AppendPatternToFile(address, ..) => (address, 2),
+            Command::AppendFirstLineToFile(address, ..) => (address, 2),
             Command::ExchangeSpaces(address) => (address, 2),
             Command::ReplaceCharSet(address, ..) => (address, 2),
             Command::PrintStandard(address) => (address, 1),
@@ -427,57 +459,74 @@ impl Command {
             return Ok(true);
         }
 
+        let line_number = line_number + 1;
         let mut need_execute = true;
         for range in address.as_mut().unwrap().0.iter_mut() {
-            if let Some(AddressToken::Pattern(..)) = range.limits.first() {
-                if let Some(AddressToken::Pattern(..)) = range.limits.get(1) {
-                    if range.passed == Some((true, true)) {
-                        range.passed = Some((false, false));
+            let first = &range.limits[0];
+            let mut result = match (range.limits.get(1), range.started_at) {
+                (None, _) => token_matches(first, line_number, line, last_line)?,
+                (Some(last), None) => {
+                    if token_matches(first, line_number, line, last_line)? {
+                        // A second address that can't match after the line
+                        // the range starts on ends it on that line
+                        let ends_now = match last {
+                            AddressToken::Number(n) => *n <= line_number,
+                            AddressToken::Relative(n) => *n == 0,
+                            AddressToken::Last => last_line,
+                            _ => false,
+                        };
+                        if !ends_now {
+                            range.started_at = Some(line_number);
+                        }
+                        true
+                    } else {
+                        false
                     }
                 }
-            }
-
-            let mut reached_now = vec![];
-            for (i, token) in range.limits.iter().enumerate() {
-                reached_now.push(match token {
-                    AddressToken::Number(position) => *position == line_number + 1,
-                    AddressToken::Pattern(re, pattern) => {
-                        !(match_pattern(*re, pattern.clone(), line, line_number + 1)?.is_empty())
-                    }
-                    AddressToken::Last => match i {
-                        0 => last_line,
-                        1 => false,
-                        _ => unreachable!(),
-                    },
-                    _ => unreachable!(),
-                });
-            }
-
-            match range.limits.len() {
-                1 => need_execute &= reached_now[0],
-                2 => {
-                    let (mut old_a, mut old_b) = range.passed.unwrap();
-                    if !old_a && old_b {
-                        range.passed = Some((false, false));
-                        (old_a, old_b) = range.passed.unwrap();
-                    }
-                    range.passed = Some((reached_now[0] || old_a, reached_now[1] || old_b));
-                    let (a, b) = range.passed.unwrap();
-                    range.on_limits = Some((reached_now[0], reached_now[1]));
-                    let mut result = (old_a && !old_b && reached_now[1]) || (a && !b);
-                    if range.is_negative {
-                        result = !result;
+                (Some(last), Some(started_at)) => {
+                    let ends_now = match last {
+                        AddressToken::Number(n) => *n <= line_number,
+                        AddressToken::Relative(n) => started_at + n <= line_number,
+                        _ => token_matches(last, line_number, line, last_line)?,
+                    };
+                    if ends_now {
+                        range.started_at = None;
                     }
-                    need_execute &= result;
+                    true
                 }
-                _ => unreachable!(),
+            };
+            if range.is_negative {
+                result = !result;
             }
+            need_execute &= result;
         }
 
         Ok(need_execute)
     }
 }
 
+/// Check if address bound `token` selects line `line_number` with
+/// contents `line`
+fn token_matches(
+    token: &AddressToken,
+    line_number: usize,
+    line: &str,
+    last_line: bool,
+) -> Result<bool, SedError> {
+    Ok(match token {
+        AddressToken::Number(position) => *position == line_number,
+        AddressToken::Pattern(re, pattern) => {
+            !(match_pattern(*re, pattern.clone(), line, line_number)?.is_empty())
+        }
+        AddressToken::Last => last_line,
+        AddressToken::Step(first, 0) => *first == line_number,
+        AddressToken::Step(first, step) => {
+            line_number >= *first && (line_number - first) % step == 0
+        }
+        AddressToken::Relative(_) | AddressToken::Delimiter => unreachable!(),
+    })
+}
+
 /// Function [`regexec`] can return from 1 to 9 range that can be nested.
 /// [`delete_nested_ranges`] function deletes ranges that contain another ranges.
 fn delete_nested_ranges(mut ranges: Vec<(usize, Range<usize>)>) -> Vec<(usize, Range<usize>)> {
@@ -723,14 +772,16 @@ fn get_pattern_token(
     Ok(pattern)
 }
 
-/// Parse [`Address`] BRE as [`AddressToken`]
+/// Parse [`Address`] BRE, as `/BRE/` or `\cBREc`, as [`AddressToken`]
 fn parse_pattern_token(
     chars: &[char],
     i: &mut usize,
     tokens: &mut Vec<AddressToken>,
 ) -> Result<(), SedError> {
     let position = get_current_line_and_col(chars, *i);
-    *i += 1;
+    if chars[*i] == '\\' {
+        *i += 1;
+    }
     let Some(ch) = chars.get(*i) else {
         return Err(SedError::ScriptParse(
             "unterminated address regex".to_string(),
@@ -782,8 +833,21 @@ fn parse_pattern_token(
     Ok(())
 }
 
+/// Parse the number after `~` or `+` in an [`AddressToken`]
+fn parse_extension_number(chars: &[char], i: &mut usize) -> Result<usize, SedError> {
+    let position = get_current_line_and_col(chars, *i);
+    *i += 1;
+    parse_number(chars, i)?.ok_or_else(|| {
+        SedError::ScriptParse(
+            format!("expected number after '{}'", chars[*i - 1]),
+            position,
+        )
+    })
+}
+
 /// Highlight future [`Address`] string and split it on [`AddressToken`]s
 fn to_address_tokens(chars: &[char], i: &mut usize) -> Result<Vec<AddressToken>, SedError> {
+    let extensions = *EXTENSIONS.lock().unwrap();
     let mut tokens = vec![];
     loop {
         let Some(ch) = chars.get(*i) else {
@@ -797,10 +861,20 @@ fn to_address_tokens(chars: &[char], i: &mut usize) -> Result<Vec<AddressToken>,
                 let Some(number) = parse_number(chars, i)? else {
                     unreachable!();
                 };
-                tokens.push(AddressToken::Number(number));
+                if extensions && chars.get(*i) == Some(&'~') {
+                    let step = parse_extension_number(chars, i)?;
+                    tokens.push(AddressToken::Step(number, step));
+                } else {
+                    tokens.push(AddressToken::Number(number));
+                }
                 continue;
             }
-            '\\' => parse_pattern_token(chars, i, &mut tokens)?,
+            '+' if extensions && tokens.last() == Some(&AddressToken::Delimiter) => {
+                let n = parse_extension_number(chars, i)?;
+                tokens.push(AddressToken::Relative(n));
+                continue;
+            }
+            '\\' | '/' => parse_pattern_token(chars, i, &mut tokens)?,
             '$' => tokens.push(AddressToken::Last),
             ',' => tokens.push(AddressToken::Delimiter),
             ' ' => {}
@@ -835,10 +909,14 @@ fn tokens_to_address(
         .filter(|token| !matches!(token, AddressToken::Delimiter))
         .collect::<Vec<_>>();
     if let Some(range) = AddressRange::new(tokens, is_negative)? {
+        // Only `0,/re/` can start before the first line
+        let zero_allowed = *EXTENSIONS.lock().unwrap()
+            && matches!(range.limits.get(1), Some(AddressToken::Pattern(..)));
         if range
             .limits
             .iter()
-            .any(|token| AddressToken::Number(0) == *token)
+            .enumerate()
+            .any(|(i, token)| AddressToken::Number(0) == *token && (i > 0 || !zero_allowed))
         {
             return Err(SedError::ScriptParse(
                 "address lower bound must be bigger than 0".to_string(),
@@ -998,7 +1076,14 @@ fn parse_word_attribute(chars: &[char], i: &mut usize) -> Result<Option<String>,
     Ok(if label.is_empty() { None } else { Some(label) })
 }
 
-/// Parse rfile attribute of r [`Command`]
+/// Whether `path` names standard output or standard error, which wfiles
+/// write to through the streams sed itself writes to
+fn is_standard_stream(path: &Path) -> bool {
+    path == Path::new("/dev/stdout") || path == Path::new("/dev/stderr")
+}
+
+/// Parse rfile/wfile attribute of r, R, w, W [`Command`]s and the w flag
+/// of s, which runs to the end of the line, ';' included
 fn parse_path_attribute(chars: &[char], i: &mut usize) -> Result<PathBuf, SedError> {
     *i += 1;
     let mut path = String::new();
@@ -1007,7 +1092,7 @@ fn parse_path_attribute(chars: &[char], i: &mut usize) -> Result<PathBuf, SedErr
             break;
         };
         match ch {
-            '\n' | ';' => {
+            '\n' => {
                 *i -= 1;
                 break;
             }
@@ -1027,6 +1112,9 @@ fn parse_path_attribute(chars: &[char], i: &mut usize) -> Result<PathBuf, SedErr
         ));
     }
     let file = PathBuf::from(path);
+    if is_standard_stream(&file) {
+        return Ok(file);
+    }
     if file.exists() {
         if file.is_file() {
             Ok(file)
@@ -1230,7 +1318,9 @@ fn parse_replace_flags(chars: &[char], i: &mut usize) -> Result<Vec<ReplaceFlag>
     if let Some(w_start_position) = w_start_position {
         *i = w_start_position;
         let path = parse_path_attribute(chars, i)?;
-        flags.push(ReplaceFlag::AppendToIfReplace(path));
+        if let Some(flag) = flags.last_mut() {
+            *flag = ReplaceFlag::AppendToIfReplace(path);
+        }
     }
 
     let is_replace_nth = |f| matches!(f, ReplaceFlag::ReplaceNth(_));
@@ -1430,7 +1520,11 @@ impl Script {
         let mut i = 0;
         let mut last_commands_count = 0;
         let mut command_added = false;
+        // Set by commands whose filename runs to the end of the line, which
+        // ends them
+        let mut line_ended = false;
         let chars = filter_comments(&raw_script).chars().collect::<Vec<_>>();
+        let extensions = *EXTENSIONS.lock().unwrap();
 
         if let Some(slice) = raw_script.as_ref().get(0..2) {
             if slice.get(0..1) == Some("#") && slice.get(1..2) == Some("n") {
@@ -1469,7 +1563,7 @@ impl Script {
                         position,
                     ));
                 }
-                ch if ch.is_ascii_digit() || "\\$".contains(ch) => {
+                ch if ch.is_ascii_digit() || "\\$/".contains(ch) => {
                     parse_address(&chars, &mut i, &mut address)?;
                     continue;
                 }
@@ -1531,13 +1625,22 @@ impl Script {
                 'q' => commands.push(Command::Quit(address.clone())),
                 'r' => {
                     let rfile = parse_path_attribute(&chars, &mut i)?;
-                    commands.push(Command::PrintFile(address.clone(), rfile))
+                    commands.push(Command::PrintFile(address.clone(), rfile));
+                    line_ended = true;
+                }
+                'R' if extensions => {
+                    let rfile = parse_path_attribute(&chars, &mut i)?;
+                    commands.push(Command::PrintFileLine(address.clone(), rfile));
+                    line_ended = true;
                 }
                 's' => {
                     let (pattern, replacement) =
                         parse_replace_command(&chars, &mut i, "s".to_string())?;
                     let re = compile_regex(pattern.clone())?;
                     let flags = parse_replace_flags(&chars, &mut i)?;
+                    line_ended = flags
+                        .iter()
+                        .any(|flag| matches!(flag, ReplaceFlag::AppendToIfReplace(_)));
                     commands.push(Command::Replace(
                         address.clone(),
                         Regex(re),
@@ -1559,6 +1662,12 @@ impl Script {
                         Err(SedError::ScriptParse(msg, _)) if msg.starts_with("missing") => {}
                         Err(err) => return Err(err),
                     };
+                    line_ended = true;
+                }
+                'W' if extensions => {
+                    let wfile = parse_path_attribute(&chars, &mut i)?;
+                    commands.push(Command::AppendFirstLineToFile(address.clone(), wfile));
+                    line_ended = true;
                 }
                 'x' => commands.push(Command::ExchangeSpaces(address.clone())),
                 'y' => {
@@ -1599,6 +1708,11 @@ impl Script {
                 last_commands_count = commands.len();
                 command_added = true;
             }
+            if line_ended {
+                line_ended = false;
+                address = None;
+                command_added = false;
+            }
             i += 1;
         }
 
@@ -1651,6 +1765,46 @@ impl Script {
         }
         Ok(())
     }
+
+    /// Create or truncate every wfile of w and W [`Command`]s and
+    /// of [`Command::Replace`] w flags, each of them once
+    fn open_wfiles(&self) -> Result<HashMap<PathBuf, Box<dyn Write>>, SedError> {
+        let mut wfiles: HashMap<PathBuf, Box<dyn Write>> = HashMap::new();
+        let paths = self.0.iter().filter_map(|cmd| match cmd {
+            Command::AppendPatternToFile(_, wfile) | Command::AppendFirstLineToFile(_, wfile) => {
+                Some(wfile)
+            }
+            Command::Replace(.., flags) => flags.iter().find_map(|flag| match flag {
+                ReplaceFlag::AppendToIfReplace(wfile) => Some(wfile),
+                _ => None,
+            }),
+            _ => None,
+        });
+        for wfile in paths {
+            if wfiles.contains_key(wfile) {
+                continue;
+            }
+            let file: Box<dyn Write> = if wfile == Path::new("/dev/stdout") {
+                Box::new(io::stdout())
+            } else if wfile == Path::new("/dev/stderr") {
+                Box::new(io::stderr())
+            } else {
+                let file = File::create(wfile).map_err(|err| {
+                    SedError::Io(Error::new(
+                        err.kind(),
+                        format!(
+                            "can't open '{}': {}",
+                            wfile.display(),
+                            err.to_string().to_lowercase()
+                        ),
+                    ))
+                })?;
+                Box::new(BufWriter::new(file))
+            };
+            wfiles.insert(wfile.clone(), file);
+        }
+        Ok(wfiles)
+    }
 }
 
 /// Replace every [`Command::Block`] with inner [`Command`]
@@ -1814,27 +1968,19 @@ fn execute_replace(
         println!("{}", *pattern_space);
     }
 
-    if let Some(wfile) = flags.iter().find_map(|flag| {
-        let ReplaceFlag::AppendToIfReplace(wfile) = flag else {
-            return None;
-        };
-        Some(wfile)
-    }) {
-        if replace && wfile.components().next().is_some() {
-            if let Ok(mut file) = std::fs::OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(wfile)
-                .map_err(SedError::Io)
-            {
-                let _ = file.write(pattern_space.as_bytes());
-            }
-        }
-    }
-
     Ok(replace)
 }
 
+/// Output of a, r and R [`Command`]s, which is written at the end of
+/// the cycle or when the next line of input is read, whichever is first
+#[derive(Debug)]
+enum AppendedOutput {
+    /// Text of a, or a line of R, written as is
+    Text(String),
+    /// Path of r, whose contents are copied if it can be read
+    File(PathBuf),
+}
+
 /// Set of states that are returned from [`Sed::execute`]
 /// for controling [`Sed`] [`Script`] execution loop for
 /// current input file
@@ -1893,6 +2039,15 @@ struct Sed {
     is_last_line: bool,
     /// Contains chars '\n\r' line end of current line for processed file
     current_end: Option<String>,
+    /// Output of a, r and R [`Command`]s executed in the current cycle
+    appended: Vec<AppendedOutput>,
+    /// [`true`] if the pattern space was last written without its
+    /// <newline>, which is written before any appended output
+    missing_newline: bool,
+    /// wfiles of the [`Script`], opened before any input is read
+    wfiles: HashMap<PathBuf, Box<dyn Write>>,
+    /// rfiles of R [`Command`]s, opened when first read from
+    rfiles: HashMap<PathBuf, Box<dyn BufRead>>,
 }
 
 impl Sed {
@@ -1913,8 +2068,7 @@ impl Sed {
                 if !self.need_execute(command_position)? {
                     return Ok(None);
                 }
-                self.pattern_space += &("\n".to_string() + &text);
-                self.current_end = Some("\n".to_string());
+                self.appended.push(AppendedOutput::Text(text + "\n"));
             }
             Command::BranchToLabel(_, label) => {
                 // b
@@ -2019,7 +2173,14 @@ impl Sed {
                 if !self.need_execute(command_position)? {
                     return Ok(None);
                 }
-                self.execute_r(rfile);
+                self.appended.push(AppendedOutput::File(rfile));
+            }
+            Command::PrintFileLine(_, rfile) => {
+                // R
+                if !self.need_execute(command_position)? {
+                    return Ok(None);
+                }
+                self.execute_upper_r(rfile)?;
             }
             Command::Replace(address, ref regex, pattern, replacement, flags) => {
                 // s
@@ -2040,10 +2201,20 @@ impl Sed {
             }
             Command::AppendPatternToFile(_, wfile) => {
                 // w
-                if !self.need_execute(command_position)? || wfile.components().next().is_none() {
+                if !self.need_execute(command_position)? {
+                    return Ok(None);
+                }
+                let line = self.pattern_space.clone();
+                self.write_to_wfile(&wfile, &line)?;
+            }
+            Command::AppendFirstLineToFile(_, wfile) => {
+                // W
+                if !self.need_execute(command_position)? {
                     return Ok(None);
                 }
-                self.execute_w(wfile)?;
+                let line = self.pattern_space.split('\n').next().unwrap_or_default();
+                let line = line.to_string();
+                self.write_to_wfile(&wfile, &line)?;
             }
             Command::ExchangeSpaces(_) => {
                 // x
@@ -2169,22 +2340,21 @@ impl Sed {
         }
     }
 
-    fn execute_r(&mut self, rfile: PathBuf) {
-        if let Ok(file) = File::open(rfile) {
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                let Ok(line) = line else {
-                    break;
-                };
-                self.pattern_space += "\n";
-                self.pattern_space += &line;
-            }
-            if self.current_end.is_none() {
-                self.current_end = Some("\n".to_string());
-            }
-        } else {
-            self.current_end = Some("\n".to_string());
+    fn execute_upper_r(&mut self, rfile: PathBuf) -> Result<(), SedError> {
+        // An rfile that can't be read is treated as an empty file
+        let reader = self
+            .rfiles
+            .entry(rfile)
+            .or_insert_with_key(|rfile| match File::open(rfile) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(_) => Box::new(io::empty()),
+            });
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(SedError::Io)?;
+        if !line.is_empty() {
+            self.appended.push(AppendedOutput::Text(line));
         }
+        Ok(())
     }
 
     fn execute_s(
@@ -2203,33 +2373,58 @@ impl Sed {
                 return Err(SedError::NoRegex);
             }
         }
+        let wfile = flags.iter().find_map(|flag| match flag {
+            ReplaceFlag::AppendToIfReplace(wfile) => Some(wfile.clone()),
+            _ => None,
+        });
         self.has_replacements_since_t = execute_replace(
             &mut self.pattern_space,
             Command::Replace(address, regex.clone(), pattern, replacement, flags),
             self.current_line,
         )?;
         self.last_regex = Some(regex.clone());
+        if let Some(wfile) = wfile {
+            if self.has_replacements_since_t {
+                let line = self.pattern_space.clone();
+                self.write_to_wfile(&wfile, &line)?;
+            }
+        }
         Ok(())
     }
 
-    fn execute_w(&mut self, wfile: PathBuf) -> Result<(), SedError> {
-        let _ = match std::fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(wfile.clone())
-        {
-            Ok(mut file) => file.write(self.pattern_space.as_bytes()),
-            Err(err) => {
-                return Err(SedError::Io(Error::new(
-                    ErrorKind::NotFound,
-                    format!(
-                        "can't find '{}': {}",
-                        wfile.display(),
-                        err.to_string().to_lowercase()
-                    ),
-                )));
-            }
+    /// Write `line` and a <newline> to `wfile`, which was opened
+    /// with the others when [`Sed`] was created. It is flushed, so
+    /// that the line can be read back with r or R right away
+    fn write_to_wfile(&mut self, wfile: &Path, line: &str) -> Result<(), SedError> {
+        let Some(file) = self.wfiles.get_mut(wfile) else {
+            unreachable!();
         };
+        writeln!(file, "{line}")
+            .and_then(|_| file.flush())
+            .map_err(SedError::Io)
+    }
+
+    /// Write the output of a, r and R [`Command`]s of the current cycle
+    fn flush_appended(&mut self) -> Result<(), SedError> {
+        if self.appended.is_empty() {
+            return Ok(());
+        }
+        let mut stdout = io::stdout().lock();
+        if self.missing_newline {
+            writeln!(stdout).map_err(SedError::Io)?;
+            self.missing_newline = false;
+        }
+        for output in self.appended.drain(..) {
+            match output {
+                AppendedOutput::Text(text) => stdout.write_all(text.as_bytes()),
+                AppendedOutput::File(rfile) => match std::fs::read(rfile) {
+                    Ok(contents) => stdout.write_all(&contents),
+                    // An rfile that can't be read is treated as an empty file
+                    Err(_) => Ok(()),
+                },
+            }
+            .map_err(SedError::Io)?;
+        }
         Ok(())
     }
 
@@ -2349,6 +2544,7 @@ impl Sed {
                         if line.is_empty() {
                             return Ok(None);
                         }
+                        self.flush_appended()?;
                         if let Some(l) = line.strip_suffix("\n") {
                             self.current_end = Some("\n".to_string());
                             line = l.to_string();
@@ -2368,7 +2564,10 @@ impl Sed {
                         if !self.quiet {
                             print!("{}", self.pattern_space);
                             print!("{}", self.current_end.clone().unwrap_or_default());
+                            self.missing_newline =
+                                !self.pattern_space.is_empty() && self.current_end.is_none();
                         }
+                        self.flush_appended()?;
                         if let Some(l) = line.strip_suffix("\n") {
                             line = l.to_string();
                         }
@@ -2388,6 +2587,7 @@ impl Sed {
             if let Some(end) = &self.current_end {
                 print!("{end}");
             }
+            self.missing_newline = !self.pattern_space.is_empty() && self.current_end.is_none();
         }
 
         Ok(global_instruction)
@@ -2418,7 +2618,9 @@ impl Sed {
             }
             self.has_replacements_since_t = false;
             self.pattern_space = line;
-            if let Some(ControlFlowInstruction::Break) = self.process_line()? {
+            let instruction = self.process_line()?;
+            self.flush_appended()?;
+            if let Some(ControlFlowInstruction::Break) = instruction {
                 break;
             }
             self.current_line += 1;
@@ -2430,6 +2632,17 @@ impl Sed {
     /// Main [`Sed`] function. Executes all commands of
     /// own [`Script`] for all content of all input files
     fn sed(&mut self) -> Result<(), SedError> {
+        let result = self.process_input_sources();
+        // wfiles are complete even if processing stopped on an error
+        for file in self.wfiles.values_mut() {
+            file.flush().map_err(SedError::Io)?;
+        }
+        result
+    }
+
+    /// Executes all commands of own [`Script`]
+    /// for all content of all input files
+    fn process_input_sources(&mut self) -> Result<(), SedError> {
         *ERE.lock().unwrap() = self.ere;
         for mut input in self.input_sources.drain(..).collect::<Vec<_>>() {
             self.current_file = Some(if input == "-" {
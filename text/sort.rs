@@ -9,7 +9,7 @@
 
 use std::cmp::Ordering;
 
-use std::io::{ErrorKind, Read};
+use std::io::ErrorKind;
 use std::{
     fs::File,
     io::{self, BufRead, BufWriter, Error, Write},
@@ -18,6 +18,7 @@ use std::{
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::{MappedReader, RecordReader};
 
 /// Sort, merge, or sequence check text files
 #[derive(Parser)]
@@ -43,6 +44,10 @@ struct Args {
     #[arg(short = 'u')]
     unique: bool,
 
+    /// Stable: keep lines having equal keys in their input order, instead of comparing them whole
+    #[arg(short = 's')]
+    stable: bool,
+
     /// Specify that only <blank> characters and alphanumeric characters, according to the current setting of LC_CTYPE, shall be significant in comparisons. The behavior is undefined for a sort key to which -i or -n also applies.
     #[arg(short = 'd')]
     dictionary_order: bool,
@@ -106,180 +111,357 @@ impl Args {
             return Err("Options '-n' and '-i' cannot be used together".to_string());
         }
 
-        // Check if conflicting options are used together
-        if self.ignore_leading_blanks && self.key_definition.is_empty() {
-            return Err("Options '-b' can be used together with '-k' ".to_string());
-        }
-        // Check if conflicting options are used together
-        if self.field_separator.is_some() && self.key_definition.is_empty() {
-            return Err("Options '-t' can be used together with '-k' ".to_string());
-        }
-
         Ok(())
     }
 }
 
-/// A struct representing a range field with various sorting and comparison options.
-#[derive(Clone, Default)]
-struct RangeField {
-    /// The number of the field to be considered in the range.
-    field_number: usize,
+/// Ordering options, applying either to a single sort key or, when given
+/// independently of any key, to all keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct OrderingOptions {
+    /// Only blanks and alphanumeric characters are significant.
+    dictionary_order: bool,
+
+    /// Lowercase characters compare as their uppercase equivalents.
+    fold_case: bool,
 
-    /// The position of the first character in the field to start comparison.
-    first_character: usize,
+    /// Non-printable characters are ignored.
+    ignore_nonprintable: bool,
 
-    /// A boolean flag to indicate if the field should be sorted numerically.
+    /// The key is compared as an initial numeric string.
     numeric_sort: bool,
 
-    /// A boolean flag to indicate if leading blanks should be ignored during comparison.
-    ignore_leading_blanks: bool,
-
-    /// A boolean flag to indicate if the sort order should be reversed.
+    /// The sense of the comparison is reversed.
     reverse: bool,
+}
 
-    /// A boolean flag to indicate if non-printable characters should be ignored during comparison.
-    ignore_nonprintable: bool,
+impl OrderingOptions {
+    /// The ordering options given independently of any key.
+    fn global(args: &Args) -> Self {
+        Self {
+            dictionary_order: args.dictionary_order,
+            fold_case: args.fold_case,
+            ignore_nonprintable: args.ignore_nonprintable,
+            numeric_sort: args.numeric_sort,
+            reverse: args.reverse,
+        }
+    }
+}
 
-    /// A boolean flag to indicate if the case should be folded (case-insensitive comparison).
-    fold_case: bool,
+/// One end of a sort key: a field and a character position in that field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct KeyPosition {
+    /// The zero-based number of the field.
+    field: usize,
 
-    /// A boolean flag to indicate if the field should be compared in dictionary order
-    /// (considers only alphanumeric characters and blanks).
-    dictionary_order: bool,
+    /// The zero-based position of the character in the field, or `None` for
+    /// the end of the field.
+    character: Option<usize>,
+
+    /// Whether leading blanks of the field are skipped before counting
+    /// characters.
+    ignore_leading_blanks: bool,
 }
 
-/// Updates two RangeField objects based on their comparison options.
-///
-/// This function takes two RangeField objects, compares their fields, and updates the fields
-/// according to the comparison options. If any of the comparison options (`dictionary_order`,
-/// `fold_case`, `ignore_nonprintable`, or `numeric_sort`) is true in either of the objects,
-/// it sets the same option to true in both objects.
-///
-/// # Arguments
-///
-/// * `field1` - The first RangeField object to compare and update.
-/// * `field2` - The second RangeField object to compare and update.
-///
-/// # Returns
-///
-/// A tuple containing the updated RangeField objects.
-///
-fn update_range_field(mut field1: RangeField, mut field2: RangeField) -> (RangeField, RangeField) {
-    if field1.dictionary_order || field2.dictionary_order {
-        field1.dictionary_order = true;
-        field2.dictionary_order = true;
-    }
+/// A sort key given with `-k field_start[type][,field_end[type]]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SortKey {
+    /// The position of the first character of the key.
+    start: KeyPosition,
 
-    if field1.fold_case || field2.fold_case {
-        field1.fold_case = true;
-        field2.fold_case = true;
-    }
+    /// The position of the last character of the key, or `None` when the key
+    /// extends to the end of the line.
+    end: Option<KeyPosition>,
+
+    /// The ordering options of the key.
+    options: OrderingOptions,
+}
 
-    if field1.ignore_nonprintable || field2.ignore_nonprintable {
-        field1.ignore_nonprintable = true;
-        field2.ignore_nonprintable = true;
+impl SortKey {
+    /// A key spanning the whole line, used when no `-k` option is given,
+    /// with the global ordering options and `-b`.
+    fn whole_line(args: &Args) -> Self {
+        Self {
+            start: KeyPosition {
+                field: 0,
+                character: Some(0),
+                ignore_leading_blanks: args.ignore_leading_blanks,
+            },
+            end: None,
+            options: OrderingOptions::global(args),
+        }
     }
 
-    if field1.numeric_sort || field2.numeric_sort {
-        field1.numeric_sort = true;
-        field2.numeric_sort = true;
+    /// Parses a key definition.
+    ///
+    /// Type modifiers (`b`, `d`, `f`, `i`, `n` and `r`) may follow either
+    /// position. `b` applies to the position it follows, the others to the
+    /// whole key. A key with modifiers ignores all the global ordering
+    /// options and `-b`, a key without any uses them all.
+    ///
+    /// # Arguments
+    ///
+    /// * `definition` - The option-argument of `-k`.
+    /// * `args` - The command line arguments holding the global options.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `SortKey`, or an error if the definition is invalid.
+    ///
+    fn parse(definition: &str, args: &Args) -> Result<Self, Box<dyn std::error::Error>> {
+        if definition.is_empty() {
+            return Err("key must be non-empty".into());
+        }
+
+        let mut options = OrderingOptions::default();
+        let mut has_modifiers = false;
+        let (start, end) = match definition.split_once(',') {
+            Some((start, end)) => (start, Some(end)),
+            None => (definition, None),
+        };
+
+        let (start_field, start_character, start_blanks) =
+            parse_key_position(start, &mut options, &mut has_modifiers)?;
+        if start_field == 0 {
+            return Err("the key can't be zero.".into());
+        }
+        if start_character == Some(0) {
+            return Err("the character position of the key start can't be zero.".into());
+        }
+        let mut start = KeyPosition {
+            field: start_field - 1,
+            character: Some(start_character.unwrap_or(1) - 1),
+            ignore_leading_blanks: start_blanks,
+        };
+
+        let mut end = match end {
+            Some(end) => {
+                let (end_field, end_character, end_blanks) =
+                    parse_key_position(end, &mut options, &mut has_modifiers)?;
+                if end_field == 0 {
+                    return Err("the key can't be zero.".into());
+                }
+                Some(KeyPosition {
+                    field: end_field - 1,
+                    // a character position of 0 is the end of the field
+                    character: end_character.filter(|c| *c != 0).map(|c| c - 1),
+                    ignore_leading_blanks: end_blanks,
+                })
+            }
+            None => None,
+        };
+
+        if !has_modifiers {
+            options = OrderingOptions::global(args);
+            start.ignore_leading_blanks = args.ignore_leading_blanks;
+            if let Some(end) = &mut end {
+                end.ignore_leading_blanks = args.ignore_leading_blanks;
+            }
+        }
+
+        if let Some(end) = &end {
+            let end_before_start = match end.field.cmp(&start.field) {
+                Ordering::Less => true,
+                Ordering::Equal => end.character.is_some_and(|c| Some(c) < start.character),
+                Ordering::Greater => false,
+            };
+            if end_before_start {
+                return Err("keys fields with end position before start!".into());
+            }
+        }
+
+        Ok(Self {
+            start,
+            end,
+            options,
+        })
+    }
+
+    /// Extracts the key from a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to extract the key from.
+    /// * `field_separator` - The character given with `-t`. When `None`, each
+    ///   field is a maximal sequence of non-blanks and the blanks before it.
+    ///
+    /// # Returns
+    ///
+    /// The part of the line holding the key, which is empty if the key lies
+    /// beyond the end of the line. As in other implementations, character
+    /// positions are not limited to their field, only to the line.
+    ///
+    fn extract<'a>(&self, line: &'a str, field_separator: Option<char>) -> &'a str {
+        let fields = field_bounds(line, field_separator);
+
+        let start = match fields.get(self.start.field) {
+            Some(&(field_start, _)) => {
+                let mut start = field_start;
+                if self.start.ignore_leading_blanks {
+                    start = skip_blanks(line, start);
+                }
+                advance_chars(line, start, self.start.character.unwrap_or(0))
+            }
+            None => line.len(),
+        };
+
+        let end = match &self.end {
+            Some(end) => match fields.get(end.field) {
+                Some(&(field_start, field_end)) => match end.character {
+                    Some(character) => {
+                        let mut position = field_start;
+                        if end.ignore_leading_blanks {
+                            position = skip_blanks(line, position);
+                        }
+                        advance_chars(line, position, character + 1)
+                    }
+                    None => field_end,
+                },
+                None => line.len(),
+            },
+            None => line.len(),
+        };
+
+        if end <= start {
+            ""
+        } else {
+            &line[start..end]
+        }
     }
 
-    (field1, field2)
+    /// Compares two lines by this key only.
+    fn compare(&self, line1: &str, line2: &str, field_separator: Option<char>) -> Ordering {
+        let key1 = self.extract(line1, field_separator);
+        let key2 = self.extract(line2, field_separator);
+
+        let result = if self.options.numeric_sort {
+            compare_numeric(key1, key2)
+        } else {
+            let mut key1 = key1.to_string();
+            let mut key2 = key2.to_string();
+            if self.options.dictionary_order {
+                key1 = dictionary_order_filter(&key1);
+                key2 = dictionary_order_filter(&key2);
+            }
+            if self.options.ignore_nonprintable {
+                key1 = ignore_nonprintable_filter(&key1);
+                key2 = ignore_nonprintable_filter(&key2);
+            }
+            if self.options.fold_case {
+                key1.to_uppercase().cmp(&key2.to_uppercase())
+            } else {
+                key1.cmp(&key2)
+            }
+        };
+
+        if self.options.reverse {
+            result.reverse()
+        } else {
+            result
+        }
+    }
 }
 
-/// Compares two RangeField objects based on their field numbers and first characters.
-///
-/// This function takes references to two RangeField objects and compares them based on their
-/// field numbers and first characters. If the field number of the first object is less than
-/// the field number of the second object, it returns true. If the field numbers are equal,
-/// it compares the first characters, returning true if the first character of the first object
-/// is less than or equal to the first character of the second object. Otherwise, it returns false.
-///
-/// # Arguments
+/// Parses one position of a key definition, `field[.character][modifiers]`.
 ///
-/// * `field1` - A reference to the first RangeField object to compare.
-/// * `field2` - A reference to the second RangeField object to compare.
+/// Ordering modifiers are added to `options` and `has_modifiers` is set if
+/// there are any.
 ///
 /// # Returns
 ///
-/// A boolean value indicating whether the first RangeField object is less than or equal to
-/// the second RangeField object based on their field numbers and first characters.
-///
-fn compare_range_fields(field1: &RangeField, field2: &RangeField) -> bool {
-    match field1.field_number.cmp(&field2.field_number) {
-        Ordering::Less => true,
-        Ordering::Equal => field1.first_character <= field2.first_character,
-        Ordering::Greater => false,
+/// The one-based field number, the one-based character position if given,
+/// and whether leading blanks are skipped for this position.
+///
+fn parse_key_position(
+    position: &str,
+    options: &mut OrderingOptions,
+    has_modifiers: &mut bool,
+) -> Result<(usize, Option<usize>, bool), Box<dyn std::error::Error>> {
+    let modifiers_start = position
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(position.len());
+    let (numbers, modifiers) = position.split_at(modifiers_start);
+
+    let (field, character) = match numbers.split_once('.') {
+        Some((field, character)) => (field, Some(character)),
+        None => (numbers, None),
+    };
+    let field = field.parse::<usize>()?;
+    let character = character.map(|c| c.parse::<usize>()).transpose()?;
+
+    let mut ignore_leading_blanks = false;
+    for modifier in modifiers.chars() {
+        match modifier {
+            'b' => ignore_leading_blanks = true,
+            'd' => options.dictionary_order = true,
+            'f' => options.fold_case = true,
+            'i' => options.ignore_nonprintable = true,
+            'n' => options.numeric_sort = true,
+            'r' => options.reverse = true,
+            other => return Err(format!("invalid key modifier '{other}'").into()),
+        }
+        *has_modifiers = true;
     }
+
+    Ok((field, character, ignore_leading_blanks))
 }
 
-/// Trims and concatenates substrings from a vector of strings based on a specified range.
-///
-/// This function takes a vector of string slices and a key range, then extracts and concatenates
-/// the substrings from the specified range. The range is defined by a tuple of `RangeField`
-/// structs, where the first element defines the start of the range, and the optional second element
-/// defines the end of the range.
-///
-/// # Arguments
-///
-/// * `line` - A vector of string slices representing the fields to be processed.
-/// * `key_range` - A tuple containing two elements:
-///     * The first `RangeField` specifies the starting field and character position.
-///     * An optional `RangeField` that specifies the ending field and character position.
+/// Splits a line into fields.
 ///
 /// # Returns
 ///
-/// A `String` that contains the concatenated result of the specified range of substrings.
-///
-fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField>)) -> String {
-    let mut result = String::new();
-
-    let start_field = key_range.0.field_number;
-    let start_char = key_range.0.first_character;
-
-    let end_field = match &key_range.1 {
-        Some(range) => range.field_number,
-        None => line.len() - 1,
-    };
-    let end_char = key_range.1.as_ref().map(|range| range.first_character);
-
-    for (i, field) in line.iter().skip(start_field).enumerate() {
-        let i = i + start_field;
-        if i >= start_field && i <= end_field {
-            let start = if i == start_field {
-                if key_range.0.ignore_leading_blanks {
-                    start_char + (field.len() - field.trim_start().len())
-                } else {
-                    start_char
+/// The byte offsets of the start and end of each field. Without a field
+/// separator the leading blanks of a field are part of it, with one the
+/// separators are not part of any field.
+///
+fn field_bounds(line: &str, field_separator: Option<char>) -> Vec<(usize, usize)> {
+    let mut fields = vec![];
+    match field_separator {
+        Some(separator) => {
+            let mut start = 0;
+            for (i, c) in line.char_indices() {
+                if c == separator {
+                    fields.push((start, i));
+                    start = i + c.len_utf8();
                 }
-            } else {
-                0
-            };
-            let mut end = if i == end_field {
-                if let Some(char) = end_char {
-                    if char == usize::MAX - 1 {
-                        field.len() - 1
-                    } else if key_range.clone().1.unwrap().ignore_leading_blanks {
-                        char + (field.len() - field.trim_start().len())
-                    } else {
-                        char
-                    }
-                } else {
-                    field.len() - 1
+            }
+            fields.push((start, line.len()));
+        }
+        None => {
+            let mut start = 0;
+            loop {
+                let end = skip_blanks(line, start);
+                let end = line[end..]
+                    .find(is_blank)
+                    .map_or(line.len(), |offset| end + offset);
+                fields.push((start, end));
+                if end == line.len() {
+                    break;
                 }
-            } else {
-                field.len() - 1
-            };
-            if end >= field.len() {
-                end = field.len() - 1;
+                start = end;
             }
-
-            result.push_str(&field[start..=end]);
         }
     }
+    fields
+}
+
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Returns the offset of the first non-blank at or after `start`.
+fn skip_blanks(line: &str, start: usize) -> usize {
+    line[start..]
+        .find(|c| !is_blank(c))
+        .map_or(line.len(), |offset| start + offset)
+}
 
-    result
+/// Returns the offset `count` characters after `start`.
+fn advance_chars(line: &str, start: usize, count: usize) -> usize {
+    line[start..]
+        .char_indices()
+        .nth(count)
+        .map_or(line.len(), |(offset, _)| start + offset)
 }
 
 /// Extracts a number from a string, ignoring other characters.
@@ -385,283 +567,50 @@ fn ignore_nonprintable_filter(line: &str) -> String {
         .collect()
 }
 
-/// Generates a `RangeField` struct based on the specified key range and arguments.
-///
-/// This function constructs a `RangeField` struct based on the provided key range string
-/// and the arguments specified in the `Args` struct. It parses the key range string to
-/// determine various sorting and comparison options.
-///
-/// # Arguments
-///
-/// * `key_range` - A string slice (`&str`) representing the key range configuration.
-/// * `args` - A reference to an `Args` struct containing additional configuration options.
-/// * `first` - A boolean indicating whether this is the first part of a range.
-///
-/// # Returns
-///
-/// A `Result` containing the constructed `RangeField` if successful, or a `Box` containing
-/// a dynamic `Error` trait object if parsing or construction fails.
-///
-fn generate_range(
-    key_range: &str,
-    args: &Args,
-    first: bool,
-) -> Result<RangeField, Box<dyn std::error::Error>> {
-    let mut numeric_sort = args.numeric_sort;
-    let mut ignore_leading_blanks = args.ignore_leading_blanks;
-    let mut reverse = args.reverse;
-    let mut ignore_nonprintable = args.ignore_nonprintable;
-    let mut fold_case = args.fold_case;
-    let mut dictionary_order = args.dictionary_order;
-
-    if key_range.contains('n')
-        || key_range.contains('b')
-        || key_range.contains('r')
-        || key_range.contains('i')
-        || key_range.contains('f')
-        || key_range.contains('d')
-    {
-        numeric_sort = false;
-        ignore_leading_blanks = false;
-        ignore_nonprintable = false;
-        fold_case = false;
-        dictionary_order = false;
-    }
-
-    let mut key_range = key_range.to_string();
-    if key_range.contains('n') {
-        key_range = key_range.replace('n', "");
-        numeric_sort = true;
-    }
-    if key_range.contains('b') {
-        key_range = key_range.replace('b', "");
-        ignore_leading_blanks = true;
-    }
-    if key_range.contains('r') {
-        key_range = key_range.replace('r', "");
-        reverse = true;
-    }
-    if key_range.contains('i') {
-        key_range = key_range.replace('i', "");
-        ignore_nonprintable = true;
-    }
-    if key_range.contains('f') {
-        key_range = key_range.replace('f', "");
-        fold_case = true;
-    }
-    if key_range.contains('d') {
-        key_range = key_range.replace('d', "");
-        dictionary_order = true;
-    }
-    let mut parts = key_range.split('.');
-    let start_1: usize = parts
-        .next()
-        .unwrap()
-        .parse()
-        .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?;
-
-    let start_2 = parts.next();
-    let mut start_2: usize = match first {
-        true => start_2
-            .unwrap_or("1")
-            .parse()
-            .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?,
-        false => start_2
-            .unwrap_or(&usize::MAX.to_string())
-            .parse()
-            .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?,
-    };
-
-    if !first && start_2 == 0 {
-        start_2 = usize::MAX;
-    }
-
-    Ok(RangeField {
-        field_number: start_1 - 1,
-        first_character: start_2 - 1,
-        numeric_sort,
-        ignore_leading_blanks,
-        reverse,
-        ignore_nonprintable,
-        fold_case,
-        dictionary_order,
-    })
-}
-
-/// Cuts a line into fields based on the provided RangeField object(s) and field separator.
-///
-/// This function takes a line of text, a RangeField object or a tuple of two RangeField objects
-/// representing the key range(s), and an optional field separator character. It then splits the line
-/// into fields according to the provided separator or space character, and returns a string containing
-/// the selected fields based on the key range(s).
-///
-/// If a field separator character is provided, the line is split using that separator.
-/// If no separator is provided, the line is split using space (' ') as the separator.
-///
-/// # Arguments
-///
-/// * `line` - A reference to the input line of text to be cut into fields.
-/// * `key_range` - A tuple containing one or two RangeField objects representing the key range(s).
-///   The first RangeField object represents the primary key range, and the second one represents
-///   the secondary key range. If only one key range is provided, the second element of the tuple
-///   should be None.
-/// * `field_separator` - An optional character used to separate fields in the line. If None, space
-///   (' ') is used as the default separator.
-///
-/// # Returns
-///
-/// A string containing the selected fields based on the key range(s).
-///
-fn cut_line(
-    line: &str,
-    key_range: &(RangeField, Option<RangeField>),
-    field_separator: Option<char>,
-) -> String {
-    if let Some(separator) = field_separator {
-        let split = line.split(separator);
-        cut_line_by_range(split.collect(), key_range)
-    } else {
-        let split: Vec<&str> = line.split(' ').collect();
-
-        cut_line_by_range(
-            merge_empty_lines(split)
-                .iter()
-                .map(|s| s.as_str())
-                .collect(),
-            key_range,
-        )
-    }
-}
-
-/// Compares two strings based on a specified key range and optional field separator.
-///
-/// This function compares two strings (`line1` and `line2`) based on a specified key range,
-/// taking into account optional field separation if provided. It applies sorting and comparison
-/// rules defined in the `RangeField` struct associated with the key range.
+/// Compares two lines by the sort keys only.
 ///
 /// # Arguments
 ///
-/// * `line1` - A string slice (`&str`) representing the first input string to be compared.
-/// * `line2` - A string slice (`&str`) representing the second input string to be compared.
-/// * `key_range` - A tuple containing two elements:
-///     * The first `RangeField` specifies the key range configuration for both strings.
-///     * An optional `RangeField` specifies the end of the key range if different from the start.
-/// * `field_separator` - An optional character specifying the field separator for splitting
-///   strings into fields before comparison. If `None`, whitespace is used as the separator.
-///
-/// # Returns
-///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`) indicating
-/// the result of the comparison:
-/// * `Ordering::Less` if `line1` is less than `line2` according to the specified key range.
-/// * `Ordering::Greater` if `line1` is greater than `line2` according to the specified key range.
-/// * `Ordering::Equal` if `line1` and `line2` are equal within the specified key range.
+/// * `line1` - The first line to compare.
+/// * `line2` - The second line to compare.
+/// * `keys` - The sort keys, in order of precedence.
+/// * `args` - The command line arguments.
 ///
-fn compare_key(
-    line1: &str,
-    line2: &str,
-    key_range: &(RangeField, Option<RangeField>),
-    field_separator: Option<char>,
-) -> Ordering {
-    let mut line1 = cut_line(line1, key_range, field_separator);
-    let mut line2 = cut_line(line2, key_range, field_separator);
-
-    // Compare keys
-    if key_range.0.numeric_sort {
-        // If the keys are represented by numbers, compare them as numbers
-        let mut result = compare_numeric(&line1, &line2);
-        if key_range.0.reverse {
-            match result {
-                Ordering::Less => result = Ordering::Greater,
-                Ordering::Greater => result = Ordering::Less,
-                _ => (),
-            }
-        }
-        return result;
-    } else if key_range.0.dictionary_order {
-        line1 = dictionary_order_filter(&line1);
-        line2 = dictionary_order_filter(&line2);
-    } else if key_range.0.ignore_nonprintable {
-        line1 = ignore_nonprintable_filter(&line1);
-        line2 = ignore_nonprintable_filter(&line2);
-    }
-
-    let result;
-
-    if key_range.0.fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
-        if cmp == std::cmp::Ordering::Equal {
-            result = line1.cmp(&line2);
-        } else {
-            result = cmp;
-        }
-    } else {
-        result = line1.cmp(&line2);
-    }
-    if key_range.0.reverse {
-        match result {
-            Ordering::Less => Ordering::Greater,
-            Ordering::Greater => Ordering::Less,
-            _ => Ordering::Equal,
+fn compare_keys(line1: &str, line2: &str, keys: &[SortKey], args: &Args) -> Ordering {
+    for key in keys {
+        let ordering = key.compare(line1, line2, args.field_separator);
+        if ordering != Ordering::Equal {
+            return ordering;
         }
-    } else {
-        result
     }
+    Ordering::Equal
 }
 
-/// Compares two lines of text based on specified sorting and comparison options.
+/// Compares two lines by the sort keys, then, as a last resort, by all
+/// their bytes.
 ///
-/// This function compares two lines of text (`line1` and `line2`) based on specified
-/// sorting and comparison options. It supports options for numeric sorting, dictionary
-/// ordering, case folding, and ignoring non-printable characters.
+/// There is no last resort with `-u`, where lines with equal keys are
+/// duplicates, or with `-s`, where they are kept in input order.
 ///
 /// # Arguments
 ///
-/// * `line1` - A string slice (`&str`) representing the first line of text to be compared.
-/// * `line2` - A string slice (`&str`) representing the second line of text to be compared.
-/// * `dictionary_order` - A boolean indicating whether to use dictionary ordering.
-/// * `fold_case` - A boolean indicating whether to fold case during comparison.
-/// * `ignore_nonprintable` - A boolean indicating whether to ignore non-printable characters.
-/// * `numeric_sort` - A boolean indicating whether to perform numeric sorting.
-///
-/// # Returns
-///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`)
-/// indicating the result of the comparison:
-/// * `Ordering::Less` if `line1` is less than `line2` according to the specified options.
-/// * `Ordering::Greater` if `line1` is greater than `line2` according to the specified options.
-/// * `Ordering::Equal` if `line1` and `line2` are equal within the specified options.
+/// * `line1` - The first line to compare.
+/// * `line2` - The second line to compare.
+/// * `keys` - The sort keys, in order of precedence.
+/// * `args` - The command line arguments.
 ///
-fn compare_lines(
-    line1: &str,
-    line2: &str,
-    dictionary_order: bool,
-    fold_case: bool,
-    ignore_nonprintable: bool,
-    numeric_sort: bool,
-) -> Ordering {
-    let mut line1 = line1.to_string();
-    let mut line2 = line2.to_string();
-
-    if numeric_sort {
-        return compare_numeric(&line1, &line2);
-    } else if dictionary_order {
-        line1 = dictionary_order_filter(&line1);
-        line2 = dictionary_order_filter(&line2);
-    } else if ignore_nonprintable {
-        line1 = ignore_nonprintable_filter(&line1);
-        line2 = ignore_nonprintable_filter(&line2);
+fn compare_lines(line1: &str, line2: &str, keys: &[SortKey], args: &Args) -> Ordering {
+    let ordering = compare_keys(line1, line2, keys, args);
+    if ordering != Ordering::Equal || args.unique || args.stable {
+        return ordering;
     }
-
-    if fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
-        if cmp == std::cmp::Ordering::Equal {
-            line1.cmp(&line2)
-        } else {
-            cmp
-        }
+    // lines with equal keys are ordered as if no ordering option was given,
+    // except for -r
+    let ordering = line1.cmp(line2);
+    if args.reverse {
+        ordering.reverse()
     } else {
-        line1.cmp(&line2)
+        ordering
     }
 }
 
@@ -702,66 +651,6 @@ fn find_first_difference(lines_1: &[String], lines_2: &[String]) -> Option<(usiz
     None
 }
 
-/// Creates range fields based on the specified key range and arguments.
-///
-/// This function takes a key range string (`key_range`) and a reference to an `Args` struct (`args`)
-/// containing additional configuration options. It splits the key range string into individual
-/// range components separated by commas and generates corresponding `RangeField` structs for each
-/// component using the `generate_range` function.
-///
-/// # Arguments
-///
-/// * `key_range` - A string slice (`&str`) representing the key range configuration.
-/// * `args` - A reference to an `Args` struct containing additional configuration options.
-///
-/// # Returns
-///
-/// A `Result` containing a tuple `(RangeField, Option<RangeField>)` if successful, or a `Box`
-/// containing a dynamic `Error` trait object if parsing or construction fails.
-///
-fn create_ranges(
-    key_range: &str,
-    args: &Args,
-) -> Result<(RangeField, Option<RangeField>), Box<dyn std::error::Error>> {
-    // Split the key range with commas
-    let key_ranges: Vec<&str> = key_range.split(',').collect();
-    let mut key_ranges = key_ranges.iter();
-
-    // Convert key ranges to numeric representations
-    let mut ranges: (RangeField, Option<RangeField>) = (RangeField::default(), None);
-
-    ranges.0 = {
-        let key_range = key_ranges.next().unwrap().to_string();
-        if key_range == "0" {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "the key can't be zero.",
-            )));
-        }
-        generate_range(&key_range, args, true)?
-    };
-    ranges.1 = {
-        if let Some(key_range) = key_ranges.next() {
-            Some(generate_range(key_range, args, false)?)
-        } else {
-            None
-        }
-    };
-    if let Some(range_2) = ranges.1 {
-        let (range_1, range_2) = update_range_field(ranges.0, range_2);
-        if !compare_range_fields(&range_1, &range_2) {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "keys fields with end position before start!",
-            )));
-        }
-
-        ranges = (range_1, Some(range_2));
-    }
-
-    Ok(ranges)
-}
-
 /// Sorts strings based on specified sorting criteria and writes the result to the output.
 ///
 /// This function reads lines from the provided input reader and sorts them based on the
@@ -784,62 +673,26 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
     let mut result_lines = lines.clone();
     let mut duplicates = vec![];
 
-    if !args.key_definition.is_empty() {
-        let key_range = &args.key_definition[0];
-
-        if key_range.is_empty() {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "key must be non-empty",
-            )));
-        }
-
-        let ranges = create_ranges(key_range, args)?;
-        let ranges_2 = match args.key_definition.get(1) {
-            Some(key_range_2) => Some(create_ranges(key_range_2, args)?),
-            None => None,
-        };
-
-        // Sort strings by keys
-        result_lines.sort_by(|a, b| {
-            let mut ordering = compare_key(a, b, &ranges, args.field_separator);
-            if let Ordering::Equal = ordering {
-                if let Some(ranges_2) = &ranges_2 {
-                    let ordering_2 = compare_key(a, b, ranges_2, args.field_separator);
-                    if let Ordering::Equal = ordering_2 {
-                        duplicates.push(a.to_string());
-                    }
-                    ordering = ordering_2
-                }
-            }
-            ordering
-        });
-        if args.unique {
-            result_lines.retain(|line| !duplicates.contains(line));
-        }
+    let keys = if args.key_definition.is_empty() {
+        vec![SortKey::whole_line(args)]
     } else {
-        result_lines.sort_by(|a, b| {
-            let ord = compare_lines(
-                a,
-                b,
-                args.dictionary_order,
-                args.fold_case,
-                args.ignore_nonprintable,
-                args.numeric_sort,
-            );
-            if let Ordering::Equal = ord {
-                duplicates.push(a.to_string());
+        args.key_definition
+            .iter()
+            .map(|definition| SortKey::parse(definition, args))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    // The sort is stable, so of lines with equal keys, -u keeps the first
+    // in input order.
+    result_lines.sort_by(|a, b| compare_lines(a, b, &keys, args));
+    if args.unique {
+        result_lines.dedup_by(|line, kept| {
+            let equal_keys = compare_keys(line, kept, &keys, args) == Ordering::Equal;
+            if equal_keys {
+                duplicates.push(line.clone());
             }
-            ord
+            equal_keys
         });
-
-        if args.unique {
-            result_lines.retain(|line| !duplicates.contains(line));
-        }
-    }
-
-    if args.reverse {
-        result_lines.reverse();
     }
 
     if args.check_order_without_war_mess {
@@ -876,7 +729,9 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
         }
     } else {
         let result = result_lines.join("\n");
-        println!("{result}");
+        let mut stdout = plib::io::stdout();
+        writeln!(stdout, "{result}")?;
+        stdout.finish()?;
     }
 
     Ok(())
@@ -902,57 +757,28 @@ fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error:
 /// * `Ok(())` if the merging process completes successfully.
 /// * `Err(io::Error)` if an error occurs during file I/O or copying.
 ///
-fn merge_files(paths: &mut Vec<Box<dyn Read>>, output_path: &Option<PathBuf>) -> io::Result<()> {
+fn merge_files(paths: &mut Vec<MappedReader>, output_path: &Option<PathBuf>) -> io::Result<()> {
     let mut output_file: Box<dyn Write> = match output_path {
         Some(path) => Box::new(File::create(path)?),
-        None => Box::new(io::stdout()),
+        None => Box::new(plib::io::stdout()),
     };
 
-    for path in paths {
-        let mut input_file = path;
-
-        // Copy the contents of the input file to the output file or stdout
-        io::copy(&mut input_file, &mut output_file)?;
-    }
-
-    Ok(())
-}
-
-/// Merges consecutive empty strings in the input vector with the nearest non-empty string.
-/// Spaces are added to empty strings to align them with the nearest non-empty string.
-///
-/// # Arguments
-///
-/// * `vec` - A vector of string references (`&str`).
-///
-/// # Returns
-///
-/// A vector of strings (`Vec<String>`) where consecutive empty strings are merged with the nearest non-empty string.
-///
-/// # Examples
-///
-/// ```
-/// let result = merge_empty_lines(vec!["line1", "line2", "", "", "", "lineN"]);
-/// assert_eq!(result, vec!["line1", "line2", "   lineN"]);
-/// ```
-///
-fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
-    let mut empty_count = 0;
-    let mut result = vec![];
-
-    for i in vec {
-        if i.is_empty() {
-            empty_count += 1;
-        } else if empty_count > 0 {
-            let spaces = " ".repeat(empty_count);
-            result.push(format!("{}{}", spaces, i));
-            empty_count = 0;
-        } else {
-            result.push(i.to_string());
+    for input_file in paths {
+        // Copy the contents of the input file to the output file or stdout,
+        // straight from the mapping when the file is mapped
+        loop {
+            let buf = input_file.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            output_file.write_all(buf)?;
+            let n = buf.len();
+            input_file.consume(n);
         }
     }
+    output_file.flush()?;
 
-    result
+    Ok(())
 }
 
 /// Sorts the contents of input files or standard input based on specified criteria.
@@ -973,15 +799,15 @@ fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
 /// * `Err(Box<dyn Error>)` if an error occurs during sorting or merging.
 ///
 fn sort(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let mut readers: Vec<Box<dyn Read>> = if (args.filenames.len() == 1
+    let mut readers: Vec<MappedReader> = if (args.filenames.len() == 1
         && args.filenames[0] == PathBuf::from("-"))
         || args.filenames.is_empty()
     {
-        vec![Box::new(io::stdin().lock())]
+        vec![MappedReader::from_reader(Box::new(io::stdin().lock()))]
     } else {
-        let mut bufs: Vec<Box<dyn Read>> = vec![];
+        let mut bufs: Vec<MappedReader> = vec![];
         for file in &args.filenames {
-            bufs.push(Box::new(std::fs::File::open(file)?))
+            bufs.push(MappedReader::from_file(std::fs::File::open(file)?))
         }
         bufs
     };
@@ -992,9 +818,10 @@ fn sort(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     }
     let mut all_lines: Vec<String> = Vec::new();
     for reader in readers {
-        let reader = io::BufReader::new(reader);
-        let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
-        all_lines.extend(lines);
+        let mut records = RecordReader::lines(reader);
+        while let Some(line) = records.next_text()? {
+            all_lines.push(line.to_string());
+        }
     }
     sort_lines(args, all_lines)?;
 
@@ -1024,9 +851,106 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 mod tests {
     use super::*;
 
+    fn key(definition: &str, global: &[&str]) -> SortKey {
+        let args = Args::parse_from(["sort"].iter().chain(global));
+        SortKey::parse(definition, &args).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key_positions() {
+        let key = key("2.3,4.5", &[]);
+        assert_eq!(
+            key.start,
+            KeyPosition {
+                field: 1,
+                character: Some(2),
+                ignore_leading_blanks: false
+            }
+        );
+        assert_eq!(
+            key.end,
+            Some(KeyPosition {
+                field: 3,
+                character: Some(4),
+                ignore_leading_blanks: false
+            })
+        );
+
+        assert_eq!(super::tests::key("2", &[]).end, None);
+        assert_eq!(super::tests::key("2,3.0", &[]).end.unwrap().character, None);
+    }
+
+    #[test]
+    fn test_parse_key_modifiers() {
+        let key = key("1.1b,1.2n", &[]);
+        assert!(key.start.ignore_leading_blanks);
+        assert!(!key.end.unwrap().ignore_leading_blanks);
+        assert_eq!(
+            key.options,
+            OrderingOptions {
+                numeric_sort: true,
+                ..Default::default()
+            }
+        );
+
+        let key = super::tests::key("2,2fd", &[]);
+        assert!(key.options.fold_case && key.options.dictionary_order);
+    }
+
+    #[test]
+    fn test_parse_key_global_options() {
+        // without modifiers, the key uses all global options
+        let key = key("1.1,1.2", &["-nb", "-r"]);
+        assert!(key.start.ignore_leading_blanks);
+        assert!(key.end.unwrap().ignore_leading_blanks);
+        assert!(key.options.numeric_sort && key.options.reverse);
+
+        // with any modifier, the key uses none of them
+        let key = super::tests::key("1.1,1.2f", &["-nb", "-r"]);
+        assert!(!key.start.ignore_leading_blanks);
+        assert_eq!(
+            key.options,
+            OrderingOptions {
+                fold_case: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_keys() {
+        let args = Args::parse_from(["sort"]);
+        assert!(SortKey::parse("", &args).is_err());
+        assert!(SortKey::parse("0", &args).is_err());
+        assert!(SortKey::parse("1.0", &args).is_err());
+        assert!(SortKey::parse("2.,3", &args).is_err());
+        assert!(SortKey::parse("2,", &args).is_err());
+        assert!(SortKey::parse("1.3,1.1", &args).is_err());
+        assert!(SortKey::parse("1x", &args).is_err());
+    }
+
+    #[test]
+    fn test_extract_key() {
+        assert_eq!(key("2.3,2.3", &[]).extract("z ba", None), "a");
+        assert_eq!(key("2.2,2.2", &["-b"]).extract("z ba", None), "a");
+        assert_eq!(key("2.2,2.2", &[]).extract(":ba", Some(':')), "a");
+        assert_eq!(key("1.1,1.2nb", &[]).extract(" 901", None), " 90");
+        assert_eq!(key("1.1b,1.2n", &[]).extract(" 901", None), "9");
+        assert_eq!(key("1,1b", &[]).extract("a\t:a", Some(':')), "a\t");
+        assert_eq!(key("2,3", &[]).extract("a b  c d", None), " b  c");
+        assert_eq!(key("2", &[]).extract("a b c", None), " b c");
+        assert_eq!(key("3", &[]).extract("a b", None), "");
+        assert_eq!(key("2,2", &[]).extract("a::b", Some(':')), "");
+        assert_eq!(key("1.2,1.2", &[]).extract("x a", None), " ");
+    }
+
     #[test]
-    fn test_merge_empty_lines() {
-        let result = merge_empty_lines(vec!["line1", "line2", "", "", "", "lineN"]);
-        assert_eq!(result, vec!["line1", "line2", "   lineN"]);
+    fn test_compare_key() {
+        let fold = key("2,2fd", &[]);
+        assert_eq!(fold.compare("3 b", "4 B", None), Ordering::Equal);
+        let reverse = key("1,1r", &[]);
+        assert_eq!(reverse.compare("3 b", "4 B", None), Ordering::Greater);
+        let numeric = key("1,1n", &[]);
+        assert_eq!(numeric.compare(".0b", ".0a", None), Ordering::Equal);
     }
 }
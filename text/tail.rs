@@ -2,21 +2,17 @@ use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{
-    self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, StdinLock, StdoutLock, Write,
-};
-use std::path::PathBuf;
-use std::str::FromStr;
-use std::sync::mpsc;
-use std::time::Duration;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, StdinLock, Write};
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use notify_debouncer_full::new_debouncer;
-use notify_debouncer_full::notify::event::{ModifyKind, RemoveKind};
-use notify_debouncer_full::notify::{EventKind, RecursiveMode, Watcher};
+use plib::diag::Status;
+use plib::follow::{Change, FollowMode, Follower, DEFAULT_POLL_INTERVAL};
+use plib::io::{copy_to_stdout, StdoutWriter};
+use plib::sizestr::{parse_size_as, SizeError};
 use plib::BUFSZ;
 
+#[derive(Clone, Copy)]
 enum RelativeFrom {
     StartOfFile(usize),
     EndOfFile(usize),
@@ -25,76 +21,109 @@ enum RelativeFrom {
 #[derive(Debug)]
 enum RelativeFromFromStrError {
     EmptyString,
-    IntegerParseError,
+    InvalidCount(SizeError),
 }
 
 impl Display for RelativeFromFromStrError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let st = match self {
+        match self {
             RelativeFromFromStrError::EmptyString => {
-                "'number' argument following -c or -n was empty"
-            }
-            RelativeFromFromStrError::IntegerParseError => {
-                "'number' argument following -c or -n could not be parsed as an integer"
+                write!(f, "the number must not be empty")
             }
-        };
-
-        write!(f, "{st}")
+            RelativeFromFromStrError::InvalidCount(err) => write!(f, "{err}"),
+        }
     }
 }
 
 impl Error for RelativeFromFromStrError {}
 
-impl FromStr for RelativeFrom {
-    type Err = RelativeFromFromStrError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-
-        let (chars_to_use, relative_from_start_of_file) = match chars.next() {
-            Some('+') => (chars, true),
-            Some('-') => (chars, false),
+impl RelativeFrom {
+    /// Parses a number with an optional leading '+' (relative to the start
+    /// of the file) or '-' (relative to the end, the default), using
+    /// `parse_count` for the part after the sign.
+    fn parse(
+        s: &str,
+        parse_count: fn(&str) -> Result<usize, SizeError>,
+    ) -> Result<Self, RelativeFromFromStrError> {
+        let (count, relative_from_start_of_file) = match s.as_bytes().first() {
+            Some(b'+') => (&s[1..], true),
+            Some(b'-') => (&s[1..], false),
             // If no explicit sign is provided, then relative from end of file
-            Some(_) => (s.chars(), false),
+            Some(_) => (s, false),
             None => {
                 return Err(RelativeFromFromStrError::EmptyString);
             }
         };
 
-        let usize = chars_to_use
-            .as_str()
-            .parse::<usize>()
-            .map_err(|_| RelativeFromFromStrError::IntegerParseError)?;
+        let count = parse_count(count).map_err(RelativeFromFromStrError::InvalidCount)?;
 
         let relative_from = if relative_from_start_of_file {
-            RelativeFrom::StartOfFile(usize)
+            RelativeFrom::StartOfFile(count)
         } else {
-            RelativeFrom::EndOfFile(usize)
+            RelativeFrom::EndOfFile(count)
         };
 
         Ok(relative_from)
     }
 }
 
+/// The option-argument of -n, a decimal number of lines
+fn parse_lines(s: &str) -> Result<RelativeFrom, RelativeFromFromStrError> {
+    RelativeFrom::parse(s, |count| {
+        if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(SizeError::InvalidNumber);
+        }
+        parse_size_as::<usize>(count)
+    })
+}
+
+/// The option-argument of -c, a number of bytes that may have a size suffix
+fn parse_bytes(s: &str) -> Result<RelativeFrom, RelativeFromFromStrError> {
+    RelativeFrom::parse(s, parse_size_as::<usize>)
+}
+
 /// tail - copy the last part of a file
 /// If neither -n nor -c are specified, copies the last 10 lines (-n 10).
 #[derive(Parser)]
 #[command(version, about)]
 struct Args {
     /// The number of lines to print from the end of the file
-    #[arg(short = 'n', long = "lines", allow_hyphen_values = true)]
-    lines: Option<String>,
+    #[arg(
+        short = 'n',
+        long = "lines",
+        allow_hyphen_values = true,
+        value_parser = parse_lines
+    )]
+    lines: Option<RelativeFrom>,
 
     /// The number of bytes to print from the end of the file
-    #[arg(short = 'c', long = "bytes", allow_hyphen_values = true)]
-    bytes: Option<String>,
+    #[arg(
+        short = 'c',
+        long = "bytes",
+        allow_hyphen_values = true,
+        value_parser = parse_bytes
+    )]
+    bytes: Option<RelativeFrom>,
 
     /// Output appended data as the file grows
     #[arg(short = 'f')]
     follow: bool,
 
-    /// The file to read
-    file: Option<PathBuf>,
+    /// Never print headers giving file names
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        visible_alias = "silent",
+        overrides_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Always print headers giving file names
+    #[arg(short = 'v', long = "verbose", overrides_with = "quiet")]
+    verbose: bool,
+
+    /// The files to read
+    files: Vec<PathBuf>,
 }
 
 enum BytesOrLines {
@@ -104,9 +133,9 @@ enum BytesOrLines {
 
 impl Args {
     fn get_bytes_or_lines(&self) -> Result<BytesOrLines, Box<dyn Error>> {
-        let bytes_or_lines = match (&self.bytes, &self.lines) {
-            (Some(st), None) => BytesOrLines::Bytes(RelativeFrom::from_str(st.as_str())?),
-            (None, Some(st)) => BytesOrLines::Lines(RelativeFrom::from_str(st.as_str())?),
+        let bytes_or_lines = match (self.bytes, self.lines) {
+            (Some(re), None) => BytesOrLines::Bytes(re),
+            (None, Some(re)) => BytesOrLines::Lines(re),
             (Some(_), Some(_)) => {
                 // Check if conflicting options are used together
                 return Err(Box::from("options '-c' and '-n' cannot be used together"));
@@ -124,357 +153,306 @@ impl Args {
 }
 
 enum FileOrStdin {
-    File(PathBuf, BufReader<File>),
+    File(BufReader<File>),
     Stdin(StdinLock<'static>),
 }
 
 impl FileOrStdin {
     fn get_buf_read(&mut self) -> &mut dyn BufRead {
         match self {
-            Self::File(_, ref mut bu) => bu,
+            Self::File(ref mut bu) => bu,
             Self::Stdin(ref mut st) => st,
         }
     }
+
+    /// Skips the first `n` bytes, seeking over them in regular files.
+    fn skip_bytes(&mut self, n: u64) -> io::Result<()> {
+        if let Self::File(bu) = self {
+            let metadata = bu.get_ref().metadata()?;
+            if metadata.is_file() {
+                // Not past the end, where following the file continues
+                let pos = n.min(metadata.len());
+                bu.seek(SeekFrom::Start(pos))?;
+                return Ok(());
+            }
+        }
+
+        io::copy(&mut self.get_buf_read().take(n), &mut io::sink())?;
+        Ok(())
+    }
+
+    /// Copies the rest of the input as is, which the kernel can do by
+    /// itself when both ends are regular files or pipes.
+    fn copy_rest(&mut self, stdout_lock: &mut StdoutWriter) -> io::Result<()> {
+        match self {
+            Self::File(bu) => copy_to_stdout(bu, stdout_lock)?,
+            Self::Stdin(st) => copy_to_stdout(st, stdout_lock)?,
+        };
+        Ok(())
+    }
 }
 
-fn print_bytes(stdout_lock: &mut StdoutLock, bytes: &[u8]) -> io::Result<()> {
+fn print_bytes(stdout_lock: &mut StdoutWriter, bytes: &[u8]) -> io::Result<()> {
     stdout_lock.write_all(bytes)
 }
 
-fn print_n_lines<R: Read + BufRead>(
-    stdout_lock: &mut StdoutLock,
-    read: &mut R,
-    relative_from: RelativeFrom,
-) -> Result<(), Box<dyn std::error::Error>> {
-    match relative_from {
-        RelativeFrom::EndOfFile(us) => {
-            let n = if us == 0_usize {
-                // Nothing to read or print
-                return Ok(());
-            } else {
-                us
-            };
-
-            // Create a vector to store the last `n` lines. Preallocate memory for efficiency.
-            let mut lines = VecDeque::<Vec<u8>>::with_capacity(n);
-
-            // Temporary buffer for the line currently being processed
-            let mut line = Vec::<u8>::with_capacity(BUFSZ);
+/// Skips the first `n` lines.
+fn skip_lines(read: &mut dyn BufRead, n: usize) -> io::Result<()> {
+    let mut line = Vec::with_capacity(BUFSZ);
 
-            // Read each line and store the last `n` lines.
-            loop {
-                match read.read_until(b'\n', &mut line) {
-                    Ok(us) => {
-                        if us == 0_usize {
-                            // Reached EOF
-                            break;
-                        }
+    for _ in 0..n {
+        line.clear();
+        if read.read_until(b'\n', &mut line)? == 0_usize {
+            break;
+        }
+    }
 
-                        let lines_len = lines.len();
+    Ok(())
+}
 
-                        if let Some(ve) = lines.pop_front() {
-                            if lines_len == n {
-                                // If the vector is full, remove the first (oldest) line.
-                                let mut ve_mut = ve;
+/// Prints the last `n` lines.
+fn print_last_lines(
+    stdout_lock: &mut StdoutWriter,
+    read: &mut dyn BufRead,
+    n: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if n == 0_usize {
+        // Nothing to read or print
+        return Ok(());
+    }
 
-                                ve_mut.clear();
+    // Create a vector to store the last `n` lines. Preallocate memory for efficiency,
+    // but not for counts larger than any input is likely to be.
+    let mut lines = VecDeque::<Vec<u8>>::with_capacity(n.min(BUFSZ));
 
-                                ve_mut.extend_from_slice(line.as_slice());
+    // Temporary buffer for the line currently being processed
+    let mut line = Vec::<u8>::with_capacity(BUFSZ);
 
-                                lines.push_back(ve_mut);
-                            } else {
-                                // Undo `pop_front`, since it was premature
-                                lines.push_front(ve);
+    // Read each line and store the last `n` lines.
+    loop {
+        match read.read_until(b'\n', &mut line) {
+            Ok(us) => {
+                if us == 0_usize {
+                    // Reached EOF
+                    break;
+                }
 
-                                // Add the current line to the vector.
-                                lines.push_back(line.clone());
-                            }
-                        } else {
-                            // Add the current line to the vector.
-                            lines.push_back(line.clone());
-                        }
+                let lines_len = lines.len();
 
-                        // Clear the temporary storage for the next line.
-                        line.clear();
-                    }
-                    Err(er) => {
-                        if er.kind() == ErrorKind::Interrupted {
-                            continue;
-                        }
+                if let Some(ve) = lines.pop_front() {
+                    if lines_len == n {
+                        // If the vector is full, remove the first (oldest) line.
+                        let mut ve_mut = ve;
 
-                        return Err(Box::from(er));
-                    }
-                }
-            }
+                        ve_mut.clear();
 
-            // Print the collected lines
-            for line in lines {
-                stdout_lock.write_all(line.as_slice())?;
-            }
-        }
-        RelativeFrom::StartOfFile(us) => {
-            // Ensure `n` is at least 1 to avoid unnecessary work when `n` is 0.
-            let n = if us == 0_usize { 1_usize } else { us };
-
-            // TODO: performance
-            // Buffer to read a single byte at a time.
-            let mut empty_buffer = [0_u8; 1_usize];
-            let mut line_count = 0_usize;
-
-            // Skip the first `n` lines.
-            if n != 1_usize {
-                'skip_lines: loop {
-                    // Read a single byte from the reader.
-                    let bytes_read = read.read(&mut empty_buffer)?;
-
-                    if bytes_read == 0_usize {
-                        break;
-                    }
+                        ve_mut.extend_from_slice(line.as_slice());
 
-                    // Check if the byte is a newline character.
-                    if empty_buffer[0_usize] == b'\n' {
-                        line_count += 1;
+                        lines.push_back(ve_mut);
+                    } else {
+                        // Undo `pop_front`, since it was premature
+                        lines.push_front(ve);
 
-                        // If the required number of lines are skipped, exit the loop.
-                        if line_count == n - 1_usize {
-                            break 'skip_lines;
-                        }
+                        // Add the current line to the vector.
+                        lines.push_back(line.clone());
                     }
+                } else {
+                    // Add the current line to the vector.
+                    lines.push_back(line.clone());
                 }
-            }
-
-            // Buffer to read chunks of data from the reader.
-            let mut buffer = [0_u8; BUFSZ];
-
-            // Read and print the remaining lines.
-            loop {
-                let bytes_read = read
-                    .read(&mut buffer)
-                    .map_err(|er| format!("Failed to read: {er}"))?;
 
-                // If no more bytes are read, exit the loop.
-                if bytes_read == 0 {
-                    break;
+                // Clear the temporary storage for the next line.
+                line.clear();
+            }
+            Err(er) => {
+                if er.kind() == ErrorKind::Interrupted {
+                    continue;
                 }
 
-                // Print the bytes read.
-                print_bytes(stdout_lock, &buffer[..bytes_read])?;
+                return Err(Box::from(er));
             }
         }
     }
 
+    // Print the collected lines
+    for line in lines {
+        stdout_lock.write_all(line.as_slice())?;
+    }
+
     Ok(())
 }
 
-fn print_n_bytes<R: Read>(
-    stdout_lock: &mut StdoutLock,
-    read: &mut R,
-    relative_from: RelativeFrom,
+/// Prints the last `n` bytes.
+fn print_last_bytes(
+    stdout_lock: &mut StdoutWriter,
+    read: &mut dyn BufRead,
+    n: usize,
 ) -> Result<(), Box<dyn Error>> {
-    match relative_from {
-        RelativeFrom::EndOfFile(us) => {
-            let n = if us == 0_usize {
-                // Nothing to read or print
-                return Ok(());
-            } else {
-                us
-            };
-
-            // Buffer to read `n` bytes at a time.
-            let mut buffer_1 = vec![0_u8; n];
-
-            // Buffer to store the last `n` bytes read.
-            let mut buffer_2 = Vec::<u8>::new();
+    if n == 0_usize {
+        // Nothing to read or print
+        return Ok(());
+    }
 
-            // Continuously read bytes into buffer_1.
-            loop {
-                let bytes_read = read
-                    .read(&mut buffer_1)
-                    .map_err(|er| format!("Failed to read: {er}"))?;
+    // The last `n` bytes read so far. Older bytes are only dropped
+    // once there are as many of them, so that dropping them takes
+    // linear time overall.
+    let mut last_bytes = Vec::<u8>::new();
 
-                // If the number of bytes read is less than the buffer size, we're at the end of the file.
-                if bytes_read < buffer_1.len() {
-                    // Add the bytes read to buffer_2.
-                    buffer_2.extend(&buffer_1[..bytes_read]);
+    // Buffer to read chunks of data from the reader.
+    let mut buffer = [0_u8; BUFSZ];
 
-                    // If buffer_2 contains fewer than `n` bytes, print all of them.
-                    if buffer_2.len() < n {
-                        print_bytes(stdout_lock, &buffer_2)?;
-                    } else {
-                        // Otherwise, print the last `n` bytes from buffer_2.
-                        let start = buffer_2.len() - n;
-                        print_bytes(stdout_lock, &buffer_2[start..])?;
-                    }
-
-                    // Exit the loop since we've reached the end of the file.
-                    break;
-                }
+    loop {
+        let bytes_read = read
+            .read(&mut buffer)
+            .map_err(|er| format!("Failed to read: {er}"))?;
 
-                // Clone buffer_1 into buffer_2 for the next iteration.
-                buffer_2.clone_from(&buffer_1);
-            }
+        if bytes_read == 0_usize {
+            break;
         }
-        RelativeFrom::StartOfFile(us) => {
-            let mut skip = us;
-
-            // TODO: performance
-            // Buffer to read one byte at a time.
-            let mut empty_buffer = [0_u8; 1_usize];
-
-            // Skip the first `n` bytes.
-            while skip > 1_usize {
-                let bytes_read = read.read(&mut empty_buffer)?;
-
-                if bytes_read == 0_usize {
-                    break;
-                }
-
-                skip -= 1_usize;
-            }
 
-            // Buffer to read chunks of data from the reader.
-            let mut buffer = [0_u8; BUFSZ];
+        last_bytes.extend_from_slice(&buffer[..bytes_read]);
 
-            // Read and print the remaining bytes.
-            loop {
-                let bytes_read = read
-                    .read(&mut buffer)
-                    .map_err(|er| format!("Failed to read: {er}"))?;
-
-                // If no more bytes are read, exit the loop.
-                if bytes_read == 0_usize {
-                    break;
-                }
-
-                // Print the bytes read.
-                print_bytes(stdout_lock, &buffer[..bytes_read])?;
-            }
+        if last_bytes.len() > n.saturating_add(n.max(BUFSZ)) {
+            last_bytes.drain(..last_bytes.len() - n);
         }
     }
 
+    let start = last_bytes.len().saturating_sub(n);
+    print_bytes(stdout_lock, &last_bytes[start..])?;
+
     Ok(())
 }
 
-/// The main logic for the `tail` command.
-///
-/// This function processes the command-line arguments to determine how many lines or bytes
-/// to print from the end of a specified file or standard input. It supports an option to
-/// follow the file, printing new data as it is appended to the file.
+/// Prints the part of `file` selected by `bytes_or_lines`, or of standard
+/// input if `file` is "-". With `header`, which tells whether this is the
+/// first header, the part is preceded by a header giving the file name.
 ///
 /// # Returns
-/// * `Ok(())` - If the operation completes successfully.
+/// * `Ok(Some(_))` - The file and the position up to which it was read, to follow it.
+/// * `Ok(None)` - If the input was standard input, which is not followed.
 /// * `Err(Box<dyn std::error::Error>)` - If an error occurs during the operation.
 ///
 /// # Errors
 /// This function will return an error if:
 /// - The specified file cannot be opened.
 /// - An error occurs while reading from the file or stdin.
-/// - An error occurs while watching the file for changes.
-fn tail(
-    file: Option<PathBuf>,
-    follow: bool,
-    bytes_or_lines: BytesOrLines,
-) -> Result<(), Box<dyn Error>> {
-    fn get_stdin() -> FileOrStdin {
+fn tail_file(
+    file: &Path,
+    bytes_or_lines: &BytesOrLines,
+    header: Option<&mut bool>,
+    stdout_lock: &mut StdoutWriter,
+) -> Result<Option<(File, u64)>, Box<dyn Error>> {
+    let mut file_or_stdin = if file.as_os_str() == "-" {
         FileOrStdin::Stdin(io::stdin().lock())
-    }
-
-    let mut file_or_stdin = match file {
-        Some(pa) => {
-            if pa.as_os_str() == "-" {
-                get_stdin()
-            } else {
-                let fi = File::open(pa.as_path())?;
+    } else {
+        let fi = File::open(file)?;
 
-                FileOrStdin::File(pa, BufReader::new(fi))
-            }
-        }
-        None => get_stdin(),
+        FileOrStdin::File(BufReader::new(fi))
     };
 
-    let mut stdout_lock = io::stdout().lock();
-
-    {
-        let mut buf_reader = file_or_stdin.get_buf_read();
+    // print file header, once the file could be opened
+    if let Some(first) = header {
+        print_header(stdout_lock, file, *first)?;
+        *first = false;
+    }
 
-        match bytes_or_lines {
-            BytesOrLines::Bytes(re) => {
-                print_n_bytes(&mut stdout_lock, &mut buf_reader, re)?;
-            }
-            BytesOrLines::Lines(re) => {
-                print_n_lines(&mut stdout_lock, &mut buf_reader, re)?;
-            }
+    // Counts from the start of the file number from 1, as do +0 and +1
+    match *bytes_or_lines {
+        BytesOrLines::Bytes(RelativeFrom::EndOfFile(n)) => {
+            print_last_bytes(stdout_lock, file_or_stdin.get_buf_read(), n)?;
+        }
+        BytesOrLines::Lines(RelativeFrom::EndOfFile(n)) => {
+            print_last_lines(stdout_lock, file_or_stdin.get_buf_read(), n)?;
+        }
+        BytesOrLines::Bytes(RelativeFrom::StartOfFile(n)) => {
+            file_or_stdin.skip_bytes(n.saturating_sub(1) as u64)?;
+            file_or_stdin.copy_rest(stdout_lock)?;
+        }
+        BytesOrLines::Lines(RelativeFrom::StartOfFile(n)) => {
+            skip_lines(file_or_stdin.get_buf_read(), n.saturating_sub(1))?;
+            file_or_stdin.copy_rest(stdout_lock)?;
+        }
+    }
+    stdout_lock.flush()?;
+
+    match file_or_stdin {
+        FileOrStdin::File(mut bu) => {
+            // Continue from the end of what has been output, which data
+            // appended in the meantime comes after
+            let pos = bu.stream_position()?;
+            Ok(Some((bu.into_inner(), pos)))
         }
+        FileOrStdin::Stdin(_) => Ok(None),
     }
+}
 
-    if follow {
-        // If follow option is specified, continue monitoring the file
-        if let FileOrStdin::File(pa, mut bu) = file_or_stdin {
-            // Move the the cursor to the end of the file
-            // Is this still necessary now that the `BufReader` and underlying `File` are being reused?
-            bu.seek(SeekFrom::End(0_i64))?;
-
-            let (tx, rx) = mpsc::channel();
-
-            // Automatically select the best implementation for your platform.
-            let mut debouncer = new_debouncer(Duration::from_millis(1_u64), None, tx).unwrap();
-
-            // Add a path to be watched.
-            // below will be monitored for changes.
-            debouncer
-                .watcher()
-                .watch(pa.as_path(), RecursiveMode::NonRecursive)?;
-
-            for res in rx {
-                match res {
-                    Ok(events) => {
-                        let event = events.first().unwrap();
-                        match event.kind {
-                            EventKind::Modify(ModifyKind::Any)
-                            | EventKind::Modify(ModifyKind::Data(_))
-                            | EventKind::Modify(ModifyKind::Other) => {
-                                // If the file has been modified, check if the file was truncated
-                                let metadata = bu.get_mut().metadata()?;
-                                let current_size = metadata.len();
-
-                                if current_size < bu.stream_position()? {
-                                    eprintln!("\ntail: {}: file truncated", pa.display());
-
-                                    bu.seek(SeekFrom::Start(0_u64))?;
-                                }
-
-                                // Read the new lines and output them
-                                let mut new_data = Vec::<u8>::new();
-
-                                let bytes_read = bu.read_to_end(&mut new_data)?;
-
-                                if bytes_read > 0_usize {
-                                    print_bytes(&mut stdout_lock, &new_data)?;
-
-                                    io::stdout().flush()?;
-                                }
-                            }
-                            EventKind::Remove(RemoveKind::File) => {
-                                debouncer.watcher().unwatch(pa.as_path())?
-                            }
-                            _ => {}
-                        }
-                    }
-                    Err(ve) => {
-                        eprintln!("tail: watch error: {ve:?}");
+/// The name of `file` in headers
+fn display_name(file: &Path) -> std::borrow::Cow<'_, str> {
+    if file.as_os_str() == "-" {
+        "standard input".into()
+    } else {
+        file.to_string_lossy()
+    }
+}
+
+fn print_header(stdout_lock: &mut StdoutWriter, file: &Path, first: bool) -> io::Result<()> {
+    if first {
+        writeln!(stdout_lock, "==> {} <==", display_name(file))
+    } else {
+        writeln!(stdout_lock, "\n==> {} <==", display_name(file))
+    }
+}
+
+/// Prints data appended to the followed files as it arrives, forever.
+///
+/// When there are headers, a header is printed before data that comes from
+/// another file than the data printed last.
+fn follow_files(
+    mut followed: Vec<(PathBuf, Follower)>,
+    want_header: bool,
+    stdout_lock: &mut StdoutWriter,
+) -> Result<(), Box<dyn Error>> {
+    let single_file = followed.len() == 1;
+    let mut last_printed = followed.len() - 1;
+
+    loop {
+        let mut changed = false;
+
+        for (index, (pa, follower)) in followed.iter_mut().enumerate() {
+            // A single file can wait for changes, several are checked in turn
+            let change = if single_file {
+                Some(follower.next_change()?)
+            } else {
+                follower.check()?
+            };
+
+            match change {
+                Some(Change::Appended(new_data)) => {
+                    if want_header && index != last_printed {
+                        print_header(stdout_lock, pa, false)?;
                     }
+                    last_printed = index;
+
+                    print_bytes(stdout_lock, &new_data)?;
+
+                    stdout_lock.flush()?;
+                    changed = true;
+                }
+                Some(Change::Truncated) => {
+                    eprintln!("\ntail: {}: file truncated", pa.display());
                 }
+                Some(Change::Replaced | Change::Removed) | None => {}
             }
         }
-    }
 
-    Ok(())
+        if !changed && !single_file {
+            std::thread::sleep(DEFAULT_POLL_INTERVAL);
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    plib::i18n::init();
 
     let args = Args::parse();
 
@@ -487,13 +465,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let mut exit_code = 0_i32;
+    let mut files = args.files;
+
+    // if no files, read from stdin
+    if files.is_empty() {
+        files.push(PathBuf::from("-"));
+    }
 
-    if let Err(er) = tail(args.file, args.follow, bytes_or_lines) {
-        exit_code = 1_i32;
+    let want_header = args.verbose || (files.len() > 1 && !args.quiet);
+
+    let mut status = Status::new();
+    let mut first = true;
+    let mut followed = Vec::new();
+
+    let mut stdout_lock = plib::io::stdout();
+
+    for file in files {
+        match tail_file(
+            &file,
+            &bytes_or_lines,
+            want_header.then_some(&mut first),
+            &mut stdout_lock,
+        ) {
+            Ok(Some((mut fi, pos))) if args.follow => {
+                let follower = fi
+                    .seek(SeekFrom::Start(pos))
+                    .and_then(|_| Follower::new(&file, fi, FollowMode::Descriptor));
+                match follower {
+                    Ok(follower) => followed.push((file, follower)),
+                    Err(e) => status.operand_error(file.display(), &e),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => status.operand_error(file.display(), &e),
+        }
+    }
+
+    if !followed.is_empty() {
+        if let Err(e) = follow_files(followed, want_header, &mut stdout_lock) {
+            status.error(&e);
+        }
+    }
 
-        eprintln!("tail: {}", er);
+    if let Err(e) = stdout_lock.finish() {
+        status.error(&e);
     }
 
-    std::process::exit(exit_code)
+    status.exit()
 }
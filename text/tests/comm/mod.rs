@@ -65,3 +65,21 @@ fn comm_suppress_third_column() {
         "comm.3",
     );
 }
+
+#[test]
+fn comm_check_order() {
+    run_test(TestPlan {
+        cmd: String::from("comm"),
+        args: vec![
+            String::from("--check-order"),
+            String::from("tests/comm/comm.unsorted"),
+            String::from("tests/comm/comm.unsorted"),
+        ],
+        expected_out: String::from("\t\tbanana\n"),
+        expected_err: String::from(
+            "comm: tests/comm/comm.unsorted: line 2 is not in sorted order\n",
+        ),
+        stdin_data: String::new(),
+        expected_exit_code: 1,
+    });
+}
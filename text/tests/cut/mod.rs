@@ -8,7 +8,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
 
 fn cut_test(args: &[&str], test_data: &str, expected_output: &str) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
@@ -354,3 +354,47 @@ fn test_stdin_no_hyphen() {
         "081ca869c86b 41 truefalse/src/false.rs\n",
     );
 }
+
+#[test]
+fn test_multibyte_delimiter() {
+    cut_test(&["-d", "é", "-f", "2,3", "-"], "aébéc\nd\n", "béc\nd\n");
+    cut_test(&["-s", "-d", "→", "-f", "1", "-"], "a→b\nc\n", "a\n");
+}
+
+#[test]
+fn test_tab_delimiter() {
+    cut_test(&["-d", "\t", "-f", "2", "-"], "a\tb\tc\n", "b\n");
+}
+
+#[test]
+fn test_multi_character_delimiter() {
+    run_test(TestPlan {
+        cmd: String::from("cut"),
+        args: vec![
+            String::from("-d"),
+            String::from("ab"),
+            String::from("-f"),
+            String::from("1"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(
+            "error: invalid value 'ab' for '--delimiter <DELIMITER>': \
+             the delimiter must be a single character: 'ab'\n\n\
+             For more information, try '--help'.\n",
+        ),
+        expected_exit_code: 2,
+    });
+}
+
+#[test]
+fn test_cut_bytes_not_utf8() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("cut"),
+        args: vec![String::from("-b"), String::from("2-3")],
+        stdin_data: b"a\xff\xfeb\n\xe2\x82\xac\n".to_vec(),
+        expected_out: b"\xff\xfe\n\x82\xac\n".to_vec(),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
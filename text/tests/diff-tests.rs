@@ -11,414 +11,239 @@
 #[path = "../diff_util/constants.rs"]
 mod constants;
 
-use constants::{EXIT_STATUS_DIFFERENCE, EXIT_STATUS_NO_DIFFERENCE};
-use plib::testing::{run_test, TestPlan};
-use std::{collections::HashMap, path::PathBuf, process::Stdio, sync::LazyLock};
-
-fn diff_test(args: &[&str], expected_output: &str, expected_diff_exit_status: u8) {
-    let str_args = args.iter().cloned().map(str::to_owned).collect();
+use constants::{EXIT_STATUS_DIFFERENCE, EXIT_STATUS_NO_DIFFERENCE, EXIT_STATUS_TROUBLE};
+use plib::testing::{run_test_golden, TestOptions};
+use std::path::PathBuf;
+
+/// Masks the modification times in context and unified diff headers,
+/// which depend on when the fixtures were checked out.
+fn mask_timestamps(output: &str) -> String {
+    output
+        .split_inclusive('\n')
+        .map(|line| {
+            let is_header = ["*** ", "--- ", "+++ "]
+                .iter()
+                .any(|prefix| line.starts_with(prefix));
+            match line.find('\t') {
+                Some(tab) if is_header => {
+                    let eol = if line.ends_with('\n') { "\n" } else { "" };
+                    format!("{}\tTIMESTAMP{}", &line[..tab], eol)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect()
+}
 
-    run_test(TestPlan {
-        cmd: String::from("diff"),
-        args: str_args,
-        stdin_data: String::from(""),
-        expected_out: String::from(expected_output),
-        expected_err: String::from(""),
-        expected_exit_code: i32::from(expected_diff_exit_status),
-    });
+fn diff_test(args: &[&str], golden: &str, expected_diff_exit_status: u8) {
+    run_test_golden(
+        "diff",
+        args,
+        i32::from(expected_diff_exit_status),
+        diff_base_path().join("expected").join(golden),
+        TestOptions {
+            normalize: Some(mask_timestamps),
+            ..Default::default()
+        },
+    );
 }
 
 fn diff_base_path() -> PathBuf {
     PathBuf::from("tests").join("diff")
 }
 
-fn f1_txt_path() -> String {
+fn diff_path(name: &str) -> String {
     diff_base_path()
-        .join("f1.txt")
+        .join(name)
         .to_str()
-        .expect("Could not unwrap f1_txt_path")
+        .expect("Could not unwrap diff path")
         .to_string()
 }
 
+fn f1_txt_path() -> String {
+    diff_path("f1.txt")
+}
+
 fn f2_txt_path() -> String {
-    diff_base_path()
-        .join("f2.txt")
-        .to_str()
-        .expect("Could not unwrap f2_txt_path")
-        .to_string()
+    diff_path("f2.txt")
 }
 
 fn f1_dir_path() -> String {
-    diff_base_path()
-        .join("f1")
-        .to_str()
-        .expect("Could not unwrap f1_dir_path")
-        .to_string()
+    diff_path("f1")
 }
 
 fn f2_dir_path() -> String {
-    diff_base_path()
-        .join("f2")
-        .to_str()
-        .expect("Could not unwrap f2_dir_path")
-        .to_string()
+    diff_path("f2")
 }
 
 fn f1_txt_with_eol_spaces_path() -> String {
-    diff_base_path()
-        .join("f1_with_eol_spaces.txt")
-        .to_str()
-        .expect("Could not unwrap f1_txt_with_eol_spaces_path")
-        .to_string()
-}
-
-struct DiffTestHelper {
-    content: String,
-    file1_path: String,
-    file2_path: String,
-}
-
-impl DiffTestHelper {
-    fn new(options: &str, file1_path: String, file2_path: String) -> Self {
-        let args = format!(
-            "run --release --bin diff --{} {} {}",
-            options, file1_path, file2_path
-        );
-
-        let args_list = args.split(' ').collect::<Vec<&str>>();
-
-        let output = std::process::Command::new("cargo")
-            .args(args_list)
-            // .stdout(output_file)
-            .stdout(Stdio::piped())
-            .output()
-            .expect("Could not run cargo command!");
-
-        let content = String::from_utf8(output.stdout).expect("Failed to read output of Command!");
-
-        Self {
-            file1_path,
-            file2_path,
-            content,
-        }
-    }
-
-    fn content(&self) -> &str {
-        &self.content
-    }
-
-    fn file1_path(&self) -> &str {
-        &self.file1_path
-    }
-
-    fn file2_path(&self) -> &str {
-        &self.file2_path
-    }
-}
-
-fn get_diff_test_helper_hash_map() -> HashMap<String, DiffTestHelper> {
-    let diff_test_helper_init_data = [
-        ("", f1_txt_path(), f2_txt_path(), "test_diff_normal"),
-        (" -c", f1_txt_path(), f2_txt_path(), "test_diff_context3"),
-        (" -C 1", f1_txt_path(), f2_txt_path(), "test_diff_context1"),
-        (
-            " -C 10",
-            f1_txt_path(),
-            f2_txt_path(),
-            "test_diff_context10",
-        ),
-        (" -e", f1_txt_path(), f2_txt_path(), "test_diff_edit_script"),
-        (
-            " -f",
-            f1_txt_path(),
-            f2_txt_path(),
-            "test_diff_forward_edit_script",
-        ),
-        (" -u", f1_txt_path(), f2_txt_path(), "test_diff_unified3"),
-        (" -U 0", f1_txt_path(), f2_txt_path(), "test_diff_unified0"),
-        (
-            " -U 10",
-            f1_txt_path(),
-            f2_txt_path(),
-            "test_diff_unified10",
-        ),
-        ("", f1_txt_path(), f2_dir_path(), "test_diff_file_directory"),
-        ("", f1_dir_path(), f2_dir_path(), "test_diff_directories"),
-        (
-            " -r",
-            f1_dir_path(),
-            f2_dir_path(),
-            "test_diff_directories_recursive",
-        ),
-        (
-            " -r -c",
-            f1_dir_path(),
-            f2_dir_path(),
-            "test_diff_directories_recursive_context",
-        ),
-        (
-            " -r -e",
-            f1_dir_path(),
-            f2_dir_path(),
-            "test_diff_directories_recursive_edit_script",
-        ),
-        (
-            " -r -f",
-            f1_dir_path(),
-            f2_dir_path(),
-            "test_diff_directories_recursive_forward_edit_script",
-        ),
-        (
-            " -r -u",
-            f1_dir_path(),
-            f2_dir_path(),
-            "test_diff_directories_recursive_unified",
-        ),
-        (
-            "",
-            f1_txt_path(),
-            f1_txt_with_eol_spaces_path(),
-            "test_diff_counting_eol_spaces",
-        ),
-        (
-            " -b",
-            f1_txt_path(),
-            f1_txt_with_eol_spaces_path(),
-            "test_diff_ignoring_eol_spaces",
-        ),
-        (
-            " --label F1 --label2 F2 -u",
-            f1_txt_path(),
-            f1_txt_with_eol_spaces_path(),
-            "test_diff_unified_two_labels",
-        ),
-    ];
-
-    let mut diff_test_helper_hash_map =
-        HashMap::<String, DiffTestHelper>::with_capacity(diff_test_helper_init_data.len());
-
-    for (options, file1_path, file2_path, key) in diff_test_helper_init_data {
-        let insert_option = diff_test_helper_hash_map.insert(
-            key.to_owned(),
-            DiffTestHelper::new(options, file1_path, file2_path),
-        );
-
-        assert!(insert_option.is_none());
-    }
-
-    diff_test_helper_hash_map
-}
-
-fn input_by_key(key: &str) -> &'static DiffTestHelper {
-    static DIFF_TEST_INPUT: LazyLock<HashMap<String, DiffTestHelper>> =
-        LazyLock::new(get_diff_test_helper_hash_map);
-
-    // Initialized on first access
-    DIFF_TEST_INPUT.get(key).unwrap()
+    diff_path("f1_with_eol_spaces.txt")
 }
 
 #[test]
 fn test_diff_normal() {
-    let data = input_by_key("test_diff_normal");
-
     diff_test(
-        &[data.file1_path(), data.file2_path()],
-        data.content(),
+        &[&f1_txt_path(), &f2_txt_path()],
+        "normal",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_context3() {
-    let data = input_by_key("test_diff_context3");
-
     diff_test(
-        &["-c", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-c", &f1_txt_path(), &f2_txt_path()],
+        "context3",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_context1() {
-    let data = input_by_key("test_diff_context1");
-
     diff_test(
-        &["-C", "1", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-C", "1", &f1_txt_path(), &f2_txt_path()],
+        "context1",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_context10() {
-    let data = input_by_key("test_diff_context10");
-
     diff_test(
-        &["-C", "10", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-C", "10", &f1_txt_path(), &f2_txt_path()],
+        "context10",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_edit_script() {
-    let data = input_by_key("test_diff_edit_script");
-
     diff_test(
-        &["-e", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-e", &f1_txt_path(), &f2_txt_path()],
+        "edit_script",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_forward_edit_script() {
-    let data = input_by_key("test_diff_forward_edit_script");
-
     diff_test(
-        &["-f", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-f", &f1_txt_path(), &f2_txt_path()],
+        "forward_edit_script",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_unified3() {
-    let data = input_by_key("test_diff_unified3");
-
     diff_test(
-        &["-u", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-u", &f1_txt_path(), &f2_txt_path()],
+        "unified3",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_unified0() {
-    let data = input_by_key("test_diff_unified0");
-
     diff_test(
-        &["-U", "0", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-U", "0", &f1_txt_path(), &f2_txt_path()],
+        "unified0",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_unified10() {
-    let data = input_by_key("test_diff_unified10");
-
     diff_test(
-        &["-U", "10", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-U", "10", &f1_txt_path(), &f2_txt_path()],
+        "unified10",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_file_directory() {
-    let data = input_by_key("test_diff_file_directory");
-
     diff_test(
-        &[data.file1_path(), data.file2_path()],
-        data.content(),
+        &[&f1_txt_path(), &f2_dir_path()],
+        "file_directory",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories() {
-    let data = input_by_key("test_diff_directories");
-
     diff_test(
-        &[data.file1_path(), data.file2_path()],
-        data.content(),
+        &[&f1_dir_path(), &f2_dir_path()],
+        "directories",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories_recursive() {
-    let data = input_by_key("test_diff_directories_recursive");
-
     diff_test(
-        &["-r", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-r", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories_recursive_context() {
-    let data = input_by_key("test_diff_directories_recursive_context");
-
     diff_test(
-        &["-r", "-c", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-r", "-c", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive_context",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories_recursive_edit_script() {
-    let data = input_by_key("test_diff_directories_recursive_edit_script");
-
     diff_test(
-        &["-r", "-e", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-r", "-e", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive_edit_script",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories_recursive_forward_edit_script() {
-    let data = input_by_key("test_diff_directories_recursive_forward_edit_script");
-
     diff_test(
-        &["-r", "-f", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-r", "-f", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive_forward_edit_script",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_directories_recursive_unified() {
-    let data = input_by_key("test_diff_directories_recursive_unified");
-
     diff_test(
-        &["-r", "-u", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-r", "-u", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive_unified",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_counting_eol_spaces() {
-    let data = input_by_key("test_diff_counting_eol_spaces");
-
     diff_test(
-        &[data.file1_path(), data.file2_path()],
-        data.content(),
+        &[&f1_txt_path(), &f1_txt_with_eol_spaces_path()],
+        "counting_eol_spaces",
         EXIT_STATUS_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_ignoring_eol_spaces() {
-    let data = input_by_key("test_diff_ignoring_eol_spaces");
-
     diff_test(
-        &["-b", data.file1_path(), data.file2_path()],
-        data.content(),
+        &["-b", &f1_txt_path(), &f1_txt_with_eol_spaces_path()],
+        "ignoring_eol_spaces",
         EXIT_STATUS_NO_DIFFERENCE,
     );
 }
 
 #[test]
 fn test_diff_unified_two_labels() {
-    let data = input_by_key("test_diff_unified_two_labels");
-
     diff_test(
         &[
             "--label",
@@ -426,10 +251,104 @@ fn test_diff_unified_two_labels() {
             "--label2",
             "F2",
             "-u",
-            data.file1_path(),
-            data.file2_path(),
+            &f1_txt_path(),
+            &f1_txt_with_eol_spaces_path(),
         ],
-        data.content(),
+        "unified_two_labels",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_counting_trailing_cr() {
+    diff_test(
+        &[&f1_txt_path(), &diff_path("f1_crlf.txt")],
+        "counting_trailing_cr",
         EXIT_STATUS_DIFFERENCE,
     );
 }
+
+#[test]
+fn test_diff_strip_trailing_cr() {
+    diff_test(
+        &[
+            "--strip-trailing-cr",
+            &f1_txt_path(),
+            &diff_path("f1_crlf.txt"),
+        ],
+        "strip_trailing_cr",
+        EXIT_STATUS_NO_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_binary() {
+    diff_test(
+        &[&diff_path("f1.bin"), &diff_path("f2.bin")],
+        "binary",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_binary_as_text() {
+    diff_test(
+        &["-a", &diff_path("f1.bin"), &diff_path("f2.bin")],
+        "binary_as_text",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_brief() {
+    diff_test(
+        &["-q", &f1_txt_path(), &f2_txt_path()],
+        "brief",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_brief_no_difference() {
+    diff_test(
+        &["-q", "-b", &f1_txt_path(), &f1_txt_with_eol_spaces_path()],
+        "brief_no_difference",
+        EXIT_STATUS_NO_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_brief_binary() {
+    diff_test(
+        &["-q", &diff_path("f1.bin"), &diff_path("f2.bin")],
+        "brief_binary",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_directories_recursive_brief() {
+    diff_test(
+        &["-r", "-q", &f1_dir_path(), &f2_dir_path()],
+        "directories_recursive_brief",
+        EXIT_STATUS_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_same_file() {
+    diff_test(
+        &[&f1_txt_path(), &f1_txt_path()],
+        "same_file",
+        EXIT_STATUS_NO_DIFFERENCE,
+    );
+}
+
+#[test]
+fn test_diff_missing_file() {
+    diff_test(
+        &[&f1_txt_path(), &diff_path("missing.txt")],
+        "missing_file",
+        EXIT_STATUS_TROUBLE,
+    );
+}
@@ -8,7 +8,7 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_with_options, TestOptions, TestPlan};
 
 const LINES_INPUT: &str =
     "line_{1}\np_line_{2}_s\n  line_{3}  \nLINE_{4}\np_LINE_{5}_s\nl_{6}\nline_{70}\n";
@@ -208,6 +208,51 @@ fn test_basic_regexp_ignore_case_02() {
     grep_test(&["-i", BRE], BAD_INPUT, "", "", 1);
 }
 
+fn grep_test_in_utf8_locale(args: &[&str], test_data: &str, expected_output: &str) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("grep"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::from(test_data),
+            expected_out: String::from(expected_output),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions::default().env("LC_ALL", "C.UTF-8"),
+    );
+}
+
+#[test]
+fn test_ignore_case_multibyte() {
+    let input = "\u{e9}t\u{e9}\n\u{c9}T\u{c9}\nete\n";
+    let expected = "\u{e9}t\u{e9}\n\u{c9}T\u{c9}\n";
+    grep_test_in_utf8_locale(&["-i", "\u{c9}"], input, expected);
+    grep_test_in_utf8_locale(&["-i", "[\u{e9}]T"], input, expected);
+    grep_test_in_utf8_locale(&["-iF", "\u{c9}t\u{c9}"], input, expected);
+
+    // The Kelvin sign folds to 'k', which is shorter
+    grep_test_in_utf8_locale(
+        &["-o", "-iF", "k\u{e9}"],
+        "x\u{212a}\u{c9}y k\u{e9}\n",
+        "\u{212a}\u{c9}\nk\u{e9}\n",
+    );
+}
+
+#[test]
+fn test_ignore_case_is_ascii_in_c_locale() {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("grep"),
+            args: vec![String::from("-iF"), String::from("\u{c9}t\u{c9}")],
+            stdin_data: String::from("\u{e9}t\u{e9}\n\u{c9}T\u{c9}\n"),
+            expected_out: String::from("\u{c9}T\u{c9}\n"),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions::default().env("LC_ALL", "C"),
+    );
+}
+
 #[test]
 fn test_basic_regexp_line_number_01() {
     grep_test(
@@ -1389,3 +1434,62 @@ fn test_long_names_files() {
             0,
         );
 }
+
+#[test]
+fn test_only_matching_01() {
+    grep_test(
+        &["-o", BRE],
+        LINES_INPUT,
+        "line_{1}\nline_{2}\nline_{3}\nline_{70}\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_only_matching_02() {
+    grep_test(
+        &["-on", "-E", "[0-9]+"],
+        "a1b22\nnone\n333\n",
+        "1:1\n1:22\n3:333\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_only_matching_03() {
+    grep_test(
+        &["-oiF", "-e", "ab", "-e", "abc"],
+        "xABCabx\n",
+        "ABC\nab\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_only_matching_04() {
+    grep_test(&["-ov", "x"], "a\nx\n", "", "", 0);
+}
+
+#[test]
+fn test_only_matching_05() {
+    grep_test(&["-oc", "a"], "aaa\nb\na\n", "2\n", "", 0);
+}
+
+#[test]
+fn test_only_matching_06() {
+    grep_test(&["-o", "x*"], "abc\nxx\n", "xx\n", "", 0);
+}
+
+#[test]
+fn test_directory_input() {
+    grep_test(
+        &["line", "tests/grep"],
+        "",
+        "",
+        "tests/grep: Error reading line 1 (Is a directory (os error 21))\n",
+        2,
+    );
+}
@@ -117,6 +117,12 @@ a
 fn test_head_c() {
     head_test(None, Some("3"), "123456789", "123");
 }
+
+#[test]
+fn test_head_c_suffix() {
+    let input = "0123456789".repeat(200);
+    head_test(None, Some("1k"), &input, &input[..1024]);
+}
 /* #endregion */
 
 /* #region Property-based tests */
@@ -267,3 +273,18 @@ input_len: {input_len}
     }
 }
 /* #endregion */
+
+#[test]
+fn test_head_c_file_operand() {
+    let file = "tests/assets/test_file.txt";
+    let contents = std::fs::read_to_string(file).unwrap();
+    head_test(None, Some("10"), &contents, &contents[..10]);
+    run_test(TestPlan {
+        cmd: String::from("head"),
+        args: vec![String::from("-c"), String::from("10"), String::from(file)],
+        stdin_data: String::new(),
+        expected_out: contents[..10].to_string(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
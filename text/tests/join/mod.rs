@@ -142,3 +142,70 @@ fn third_field_join_test() {
 
     run_test_join(&args, expected_output, "", 0)
 }
+
+#[test]
+fn check_order_test() {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let file1 = format!("{}/tests/join/file3.txt", project_root);
+    let file2 = format!("{}/tests/join/file4.txt", project_root);
+    let args = [
+        "--check-order",
+        "-1",
+        "3",
+        "-2",
+        "3",
+        file1.as_str(),
+        file2.as_str(),
+    ];
+
+    let expected_error = format!("join: {}: line 2 is not in sorted order\n", file2);
+
+    run_test_join(&args, "1 Bob HR Director HR\n", &expected_error, 1);
+
+    // The last of the two options wins
+    let args = [
+        "--check-order",
+        "--nocheck-order",
+        "-1",
+        "3",
+        "-2",
+        "3",
+        file1.as_str(),
+        file2.as_str(),
+    ];
+    run_test_join(
+        &args,
+        "1 Bob HR Director HR\n2 Charlie Finance Analyst Finance\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn multibyte_separator_test() {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let file1 = format!("{}/tests/join/multibyte1.txt", project_root);
+    let file2 = format!("{}/tests/join/multibyte2.txt", project_root);
+    let args = ["-t", "é", file1.as_str(), file2.as_str()];
+
+    let expected_output = "kéxéy\nzélém\n";
+
+    run_test_join(&args, expected_output, "", 0)
+}
+
+#[test]
+fn multi_character_separator_test() {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let file1 = format!("{}/tests/join/multibyte1.txt", project_root);
+    let file2 = format!("{}/tests/join/multibyte2.txt", project_root);
+    let args = ["-t", "éé", file1.as_str(), file2.as_str()];
+
+    run_test_join(
+        &args,
+        "",
+        "error: invalid value 'éé' for '-t <SEPARATOR>': \
+         the delimiter must be a single character: 'éé'\n\n\
+         For more information, try '--help'.\n",
+        2,
+    )
+}
@@ -93,3 +93,45 @@ fn test_nl_regex() {
         "     1\tsomething\n     2\tanything\n     3\teverything\n       cat\n       dog\n",
     );
 }
+
+#[test]
+fn test_nl_multibyte_separator() {
+    nl_test(&["-s", "::"], "a\n\nb", "     1::a\n        \n     2::b\n");
+    // unnumbered lines are indented by the width of the separator in bytes,
+    // like the reference implementation
+    nl_test(
+        &["-s", "\u{e9}\u{2192}"],
+        "a\n\nb",
+        "     1\u{e9}\u{2192}a\n           \n     2\u{e9}\u{2192}b\n",
+    );
+    nl_test(&["-s", ""], "a", "     1a\n");
+}
+
+#[test]
+fn test_nl_number_wider_than_width() {
+    nl_test(&["-w", "2", "-v", "99"], "a\nb", "99\ta\n100\tb\n");
+    nl_test(
+        &["-w", "2", "-v", "99", "-n", "rz"],
+        "a\nb",
+        "99\ta\n100\tb\n",
+    );
+    nl_test(
+        &["-w", "2", "-v", "99", "-n", "ln"],
+        "a\nb",
+        "99\ta\n100\tb\n",
+    );
+}
+
+#[test]
+fn test_nl_negative_numbers() {
+    nl_test(
+        &["-w", "3", "-n", "rz", "-v", "-5", "-i", "5"],
+        "a\nb",
+        "-05\ta\n000\tb\n",
+    );
+    nl_test(
+        &["-w", "3", "-v", "1", "-i", "-2"],
+        "a\nb",
+        "  1\ta\n -1\tb\n",
+    );
+}
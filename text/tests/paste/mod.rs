@@ -330,3 +330,20 @@ fn paste_custom_delimiters_serial() {
         "output_paste_custom_delimiters_serial.txt",
     );
 }
+
+#[test]
+fn paste_multibyte_delimiters() {
+    let args = ["-d", "é→", "-s", "--", "-"]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+
+    run_test(TestPlan {
+        args,
+        cmd: "paste".to_owned(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+        expected_out: "aéb→céd\n".to_owned(),
+        stdin_data: "a\nb\nc\nd\n".to_owned(),
+    });
+}
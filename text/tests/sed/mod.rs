@@ -581,7 +581,7 @@ mod tests {
                 "/5/,+3p",
                 "a\nb\nc\nd\ne\nf\ng\nm\nn\nt\nw\nq\nh\nw",
                 "",
-                "sed: unknown character '/' (line: 0, col: 1)\n",
+                "sed: address bound can be only one pattern, number or '$' (line: 0, col: 5)\n",
             ),
             (
                 "7;+ p",
@@ -636,6 +636,12 @@ mod tests {
                 "a\nb\nc\nd\nabc\nabc\nf\ng\nm\nn\nt\nw\nq\nh\nw\n",
                 "",
             ),
+            (
+                "/abc/,/f/ p",
+                "a\nabc\nf\ng\n",
+                "a\nabc\nabc\nf\nf\ng\n",
+                "",
+            ),
             (
                 r"\@abc@ p",
                 "a\nb\nc\nd\nabc\nf\ng\nm\nn\nt\nw\nq\nh\nw\n",
@@ -674,6 +680,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_address_range_ends() {
+        let test_data = [
+            ("2,2p", "a\nb\nc\n", "b\n"),
+            ("3,1p", "a\nb\nc\nd\n", "c\n"),
+            ("1,/a/p", "a\nb\na\nb\n", "a\nb\na\n"),
+            ("/a/,/a/p", "a\nb\na\nb\na\n", "a\nb\na\na\n"),
+            ("2!p", "a\nb\nc\n", "a\nc\n"),
+        ];
+
+        for (script, input, output) in test_data {
+            sed_test(&["-n", "-e", script], input, output, "", 0);
+        }
+    }
+
+    #[test]
+    fn test_address_extensions() {
+        let test_data = [
+            // correct
+            ("0,/a/p", "a\nb\na\n", "a\n", ""),
+            ("0,/b/p", "a\nb\na\n", "a\nb\n", ""),
+            ("0~2p", "a\nb\nc\nd\ne\n", "b\nd\n", ""),
+            ("1~3p", "a\nb\nc\nd\ne\n", "a\nd\n", ""),
+            ("2~0p", "a\nb\nc\n", "b\n", ""),
+            ("/b/,+1p", "a\nb\nc\nd\nb\n", "b\nc\nb\n", ""),
+            ("2,+0p", "a\nb\nc\n", "b\n", ""),
+            // wrong
+            (
+                "0,3p",
+                "a\n",
+                "",
+                "sed: address lower bound must be bigger than 0 (line: 0, col: 4)\n",
+            ),
+            (
+                "1~p",
+                "a\n",
+                "",
+                "sed: expected number after '~' (line: 0, col: 2)\n",
+            ),
+        ];
+
+        for (script, input, output, err) in test_data {
+            sed_test(
+                &["--extensions", "-n", "-e", script],
+                input,
+                output,
+                err,
+                !err.is_empty() as i32,
+            );
+        }
+
+        // Without the flag they are not addresses
+        sed_test(
+            &["-n", "-e", "0,/a/p"],
+            "a\n",
+            "",
+            "sed: address lower bound must be bigger than 0 (line: 0, col: 6)\n",
+            1,
+        );
+    }
+
     #[test]
     fn test_block() {
         let test_data = [
@@ -1711,24 +1778,6 @@ mod tests {
                 "",
                 "sed: n and g flags can't be used together (line: 0, col: 9)\n",
             ),
-            (
-                "s/b/r/w./REA;DME.md",
-                "abc\nbbb\nbcb\nrbt",
-                "",
-                "sed: commands must be delimited with ';' (line: 0, col: 15)\n",
-            ),
-            (
-                "s/b/r/w ./REA;DME.md",
-                "abc\nbbb\nbcb\nrbt",
-                "",
-                "sed: commands must be delimited with ';' (line: 0, col: 16)\n",
-            ),
-            (
-                "s/b/r/w ./REA;DME.md p",
-                "abc\nbbb\nbcb\nrbt",
-                "",
-                "sed: commands must be delimited with ';' (line: 0, col: 16)\n",
-            ),
             (
                 "s/b/r/6gpw ./tests/sed/assets/r",
                 "abc\nbbb\nbcb\nrbt",
@@ -1829,19 +1878,19 @@ mod tests {
             ("w atyfv", "abc\ncdf\n", "abc\ncdf\n", ""),
             ("w./tests/sed/assets/r", "", "", ""),
             ("w./tests/sed/assets/newfile", "a\n", "a\n", ""),
-            ("w ; h", "abc\ncdf\n", "abc\ncdf\n", ""),
+            ("w\nh", "abc\ncdf\n", "abc\ncdf\n", ""),
             // wrong
             (
                 "w./tests/s\x04ed/assets/abc",
                 "a\n",
                 "",
-                "sed: read stdin: can't find './tests/s\u{4}ed/assets/abc': no such file or directory (os error 2)\n",
+                "sed: can't open './tests/s\u{4}ed/assets/abc': no such file or directory (os error 2)\n",
             ),
             (
                 "w./tests/ard/assets/abc",
                 "a\n",
                 "",
-                "sed: read stdin: can't find './tests/ard/assets/abc': no such file or directory (os error 2)\n",
+                "sed: can't open './tests/ard/assets/abc': no such file or directory (os error 2)\n",
             ),
         ];
 
@@ -1850,6 +1899,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_w_files() {
+        let dir = std::env::temp_dir().join(format!("sed_w_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wfile = dir.join("out");
+        let wfile = wfile.to_str().unwrap();
+
+        // Lines written by w and the w flag of s share the file, which is truncated first
+        std::fs::write(wfile, "old\n").unwrap();
+        let w = format!("/b/w {wfile}");
+        let s = format!("s/c/C/w {wfile}");
+        sed_test(&["-n", "-e", &w, "-e", &s], "abc\nbcd\ncde\n", "", "", 0);
+        assert_eq!(
+            std::fs::read_to_string(wfile).unwrap(),
+            "abc\nabC\nbcd\nbCd\nCde\n"
+        );
+
+        // The file is created even if nothing is written to it
+        std::fs::write(wfile, "old\n").unwrap();
+        sed_test(&["-n", "-e", &format!("/z/w {wfile}")], "abc\n", "", "", 0);
+        assert_eq!(std::fs::read_to_string(wfile).unwrap(), "");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filename_runs_to_end_of_line() {
+        let dir = std::env::temp_dir().join(format!("sed_filename_eol_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wfile = dir.join("out");
+        let wfile = wfile.to_str().unwrap();
+
+        // The newline ends the filename and the command
+        let script = format!("1w {wfile}\n2p");
+        sed_test(&["-n", &script], "a\nb\n", "b\n", "", 0);
+        assert_eq!(std::fs::read_to_string(wfile).unwrap(), "a\n");
+
+        let script = format!("s/a/A/w {wfile}\n$p");
+        sed_test(&["-n", "-e", &script], "a\nb\n", "b\n", "", 0);
+        assert_eq!(std::fs::read_to_string(wfile).unwrap(), "A\n");
+
+        // A ';' is part of the filename
+        let semicolon = dir.join("a;p");
+        sed_test(
+            &["-n", "-e", &format!("w {}", semicolon.to_str().unwrap())],
+            "a\n",
+            "",
+            "",
+            0,
+        );
+        assert_eq!(std::fs::read_to_string(&semicolon).unwrap(), "a\n");
+
+        let script_file = dir.join("script");
+        std::fs::write(&script_file, format!("1r {ABC_FILE}\n2p\n")).unwrap();
+        sed_test(
+            &["-f", script_file.to_str().unwrap()],
+            "x\ny\n",
+            "x\nabc\ny\ny\n",
+            "",
+            0,
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_appended_output_order() {
+        let test_data = [
+            (
+                ["-e", "r ./tests/sed/assets/abc", "-e", "a\\text"],
+                "l1\nl2",
+                "l1\nabc\ntext\nl2\nabc\ntext\n",
+            ),
+            (
+                ["-e", "r ./tests/sed/assets/abc", "-e", "n"],
+                "l1\nl2\nl3\n",
+                "l1\nabc\nl2\nl3\nabc\n",
+            ),
+            (
+                ["-e", "r ./tests/sed/assets/abc", "-e", "$!d"],
+                "l1\nl2\n",
+                "abc\nl2\nabc\n",
+            ),
+            (
+                ["-e", "r ./tests/sed/assets/abc", "-e", "2q"],
+                "l1\nl2\nl3\n",
+                "l1\nabc\nl2\nabc\n",
+            ),
+        ];
+
+        for (args, input, output) in test_data {
+            sed_test(&args, input, output, "", 0);
+        }
+    }
+
+    #[test]
+    fn test_upper_r_and_upper_w() {
+        let test_data = [
+            (
+                "R ./tests/sed/assets/script_some_newlines",
+                "a\nb\nc\n",
+                "a\ns/a/ab/g;\nb\ns/b/bc/g;\nc\ns/c/ca/g\n",
+            ),
+            ("R ./tests/sed/assets/abc", "a\nb\n", "a\nabc\nb\n"),
+            ("R ./tests/sed/assets/missing", "a\nb\n", "a\nb\n"),
+            ("N;W /dev/stdout", "a\nb\n", "a\na\nb\n"),
+            ("w /dev/stdout", "a\nb\n", "a\na\nb\nb\n"),
+        ];
+
+        for (script, input, output) in test_data {
+            sed_test(&["--extensions", "-e", script], input, output, "", 0);
+        }
+
+        // Without the flag they are not commands
+        sed_test(
+            &["-e", "R ./tests/sed/assets/abc"],
+            "a\n",
+            "",
+            "sed: unknown character 'R' (line: 0, col: 1)\n",
+            1,
+        );
+    }
+
     #[test]
     fn test_x() {
         let test_data = [
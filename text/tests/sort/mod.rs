@@ -71,7 +71,7 @@ fn test_n8a() {
 
 #[test]
 fn test_n8b() {
-    sort_test(&["-n", "-k1,1"], ".0b\n.0a\n", ".0b\n.0a\n", 0, "");
+    sort_test(&["-n", "-k1,1"], ".0b\n.0a\n", ".0a\n.0b\n", 0, "");
 }
 
 #[test]
@@ -81,22 +81,22 @@ fn test_n9a() {
 
 #[test]
 fn test_n9b() {
-    sort_test(&["-n", "-k1,1"], ".000b\n.000a\n", ".000b\n.000a\n", 0, "");
+    sort_test(&["-n", "-k1,1"], ".000b\n.000a\n", ".000a\n.000b\n", 0, "");
 }
 
 #[test]
 fn test_n10a() {
-    sort_test(&["-n", "-k1,1"], ".00a\n.000b\n", ".00a\n.000b\n", 0, "");
+    sort_test(&["-n", "-k1,1"], ".00a\n.000b\n", ".000b\n.00a\n", 0, "");
 }
 
 #[test]
 fn test_n10b() {
-    sort_test(&["-n", "-k1,1"], ".00b\n.000a\n", ".00b\n.000a\n", 0, "");
+    sort_test(&["-n", "-k1,1"], ".00b\n.000a\n", ".000a\n.00b\n", 0, "");
 }
 
 #[test]
 fn test_n11a() {
-    sort_test(&["-n", "-k1,1"], ".01a\n.010\n", ".01a\n.010\n", 0, "");
+    sort_test(&["-n", "-k1,1"], ".01a\n.010\n", ".010\n.01a\n", 0, "");
 }
 
 #[test]
@@ -577,6 +577,53 @@ fn test_22a() {
     );
 }
 
+#[test]
+fn test_key_global_reverse() {
+    sort_test(&["-r", "-k1", "-"], "b\na\nc\n", "c\nb\na\n", 0, "");
+}
+
+#[test]
+fn test_key_modifier_on_end() {
+    sort_test(&["-k1,1r", "-"], "a\nb\n", "b\na\n", 0, "");
+}
+
+#[test]
+fn test_key_modifiers_override_global() {
+    sort_test(&["-n", "-k2f,2", "-"], "x 10\ny 9\n", "x 10\ny 9\n", 0, "");
+}
+
+#[test]
+fn test_key_blanks_on_start() {
+    sort_test(&["-k2b,2", "-"], "y  b\nx a\n", "x a\ny  b\n", 0, "");
+    sort_test(&["-k2,2", "-"], "y  b\nx a\n", "y  b\nx a\n", 0, "");
+}
+
+#[test]
+fn test_key_dictionary_order() {
+    sort_test(&["-k1.2,1.2d", "-"], "x a\nx-b\n", "x-b\nx a\n", 0, "");
+}
+
+#[test]
+fn test_more_than_two_keys() {
+    sort_test(
+        &["-k1,1", "-k2,2", "-k3,3n", "-"],
+        "a b 10\na b 9\na a 11\n",
+        "a a 11\na b 9\na b 10\n",
+        0,
+        "",
+    );
+}
+
+#[test]
+fn test_last_resort_keeps_reverse() {
+    sort_test(&["-r", "-k1,1f", "-"], "b\nB\na\n", "a\nb\nB\n", 0, "");
+}
+
+#[test]
+fn test_invalid_key_modifier() {
+    sort_test(&["-k1x", "-"], "", "", 1, "invalid key modifier 'x'\n");
+}
+
 #[test]
 fn test_neg_nls() {
     sort_test(&["-n", "-"], "-1\n-9\n", "-9\n-1\n", 0, "");
@@ -613,3 +660,56 @@ fn test_files_sort_2() {
             "",
         );
 }
+
+#[test]
+fn test_unique_keeps_one_line_of_each_key() {
+    sort_test(&["-u"], "b\na\nb\n", "a\nb\n", 0, "");
+    sort_test(&["-u", "-k1,1"], "a 2\na 1\nb 1\n", "a 2\nb 1\n", 0, "");
+    sort_test(&["-u", "-k2,2"], "a 2\nb 1\nc 1\n", "b 1\na 2\n", 0, "");
+    sort_test(&["-u", "-f"], "B\na\nb\nA\n", "a\nB\n", 0, "");
+    sort_test(&["-u", "-n"], "01\n1\n2\n1.0\n", "01\n2\n", 0, "");
+}
+
+#[test]
+fn test_last_resort_comparison() {
+    sort_test(&["-k1,1"], "a 2\na 1\nb 1\n", "a 1\na 2\nb 1\n", 0, "");
+    sort_test(&["-f"], "b\nB\na\nA\n", "A\na\nB\nb\n", 0, "");
+    sort_test(&["-n"], "1.0\n01\n1\n", "01\n1\n1.0\n", 0, "");
+    sort_test(
+        &["-r", "-k1,1"],
+        "a 1\na 2\nb 1\n",
+        "b 1\na 2\na 1\n",
+        0,
+        "",
+    );
+}
+
+#[test]
+fn test_stable() {
+    sort_test(
+        &["-s", "-k1,1"],
+        "a 2\na 1\nb 1\n",
+        "a 2\na 1\nb 1\n",
+        0,
+        "",
+    );
+    sort_test(&["-s", "-f"], "b\nB\na\nA\n", "a\nA\nb\nB\n", 0, "");
+    sort_test(
+        &["-s", "-r", "-k1,1"],
+        "a 1\na 2\nb 1\n",
+        "b 1\na 1\na 2\n",
+        0,
+        "",
+    );
+}
+
+#[test]
+fn test_ignore_leading_blanks_without_key() {
+    sort_test(&["-b"], "  b\na\n c\n", "a\n  b\n c\n", 0, "");
+    sort_test(&[], "  b\na\n c\n", "  b\n c\na\n", 0, "");
+}
+
+#[test]
+fn test_field_separator_without_key() {
+    sort_test(&["-t:"], "b:1\na:2\nab\n", "a:2\nab\nb:1\n", 0, "");
+}
@@ -8,7 +8,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
+use plib::testing::{
+    run_test, run_test_u8, run_test_with_options, TestOptions, TestPlan, TestPlanU8, TestStdin,
+};
 
 fn tail_test(args: &[&str], test_data: &str, expected_output: &str) {
     let str_args = args.iter().map(|st| (*st).to_owned()).collect::<Vec<_>>();
@@ -235,6 +237,12 @@ fn test_tail_zero_bytes_and_zero_lines() {
     tail_test(&["-n", "-0"], INPUT, "");
 }
 
+#[test]
+fn test_tail_c_suffix() {
+    let input = "0123456789".repeat(200);
+    tail_test(&["-c", "1b"], &input, &input[input.len() - 512..]);
+}
+
 #[test]
 fn test_tail_c_and_n() {
     tail_test_failure(
@@ -246,3 +254,144 @@ fn test_tail_c_and_n() {
         "tail: options '-c' and '-n' cannot be used together\n",
     );
 }
+
+fn tail_test_usage_error(args: &[&str], expected_stderr: &str) {
+    let str_args = args.iter().map(|st| (*st).to_owned()).collect::<Vec<_>>();
+
+    run_test(TestPlan {
+        cmd: "tail".to_owned(),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: expected_stderr.to_owned(),
+        expected_exit_code: 2_i32,
+    });
+}
+
+#[test]
+fn test_tail_malformed_counts() {
+    tail_test_usage_error(
+        &["-n", "1x"],
+        "error: invalid value '1x' for '--lines <LINES>': invalid number\n\n\
+         For more information, try '--help'.\n",
+    );
+    tail_test_usage_error(
+        &["-n", "+"],
+        "error: invalid value '+' for '--lines <LINES>': invalid number\n\n\
+         For more information, try '--help'.\n",
+    );
+    tail_test_usage_error(
+        &["-n", ""],
+        "error: invalid value '' for '--lines <LINES>': the number must not be empty\n\n\
+         For more information, try '--help'.\n",
+    );
+    tail_test_usage_error(
+        &["-c", "-2x"],
+        "error: invalid value '-2x' for '--bytes <BYTES>': invalid suffix 'x'\n\n\
+         For more information, try '--help'.\n",
+    );
+    tail_test_usage_error(
+        &["-c", "+99999999999999999999999"],
+        "error: invalid value '+99999999999999999999999' for '--bytes <BYTES>': value too large\n\n\
+         For more information, try '--help'.\n",
+    );
+}
+
+#[test]
+fn test_tail_counts_larger_than_input() {
+    tail_test(&["-c", "1T"], "abcd", "abcd");
+    tail_test(&["-n", "99999999999999"], "a\nb\n", "a\nb\n");
+}
+
+#[test]
+fn test_tail_multiple_files_headers() {
+    let files = ["tests/assets/empty_line.txt", "tests/assets/test_file.txt"];
+    // the files don't end with a newline, so the blank line before the
+    // second header only ends the last line of the first file
+    tail_test(
+        &["-n", "1", files[0], files[1]],
+        "",
+        "==> tests/assets/empty_line.txt <==\nline 3\n==> tests/assets/test_file.txt <==\n17",
+    );
+    tail_test(&["-n", "1", "-q", files[0], files[1]], "", "line 317");
+    tail_test(
+        &["-n", "1", "-v", files[0]],
+        "",
+        "==> tests/assets/empty_line.txt <==\nline 3",
+    );
+    tail_test(&["-n", "1", "-v", "-q", files[0], files[1]], "", "line 317");
+    tail_test(&["-c", "2", "-v", "-"], "abc", "==> standard input <==\nbc");
+}
+
+#[test]
+fn test_tail_c_seekable_stdin() {
+    run_test_with_options(
+        TestPlan {
+            cmd: "tail".to_owned(),
+            args: vec!["-c".to_owned(), "8".to_owned()],
+            stdin_data: String::new(),
+            expected_out: "15\n16\n17".to_owned(),
+            expected_err: String::new(),
+            expected_exit_code: 0_i32,
+        },
+        TestOptions {
+            stdin: Some(TestStdin::File("tests/assets/test_file.txt".into())),
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn test_tail_follow_appended_data() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+
+    let dir = std::env::temp_dir().join(format!("tail_follow_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("log");
+    std::fs::write(&path, "1\n2\n").unwrap();
+
+    let mut child = Command::new(plib::testing::test_bin_path("tail"))
+        .args(["-n", "1", "-f"])
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // Don't hang if the appended data never shows up
+    let pid = child.id() as libc::pid_t;
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(30));
+        unsafe { libc::kill(pid, libc::SIGKILL) };
+    });
+
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "2\n");
+
+    let mut log = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    log.write_all(b"3\n").unwrap();
+
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "3\n");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_tail_from_start_of_file_operand() {
+    let file = "tests/assets/test_file.txt";
+    let contents = std::fs::read_to_string(file).unwrap();
+    tail_test(&["-c", "+10", file], "", &contents[9..]);
+    tail_test(&["-c", "+1", file], "", &contents);
+    tail_test(&["-c", "+100000", file], "", "");
+    let third_line = contents.match_indices('\n').nth(1).unwrap().0 + 1;
+    tail_test(&["-n", "+3", file], "", &contents[third_line..]);
+}
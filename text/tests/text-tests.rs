@@ -22,6 +22,7 @@ mod sed;
 mod sort;
 mod tail;
 mod tr;
+mod tsort;
 mod unexpand;
 mod uniq;
 mod wc;
@@ -8,7 +8,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, run_test_u8, TestPlan, TestPlanU8};
+use plib::testing::{
+    run_test, run_test_u8, run_test_with_options, TestOptions, TestPlan, TestPlanU8, TestStdin,
+};
 
 fn tr_test_binary(args: &[&str], test_data: &[u8], expected_output: &[u8]) {
     let str_args = args
@@ -277,6 +279,53 @@ fn tr_dncase() {
     tr_test(&["[:upper:]", "[:lower:]"], "abcxyzABCXYZ", "abcxyzabcxyz");
 }
 
+fn tr_test_in_utf8_locale(args: &[&str], test_data: &str, expected_output: &str) {
+    run_test_with_options(
+        TestPlan {
+            cmd: "tr".to_owned(),
+            args: args.iter().map(|st| st.to_string()).collect(),
+            stdin_data: test_data.to_owned(),
+            expected_out: expected_output.to_owned(),
+            expected_err: String::new(),
+            expected_exit_code: 0_i32,
+        },
+        TestOptions::default().env("LC_ALL", "C.UTF-8"),
+    );
+}
+
+#[test]
+fn tr_upcase_multibyte() {
+    tr_test_in_utf8_locale(
+        &["[:lower:]", "[:upper:]"],
+        "h\u{e9}llo w\u{f6}rld \u{3b1}\u{3b2}\u{3b3}\n",
+        "H\u{c9}LLO W\u{d6}RLD \u{391}\u{392}\u{393}\n",
+    );
+}
+
+#[test]
+fn tr_dncase_multibyte() {
+    tr_test_in_utf8_locale(
+        &["[:upper:]", "[:lower:]"],
+        "\u{c9}T\u{c9} \u{416}\u{423}\u{41a} Stra\u{df}e\n",
+        "\u{e9}t\u{e9} \u{436}\u{443}\u{43a} stra\u{df}e\n",
+    );
+}
+
+#[test]
+fn tr_case_classes_are_ascii_in_c_locale() {
+    run_test_with_options(
+        TestPlan {
+            cmd: "tr".to_owned(),
+            args: vec!["[:lower:]".to_owned(), "[:upper:]".to_owned()],
+            stdin_data: "h\u{e9}llo\n".to_owned(),
+            expected_out: "H\u{e9}LLO\n".to_owned(),
+            expected_err: String::new(),
+            expected_exit_code: 0_i32,
+        },
+        TestOptions::default().env("LC_ALL", "C"),
+    );
+}
+
 #[test]
 fn tr_rep_2() {
     tr_test(&["a[b*512]c", "1[x*]2"], "abc", "1x2");
@@ -793,3 +842,69 @@ Only one string may be given when deleting without squeezing repeats.
 ",
     );
 }
+
+#[test]
+fn tr_delete_non_utf_8() {
+    run_test_with_options(
+        TestPlan {
+            cmd: "tr".to_owned(),
+            args: vec!["-d".to_owned(), r"\377\376".to_owned()],
+            stdin_data: String::new(),
+            expected_out: "abc\n".to_owned(),
+            expected_err: String::new(),
+            expected_exit_code: 0_i32,
+        },
+        TestOptions {
+            stdin: Some(TestStdin::Bytes(b"a\xFFb\xFEc\xFF\n".to_vec())),
+            ..Default::default()
+        },
+    );
+}
+
+fn spawn_tr_with_stdout(stdout: std::process::Stdio) -> std::process::Child {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(plib::testing::test_bin_path("tr"))
+        .args(["a-z", "A-Z"])
+        .stdin(Stdio::piped())
+        .stdout(stdout)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Much more than a pipe holds; tr may exit before reading all of it
+    let mut stdin = child.stdin.take().unwrap();
+    std::thread::spawn(move || {
+        let _ = stdin.write_all(&vec![b'x'; 1024 * 1024]);
+    });
+
+    child
+}
+
+#[test]
+fn tr_closed_stdout_exits_quietly() {
+    let mut child = spawn_tr_with_stdout(std::process::Stdio::piped());
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn tr_write_error_is_reported() {
+    let dev_full = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/full")
+        .unwrap();
+    let child = spawn_tr_with_stdout(dev_full.into());
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("tr: No space left on device"),
+        "{stderr}"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,70 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::testing::{run_test, TestPlan};
+
+fn tsort_test(args: &[&str], stdin: &str, expected_out: &str, expected_err: &str, code: i32) {
+    run_test(TestPlan {
+        cmd: String::from("tsort"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::from(stdin),
+        expected_out: String::from(expected_out),
+        expected_err: String::from(expected_err),
+        expected_exit_code: code,
+    });
+}
+
+#[test]
+fn tsort_order() {
+    tsort_test(&[], "a b\nb c\na d d c\n", "a\nb\nd\nc\n", "", 0);
+}
+
+#[test]
+fn tsort_lone_items() {
+    // A pair of the same item only says that it exists
+    tsort_test(&["-"], "x x b a\n\ty y", "x\nb\ny\na\n", "", 0);
+}
+
+#[test]
+fn tsort_empty() {
+    tsort_test(&[], "", "", "", 0);
+}
+
+#[test]
+fn tsort_odd_tokens() {
+    tsort_test(
+        &[],
+        "a b c",
+        "",
+        "tsort: -: input contains an odd number of tokens\n",
+        1,
+    );
+}
+
+#[test]
+fn tsort_cycle() {
+    tsort_test(
+        &[],
+        "a b\nb c\nc b\nc d\n",
+        "a\nb\nc\nd\n",
+        "tsort: -: input contains a loop:\ntsort: b\ntsort: c\n",
+        1,
+    );
+}
+
+#[test]
+fn tsort_missing_file() {
+    tsort_test(
+        &["/nonexistent/file"],
+        "",
+        "",
+        "tsort: /nonexistent/file: No such file or directory\n",
+        1,
+    );
+}
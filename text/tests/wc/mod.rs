@@ -43,3 +43,46 @@ fn wc_two() {
     wc_test(&["-l"], "x y\n", "1\n");
     wc_test(&["-w"], "x y\n", "2\n");
 }
+
+#[test]
+fn wc_missing_file_continues() {
+    run_test(TestPlan {
+        cmd: String::from("wc"),
+        args: vec![
+            String::from("-l"),
+            String::from("tests/wc/missing"),
+            String::from("tests/assets/in_seq"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::from("5 tests/assets/in_seq\n5 total\n"),
+        expected_err: String::from("wc: tests/wc/missing: No such file or directory\n"),
+        expected_exit_code: 1,
+    });
+}
+
+#[test]
+fn wc_multiple_files_align_with_total() {
+    run_test(TestPlan {
+        cmd: String::from("wc"),
+        args: vec![
+            String::from("-c"),
+            String::from("tests/assets/in_seq"),
+            String::from("tests/assets/test_file.txt"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::from(
+            " 11 tests/assets/in_seq\n148 tests/assets/test_file.txt\n159 total\n",
+        ),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn wc_max_line_length() {
+    wc_test(&["-L"], "", "0\n");
+    wc_test(&["-L"], "ab\nabcd\nabc", "4\n");
+    wc_test(&["-L"], "a\tb\n", "9\n");
+    wc_test(&["-L"], "h\u{e9}llo\n", "5\n");
+    wc_test(&["-lL"], "ab\nabcd\n", "       2        4\n");
+}
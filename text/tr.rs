@@ -1,5 +1,4 @@
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use setup::{ForRemoval, ForTranslation};
 use std::error::Error;
 use std::process;
@@ -288,9 +287,7 @@ fn tr(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    plib::i18n::init();
 
     let args = Args::parse();
 
@@ -314,6 +311,7 @@ mod parsing {
     use std::str::Chars;
 
     use crate::setup::FullChar;
+    use plib::ctype;
 
     #[derive(Clone)]
     pub enum CharRepetition {
@@ -928,6 +926,25 @@ mod parsing {
         Ok(())
     }
 
+    /// Lowercase characters of the current locale paired with their
+    /// uppercase counterparts, ordered by the lowercase character.
+    ///
+    /// "[:lower:]" and "[:upper:]" expand to the two sides of these pairs, so
+    /// that translating one class into the other maps every character to
+    /// its other case, multibyte characters included. Characters whose case
+    /// mapping doesn't go both ways, such as 'ß', are in neither class.
+    fn case_pairs() -> Vec<(char, char)> {
+        (0_u32..=u32::from(char::MAX))
+            .filter_map(char::from_u32)
+            .filter(|&lower| ctype::is_lower(lower))
+            .filter_map(|lower| {
+                let upper = ctype::to_upper(lower);
+                (upper != lower && ctype::is_upper(upper) && ctype::to_lower(upper) == lower)
+                    .then_some((lower, upper))
+            })
+            .collect()
+    }
+
     fn expand_character_class(
         square_bracket_constructs_buffer: &[char],
         operand_vec: &mut Vec<Operand>,
@@ -952,8 +969,14 @@ mod parsing {
                 .collect::<Vec<_>>(),
             "alpha" => ('A'..='Z').chain('a'..='z').collect::<Vec<_>>(),
             "digit" => ('0'..='9').collect::<Vec<_>>(),
-            "lower" => ('a'..='z').collect::<Vec<_>>(),
-            "upper" => ('A'..='Z').collect::<Vec<_>>(),
+            "lower" => case_pairs()
+                .into_iter()
+                .map(|(lower, _)| lower)
+                .collect::<Vec<_>>(),
+            "upper" => case_pairs()
+                .into_iter()
+                .map(|(_, upper)| upper)
+                .collect::<Vec<_>>(),
             "space" => vec![' ', '\t', '\n', '\r', '\x0b', '\x0c'],
             "blank" => vec![' ', '\t'],
             "cntrl" => (0_u8..=31_u8)
@@ -2036,7 +2059,7 @@ mod transformation {
         // TODO
         // Improve this
         let mut stdin_lock = io::stdin().lock();
-        let mut stdout_lock = io::stdout().lock();
+        let mut stdout_lock = plib::io::stdout();
 
         loop {
             let buf = &mut input[leftover_bytes..];
@@ -2069,6 +2092,8 @@ mod transformation {
             }
         }
 
+        stdout_lock.finish()?;
+
         Ok(())
     }
 
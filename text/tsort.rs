@@ -7,77 +7,193 @@
 // SPDX-License-Identifier: MIT
 //
 
-use std::io::{self, BufRead};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream_opt;
-use topological_sort::TopologicalSort;
+use plib::diag::error_message;
+use plib::io::input_stream;
+use plib::tdiag;
 
 /// tsort - topological sort
 #[derive(Parser)]
 #[command(version, about)]
 struct Args {
-    /// File to read as input.
+    /// File to read as input; standard input if '-' or omitted.
     file: Option<PathBuf>,
 }
 
-fn tsort_file(pathname: &Option<PathBuf>) -> io::Result<()> {
-    let file = input_stream_opt(pathname)?;
-    let mut reader = io::BufReader::new(file);
+/// The partial order read from the input: items in the order they first
+/// appear, and for each, the items that follow it.
+#[derive(Default)]
+struct Graph {
+    items: Vec<Vec<u8>>,
+    index: HashMap<Vec<u8>, usize>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<usize>,
+}
 
-    let mut ts = TopologicalSort::<String>::new();
-    let mut sv: Vec<String> = Vec::new();
+impl Graph {
+    fn item(&mut self, name: &[u8]) -> usize {
+        if let Some(&i) = self.index.get(name) {
+            return i;
+        }
+        let i = self.items.len();
+        self.items.push(name.to_vec());
+        self.index.insert(name.to_vec(), i);
+        self.successors.push(Vec::new());
+        self.predecessors.push(0);
+        i
+    }
 
-    loop {
-        let mut buffer = String::new();
-        let n_read = reader.read_line(&mut buffer)?;
-        if n_read == 0 {
-            break;
+    /// Adds the pair `a b`: `a` comes before `b`. A pair of the same
+    /// item only says that it exists.
+    fn add_pair(&mut self, a: &[u8], b: &[u8]) {
+        let (a, b) = (self.item(a), self.item(b));
+        if a != b {
+            self.successors[a].push(b);
+            self.predecessors[b] += 1;
         }
+    }
 
-        for token in buffer.split_whitespace() {
-            sv.push(String::from(token));
+    /// Finds a cycle among the items not yet written, following the
+    /// first remaining successor of each, in the order followed.
+    fn find_cycle(&self, done: &[bool], start: usize) -> Vec<usize> {
+        let mut path = vec![start];
+        let mut on_path = HashMap::from([(start, 0)]);
+        loop {
+            let last = *path.last().unwrap();
+            let next = self.successors[last]
+                .iter()
+                .copied()
+                .find(|&next| !done[next])
+                .expect("an item left over has an unwritten predecessor");
+            if let Some(&pos) = on_path.get(&next) {
+                return path.split_off(pos);
+            }
+            on_path.insert(next, path.len());
+            path.push(next);
+        }
+    }
 
-            if sv.len() == 2 {
-                if sv[0] == sv[1] {
-                    ts.insert(String::from(&sv[0]));
-                } else {
-                    ts.add_dependency(String::from(&sv[0]), String::from(&sv[1]));
+    /// Sorts the items, reporting each cycle found. A cycle is broken by
+    /// writing its item that came first in the input as if its
+    /// predecessors were written.
+    fn sort(mut self, name: &str) -> (Vec<Vec<u8>>, bool) {
+        let mut order = Vec::with_capacity(self.items.len());
+        let mut done = vec![false; self.items.len()];
+        let mut queue: VecDeque<usize> = (0..self.items.len())
+            .filter(|&i| self.predecessors[i] == 0)
+            .collect();
+        let mut cycles = false;
+
+        while order.len() < self.items.len() {
+            let Some(i) = queue.pop_front() else {
+                // Every item left has an unwritten predecessor, so
+                // following predecessors from any of them leads around a
+                // cycle
+                let start = (0..self.items.len()).find(|&i| !done[i]).unwrap();
+                let reversed = self.reversed(&done);
+                let mut cycle = reversed.find_cycle(&done, start);
+                cycle.reverse();
+                let earliest = cycle.iter().enumerate().min_by_key(|&(_, &i)| i).unwrap().0;
+                cycle.rotate_left(earliest);
+
+                tdiag!("{}: input contains a loop:", name);
+                for &item in &cycle {
+                    plib::diag!("{}", String::from_utf8_lossy(&self.items[item]));
+                }
+                cycles = true;
+                let first = cycle[0];
+                self.predecessors[first] = 0;
+                queue.push_back(first);
+                continue;
+            };
+            if done[i] {
+                continue;
+            }
+            done[i] = true;
+            order.push(i);
+            for &next in &self.successors[i] {
+                if !done[next] {
+                    self.predecessors[next] -= 1;
+                    if self.predecessors[next] == 0 {
+                        queue.push_back(next);
+                    }
                 }
-                sv.clear();
             }
         }
-    }
 
-    for s in ts {
-        println!("{}", s);
+        let items = order
+            .into_iter()
+            .map(|i| std::mem::take(&mut self.items[i]))
+            .collect();
+        (items, cycles)
     }
 
-    Ok(())
+    /// The graph with its edges reversed, for finding a cycle through
+    /// the predecessors of an item.
+    fn reversed(&self, done: &[bool]) -> Graph {
+        let mut reversed = Graph {
+            successors: vec![Vec::new(); self.items.len()],
+            ..Graph::default()
+        };
+        for (i, successors) in self.successors.iter().enumerate() {
+            if done[i] {
+                continue;
+            }
+            for &next in successors {
+                reversed.successors[next].push(i);
+            }
+        }
+        reversed
+    }
 }
 
-fn pathname_display(path: &Option<PathBuf>) -> String {
-    match path {
-        None => String::from("stdin"),
-        Some(p) => p.display().to_string(),
+fn tsort_file(pathname: &PathBuf, name: &str) -> io::Result<bool> {
+    let mut input = Vec::new();
+    input_stream(pathname, true)?.read_to_end(&mut input)?;
+
+    let tokens: Vec<&[u8]> = input
+        .split(u8::is_ascii_whitespace)
+        .filter(|token| !token.is_empty())
+        .collect();
+    if tokens.len() % 2 != 0 {
+        return Err(io::Error::other(plib::tr!(
+            "input contains an odd number of tokens"
+        )));
+    }
+
+    let mut graph = Graph::default();
+    for pair in tokens.chunks(2) {
+        graph.add_pair(pair[0], pair[1]);
+    }
+    let (items, cycles) = graph.sort(name);
+
+    let mut stdout = plib::io::stdout();
+    for item in items {
+        stdout.write_all(&item)?;
+        stdout.write_all(b"\n")?;
     }
+    stdout.finish()?;
+    Ok(cycles)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let args = Args::parse();
 
-    let mut exit_code = 0;
+    let pathname = args.file.unwrap_or_else(|| PathBuf::from("-"));
+    let name = pathname.display().to_string();
 
-    if let Err(e) = tsort_file(&args.file) {
-        exit_code = 1;
-        eprintln!("{}: {}", pathname_display(&args.file), e);
+    match tsort_file(&pathname, &name) {
+        Ok(false) => std::process::exit(0),
+        Ok(true) => std::process::exit(1),
+        Err(e) => {
+            tdiag!("{}: {}", name, error_message(&e));
+            std::process::exit(1);
+        }
     }
-
-    std::process::exit(exit_code)
 }
@@ -27,7 +27,7 @@ fn unexpand(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         Some(s) => parse_tablist(s)?,
         None => vec![8],
     };
-    let mut stdout = io::stdout();
+    let mut stdout = plib::io::stdout();
 
     if (args.files.len() == 1 && args.files[0] == PathBuf::from("-")) || args.files.is_empty() {
         let reader = io::stdin();
@@ -56,6 +56,8 @@ fn unexpand(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    stdout.finish()?;
+
     Ok(())
 }
 
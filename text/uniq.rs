@@ -79,7 +79,7 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let mut output: Box<dyn Write> = match &args.output_file {
         Some(file) => Box::new(File::create(file)?),
-        None => Box::new(io::stdout()),
+        None => Box::new(plib::io::stdout()),
     };
 
     let lines: Vec<String> = input.lines().collect::<Result<_, _>>()?;
@@ -106,6 +106,7 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(last) = last_line {
         output_result(&mut output, &last, current_count, args)?;
     }
+    output.flush()?;
     Ok(())
 }
 
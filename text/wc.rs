@@ -9,15 +9,15 @@
 
 use std::{
     ffi::OsStr,
-    io::{self, Read},
+    io::{self, BufRead, Write},
     ops::AddAssign,
     path::PathBuf,
 };
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::io::input_stream;
-use plib::BUFSZ;
+use plib::diag::Status;
+use plib::io::MappedReader;
 
 /// wc - word, line, and byte or character count
 #[derive(Parser)]
@@ -39,15 +39,20 @@ struct Args {
     #[arg(short, long)]
     words: bool,
 
+    /// Print the length of the longest line in each file
+    #[arg(short = 'L', long)]
+    max_line_length: bool,
+
     /// Files to read as input.
     files: Vec<PathBuf>,
 }
 
-#[derive(Default)]
+#[derive(Clone, Copy, Default)]
 struct CountInfo {
     words: usize,
     chars: usize,
     nl: usize,
+    max_line: usize,
 }
 
 impl AddAssign for CountInfo {
@@ -55,6 +60,7 @@ impl AddAssign for CountInfo {
         self.words += rhs.words;
         self.chars += rhs.chars;
         self.nl += rhs.nl;
+        self.max_line = self.max_line.max(rhs.max_line);
     }
 }
 
@@ -72,38 +78,46 @@ const fn create_table() -> [bool; 256] {
 
 const BYTE_TABLE: [bool; 256] = create_table();
 
-fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr) -> String {
-    let mut output = String::with_capacity(filename.len() + (3 * 10));
-
+/// Counts selected by the options, in output order.
+fn selected_counts(args: &Args, count: &CountInfo) -> Vec<usize> {
+    let mut counts = Vec::with_capacity(4);
     if args.lines {
-        let only_lines = !args.words && !args.bytes && !args.chars;
-        let numstr = match only_lines {
-            true => format!("{}", count.nl),
-            false => format!("{:>8}", count.nl),
-        };
-        output.push_str(&numstr);
+        counts.push(count.nl);
     }
     if args.words {
-        if !output.is_empty() {
-            output.push(' ');
-        }
-        let only_words = !args.lines && !args.bytes && !args.chars;
-        let numstr = match only_words {
-            true => format!("{}", count.words),
-            false => format!("{:>8}", count.words),
-        };
-        output.push_str(&numstr);
+        counts.push(count.words);
     }
     if args.bytes || args.chars {
+        counts.push(count.chars);
+    }
+    if args.max_line_length {
+        counts.push(count.max_line);
+    }
+    counts
+}
+
+/// Width of the count columns.  Several counts on a line get at least
+/// 8 columns each; all lines share the width of the widest count, so that
+/// the columns of several files and their total line up.
+fn column_width(args: &Args, totals: &CountInfo) -> usize {
+    let counts = selected_counts(args, totals);
+    let widest = counts.iter().map(|n| n.to_string().len()).max();
+    let widest = widest.unwrap_or(1);
+    if counts.len() > 1 {
+        widest.max(8)
+    } else {
+        widest
+    }
+}
+
+fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr, width: usize) -> String {
+    let mut output = String::with_capacity(filename.len() + (3 * 10));
+
+    for n in selected_counts(args, count) {
         if !output.is_empty() {
             output.push(' ');
         }
-        let only_bytechars = !args.lines && !args.words;
-        let numstr = match only_bytechars {
-            true => format!("{}", count.chars),
-            false => format!("{:>8}", count.chars),
-        };
-        output.push_str(&numstr);
+        output.push_str(&format!("{:>width$}", n));
     }
 
     let multi_file = args.files.len() > 1;
@@ -121,19 +135,18 @@ fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr) -> String
 }
 
 fn wc_file_bytes(count: &mut CountInfo, pathname: &PathBuf, chars_mode: bool) -> io::Result<()> {
-    let mut file = input_stream(pathname, false)?;
+    let mut file = MappedReader::open(pathname, false)?;
 
-    let mut buffer = [0; BUFSZ];
     let mut was_space = true;
+    let mut column = 0;
 
     loop {
-        let n_read = file.read(&mut buffer[..])?;
+        let bufslice = file.fill_buf()?;
+        let n_read = bufslice.len();
         if n_read == 0 {
             break;
         }
 
-        let bufslice = &buffer[0..n_read];
-
         if !chars_mode {
             // number of bytes read
             count.chars += n_read;
@@ -147,23 +160,24 @@ fn wc_file_bytes(count: &mut CountInfo, pathname: &PathBuf, chars_mode: bool) ->
             count.nl += (ch_u8 == &10) as usize;
             count.words += (!is_space && was_space) as usize;
             was_space = is_space;
-        }
-    }
 
-    Ok(())
-}
-
-fn wc_file(
-    args: &Args,
-    chars_mode: bool,
-    pathname: &PathBuf,
-    count: &mut CountInfo,
-) -> io::Result<()> {
-    wc_file_bytes(count, pathname, chars_mode)?;
-
-    let output = build_display_str(args, count, pathname.as_os_str());
+            // Line length in display columns: tabs advance to the next
+            // multiple of 8, other control characters take no room, and a
+            // multibyte character counts once, at its first byte.
+            match ch_u8 {
+                b'\n' | b'\r' | 12 => {
+                    count.max_line = count.max_line.max(column);
+                    column = 0;
+                }
+                b'\t' => column += 8 - column % 8,
+                0..=31 | 127 => {}
+                _ => column += ((ch_u8 >> 6) != 0b10) as usize,
+            }
+        }
 
-    println!("{}", output);
+        file.consume(n_read);
+    }
+    count.max_line = count.max_line.max(column);
 
     Ok(())
 }
@@ -178,7 +192,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut chars_mode = false;
 
     // Assign defaults, per POSIX
-    if !args.bytes && !args.lines && !args.chars && !args.words {
+    if !args.bytes && !args.lines && !args.chars && !args.words && !args.max_line_length {
         args.bytes = true;
         args.lines = true;
         args.words = true;
@@ -187,36 +201,52 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         chars_mode = true;
     }
 
-    let mut exit_code = 0;
+    let mut status = Status::new();
     let mut totals = CountInfo::default();
+    let mut out = plib::io::stdout();
+
+    // no operands: input via stdin, named by an empty path
+    let stdin = [PathBuf::new()];
+    let files = if args.files.is_empty() {
+        &stdin[..]
+    } else {
+        &args.files[..]
+    };
 
-    // input via stdin
-    if args.files.is_empty() {
+    // Count everything before printing anything, as the column width
+    // depends on the totals.
+    let mut results = Vec::with_capacity(files.len());
+    for filename in files {
         let mut count = CountInfo::default();
 
-        if let Err(e) = wc_file(&args, chars_mode, &PathBuf::new(), &mut count) {
-            exit_code = 1;
-            eprintln!("stdin: {}", e);
+        match wc_file_bytes(&mut count, filename, chars_mode) {
+            Ok(()) => results.push((filename, count)),
+            Err(e) if filename.as_os_str().is_empty() => status.operand_error("stdin", &e),
+            Err(e) => status.operand_error(filename.display(), &e),
         }
+    }
+    for (_, count) in &results {
+        totals += *count;
+    }
 
-    // input files
-    } else {
-        for filename in &args.files {
-            let mut count = CountInfo::default();
-
-            if let Err(e) = wc_file(&args, chars_mode, filename, &mut count) {
-                exit_code = 1;
-                eprintln!("{}: {}", filename.display(), e);
-            }
-
-            totals += count;
+    let width = column_width(&args, &totals);
+    for (filename, count) in &results {
+        let output = build_display_str(&args, count, filename.as_os_str(), width);
+        if let Err(e) = writeln!(out, "{}", output) {
+            status.error(&e);
         }
     }
 
     if args.files.len() > 1 {
-        let output = build_display_str(&args, &totals, OsStr::new("total"));
-        println!("{}", output);
+        let output = build_display_str(&args, &totals, OsStr::new("total"), width);
+        if let Err(e) = writeln!(out, "{}", output) {
+            status.error(&e);
+        }
+    }
+
+    if let Err(e) = out.finish() {
+        status.error(&e);
     }
 
-    std::process::exit(exit_code)
+    status.exit()
 }
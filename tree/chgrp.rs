@@ -12,7 +12,8 @@ mod common;
 use self::common::error_string;
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use std::{cell::RefCell, ffi::CString, io, os::unix::fs::MetadataExt};
+use plib::userdb;
+use std::{cell::RefCell, io, os::unix::fs::MetadataExt};
 
 /// chgrp - change file group ownership
 #[derive(Parser)]
@@ -138,20 +139,9 @@ fn parse_group(group: &str) -> Result<Option<u32>, String> {
         return Ok(None);
     }
 
-    match group.parse::<u32>() {
-        Ok(gid) => Ok(Some(gid)),
-        Err(_) => {
-            // lookup group by name
-            let group_cstr = CString::new(group).unwrap();
-            let group_st = unsafe { libc::getgrnam(group_cstr.as_ptr()) };
-            if group_st.is_null() {
-                let err_str = gettext!("invalid group: '{}'", group);
-                return Err(err_str);
-            }
-
-            let gid = unsafe { (*group_st).gr_gid };
-            Ok(Some(gid))
-        }
+    match userdb::parse_gid(group) {
+        Some(gid) => Ok(Some(gid)),
+        None => Err(gettext!("invalid group: '{}'", group)),
     }
 }
 
@@ -14,7 +14,7 @@ use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use modestr::ChmodMode;
 use plib::modestr;
-use std::{cell::RefCell, io, os::unix::fs::MetadataExt};
+use std::{cell::Cell, io, os::unix::fs::MetadataExt};
 
 /// chmod - change the file modes
 #[derive(Parser)]
@@ -32,20 +32,33 @@ struct Args {
     files: Vec<String>,
 }
 
-fn chmod_file(filename: &str, mode: &ChmodMode, recurse: bool) -> Result<(), io::Error> {
-    let terminate = RefCell::new(false);
-    let result = RefCell::new(Ok(())); // Either `Ok(())` or the last error encountered
+/// Changes the mode of `filename`, and of the files below it with `recurse`.
+/// Errors are reported as they happen, without stopping; returns whether
+/// there were none.
+fn chmod_file(filename: &str, mode: &ChmodMode, recurse: bool) -> bool {
+    let success = Cell::new(true);
 
     ftw::traverse_directory(
         filename,
         |entry| {
-            if *terminate.borrow() {
-                return Ok(false);
-            }
-
             let md = entry.metadata().unwrap();
             let is_dir = md.is_dir();
 
+            // The symlinks met while walking the tree are left alone, only the
+            // symlinks given as operands are followed
+            let is_operand_symlink = entry.read_link().is_some();
+            if entry.is_symlink() == Some(true) && !is_operand_symlink {
+                return Ok(false);
+            }
+            if md.is_symlink() {
+                eprintln!(
+                    "chmod: {}",
+                    gettext!("cannot operate on dangling symlink '{}'", entry.path())
+                );
+                success.set(false);
+                return Err(());
+            }
+
             let new_mode = match mode {
                 ChmodMode::Absolute(m, num_digits) => {
                     // Done to match the behavior of coreutils chmod:
@@ -63,43 +76,31 @@ fn chmod_file(filename: &str, mode: &ChmodMode, recurse: bool) -> Result<(), io:
                 ChmodMode::Symbolic(s) => modestr::mutate(md.mode(), is_dir, s),
             };
 
-            if md.is_symlink() {
-                // Uses libc::fstatat to check for the validity of the symlink
-                let is_dangling = {
-                    let target_deref_md =
-                        ftw::Metadata::new(entry.dir_fd(), entry.file_name(), true);
-                    target_deref_md.is_err()
-                };
-
-                if is_dangling {
-                    let err_str = gettext!("cannot operate on dangling symlink '{}'", entry.path());
-                    *result.borrow_mut() = Err(io::Error::other(err_str));
-                    *terminate.borrow_mut() = true;
-                    return Err(());
-                } else {
-                    // Symlink permissions are always lrwxrwxrwx
-                    // fchmodat with AT_SYMLINK_NOFOLLOW on Linux would fail
-                    if cfg!(target_os = "linux") {
-                        return Ok(is_dir && recurse);
-                    }
-                }
-            }
-
+            let flags = if is_operand_symlink {
+                0
+            } else {
+                libc::AT_SYMLINK_NOFOLLOW
+            };
             let ret = unsafe {
                 libc::fchmodat(
                     entry.dir_fd(),
                     entry.file_name().as_ptr(),
                     new_mode as libc::mode_t, // Cast for macOS
-                    libc::AT_SYMLINK_NOFOLLOW,
+                    flags,
                 )
             };
 
             if ret != 0 {
                 let e = io::Error::last_os_error();
-
-                *result.borrow_mut() = Err(e);
-                *terminate.borrow_mut() = true;
-                return Err(());
+                eprintln!(
+                    "chmod: {}",
+                    gettext!(
+                        "changing permissions of '{}': {}",
+                        entry.path(),
+                        error_string(&e)
+                    )
+                );
+                success.set(false);
             }
 
             Ok(is_dir && recurse)
@@ -107,9 +108,11 @@ fn chmod_file(filename: &str, mode: &ChmodMode, recurse: bool) -> Result<(), io:
         |_| Ok(()), // No-op
         |entry, error| {
             let e = error.inner();
-            let err_str = gettext!("cannot access '{}': {}", entry.path(), error_string(&e));
-            *result.borrow_mut() = Err(io::Error::other(err_str));
-            *terminate.borrow_mut() = true;
+            eprintln!(
+                "chmod: {}",
+                gettext!("cannot access '{}': {}", entry.path(), error_string(&e))
+            );
+            success.set(false);
         },
         ftw::TraverseDirectoryOpts {
             follow_symlinks_on_args: true, // Default behavior of coreutils chmod with or without -R
@@ -117,7 +120,7 @@ fn chmod_file(filename: &str, mode: &ChmodMode, recurse: bool) -> Result<(), io:
         },
     );
 
-    result.into_inner()
+    success.get()
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -137,9 +140,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // apply the mode to each file
     for filename in &args.files {
-        if let Err(e) = chmod_file(filename, &mode, args.recurse) {
+        if !chmod_file(filename, &mode, args.recurse) {
             exit_code = 1;
-            eprintln!("chmod: {}", error_string(&e));
         }
     }
 
@@ -12,6 +12,7 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::userdb;
 use std::ffi::CString;
 use std::os::unix::fs::MetadataExt;
 use std::path::Path;
@@ -82,38 +83,12 @@ fn chown_file(filename: &str, uid: u32, gid: Option<u32>, recurse: bool) -> Resu
 
 // lookup string group by name, or parse numeric group ID
 fn parse_group(group: &str) -> Result<u32, &'static str> {
-    match group.parse::<u32>() {
-        Ok(gid) => Ok(gid),
-        Err(_) => {
-            // lookup group by name
-            let group_cstr = CString::new(group).unwrap();
-            let group = unsafe { libc::getgrnam(group_cstr.as_ptr()) };
-            if group.is_null() {
-                return Err("group not found");
-            }
-
-            let gid = unsafe { (*group).gr_gid };
-            Ok(gid)
-        }
-    }
+    userdb::parse_gid(group).ok_or("group not found")
 }
 
 // lookup string user by name, or parse numeric user ID
 fn parse_user(user: &str) -> Result<u32, &'static str> {
-    match user.parse::<u32>() {
-        Ok(uid) => Ok(uid),
-        Err(_) => {
-            // lookup user by name
-            let user_cstr = CString::new(user).unwrap();
-            let user = unsafe { libc::getpwnam(user_cstr.as_ptr()) };
-            if user.is_null() {
-                return Err("user not found");
-            }
-
-            let uid = unsafe { (*user).pw_uid };
-            Ok(uid)
-        }
-    }
+    userdb::parse_uid(user).ok_or("user not found")
 }
 
 fn parse_owner_group(owner_group: &str) -> Result<(u32, Option<u32>), &'static str> {
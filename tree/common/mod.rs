@@ -60,11 +60,80 @@ pub struct CopyConfig {
 }
 
 enum CopyResult {
-    CopyingDirectory,
+    /// The contents of the directory are to be copied next. `created` tells
+    /// whether the target directory was created rather than already there.
+    CopyingDirectory {
+        created: bool,
+    },
     CopiedFile,
     Skipped,
 }
 
+/// A source directory whose contents are being copied.
+struct SourceDir {
+    /// Device and inode numbers.
+    identifier: (u64, u64),
+    mode: u32,
+    /// Whether the target directory was created rather than already there.
+    created: bool,
+}
+
+/// Reads the contents of the symbolic link `source`.
+fn read_link(source: &ftw::Entry) -> io::Result<CString> {
+    // Only symbolic links that were followed have been read already
+    if let Some(contents) = source.read_link() {
+        return Ok(contents.to_owned());
+    }
+
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+    let ret = unsafe {
+        libc::readlinkat(
+            source.dir_fd(),
+            source.file_name().as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(ret as usize);
+    Ok(CString::new(buf).unwrap())
+}
+
+/// Whether copying the directory `source` to `target` would copy it into
+/// itself, or into one of its subdirectories. Compares the paths with
+/// symbolic links resolved, as either may lead into the other.
+fn copies_into_itself(source: &Path, target: &Path, follow_source: bool) -> bool {
+    let source_md = if follow_source {
+        fs::metadata(source)
+    } else {
+        fs::symlink_metadata(source)
+    };
+    if !source_md.is_ok_and(|md| md.is_dir()) {
+        return false;
+    }
+
+    let Ok(source) = fs::canonicalize(source) else {
+        return false;
+    };
+    let target = match (target.parent(), target.file_name()) {
+        (Some(parent), Some(file_name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            match fs::canonicalize(parent) {
+                Ok(parent) => parent.join(file_name),
+                Err(_) => return false,
+            }
+        }
+        _ => return false,
+    };
+    target != source && target.starts_with(&source)
+}
+
 // Implements the algorithm for `cp`:
 //
 // https://pubs.opengroup.org/onlinepubs/9699919799/utilities/cp.html
@@ -81,7 +150,9 @@ where
     F: Fn(&str) -> bool,
 {
     let source_md = source.metadata().unwrap();
-    let source_is_symlink = source.is_symlink().unwrap_or(false);
+    // A symbolic link that is not followed, or a dangling one, is copied as
+    // a symbolic link
+    let source_is_symlink = source_md.file_type() == ftw::FileType::SymbolicLink;
     let source_file_type = source_md.file_type();
     let source_is_dir = source_file_type == ftw::FileType::Directory;
 
@@ -175,15 +246,6 @@ where
 
         // 2.e
         if !target_exists {
-            if target.starts_with(PathBuf::from(format!("{}", source.path()))) {
-                let err_str = gettext!(
-                    "cannot copy a directory, '{}', into itself, '{}'",
-                    source.path(),
-                    target.display()
-                );
-                return Err(io::Error::other(err_str));
-            }
-
             unsafe {
                 // Creates the target directory with the same file permission bits as the source,
                 // modified by the umask of the process. Copying the permission bits without the
@@ -208,7 +270,9 @@ where
             }
         }
 
-        return Ok(CopyResult::CopyingDirectory);
+        return Ok(CopyResult::CopyingDirectory {
+            created: !target_exists,
+        });
     } else {
         // 3. If source_file is of type regular file
 
@@ -349,11 +413,7 @@ where
                 }
 
                 let ret = unsafe {
-                    libc::symlinkat(
-                        source.read_link().unwrap().as_ptr(),
-                        target_dirfd,
-                        target_filename,
-                    )
+                    libc::symlinkat(read_link(source)?.as_ptr(), target_dirfd, target_filename)
                 };
                 if ret != 0 {
                     return Err(io::Error::last_os_error());
@@ -418,11 +478,7 @@ where
             // 4.c
             if source_is_symlink {
                 let ret = unsafe {
-                    libc::symlinkat(
-                        source.read_link().unwrap().as_ptr(),
-                        target_dirfd,
-                        target_filename,
-                    )
+                    libc::symlinkat(read_link(source)?.as_ptr(), target_dirfd, target_filename)
                 };
                 if ret != 0 {
                     return Err(io::Error::last_os_error());
@@ -449,9 +505,29 @@ pub fn copy_file<F>(
 where
     F: Copy + Fn(&str) -> bool,
 {
+    // 2.e, checked once for the whole hierarchy
+    if cfg.recursive && copies_into_itself(source_arg, target_arg, cfg.follow_cli) {
+        let err_str = gettext!(
+            "cannot copy a directory, '{}', into itself, '{}'",
+            source_arg.display(),
+            target_arg.display()
+        );
+        return Err(io::Error::other(err_str));
+    }
+
+    // Read without changing it
+    let umask = unsafe {
+        let umask = libc::umask(0);
+        libc::umask(umask);
+        umask
+    };
+
     // `RefCell` to allow sharing these between closures
     let target_dirfd_stack = RefCell::new(vec![ftw::FileDescriptor::cwd()]);
     let target_dir_path = RefCell::new(PathBuf::new());
+    // The source directories being copied. Following symbolic links can
+    // lead back to one of them.
+    let source_dir_stack: RefCell<Vec<SourceDir>> = RefCell::new(Vec::new());
     let terminate = RefCell::new(false);
     let last_error = RefCell::new(None);
 
@@ -480,6 +556,18 @@ where
             let source_md = source.metadata().unwrap();
             let identifier = (source_md.dev(), source_md.ino());
 
+            if source_md.is_dir()
+                && source_dir_stack
+                    .borrow()
+                    .iter()
+                    .any(|ancestor| ancestor.identifier == identifier)
+            {
+                let err_str = gettext!("cannot copy '{}': directory causes a cycle", source.path());
+                *last_error.borrow_mut() = Some(io::Error::other(err_str));
+                *terminate_borrowed = true;
+                return Ok(false);
+            }
+
             // Hard-link preserving behavior of `mv`. `cp` does not maintain the hard-link structure
             // of the hierarchy according to the standard
             if let Some(inode_map) = inode_map.as_deref_mut() {
@@ -531,7 +619,7 @@ where
                     }
 
                     match copy_result {
-                        CopyResult::CopyingDirectory => {
+                        CopyResult::CopyingDirectory { created } => {
                             // mkdir/mkdirat doesn't return a file descriptor so a new one must be
                             // opened here. Using O_CREAT | O_DIRECTORY in a call to open/openat would
                             // not allow atomically creating a directory then opening it:
@@ -559,6 +647,11 @@ where
 
                             target_dirfd_stack_borrowed.push(new_target_dirfd);
                             target_dir_path_borrowed.push(target_filename);
+                            source_dir_stack.borrow_mut().push(SourceDir {
+                                identifier,
+                                mode: source_md.mode(),
+                                created,
+                            });
 
                             true
                         }
@@ -601,28 +694,59 @@ where
 
             target_dir_path_borrowed.pop();
             target_dirfd_stack_borrowed.pop();
+            let source_dir = source_dir_stack.borrow_mut().pop();
+            if *terminate_borrowed {
+                return Ok(());
+            }
+
+            let target_dirfd = target_dirfd_stack_borrowed.last().unwrap();
+            let target_filename = if target_dirfd.as_raw_fd() == libc::AT_FDCWD {
+                target_arg.as_os_str()
+            } else {
+                OsStr::from_bytes(source.file_name().to_bytes())
+            };
+            let target_filename_cstr = CString::new(target_filename.as_bytes()).unwrap();
+
+            let target = target_dir_path_borrowed.join(target_filename);
 
             // Preserve metadata for directories. Must do this inside this closure to ensure no
             // further last access time changes to the source will be made.
             if cfg.preserve {
-                let target_dirfd = target_dirfd_stack_borrowed.last().unwrap();
-
-                let target_filename = if target_dirfd.as_raw_fd() == libc::AT_FDCWD {
-                    target_arg.as_os_str()
-                } else {
-                    OsStr::from_bytes(source.file_name().to_bytes())
-                };
-                let target_filename_cstr = CString::new(target_filename.as_bytes()).unwrap();
-
                 if let Err(e) = copy_characteristics(
                     &source,
-                    &target_dir_path_borrowed,
+                    &target,
                     target_dirfd.as_raw_fd(),
                     target_filename_cstr.as_ptr(),
                 ) {
                     *last_error.borrow_mut() = Some(e);
                     *terminate_borrowed = true;
                 }
+            } else if let Some(SourceDir {
+                mode,
+                created: true,
+                ..
+            }) = source_dir
+            {
+                // 2.f A created directory gets the permission bits of the
+                // source, without the S_IRWXU it was created with
+                let mode = mode as libc::mode_t & 0o777 & !umask;
+                let ret = unsafe {
+                    libc::fchmodat(
+                        target_dirfd.as_raw_fd(),
+                        target_filename_cstr.as_ptr(),
+                        mode,
+                        0,
+                    )
+                };
+                if ret != 0 {
+                    let err_str = gettext!(
+                        "failed to set permissions for '{}': {}",
+                        target.display(),
+                        error_string(&io::Error::last_os_error())
+                    );
+                    *last_error.borrow_mut() = Some(io::Error::other(err_str));
+                    *terminate_borrowed = true;
+                }
             }
 
             Ok(())
@@ -721,15 +845,18 @@ fn copy_special_file(
     let dev = source_md.rdev();
 
     // 4.b
+    let mode = source_md.mode() as libc::mode_t;
     let mode = if is_fifo {
         // Mandatory to be the same as source for FIFO
-        source_md.mode()
+        mode
     } else {
         // Under Rationale:
         // "In general, it is strongly suggested that the permissions,
         // owner, and group be the same as if the user had run the
         // historical mknod, ln, or other utility to create the file"
-        0o644
+        //
+        // The file type bits tell mknod what kind of file to create.
+        (mode & libc::S_IFMT) | 0o644
     };
 
     let mut stat_buf = MaybeUninit::uninit();
@@ -752,21 +879,14 @@ fn copy_special_file(
         }
     }
 
-    let ret = unsafe {
-        libc::mknodat(
-            target_dirfd,
-            target_filename,
-            mode as libc::mode_t,
-            dev as libc::dev_t,
-        )
-    };
+    let ret = unsafe { libc::mknodat(target_dirfd, target_filename, mode, dev as libc::dev_t) };
     if ret == 0 {
         created_files.insert(target.to_path_buf());
         Ok(())
     } else {
         let e = io::Error::last_os_error();
         let err_str = gettext!(
-            "cannot create regular file '{}': {}",
+            "cannot create special file '{}': {}",
             target.display(),
             error_string(&e)
         );
@@ -811,7 +931,7 @@ fn copy_characteristics(
             let err_str = gettext!(
                 "failed to preserve times for '{}': {}",
                 target.display(),
-                io::Error::last_os_error()
+                error_string(&io::Error::last_os_error())
             );
             return Err(io::Error::other(err_str));
         }
@@ -824,16 +944,19 @@ fn copy_characteristics(
             source_md.gid(),
             libc::AT_SYMLINK_NOFOLLOW,
         );
+        let mut mode = source_md.mode() as libc::mode_t;
         if ret != 0 {
-            // Ignore errors
+            // Not an error, but the set-user-ID and set-group-ID bits must
+            // not be given to a file with a different owner or group
             errno::set_errno(errno::Errno(0));
+            mode &= !(libc::S_ISUID | libc::S_ISGID);
         }
 
         // Copy permissions
         let ret = libc::fchmodat(
             target_dirfd,
             target_filename,
-            source_md.mode() as libc::mode_t,
+            mode,
             libc::AT_SYMLINK_NOFOLLOW,
         );
         if ret != 0 {
@@ -855,7 +978,7 @@ fn copy_characteristics(
             let err_str = gettext!(
                 "failed to preserve permissions for '{}': {}",
                 target.display(),
-                io::Error::last_os_error()
+                error_string(&fchmodat_error)
             );
             return Err(io::Error::other(err_str));
         }
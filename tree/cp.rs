@@ -32,8 +32,7 @@ struct Args {
             "follow_cli",
             "dereference",
             "no_dereference"
-        ],
-        requires = "recursive"
+        ]
     )]
     follow_cli: bool,
 
@@ -45,9 +44,7 @@ struct Args {
             "follow_cli",
             "dereference",
             "no_dereference"
-        ],
-        requires = "recursive",
-        default_value_t = true,
+        ]
     )]
     dereference: bool,
 
@@ -81,11 +78,14 @@ struct Args {
 
 impl CopyConfig {
     fn new(args: &Args) -> Self {
-        // `args.no_dereference` serves only to disable `args.dereference` or
-        // `follow_cli`
+        // Only the last of -H, -L and -P is set. Without any of them,
+        // symbolic links are followed only when copying files, as if by -H,
+        // and copied as links when copying hierarchies, as if by -P.
+        let follow_cli =
+            args.follow_cli || args.dereference || (!args.no_dereference && !args.recursive);
         CopyConfig {
             force: args.force,
-            follow_cli: args.follow_cli,
+            follow_cli,
             dereference: args.dereference,
             interactive: args.interactive,
             preserve: args.preserve,
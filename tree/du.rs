@@ -6,22 +6,27 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - implement -H, -L, -x
-//
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::diag::error_message;
+use plib::walk::{Order, Symlinks, Walker};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
 use std::os::unix::fs::MetadataExt;
-use std::path::Path;
-use std::{fs, io};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Upper bound of the number of threads walking directories at once.
+const MAX_WORKERS: usize = 8;
 
 /// du - estimate file space usage
 #[derive(Parser)]
 #[command(version, about)]
 struct Args {
     /// Write counts for all files, not just directories
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "sum")]
     all: bool,
 
     /// Follow command line symlinks
@@ -48,64 +53,187 @@ struct Args {
     files: Vec<String>,
 }
 
-fn calc_size(kilo: bool, size: u64) -> u64 {
-    if kilo {
-        size / 2
-    } else {
-        size
-    }
+/// What du needs to know about an entry of the walk.
+struct Record {
+    path: PathBuf,
+    depth: usize,
+    is_dir: bool,
+    /// Space allocated, in 512-byte units.
+    blocks: u64,
+    dev: u64,
+    ino: u64,
 }
 
-fn print_pathinfo(args: &Args, filename: &str, size: u64, toplevel: bool) {
-    if args.sum && !toplevel {
-        return;
+/// Walks the tree at `path` in post-order, as the entries at `depth` and
+/// below; -H only follows `path` if it is a file operand, at depth 0.
+fn walk(args: &Args, path: &Path, depth: usize, max_depth: Option<usize>) -> (Vec<Record>, bool) {
+    let symlinks = if args.dereference {
+        Symlinks::Logical
+    } else if args.follow_cli && depth == 0 {
+        Symlinks::CommandLine
+    } else {
+        Symlinks::Physical
+    };
+    let mut walker = Walker::new(path)
+        .symlinks(symlinks)
+        .order(Order::PostOrder)
+        .same_file_system(args.one_fs);
+    if let Some(max_depth) = max_depth {
+        walker = walker.max_depth(max_depth);
     }
 
-    // print the file size
-    println!("{}\t{}", size, filename);
+    let records = walker
+        .by_ref()
+        .map(|entry| {
+            let md = entry.metadata();
+            Record {
+                path: entry.path().to_path_buf(),
+                depth: entry.depth() + depth,
+                is_dir: entry.is_dir(),
+                blocks: md.blocks(),
+                dev: md.dev(),
+                ino: md.ino(),
+            }
+        })
+        .collect();
+    (records, !walker.failed())
 }
 
-fn du_cli_arg(
-    args: &Args,
-    filename: &str,
-    total: &mut u64,
-    toplevel: bool,
-) -> Result<(), io::Error> {
-    let path = Path::new(filename);
-    let metadata = fs::metadata(path)?;
-
-    // recursively process directories
-    if metadata.is_dir() {
-        let mut sub_total = 0;
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let path = entry.path();
-            let filename = path.to_str().unwrap();
-            if let Err(e) = du_cli_arg(args, filename, &mut sub_total, false) {
-                eprintln!("{}: {}", filename, e);
-            }
+/// Adds up the sizes of the records of a file operand, in post-order,
+/// and writes them out.
+struct Accounting<'a> {
+    args: &'a Args,
+    /// Files already counted, by device and inode, so that hard links and
+    /// files reached more than once are only counted the first time.
+    seen: HashSet<(u64, u64)>,
+    /// Device of the file operand, for -x.
+    root_dev: u64,
+    /// sizes[depth] accumulates the entries seen at that depth, until
+    /// the directory containing them is reached
+    sizes: Vec<u64>,
+    out: plib::io::StdoutWriter,
+}
+
+impl Accounting<'_> {
+    fn print(&mut self, path: &Path, blocks: u64) {
+        let size = if self.args.kilo {
+            blocks.div_ceil(2)
+        } else {
+            blocks
+        };
+        let _ = writeln!(self.out, "{}\t{}", size, path.display());
+    }
+
+    fn add(&mut self, record: &Record) {
+        let depth = record.depth;
+        if self.sizes.len() < depth + 2 {
+            self.sizes.resize(depth + 2, 0);
+        }
+        let contents = std::mem::take(&mut self.sizes[depth + 1]);
+        if self.args.one_fs && record.dev != self.root_dev {
+            return;
+        }
+        if !self.seen.insert((record.dev, record.ino)) {
+            return;
         }
-        print_pathinfo(args, filename, sub_total, toplevel);
 
-        *total += sub_total;
-        return Ok(());
+        let size = record.blocks + contents;
+        // file operands are written even if they aren't directories, and
+        // with -s they are all that is written
+        let listed = if self.args.sum {
+            depth == 0
+        } else {
+            record.is_dir || self.args.all || depth == 0
+        };
+        if listed {
+            self.print(&record.path, size);
+        }
+        self.sizes[depth] += size;
     }
+}
 
-    // print the file size
-    let size = calc_size(args.kilo, metadata.blocks());
-    *total += size;
+/// Walks the tree of a file operand. The subdirectories of the operand
+/// are walked by a pool of threads, while their records are added up in
+/// the order a single walk would have visited them.
+fn du_cli_arg(accounting: &mut Accounting, filename: &str) -> bool {
+    let args = accounting.args;
+    accounting.sizes.clear();
 
-    if args.all {
-        print_pathinfo(args, filename, size, toplevel);
+    // with -L, a symbolic link may lead back to an ancestor, which only a
+    // single walk of the whole tree can tell
+    if args.dereference {
+        let (records, ok) = walk(args, Path::new(filename), 0, None);
+        if let Some(root) = records.last() {
+            accounting.root_dev = root.dev;
+        }
+        records.iter().for_each(|record| accounting.add(record));
+        return ok;
     }
 
-    Ok(())
+    let (top, mut ok) = walk(args, Path::new(filename), 0, Some(1));
+    let Some(root) = top.last() else {
+        return ok;
+    };
+    accounting.root_dev = root.dev;
+
+    // entries at depth 1 are either walked by the pool or added as they are
+    let is_job = |record: &Record| {
+        record.depth == 1 && record.is_dir && !(args.one_fs && record.dev != root.dev)
+    };
+    let jobs: Vec<&Path> = top
+        .iter()
+        .filter(|record| is_job(record))
+        .map(|record| record.path.as_path())
+        .collect();
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, MAX_WORKERS)
+        .min(jobs.len());
+
+    let next_job = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (jobs, next_job) = (&jobs, &next_job);
+            scope.spawn(move || loop {
+                let i = next_job.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = jobs.get(i) else {
+                    break;
+                };
+                if tx.send((i, walk(args, path, 1, None))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // results arrive in any order, but are added up in walk order
+        let mut done = BTreeMap::new();
+        let mut job = 0;
+        let mut top = top.iter();
+        for (i, result) in rx {
+            done.insert(i, result);
+            while let Some((records, job_ok)) = done.remove(&job) {
+                for record in top.by_ref() {
+                    if is_job(record) {
+                        break;
+                    }
+                    accounting.add(record);
+                }
+                records.iter().for_each(|record| accounting.add(record));
+                ok &= job_ok;
+                job += 1;
+            }
+        }
+        top.for_each(|record| accounting.add(record));
+    });
+
+    ok
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+    plib::i18n::init();
 
     let mut args = Args::parse();
 
@@ -114,15 +242,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.files.push(".".to_string());
     }
     let mut exit_code = 0;
-    let mut total = 0;
 
-    // apply the group to each file
+    let mut accounting = Accounting {
+        args: &args,
+        seen: HashSet::new(),
+        root_dev: 0,
+        sizes: Vec::new(),
+        out: plib::io::stdout(),
+    };
     for filename in &args.files {
-        if let Err(e) = du_cli_arg(&args, filename, &mut total, true) {
+        if !du_cli_arg(&mut accounting, filename) {
             exit_code = 1;
-            eprintln!("{}: {}", filename, e);
         }
     }
 
+    if let Err(e) = accounting.out.finish() {
+        plib::diag!("{}", error_message(&e));
+        exit_code = 1;
+    }
     std::process::exit(exit_code)
 }
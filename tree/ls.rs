@@ -9,14 +9,16 @@
 
 mod ls_util;
 
-use self::ls_util::{ls_from_utf8_lossy, Entry, LongFormatPadding, MultiColumnPadding};
+use self::ls_util::{
+    display_width, ls_from_utf8_lossy, Entry, LongFormatPadding, MultiColumnPadding,
+};
 use clap::{CommandFactory, FromArgMatches, Parser};
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use gettextrs::gettext;
 use plib::platform::P_WINSIZE_REQUEST_CODE;
 use std::{
     collections::HashMap,
     ffi::{CString, OsStr},
-    io,
+    io::{self, IsTerminal},
     mem::MaybeUninit,
     os::unix::{ffi::OsStrExt, fs::MetadataExt},
     path::{Path, PathBuf},
@@ -280,8 +282,8 @@ struct Args {
     file: Vec<PathBuf>,
 }
 
-const DATE_TIME_FORMAT_RECENT: &str = "%b %d %H:%M";
-const DATE_TIME_FORMAT_OLD_OR_FUTURE: &str = "%b %d  %Y"; // Two spaces between %d and %Y
+const DATE_TIME_FORMAT_RECENT: &str = "%b %e %H:%M";
+const DATE_TIME_FORMAT_OLD_OR_FUTURE: &str = "%b %e  %Y"; // Two spaces between %e and %Y
 const BLOCK_SIZE: u64 = 512;
 const BLOCK_SIZE_KIBIBYTES: u64 = 1024;
 const COLUMN_SPACING: usize = 2; // How many spaces in the column separator
@@ -457,7 +459,11 @@ impl Config {
                     // format is implementation-defined.
                     //
                     // coreutils uses -C by default.
-                    OutputFormat::OneEntryPerLine
+                    if io::stdout().is_terminal() {
+                        OutputFormat::MultiColumn
+                    } else {
+                        OutputFormat::OneEntryPerLine
+                    }
                 }
             }
             (true, false, false, false) => OutputFormat::MultiColumn,
@@ -550,9 +556,9 @@ impl Config {
 
 fn get_terminal_width() -> usize {
     // COLUMNS is usually automatically set and it even changes when the
-    // terminal window is resized.
+    // terminal window is resized. A width of 0 means it is unknown.
     if let Ok(s) = std::env::var("COLUMNS") {
-        if let Ok(num_columns) = s.parse() {
+        if let Ok(num_columns @ 1..) = s.parse() {
             return num_columns;
         }
     }
@@ -570,7 +576,9 @@ fn get_terminal_width() -> usize {
         // so we won't query further if the first `ioctl` call fails.
         if ret == 0 {
             let winsize = winsize.assume_init();
-            return winsize.ws_col as usize;
+            if winsize.ws_col > 0 {
+                return winsize.ws_col as usize;
+            }
         }
     }
 
@@ -757,7 +765,6 @@ fn display_entries(entries: &mut [Entry], config: &Config, dir_path: Option<&str
         }
         OutputFormat::MultiColumn => {
             let paddings = calc_optimal_padding(entries, config.terminal_width, false);
-            let last_col_idx = paddings.len() - 1;
 
             let num_columns = paddings.len();
             let num_rows = entries.len().div_ceil(num_columns);
@@ -779,12 +786,13 @@ fn display_entries(entries: &mut [Entry], config: &Config, dir_path: Option<&str
                     // |   | * |   |   |
                     // |   | 5 |   |   |
                     if let Some(entry) = col.get(row_idx) {
-                        if col_idx == last_col_idx {
-                            entry.print_multi_column(padding);
-                        } else {
-                            entry.print_multi_column(padding);
+                        if col_idx > 0 {
                             print!("{:COLUMN_SPACING$}", "");
                         }
+                        // No trailing whitespace after the last entry of
+                        // the row
+                        let next = (col_idx + 1) * num_rows + row_idx;
+                        entry.print_multi_column(padding, next < entries.len());
                     }
                 }
                 println!();
@@ -794,8 +802,6 @@ fn display_entries(entries: &mut [Entry], config: &Config, dir_path: Option<&str
             let paddings = calc_optimal_padding(entries, config.terminal_width, true);
             let last_col_idx = paddings.len() - 1;
 
-            let num_columns = paddings.len();
-
             // The `zip` of `entries` and `paddings` in this for loop
             // iterates like the following:
             //
@@ -808,30 +814,26 @@ fn display_entries(entries: &mut [Entry], config: &Config, dir_path: Option<&str
             // | 0 | 1 | 2 | 3 |
             // | 0 | 1 | 2 |   |
             // |   |   |   |   |
-            for (entry, (col_idx, padding)) in
-                entries.iter().zip(paddings.iter().enumerate().cycle())
+            for (i, (entry, (col_idx, padding))) in entries
+                .iter()
+                .zip(paddings.iter().enumerate().cycle())
+                .enumerate()
             {
-                if col_idx == last_col_idx {
-                    entry.print_multi_column(padding);
+                if col_idx == last_col_idx || i + 1 == entries.len() {
+                    entry.print_multi_column(padding, false);
                     println!();
                 } else {
-                    entry.print_multi_column(padding);
+                    entry.print_multi_column(padding, true);
                     print!("{:COLUMN_SPACING$}", "");
                 }
             }
-
-            // If the last entry does not end up on the bottom right of
-            // the grid
-            if entries.len() % num_columns != 0 {
-                println!();
-            }
         }
         OutputFormat::Stream => {
             let stream_outputs: Vec<_> = entries
                 .iter()
                 .map(|entry| entry.build_stream_mode_string())
                 .collect();
-            let char_counts: Vec<_> = stream_outputs.iter().map(|s| s.chars().count()).collect();
+            let char_counts: Vec<_> = stream_outputs.iter().map(|s| display_width(s)).collect();
             let mut start = 0;
 
             'outer: loop {
@@ -894,7 +896,7 @@ fn display_entries(entries: &mut [Entry], config: &Config, dir_path: Option<&str
             let padding = paddings.first().unwrap();
 
             for entry in entries.iter() {
-                entry.print_multi_column(padding);
+                entry.print_multi_column(padding, false);
                 println!();
             }
         }
@@ -1319,9 +1321,7 @@ fn process_single_dir(
 }
 
 fn main() -> ExitCode {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs").unwrap();
-    bind_textdomain_codeset("posixutils-rs", "UTF-8").unwrap();
+    plib::i18n::init();
     let (config, paths) = Config::new();
 
     match ls(paths, &config) {
@@ -7,15 +7,15 @@
 // SPDX-License-Identifier: MIT
 //
 
-use super::ls_from_utf8_lossy;
+use super::{display_width, ls_from_utf8_lossy};
 use crate::{
     ClassifyFiles, Config, FileTimeOption, LongFormatOptions, OutputFormat,
     DATE_TIME_FORMAT_OLD_OR_FUTURE, DATE_TIME_FORMAT_RECENT,
 };
-use chrono::{DateTime, Local};
+use plib::{collate, timefmt, userdb};
 use std::{
     cmp::Ordering,
-    ffi::{CStr, OsStr, OsString},
+    ffi::OsString,
     io,
     os::unix::{
         ffi::OsStrExt,
@@ -38,6 +38,7 @@ pub struct Entry {
 
     file_name_raw: OsString,   // Actual file name, might not be valid UTF-8
     file_name_display: String, // File name to be displayed
+    collation_key: Vec<u8>,    // Orders file names by LC_COLLATE
 
     blocks_str: Option<String>,
     inode_str: Option<String>,
@@ -132,7 +133,7 @@ impl Entry {
             }
         };
 
-        let mut file_name_width = file_name_display.chars().count();
+        let mut file_name_width = display_width(&file_name_display);
         if suffix.is_some() {
             file_name_width += 1;
         }
@@ -167,6 +168,7 @@ impl Entry {
             blocks,
             time,
             time_string,
+            collation_key: collate::sort_key(file_name_raw.as_bytes()),
             file_name_raw,
             file_name_display,
             blocks_str,
@@ -229,8 +231,10 @@ impl Entry {
         output
     }
 
-    /// Print a single grid cell in multi-column format.
-    pub fn print_multi_column(&self, padding: &MultiColumnPadding) {
+    /// Print a single grid cell in multi-column format. The file name is
+    /// padded to the width of the column only if `pad_file_name` is set,
+    /// which it shouldn't be at the end of a line.
+    pub fn print_multi_column(&self, padding: &MultiColumnPadding, pad_file_name: bool) {
         let MultiColumnPadding {
             total_width,
             inode_str_width,
@@ -255,16 +259,20 @@ impl Entry {
             file_name.push(*suffix);
         }
 
-        let mut file_name_width = *file_name_width;
+        // Pad by display width, which `format!` doesn't know about
+        let mut padding_width = file_name_width.saturating_sub(display_width(&file_name));
 
         // This implies that this will be printed in a single column. Don't
         // inherit the padding for the longest string to avoid unnecessary
         // whitespaces.
-        if *total_width > self.terminal_width {
-            file_name_width = 0;
+        if *total_width > self.terminal_width || !pad_file_name {
+            padding_width = 0;
         }
 
-        print!("{}{}{:<file_name_width$}", inode_str, blocks_str, file_name,);
+        print!(
+            "{}{}{}{:padding_width$}",
+            inode_str, blocks_str, file_name, ""
+        );
     }
 
     /// Print one row in long format (-l).
@@ -351,17 +359,21 @@ impl Entry {
         );
     }
 
-    /// Comparison key for sorting based on just the file name.
+    /// Comparison key for sorting based on just the file name, in the
+    /// collating sequence of the locale. Names that collate equally are
+    /// ordered by their bytes.
     pub fn sorting_cmp_lexicographic(&self, other: &Self) -> Ordering {
-        self.file_name_raw.cmp(&other.file_name_raw)
+        self.collation_key
+            .cmp(&other.collation_key)
+            .then_with(|| self.file_name_raw.cmp(&other.file_name_raw))
     }
 
-    // Returns (is_device, size, file_name). The `bool` is to have devices
-    // sorted after normal files.
-    fn sorting_key_size(&self) -> (bool, u64, &OsStr) {
+    // Returns (is_device, size). The `bool` is to have devices sorted after
+    // normal files.
+    fn sorting_key_size(&self) -> (bool, u64) {
         match self.file_info {
-            FileInfo::Size(size) => (false, size, &self.file_name_raw),
-            FileInfo::DeviceInfo(_) => (true, 0, &self.file_name_raw),
+            FileInfo::Size(size) => (false, size),
+            FileInfo::DeviceInfo(_) => (true, 0),
         }
     }
 
@@ -377,7 +389,7 @@ impl Entry {
         match self_sorting_key.0.cmp(&other_sorting_key.0) {
             Ordering::Equal => {
                 match self_sorting_key.1.cmp(&other_sorting_key.1) {
-                    Ordering::Equal => self_sorting_key.2.cmp(other_sorting_key.2),
+                    Ordering::Equal => self.sorting_cmp_lexicographic(other),
                     r => r.reverse(), // Default is from largest file size to smallest
                 }
             }
@@ -391,7 +403,7 @@ impl Entry {
     /// The kind of time is dependent on the flags -t, -c, -u.
     pub fn sorting_cmp_time(&self, other: &Self) -> Ordering {
         match self.time.cmp(&other.time) {
-            Ordering::Equal => self.file_name_raw.cmp(&other.file_name_raw),
+            Ordering::Equal => self.sorting_cmp_lexicographic(other),
             r => r.reverse(), // Default is newest to oldest
         }
     }
@@ -551,7 +563,7 @@ impl LongFormatData {
             Some(get_owner_name(
                 metadata,
                 long_format_options.numeric_uid_gid,
-            )?)
+            ))
         };
 
         let group_name = if long_format_options.without_group {
@@ -560,7 +572,7 @@ impl LongFormatData {
             Some(get_group_name(
                 metadata,
                 long_format_options.numeric_uid_gid,
-            )?)
+            ))
         };
 
         Ok(Self {
@@ -584,6 +596,7 @@ fn get_file_mode_string(metadata: &ftw::Metadata) -> String {
         ftw::FileType::Directory => 'd',
         ftw::FileType::CharacterDevice => 'c',
         ftw::FileType::Fifo => 'p',
+        ftw::FileType::Socket => 's',
         _ => '-',
     });
 
@@ -622,10 +635,15 @@ fn get_file_mode_string(metadata: &ftw::Metadata) -> String {
     } else {
         '-'
     });
-    file_mode.push(if mode & (libc::S_IXGRP as u32) != 0 {
-        'x'
-    } else {
-        '-'
+    file_mode.push({
+        let executable = mode & (libc::S_IXGRP as u32) != 0;
+        let set_group_id = mode & (libc::S_ISGID as u32) != 0;
+        match (executable, set_group_id) {
+            (true, true) => 's',
+            (true, false) => 'x',
+            (false, true) => 'S',
+            (false, false) => '-',
+        }
     });
 
     // Other permissions
@@ -639,60 +657,38 @@ fn get_file_mode_string(metadata: &ftw::Metadata) -> String {
     } else {
         '-'
     });
+    // The S_ISVTX bit is shown on files other than directories too, where
+    // its meaning is implementation-defined
     file_mode.push({
-        if file_type.is_dir() {
-            let searchable = mode & (libc::S_IXOTH as u32) != 0;
-            let restricted_deletion = mode & (libc::S_ISVTX as u32) != 0;
-            match (searchable, restricted_deletion) {
-                (true, true) => 't',
-                (true, false) => 'x',
-                (false, true) => 'T',
-                (false, false) => '-',
-            }
-        } else {
-            if mode & (libc::S_IXOTH as u32) != 0 {
-                'x'
-            } else {
-                '-'
-            }
+        let executable = mode & (libc::S_IXOTH as u32) != 0;
+        let sticky = mode & (libc::S_ISVTX as u32) != 0;
+        match (executable, sticky) {
+            (true, true) => 't',
+            (true, false) => 'x',
+            (false, true) => 'T',
+            (false, false) => '-',
         }
     });
 
     file_mode
 }
 
-fn get_owner_name(metadata: &ftw::Metadata, numeric: bool) -> io::Result<String> {
+/// Name of the file's owner, or the numeric user ID if it has no name.
+fn get_owner_name(metadata: &ftw::Metadata, numeric: bool) -> String {
     let uid = metadata.uid();
     if numeric {
-        Ok(uid.to_string())
-    } else {
-        unsafe {
-            let passwd = libc::getpwuid(uid);
-            if passwd.is_null() {
-                return Err(io::Error::last_os_error());
-            }
-            let passwd_ref = &*passwd;
-            let name = CStr::from_ptr(passwd_ref.pw_name);
-            Ok(ls_from_utf8_lossy(name.to_bytes()))
-        }
+        return uid.to_string();
     }
+    userdb::user_by_uid(uid).map_or_else(|| uid.to_string(), |user| user.name.clone())
 }
 
-fn get_group_name(metadata: &ftw::Metadata, numeric: bool) -> io::Result<String> {
+/// Name of the file's group, or the numeric group ID if it has no name.
+fn get_group_name(metadata: &ftw::Metadata, numeric: bool) -> String {
     let gid = metadata.gid();
     if numeric {
-        Ok(gid.to_string())
-    } else {
-        unsafe {
-            let group = libc::getgrgid(gid);
-            if group.is_null() {
-                return Err(io::Error::last_os_error());
-            }
-            let group_ref = &*group;
-            let name = CStr::from_ptr(group_ref.gr_name);
-            Ok(ls_from_utf8_lossy(name.to_bytes()))
-        }
+        return gid.to_string();
     }
+    userdb::group_by_gid(gid).map_or_else(|| gid.to_string(), |group| group.name.clone())
 }
 
 fn get_file_info(metadata: &ftw::Metadata) -> FileInfo {
@@ -720,21 +716,19 @@ fn get_system_time(
     file_time_option: &FileTimeOption,
 ) -> io::Result<SystemTime> {
     let seconds_since_epoch = match file_time_option {
-        FileTimeOption::LastModificationTime => {
-            u64::try_from(metadata.mtime()).map_err(|_| io::Error::other("negative mtime"))?
-        }
-        FileTimeOption::LastAcessTime => {
-            u64::try_from(metadata.atime()).map_err(|_| io::Error::other("negative atime"))?
-        }
-        FileTimeOption::LastStatusChangeTime => {
-            u64::try_from(metadata.ctime()).map_err(|_| io::Error::other("negative ctime"))?
-        }
+        FileTimeOption::LastModificationTime => metadata.mtime(),
+        FileTimeOption::LastAcessTime => metadata.atime(),
+        FileTimeOption::LastStatusChangeTime => metadata.ctime(),
     };
 
-    let time = SystemTime::UNIX_EPOCH
-        .checked_add(Duration::from_secs(seconds_since_epoch))
-        .ok_or(io::Error::other("`SystemTime` overflow"))?;
-    Ok(time)
+    // Times before the Epoch are valid too
+    let offset = Duration::from_secs(seconds_since_epoch.unsigned_abs());
+    let time = if seconds_since_epoch < 0 {
+        SystemTime::UNIX_EPOCH.checked_sub(offset)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_add(offset)
+    };
+    time.ok_or(io::Error::other("`SystemTime` overflow"))
 }
 
 fn get_time_and_time_string(
@@ -762,10 +756,6 @@ fn get_time_and_time_string(
         }
     };
 
-    let time_string = {
-        // chrono parses the TZ environment variable in this conversion
-        let dt: DateTime<Local> = time.into();
-        dt.format(dt_format).to_string()
-    };
+    let time_string = timefmt::format_local(dt_format, time);
     Ok((time, time_string))
 }
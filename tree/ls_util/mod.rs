@@ -9,6 +9,8 @@
 
 mod entry;
 mod utf8_lossy;
+mod width;
 
 pub use entry::{Entry, LongFormatPadding, MultiColumnPadding};
 pub use utf8_lossy::ls_from_utf8_lossy;
+pub use width::display_width;
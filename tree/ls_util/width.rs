@@ -0,0 +1,29 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern "C" {
+    fn wcwidth(c: libc::wchar_t) -> libc::c_int;
+}
+
+/// Number of terminal columns `s` takes up, as told by wcwidth(3) for the
+/// current locale. Characters it doesn't know are taken to be one column
+/// wide, unless they are control characters.
+pub fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| {
+            // SAFETY: wcwidth accepts any value.
+            let width = unsafe { wcwidth(c as libc::wchar_t) };
+            match usize::try_from(width) {
+                Ok(width) => width,
+                Err(_) if c.is_control() => 0,
+                Err(_) => 1,
+            }
+        })
+        .sum()
+}
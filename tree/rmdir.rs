@@ -9,6 +9,7 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::diag::Status;
 use std::fs;
 use std::io::{self, Error, ErrorKind};
 use std::path::Path;
@@ -51,14 +52,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
-    let mut exit_code = 0;
+    let mut status = Status::new();
 
     for dirname in &args.dirs {
         if let Err(e) = remove_dir(dirname, args.parents) {
-            exit_code = 1;
-            eprintln!("{}: {}", dirname, e);
+            status.operand_error(dirname, &e);
         }
     }
 
-    std::process::exit(exit_code)
+    status.exit()
 }
@@ -185,3 +185,81 @@ fn test_chmod_thru_dangling() {
 
     fs::remove_dir_all(test_dir).unwrap();
 }
+
+#[test]
+fn test_chmod_recursive_exec_dir() {
+    let test_dir = &format!(
+        "{}/test_chmod_recursive_exec_dir",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let sub = &format!("{test_dir}/sub");
+    let exe = &format!("{test_dir}/sub/exe");
+    let plain = &format!("{test_dir}/sub/plain");
+
+    fs::create_dir_all(sub).unwrap();
+    fs::write(exe, "").unwrap();
+    fs::write(plain, "").unwrap();
+    fs::set_permissions(exe, fs::Permissions::from_mode(0o744)).unwrap();
+    fs::set_permissions(plain, fs::Permissions::from_mode(0o600)).unwrap();
+    fs::set_permissions(sub, fs::Permissions::from_mode(0o700)).unwrap();
+    fs::set_permissions(test_dir, fs::Permissions::from_mode(0o700)).unwrap();
+
+    let mode = |path: &str| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+    chmod_test(&["-R", "go+rX", test_dir], "", "", 0);
+    assert_eq!(mode(test_dir), 0o755);
+    assert_eq!(mode(sub), 0o755);
+    assert_eq!(mode(exe), 0o755);
+    assert_eq!(mode(plain), 0o644);
+
+    chmod_test(&["-R", "g+s,+t", sub], "", "", 0);
+    assert_eq!(mode(sub), 0o3755);
+    assert_eq!(mode(plain), 0o3644);
+
+    chmod_test(&["-R", "u+s,g-s,-t", sub], "", "", 0);
+    assert_eq!(mode(sub), 0o4755);
+    assert_eq!(mode(exe), 0o4755);
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_chmod_continues_after_error() {
+    let test_dir = &format!(
+        "{}/test_chmod_continues_after_error",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let missing = &format!("{test_dir}/missing");
+    let a = &format!("{test_dir}/a");
+    let b = &format!("{test_dir}/b");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::write(a, "").unwrap();
+    fs::write(b, "").unwrap();
+    fs::set_permissions(a, fs::Permissions::from_mode(0o600)).unwrap();
+    fs::set_permissions(b, fs::Permissions::from_mode(0o600)).unwrap();
+
+    chmod_test(
+        &["o+r", a, missing, b],
+        "",
+        &format!("chmod: cannot access '{missing}': No such file or directory\n"),
+        1,
+    );
+    let mode = |path: &str| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(a), 0o604);
+    assert_eq!(mode(b), 0o604);
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_chmod_invalid_symbolic_mode() {
+    for mode in ["u", "u+x,g", "u=go", "+k"] {
+        chmod_test(
+            &[mode, "."],
+            "",
+            &format!("chmod: invalid mode: '{mode}'\n"),
+            1,
+        );
+    }
+}
@@ -970,3 +970,176 @@ fn test_cp_issue199() {
 
     fs::remove_dir_all(test_dir).unwrap();
 }
+
+#[test]
+fn test_cp_r_symlinks() {
+    let test_dir = &format!("{}/test_cp_r_symlinks", env!("CARGO_TARGET_TMPDIR"));
+    let src = &format!("{test_dir}/src");
+    let link = &format!("{test_dir}/link");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(src).unwrap();
+    fs::write(format!("{src}/file"), "abc\n").unwrap();
+    unix::fs::symlink("file", format!("{src}/file_link")).unwrap();
+    unix::fs::symlink("src", link).unwrap();
+
+    // -P, the default with -R: symbolic links are copied as links
+    for (i, flag) in ["-R", "-RP"].iter().enumerate() {
+        let dst = &format!("{test_dir}/dst{i}");
+        cp_test(&[flag, link, dst], "", "", 0);
+        assert_eq!(fs::read_link(dst).unwrap(), Path::new("src"));
+    }
+
+    // -H: only the operand is followed
+    let dst = &format!("{test_dir}/dst_h");
+    cp_test(&["-RH", link, dst], "", "", 0);
+    assert!(fs::symlink_metadata(dst).unwrap().is_dir());
+    assert_eq!(
+        fs::read_link(format!("{dst}/file_link")).unwrap(),
+        Path::new("file")
+    );
+
+    // -L: all links are followed
+    let dst = &format!("{test_dir}/dst_l");
+    cp_test(&["-RL", link, dst], "", "", 0);
+    let md = fs::symlink_metadata(format!("{dst}/file_link")).unwrap();
+    assert!(md.is_file());
+    assert_eq!(
+        fs::read_to_string(format!("{dst}/file_link")).unwrap(),
+        "abc\n"
+    );
+
+    // Without -R the operand is followed unless -P is given
+    let dst = &format!("{test_dir}/file");
+    cp_test(&[&format!("{src}/file_link"), dst], "", "", 0);
+    assert!(fs::symlink_metadata(dst).unwrap().is_file());
+    let dst = &format!("{test_dir}/file_p");
+    cp_test(&["-P", &format!("{src}/file_link"), dst], "", "", 0);
+    assert_eq!(fs::read_link(dst).unwrap(), Path::new("file"));
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_r_symlink_cycle() {
+    let test_dir = &format!("{}/test_cp_r_symlink_cycle", env!("CARGO_TARGET_TMPDIR"));
+    let a = &format!("{test_dir}/a");
+    let b = &format!("{test_dir}/b");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(a).unwrap();
+    fs::create_dir(format!("{a}/sub")).unwrap();
+    unix::fs::symlink("..", format!("{a}/sub/up")).unwrap();
+
+    cp_test(
+        &["-RL", a, b],
+        "",
+        &format!("cp: cannot copy '{a}/sub/up': directory causes a cycle\n"),
+        1,
+    );
+    // Copied as a link otherwise
+    let c = &format!("{test_dir}/c");
+    cp_test(&["-R", a, c], "", "", 0);
+    assert_eq!(
+        fs::read_link(format!("{c}/sub/up")).unwrap(),
+        Path::new("..")
+    );
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_r_fifo() {
+    let test_dir = &format!("{}/test_cp_r_fifo", env!("CARGO_TARGET_TMPDIR"));
+    let src = &format!("{test_dir}/src");
+    let dst = &format!("{test_dir}/dst");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(src).unwrap();
+    let fifo = CString::new(format!("{src}/fifo")).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o640) }, 0);
+
+    cp_test(&["-Rp", src, dst], "", "", 0);
+    let md = fs::symlink_metadata(format!("{dst}/fifo")).unwrap();
+    assert!(md.file_type().is_fifo());
+    assert_eq!(md.mode() & 0o7777, 0o640);
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_preserve() {
+    let test_dir = &format!("{}/test_cp_preserve", env!("CARGO_TARGET_TMPDIR"));
+    let src = &format!("{test_dir}/src");
+    let file = &format!("{src}/file");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(src).unwrap();
+    fs::write(file, "abc\n").unwrap();
+    fs::set_permissions(file, fs::Permissions::from_mode(0o604)).unwrap();
+    let times = [
+        libc::timespec {
+            tv_sec: 1_000_000_000,
+            tv_nsec: 0,
+        },
+        libc::timespec {
+            tv_sec: 1_000_000_000,
+            tv_nsec: 0,
+        },
+    ];
+    for path in [file, src] {
+        let path = CString::new(path.as_bytes()).unwrap();
+        assert_eq!(
+            unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0) },
+            0
+        );
+    }
+    fs::set_permissions(src, fs::Permissions::from_mode(0o700)).unwrap();
+
+    let dst = &format!("{test_dir}/dst_p");
+    cp_test(&["-Rp", src, dst], "", "", 0);
+    for (path, mode) in [(dst.clone(), 0o700), (format!("{dst}/file"), 0o604)] {
+        let md = fs::metadata(path).unwrap();
+        assert_eq!(md.mode() & 0o7777, mode);
+        assert_eq!(md.mtime(), 1_000_000_000);
+    }
+
+    // Without -p, a created directory still gets the permission bits of the
+    // source once its contents are copied
+    let dst = &format!("{test_dir}/dst");
+    cp_test(&["-R", src, dst], "", "", 0);
+    assert_eq!(fs::metadata(dst).unwrap().mode() & 0o7777, 0o700);
+    assert_ne!(fs::metadata(dst).unwrap().mtime(), 1_000_000_000);
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_into_subdirectory() {
+    let test_dir = &format!("{}/test_cp_into_subdirectory", env!("CARGO_TARGET_TMPDIR"));
+    let dir = &format!("{test_dir}/dir");
+    let sub = &format!("{test_dir}/dir/sub");
+    let link = &format!("{test_dir}/link");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(dir).unwrap();
+    fs::create_dir(sub).unwrap();
+    unix::fs::symlink("dir/sub", link).unwrap();
+
+    cp_test(
+        &["-R", dir, sub],
+        "",
+        &format!("cp: cannot copy a directory, '{dir}', into itself, '{sub}/dir'\n"),
+        1,
+    );
+    // The target is reached through a symbolic link
+    cp_test(
+        &["-R", dir, &format!("{link}/copy")],
+        "",
+        &format!("cp: cannot copy a directory, '{dir}', into itself, '{link}/copy'\n"),
+        1,
+    );
+    assert!(!Path::new(&format!("{sub}/copy")).exists());
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
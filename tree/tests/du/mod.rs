@@ -0,0 +1,136 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use plib::testing::{
+    run_test_with_checker, run_test_with_options, Fixture, OutputMatch, TestOptions, TestPlan,
+};
+
+/// Runs du and returns the size it reports for each path.
+fn du_sizes(args: &[&str]) -> Vec<(u64, String)> {
+    let mut sizes = Vec::new();
+    let plan = TestPlan {
+        cmd: String::from("du"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    };
+    run_test_with_checker(plan, |_, output| {
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+        assert_eq!(output.status.code(), Some(0));
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let (size, path) = line.split_once('\t').unwrap();
+            sizes.push((size.parse().unwrap(), path.to_string()));
+        }
+    });
+    sizes
+}
+
+/// Blocks allocated to the tree at `path`, counting each inode once.
+fn blocks(path: &Path, seen: &mut Vec<u64>) -> u64 {
+    let md = fs::symlink_metadata(path).unwrap();
+    if seen.contains(&md.ino()) {
+        return 0;
+    }
+    seen.push(md.ino());
+    let mut total = md.blocks();
+    if md.is_dir() {
+        for entry in fs::read_dir(path).unwrap() {
+            total += blocks(&entry.unwrap().path(), seen);
+        }
+    }
+    total
+}
+
+#[test]
+fn du_counts_hard_links_once() {
+    let mut builder = Fixture::builder("du_hard_links").file("tree/a", "a".repeat(10000));
+    for i in 0..20 {
+        builder = builder.file(&format!("tree/dir{i}/file"), "b".repeat(5000 * i));
+    }
+    let fixture = builder.build();
+    fs::hard_link(fixture.path("tree/a"), fixture.path("tree/dir7/link")).unwrap();
+    let tree = fixture.arg("tree");
+
+    let sizes = du_sizes(&["-a", "-x", &tree]);
+    let listed: HashMap<&str, u64> = sizes.iter().map(|(n, p)| (p.as_str(), *n)).collect();
+    assert_eq!(listed.len(), sizes.len(), "a path is listed twice");
+    let links = [format!("{tree}/a"), format!("{tree}/dir7/link")];
+    assert_eq!(
+        links
+            .iter()
+            .filter(|link| listed.contains_key(link.as_str()))
+            .count(),
+        1
+    );
+    for i in 0..20 {
+        let dir = format!("{tree}/dir{i}");
+        assert!(listed.contains_key(dir.as_str()));
+        assert!(listed.contains_key(format!("{dir}/file").as_str()));
+    }
+    // the operand comes last, with the whole tree counted once
+    let expected = blocks(&fixture.path("tree"), &mut Vec::new());
+    assert_eq!(sizes.last().unwrap(), &(expected, tree.clone()));
+
+    assert_eq!(du_sizes(&["-s", &tree]), vec![(expected, tree.clone())]);
+    assert_eq!(
+        du_sizes(&["-s", "-k", &tree]),
+        vec![(expected.div_ceil(2), tree)]
+    );
+}
+
+#[test]
+fn du_lists_file_operands() {
+    let fixture = Fixture::builder("du_operands")
+        .file("tree/sub/a", "a")
+        .file("file", "b")
+        .build();
+    let (tree, file) = (fixture.arg("tree"), fixture.arg("file"));
+
+    let paths = |sizes: Vec<(u64, String)>| -> Vec<String> {
+        sizes.into_iter().map(|(_, path)| path).collect()
+    };
+    assert_eq!(
+        paths(du_sizes(&[&tree, &file])),
+        [format!("{tree}/sub"), tree.clone(), file.clone()]
+    );
+    assert_eq!(
+        paths(du_sizes(&["-s", &tree, &file])),
+        [tree.clone(), file.clone()]
+    );
+    // a file already counted under an earlier operand is not listed again
+    assert_eq!(
+        paths(du_sizes(&["-s", &tree, &format!("{tree}/sub")])),
+        [tree]
+    );
+}
+
+#[test]
+fn du_all_conflicts_with_sum() {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("du"),
+            args: vec![String::from("-a"), String::from("-s")],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::from("cannot be used with"),
+            expected_exit_code: 2,
+        },
+        TestOptions {
+            stderr_match: OutputMatch::Contains,
+            ..Default::default()
+        },
+    );
+}
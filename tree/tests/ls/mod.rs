@@ -7,7 +7,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, run_test_with_checker, TestPlan};
+use plib::testing::{
+    run_test, run_test_with_checker, run_test_with_options, Fixture, TestOptions, TestPlan,
+};
 use regex::Regex;
 use std::ffi::CString;
 use std::fs;
@@ -663,3 +665,102 @@ fn test_ls_time() {
 
     fs::remove_dir_all(test_dir).unwrap();
 }
+
+fn ls_test_with_env(args: &[&str], env: &[(&str, &str)], expected_output: &str) {
+    let options = env
+        .iter()
+        .fold(TestOptions::default(), |options, (key, value)| {
+            options.env(key, value)
+        });
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("ls"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: String::from(expected_output),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        options,
+    );
+}
+
+#[test]
+fn test_ls_long_format() {
+    const YEAR: i64 = 365 * 24 * 3600;
+    let fixture = Fixture::builder("ls_long_format")
+        .file("dir/setgid", "12345")
+        .mode(0o2644)
+        .mtime(-YEAR)
+        .file("dir/setid", "")
+        .mode(0o6755)
+        .mtime(0)
+        .dir("dir/sticky")
+        .mode(0o1777)
+        .mtime(YEAR)
+        .build();
+    let dir = fixture.arg("dir");
+    let md = fs::metadata(&dir).unwrap();
+    let (uid, gid) = (md.uid(), md.gid());
+    let size = fs::metadata(fixture.path("dir/sticky"))
+        .unwrap()
+        .size()
+        .to_string();
+    let total: u64 = ["setgid", "setid", "sticky"]
+        .iter()
+        .map(|name| {
+            fs::metadata(fixture.path(&format!("dir/{name}")))
+                .unwrap()
+                .blocks()
+        })
+        .sum();
+
+    // the owner and group of the fixture's entries are the same as its root's
+    ls_test_with_env(
+        &["-ln", &dir],
+        &[("TZ", "UTC0"), ("LC_ALL", "C")],
+        &format!(
+            "total {total}\n\
+             -rw-r-Sr-- 1 {uid} {gid} {:>w$} Jan  1  1969 setgid\n\
+             -rwsr-sr-x 1 {uid} {gid} {:>w$} Jan  1  1970 setid\n\
+             drwxrwxrwt 2 {uid} {gid} {size} Jan  1  1971 sticky\n",
+            5,
+            0,
+            w = size.len(),
+        ),
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ls_long_format_device() {
+    ls_test_with_checker(&["-l", "/dev/null"], |_, output| {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let re = Regex::new(r"^crw-rw-rw- +1 root +root +1, +3 .* /dev/null\n$").unwrap();
+        assert!(re.is_match(&stdout), "{stdout}");
+    });
+}
+
+#[test]
+fn test_ls_multi_column() {
+    let fixture = Fixture::builder("ls_multi_column")
+        .file("dir/a1", "")
+        .file("dir/a2", "")
+        .file("dir/a3", "")
+        .file("dir/a4", "")
+        .file("dir/b5", "")
+        .file("dir/B6", "")
+        .build();
+    let dir = fixture.arg("dir");
+    let env = [("COLUMNS", "20"), ("LC_ALL", "C")];
+
+    ls_test_with_env(&["-C", &dir], &env, "B6  a2  a4\na1  a3  b5\n");
+    ls_test_with_env(&["-x", &dir], &env, "B6  a1  a2  a3  a4\nb5\n");
+    ls_test_with_env(
+        &["-x", &dir],
+        &[("COLUMNS", "9"), ("LC_ALL", "C")],
+        "B6  a1\na2  a3\na4  b5\n",
+    );
+    // -1 and -l override the column formats given before them
+    ls_test_with_env(&["-C", "-1", &dir], &env, "B6\na1\na2\na3\na4\nb5\n");
+}
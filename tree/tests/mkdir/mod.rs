@@ -8,10 +8,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test_with_checker, TestPlan};
+use plib::testing::{run_test_with_options, OutputMatch, TestOptions, TestPlan};
 use std::fs;
 use std::path::Path;
-use std::process::Output;
 use tempfile::{tempdir, TempDir};
 
 fn setup_test_env() -> (TempDir, String) {
@@ -26,18 +25,17 @@ fn run_mkdir_test(args: Vec<&str>, expected_exit_code: i32, expected_err_substr:
         args: args.iter().map(|&s| s.into()).collect(),
         stdin_data: String::new(),
         expected_out: String::new(),
-        expected_err: String::new(),
+        expected_err: String::from(expected_err_substr),
         expected_exit_code,
     };
 
-    run_test_with_checker(plan, move |_, output: &Output| {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        assert!(
-            stderr.contains(expected_err_substr),
-            "Expected substring not found in stderr: '{}'",
-            stderr
-        );
-    });
+    run_test_with_options(
+        plan,
+        TestOptions {
+            stderr_match: OutputMatch::Contains,
+            ..Default::default()
+        },
+    );
 }
 
 #[test]
@@ -67,6 +65,27 @@ fn test_directory_already_exists() {
     fs::remove_dir(&dir_path).expect("Unable to remove test directory");
 }
 
+#[test]
+fn test_directory_already_exists_names_path() {
+    let (_temp_dir, dir_path) = setup_test_env();
+    fs::create_dir(&dir_path).expect("Unable to create test directory");
+
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("mkdir"),
+            args: vec![dir_path.clone()],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::from(r"^\S*/testdir: File exists"),
+            expected_exit_code: 1,
+        },
+        TestOptions {
+            stderr_match: OutputMatch::Regex,
+            ..Default::default()
+        },
+    );
+}
+
 #[test]
 fn test_invalid_mode() {
     let (_temp_dir, dir_path) = setup_test_env();
@@ -8,10 +8,9 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test_with_checker, TestPlan};
+use plib::testing::{run_test_with_options, OutputMatch, TestOptions, TestPlan};
 use std::fs;
 use std::path::Path;
-use std::process::Output;
 use tempfile::{tempdir, TempDir};
 
 fn setup_test_env() -> (TempDir, String) {
@@ -26,18 +25,17 @@ fn run_rmdir_test(args: Vec<&str>, expected_exit_code: i32, expected_err_substr:
         args: args.iter().map(|&s| s.into()).collect(),
         stdin_data: String::new(),
         expected_out: String::new(),
-        expected_err: String::new(),
+        expected_err: String::from(expected_err_substr),
         expected_exit_code,
     };
 
-    run_test_with_checker(plan, move |_, output: &Output| {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        assert!(
-            stderr.contains(expected_err_substr),
-            "Expected substring not found in stderr: '{}'",
-            stderr
-        );
-    });
+    run_test_with_options(
+        plan,
+        TestOptions {
+            stderr_match: OutputMatch::Contains,
+            ..Default::default()
+        },
+    );
 }
 
 #[test]
@@ -86,7 +84,21 @@ fn rmdir_remove_directory_with_parents() {
 
     fs::create_dir_all(&dir_path).expect("Unable to create test directories");
 
-    run_rmdir_test(vec!["-p", dir_path.to_str().unwrap()], 0, "");
+    // Use a relative path, so that -p stops at the temporary directory
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("rmdir"),
+            args: vec![String::from("-p"), String::from("parent/testdir")],
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::new(),
+            expected_exit_code: 0,
+        },
+        TestOptions {
+            cwd: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        },
+    );
 
     // Ensure the directories have been removed
     assert!(!dir_path.exists());
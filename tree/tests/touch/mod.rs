@@ -0,0 +1,186 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::os::unix::fs::{symlink, MetadataExt};
+use std::path::Path;
+
+use plib::testing::{run_test_with_options, TestOptions, TestPlan};
+use tempfile::tempdir;
+
+fn touch_test(args: &[&str], expected_err: &str, expected_exit_code: i32) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("touch"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: String::new(),
+            expected_err: String::from(expected_err),
+            expected_exit_code,
+        },
+        TestOptions::default().env("TZ", "UTC0"),
+    );
+}
+
+/// Access and modification times, as seconds and nanoseconds.
+fn times(path: &Path) -> ((i64, i64), (i64, i64)) {
+    let md = fs::symlink_metadata(path).unwrap();
+    ((md.atime(), md.atime_nsec()), (md.mtime(), md.mtime_nsec()))
+}
+
+#[test]
+fn test_touch_posix_time() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+    let file = file.to_str().unwrap();
+
+    touch_test(&["-t", "200001020304.05", file], "", 0);
+    assert_eq!(times(Path::new(file)), ((946782245, 0), (946782245, 0)));
+
+    // Two-digit years from 69 are in the 20th century
+    touch_test(&["-t", "6901010000", file], "", 0);
+    assert_eq!(times(Path::new(file)).1, (-31536000, 0));
+    touch_test(&["-t", "6801010000", file], "", 0);
+    assert_eq!(times(Path::new(file)).1, (3092601600, 0));
+}
+
+#[test]
+fn test_touch_iso_time() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+    let file = file.to_str().unwrap();
+
+    touch_test(&["-d", "2000-01-02T03:04:05.123456789", file], "", 0);
+    assert_eq!(
+        times(Path::new(file)),
+        ((946782245, 123456789), (946782245, 123456789))
+    );
+
+    touch_test(&["-d", "2000-01-02 03:04:05,5Z", file], "", 0);
+    assert_eq!(times(Path::new(file)).1, (946782245, 500000000));
+
+    touch_test(&["-d", "2000-01-02T03:04:05+01:00", file], "", 0);
+    assert_eq!(times(Path::new(file)).1, (946778645, 0));
+}
+
+#[test]
+fn test_touch_access_or_modification() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+    let file = file.to_str().unwrap();
+
+    touch_test(&["-t", "200001010000", file], "", 0);
+    touch_test(&["-a", "-t", "200101010000", file], "", 0);
+    assert_eq!(times(Path::new(file)), ((978307200, 0), (946684800, 0)));
+    touch_test(&["-m", "-t", "200201010000", file], "", 0);
+    assert_eq!(times(Path::new(file)), ((978307200, 0), (1009843200, 0)));
+}
+
+#[test]
+fn test_touch_reference_file() {
+    let dir = tempdir().unwrap();
+    let reference = dir.path().join("reference");
+    let file = dir.path().join("file");
+
+    touch_test(
+        &["-d", "2000-01-02T03:04:05.25Z", reference.to_str().unwrap()],
+        "",
+        0,
+    );
+    touch_test(
+        &["-r", reference.to_str().unwrap(), file.to_str().unwrap()],
+        "",
+        0,
+    );
+    assert_eq!(times(&file), times(&reference));
+}
+
+#[test]
+fn test_touch_no_create() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+
+    touch_test(&["-c", file.to_str().unwrap()], "", 0);
+    assert!(!file.exists());
+
+    touch_test(&[file.to_str().unwrap()], "", 0);
+    assert_eq!(fs::metadata(&file).unwrap().len(), 0);
+}
+
+#[test]
+fn test_touch_keeps_contents() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+    fs::write(&file, "contents").unwrap();
+
+    touch_test(&[file.to_str().unwrap()], "", 0);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "contents");
+}
+
+#[test]
+fn test_touch_no_dereference() {
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("target");
+    let link = dir.path().join("link");
+    fs::write(&target, "").unwrap();
+    symlink(&target, &link).unwrap();
+
+    touch_test(&["-t", "200001010000", target.to_str().unwrap()], "", 0);
+    touch_test(&["-h", "-t", "200101010000", link.to_str().unwrap()], "", 0);
+    assert_eq!(times(&link).1, (978307200, 0));
+    assert_eq!(times(&target).1, (946684800, 0));
+
+    // The times of a dangling link are its own
+    let dangling = dir.path().join("dangling");
+    symlink(dir.path().join("missing"), &dangling).unwrap();
+    touch_test(&["-h", dangling.to_str().unwrap()], "", 0);
+    assert!(!dir.path().join("missing").exists());
+
+    // With -h, a missing file isn't created
+    let missing = dir.path().join("missing");
+    touch_test(
+        &["-h", missing.to_str().unwrap()],
+        &format!("touch: {}: No such file or directory\n", missing.display()),
+        1,
+    );
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_touch_invalid_time() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file");
+    let file = file.to_str().unwrap();
+
+    for time in [
+        "0230000000",
+        "200001010000.5",
+        "20000101000",
+        "2000010100a0",
+    ] {
+        touch_test(
+            &["-t", time, file],
+            &format!("touch: invalid date format '{}'\n", time),
+            1,
+        );
+    }
+    for time in [
+        "2000-01-01",
+        "2000-01-01T00:00:61",
+        "2000-1-01T00:00:00",
+        "2000-01-01T00:00:00.Z",
+    ] {
+        touch_test(
+            &["-d", time, file],
+            &format!("touch: invalid date format '{}'\n", time),
+            1,
+        );
+    }
+    assert!(!Path::new(file).exists());
+}
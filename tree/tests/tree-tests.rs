@@ -10,6 +10,7 @@
 mod chgrp;
 mod chmod;
 mod cp;
+mod du;
 mod link;
 mod ls;
 mod mkdir;
@@ -17,4 +18,5 @@ mod mv;
 mod readlink;
 mod rm;
 mod rmdir;
+mod touch;
 mod unlink;
@@ -7,14 +7,24 @@
 // SPDX-License-Identifier: MIT
 //
 
-use chrono::{DateTime, Datelike, LocalResult, TimeZone, Utc};
+use std::ffi::CString;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+
+use chrono::{Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::diag::{error_message, Status};
+use plib::tdiag;
 
 /// touch - change file access and modification times
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about, disable_help_flag = true)]
 struct Args {
+    #[arg(long, action = clap::ArgAction::HelpLong)] // Bec. help clashes with -h
+    help: Option<bool>,
+
     /// Change the access time of file.
     #[arg(short, long)]
     access: bool,
@@ -27,6 +37,11 @@ struct Args {
     #[arg(short, long)]
     mtime: bool,
 
+    /// Change the times of symbolic links, rather than of the files they
+    /// point to, and take those of a symbolic ref_file itself.
+    #[arg(short = 'h', long)]
+    no_dereference: bool,
+
     /// Use the specified ISO 8601:2000 date-time format, instead of the current time.
     #[arg(short, long, group = "timefmt")]
     datetime: Option<String>,
@@ -40,216 +55,215 @@ struct Args {
     ref_file: Option<String>,
 
     /// A pathname of a file whose times shall be modified.
+    #[arg(required = true)]
     files: Vec<String>,
 }
 
-fn parse_tm_iso(time: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
-    let dt = DateTime::parse_from_rfc3339(time)?;
-    Ok(dt.into())
+/// The time zone a time is given in.
+enum Zone {
+    /// As TZ gives it
+    Local,
+    /// East of UTC, in seconds
+    Offset(i32),
 }
 
-fn parse_tm_posix(time: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
-    let mut time = String::from(time);
-    let mut seconds = String::from("0");
-
-    // split into YYYYMMDDhhmm and [.SS] components
-    let mut tmp_time = String::new();
-    match time.split_once('.') {
-        Some((t, secs)) => {
-            tmp_time = t.to_string();
-            seconds = secs.to_string();
-        }
-        None => {}
-    }
-    if !tmp_time.is_empty() {
-        time = tmp_time;
+fn number(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
-
-    // extract date and time elements, with length implying format
-    let tmp_year;
-    let (year_str, month_str, day_str, hour_str, minute_str) = match time.len() {
-        // format: MMDDhhmm[.SS]
-        8 => {
-            tmp_year = Utc::now().year().to_string();
-            (
-                tmp_year.as_str(),
-                &time[0..2],
-                &time[2..4],
-                &time[4..6],
-                &time[6..8],
-            )
-        }
-
-        // format: YYMMDDhhmm[.SS]
-        10 => {
-            let mut yearling = time[0..2].parse::<u32>()?;
-            if yearling <= 68 {
-                yearling += 2000;
-            } else {
-                yearling += 1900;
-            }
-            tmp_year = yearling.to_string();
-            (
-                tmp_year.as_str(),
-                &time[2..4],
-                &time[4..6],
-                &time[6..8],
-                &time[8..10],
-            )
-        }
-
-        // format: YYYYMMDDhhmm[.SS]
-        12 => (
-            &time[0..4],
-            &time[4..6],
-            &time[6..8],
-            &time[8..10],
-            &time[10..12],
-        ),
-        _ => {
-            return Err("Invalid time format".into());
-        }
-    };
-
-    // convert strings to integers
-    let year = year_str.parse::<i32>()?;
-    let month = month_str.parse::<u32>()?;
-    let day = day_str.parse::<u32>()?;
-    let hour = hour_str.parse::<u32>()?;
-    let minute = minute_str.parse::<u32>()?;
-    let secs = seconds.parse::<u32>()?;
-
-    // convert to DateTime and validate input
-    let res = Utc.with_ymd_and_hms(year, month, day, hour, minute, secs);
-    if res == LocalResult::None {
-        return Err("Invalid time".into());
-    }
-
-    // return parsed date
-    let dt = res.unwrap();
-    Ok(dt)
+    s.parse().ok()
 }
 
-fn parse_tm_ref_file(filename: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
-    let metadata = std::fs::metadata(filename)?;
-    let timespec = metadata.modified()?;
-    Ok(DateTime::from(timespec))
+/// Seconds since the Epoch of a date and time. A second of 60, a leap
+/// second, is the first second of the next minute.
+fn epoch_seconds(date: NaiveDate, hms: (u32, u32, u32), zone: Zone) -> Option<i64> {
+    let (hour, minute, second) = hms;
+    if second > 60 {
+        return None;
+    }
+    let time: NaiveDateTime =
+        date.and_hms_opt(hour, minute, second.min(59))? + Duration::seconds(i64::from(second / 60));
+    let seconds = match zone {
+        // In a repeated hour, the first of the two times is meant; a
+        // skipped hour doesn't exist
+        Zone::Local => Local.from_local_datetime(&time).earliest()?.timestamp(),
+        Zone::Offset(east) => FixedOffset::east_opt(east)?
+            .from_local_datetime(&time)
+            .single()?
+            .timestamp(),
+    };
+    Some(seconds)
 }
 
-fn touch_file_new(time: libc::time_t, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // open file for writing, creating if necessary
-    let flags = libc::O_CREAT | libc::O_WRONLY | libc::O_TRUNC;
-    let fd = unsafe { libc::open(filename.as_ptr() as *const i8, flags, 0o666) };
-    if fd < 0 {
-        return Err("Failed to open file".into());
+/// Parses the `[[CC]YY]MMDDhhmm[.SS]` time of `-t`, in the local time
+/// zone. A two-digit year of 69 to 99 is in the 20th century, else in
+/// the 21st; without a year, the time is in the current year.
+fn parse_posix_time(s: &str) -> Option<libc::timespec> {
+    let (digits, second) = match s.split_once('.') {
+        Some((digits, second)) if second.len() == 2 => (digits, number(second)?),
+        Some(_) => return None,
+        None => (s, 0),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
 
-    // configure file times array
-    let times = [
-        libc::timeval {
-            tv_sec: time,
-            tv_usec: 0,
-        },
-        libc::timeval {
-            tv_sec: time,
-            tv_usec: 0,
+    let (year, rest) = match digits.len() {
+        8 => (Local::now().year(), digits),
+        10 => match number(&digits[..2])? {
+            year @ 69..=99 => (1900 + year as i32, &digits[2..]),
+            year => (2000 + year as i32, &digits[2..]),
         },
-    ];
+        12 => (number(&digits[..4])? as i32, &digits[4..]),
+        _ => return None,
+    };
+    let field = |i: usize| number(&rest[i..i + 2]);
+    let date = NaiveDate::from_ymd_opt(year, field(0)?, field(2)?)?;
+    let seconds = epoch_seconds(date, (field(4)?, field(6)?, second), Zone::Local)?;
+
+    Some(libc::timespec {
+        tv_sec: seconds as libc::time_t,
+        tv_nsec: 0,
+    })
+}
 
-    // set file times
-    if unsafe { libc::futimes(fd, times.as_ptr()) } < 0 {
-        return Err("Failed to change file times".into());
+/// Parses the time zone ending an ISO 8601 time: none for local time,
+/// `Z` for UTC, or as an extension, an offset of `+hh`, `+hhmm` or
+/// `+hh:mm`, or the same with `-`.
+fn parse_zone(s: &str) -> Option<Zone> {
+    if s.is_empty() {
+        return Some(Zone::Local);
     }
-
-    // close file
-    if unsafe { libc::close(fd) } < 0 {
-        return Err("Failed to close file".into());
+    if s == "Z" {
+        return Some(Zone::Offset(0));
     }
-
-    Ok(())
+    let (sign, offset) = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(offset), _) => (1, offset),
+        (_, Some(offset)) => (-1, offset),
+        _ => return None,
+    };
+    if !offset.is_ascii() {
+        return None;
+    }
+    let (hours, minutes) = match offset.len() {
+        2 => (offset, "00"),
+        4 => offset.split_at(2),
+        5 if offset.as_bytes()[2] == b':' => (&offset[..2], &offset[3..]),
+        _ => return None,
+    };
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(Zone::Offset(sign * (hours * 3600 + minutes * 60) as i32))
 }
 
-fn touch_file_existing(
-    args: &Args,
-    time: libc::time_t,
-    filename: &str,
-    md: std::fs::Metadata,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // configure access and modification times
-    let atime = if args.access {
-        libc::timeval {
-            tv_sec: time,
-            tv_usec: 0,
-        }
-    } else {
-        libc::timeval {
-            tv_sec: md
-                .accessed()?
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs() as libc::time_t,
-            tv_usec: 0,
+/// Parses the `YYYY-MM-DDThh:mm:SS[.frac][tz]` time of `-d`, where `T`
+/// may be a space, and `.` a comma.
+fn parse_iso_time(s: &str) -> Option<libc::timespec> {
+    let (date, time) = s.split_once(['T', ' '])?;
+
+    let mut date_fields = date.split('-');
+    let (year, month, day) = (
+        date_fields.next()?,
+        date_fields.next()?,
+        date_fields.next()?,
+    );
+    if date_fields.next().is_some() || year.len() < 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let date = NaiveDate::from_ymd_opt(number(year)? as i32, number(month)?, number(day)?)?;
+
+    let hms = time.get(..8)?;
+    let bytes = hms.as_bytes();
+    if bytes[2] != b':' || bytes[5] != b':' {
+        return None;
+    }
+    let hms = (number(&hms[..2])?, number(&hms[3..5])?, number(&hms[6..8])?);
+
+    let rest = &time[8..];
+    let (nanoseconds, zone) = match rest.strip_prefix(['.', ',']) {
+        Some(rest) => {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            // Digits beyond nanoseconds are dropped
+            let fraction = format!("{:0<9}", &rest[..end.min(9)]);
+            (number(&fraction)?, &rest[end..])
         }
+        None => (0, rest),
     };
 
-    let mtime = if args.mtime {
-        libc::timeval {
-            tv_sec: time,
-            tv_usec: 0,
-        }
+    let seconds = epoch_seconds(date, hms, parse_zone(zone)?)?;
+    Some(libc::timespec {
+        tv_sec: seconds as libc::time_t,
+        tv_nsec: nanoseconds as _,
+    })
+}
+
+/// The access and modification times of `path`.
+fn file_times(path: &str, no_dereference: bool) -> io::Result<[libc::timespec; 2]> {
+    let md = if no_dereference {
+        fs::symlink_metadata(path)?
     } else {
-        libc::timeval {
-            tv_sec: md
-                .modified()?
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs() as libc::time_t,
-            tv_usec: 0,
-        }
+        fs::metadata(path)?
     };
-
-    // configure file times array
-    let times = [
-        libc::timeval {
-            tv_sec: atime.tv_sec,
-            tv_usec: atime.tv_usec,
+    Ok([
+        libc::timespec {
+            tv_sec: md.atime() as libc::time_t,
+            tv_nsec: md.atime_nsec() as _,
         },
-        libc::timeval {
-            tv_sec: mtime.tv_sec,
-            tv_usec: mtime.tv_usec,
+        libc::timespec {
+            tv_sec: md.mtime() as libc::time_t,
+            tv_nsec: md.mtime_nsec() as _,
         },
-    ];
+    ])
+}
 
-    // set file times
-    if unsafe { libc::utimes(filename.as_ptr() as *const i8, times.as_ptr()) } < 0 {
-        return Err("Failed to change file times".into());
+/// Sets the times of a file, creating it if it doesn't exist. A time of
+/// `UTIME_OMIT` is left as it is.
+fn touch_file(args: &Args, times: &[libc::timespec; 2], filename: &str) -> io::Result<()> {
+    let path = CString::new(filename)?;
+    let flags = if args.no_dereference {
+        libc::AT_SYMLINK_NOFOLLOW
+    } else {
+        0
+    };
+    // SAFETY: path is a C string and times holds two timespecs
+    if unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), flags) } == 0 {
+        return Ok(());
     }
 
-    Ok(())
-}
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() != Some(libc::ENOENT) {
+        return Err(e);
+    }
+    if args.no_create {
+        return Ok(());
+    }
+    // A symbolic link can't be created in place of a missing file
+    if args.no_dereference {
+        return Err(e);
+    }
 
-fn touch_file(
-    args: &Args,
-    timespec: &DateTime<Utc>,
-    filename: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // convert timespec to time_t
-    let time = timespec.timestamp() as libc::time_t;
-
-    // check if file exists, and dispatch based on that
-    match std::fs::metadata(filename) {
-        Ok(md) => touch_file_existing(args, time, filename, md),
-        Err(_) => {
-            if args.no_create {
-                return Err("File does not exist".into());
-            }
-            touch_file_new(time, filename)
-        }
+    // The file is new, so there is nothing to truncate
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(filename)?;
+    // SAFETY: the descriptor is open, and times holds two timespecs
+    if unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
     }
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    plib::i18n::init();
 
     let mut args = Args::parse();
 
@@ -260,27 +274,50 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // parse time format, or default to current time
-    let timespec: DateTime<Utc> = {
-        if let Some(datetime) = &args.datetime {
-            parse_tm_iso(datetime)?
-        } else if let Some(time) = &args.time {
-            parse_tm_posix(time)?
-        } else if let Some(ref_file) = &args.ref_file {
-            parse_tm_ref_file(ref_file)?
-        } else {
-            Utc::now()
+    let mut times = if let Some(datetime) = &args.datetime {
+        let Some(time) = parse_iso_time(datetime) else {
+            tdiag!("invalid date format '{}'", datetime);
+            std::process::exit(1);
+        };
+        [time, time]
+    } else if let Some(time) = &args.time {
+        let Some(time) = parse_posix_time(time) else {
+            tdiag!("invalid date format '{}'", time);
+            std::process::exit(1);
+        };
+        [time, time]
+    } else if let Some(ref_file) = &args.ref_file {
+        match file_times(ref_file, args.no_dereference) {
+            Ok(times) => times,
+            Err(e) => {
+                tdiag!(
+                    "failed to get attributes of '{}': {}",
+                    ref_file,
+                    error_message(&e)
+                );
+                std::process::exit(1);
+            }
         }
+    } else {
+        let now = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_NOW,
+        };
+        [now, now]
     };
 
-    let mut exit_code = 0;
+    if !args.access {
+        times[0].tv_nsec = libc::UTIME_OMIT;
+    }
+    if !args.mtime {
+        times[1].tv_nsec = libc::UTIME_OMIT;
+    }
 
-    // touch each file
+    let mut status = Status::new();
     for filename in &args.files {
-        if let Err(e) = touch_file(&args, &timespec, filename) {
-            exit_code = 1;
-            eprintln!("{}: {}", filename, e);
+        if let Err(e) = touch_file(&args, &times, filename) {
+            status.operand_error(filename, &e);
         }
     }
-
-    std::process::exit(exit_code)
+    status.exit();
 }
@@ -12,9 +12,8 @@
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::group;
+use plib::{group, userdb};
 use std::collections::HashMap;
-use std::io::Error;
 
 /// id - return user identity
 #[derive(Parser)]
@@ -61,42 +60,29 @@ fn userinfo_process(userinfo: &mut UserInfo) -> Result<(), Box<dyn std::error::E
     userinfo.euid = unsafe { libc::geteuid() };
     userinfo.egid = unsafe { libc::getegid() };
 
-    let passwd = unsafe { libc::getpwuid(userinfo.uid) };
-    if passwd.is_null() {
-        let err = Error::last_os_error();
-        eprintln!("getpwuid: {}", err);
-        return Err(Box::new(err));
-    }
-
-    userinfo.username = unsafe {
-        std::ffi::CStr::from_ptr((*passwd).pw_name)
-            .to_string_lossy()
-            .to_string()
+    let Some(user) = userdb::user_by_uid(userinfo.uid) else {
+        let err = format!("cannot find name for user ID {}", userinfo.uid);
+        eprintln!("id: {}", err);
+        return Err(err.into());
     };
 
+    userinfo.username = user.name.clone();
+
     Ok(())
 }
 
 fn userinfo_name(userinfo: &mut UserInfo, user: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let user_str = std::ffi::CString::new(user).unwrap();
-    let passwd = unsafe { libc::getpwnam(user_str.as_ptr()) };
-    if passwd.is_null() {
-        let err = Error::last_os_error();
-        eprintln!("getpwnam: {}", err);
-        return Err(Box::new(err));
-    }
+    let Some(passwd) = userdb::user_by_name(user) else {
+        let err = format!("{}: no such user", user);
+        eprintln!("id: {}", err);
+        return Err(err.into());
+    };
 
-    unsafe {
-        userinfo.uid = (*passwd).pw_uid;
-        userinfo.gid = (*passwd).pw_gid;
-    }
+    userinfo.uid = passwd.uid;
+    userinfo.gid = passwd.gid;
     userinfo.euid = userinfo.uid;
     userinfo.egid = userinfo.gid;
-    userinfo.username = unsafe {
-        std::ffi::CStr::from_ptr((*passwd).pw_name)
-            .to_string_lossy()
-            .to_string()
-    };
+    userinfo.username = passwd.name.clone();
 
     Ok(())
 }
@@ -7,137 +7,191 @@
 // SPDX-License-Identifier: MIT
 //
 
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use gettextrs::gettext;
+use plib::diag::{error_message, Status};
 use plib::io::input_stream;
-use plib::lzw::UnixLZWWriter;
-use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use plib::lzw::{UnixLZWWriter, MAX_BITS, MIN_BITS};
+use plib::{diag, i18n, tdiag, tr};
 
 const NAME_MAX: usize = 255;
 
-/// compress - compress data
+/// Exit status when a file was left alone because compressing it would
+/// have made it larger.
+const EXIT_NOT_COMPRESSED: i32 = 2;
+
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about = gettext("compress - compress data"))]
 struct Args {
-    /// Specify the maximum number of bits to use in a code. 9 <= bits <= 14
-    #[arg(short = 'b')]
-    bits: Option<u32>,
-
-    /// Write to the standard output; the input file is not changed, and no .Z files are created.
-    #[arg(short = 'c', long)]
+    #[arg(
+        short = 'b',
+        default_value_t = MAX_BITS,
+        value_parser = clap::value_parser!(u32).range(i64::from(MIN_BITS)..=i64::from(MAX_BITS)),
+        help = gettext("Maximum number of bits to use in a code, from 9 to 16")
+    )]
+    bits: u32,
+
+    #[arg(
+        short = 'c',
+        long,
+        help = gettext("Write to standard output; no files are changed")
+    )]
     stdout: bool,
 
-    /// Do not prompt for overwriting files
-    #[arg(short = 'f', long)]
+    #[arg(
+        short = 'f',
+        long,
+        help = gettext("Compress files even if they don't shrink, and overwrite existing files without prompting")
+    )]
     force: bool,
 
-    /// Write messages to standard error concerning the expansion of each file.
-    #[arg(short = 'v', long)]
+    #[arg(
+        short = 'v',
+        long,
+        help = gettext("Write the compression achieved for each file to standard error")
+    )]
     verbose: bool,
 
-    /// Files to read as input.  Use "-" or no-args for stdin.
+    #[arg(help = gettext("Files to compress; standard input if there are none"))]
     files: Vec<PathBuf>,
 }
 
-fn prompt_user(prompt: &str) -> bool {
-    eprint!("compress: {} ", prompt);
+/// Asks whether to overwrite `path`, if standard input is a terminal.
+fn may_overwrite(path: &Path) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        tdiag!("{}: already exists", path.display());
+        return Ok(false);
+    }
+    eprint!(
+        "{}",
+        tr!("{} already exists; overwrite (y or n)? ", path.display())
+    );
     let mut response = String::new();
-    io::stdin().read_line(&mut response).unwrap();
-    response.to_lowercase().starts_with('y')
+    io::stdin().read_line(&mut response)?;
+    if response.to_lowercase().starts_with('y') {
+        Ok(true)
+    } else {
+        eprintln!("{}", tr!("{} not overwritten", path.display()));
+        Ok(false)
+    }
 }
 
-fn compress_file(args: &Args, pathname: &PathBuf) -> io::Result<i32> {
-    let mut file = input_stream(pathname, false)?;
+/// Gives `path` the permissions and times of the file it replaces.
+fn copy_attributes(path: &Path, md: &fs::Metadata) -> io::Result<()> {
+    fs::set_permissions(path, md.permissions())?;
+    let times = FileTimes::new()
+        .set_accessed(md.accessed()?)
+        .set_modified(md.modified()?);
+    File::options().write(true).open(path)?.set_times(times)
+}
 
-    let mut encoder = UnixLZWWriter::new(args.bits);
+fn compress_stream(input: &mut dyn io::Read, output: impl Write, bits: u32) -> io::Result<()> {
+    let mut writer = UnixLZWWriter::new(output, bits)?;
+    io::copy(input, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
 
-    let mut inp_buf = Vec::new();
-    file.read_to_end(&mut inp_buf)?;
-    let inp_buf_size = inp_buf.len();
+fn compress_file(args: &Args, path: &Path, status: &mut Status) -> io::Result<()> {
+    let md = fs::metadata(path)?;
+    if !md.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            tr!("not a regular file"),
+        ));
+    }
+    let mut input = BufReader::new(File::open(path)?);
 
-    let mut out_buf = encoder.write(&inp_buf)?;
-    out_buf.extend_from_slice(&encoder.close()?);
-    let out_buf_size = out_buf.len();
+    if args.stdout {
+        let mut stdout = plib::io::stdout();
+        compress_stream(&mut input, &mut stdout, args.bits)?;
+        return stdout.finish();
+    }
 
-    // check if the output buffer size is greater than input buffer size
-    // this can indicate that the compressed file is greater than the input file
-    //
-    // in such case using '-f' works, i.e "force", which means we don't care if size
-    // isn't reduced, just compress it
-    if (out_buf_size < inp_buf_size) || args.force {
-        if args.stdout {
-            io::stdout().write_all(&out_buf)?;
-        } else {
-            let fname = format!("{}.Z", pathname.file_name().unwrap().to_str().unwrap());
-            // If "on adding .Z" the name exceeds the limit of NAME_MAX, then we output on the stdout
-            if fname.len() > NAME_MAX {
-                io::stdout().write_all(&out_buf)?;
-            } else {
-                let path = Path::new(&fname);
-                if path.exists() && !args.force {
-                    let is_affirm = prompt_user(&gettext!(
-                        "Do you want to overwrite {} (y)es or (n)o?",
-                        fname
-                    ));
-
-                    if !is_affirm {
-                        println!("{fname} not overwritten");
-                        return Ok(1);
-                    }
-                }
-
-                let mut new_file = pathname.clone();
-                new_file.set_file_name(&fname);
-                let mut f = File::create(&new_file)?;
-
-                fs::remove_file(pathname)?;
-                f.write_all(&out_buf)?;
-
-                if args.verbose {
-                    println!(
-                        "{}: -- replaced with {} Compression: {:.1}%",
-                        pathname.display(),
-                        new_file.display(),
-                        100_f32 - (out_buf_size as f32 / inp_buf_size as f32) * 100_f32
-                    );
-                }
-            }
+    if path.extension().is_some_and(|ext| ext == "Z") {
+        tdiag!("{}: already has .Z suffix -- no change", path.display());
+        return Ok(());
+    }
+    let mut target = OsString::from(path);
+    target.push(".Z");
+    let target = PathBuf::from(target);
+    if target.file_name().map_or(0, |name| name.len()) > NAME_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            tr!("file name too long"),
+        ));
+    }
+    if !args.force && target.symlink_metadata().is_ok() && !may_overwrite(&target)? {
+        status.fail();
+        return Ok(());
+    }
+
+    let output = BufWriter::new(File::create(&target)?);
+    if let Err(e) = compress_stream(&mut input, output, args.bits) {
+        let _ = fs::remove_file(&target);
+        return Err(e);
+    }
+
+    let size_in = md.len();
+    let size_out = fs::metadata(&target)?.len();
+    if size_out >= size_in && !args.force {
+        fs::remove_file(&target)?;
+        if args.verbose {
+            eprintln!("{}", tr!("{}: file unchanged", path.display()));
         }
-    } else {
-        // error status code when file was not compressed as they would have increased in size and (-f
-        // was not provided)
-        return Ok(2);
+        status.fail_with(EXIT_NOT_COMPRESSED);
+        return Ok(());
     }
 
-    Ok(0)
+    copy_attributes(&target, &md)?;
+    fs::remove_file(path)?;
+    if args.verbose {
+        let saved = if size_in == 0 {
+            0.0
+        } else {
+            100.0 - (size_out as f64 / size_in as f64) * 100.0
+        };
+        eprintln!(
+            "{}",
+            tr!(
+                "{}: -- replaced with {} Compression: {}%",
+                path.display(),
+                target.display(),
+                format!("{:.2}", saved)
+            )
+        );
+    }
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+fn main() {
+    i18n::init();
 
-    let mut args = Args::parse();
+    let args = Args::parse();
+    let mut status = Status::new();
 
     if args.files.is_empty() {
-        args.files.push(PathBuf::new());
+        let result = input_stream(&PathBuf::new(), false).and_then(|mut input| {
+            let mut stdout = plib::io::stdout();
+            compress_stream(&mut input, &mut stdout, args.bits)?;
+            stdout.finish()
+        });
+        if let Err(e) = result {
+            diag!("{}", error_message(&e));
+            status.fail();
+        }
     }
 
-    let mut exit_code = 0;
-
-    for filename in &args.files {
-        match compress_file(&args, filename) {
-            Ok(v) => {
-                exit_code = v;
-            }
-            Err(e) => {
-                exit_code = 1;
-                eprintln!("{}: {}", filename.display(), e);
-            }
+    for path in &args.files {
+        if let Err(e) = compress_file(&args, path, &mut status) {
+            status.operand_error(path.display(), &e);
         }
     }
 
-    std::process::exit(exit_code)
+    status.exit()
 }
@@ -0,0 +1,913 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+// TODO:
+// - -a (append to an archive), -i (interactive renaming), -o and -t
+// - write the pax interchange format; it is read, but not written
+//
+
+mod pax_util;
+
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{symlink, DirBuilderExt, FileTypeExt, MetadataExt, OpenOptionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use gettextrs::gettext;
+use pax_util::subst::{self, Substitution};
+use pax_util::{ArchiveReader, ArchiveWriter, Format, Kind, Member};
+use plib::diag::{error_message, Status};
+use plib::timefmt::{format_local, unix_seconds};
+use plib::userdb;
+use plib::walk::{Symlinks, Walker};
+use plib::{diag, i18n, tr};
+
+/// Files modified longer ago than this are listed with their year
+/// instead of their time of day, as ls does.
+const SIX_MONTHS: i64 = 6 * 30 * 86400;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = gettext("pax - portable archive interchange")
+)]
+struct Args {
+    #[arg(short, help = gettext("Read an archive"))]
+    read: bool,
+
+    #[arg(short, help = gettext("Write an archive"))]
+    write: bool,
+
+    #[arg(
+        short,
+        value_parser = parse_block_size,
+        help = gettext("Block size of the archive written, in bytes, or blocks of 512 bytes with b, or kilobytes with k")
+    )]
+    blocksize: Option<usize>,
+
+    #[arg(
+        short = 'c',
+        help = gettext("Select all files except those matching the patterns")
+    )]
+    complement: bool,
+
+    #[arg(
+        short = 'd',
+        help = gettext("Don't select or write the hierarchy below directories")
+    )]
+    no_descend: bool,
+
+    #[arg(short = 'f', help = gettext("Archive to read or write instead of standard input or output"))]
+    archive: Option<PathBuf>,
+
+    #[arg(
+        short = 'H',
+        overrides_with = "follow",
+        help = gettext("Follow symbolic links given as operands")
+    )]
+    follow_operands: bool,
+
+    #[arg(
+        short = 'k',
+        help = gettext("Don't overwrite existing files")
+    )]
+    keep_old: bool,
+
+    #[arg(
+        short = 'l',
+        help = gettext("In copy mode, link files instead of copying them where possible")
+    )]
+    link: bool,
+
+    #[arg(
+        short = 'L',
+        overrides_with = "follow_operands",
+        help = gettext("Follow all symbolic links")
+    )]
+    follow: bool,
+
+    #[arg(
+        short = 'n',
+        help = gettext("Select only the first member matching each pattern")
+    )]
+    first_match: bool,
+
+    #[arg(
+        short = 'p',
+        help = gettext("Characteristics to preserve: a, e, m, o or p")
+    )]
+    privileges: Vec<String>,
+
+    #[arg(
+        short = 's',
+        help = gettext("Substitute names with an expression of the form /old/new/[gp]")
+    )]
+    substitutions: Vec<String>,
+
+    #[arg(
+        short = 'u',
+        help = gettext("Don't replace files with older ones")
+    )]
+    update: bool,
+
+    #[arg(short, help = gettext("List the files processed"))]
+    verbose: bool,
+
+    #[arg(short = 'x', help = gettext("Format of the archive written: ustar or cpio"))]
+    format: Option<String>,
+
+    #[arg(
+        short = 'X',
+        help = gettext("Don't descend into directories on other file systems")
+    )]
+    one_file_system: bool,
+
+    /// Patterns to select members with, files to archive, or files to
+    /// copy followed by the directory to copy them to.
+    operands: Vec<OsString>,
+}
+
+fn parse_block_size(s: &str) -> Result<usize, String> {
+    let (digits, unit) = if let Some(digits) = s.strip_suffix('b') {
+        (digits, 512)
+    } else if let Some(digits) = s.strip_suffix('k') {
+        (digits, 1024)
+    } else {
+        (s, 1)
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .filter(|&n| n > 0 && n % 512 == 0)
+        .ok_or_else(|| tr!("invalid block size: {}", s))
+}
+
+/// Which characteristics of the files are given to the files extracted
+/// or copied, as -p selects them.
+struct Preserve {
+    atime: bool,
+    mtime: bool,
+    owner: bool,
+    mode: bool,
+}
+
+impl Preserve {
+    fn parse(specs: &[String]) -> Result<Preserve, String> {
+        let mut preserve = Preserve {
+            atime: true,
+            mtime: true,
+            owner: false,
+            mode: false,
+        };
+        for c in specs.iter().flat_map(|s| s.chars()) {
+            match c {
+                'a' => preserve.atime = false,
+                'e' => {
+                    preserve = Preserve {
+                        atime: true,
+                        mtime: true,
+                        owner: true,
+                        mode: true,
+                    }
+                }
+                'm' => preserve.mtime = false,
+                'o' => preserve.owner = true,
+                'p' => preserve.mode = true,
+                _ => return Err(tr!("invalid -p specifier: {}", c)),
+            }
+        }
+        Ok(preserve)
+    }
+}
+
+fn fnmatch(pattern: &CString, name: &OsStr) -> bool {
+    let Ok(name) = CString::new(name.as_bytes()) else {
+        return false;
+    };
+    // SAFETY: both are valid C strings.
+    unsafe { libc::fnmatch(pattern.as_ptr(), name.as_ptr(), 0) == 0 }
+}
+
+/// Selects the members to list or extract by the pattern operands.
+struct Selector {
+    patterns: Vec<(CString, bool)>,
+    complement: bool,
+    no_descend: bool,
+    first_match: bool,
+    /// Directories selected with -n, whose hierarchies are selected too.
+    dirs: Vec<OsString>,
+}
+
+impl Selector {
+    fn new(args: &Args, patterns: &[OsString]) -> Result<Selector, String> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                CString::new(p.as_bytes())
+                    .map(|p| (p, false))
+                    .map_err(|_| tr!("invalid pattern: {}", p.to_string_lossy()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Selector {
+            patterns,
+            complement: args.complement,
+            no_descend: args.no_descend,
+            first_match: args.first_match,
+            dirs: Vec::new(),
+        })
+    }
+
+    /// Parent directories of `name`, from the top.
+    fn ancestors(name: &OsStr) -> impl Iterator<Item = &OsStr> {
+        let bytes = name.as_bytes();
+        (1..bytes.len())
+            .filter(move |&i| bytes[i] == b'/')
+            .map(move |i| OsStr::from_bytes(&bytes[..i]))
+    }
+
+    fn matches(&mut self, member: &Member) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+        let name = member.name.as_os_str();
+        if !self.no_descend && Self::ancestors(name).any(|dir| self.dirs.iter().any(|d| d == dir)) {
+            return true;
+        }
+
+        let mut matched = false;
+        for (pattern, used) in &mut self.patterns {
+            if self.first_match && *used {
+                continue;
+            }
+            if fnmatch(pattern, name)
+                || (!self.no_descend
+                    && !self.first_match
+                    && Self::ancestors(name).any(|dir| fnmatch(pattern, dir)))
+            {
+                *used = true;
+                matched = true;
+                break;
+            }
+        }
+        if matched && self.first_match && member.kind == Kind::Dir {
+            self.dirs.push(member.name.clone());
+        }
+        matched
+    }
+
+    fn select(&mut self, member: &Member) -> bool {
+        self.matches(member) != self.complement
+    }
+}
+
+/// Creates the files read from an archive or copied, and sets their
+/// attributes.
+struct Extractor {
+    preserve: Preserve,
+    keep_old: bool,
+    update: bool,
+    umask: u32,
+    /// Directories created, whose attributes are set once their
+    /// contents are in place.
+    dirs: Vec<(PathBuf, Member, Option<i64>)>,
+}
+
+fn cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn check(ret: libc::c_int) -> io::Result<()> {
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn timespec(time: Option<i64>) -> libc::timespec {
+    libc::timespec {
+        tv_sec: time.unwrap_or(0) as libc::time_t,
+        tv_nsec: if time.is_some() { 0 } else { libc::UTIME_OMIT },
+    }
+}
+
+impl Extractor {
+    fn new(preserve: Preserve, args: &Args) -> Extractor {
+        // SAFETY: umask() can't fail; it is set back right away.
+        let umask = unsafe {
+            let mask = libc::umask(0);
+            libc::umask(mask);
+            mask
+        };
+        Extractor {
+            preserve,
+            keep_old: args.keep_old,
+            update: args.update,
+            umask: umask as u32,
+            dirs: Vec::new(),
+        }
+    }
+
+    /// Creates `path` as `member` describes it, with `data` as its
+    /// contents. `atime` is the access time to give it, if known. Hard
+    /// links are made to the path in `member.link`. Returns whether the
+    /// file was created.
+    fn extract(
+        &mut self,
+        member: &Member,
+        atime: Option<i64>,
+        data: &mut dyn Read,
+        path: &Path,
+    ) -> io::Result<bool> {
+        if let Ok(md) = fs::symlink_metadata(path) {
+            if self.keep_old || (self.update && md.mtime() >= member.mtime) {
+                return Ok(false);
+            }
+            if md.is_dir() {
+                if member.kind == Kind::Dir {
+                    self.dirs.push((path.to_path_buf(), member.clone(), atime));
+                    return Ok(true);
+                }
+                fs::remove_dir(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(0o777)
+                    .create(parent)?;
+            }
+        }
+
+        match member.kind {
+            Kind::File => {
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(member.mode & 0o777)
+                    .open(path)?;
+                io::copy(&mut data.take(member.size), &mut file)?;
+            }
+            Kind::Dir => {
+                // Keep the directory writable until its contents are in
+                fs::DirBuilder::new().mode(0o700).create(path)?;
+                self.dirs.push((path.to_path_buf(), member.clone(), atime));
+                return Ok(true);
+            }
+            Kind::Symlink => symlink(&member.link, path)?,
+            Kind::HardLink => {
+                fs::hard_link(&member.link, path)?;
+                return Ok(true);
+            }
+            Kind::CharDevice | Kind::BlockDevice | Kind::Fifo => {
+                let file_type = match member.kind {
+                    Kind::CharDevice => libc::S_IFCHR,
+                    Kind::BlockDevice => libc::S_IFBLK,
+                    _ => libc::S_IFIFO,
+                };
+                let dev = libc::makedev(member.dev_major, member.dev_minor);
+                let c_path = cstring(path)?;
+                let mode = file_type | (member.mode & 0o777) as libc::mode_t;
+                // SAFETY: c_path is a valid C string.
+                check(unsafe { libc::mknod(c_path.as_ptr(), mode, dev) })?;
+            }
+        }
+
+        self.set_attributes(path, member, atime)?;
+        Ok(true)
+    }
+
+    fn set_attributes(&self, path: &Path, member: &Member, atime: Option<i64>) -> io::Result<()> {
+        let c_path = cstring(path)?;
+
+        let mut owner_set = false;
+        if self.preserve.owner {
+            let uid = userdb::user_by_name(&member.uname).map_or(member.uid, |u| u.uid);
+            let gid = userdb::group_by_name(&member.gname).map_or(member.gid, |g| g.gid);
+            // SAFETY: c_path is a valid C string.
+            check(unsafe { libc::lchown(c_path.as_ptr(), uid, gid) })?;
+            owner_set = true;
+        }
+
+        if member.kind != Kind::Symlink {
+            let mode = if self.preserve.mode {
+                member.mode & 0o7777
+            } else {
+                member.mode & 0o7777 & !self.umask
+            };
+            // Set-ID bits are only kept along with the owner
+            let mode = if owner_set { mode } else { mode & 0o1777 };
+            // SAFETY: c_path is a valid C string.
+            check(unsafe { libc::chmod(c_path.as_ptr(), mode as libc::mode_t) })?;
+        }
+
+        let mtime = self.preserve.mtime.then_some(member.mtime);
+        let atime = if self.preserve.atime {
+            atime.or(Some(member.mtime))
+        } else {
+            None
+        };
+        if mtime.is_some() || atime.is_some() {
+            let times = [timespec(atime), timespec(mtime)];
+            // SAFETY: c_path is a valid C string and times has two elements.
+            check(unsafe {
+                libc::utimensat(
+                    libc::AT_FDCWD,
+                    c_path.as_ptr(),
+                    times.as_ptr(),
+                    libc::AT_SYMLINK_NOFOLLOW,
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Sets the attributes of the directories created, deepest first.
+    fn finish(&mut self, status: &mut Status) {
+        let dirs = std::mem::take(&mut self.dirs);
+        for (path, member, atime) in dirs.into_iter().rev() {
+            if let Err(e) = self.set_attributes(&path, &member, atime) {
+                status.operand_error(path.display(), &e);
+            }
+        }
+    }
+}
+
+/// Describes a file being archived or copied as a member.
+fn member_from_metadata(name: OsString, md: &fs::Metadata, path: &Path) -> io::Result<Member> {
+    let file_type = md.file_type();
+    let kind = if file_type.is_dir() {
+        Kind::Dir
+    } else if file_type.is_symlink() {
+        Kind::Symlink
+    } else if file_type.is_char_device() {
+        Kind::CharDevice
+    } else if file_type.is_block_device() {
+        Kind::BlockDevice
+    } else if file_type.is_fifo() {
+        Kind::Fifo
+    } else if file_type.is_file() {
+        Kind::File
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            tr!("file type not supported by the archive format"),
+        ));
+    };
+    let mut member = Member::new(name, kind);
+    member.mode = md.mode() & 0o7777;
+    member.uid = md.uid();
+    member.gid = md.gid();
+    member.uname = userdb::user_by_uid(md.uid()).map_or_else(String::new, |u| u.name.clone());
+    member.gname = userdb::group_by_gid(md.gid()).map_or_else(String::new, |g| g.name.clone());
+    member.mtime = md.mtime();
+    match kind {
+        Kind::File => member.size = md.len(),
+        Kind::Symlink => member.link = fs::read_link(path)?.into_os_string(),
+        Kind::CharDevice | Kind::BlockDevice => {
+            member.dev_major = libc::major(md.rdev());
+            member.dev_minor = libc::minor(md.rdev());
+        }
+        _ => {}
+    }
+    Ok(member)
+}
+
+/// Formats a member the way `ls -l` formats a file.
+fn long_listing(member: &Member, now: i64) -> String {
+    let type_char = match member.kind {
+        Kind::File | Kind::HardLink => '-',
+        Kind::Dir => 'd',
+        Kind::Symlink => 'l',
+        Kind::CharDevice => 'c',
+        Kind::BlockDevice => 'b',
+        Kind::Fifo => 'p',
+    };
+    let mut mode = String::from(type_char);
+    for (shift, special, set_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = member.mode >> shift;
+        mode.push(if bits & 4 != 0 { 'r' } else { '-' });
+        mode.push(if bits & 2 != 0 { 'w' } else { '-' });
+        mode.push(match (bits & 1 != 0, member.mode & special != 0) {
+            (true, true) => set_char,
+            (false, true) => set_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+
+    let owner = if member.uname.is_empty() {
+        member.uid.to_string()
+    } else {
+        member.uname.clone()
+    };
+    let group = if member.gname.is_empty() {
+        member.gid.to_string()
+    } else {
+        member.gname.clone()
+    };
+    let size = match member.kind {
+        Kind::CharDevice | Kind::BlockDevice => {
+            format!("{}, {}", member.dev_major, member.dev_minor)
+        }
+        _ => member.size.to_string(),
+    };
+    let mtime = UNIX_EPOCH + Duration::from_secs(u64::try_from(member.mtime).unwrap_or(0));
+    let date = if (now - member.mtime).abs() < SIX_MONTHS {
+        format_local("%b %e %H:%M", mtime)
+    } else {
+        format_local("%b %e  %Y", mtime)
+    };
+
+    let mut line = format!(
+        "{} {:>3} {:<8} {:<8} {:>8} {} {}",
+        mode,
+        1,
+        owner,
+        group,
+        size,
+        date,
+        member.name.to_string_lossy()
+    );
+    match member.kind {
+        Kind::Symlink => line += &format!(" -> {}", member.link.to_string_lossy()),
+        Kind::HardLink => line += &format!(" == {}", member.link.to_string_lossy()),
+        _ => {}
+    }
+    line
+}
+
+fn open_archive(args: &Args) -> io::Result<ArchiveReader> {
+    let input: Box<dyn Read> = match &args.archive {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin()),
+    };
+    ArchiveReader::new(input)
+}
+
+/// Applies the substitutions to the name of a member and of the member
+/// it is a hard link to. Returns `None` if the member is to be skipped.
+fn rename_member(member: &mut Member, substs: &[Substitution]) -> Option<()> {
+    if substs.is_empty() {
+        return Some(());
+    }
+    member.name = subst::rename(substs, &member.name);
+    if member.name.is_empty() {
+        return None;
+    }
+    if member.kind == Kind::HardLink {
+        member.link = subst::rename(substs, &member.link);
+    }
+    Some(())
+}
+
+fn list(args: &Args, substs: &[Substitution]) -> Result<Status, String> {
+    let mut selector = Selector::new(args, &args.operands)?;
+    let mut reader = open_archive(args).map_err(|e| error_message(&e))?;
+    let now = unix_seconds(SystemTime::now());
+    let mut stdout = io::stdout().lock();
+
+    while let Some(mut member) = reader.next_member().map_err(|e| error_message(&e))? {
+        if !selector.select(&member) || rename_member(&mut member, substs).is_none() {
+            continue;
+        }
+        let line = if args.verbose {
+            long_listing(&member, now)
+        } else {
+            member.name.to_string_lossy().into_owned()
+        };
+        writeln!(stdout, "{}", line).map_err(|e| error_message(&e))?;
+    }
+    Ok(Status::new())
+}
+
+/// The path to extract an archive member named `name` to, relative to the
+/// current directory: a leading `/` is removed. Returns `None` for names
+/// with a `..` component, as they could point outside of it.
+fn extraction_path(name: &OsStr) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+    let path: PathBuf = path
+        .components()
+        .filter(|c| *c != Component::RootDir)
+        .collect();
+    if path.as_os_str().is_empty() {
+        Some(PathBuf::from("."))
+    } else {
+        Some(path)
+    }
+}
+
+fn read(args: &Args, substs: &[Substitution], preserve: Preserve) -> Result<Status, String> {
+    let mut selector = Selector::new(args, &args.operands)?;
+    let mut reader = open_archive(args).map_err(|e| error_message(&e))?;
+    let mut extractor = Extractor::new(preserve, args);
+    let mut status = Status::new();
+
+    while let Some(mut member) = reader.next_member().map_err(|e| error_message(&e))? {
+        if !selector.select(&member) || rename_member(&mut member, substs).is_none() {
+            continue;
+        }
+        if args.verbose {
+            eprintln!("{}", member.name.to_string_lossy());
+        }
+        let Some(path) = extraction_path(&member.name) else {
+            status.operand_error(
+                member.name.to_string_lossy(),
+                &tr!("member name contains '..', skipping"),
+            );
+            continue;
+        };
+        if member.kind == Kind::HardLink {
+            let Some(link) = extraction_path(&member.link) else {
+                status.operand_error(
+                    member.name.to_string_lossy(),
+                    &tr!("link target contains '..', skipping"),
+                );
+                continue;
+            };
+            member.link = link.into_os_string();
+        }
+        if let Err(e) = extractor.extract(&member, None, &mut reader, &path) {
+            status.operand_error(path.display(), &e);
+        }
+    }
+
+    extractor.finish(&mut status);
+    Ok(status)
+}
+
+/// The files to archive or copy: the operands, or else the pathnames
+/// read from standard input, one per line.
+fn file_operands(operands: &[OsString]) -> Result<Vec<PathBuf>, String> {
+    if !operands.is_empty() {
+        return Ok(operands.iter().map(PathBuf::from).collect());
+    }
+    let mut paths = Vec::new();
+    for line in io::stdin().lock().split(b'\n') {
+        let line = line.map_err(|e| error_message(&e))?;
+        if !line.is_empty() {
+            paths.push(PathBuf::from(OsStr::from_bytes(&line)));
+        }
+    }
+    Ok(paths)
+}
+
+fn walker(args: &Args, path: &Path) -> Walker {
+    let symlinks = if args.follow {
+        Symlinks::Logical
+    } else if args.follow_operands {
+        Symlinks::CommandLine
+    } else {
+        Symlinks::Physical
+    };
+    let walker = Walker::new(path)
+        .symlinks(symlinks)
+        .same_file_system(args.one_file_system);
+    if args.no_descend {
+        walker.max_depth(0)
+    } else {
+        walker
+    }
+}
+
+fn stat(args: &Args, path: &Path, depth: usize) -> io::Result<fs::Metadata> {
+    if args.follow || (args.follow_operands && depth == 0) {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    }
+}
+
+fn write(args: &Args, substs: &[Substitution]) -> Result<Status, String> {
+    let format = match &args.format {
+        Some(name) => {
+            Format::parse(name).ok_or_else(|| tr!("unsupported archive format: {}", name))?
+        }
+        None => Format::Ustar,
+    };
+    let paths = file_operands(&args.operands)?;
+    let output: Box<dyn Write> = match &args.archive {
+        Some(path) => Box::new(
+            File::create(path).map_err(|e| format!("{}: {}", path.display(), error_message(&e)))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+    let block_size = args
+        .blocksize
+        .unwrap_or_else(|| format.default_block_size());
+    let mut writer = ArchiveWriter::new(output, format, block_size);
+    let mut status = Status::new();
+    // First names of the files with several links, to archive the others
+    // as links to them
+    let mut links: HashMap<(u64, u64), OsString> = HashMap::new();
+
+    for path in &paths {
+        let mut walker = walker(args, path);
+        for entry in walker.by_ref() {
+            let path = entry.path();
+            let name = subst::rename(substs, path.as_os_str());
+            if name.is_empty() {
+                continue;
+            }
+            let md = match stat(args, path, entry.depth()) {
+                Ok(md) => md,
+                Err(e) => {
+                    status.operand_error(path.display(), &e);
+                    continue;
+                }
+            };
+            let mut member = match member_from_metadata(name, &md, path) {
+                Ok(member) => member,
+                Err(e) => {
+                    status.operand_error(path.display(), &e);
+                    continue;
+                }
+            };
+            if member.kind == Kind::File && md.nlink() > 1 {
+                if let Some(first) = links.get(&(md.dev(), md.ino())) {
+                    member.kind = Kind::HardLink;
+                    member.link = first.clone();
+                    member.size = 0;
+                } else {
+                    links.insert((md.dev(), md.ino()), member.name.clone());
+                }
+            }
+
+            let mut file = None;
+            if member.kind == Kind::File {
+                match File::open(path) {
+                    Ok(f) => file = Some(f),
+                    Err(e) => {
+                        status.operand_error(path.display(), &e);
+                        continue;
+                    }
+                }
+            }
+            if args.verbose {
+                eprintln!("{}", member.name.to_string_lossy());
+            }
+            let data: &mut dyn Read = match &mut file {
+                Some(f) => f,
+                None => &mut io::empty(),
+            };
+            let file_id = (md.dev(), md.ino(), md.nlink());
+            if let Err(e) = writer.append(&member, file_id, data) {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    status.operand_error(path.display(), &e);
+                } else {
+                    return Err(error_message(&e));
+                }
+            }
+        }
+        if walker.failed() {
+            status.fail();
+        }
+    }
+
+    writer.finish().map_err(|e| error_message(&e))?;
+    Ok(status)
+}
+
+fn copy(args: &Args, substs: &[Substitution], preserve: Preserve) -> Result<Status, String> {
+    let Some((dest, operands)) = args.operands.split_last() else {
+        return Err(tr!("missing destination directory operand"));
+    };
+    let dest = PathBuf::from(dest);
+    let dest_md = fs::metadata(&dest)
+        .ok()
+        .filter(|md| md.is_dir())
+        .ok_or_else(|| tr!("{}: not a directory", dest.display()))?;
+    let paths = file_operands(operands)?;
+    let mut extractor = Extractor::new(preserve, args);
+    let mut status = Status::new();
+    // Destinations of the files with several links, to link the others
+    // to them
+    let mut links: HashMap<(u64, u64), PathBuf> = HashMap::new();
+
+    for path in &paths {
+        let mut walker = walker(args, path);
+        while let Some(entry) = walker.next() {
+            let path = entry.path();
+            let md = match stat(args, path, entry.depth()) {
+                Ok(md) => md,
+                Err(e) => {
+                    status.operand_error(path.display(), &e);
+                    continue;
+                }
+            };
+            // Don't copy the destination into itself
+            if md.dev() == dest_md.dev() && md.ino() == dest_md.ino() {
+                walker.skip_current_dir();
+                continue;
+            }
+            let name = subst::rename(substs, path.as_os_str());
+            if name.is_empty() {
+                continue;
+            }
+            let target = dest.join(Path::new(&name).strip_prefix("/").unwrap_or(name.as_ref()));
+            let mut member = match member_from_metadata(name, &md, path) {
+                Ok(member) => member,
+                Err(e) => {
+                    status.operand_error(path.display(), &e);
+                    continue;
+                }
+            };
+            if args.verbose {
+                eprintln!("{}", target.display());
+            }
+
+            if member.kind == Kind::File {
+                if args.link {
+                    let mut link = member.clone();
+                    link.kind = Kind::HardLink;
+                    link.link = path.as_os_str().to_os_string();
+                    if extractor
+                        .extract(&link, None, &mut io::empty(), &target)
+                        .is_ok()
+                    {
+                        continue;
+                    }
+                }
+                if md.nlink() > 1 {
+                    if let Some(first) = links.get(&(md.dev(), md.ino())) {
+                        member.kind = Kind::HardLink;
+                        member.link = first.as_os_str().to_os_string();
+                    } else {
+                        links.insert((md.dev(), md.ino()), target.clone());
+                    }
+                }
+            }
+
+            let mut file = None;
+            if member.kind == Kind::File {
+                match File::open(path) {
+                    Ok(f) => file = Some(f),
+                    Err(e) => {
+                        status.operand_error(path.display(), &e);
+                        continue;
+                    }
+                }
+            }
+            let data: &mut dyn Read = match &mut file {
+                Some(f) => f,
+                None => &mut io::empty(),
+            };
+            if let Err(e) = extractor.extract(&member, Some(md.atime()), data, &target) {
+                status.operand_error(target.display(), &e);
+            }
+        }
+        if walker.failed() {
+            status.fail();
+        }
+    }
+
+    extractor.finish(&mut status);
+    Ok(status)
+}
+
+fn pax(args: &Args) -> Result<Status, String> {
+    let substs = args
+        .substitutions
+        .iter()
+        .map(|s| Substitution::parse(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let preserve = Preserve::parse(&args.privileges)?;
+
+    match (args.read, args.write) {
+        (false, false) => list(args, &substs),
+        (true, false) => read(args, &substs, preserve),
+        (false, true) => write(args, &substs),
+        (true, true) => copy(args, &substs, preserve),
+    }
+}
+
+fn main() {
+    i18n::init();
+
+    let args = Args::parse();
+    match pax(&args) {
+        Ok(status) => status.exit(),
+        Err(msg) => {
+            diag!("{}", msg);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The cpio interchange format, with its headers of octal numbers.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+use super::{invalid_data, parse_octal, read_full, unexpected_eof, Kind, Member};
+use plib::tr;
+
+const MAGIC: &[u8] = b"070707";
+const HEADER_SIZE: usize = 76;
+const TRAILER: &[u8] = b"TRAILER!!!";
+
+/// Widths of the header fields after the magic number, in order: c_dev,
+/// c_ino, c_mode, c_uid, c_gid, c_nlink, c_rdev, c_mtime, c_namesize and
+/// c_filesize.
+const FIELDS: [usize; 10] = [6, 6, 6, 6, 6, 6, 6, 11, 6, 11];
+
+const C_ISDIR: u32 = 0o040000;
+const C_ISFIFO: u32 = 0o010000;
+const C_ISREG: u32 = 0o100000;
+const C_ISBLK: u32 = 0o060000;
+const C_ISCHR: u32 = 0o020000;
+const C_ISLNK: u32 = 0o120000;
+const C_ISMASK: u32 = 0o170000;
+
+pub fn is_header(start: &[u8]) -> bool {
+    start.starts_with(MAGIC)
+}
+
+/// Reads the header of the next member, or `None` at the end of the
+/// archive. `links` keeps the first names of files with several links,
+/// so that the others can be made hard links to them.
+pub fn read_header(
+    r: &mut dyn Read,
+    links: &mut HashMap<(u64, u64), OsString>,
+) -> io::Result<Option<Member>> {
+    let mut header = [0; HEADER_SIZE];
+    match read_full(r, &mut header)? {
+        0 => return Ok(None),
+        HEADER_SIZE => {}
+        _ => return Err(unexpected_eof()),
+    }
+    if !header.starts_with(MAGIC) {
+        return Err(invalid_data(tr!("invalid cpio header")));
+    }
+
+    let mut values = [0; FIELDS.len()];
+    let mut offset = MAGIC.len();
+    for (value, width) in values.iter_mut().zip(FIELDS) {
+        *value = parse_octal(&header[offset..offset + width])
+            .ok_or_else(|| invalid_data(tr!("invalid number in archive header")))?;
+        offset += width;
+    }
+    let [dev, ino, mode, uid, gid, nlink, rdev, mtime, namesize, filesize] = values;
+
+    let mut name = vec![0; usize::try_from(namesize).unwrap_or(usize::MAX)];
+    if read_full(r, &mut name)? < name.len() {
+        return Err(unexpected_eof());
+    }
+    if name.last() == Some(&0) {
+        name.pop();
+    }
+    if name == TRAILER {
+        return Ok(None);
+    }
+
+    let mode = mode as u32;
+    let kind = match mode & C_ISMASK {
+        C_ISDIR => Kind::Dir,
+        C_ISLNK => Kind::Symlink,
+        C_ISCHR => Kind::CharDevice,
+        C_ISBLK => Kind::BlockDevice,
+        C_ISFIFO => Kind::Fifo,
+        _ => Kind::File,
+    };
+    let mut member = Member::new(OsString::from_vec(name), kind);
+    member.mode = mode & 0o7777;
+    member.uid = uid as u32;
+    member.gid = gid as u32;
+    member.mtime = mtime as i64;
+    member.size = filesize;
+
+    match kind {
+        Kind::Symlink => {
+            let mut target = Vec::new();
+            (&mut *r).take(filesize).read_to_end(&mut target)?;
+            if (target.len() as u64) < filesize {
+                return Err(unexpected_eof());
+            }
+            member.link = OsString::from_vec(target);
+            member.size = 0;
+        }
+        Kind::CharDevice | Kind::BlockDevice => {
+            member.dev_major = libc::major(rdev as libc::dev_t);
+            member.dev_minor = libc::minor(rdev as libc::dev_t);
+        }
+        Kind::File if nlink > 1 => match links.get(&(dev, ino)) {
+            // Links after the first are stored without data
+            Some(first) if filesize == 0 => {
+                member.kind = Kind::HardLink;
+                member.link = first.clone();
+            }
+            Some(_) => {}
+            None => {
+                links.insert((dev, ino), member.name.clone());
+            }
+        },
+        _ => {}
+    }
+    Ok(Some(member))
+}
+
+fn put_octal(out: &mut Vec<u8>, width: usize, value: u64) -> io::Result<()> {
+    let s = format!("{:0width$o}", value, width = width);
+    if s.len() > width {
+        return Err(invalid_data(tr!("value too large for cpio header")));
+    }
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Encodes the header of a member, with the inode number and number of
+/// links given. The data following it is the member's data, or the
+/// target of a symbolic link.
+pub fn header(member: &Member, ino: u64, nlink: u64) -> io::Result<Vec<u8>> {
+    let (type_bits, size) = match member.kind {
+        Kind::File => (C_ISREG, member.size),
+        // A link after the first, which cpio readers recognize by its
+        // inode number
+        Kind::HardLink => (C_ISREG, 0),
+        Kind::Dir => (C_ISDIR, 0),
+        Kind::Symlink => (C_ISLNK, member.link.len() as u64),
+        Kind::CharDevice => (C_ISCHR, 0),
+        Kind::BlockDevice => (C_ISBLK, 0),
+        Kind::Fifo => (C_ISFIFO, 0),
+    };
+    let rdev = match member.kind {
+        Kind::CharDevice | Kind::BlockDevice => {
+            libc::makedev(member.dev_major, member.dev_minor) as u64
+        }
+        _ => 0,
+    };
+    let name = member.name.as_bytes();
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + name.len() + 1);
+    out.extend_from_slice(MAGIC);
+    let values = [
+        0,
+        ino,
+        u64::from(type_bits | (member.mode & 0o7777)),
+        u64::from(member.uid),
+        u64::from(member.gid),
+        nlink.min(0o777777),
+        rdev,
+        u64::try_from(member.mtime).unwrap_or(0),
+        name.len() as u64 + 1,
+        size,
+    ];
+    for (value, width) in values.into_iter().zip(FIELDS) {
+        put_octal(&mut out, width, value)?;
+    }
+    out.extend_from_slice(name);
+    out.push(0);
+    Ok(out)
+}
+
+/// The member marking the end of the archive.
+pub fn trailer() -> Vec<u8> {
+    let member = Member::new(OsString::from_vec(TRAILER.to_vec()), Kind::File);
+    header(&member, 0, 1).expect("trailer fits")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_links() {
+        let mut archive = Vec::new();
+        let mut first = Member::new("a".into(), Kind::File);
+        first.size = 1;
+        archive.extend(header(&first, 7, 2).unwrap());
+        archive.push(b'x');
+        archive.extend(header(&Member::new("b".into(), Kind::HardLink), 7, 2).unwrap());
+        archive.extend(trailer());
+
+        let mut r = &archive[..];
+        let mut links = HashMap::new();
+        let a = read_header(&mut r, &mut links).unwrap().unwrap();
+        assert_eq!((a.kind, a.size), (Kind::File, 1));
+        r = &r[1..];
+        let b = read_header(&mut r, &mut links).unwrap().unwrap();
+        assert_eq!((b.kind, b.link.as_os_str()), (Kind::HardLink, "a".as_ref()));
+        assert!(read_header(&mut r, &mut links).unwrap().is_none());
+    }
+}
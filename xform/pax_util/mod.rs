@@ -0,0 +1,423 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Archive formats understood by pax, and reading and writing archives
+//! in them a member at a time.
+
+mod cpio;
+pub mod subst;
+mod ustar;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Cursor, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ustar,
+    /// The portable octal ("odc") cpio format POSIX describes.
+    Cpio,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "ustar" => Some(Format::Ustar),
+            "cpio" => Some(Format::Cpio),
+            _ => None,
+        }
+    }
+
+    /// The block size written by default, as POSIX specifies it.
+    pub fn default_block_size(self) -> usize {
+        match self {
+            Format::Ustar => 10240,
+            Format::Cpio => 5120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Dir,
+    Symlink,
+    /// A hard link to a member earlier in the archive.
+    HardLink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+}
+
+/// An archive member: a file, its attributes and, for regular files,
+/// the size of the data that follows it.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: OsString,
+    pub kind: Kind,
+    /// Permission bits, including the set-ID and sticky bits.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// User and group names, where the format records them.
+    pub uname: String,
+    pub gname: String,
+    pub size: u64,
+    pub mtime: i64,
+    /// Target of a symbolic or hard link.
+    pub link: OsString,
+    pub dev_major: u32,
+    pub dev_minor: u32,
+}
+
+impl Member {
+    pub fn new(name: OsString, kind: Kind) -> Member {
+        Member {
+            name,
+            kind,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            uname: String::new(),
+            gname: String::new(),
+            size: 0,
+            mtime: 0,
+            link: OsString::new(),
+            dev_major: 0,
+            dev_minor: 0,
+        }
+    }
+}
+
+pub(crate) fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads until `buf` is full or the end of input, returning the number
+/// of bytes read.
+fn read_full(r: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(len) => n += len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+/// Parses an octal number, ignoring leading blanks and stopping at the
+/// first non-octal character, as archive headers are written.
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let digits: Vec<u8> = field
+        .iter()
+        .copied()
+        .skip_while(|&b| b == b' ')
+        .take_while(|b| (b'0'..=b'7').contains(b))
+        .collect();
+    if digits.is_empty() {
+        // An empty field is zero
+        return field.iter().all(|&b| b == 0 || b == b' ').then_some(0);
+    }
+    u64::from_str_radix(std::str::from_utf8(&digits).ok()?, 8).ok()
+}
+
+/// Reads the members of an archive, in whichever format it turns out to
+/// be written in.
+pub struct ArchiveReader {
+    inner: Box<dyn Read>,
+    format: Format,
+    /// Data of the current member not read yet, and the padding after it.
+    remaining: u64,
+    padding: u64,
+    /// First names of the cpio members with several links, by device and
+    /// inode number.
+    links: HashMap<(u64, u64), OsString>,
+}
+
+impl ArchiveReader {
+    pub fn new(mut inner: Box<dyn Read>) -> io::Result<ArchiveReader> {
+        let mut start = vec![0; ustar::BLOCK_SIZE];
+        let n = read_full(&mut inner, &mut start)?;
+        start.truncate(n);
+
+        let format = if cpio::is_header(&start) {
+            Format::Cpio
+        } else if ustar::is_header(&start) {
+            Format::Ustar
+        } else {
+            return Err(invalid_data(plib::tr!("unrecognized archive format")));
+        };
+
+        Ok(ArchiveReader {
+            inner: Box::new(Cursor::new(start).chain(inner)),
+            format,
+            remaining: 0,
+            padding: 0,
+            links: HashMap::new(),
+        })
+    }
+
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(n), &mut io::sink())?;
+        if skipped < n {
+            return Err(unexpected_eof());
+        }
+        Ok(())
+    }
+
+    /// Moves on to the next member, skipping the data of the current one
+    /// if it wasn't read. Returns `None` at the end of the archive.
+    pub fn next_member(&mut self) -> io::Result<Option<Member>> {
+        self.skip(self.remaining + self.padding)?;
+        self.remaining = 0;
+        self.padding = 0;
+
+        let mut member = match self.format {
+            Format::Ustar => ustar::read_header(&mut self.inner)?,
+            Format::Cpio => cpio::read_header(&mut self.inner, &mut self.links)?,
+        };
+        if let Some(member) = &mut member {
+            // ustar writes directory names with a trailing slash
+            while member.kind == Kind::Dir
+                && member.name.len() > 1
+                && member.name.as_bytes().ends_with(b"/")
+            {
+                let len = member.name.len() - 1;
+                member.name = OsStr::from_bytes(&member.name.as_bytes()[..len]).to_os_string();
+            }
+            self.remaining = member.size;
+            if self.format == Format::Ustar {
+                self.padding = ustar::padding(member.size);
+            }
+        }
+        Ok(member)
+    }
+}
+
+/// Reads the data of the current member.
+impl Read for ArchiveReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(unexpected_eof());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn unexpected_eof() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        plib::tr!("unexpected end of archive"),
+    )
+}
+
+/// Groups output into blocks of a fixed size, as archives are written.
+struct BlockWriter {
+    inner: Box<dyn Write>,
+    block: Vec<u8>,
+    block_size: usize,
+}
+
+impl BlockWriter {
+    /// Pads the last block with zeros and writes it out.
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.block.is_empty() {
+            self.block.resize(self.block_size, 0);
+            self.inner.write_all(&self.block)?;
+            self.block.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+impl Write for BlockWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.block_size - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+        if self.block.len() == self.block_size {
+            self.inner.write_all(&self.block)?;
+            self.block.clear();
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes an archive a member at a time.
+pub struct ArchiveWriter {
+    out: BlockWriter,
+    format: Format,
+    /// Inode numbers given to the files written to a cpio archive, by
+    /// their device and inode number. Renumbering them keeps them from
+    /// being truncated to the width of the header fields.
+    inodes: HashMap<(u64, u64), u64>,
+}
+
+impl ArchiveWriter {
+    pub fn new(inner: Box<dyn Write>, format: Format, block_size: usize) -> ArchiveWriter {
+        ArchiveWriter {
+            out: BlockWriter {
+                inner,
+                block: Vec::with_capacity(block_size),
+                block_size,
+            },
+            format,
+            inodes: HashMap::new(),
+        }
+    }
+
+    /// Appends a member, followed by `member.size` bytes of `data`. If
+    /// `data` ends early, the data is padded with zeros to keep the
+    /// archive intact, and an error returned. `file_id` is the device and
+    /// inode number of the file, and its number of links.
+    pub fn append(
+        &mut self,
+        member: &Member,
+        file_id: (u64, u64, u64),
+        data: &mut dyn Read,
+    ) -> io::Result<()> {
+        match self.format {
+            Format::Ustar => {
+                let header = ustar::header(member)?;
+                self.out.write_all(&header)?;
+            }
+            Format::Cpio => {
+                let next_ino = self.inodes.len() as u64 + 1;
+                let (dev, ino, nlink) = file_id;
+                let ino = *self.inodes.entry((dev, ino)).or_insert(next_ino);
+                let header = cpio::header(member, ino, nlink)?;
+                self.out.write_all(&header)?;
+                // The target of a symbolic link is its data
+                if member.kind == Kind::Symlink {
+                    return self.out.write_all(member.link.as_bytes());
+                }
+            }
+        }
+
+        let copied = io::copy(&mut data.take(member.size), &mut self.out)?;
+        if copied < member.size {
+            io::copy(&mut io::repeat(0).take(member.size - copied), &mut self.out)?;
+        }
+        if self.format == Format::Ustar {
+            let padding = ustar::padding(member.size);
+            io::copy(&mut io::repeat(0).take(padding), &mut self.out)?;
+        }
+        if copied < member.size {
+            return Err(invalid_data(plib::tr!("file shrank while being archived")));
+        }
+        Ok(())
+    }
+
+    /// Writes the end of the archive, padded to a whole block.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.format {
+            Format::Ustar => self.out.write_all(&[0; ustar::BLOCK_SIZE * 2])?,
+            Format::Cpio => self.out.write_all(&cpio::trailer())?,
+        }
+        self.out.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(format: Format) -> Vec<u8> {
+        let buf = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+
+        struct Shared(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = ArchiveWriter::new(Box::new(Shared(buf.clone())), format, 1024);
+        let mut dir = Member::new("dir".into(), Kind::Dir);
+        dir.mode = 0o755;
+        writer.append(&dir, (1, 1, 2), &mut io::empty()).unwrap();
+
+        let mut file = Member::new("dir/file".into(), Kind::File);
+        file.mode = 0o644;
+        file.size = 5;
+        file.mtime = 1_000_000_000;
+        file.uid = 1000;
+        writer.append(&file, (1, 2, 1), &mut &b"hello"[..]).unwrap();
+
+        let mut link = Member::new("dir/link".into(), Kind::Symlink);
+        link.mode = 0o777;
+        link.link = "file".into();
+        writer.append(&link, (1, 3, 1), &mut io::empty()).unwrap();
+        writer.finish().unwrap();
+
+        buf.take()
+    }
+
+    fn round_trip(format: Format) {
+        let data = sample(format);
+        assert_eq!(data.len() % 1024, 0);
+
+        let mut reader = ArchiveReader::new(Box::new(Cursor::new(data))).unwrap();
+        assert_eq!(reader.format, format);
+
+        let dir = reader.next_member().unwrap().unwrap();
+        assert_eq!(dir.kind, Kind::Dir);
+        assert_eq!(dir.mode, 0o755);
+
+        let file = reader.next_member().unwrap().unwrap();
+        assert_eq!(file.name, "dir/file");
+        assert_eq!((file.kind, file.mode, file.uid), (Kind::File, 0o644, 1000));
+        assert_eq!(file.mtime, 1_000_000_000);
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello");
+
+        let link = reader.next_member().unwrap().unwrap();
+        assert_eq!(
+            (link.kind, link.link.as_os_str()),
+            (Kind::Symlink, "file".as_ref())
+        );
+        assert!(reader.next_member().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ustar_round_trip() {
+        round_trip(Format::Ustar);
+    }
+
+    #[test]
+    fn test_cpio_round_trip() {
+        round_trip(Format::Cpio);
+    }
+
+    #[test]
+    fn test_unrecognized() {
+        let data = vec![b'x'; 600];
+        assert!(ArchiveReader::new(Box::new(Cursor::new(data))).is_err());
+    }
+}
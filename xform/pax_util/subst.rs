@@ -0,0 +1,194 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Substitutions of the `-s /old/new/[gp]` option, applied to the names
+//! of the files archived or extracted.
+
+use std::ffi::{OsStr, OsString};
+use std::ops::Range;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+use plib::regex::Regex;
+use plib::tr;
+
+pub struct Substitution {
+    regex: Regex,
+    replacement: Vec<u8>,
+    global: bool,
+    print: bool,
+}
+
+/// Splits `s` at the next unescaped `delim`, removing the backslashes
+/// that escape it.
+fn split_at_delim(s: &[u8], delim: u8) -> Option<(Vec<u8>, &[u8])> {
+    let mut part = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'\\' if s.get(i + 1) == Some(&delim) => {
+                part.push(delim);
+                i += 2;
+            }
+            b'\\' if i + 1 < s.len() => {
+                part.extend_from_slice(&s[i..i + 2]);
+                i += 2;
+            }
+            c if c == delim => return Some((part, &s[i + 1..])),
+            c => {
+                part.push(c);
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+impl Substitution {
+    /// Parses an expression of the form `/old/new/[gp]`, where any
+    /// character can stand for the `/`.
+    pub fn parse(expr: &str) -> Result<Substitution, String> {
+        let bytes = expr.as_bytes();
+        let invalid = || tr!("invalid substitution: {}", expr);
+        let (&delim, rest) = bytes.split_first().ok_or_else(invalid)?;
+        if delim == b'\\' || delim == b'\n' {
+            return Err(invalid());
+        }
+        let (pattern, rest) = split_at_delim(rest, delim).ok_or_else(invalid)?;
+        let (replacement, flags) = split_at_delim(rest, delim).ok_or_else(invalid)?;
+
+        let mut subst = Substitution {
+            regex: Regex::bre(&pattern)
+                .map_err(|_| format!("{}: {}", tr!("invalid regular expression"), expr))?,
+            replacement,
+            global: false,
+            print: false,
+        };
+        for &flag in flags {
+            match flag {
+                b'g' => subst.global = true,
+                b'p' => subst.print = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(subst)
+    }
+
+    /// Appends the replacement for a match to `out`.
+    fn expand(&self, text: &[u8], groups: &[Option<Range<usize>>], out: &mut Vec<u8>) {
+        let mut chars = self.replacement.iter().copied();
+        while let Some(c) = chars.next() {
+            let group = match c {
+                b'&' => Some(0),
+                b'\\' => match chars.next() {
+                    Some(d @ b'1'..=b'9') => Some(usize::from(d - b'0')),
+                    Some(other) => {
+                        out.push(other);
+                        None
+                    }
+                    None => None,
+                },
+                _ => {
+                    out.push(c);
+                    None
+                }
+            };
+            if let Some(range) = group.and_then(|i| groups.get(i).cloned().flatten()) {
+                out.extend_from_slice(&text[range]);
+            }
+        }
+    }
+
+    /// Applies the substitution to `name`, or returns `None` if the
+    /// expression doesn't match it.
+    pub fn apply(&self, name: &OsStr) -> Option<OsString> {
+        let text = name.as_bytes();
+        let mut out = Vec::new();
+        let mut pos = 0;
+        let mut matched = false;
+
+        while pos <= text.len() {
+            let Some(groups) = self.regex.captures_at(text, pos) else {
+                break;
+            };
+            let Range { start, end } = groups[0].clone().expect("a match has a range");
+            matched = true;
+            out.extend_from_slice(&text[pos..start]);
+            self.expand(text, &groups, &mut out);
+
+            if !self.global {
+                pos = end;
+                break;
+            }
+            if end == start {
+                // Step past an empty match so as not to match it again
+                if let Some(&c) = text.get(end) {
+                    out.push(c);
+                }
+                pos = end + 1;
+            } else {
+                pos = end;
+            }
+        }
+
+        if !matched {
+            return None;
+        }
+        if pos < text.len() {
+            out.extend_from_slice(&text[pos..]);
+        }
+        let result = OsString::from_vec(out);
+        if self.print {
+            eprintln!("{} >> {}", name.to_string_lossy(), result.to_string_lossy());
+        }
+        Some(result)
+    }
+}
+
+/// Applies the first of `substs` that matches `name`. The name is left
+/// as it is if none does.
+pub fn rename(substs: &[Substitution], name: &OsStr) -> OsString {
+    substs
+        .iter()
+        .find_map(|subst| subst.apply(name))
+        .unwrap_or_else(|| name.to_os_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(expr: &str, name: &str) -> Option<String> {
+        let subst = Substitution::parse(expr).unwrap();
+        subst
+            .apply(OsStr::new(name))
+            .map(|s| s.into_string().unwrap())
+    }
+
+    #[test]
+    fn test_substitution() {
+        assert_eq!(apply("/a/b/", "banana").as_deref(), Some("bbnana"));
+        assert_eq!(apply("/a/b/g", "banana").as_deref(), Some("bbnbnb"));
+        assert_eq!(apply("/^x/y/", "banana"), None);
+        assert_eq!(
+            apply(",\\(.*\\)/\\(.*\\),\\2-\\1,", "dir/file").as_deref(),
+            Some("file-dir")
+        );
+        assert_eq!(apply("/n/[&]/", "banana").as_deref(), Some("ba[n]ana"));
+        assert_eq!(apply("|^a|/|g", "aaa").as_deref(), Some("/aa"));
+        assert_eq!(apply("/x*/-/g", "ab").as_deref(), Some("-a-b-"));
+        assert_eq!(apply("/.*//", "gone").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Substitution::parse("/a/b").is_err());
+        assert!(Substitution::parse("/a/b/q").is_err());
+        assert!(Substitution::parse("").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The ustar interchange format.
+//!
+//! Besides plain ustar, archives written by other tar implementations
+//! are read with their extended headers for long names and large
+//! values: pax `x` headers and the GNU `L` and `K` headers.
+
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+use super::{invalid_data, parse_octal, read_full, unexpected_eof, Kind, Member};
+use plib::tr;
+
+pub const BLOCK_SIZE: usize = 512;
+
+const NAME: (usize, usize) = (0, 100);
+const MODE: (usize, usize) = (100, 8);
+const UID: (usize, usize) = (108, 8);
+const GID: (usize, usize) = (116, 8);
+const SIZE: (usize, usize) = (124, 12);
+const MTIME: (usize, usize) = (136, 12);
+const CHKSUM: (usize, usize) = (148, 8);
+const TYPEFLAG: usize = 156;
+const LINKNAME: (usize, usize) = (157, 100);
+const MAGIC: (usize, usize) = (257, 6);
+const VERSION: (usize, usize) = (263, 2);
+const UNAME: (usize, usize) = (265, 32);
+const GNAME: (usize, usize) = (297, 32);
+const DEVMAJOR: (usize, usize) = (329, 8);
+const DEVMINOR: (usize, usize) = (337, 8);
+const PREFIX: (usize, usize) = (345, 155);
+
+type Block = [u8; BLOCK_SIZE];
+
+fn field(block: &Block, (offset, len): (usize, usize)) -> &[u8] {
+    &block[offset..offset + len]
+}
+
+/// A string field, which ends at a NUL byte unless it fills the field.
+fn string_field(block: &Block, f: (usize, usize)) -> &[u8] {
+    let bytes = field(block, f);
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
+/// A numeric field, in octal or in the base-256 encoding other
+/// implementations use for values that don't fit.
+fn numeric_field(block: &Block, f: (usize, usize)) -> io::Result<u64> {
+    let bytes = field(block, f);
+    if bytes[0] & 0x80 != 0 {
+        let value = bytes[1..]
+            .iter()
+            .fold(u64::from(bytes[0] & 0x7f), |acc, &b| {
+                (acc << 8) | u64::from(b)
+            });
+        return Ok(value);
+    }
+    parse_octal(bytes).ok_or_else(|| invalid_data(tr!("invalid number in archive header")))
+}
+
+fn checksum(block: &Block) -> u64 {
+    block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (CHKSUM.0..CHKSUM.0 + CHKSUM.1).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum()
+}
+
+fn checksum_ok(block: &Block) -> bool {
+    parse_octal(field(block, CHKSUM)) == Some(checksum(block))
+}
+
+/// Whether `start`, the beginning of an archive, is a ustar header or
+/// the end of an empty archive.
+pub fn is_header(start: &[u8]) -> bool {
+    let Ok(block) = <&Block>::try_from(start) else {
+        return false;
+    };
+    block.iter().all(|&b| b == 0)
+        || (field(block, MAGIC).starts_with(b"ustar") && checksum_ok(block))
+}
+
+/// The number of zero bytes following `size` bytes of data, to fill the
+/// last block.
+pub fn padding(size: u64) -> u64 {
+    let block = BLOCK_SIZE as u64;
+    (block - size % block) % block
+}
+
+/// Values from extended headers, which override those of the header
+/// that follows them.
+#[derive(Default)]
+struct Overrides {
+    name: Option<OsString>,
+    link: Option<OsString>,
+    size: Option<u64>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    mtime: Option<i64>,
+    uname: Option<String>,
+    gname: Option<String>,
+}
+
+impl Overrides {
+    /// Takes the records of a pax extended header, `<length> <key>=<value>\n`.
+    fn parse_pax(&mut self, mut data: &[u8]) -> io::Result<()> {
+        let bad = || invalid_data(tr!("invalid extended header"));
+        while !data.is_empty() {
+            let space = data.iter().position(|&b| b == b' ').ok_or_else(bad)?;
+            let len: usize = std::str::from_utf8(&data[..space])
+                .ok()
+                .and_then(|len| len.parse().ok())
+                .filter(|&len| len > space && len <= data.len())
+                .ok_or_else(bad)?;
+            let record = &data[space + 1..len];
+            data = &data[len..];
+
+            let record = record.strip_suffix(b"\n").unwrap_or(record);
+            let eq = record.iter().position(|&b| b == b'=').ok_or_else(bad)?;
+            let (key, value) = (&record[..eq], &record[eq + 1..]);
+            let text = String::from_utf8_lossy(value);
+            match key {
+                b"path" => self.name = Some(OsString::from_vec(value.to_vec())),
+                b"linkpath" => self.link = Some(OsString::from_vec(value.to_vec())),
+                b"size" => self.size = text.parse().ok(),
+                b"uid" => self.uid = text.parse().ok(),
+                b"gid" => self.gid = text.parse().ok(),
+                // Fractions of seconds are dropped
+                b"mtime" => self.mtime = text.split('.').next().and_then(|s| s.parse().ok()),
+                b"uname" => self.uname = Some(text.into_owned()),
+                b"gname" => self.gname = Some(text.into_owned()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(self, member: &mut Member) {
+        if let Some(name) = self.name {
+            member.name = name;
+        }
+        if let Some(link) = self.link {
+            member.link = link;
+        }
+        if let Some(size) = self.size {
+            member.size = size;
+        }
+        if let Some(uid) = self.uid {
+            member.uid = uid;
+        }
+        if let Some(gid) = self.gid {
+            member.gid = gid;
+        }
+        if let Some(mtime) = self.mtime {
+            member.mtime = mtime;
+        }
+        if let Some(uname) = self.uname {
+            member.uname = uname;
+        }
+        if let Some(gname) = self.gname {
+            member.gname = gname;
+        }
+    }
+}
+
+/// Reads the data of an extended header.
+fn read_data(r: &mut dyn Read, size: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    r.take(size).read_to_end(&mut data)?;
+    if (data.len() as u64) < size {
+        return Err(unexpected_eof());
+    }
+    io::copy(&mut r.take(padding(size)), &mut io::sink())?;
+    Ok(data)
+}
+
+/// Reads the header of the next member, or `None` at the end of the
+/// archive.
+pub fn read_header(r: &mut dyn Read) -> io::Result<Option<Member>> {
+    let mut overrides = Overrides::default();
+    loop {
+        let mut block = [0; BLOCK_SIZE];
+        match read_full(r, &mut block)? {
+            // Tolerate archives missing their end-of-archive blocks
+            0 => return Ok(None),
+            BLOCK_SIZE => {}
+            _ => return Err(unexpected_eof()),
+        }
+        if block.iter().all(|&b| b == 0) {
+            return Ok(None);
+        }
+        if !checksum_ok(&block) {
+            return Err(invalid_data(tr!("invalid archive header checksum")));
+        }
+
+        let size = numeric_field(&block, SIZE)?;
+        let typeflag = block[TYPEFLAG];
+        match typeflag {
+            b'x' => {
+                overrides.parse_pax(&read_data(r, size)?)?;
+                continue;
+            }
+            b'g' => {
+                read_data(r, size)?;
+                continue;
+            }
+            b'L' | b'K' => {
+                let mut data = read_data(r, size)?;
+                if let Some(nul) = data.iter().position(|&b| b == 0) {
+                    data.truncate(nul);
+                }
+                let data = Some(OsString::from_vec(data));
+                if typeflag == b'L' {
+                    overrides.name = data;
+                } else {
+                    overrides.link = data;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let kind = match typeflag {
+            b'1' => Kind::HardLink,
+            b'2' => Kind::Symlink,
+            b'3' => Kind::CharDevice,
+            b'4' => Kind::BlockDevice,
+            b'5' => Kind::Dir,
+            b'6' => Kind::Fifo,
+            // Regular and contiguous files, and types we don't know of
+            _ => Kind::File,
+        };
+
+        let mut name = string_field(&block, NAME).to_vec();
+        let prefix = string_field(&block, PREFIX);
+        if field(&block, MAGIC).starts_with(b"ustar") && !prefix.is_empty() {
+            name = [prefix, b"/", &name].concat();
+        }
+        let mut member = Member::new(OsString::from_vec(name), kind);
+        member.mode = (numeric_field(&block, MODE)? & 0o7777) as u32;
+        member.uid = numeric_field(&block, UID)? as u32;
+        member.gid = numeric_field(&block, GID)? as u32;
+        member.uname = String::from_utf8_lossy(string_field(&block, UNAME)).into_owned();
+        member.gname = String::from_utf8_lossy(string_field(&block, GNAME)).into_owned();
+        member.mtime = i64::try_from(numeric_field(&block, MTIME)?).unwrap_or(i64::MAX);
+        member.link = OsString::from_vec(string_field(&block, LINKNAME).to_vec());
+        if matches!(kind, Kind::CharDevice | Kind::BlockDevice) {
+            member.dev_major = numeric_field(&block, DEVMAJOR).unwrap_or(0) as u32;
+            member.dev_minor = numeric_field(&block, DEVMINOR).unwrap_or(0) as u32;
+        }
+        // Only regular files have data, whatever the size field says
+        if kind == Kind::File {
+            member.size = size;
+        }
+        overrides.apply(&mut member);
+        if kind != Kind::File {
+            member.size = 0;
+        }
+        return Ok(Some(member));
+    }
+}
+
+/// Writes `value` in octal, zero-padded, into all but the last byte of
+/// a field, which is left as NUL.
+fn put_octal(block: &mut Block, (offset, len): (usize, usize), value: u64) -> io::Result<()> {
+    let digits = len - 1;
+    let s = format!("{:0width$o}", value, width = digits);
+    if s.len() > digits {
+        return Err(invalid_data(tr!("value too large for ustar header")));
+    }
+    block[offset..offset + digits].copy_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn put_bytes(block: &mut Block, (offset, len): (usize, usize), bytes: &[u8]) -> io::Result<()> {
+    if bytes.len() > len {
+        return Err(invalid_data(tr!("name too long for ustar header")));
+    }
+    block[offset..offset + bytes.len()].copy_from_slice(bytes);
+    Ok(())
+}
+
+/// Splits a name into the prefix and name fields.
+fn split_name(name: &[u8]) -> Option<(&[u8], &[u8])> {
+    if name.len() <= NAME.1 {
+        return Some((b"", name));
+    }
+    // The prefix is joined to the name with a slash, which is not
+    // stored.
+    (1..name.len())
+        .rev()
+        .filter(|&i| name[i] == b'/')
+        .map(|i| (&name[..i], &name[i + 1..]))
+        .find(|(prefix, rest)| prefix.len() <= PREFIX.1 && !rest.is_empty() && rest.len() <= NAME.1)
+}
+
+/// Encodes the header of a member.
+pub fn header(member: &Member) -> io::Result<Block> {
+    let mut block = [0; BLOCK_SIZE];
+
+    let mut name = member.name.as_bytes().to_vec();
+    if member.kind == Kind::Dir && !name.ends_with(b"/") {
+        name.push(b'/');
+    }
+    let (prefix, name) =
+        split_name(&name).ok_or_else(|| invalid_data(tr!("name too long for ustar header")))?;
+    put_bytes(&mut block, NAME, name)?;
+    put_bytes(&mut block, PREFIX, prefix)?;
+
+    put_octal(&mut block, MODE, u64::from(member.mode & 0o7777))?;
+    put_octal(&mut block, UID, u64::from(member.uid))?;
+    put_octal(&mut block, GID, u64::from(member.gid))?;
+    let size = if member.kind == Kind::File {
+        member.size
+    } else {
+        0
+    };
+    put_octal(&mut block, SIZE, size)?;
+    put_octal(&mut block, MTIME, u64::try_from(member.mtime).unwrap_or(0))?;
+    block[TYPEFLAG] = match member.kind {
+        Kind::File => b'0',
+        Kind::HardLink => b'1',
+        Kind::Symlink => b'2',
+        Kind::CharDevice => b'3',
+        Kind::BlockDevice => b'4',
+        Kind::Dir => b'5',
+        Kind::Fifo => b'6',
+    };
+    put_bytes(&mut block, LINKNAME, member.link.as_bytes())?;
+    put_bytes(&mut block, MAGIC, b"ustar\0")?;
+    put_bytes(&mut block, VERSION, b"00")?;
+    // Names that don't fit are left out, leaving the IDs
+    let _ = put_bytes(&mut block, UNAME, member.uname.as_bytes());
+    let _ = put_bytes(&mut block, GNAME, member.gname.as_bytes());
+    if matches!(member.kind, Kind::CharDevice | Kind::BlockDevice) {
+        put_octal(&mut block, DEVMAJOR, u64::from(member.dev_major))?;
+        put_octal(&mut block, DEVMINOR, u64::from(member.dev_minor))?;
+    }
+
+    // Six digits, a NUL and a space
+    let sum = format!("{:06o}\0 ", checksum(&block));
+    block[CHKSUM.0..CHKSUM.0 + CHKSUM.1].copy_from_slice(sum.as_bytes());
+    Ok(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_names() {
+        let long = format!("{}/{}", "d".repeat(150), "f".repeat(99));
+        let mut member = Member::new(long.clone().into(), Kind::File);
+        member.mode = 0o644;
+        let block = header(&member).unwrap();
+        assert!(is_header(&block));
+
+        let read = read_header(&mut &block[..]).unwrap().unwrap();
+        assert_eq!(read.name, OsString::from(long));
+
+        let member = Member::new("x".repeat(101).into(), Kind::File);
+        assert!(header(&member).is_err());
+    }
+
+    #[test]
+    fn test_pax_header() {
+        let mut ext = Member::new("././@PaxHeader".into(), Kind::File);
+        let records = b"18 path=long/name\n";
+        ext.size = records.len() as u64;
+        let mut block = header(&ext).unwrap();
+        block[TYPEFLAG] = b'x';
+        let sum = format!("{:06o}\0 ", checksum(&block));
+        block[CHKSUM.0..CHKSUM.0 + CHKSUM.1].copy_from_slice(sum.as_bytes());
+
+        let mut archive = block.to_vec();
+        archive.extend_from_slice(records);
+        archive.resize(BLOCK_SIZE * 2, 0);
+        archive.extend_from_slice(&header(&Member::new("short".into(), Kind::File)).unwrap());
+
+        let read = read_header(&mut &archive[..]).unwrap().unwrap();
+        assert_eq!(read.name, "long/name");
+    }
+}
@@ -7,10 +7,11 @@
 // SPDX-License-Identifier: MIT
 //
 
-use plib::testing::{run_test, TestPlan};
+use plib::testing::{run_test, run_test_u8, Fixture, TestPlan, TestPlanU8};
 use std::{
-    fs::{remove_file, File},
+    fs::{self, remove_file, File},
     io::Read,
+    os::unix::fs::{MetadataExt, PermissionsExt},
 };
 
 fn compress_test(args: &[&str], expected_output: &str, expected_error: &str) {
@@ -112,10 +113,109 @@ fn compression_compress_file() {
 
     compress_test(&[file.to_str().unwrap()], "", "");
 
-    uncompress_test(&[compressed_file_path.to_str().unwrap()], &buf, "");
+    uncompress_test(&["-c", compressed_file_path.to_str().unwrap()], &buf, "");
 
     // Delete the compressed file(if test is successful)
     if compressed_file_path.exists() {
         remove_file(&compressed_file_path).unwrap();
     }
 }
+
+/// "TOBEORNOTTOBEORTOBEORNOT\n" compressed with 16-bit codes.
+const TOBEORNOT_Z: [u8; 23] = [
+    0x1f, 0x9d, 0x90, 0x54, 0x9e, 0x08, 0x29, 0xf2, 0x44, 0x8a, 0x93, 0x27, 0x54, 0x02, 0x0e, 0x2c,
+    0xa8, 0x90, 0xa0, 0x41, 0x84, 0x0a, 0x00,
+];
+
+#[test]
+fn compress_stdin_to_stdout() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("compress"),
+        args: Vec::new(),
+        stdin_data: b"TOBEORNOTTOBEORTOBEORNOT\n".to_vec(),
+        expected_out: TOBEORNOT_Z.to_vec(),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn zcat_stdin() {
+    run_test_u8(TestPlanU8 {
+        cmd: String::from("zcat"),
+        args: Vec::new(),
+        stdin_data: TOBEORNOT_Z.to_vec(),
+        expected_out: b"TOBEORNOTTOBEORTOBEORNOT\n".to_vec(),
+        expected_err: Vec::new(),
+        expected_exit_code: 0,
+    });
+}
+
+#[test]
+fn compress_bits_round_trip() {
+    let text: String = (0..2000).map(|i| format!("line {}\n", i % 97)).collect();
+    let fixture = Fixture::builder("compress_bits")
+        .file("data", &text)
+        .mode(0o640)
+        .mtime(1_000_000_000)
+        .build();
+
+    compress_test(&["-b", "9", &fixture.arg("data")], "", "");
+    assert!(!fixture.path("data").exists());
+    let md = fs::metadata(fixture.path("data.Z")).unwrap();
+    assert!(md.len() < text.len() as u64);
+    assert_eq!(md.permissions().mode() & 0o777, 0o640);
+
+    // zcat and uncompress find the file without its .Z suffix too
+    run_test(TestPlan {
+        cmd: String::from("zcat"),
+        args: vec![fixture.arg("data")],
+        stdin_data: String::new(),
+        expected_out: text.clone(),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+    });
+    uncompress_test(&[&fixture.arg("data")], "", "");
+    assert!(!fixture.path("data.Z").exists());
+    assert_eq!(fs::read_to_string(fixture.path("data")).unwrap(), text);
+    let md = fs::metadata(fixture.path("data")).unwrap();
+    assert_eq!(md.mtime(), 1_000_000_000);
+}
+
+#[test]
+fn compress_file_that_grows() {
+    let fixture = Fixture::builder("compress_grows").file("tiny", "x").build();
+
+    run_test(TestPlan {
+        cmd: String::from("compress"),
+        args: vec![fixture.arg("tiny")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code: 2,
+    });
+    assert!(fixture.path("tiny").exists());
+    assert!(!fixture.path("tiny.Z").exists());
+
+    compress_test(&["-f", &fixture.arg("tiny")], "", "");
+    assert!(fixture.path("tiny.Z").exists());
+}
+
+#[test]
+fn uncompress_not_compressed() {
+    let fixture = Fixture::builder("uncompress_invalid")
+        .file("plain.Z", "not compressed\n")
+        .build();
+    let path = fixture.arg("plain.Z");
+
+    run_test(TestPlan {
+        cmd: String::from("uncompress"),
+        args: vec![path.clone()],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: format!("uncompress: {}: not in compressed format\n", path),
+        expected_exit_code: 1,
+    });
+    assert!(fixture.path("plain.Z").exists());
+    assert!(!fixture.path("plain").exists());
+}
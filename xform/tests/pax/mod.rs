@@ -0,0 +1,255 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use plib::testing::{run_test_with_options, Fixture, TestOptions, TestPlan};
+
+/// Sorts the lines of output, as pax archives the entries of a directory
+/// in the order the file system returns them.
+fn sort_lines(output: &str) -> String {
+    let mut lines: Vec<&str> = output.lines().collect();
+    lines.sort();
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Runs pax in the root of `fixture`.
+fn pax_test(
+    fixture: &Fixture,
+    args: &[&str],
+    expected_output: &str,
+    expected_error: &str,
+    expected_exit_code: i32,
+) {
+    pax_test_in(
+        fixture.root(),
+        args,
+        expected_output,
+        expected_error,
+        expected_exit_code,
+    );
+}
+
+/// Runs pax in `dir`.
+fn pax_test_in(
+    dir: &Path,
+    args: &[&str],
+    expected_output: &str,
+    expected_error: &str,
+    expected_exit_code: i32,
+) {
+    run_test_with_options(
+        TestPlan {
+            cmd: String::from("pax"),
+            args: args.iter().map(|s| String::from(*s)).collect(),
+            stdin_data: String::new(),
+            expected_out: sort_lines(expected_output),
+            expected_err: String::from(expected_error),
+            expected_exit_code,
+        },
+        TestOptions {
+            cwd: Some(dir.to_path_buf()),
+            normalize: Some(sort_lines),
+            ..Default::default()
+        },
+    );
+}
+
+fn sample_tree(name: &str) -> Fixture {
+    Fixture::builder(name)
+        .file("tree/a", "alpha\n")
+        .mode(0o640)
+        .mtime(1_000_000_000)
+        .file("tree/sub/b", "beta\n")
+        .symlink("tree/sub/link", "b")
+        .build()
+}
+
+const SAMPLE_NAMES: &str = "tree\ntree/a\ntree/sub\ntree/sub/b\ntree/sub/link\n";
+
+#[test]
+fn pax_write_list_ustar() {
+    let fixture = sample_tree("pax_ustar");
+    pax_test(&fixture, &["-w", "-f", "archive", "tree"], "", "", 0);
+    assert_eq!(fs::metadata(fixture.path("archive")).unwrap().len(), 10240);
+    pax_test(&fixture, &["-f", "archive"], SAMPLE_NAMES, "", 0);
+}
+
+#[test]
+fn pax_write_list_cpio() {
+    let fixture = sample_tree("pax_cpio");
+    pax_test(
+        &fixture,
+        &["-w", "-x", "cpio", "-f", "archive", "tree"],
+        "",
+        "",
+        0,
+    );
+    assert_eq!(fs::metadata(fixture.path("archive")).unwrap().len(), 5120);
+    pax_test(&fixture, &["-f", "archive"], SAMPLE_NAMES, "", 0);
+}
+
+#[test]
+fn pax_read() {
+    let fixture = sample_tree("pax_read");
+    pax_test(&fixture, &["-w", "-f", "archive", "tree"], "", "", 0);
+    pax_test(
+        &fixture,
+        &["-r", "-p", "p", "-f", "archive", "-s", ",^tree,out,"],
+        "",
+        "",
+        0,
+    );
+
+    let a = fixture.path("out/a");
+    assert_eq!(fs::read_to_string(&a).unwrap(), "alpha\n");
+    let md = fs::metadata(&a).unwrap();
+    assert_eq!(md.mode() & 0o777, 0o640);
+    assert_eq!(md.mtime(), 1_000_000_000);
+    assert_eq!(
+        fs::read_to_string(fixture.path("out/sub/b")).unwrap(),
+        "beta\n"
+    );
+    assert_eq!(
+        fs::read_link(fixture.path("out/sub/link")).unwrap(),
+        fixture.path("out/sub/b").file_name().unwrap()
+    );
+}
+
+#[test]
+fn pax_read_stays_in_the_current_directory() {
+    let fixture = Fixture::builder("pax_read_names")
+        .file("tree/a", "alpha\n")
+        .file("tree/b", "beta\n")
+        .dir("out")
+        .build();
+    pax_test(
+        &fixture,
+        &[
+            "-w",
+            "-f",
+            "archive",
+            "-s",
+            ",^tree/a$,../evil,",
+            "-s",
+            ",^tree/b$,/abs/b,",
+            "tree/a",
+            "tree/b",
+        ],
+        "",
+        "",
+        0,
+    );
+    pax_test_in(
+        &fixture.path("out"),
+        &["-r", "-f", "../archive"],
+        "",
+        "pax: ../evil: member name contains '..', skipping\n",
+        1,
+    );
+    assert!(!fixture.path("evil").exists());
+    assert_eq!(
+        fs::read_to_string(fixture.path("out/abs/b")).unwrap(),
+        "beta\n"
+    );
+}
+
+#[test]
+fn pax_select_patterns() {
+    let fixture = sample_tree("pax_select");
+    pax_test(&fixture, &["-w", "-f", "archive", "tree"], "", "", 0);
+    pax_test(
+        &fixture,
+        &["-f", "archive", "tree/sub"],
+        "tree/sub\ntree/sub/b\ntree/sub/link\n",
+        "",
+        0,
+    );
+    pax_test(
+        &fixture,
+        &["-d", "-f", "archive", "tree/sub"],
+        "tree/sub\n",
+        "",
+        0,
+    );
+    pax_test(
+        &fixture,
+        &["-c", "-f", "archive", "tree/sub/*"],
+        "tree\ntree/a\ntree/sub\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn pax_substitution_skips_empty_names() {
+    let fixture = sample_tree("pax_subst");
+    pax_test(
+        &fixture,
+        &[
+            "-w",
+            "-f",
+            "archive",
+            "-s",
+            ",.*/sub/.*,,",
+            "-s",
+            ",^tree,t,",
+            "tree",
+        ],
+        "",
+        "",
+        0,
+    );
+    pax_test(&fixture, &["-f", "archive"], "t\nt/a\nt/sub\n", "", 0);
+}
+
+#[test]
+fn pax_copy() {
+    let fixture = Fixture::builder("pax_copy")
+        .file("tree/a", "alpha\n")
+        .file("tree/sub/b", "beta\n")
+        .dir("dest")
+        .build();
+    pax_test(&fixture, &["-rw", "tree", "dest"], "", "", 0);
+    assert_eq!(
+        fs::read_to_string(fixture.path("dest/tree/a")).unwrap(),
+        "alpha\n"
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.path("dest/tree/sub/b")).unwrap(),
+        "beta\n"
+    );
+
+    pax_test(
+        &fixture,
+        &["-rwl", "tree", "missing"],
+        "",
+        "pax: missing: not a directory\n",
+        1,
+    );
+    fs::create_dir(fixture.path("linked")).unwrap();
+    pax_test(&fixture, &["-rwl", "tree", "linked"], "", "", 0);
+    let original = fs::metadata(fixture.path("tree/a")).unwrap();
+    let linked = fs::metadata(fixture.path("linked/tree/a")).unwrap();
+    assert_eq!(original.ino(), linked.ino());
+}
+
+#[test]
+fn pax_invalid_substitution() {
+    let fixture = sample_tree("pax_invalid");
+    pax_test(
+        &fixture,
+        &["-f", "archive", "-s", "/a/b"],
+        "",
+        "pax: invalid substitution: /a/b\n",
+        1,
+    );
+}
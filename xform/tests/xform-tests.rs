@@ -9,4 +9,5 @@
 
 mod cksum;
 mod compress;
+mod pax;
 mod uue;
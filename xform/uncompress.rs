@@ -6,84 +6,152 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - support NOT writing to stdout (but to file.Z, with .Z suffix removed)
-// - support options -f, -v
-//
+
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use gettextrs::gettext;
+use plib::diag::{error_message, Status};
 use plib::io::input_stream;
 use plib::lzw::UnixLZWReader;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use plib::{diag, i18n, tdiag, tr};
 
-/// uncompress - expand compressed data
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about = gettext("uncompress - expand compressed data"))]
 struct Args {
-    /// Write to standard output; no files are changed.
-    #[arg(short = 'c', long)]
+    #[arg(
+        short = 'c',
+        long,
+        help = gettext("Write to standard output; no files are changed")
+    )]
     stdout: bool,
 
-    /// Do not prompt for overwriting files
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        help = gettext("Overwrite existing files without prompting")
+    )]
     force: bool,
 
-    /// Write messages to standard error concerning the expansion of each file.
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        help = gettext("Write the name of each file expanded to standard error")
+    )]
     verbose: bool,
 
-    /// Files to read as input.  Use "-" or no-args for stdin.
+    #[arg(help = gettext("Files to expand; standard input if there are none"))]
     files: Vec<PathBuf>,
 }
 
-fn uncompress_file(pathname: &PathBuf) -> io::Result<()> {
-    let file = input_stream(pathname, false)?;
-    let mut decoder = UnixLZWReader::new(file);
-
-    loop {
-        let buf = decoder.read()?;
-        if buf.is_empty() {
-            break;
-        }
-
-        io::stdout().write_all(&buf)?;
+/// Asks whether to overwrite `path`, if standard input is a terminal.
+fn may_overwrite(path: &Path) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        tdiag!("{}: already exists", path.display());
+        return Ok(false);
     }
+    eprint!(
+        "{}",
+        tr!("{} already exists; overwrite (y or n)? ", path.display())
+    );
+    let mut response = String::new();
+    io::stdin().read_line(&mut response)?;
+    if response.to_lowercase().starts_with('y') {
+        Ok(true)
+    } else {
+        eprintln!("{}", tr!("{} not overwritten", path.display()));
+        Ok(false)
+    }
+}
 
-    Ok(())
+/// Gives `path` the permissions and times of the file it replaces.
+fn copy_attributes(path: &Path, md: &fs::Metadata) -> io::Result<()> {
+    fs::set_permissions(path, md.permissions())?;
+    let times = FileTimes::new()
+        .set_accessed(md.accessed()?)
+        .set_modified(md.modified()?);
+    File::options().write(true).open(path)?.set_times(times)
 }
 
-fn prog_is_zcat() -> bool {
-    let progname = std::env::args().next().unwrap();
-    progname.ends_with("zcat")
+fn uncompress_stream(input: impl Read, output: &mut dyn Write) -> io::Result<()> {
+    let mut reader = UnixLZWReader::new(input)?;
+    io::copy(&mut reader, output)?;
+    output.flush()
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain("posixutils-rs")?;
-    bind_textdomain_codeset("posixutils-rs", "UTF-8")?;
+/// Names of the compressed file and of the file it expands to: `file`
+/// names the compressed file if it ends in `.Z`, and the expanded one
+/// otherwise.
+fn file_names(file: &Path) -> (PathBuf, PathBuf) {
+    if file.extension().is_some_and(|ext| ext == "Z") {
+        (file.to_path_buf(), file.with_extension(""))
+    } else {
+        let mut compressed = OsString::from(file);
+        compressed.push(".Z");
+        (PathBuf::from(compressed), file.to_path_buf())
+    }
+}
 
-    let mut args = Args::parse();
+fn uncompress_file(args: &Args, file: &Path, status: &mut Status) -> io::Result<()> {
+    let (path, target) = file_names(file);
+    let md = fs::metadata(&path)?;
+    let input = BufReader::new(File::open(&path)?);
 
-    // if no file args, read from stdin
-    if args.files.is_empty() {
-        args.files.push(PathBuf::new());
+    if args.stdout {
+        let mut stdout = plib::io::stdout();
+        uncompress_stream(input, &mut stdout)?;
+        return stdout.finish();
     }
 
-    // zcat is a special case:  always write to stdout
-    if prog_is_zcat() {
-        args.stdout = true;
+    if !args.force && target.symlink_metadata().is_ok() && !may_overwrite(&target)? {
+        status.fail();
+        return Ok(());
+    }
+    let mut output = BufWriter::new(File::create(&target)?);
+    if let Err(e) = uncompress_stream(input, &mut output) {
+        drop(output);
+        let _ = fs::remove_file(&target);
+        return Err(e);
+    }
+    drop(output);
+
+    copy_attributes(&target, &md)?;
+    fs::remove_file(&path)?;
+    if args.verbose {
+        eprintln!(
+            "{}",
+            tr!("{}: -- replaced with {}", path.display(), target.display())
+        );
     }
+    Ok(())
+}
 
-    let mut exit_code = 0;
+fn main() {
+    i18n::init();
+
+    let args = Args::parse();
+    let mut status = Status::new();
+
+    if args.files.is_empty() {
+        let result = input_stream(&PathBuf::new(), false).and_then(|input| {
+            let mut stdout = plib::io::stdout();
+            uncompress_stream(input, &mut stdout)?;
+            stdout.finish()
+        });
+        if let Err(e) = result {
+            diag!("{}", error_message(&e));
+            status.fail();
+        }
+    }
 
-    for filename in &args.files {
-        if let Err(e) = uncompress_file(filename) {
-            exit_code = 1;
-            eprintln!("{}: {}", filename.display(), e);
+    for file in &args.files {
+        if let Err(e) = uncompress_file(&args, file, &mut status) {
+            status.operand_error(file.display(), &e);
         }
     }
 
-    std::process::exit(exit_code)
+    status.exit()
 }
@@ -0,0 +1,71 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use gettextrs::gettext;
+use plib::diag::{error_message, Status};
+use plib::io::input_stream;
+use plib::lzw::UnixLZWReader;
+use plib::{diag, i18n};
+
+#[derive(Parser)]
+#[command(version, about = gettext("zcat - expand and concatenate data"))]
+struct Args {
+    #[arg(help = gettext("Files to expand; standard input if there are none"))]
+    files: Vec<PathBuf>,
+}
+
+fn zcat(input: impl Read, output: &mut dyn Write) -> io::Result<()> {
+    let mut reader = UnixLZWReader::new(input)?;
+    io::copy(&mut reader, output)?;
+    Ok(())
+}
+
+/// Opens `file`, or `file.Z` if its name doesn't end in `.Z`.
+fn open(file: &Path) -> io::Result<File> {
+    if file.extension().is_some_and(|ext| ext == "Z") {
+        File::open(file)
+    } else {
+        let mut compressed = OsString::from(file);
+        compressed.push(".Z");
+        File::open(compressed)
+    }
+}
+
+fn main() {
+    i18n::init();
+
+    let args = Args::parse();
+    let mut status = Status::new();
+    let mut stdout = plib::io::stdout();
+
+    if args.files.is_empty() {
+        if let Err(e) = input_stream(&PathBuf::new(), false).and_then(|f| zcat(f, &mut stdout)) {
+            diag!("{}", error_message(&e));
+            status.fail();
+        }
+    }
+
+    for file in &args.files {
+        if let Err(e) = open(file).and_then(|f| zcat(BufReader::new(f), &mut stdout)) {
+            status.operand_error(file.display(), &e);
+        }
+    }
+
+    if let Err(e) = stdout.finish() {
+        diag!("{}", error_message(&e));
+        status.fail();
+    }
+    status.exit()
+}